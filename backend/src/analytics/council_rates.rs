@@ -0,0 +1,149 @@
+//! Council rates - a property's actual annual rates from its LGA
+//!
+//! Properties get their LGA from the suburb/postcode correspondence; the rates
+//! are the council's average residential rates and levies. Properties outside
+//! the correspondence, or in councils the dataset doesn't cover, have none and
+//! fall back to a flat expense assumption.
+
+use crate::ingestion::types::CouncilRates;
+use sqlx::PgPool;
+
+/// The council rates for a property's LGA, when both are known
+pub async fn council_rates_for_property(
+    db: &PgPool,
+    property_id: i32,
+) -> Result<Option<CouncilRates>, sqlx::Error> {
+    sqlx::query_as::<_, CouncilRates>(
+        r#"
+        SELECT r.lga_code, r.lga_name, r.state, r.financial_year,
+               r.average_rates, r.average_levies
+        FROM properties p
+        JOIN council_rates r ON r.lga_code = p.lga_code
+        WHERE p.id = $1
+        "#,
+    )
+    .bind(property_id)
+    .fetch_optional(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::types::{LgaAreaType, LgaCorrespondence, State};
+    use crate::ingestion::write;
+    use crate::test_support::{delete_suburb, PropertyFixture};
+
+    const SUBURB: &str = "Council Rates Testville";
+    const OTHER_SUBURB: &str = "Council Rates Otherville";
+    const POSTCODE: &str = "2998";
+
+    async fn cleanup(db: &PgPool) {
+        for suburb in [SUBURB, OTHER_SUBURB] {
+            delete_suburb(db, suburb).await.unwrap();
+        }
+        sqlx::query("DELETE FROM lga_correspondence WHERE area = ANY($1)")
+            .bind([SUBURB.to_uppercase(), POSTCODE.to_string()])
+            .execute(db)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM council_rates WHERE lga_code IN ('19991', '19992')")
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_property_lga_and_rates() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        let in_suburb = PropertyFixture::new()
+            .suburb(SUBURB)
+            .postcode(POSTCODE)
+            .insert(&db)
+            .await
+            .unwrap();
+        let in_postcode = PropertyFixture::new()
+            .suburb(OTHER_SUBURB)
+            .postcode(POSTCODE)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let mapping = |area_type, area: &str, lga_code: &str| LgaCorrespondence {
+            area_type,
+            area: area.to_string(),
+            state: State::NSW,
+            lga_code: lga_code.to_string(),
+            lga_name: format!("Council {}", lga_code),
+            ratio: 1.0,
+        };
+        write::write_lga_correspondence(
+            &db,
+            vec![
+                mapping(LgaAreaType::Suburb, &SUBURB.to_uppercase(), "19991"),
+                mapping(LgaAreaType::Postcode, POSTCODE, "19992"),
+            ],
+        )
+        .await
+        .unwrap();
+        write::write_council_rates(
+            &db,
+            vec![CouncilRates {
+                lga_code: "19991".to_string(),
+                lga_name: "Council 19991".to_string(),
+                state: Some(State::NSW),
+                financial_year: Some("2023-24".to_string()),
+                average_rates: 1500,
+                average_levies: Some(500),
+            }],
+        )
+        .await
+        .unwrap();
+
+        // Backdated, so assigning an LGA shows in last_updated
+        sqlx::query("UPDATE properties SET last_updated = '2000-01-01' WHERE id = ANY($1)")
+            .bind(vec![in_suburb, in_postcode])
+            .execute(&db)
+            .await
+            .unwrap();
+
+        // The suburb match wins over the postcode; a second pass changes nothing
+        assert!(write::assign_property_lgas(&db).await.unwrap() >= 2);
+        assert_eq!(write::assign_property_lgas(&db).await.unwrap(), 0);
+        let touched: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM properties WHERE id = ANY($1) AND last_updated > '2000-01-01'",
+        )
+        .bind(vec![in_suburb, in_postcode])
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(touched, 2);
+
+        let rates = council_rates_for_property(&db, in_suburb)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rates.lga_code, "19991");
+        assert_eq!(rates.annual_total(), 2000);
+
+        // Mapped to a council the rates dataset doesn't cover
+        let lga: Option<String> =
+            sqlx::query_scalar("SELECT lga_code FROM properties WHERE id = $1")
+                .bind(in_postcode)
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(lga.as_deref(), Some("19992"));
+        assert_eq!(
+            council_rates_for_property(&db, in_postcode).await.unwrap(),
+            None
+        );
+
+        cleanup(&db).await;
+    }
+}
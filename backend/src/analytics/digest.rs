@@ -0,0 +1,476 @@
+//! Weekly digest - what moved in the market and how ingestion went for one week
+//!
+//! Covers the highest-yield properties ingested during the week, the suburbs
+//! whose median yield changed most since the week before, and the health of
+//! the week's ingestion runs. Rendered as JSON for webhooks or as a plain HTML
+//! page for email.
+
+use crate::format::{format_money, format_yield, format_yield_change, round_yield_f64};
+use crate::ingestion::runs;
+use crate::ingestion::types::{IngestionRun, State};
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// Properties listed in the digest
+pub const TOP_PROPERTIES: i64 = 10;
+/// Suburb/bedroom groups listed as yield movers
+pub const TOP_MOVERS: usize = 10;
+
+/// Digest for the seven days ending on `week_ending`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Digest {
+    pub week_starting: NaiveDate,
+    pub week_ending: NaiveDate,
+    /// Highest rental yield first
+    pub top_new_properties: Vec<DigestProperty>,
+    /// Largest change first, in either direction
+    pub yield_movers: Vec<YieldMover>,
+    pub ingestion: IngestionHealth,
+}
+
+/// A property first ingested during the week
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct DigestProperty {
+    pub id: i32,
+    pub address: String,
+    pub suburb: String,
+    pub postcode: Option<String>,
+    pub state: State,
+    pub bedrooms: Option<i32>,
+    pub price: Option<i64>,
+    pub weekly_rent: Option<i32>,
+    /// Gross rental yield (%)
+    pub rental_yield: f64,
+}
+
+/// Latest median yield for one suburb/bedroom group within a week
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct SuburbYield {
+    pub suburb: String,
+    pub postcode: Option<String>,
+    pub state: State,
+    pub bedrooms: Option<i32>,
+    pub median_rental_yield: f64,
+}
+
+/// Change in a group's median yield from the previous week
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct YieldMover {
+    pub suburb: String,
+    pub postcode: Option<String>,
+    pub state: State,
+    pub bedrooms: Option<i32>,
+    pub previous_yield: f64,
+    pub current_yield: f64,
+    /// Percentage points, rounded to two decimals
+    pub change: f64,
+}
+
+/// Outcome of the week's ingestion runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IngestionHealth {
+    pub runs: usize,
+    pub completed: usize,
+    pub completed_with_warnings: usize,
+    pub failed: usize,
+    pub records_inserted: i64,
+    /// Sources with at least one failed run, sorted
+    pub failed_sources: Vec<String>,
+}
+
+impl IngestionHealth {
+    pub fn from_runs(runs: &[IngestionRun]) -> Self {
+        let count = |status: &str| runs.iter().filter(|r| r.status == status).count();
+
+        let mut failed_sources: Vec<String> = runs
+            .iter()
+            .filter(|r| r.status == "failed")
+            .map(|r| r.source_id.clone())
+            .collect();
+        failed_sources.sort();
+        failed_sources.dedup();
+
+        IngestionHealth {
+            runs: runs.len(),
+            completed: count("completed"),
+            completed_with_warnings: count("completed_with_warnings"),
+            failed: count("failed"),
+            records_inserted: runs.iter().map(|r| i64::from(r.records_inserted)).sum(),
+            failed_sources,
+        }
+    }
+}
+
+/// Build the digest for the seven days ending on `week_ending` (inclusive)
+/// A week without ingestion produces empty sections rather than an error
+pub async fn weekly_digest(db: &PgPool, week_ending: NaiveDate) -> Result<Digest> {
+    let week_starting = week_ending - Duration::days(6);
+    let from = midnight(week_starting);
+    let to = midnight(week_ending + Duration::days(1));
+
+    let (top_new_properties, current, previous, runs) = tokio::try_join!(
+        new_high_yield_properties(db, from, to, TOP_PROPERTIES),
+        latest_suburb_yields(db, week_starting, week_ending),
+        latest_suburb_yields(
+            db,
+            week_starting - Duration::days(7),
+            week_starting - Duration::days(1)
+        ),
+        runs::runs_started_between(db, from, to),
+    )?;
+
+    Ok(Digest {
+        week_starting,
+        week_ending,
+        top_new_properties,
+        yield_movers: yield_movers(&current, &previous, TOP_MOVERS),
+        ingestion: IngestionHealth::from_runs(&runs),
+    })
+}
+
+fn midnight(date: NaiveDate) -> NaiveDateTime {
+    date.and_hms_opt(0, 0, 0).expect("midnight is a valid time")
+}
+
+async fn new_high_yield_properties(
+    db: &PgPool,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    limit: i64,
+) -> Result<Vec<DigestProperty>, sqlx::Error> {
+    sqlx::query_as::<_, DigestProperty>(
+        r#"
+        SELECT id, address, suburb, postcode, state, bedrooms, price, weekly_rent,
+               rental_yield::FLOAT8 AS rental_yield
+        FROM properties
+        WHERE created_at >= $1 AND created_at < $2
+          AND rental_yield IS NOT NULL
+        ORDER BY rental_yield DESC, id
+        LIMIT $3
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Most recent statistics per group calculated between `from` and `to` (inclusive)
+async fn latest_suburb_yields(
+    db: &PgPool,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<SuburbYield>, sqlx::Error> {
+    sqlx::query_as::<_, SuburbYield>(
+        r#"
+        SELECT DISTINCT ON (suburb, postcode, state, bedrooms)
+            suburb, postcode, state, bedrooms,
+            median_rental_yield::FLOAT8 AS median_rental_yield
+        FROM suburb_statistics
+        WHERE calculated_date BETWEEN $1 AND $2
+          AND median_rental_yield IS NOT NULL
+        ORDER BY suburb, postcode, state, bedrooms, calculated_date DESC, id DESC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await
+}
+
+/// Groups present in both weeks whose median yield changed, largest change first
+pub fn yield_movers(
+    current: &[SuburbYield],
+    previous: &[SuburbYield],
+    limit: usize,
+) -> Vec<YieldMover> {
+    let key = |y: &SuburbYield| (y.suburb.clone(), y.postcode.clone(), y.state, y.bedrooms);
+    let previous: HashMap<_, f64> = previous
+        .iter()
+        .map(|y| (key(y), y.median_rental_yield))
+        .collect();
+
+    let mut movers: Vec<YieldMover> = current
+        .iter()
+        .filter_map(|y| {
+            let before = *previous.get(&key(y))?;
+            let change = round_yield_f64(y.median_rental_yield - before);
+            (change != 0.0).then(|| YieldMover {
+                suburb: y.suburb.clone(),
+                postcode: y.postcode.clone(),
+                state: y.state,
+                bedrooms: y.bedrooms,
+                previous_yield: before,
+                current_yield: y.median_rental_yield,
+                change,
+            })
+        })
+        .collect();
+
+    movers.sort_by(|a, b| {
+        b.change
+            .abs()
+            .total_cmp(&a.change.abs())
+            .then_with(|| a.suburb.cmp(&b.suburb))
+            .then_with(|| a.bedrooms.cmp(&b.bedrooms))
+    });
+    movers.truncate(limit);
+    movers
+}
+
+/// Pretty-printed JSON, as sent to the notification webhook
+pub fn render_json(digest: &Digest) -> String {
+    serde_json::to_string_pretty(digest).expect("digest should serialize")
+}
+
+/// Self-contained HTML page suitable for an email body
+pub fn render_html(digest: &Digest) -> String {
+    let mut out = String::new();
+    let title = format!("Market digest for the week ending {}", digest.week_ending);
+
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{}</title>", title);
+    out.push_str("</head>\n<body>\n");
+    let _ = writeln!(out, "<h1>{}</h1>", title);
+    let _ = writeln!(
+        out,
+        "<p>{} to {}</p>",
+        digest.week_starting, digest.week_ending
+    );
+
+    out.push_str("<h2>Top new high-yield properties</h2>\n");
+    if digest.top_new_properties.is_empty() {
+        out.push_str("<p>No new properties with a rental yield this week.</p>\n");
+    } else {
+        out.push_str("<table>\n<tr><th>Address</th><th>Suburb</th><th>Bedrooms</th><th>Price</th><th>Weekly rent</th><th>Yield</th></tr>\n");
+        for p in &digest.top_new_properties {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&p.address),
+                escape(&location(&p.suburb, p.state, p.postcode.as_deref())),
+                optional(p.bedrooms.map(|b| b.to_string())),
+                optional(p.price.map(format_money)),
+                optional(p.weekly_rent.map(|v| format_money(v.into()))),
+                percent(p.rental_yield)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("<h2>Biggest yield movers</h2>\n");
+    if digest.yield_movers.is_empty() {
+        out.push_str("<p>No suburb yields changed since last week.</p>\n");
+    } else {
+        out.push_str("<table>\n<tr><th>Suburb</th><th>Bedrooms</th><th>Last week</th><th>This week</th><th>Change</th></tr>\n");
+        for m in &digest.yield_movers {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&location(&m.suburb, m.state, m.postcode.as_deref())),
+                optional(m.bedrooms.map(|b| b.to_string())),
+                percent(m.previous_yield),
+                percent(m.current_yield),
+                points(m.change)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("<h2>Ingestion</h2>\n");
+    let health = &digest.ingestion;
+    if health.runs == 0 {
+        out.push_str("<p>No ingestion runs this week.</p>\n");
+    } else {
+        out.push_str("<ul>\n");
+        let _ = writeln!(out, "<li>Runs: {}</li>", health.runs);
+        let _ = writeln!(out, "<li>Completed: {}</li>", health.completed);
+        let _ = writeln!(
+            out,
+            "<li>Completed with warnings: {}</li>",
+            health.completed_with_warnings
+        );
+        let _ = writeln!(out, "<li>Failed: {}</li>", health.failed);
+        let _ = writeln!(
+            out,
+            "<li>Records inserted: {}</li>",
+            health.records_inserted
+        );
+        out.push_str("</ul>\n");
+        if !health.failed_sources.is_empty() {
+            let sources: Vec<String> = health.failed_sources.iter().map(|s| escape(s)).collect();
+            let _ = writeln!(out, "<p>Failed sources: {}</p>", sources.join(", "));
+        }
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn location(suburb: &str, state: State, postcode: Option<&str>) -> String {
+    match postcode {
+        Some(postcode) => format!("{} {} {}", suburb, state, postcode),
+        None => format!("{} {}", suburb, state),
+    }
+}
+
+fn optional(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "-".to_string())
+}
+
+fn percent(value: f64) -> String {
+    optional(Decimal::from_f64(value).map(format_yield))
+}
+
+fn points(value: f64) -> String {
+    optional(Decimal::from_f64(value).map(format_yield_change))
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, PropertyFixture, SuburbStatsFixture};
+
+    const SUBURB: &str = "Digest Testville";
+
+    fn suburb_yield(suburb: &str, bedrooms: i32, median_rental_yield: f64) -> SuburbYield {
+        SuburbYield {
+            suburb: suburb.to_string(),
+            postcode: Some("2999".to_string()),
+            state: State::NSW,
+            bedrooms: Some(bedrooms),
+            median_rental_yield,
+        }
+    }
+
+    async fn cleanup(db: &PgPool) {
+        delete_suburb(db, SUBURB).await.unwrap();
+        sqlx::query("DELETE FROM suburb_statistics WHERE suburb = $1")
+            .bind(SUBURB)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_yield_movers_ranked_by_size_of_change() {
+        let previous = vec![
+            suburb_yield("Alpha", 2, 4.00),
+            suburb_yield("Beta", 3, 5.00),
+            suburb_yield("Gamma", 2, 3.50),
+            suburb_yield("Delta", 2, 4.20),
+        ];
+        let current = vec![
+            suburb_yield("Alpha", 2, 4.10),
+            suburb_yield("Beta", 3, 4.40),
+            suburb_yield("Gamma", 2, 3.50),
+            // Not present last week, so there is nothing to compare against
+            suburb_yield("Epsilon", 2, 9.00),
+            suburb_yield("Delta", 2, 4.50),
+        ];
+
+        let movers = yield_movers(&current, &previous, 2);
+        let ranked: Vec<(&str, f64)> = movers
+            .iter()
+            .map(|m| (m.suburb.as_str(), m.change))
+            .collect();
+        assert_eq!(ranked, vec![("Beta", -0.6), ("Delta", 0.3)]);
+    }
+
+    #[test]
+    fn test_html_escapes_addresses() {
+        let digest = Digest {
+            week_starting: NaiveDate::from_ymd_opt(2025, 5, 26).unwrap(),
+            week_ending: NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+            top_new_properties: vec![DigestProperty {
+                id: 1,
+                address: "<b>1 Main</b> & Co".to_string(),
+                suburb: "Testville".to_string(),
+                postcode: None,
+                state: State::NSW,
+                bedrooms: None,
+                price: None,
+                weekly_rent: None,
+                rental_yield: 5.0,
+            }],
+            yield_movers: Vec::new(),
+            ingestion: IngestionHealth::default(),
+        };
+
+        let html = render_html(&digest);
+        assert!(html.contains("&lt;b&gt;1 Main&lt;/b&gt; &amp; Co"));
+        assert!(!html.contains("<b>"));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_weekly_digest_from_database() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        // A week long before any real data has nothing to report
+        let quiet = NaiveDate::from_ymd_opt(1999, 1, 3).unwrap();
+        let digest = weekly_digest(&db, quiet).await.unwrap();
+        assert!(digest.top_new_properties.is_empty());
+        assert!(digest.yield_movers.is_empty());
+        assert_eq!(digest.ingestion, IngestionHealth::default());
+
+        // Properties are dated by when they were ingested, so seed the current week
+        let week_ending = chrono::Utc::now().date_naive();
+        let id = PropertyFixture::new()
+            .suburb(SUBURB)
+            .price(400_000)
+            .weekly_rent(750)
+            .rental_yield("99.50")
+            .insert(&db)
+            .await
+            .unwrap();
+        for (date, rental_yield) in [
+            (week_ending - Duration::days(8), "3.00"),
+            (week_ending - Duration::days(1), "12.00"),
+        ] {
+            SuburbStatsFixture::new(SUBURB)
+                .median_rental_yield(rental_yield)
+                .calculated_date(date)
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+
+        let digest = weekly_digest(&db, week_ending).await.unwrap();
+        assert_eq!(digest.top_new_properties[0].id, id);
+        let mover = digest
+            .yield_movers
+            .iter()
+            .find(|m| m.suburb == SUBURB)
+            .unwrap();
+        assert_eq!(mover.previous_yield, 3.0);
+        assert_eq!(mover.change, 9.0);
+
+        cleanup(&db).await;
+    }
+}
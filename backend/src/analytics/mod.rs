@@ -0,0 +1,8 @@
+//! Analytics module - aggregate statistics derived from property data
+
+pub mod council_rates;
+pub mod digest;
+pub mod quadrants;
+pub mod suburb_stats;
+pub mod suppression;
+pub mod yield_history;
@@ -0,0 +1,289 @@
+//! Yield-vs-growth quadrants - sort suburbs the way investors compare them
+//! A suburb is "high" on a metric when it is at or above the threshold
+
+use serde::Serialize;
+
+/// Position of a suburb relative to the yield and growth thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quadrant {
+    HighYieldHighGrowth,
+    HighYieldLowGrowth,
+    LowYieldHighGrowth,
+    LowYieldLowGrowth,
+}
+
+/// Yield and growth for one suburb; either may be missing (or suppressed)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SuburbMetrics {
+    pub suburb: String,
+    pub postcode: Option<String>,
+    /// Median gross rental yield (%)
+    pub rental_yield: Option<f64>,
+    /// Annual change in median sale price (%)
+    pub growth: Option<f64>,
+}
+
+/// Cut-off values separating high from low
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Thresholds {
+    pub rental_yield: f64,
+    pub growth: f64,
+}
+
+impl Thresholds {
+    /// Use explicit thresholds where given, otherwise the median across suburbs
+    /// that have both metrics. None when a median is needed but nothing qualifies.
+    pub fn resolve(
+        rental_yield: Option<f64>,
+        growth: Option<f64>,
+        metrics: &[SuburbMetrics],
+    ) -> Option<Self> {
+        let complete: Vec<(f64, f64)> = metrics
+            .iter()
+            .filter_map(|m| Some((m.rental_yield?, m.growth?)))
+            .collect();
+
+        Some(Thresholds {
+            rental_yield: match rental_yield {
+                Some(value) => value,
+                None => median(complete.iter().map(|(y, _)| *y))?,
+            },
+            growth: match growth {
+                Some(value) => value,
+                None => median(complete.iter().map(|(_, g)| *g))?,
+            },
+        })
+    }
+}
+
+/// A suburb with its assigned quadrant
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClassifiedSuburb {
+    pub suburb: String,
+    pub postcode: Option<String>,
+    pub rental_yield: f64,
+    pub growth: f64,
+    pub quadrant: Quadrant,
+}
+
+/// Number of suburbs in each quadrant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct QuadrantCounts {
+    pub high_yield_high_growth: usize,
+    pub high_yield_low_growth: usize,
+    pub low_yield_high_growth: usize,
+    pub low_yield_low_growth: usize,
+    pub unclassified: usize,
+}
+
+/// Classified suburbs plus those missing a metric
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuadrantReport {
+    pub thresholds: Option<Thresholds>,
+    pub counts: QuadrantCounts,
+    pub suburbs: Vec<ClassifiedSuburb>,
+    pub unclassified: Vec<SuburbMetrics>,
+}
+
+/// Median of a set of values, or None if empty
+pub fn median(values: impl IntoIterator<Item = f64>) -> Option<f64> {
+    let mut values: Vec<f64> = values.into_iter().collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+/// Assign a quadrant; values equal to a threshold count as high
+pub fn classify(rental_yield: f64, growth: f64, thresholds: &Thresholds) -> Quadrant {
+    match (
+        rental_yield >= thresholds.rental_yield,
+        growth >= thresholds.growth,
+    ) {
+        (true, true) => Quadrant::HighYieldHighGrowth,
+        (true, false) => Quadrant::HighYieldLowGrowth,
+        (false, true) => Quadrant::LowYieldHighGrowth,
+        (false, false) => Quadrant::LowYieldLowGrowth,
+    }
+}
+
+/// Classify every suburb that has both metrics; the rest go to `unclassified`
+/// Without thresholds nothing can be classified, so every suburb is unclassified
+pub fn classify_suburbs(
+    metrics: Vec<SuburbMetrics>,
+    thresholds: Option<Thresholds>,
+) -> QuadrantReport {
+    let mut counts = QuadrantCounts::default();
+    let mut suburbs = Vec::new();
+    let mut unclassified = Vec::new();
+
+    for m in metrics {
+        match (m.rental_yield, m.growth, &thresholds) {
+            (Some(rental_yield), Some(growth), Some(thresholds)) => {
+                let quadrant = classify(rental_yield, growth, thresholds);
+                match quadrant {
+                    Quadrant::HighYieldHighGrowth => counts.high_yield_high_growth += 1,
+                    Quadrant::HighYieldLowGrowth => counts.high_yield_low_growth += 1,
+                    Quadrant::LowYieldHighGrowth => counts.low_yield_high_growth += 1,
+                    Quadrant::LowYieldLowGrowth => counts.low_yield_low_growth += 1,
+                }
+                suburbs.push(ClassifiedSuburb {
+                    suburb: m.suburb,
+                    postcode: m.postcode,
+                    rental_yield,
+                    growth,
+                    quadrant,
+                });
+            }
+            _ => {
+                counts.unclassified += 1;
+                unclassified.push(m);
+            }
+        }
+    }
+
+    QuadrantReport {
+        thresholds,
+        counts,
+        suburbs,
+        unclassified,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(suburb: &str, rental_yield: Option<f64>, growth: Option<f64>) -> SuburbMetrics {
+        SuburbMetrics {
+            suburb: suburb.to_string(),
+            postcode: None,
+            rental_yield,
+            growth,
+        }
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median([]), None);
+        assert_eq!(median([3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median([4.0, 1.0, 2.0, 3.0]), Some(2.5));
+    }
+
+    #[test]
+    fn test_classify_boundaries() {
+        let thresholds = Thresholds {
+            rental_yield: 4.0,
+            growth: 5.0,
+        };
+
+        assert_eq!(
+            classify(4.0, 5.0, &thresholds),
+            Quadrant::HighYieldHighGrowth
+        );
+        assert_eq!(
+            classify(4.5, 4.9, &thresholds),
+            Quadrant::HighYieldLowGrowth
+        );
+        assert_eq!(
+            classify(3.9, 5.0, &thresholds),
+            Quadrant::LowYieldHighGrowth
+        );
+        assert_eq!(
+            classify(3.0, -2.0, &thresholds),
+            Quadrant::LowYieldLowGrowth
+        );
+    }
+
+    #[test]
+    fn test_thresholds_default_to_medians() {
+        let all = vec![
+            metrics("A", Some(3.0), Some(1.0)),
+            metrics("B", Some(5.0), Some(9.0)),
+            metrics("C", Some(4.0), Some(5.0)),
+            // Incomplete suburbs don't move the medians
+            metrics("D", Some(100.0), None),
+        ];
+
+        assert_eq!(
+            Thresholds::resolve(None, None, &all),
+            Some(Thresholds {
+                rental_yield: 4.0,
+                growth: 5.0
+            })
+        );
+
+        // Explicit values override one metric at a time
+        assert_eq!(
+            Thresholds::resolve(Some(6.0), None, &all),
+            Some(Thresholds {
+                rental_yield: 6.0,
+                growth: 5.0
+            })
+        );
+
+        assert_eq!(Thresholds::resolve(None, None, &all[3..]), None);
+        assert!(Thresholds::resolve(Some(4.0), Some(5.0), &[]).is_some());
+    }
+
+    #[test]
+    fn test_classify_suburbs_keeps_unclassified() {
+        let thresholds = Thresholds {
+            rental_yield: 4.0,
+            growth: 5.0,
+        };
+        let report = classify_suburbs(
+            vec![
+                metrics("A", Some(5.0), Some(6.0)),
+                metrics("B", Some(3.0), Some(6.0)),
+                metrics("C", None, Some(6.0)),
+                metrics("D", Some(5.0), None),
+            ],
+            Some(thresholds),
+        );
+
+        assert_eq!(report.suburbs.len(), 2);
+        assert_eq!(report.suburbs[0].quadrant, Quadrant::HighYieldHighGrowth);
+        assert_eq!(report.suburbs[1].quadrant, Quadrant::LowYieldHighGrowth);
+
+        let unclassified: Vec<&str> = report
+            .unclassified
+            .iter()
+            .map(|m| m.suburb.as_str())
+            .collect();
+        assert_eq!(unclassified, vec!["C", "D"]);
+
+        assert_eq!(
+            report.counts,
+            QuadrantCounts {
+                high_yield_high_growth: 1,
+                high_yield_low_growth: 0,
+                low_yield_high_growth: 1,
+                low_yield_low_growth: 0,
+                unclassified: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_classify_suburbs_without_thresholds() {
+        let report = classify_suburbs(vec![metrics("A", Some(5.0), Some(6.0))], None);
+
+        assert!(report.suburbs.is_empty());
+        assert_eq!(report.counts.unclassified, 1);
+    }
+
+    #[test]
+    fn test_quadrant_serializes_snake_case() {
+        let json = serde_json::to_value(Quadrant::HighYieldLowGrowth).unwrap();
+        assert_eq!(json, "high_yield_low_growth");
+    }
+}
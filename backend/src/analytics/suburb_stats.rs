@@ -0,0 +1,168 @@
+//! Suburb statistics - recompute the suburb_statistics table from properties
+//! Shared by the ingestion pipeline and the API server's background refresh
+
+use crate::ingestion::types::State;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Recompute today's suburb statistics, and their SA3 region rollup, for one state
+/// Replaces any rows already calculated today so repeated refreshes don't duplicate
+/// Returns the number of suburb/bedroom groups written
+pub async fn refresh_suburb_statistics(db: &PgPool, state: State) -> Result<u64> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query(
+        "DELETE FROM suburb_statistics WHERE state = $1 AND calculated_date = CURRENT_DATE",
+    )
+    .bind(state)
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO suburb_statistics (
+            suburb, postcode, state, bedrooms,
+            median_price, median_weekly_rent, median_rental_yield, avg_rental_yield,
+            property_count, min_yield, max_yield,
+            yield_25th_percentile, yield_75th_percentile,
+            price_25th_percentile, price_75th_percentile,
+            calculated_date, data_source
+        )
+        SELECT
+            suburb, postcode, state, bedrooms,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY price)::INTEGER,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY weekly_rent)::INTEGER,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY rental_yield)::DECIMAL(5, 2),
+            AVG(rental_yield)::DECIMAL(5, 2),
+            COUNT(*),
+            MIN(rental_yield),
+            MAX(rental_yield),
+            percentile_cont(0.25) WITHIN GROUP (ORDER BY rental_yield)::DECIMAL(5, 2),
+            percentile_cont(0.75) WITHIN GROUP (ORDER BY rental_yield)::DECIMAL(5, 2),
+            percentile_cont(0.25) WITHIN GROUP (ORDER BY price)::INTEGER,
+            percentile_cont(0.75) WITHIN GROUP (ORDER BY price)::INTEGER,
+            CURRENT_DATE,
+            'properties'
+        FROM properties
+        WHERE state = $1 AND price IS NOT NULL
+        GROUP BY suburb, postcode, state, bedrooms
+        "#,
+    )
+    .bind(state)
+    .execute(&mut *tx)
+    .await?;
+
+    let regions = refresh_region_statistics(&mut tx, state).await?;
+
+    tx.commit().await?;
+
+    debug!(
+        "Refreshed suburb statistics for {}: {} groups, {} region groups",
+        state,
+        result.rows_affected(),
+        regions
+    );
+
+    Ok(result.rows_affected())
+}
+
+/// SA3-level rollup pass: the same medians grouped by each property's postcode region
+/// Properties whose postcode isn't in the regions lookup are left out
+async fn refresh_region_statistics(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    state: State,
+) -> Result<u64> {
+    sqlx::query(
+        "DELETE FROM region_statistics WHERE state = $1 AND calculated_date = CURRENT_DATE",
+    )
+    .bind(state)
+    .execute(&mut **tx)
+    .await?;
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO region_statistics (
+            sa3_code, sa3_name, sa4_code, state, bedrooms,
+            median_price, median_weekly_rent, median_rental_yield, avg_rental_yield,
+            property_count, calculated_date
+        )
+        SELECT
+            r.sa3_code, r.sa3_name, r.sa4_code, p.state, p.bedrooms,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY p.price)::INTEGER,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY p.weekly_rent)::INTEGER,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY p.rental_yield)::DECIMAL(5, 2),
+            AVG(p.rental_yield)::DECIMAL(5, 2),
+            COUNT(*),
+            CURRENT_DATE
+        FROM properties p
+        JOIN regions r ON r.postcode = p.postcode
+        WHERE p.state = $1 AND p.price IS NOT NULL
+        GROUP BY r.sa3_code, r.sa3_name, r.sa4_code, p.state, p.bedrooms
+        "#,
+    )
+    .bind(state)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Most recent `last_updated` per state, used to detect which states changed
+pub async fn last_updated_by_state(db: &PgPool) -> Result<HashMap<State, NaiveDateTime>> {
+    let rows = sqlx::query_as::<_, (State, Option<NaiveDateTime>)>(
+        "SELECT state, MAX(last_updated) FROM properties GROUP BY state",
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(state, updated)| updated.map(|u| (state, u)))
+        .collect())
+}
+
+/// States whose latest update differs from what was seen at the last computation
+pub fn changed_states(
+    previous: &HashMap<State, NaiveDateTime>,
+    current: &HashMap<State, NaiveDateTime>,
+) -> Vec<State> {
+    let mut changed: Vec<State> = current
+        .iter()
+        .filter(|(state, updated)| previous.get(state) != Some(updated))
+        .map(|(state, _)| *state)
+        .collect();
+    changed.sort_by_key(|s| s.to_string());
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_changed_states() {
+        let previous = HashMap::from([(State::NSW, at(1)), (State::VIC, at(1))]);
+        let current = HashMap::from([
+            (State::NSW, at(1)), // unchanged
+            (State::VIC, at(2)), // updated
+            (State::QLD, at(1)), // new
+        ]);
+
+        assert_eq!(
+            changed_states(&previous, &current),
+            vec![State::QLD, State::VIC]
+        );
+        assert!(changed_states(&current, &current).is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+//! Small-sample suppression for published statistics
+//! Any aggregate built from too few records is withheld (or rolled up to a
+//! coarser region) so we never publish misleading or identifying medians
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::hash::Hash;
+
+/// Default minimum number of underlying records for a published aggregate
+pub const DEFAULT_MIN_SAMPLE_SIZE: i64 = 5;
+
+/// Suppression rules, loaded from environment variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuppressionConfig {
+    /// Aggregates from fewer records than this are suppressed
+    pub min_sample_size: i64,
+    /// Combine small groups that share a parent region (e.g. postcode -> SA3)
+    pub roll_up: bool,
+}
+
+impl Default for SuppressionConfig {
+    fn default() -> Self {
+        SuppressionConfig {
+            min_sample_size: DEFAULT_MIN_SAMPLE_SIZE,
+            roll_up: false,
+        }
+    }
+}
+
+impl SuppressionConfig {
+    pub fn from_env() -> Self {
+        SuppressionConfig {
+            min_sample_size: env::var("STATS_MIN_SAMPLE_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_MIN_SAMPLE_SIZE),
+            roll_up: env::var("STATS_ROLL_UP_SMALL_GROUPS")
+                .map(|s| s == "true" || s == "1")
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// An aggregate value and the number of records it was derived from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregate<K> {
+    pub key: K,
+    pub value: f64,
+    pub sample_size: i64,
+}
+
+/// An aggregate after suppression rules have been applied
+/// `value` is None whenever `suppressed` is true
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Published<K, P> {
+    pub key: K,
+    pub value: Option<f64>,
+    pub sample_size: i64,
+    pub suppressed: bool,
+    /// Parent region whose combined value was published in place of this group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rolled_up_to: Option<P>,
+}
+
+/// Apply suppression rules to a set of aggregates
+///
+/// Groups with at least `min_sample_size` records are published as-is. When
+/// roll-up is enabled, the remaining small groups are combined per parent
+/// region (sample-size weighted) and publish the combined value if the
+/// combined sample clears the threshold. Everything else is suppressed.
+pub fn apply_suppression<K, P, F>(
+    aggregates: Vec<Aggregate<K>>,
+    parent_of: F,
+    config: &SuppressionConfig,
+) -> Vec<Published<K, P>>
+where
+    P: Clone + Eq + Hash,
+    F: Fn(&K) -> Option<P>,
+{
+    // Combined totals of the small groups under each parent: (weighted sum, sample size)
+    let mut rollups: HashMap<P, (f64, i64)> = HashMap::new();
+
+    if config.roll_up {
+        for agg in &aggregates {
+            if agg.sample_size >= config.min_sample_size {
+                continue;
+            }
+            if let Some(parent) = parent_of(&agg.key) {
+                let entry = rollups.entry(parent).or_insert((0.0, 0));
+                entry.0 += agg.value * agg.sample_size as f64;
+                entry.1 += agg.sample_size;
+            }
+        }
+    }
+
+    aggregates
+        .into_iter()
+        .map(|agg| {
+            if agg.sample_size >= config.min_sample_size {
+                return Published {
+                    key: agg.key,
+                    value: Some(agg.value),
+                    sample_size: agg.sample_size,
+                    suppressed: false,
+                    rolled_up_to: None,
+                };
+            }
+
+            let rolled_up = parent_of(&agg.key).and_then(|parent| {
+                let (sum, count) = *rollups.get(&parent)?;
+                (count >= config.min_sample_size).then(|| (parent, sum / count as f64, count))
+            });
+
+            match rolled_up {
+                Some((parent, value, count)) => Published {
+                    key: agg.key,
+                    value: Some(value),
+                    sample_size: count,
+                    suppressed: false,
+                    rolled_up_to: Some(parent),
+                },
+                None => Published {
+                    key: agg.key,
+                    value: None,
+                    sample_size: agg.sample_size,
+                    suppressed: true,
+                    rolled_up_to: None,
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agg(key: &str, value: f64, sample_size: i64) -> Aggregate<String> {
+        Aggregate {
+            key: key.to_string(),
+            value,
+            sample_size,
+        }
+    }
+
+    fn no_parent(_: &String) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_threshold_boundary() {
+        let config = SuppressionConfig::default();
+        let published = apply_suppression(
+            vec![agg("2000", 600.0, 5), agg("2001", 550.0, 4)],
+            no_parent,
+            &config,
+        );
+
+        // Exactly N records is published
+        assert_eq!(published[0].value, Some(600.0));
+        assert!(!published[0].suppressed);
+
+        // N - 1 records is suppressed
+        assert_eq!(published[1].value, None);
+        assert!(published[1].suppressed);
+    }
+
+    #[test]
+    fn test_roll_up_combines_small_groups() {
+        let config = SuppressionConfig {
+            min_sample_size: 5,
+            roll_up: true,
+        };
+        // 2000 and 2001 share a parent; 2002 is alone in its parent
+        let parent = |key: &String| match key.as_str() {
+            "2000" | "2001" => Some("SA3-A".to_string()),
+            _ => Some("SA3-B".to_string()),
+        };
+
+        let published = apply_suppression(
+            vec![
+                agg("2000", 400.0, 2),
+                agg("2001", 700.0, 3),
+                agg("2002", 500.0, 2),
+                agg("2003", 800.0, 10),
+            ],
+            parent,
+            &config,
+        );
+
+        // 2 + 3 = 5 records under SA3-A: weighted (400*2 + 700*3) / 5 = 580
+        assert_eq!(published[0].value, Some(580.0));
+        assert_eq!(published[0].sample_size, 5);
+        assert_eq!(published[0].rolled_up_to, Some("SA3-A".to_string()));
+        assert_eq!(published[1].value, Some(580.0));
+
+        // SA3-B only has 2 small records - still suppressed
+        assert!(published[2].suppressed);
+        assert_eq!(published[2].rolled_up_to, None);
+
+        // Large groups are never rolled up
+        assert_eq!(published[3].value, Some(800.0));
+        assert_eq!(published[3].rolled_up_to, None);
+    }
+
+    #[test]
+    fn test_roll_up_disabled_suppresses() {
+        let config = SuppressionConfig::default();
+        let parent = |_: &String| Some("SA3-A".to_string());
+
+        let published = apply_suppression(
+            vec![agg("2000", 400.0, 3), agg("2001", 700.0, 3)],
+            parent,
+            &config,
+        );
+
+        assert!(published.iter().all(|p| p.suppressed && p.value.is_none()));
+    }
+
+    #[test]
+    fn test_suppressed_serializes_marker() {
+        let published = Published::<String, String> {
+            key: "2000".to_string(),
+            value: None,
+            sample_size: 2,
+            suppressed: true,
+            rolled_up_to: None,
+        };
+
+        let json = serde_json::to_value(&published).unwrap();
+        assert_eq!(json["suppressed"], true);
+        assert!(json["value"].is_null());
+        assert!(json.get("rolled_up_to").is_none());
+    }
+}
@@ -0,0 +1,143 @@
+//! Yield history - what a property's yield would have been in each rental period
+//!
+//! Each period's median rent is set against the price the property last sold
+//! for at or before that period. Periods with no earlier sale are left out.
+
+use crate::calculate_rental_yield;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// A recorded sale of the property
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct HistoricalSale {
+    pub sale_id: i32,
+    pub sale_date: NaiveDate,
+    pub sale_price: i64,
+}
+
+/// The median rent for the property's key in one period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct HistoricalRent {
+    pub period: NaiveDate,
+    pub median_rent: i32,
+    pub sample_size: Option<i32>,
+}
+
+/// One point in the series, with the sale and median it was calculated from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YieldPoint {
+    pub period: NaiveDate,
+    /// Percent, at stored precision
+    pub rental_yield: Decimal,
+    pub sale: HistoricalSale,
+    pub rent: HistoricalRent,
+}
+
+/// Yield for every rental period that has a sale at or before it, oldest first
+/// Inputs may be in any order; of two sales on one day the later-recorded wins
+pub fn yield_history(sales: &[HistoricalSale], rents: &[HistoricalRent]) -> Vec<YieldPoint> {
+    let mut sales = sales.to_vec();
+    sales.sort_by_key(|s| (s.sale_date, s.sale_id));
+    let mut rents = rents.to_vec();
+    rents.sort_by_key(|r| r.period);
+
+    rents
+        .into_iter()
+        .filter_map(|rent| {
+            let before = sales.partition_point(|s| s.sale_date <= rent.period);
+            let sale = sales[..before].last()?.clone();
+            let rental_yield = calculate_rental_yield(sale.sale_price, rent.median_rent)?;
+            Some(YieldPoint {
+                period: rent.period,
+                rental_yield,
+                sale,
+                rent,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn sale(sale_id: i32, sale_date: &str, sale_price: i64) -> HistoricalSale {
+        HistoricalSale {
+            sale_id,
+            sale_date: date(sale_date),
+            sale_price,
+        }
+    }
+
+    fn rent(period: &str, median_rent: i32) -> HistoricalRent {
+        HistoricalRent {
+            period: date(period),
+            median_rent,
+            sample_size: Some(20),
+        }
+    }
+
+    #[test]
+    fn test_each_period_uses_latest_sale_before_it() {
+        let sales = [
+            sale(2, "2021-06-15", 800_000),
+            sale(1, "2018-03-01", 650_000),
+        ];
+        let rents = [
+            rent("2022-01-01", 600),
+            rent("2019-01-01", 500),
+            rent("2021-06-15", 550),
+        ];
+
+        let points = yield_history(&sales, &rents);
+
+        let series: Vec<(NaiveDate, i32, Decimal)> = points
+            .iter()
+            .map(|p| (p.period, p.sale.sale_id, p.rental_yield))
+            .collect();
+        assert_eq!(
+            series,
+            vec![
+                (date("2019-01-01"), 1, Decimal::new(40000, 4)),
+                // A sale on the period's own date counts as then-current
+                (date("2021-06-15"), 2, Decimal::new(35750, 4)),
+                (date("2022-01-01"), 2, Decimal::new(39000, 4)),
+            ]
+        );
+        assert_eq!(points[0].rent.median_rent, 500);
+    }
+
+    #[test]
+    fn test_periods_before_first_sale_are_omitted() {
+        let sales = [sale(1, "2020-07-01", 700_000)];
+        let rents = [rent("2020-01-01", 500), rent("2021-01-01", 600)];
+
+        let points = yield_history(&sales, &rents);
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].period, date("2021-01-01"));
+        // The canonical calculation's storage rounding: 600 * 52 / 700000
+        assert_eq!(points[0].rental_yield, Decimal::new(44571, 4));
+    }
+
+    #[test]
+    fn test_same_day_sales_use_later_record() {
+        let sales = [sale(7, "2020-01-01", 500_000), sale(3, "2020-01-01", 1)];
+        let points = yield_history(&sales, &[rent("2020-02-01", 500)]);
+
+        assert_eq!(points[0].sale.sale_id, 7);
+    }
+
+    #[test]
+    fn test_missing_inputs_give_empty_series() {
+        assert!(yield_history(&[], &[rent("2020-01-01", 500)]).is_empty());
+        assert!(yield_history(&[sale(1, "2020-01-01", 500_000)], &[]).is_empty());
+        // A zero price has no yield
+        assert!(yield_history(&[sale(1, "2020-01-01", 0)], &[rent("2020-02-01", 500)]).is_empty());
+    }
+}
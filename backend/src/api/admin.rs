@@ -0,0 +1,507 @@
+//! Admin endpoints - operational tools behind the admin API key
+
+use crate::api::params::{
+    check_length, check_range, ParamError, ValidateParams, ValidatedListParams,
+};
+use crate::api::{AppState, API_KEY_HEADER};
+use crate::ingestion::archive::RawArchive;
+use crate::ingestion::quality::{self, QualityReport};
+use crate::ingestion::refresh::{self, RefreshError, RefreshOutcome};
+use crate::ingestion::runs;
+use crate::ingestion::staging::{self, BatchReview, StagingBatch, StagingError};
+use crate::ingestion::IngestionRun;
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Path, State};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::env;
+use tracing::{error, warn};
+
+const DEFAULT_RUNS_LIMIT: i64 = 50;
+const MAX_RUNS_LIMIT: i64 = 500;
+
+/// Admin settings; admin routes are disabled when no API key is configured
+#[derive(Debug, Clone)]
+pub struct AdminConfig {
+    pub api_key: Option<String>,
+    /// Where raw source files are archived, for refreshes
+    pub archive: RawArchive,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        AdminConfig {
+            api_key: None,
+            archive: RawArchive::from_env(),
+        }
+    }
+}
+
+impl AdminConfig {
+    /// Key from ADMIN_API_KEY, archive from ARCHIVE_DIR
+    pub fn from_env() -> Self {
+        AdminConfig {
+            api_key: env::var("ADMIN_API_KEY")
+                .ok()
+                .filter(|key| !key.trim().is_empty()),
+            archive: RawArchive::from_env(),
+        }
+    }
+}
+
+/// Extractor that admits only requests carrying the admin API key
+#[derive(Debug, Clone)]
+pub struct AdminCaller {
+    /// Recorded as the actor in audit logs
+    pub actor: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminCaller {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(expected) = state.admin.api_key.as_deref() else {
+            return Err(StatusCode::FORBIDDEN);
+        };
+
+        let provided = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok());
+        match provided {
+            Some(key) if keys_match(key, expected) => Ok(AdminCaller {
+                actor: "admin".to_string(),
+            }),
+            _ => {
+                warn!("Rejected admin request to {}", parts.uri.path());
+                Err(StatusCode::UNAUTHORIZED)
+            }
+        }
+    }
+}
+
+/// Compare SHA-256 digests byte by byte without stopping at the first
+/// difference, so response times don't reveal how much of the key was right
+fn keys_match(provided: &str, expected: &str) -> bool {
+    let provided = Sha256::digest(provided.as_bytes());
+    let expected = Sha256::digest(expected.as_bytes());
+    provided
+        .iter()
+        .zip(expected.iter())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// POST /api/admin/properties/:id/refresh - re-ingest one property from its
+/// archived source row and return what changed
+/// 409 when the property can't be traced back to an archived file
+pub async fn refresh_property(
+    State(state): State<AppState>,
+    caller: AdminCaller,
+    Path(id): Path<i32>,
+) -> Result<Json<RefreshOutcome>, Response> {
+    refresh::refresh_property(&state.db, &state.admin.archive, id, &caller.actor)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            RefreshError::NotFound => StatusCode::NOT_FOUND.into_response(),
+            RefreshError::Other(inner) => {
+                error!("Refresh of property {} failed: {}", id, inner);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+            unavailable => {
+                let body =
+                    json!({ "error": "source_unavailable", "message": unavailable.to_string() });
+                (StatusCode::CONFLICT, Json(body)).into_response()
+            }
+        })
+}
+
+/// Query parameters for GET /api/admin/ingestion/runs
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RunsQuery {
+    pub source_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl ValidateParams for RunsQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("source_id", self.source_id.as_deref(), 50)?;
+        check_range("limit", self.limit, 1..=MAX_RUNS_LIMIT)
+    }
+}
+
+impl RunsQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_RUNS_LIMIT)
+    }
+}
+
+/// GET /api/admin/ingestion/runs - most recent ingestion runs first
+pub async fn list_ingestion_runs(
+    State(state): State<AppState>,
+    _caller: AdminCaller,
+    ValidatedListParams(params): ValidatedListParams<RunsQuery>,
+) -> Result<Json<Vec<IngestionRun>>, StatusCode> {
+    runs::list_runs(&state.read_db, params.source_id.as_deref(), params.limit())
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// GET /api/admin/ingestion/runs/:id - one ingestion run with its latest progress
+pub async fn get_ingestion_run(
+    State(state): State<AppState>,
+    _caller: AdminCaller,
+    Path(id): Path<i32>,
+) -> Result<Json<IngestionRun>, StatusCode> {
+    match runs::fetch_run(&state.read_db, id).await {
+        Ok(Some(run)) => Ok(Json(run)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Database error: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// GET /api/admin/quality - completeness of stored properties per data source
+pub async fn get_quality_report(
+    State(state): State<AppState>,
+    _caller: AdminCaller,
+) -> Result<Json<QualityReport>, StatusCode> {
+    quality::quality_report(&state.read_db)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// GET /api/admin/staging - pending staging batches, oldest first, with
+/// quality summaries and sample rows
+pub async fn list_staging_batches(
+    State(state): State<AppState>,
+    _caller: AdminCaller,
+) -> Result<Json<Vec<StagingBatch>>, StatusCode> {
+    staging::list_pending_batches(&state.db, staging::STAGING_SAMPLE_ROWS)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// POST /api/admin/staging/:id/approve - merge a pending batch into properties
+/// 409 when the batch was already reviewed or any record failed to merge
+pub async fn approve_staging_batch(
+    State(state): State<AppState>,
+    caller: AdminCaller,
+    Path(id): Path<i32>,
+) -> Result<Json<BatchReview>, Response> {
+    staging::approve_batch(&state.db, id, &caller.actor)
+        .await
+        .map(Json)
+        .map_err(|e| staging_error_response(id, e))
+}
+
+/// POST /api/admin/staging/:id/reject - discard a pending batch
+/// 409 when the batch was already reviewed
+pub async fn reject_staging_batch(
+    State(state): State<AppState>,
+    caller: AdminCaller,
+    Path(id): Path<i32>,
+) -> Result<Json<BatchReview>, Response> {
+    staging::reject_batch(&state.db, id, &caller.actor)
+        .await
+        .map(Json)
+        .map_err(|e| staging_error_response(id, e))
+}
+
+fn staging_error_response(id: i32, e: StagingError) -> Response {
+    let code = match &e {
+        StagingError::NotFound => return StatusCode::NOT_FOUND.into_response(),
+        StagingError::Other(inner) => {
+            error!("Review of staging batch {} failed: {}", id, inner);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+        StagingError::NotPending(_) => "already_reviewed",
+        StagingError::MergeFailed(_) => "merge_failed",
+    };
+    let body = json!({ "error": code, "message": e.to_string() });
+    (StatusCode::CONFLICT, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const SUBURB: &str = "Refresh Testville";
+    const ADMIN_KEY: &str = "test-admin-key";
+
+    #[test]
+    fn test_keys_match() {
+        assert!(keys_match(ADMIN_KEY, ADMIN_KEY));
+        assert!(!keys_match("test-admin-kez", ADMIN_KEY));
+        assert!(!keys_match("test-admin", ADMIN_KEY));
+        assert!(!keys_match("", ADMIN_KEY));
+    }
+
+    fn app(db: PgPool, admin: AdminConfig) -> axum::Router {
+        crate::api::router().with_state(AppState {
+            admin: Arc::new(admin),
+            ..test_state(db)
+        })
+    }
+
+    async fn post_refresh(app: &axum::Router, id: i32, key: Option<&str>) -> (StatusCode, Value) {
+        let mut request = Request::post(format!("/api/admin/properties/{}/refresh", id));
+        if let Some(key) = key {
+            request = request.header(API_KEY_HEADER, key);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_refresh_requires_admin_key() {
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+
+        let disabled = app(db.clone(), AdminConfig::default());
+        assert_eq!(
+            post_refresh(&disabled, 1, Some(ADMIN_KEY)).await.0,
+            StatusCode::FORBIDDEN
+        );
+
+        let enabled = app(
+            db,
+            AdminConfig {
+                api_key: Some(ADMIN_KEY.to_string()),
+                ..AdminConfig::default()
+            },
+        );
+        assert_eq!(
+            post_refresh(&enabled, 1, None).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            post_refresh(&enabled, 1, Some("wrong")).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingestion_run_requires_admin_key() {
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = app(
+            db,
+            AdminConfig {
+                api_key: Some(ADMIN_KEY.to_string()),
+                ..AdminConfig::default()
+            },
+        );
+
+        let response = app
+            .oneshot(
+                Request::get("/api/admin/ingestion/runs/1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_refresh_from_fixture_archive() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+
+        // Archive the sales fixture the way the pipeline would
+        let root = tempfile::tempdir().unwrap();
+        let archive = RawArchive::new(root.path());
+        let fixture = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/nsw_sales.csv");
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+        let key = archive.store("nsw_sales", date, &fixture).unwrap();
+
+        // Row 0 is 10 Smith Street, sold for $750,000; the stored copy is stale
+        let id = PropertyFixture::new()
+            .address("10 Smith St")
+            .suburb(SUBURB)
+            .postcode("2000")
+            .price(700_000)
+            .data_source("nsw_sales")
+            .source_row(&key, 0)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let app = app(
+            db.clone(),
+            AdminConfig {
+                api_key: Some(ADMIN_KEY.to_string()),
+                archive: archive.clone(),
+            },
+        );
+
+        let (status, outcome) = post_refresh(&app, id, Some(ADMIN_KEY)).await;
+        assert_eq!(status, StatusCode::OK);
+        let price_change = outcome["changes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["field"] == "price")
+            .unwrap();
+        assert_eq!(price_change["before"], 700000);
+        assert_eq!(price_change["after"], 750000);
+        assert_eq!(outcome["after"]["address"], "10 Smith Street");
+
+        let audited = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM property_audit_log WHERE property_id = $1 AND action = $2",
+        )
+        .bind(id)
+        .bind(refresh::REFRESH_ACTION)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(audited, 1);
+
+        // Once the archive is gone the refresh can't be done
+        std::fs::remove_file(archive.resolve(&key).unwrap()).unwrap();
+        let (status, body) = post_refresh(&app, id, Some(ADMIN_KEY)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(body["message"]
+            .as_str()
+            .unwrap()
+            .contains("no longer available"));
+
+        sqlx::query("DELETE FROM properties WHERE id = $1")
+            .bind(id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_staging_review_endpoints() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let suburb = "Staging Admin Testville";
+        delete_suburb(&db, suburb).await.unwrap();
+
+        let record = PropertyFixture::new()
+            .suburb(suburb)
+            .price(640_000)
+            .data_source("staging_admin_test")
+            .record()
+            .clone();
+        let approved = staging::stage_records(&db, "staging_admin_test", vec![record.clone()])
+            .await
+            .unwrap();
+        let rejected = staging::stage_records(&db, "staging_admin_test", vec![record])
+            .await
+            .unwrap();
+
+        let app = app(
+            db.clone(),
+            AdminConfig {
+                api_key: Some(ADMIN_KEY.to_string()),
+                ..AdminConfig::default()
+            },
+        );
+        let send = |request: axum::http::request::Builder| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        request
+                            .header(API_KEY_HEADER, ADMIN_KEY)
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+                )
+            }
+        };
+
+        let (status, body) = send(Request::get("/api/admin/staging")).await;
+        assert_eq!(status, StatusCode::OK);
+        let listed = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["id"] == approved)
+            .unwrap();
+        assert_eq!(listed["quality"]["records"], 1);
+        assert_eq!(listed["samples"][0]["sale_price"], 640000);
+
+        let approve = format!("/api/admin/staging/{}/approve", approved);
+        let (status, body) = send(Request::post(&approve)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["merged"]["inserted"], 1);
+        assert_eq!(body["reviewed_by"], "admin");
+        let (status, body) = send(Request::post(&approve)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "already_reviewed");
+
+        let reject = format!("/api/admin/staging/{}/reject", rejected);
+        let (status, body) = send(Request::post(&reject)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["discarded"], 1);
+
+        let (status, _) = send(Request::post("/api/admin/staging/2147483647/reject")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        delete_suburb(&db, suburb).await.unwrap();
+        sqlx::query("DELETE FROM staging_batches WHERE source_id = 'staging_admin_test'")
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,428 @@
+//! Admin UI - server-rendered pages for ingestion runs and data quality
+//!
+//! Compiled in with the `admin-ui` feature. Pages sit behind the same admin
+//! API key as /api/admin and read through the same queries as its JSON
+//! endpoints; there is no JavaScript and the stylesheet is embedded.
+
+use crate::api::admin::{AdminCaller, RunsQuery};
+use crate::api::params::ValidatedListParams;
+use crate::api::AppState;
+use crate::format::format_money;
+use crate::ingestion::anomaly::{Anomaly, RunMetrics};
+use crate::ingestion::quality::{self, QualityReport};
+use crate::ingestion::runs::{self, RunProgress};
+use crate::ingestion::IngestionRun;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::Router;
+use chrono::NaiveDateTime;
+use maud::{html, Markup, PreEscaped, DOCTYPE};
+use tracing::error;
+
+const STYLE: &str = include_str!("admin.css");
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin", get(runs_list))
+        .route("/admin/runs/:id", get(run_detail))
+        .route("/admin/quality", get(quality_report))
+}
+
+fn db_error(e: sqlx::Error) -> StatusCode {
+    error!("Database error: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// GET /admin - recent ingestion runs
+async fn runs_list(
+    State(state): State<AppState>,
+    _caller: AdminCaller,
+    ValidatedListParams(params): ValidatedListParams<RunsQuery>,
+) -> Result<Markup, StatusCode> {
+    let runs = runs::list_runs(&state.read_db, params.source_id.as_deref(), params.limit())
+        .await
+        .map_err(db_error)?;
+    Ok(runs_page(&runs))
+}
+
+/// GET /admin/runs/:id - one run with its metrics and errors
+async fn run_detail(
+    State(state): State<AppState>,
+    _caller: AdminCaller,
+    Path(id): Path<i32>,
+) -> Result<Markup, StatusCode> {
+    match runs::fetch_run(&state.read_db, id)
+        .await
+        .map_err(db_error)?
+    {
+        Some(run) => Ok(run_page(&run)),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// GET /admin/quality - completeness per data source
+async fn quality_report(
+    State(state): State<AppState>,
+    _caller: AdminCaller,
+) -> Result<Markup, StatusCode> {
+    let report = quality::quality_report(&state.read_db)
+        .await
+        .map_err(db_error)?;
+    Ok(quality_page(&report))
+}
+
+fn layout(title: &str, content: Markup) -> Markup {
+    html! {
+        (DOCTYPE)
+        html {
+            head {
+                meta charset="utf-8";
+                title { (title) " - Admin" }
+                style { (PreEscaped(STYLE)) }
+            }
+            body {
+                nav {
+                    a href="/admin" { "Ingestion runs" }
+                    a href="/admin/quality" { "Data quality" }
+                }
+                h1 { (title) }
+                (content)
+            }
+        }
+    }
+}
+
+fn status_badge(status: &str) -> Markup {
+    html! {
+        span class={ "badge badge-" (status) } { (status.replace('_', " ")) }
+    }
+}
+
+fn timestamp(at: NaiveDateTime) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Elapsed time such as "1h 02m" or "3m 12s"; None while the run is going
+fn duration(run: &IngestionRun) -> Option<String> {
+    let seconds = (run.completed_at? - run.started_at).num_seconds().max(0);
+    Some(match seconds {
+        s if s >= 3600 => format!("{}h {:02}m", s / 3600, s % 3600 / 60),
+        s if s >= 60 => format!("{}m {:02}s", s / 60, s % 60),
+        s => format!("{}s", s),
+    })
+}
+
+pub fn runs_page(runs: &[IngestionRun]) -> Markup {
+    layout(
+        "Ingestion runs",
+        html! {
+            @if runs.is_empty() {
+                p class="empty" { "No ingestion runs recorded yet." }
+            } @else {
+                table {
+                    tr {
+                        th { "Run" }
+                        th { "Source" }
+                        th { "Status" }
+                        th { "Started" }
+                        th { "Duration" }
+                        th class="num" { "Fetched" }
+                        th class="num" { "Inserted" }
+                        th class="num" { "Updated" }
+                        th class="num" { "Skipped" }
+                    }
+                    @for run in runs {
+                        tr {
+                            td { a href={ "/admin/runs/" (run.id) } { "#" (run.id) } }
+                            td { (run.source_id) }
+                            td { (status_badge(&run.status)) }
+                            td { (timestamp(run.started_at)) }
+                            td { (duration(run).unwrap_or_else(|| "-".to_string())) }
+                            td class="num" { (run.records_fetched) }
+                            td class="num" { (run.records_inserted) }
+                            td class="num" { (run.records_updated) }
+                            td class="num" { (run.records_skipped) }
+                        }
+                    }
+                }
+            }
+        },
+    )
+}
+
+pub fn run_page(run: &IngestionRun) -> Markup {
+    let progress: Option<RunProgress> = run
+        .progress
+        .clone()
+        .and_then(|v| serde_json::from_value(v).ok());
+    let metrics: Option<RunMetrics> = run
+        .metrics
+        .clone()
+        .and_then(|v| serde_json::from_value(v).ok());
+    let anomalies: Vec<Anomaly> = run
+        .anomalies
+        .clone()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    layout(
+        &format!("Run #{} - {}", run.id, run.source_id),
+        html! {
+            dl {
+                dt { "Status" }
+                dd { (status_badge(&run.status)) }
+                dt { "Started" }
+                dd { (timestamp(run.started_at)) }
+                dt { "Completed" }
+                dd { (run.completed_at.map(timestamp).unwrap_or_else(|| "-".to_string())) }
+                @if let Some(elapsed) = duration(run) {
+                    dt { "Duration" }
+                    dd { (elapsed) }
+                }
+                @if let Some(progress) = &progress {
+                    dt { "Progress" }
+                    dd { (format!("{:.0}%", progress.percent)) " - " (progress.stage) }
+                }
+                dt { "Fetched" }
+                dd { (run.records_fetched) }
+                dt { "Inserted" }
+                dd { (run.records_inserted) }
+                dt { "Updated" }
+                dd { (run.records_updated) }
+                dt { "Skipped" }
+                dd { (run.records_skipped) }
+            }
+
+            @if let Some(metrics) = &metrics {
+                h2 { "Metrics" }
+                dl {
+                    dt { "Records parsed" }
+                    dd { (metrics.records_parsed) }
+                    dt { "Median price" }
+                    dd { (metrics.median_price.map(|p| format_money(p.round() as i64)).unwrap_or_else(|| "-".to_string())) }
+                    dt { "Rental match rate" }
+                    dd { (metrics.rental_match_rate.map(|r| format!("{:.1}%", r * 100.0)).unwrap_or_else(|| "-".to_string())) }
+                }
+            }
+
+            @if !anomalies.is_empty() {
+                h2 { "Anomalies" }
+                table {
+                    tr {
+                        th { "Metric" }
+                        th class="num" { "Value" }
+                        th class="num" { "Baseline" }
+                        th class="num" { "Change" }
+                    }
+                    @for anomaly in &anomalies {
+                        tr {
+                            td { (anomaly.metric) }
+                            td class="num" { (format!("{:.2}", anomaly.value)) }
+                            td class="num" { (format!("{:.2}", anomaly.baseline)) }
+                            td class="num" { (format!("{:+.1}%", anomaly.change * 100.0)) }
+                        }
+                    }
+                }
+            }
+
+            @if let Some(message) = &run.error_message {
+                h2 { "Error" }
+                pre { (message) }
+            }
+        },
+    )
+}
+
+pub fn quality_page(report: &QualityReport) -> Markup {
+    layout(
+        "Data quality",
+        html! {
+            p { (report.total_properties()) " properties across " (report.sources.len()) " sources." }
+            @if !report.sources.is_empty() {
+                table {
+                    tr {
+                        th { "Source" }
+                        th class="num" { "Properties" }
+                        th class="num" { "Missing price" }
+                        th class="num" { "Missing rent" }
+                        th class="num" { "Estimated rent" }
+                        th class="num" { "Missing coordinates" }
+                        th { "Last updated" }
+                    }
+                    @for source in &report.sources {
+                        tr {
+                            td { (source.data_source) }
+                            td class="num" { (source.properties) }
+                            @for count in [source.missing_price, source.missing_rent, source.estimated_rent, source.missing_coordinates] {
+                                td class="num" { (count) " (" (format!("{:.1}%", source.percent(count))) ")" }
+                            }
+                            td { (source.last_updated.map(timestamp).unwrap_or_else(|| "-".to_string())) }
+                        }
+                    }
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::admin::AdminConfig;
+    use crate::api::API_KEY_HEADER;
+    use crate::ingestion::quality::SourceQuality;
+    use crate::test_support::test_state;
+    use axum::body::Body;
+    use axum::http::Request;
+    use chrono::NaiveDate;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn at(hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, 1)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap()
+    }
+
+    fn run(id: i32, source_id: &str, status: &str) -> IngestionRun {
+        IngestionRun {
+            id,
+            source_id: source_id.to_string(),
+            status: status.to_string(),
+            started_at: at(2, 0, 0),
+            completed_at: Some(at(2, 3, 12)),
+            records_fetched: 10_412,
+            records_inserted: 1_240,
+            records_updated: 87,
+            records_skipped: 9_085,
+            error_message: None,
+            progress: None,
+            metrics: None,
+            anomalies: None,
+            raw_files: None,
+            parse_errors: None,
+        }
+    }
+
+    #[test]
+    fn test_runs_page_lists_runs_with_badges() {
+        let html = runs_page(&[
+            run(42, "nsw_sales", "completed_with_warnings"),
+            run(41, "nsw_rentals", "failed"),
+        ])
+        .into_string();
+
+        assert!(html.contains(r#"<a href="/admin/runs/42">#42</a>"#));
+        assert!(html.contains("nsw_rentals"));
+        assert!(html.contains(r#"class="badge badge-completed_with_warnings""#));
+        assert!(html.contains("completed with warnings"));
+        assert!(html.contains(r#"class="badge badge-failed""#));
+        assert!(html.contains("2025-06-01 02:00:00"));
+        assert!(html.contains("3m 12s"));
+        assert!(html.contains("1240"));
+        assert!(html.contains("<style>"));
+
+        let empty = runs_page(&[]).into_string();
+        assert!(empty.contains("No ingestion runs recorded yet."));
+    }
+
+    #[test]
+    fn test_run_page_shows_metrics_anomalies_and_error() {
+        let mut run = run(7, "nsw_sales", "completed_with_warnings");
+        run.metrics = Some(json!({
+            "records_parsed": 10_412,
+            "records_inserted": 1_240,
+            "median_price": 812_500.0,
+            "rental_match_rate": 0.784,
+        }));
+        run.anomalies = Some(json!([{
+            "metric": "records_inserted",
+            "value": 1_240.0,
+            "baseline": 12_400.0,
+            "change": -0.9,
+        }]));
+        run.error_message = Some("row 12: <unparseable> price".to_string());
+
+        let html = run_page(&run).into_string();
+        assert!(html.contains("Run #7 - nsw_sales"));
+        assert!(html.contains("$812,500"));
+        assert!(html.contains("78.4%"));
+        assert!(html.contains("<td>records_inserted</td>"));
+        assert!(html.contains("-90.0%"));
+        // Error text is escaped, not interpreted as markup
+        assert!(html.contains("row 12: &lt;unparseable&gt; price"));
+    }
+
+    #[test]
+    fn test_quality_page_shows_counts_and_percentages() {
+        let report = QualityReport {
+            sources: vec![
+                SourceQuality {
+                    data_source: "nsw_sales".to_string(),
+                    properties: 2_000,
+                    missing_price: 50,
+                    missing_rent: 400,
+                    estimated_rent: 1_200,
+                    missing_coordinates: 10,
+                    last_updated: Some(at(3, 0, 0)),
+                },
+                SourceQuality {
+                    data_source: "unknown".to_string(),
+                    properties: 3,
+                    missing_price: 3,
+                    missing_rent: 3,
+                    estimated_rent: 0,
+                    missing_coordinates: 3,
+                    last_updated: None,
+                },
+            ],
+        };
+
+        let html = quality_page(&report).into_string();
+        assert!(html.contains("2003 properties across 2 sources."));
+        assert!(html.contains("400 (20.0%)"));
+        assert!(html.contains("1200 (60.0%)"));
+        assert!(html.contains("3 (100.0%)"));
+        assert!(html.contains("2025-06-01 03:00:00"));
+    }
+
+    #[tokio::test]
+    async fn test_pages_require_admin_key() {
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = crate::api::router().with_state(AppState {
+            admin: Arc::new(AdminConfig {
+                api_key: Some("test-admin-key".to_string()),
+                ..AdminConfig::default()
+            }),
+            ..test_state(db)
+        });
+
+        for uri in ["/admin", "/admin/runs/1", "/admin/quality"] {
+            let response = app
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::get(uri)
+                        .header(API_KEY_HEADER, "wrong")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+        }
+    }
+}
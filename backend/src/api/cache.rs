@@ -0,0 +1,292 @@
+//! In-process response cache with stale-while-revalidate
+//!
+//! A fresh entry is served as-is. A stale entry is served immediately while one
+//! background task recomputes it. On a miss, concurrent callers share a single
+//! load. Loads run in spawned tasks, so they finish even if every caller
+//! disconnects.
+
+use crate::api::stats::{StatsResponse, TopYield};
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Default time before a cached response is considered stale
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 300;
+
+/// How the returned value was obtained, reported in the X-Cache header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Stale,
+    Miss,
+}
+
+impl CacheStatus {
+    pub fn as_header(&self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "hit",
+            CacheStatus::Stale => "stale",
+            CacheStatus::Miss => "miss",
+        }
+    }
+}
+
+type LoadResult<V> = Result<Arc<V>, Arc<anyhow::Error>>;
+type Load<V> = Shared<BoxFuture<'static, LoadResult<V>>>;
+
+struct Slot<V> {
+    value: Option<(Arc<V>, Instant)>,
+    /// Load in progress, tagged so a load started before `clear` can't write back
+    loading: Option<(u64, Load<V>)>,
+}
+
+impl<V> Default for Slot<V> {
+    fn default() -> Self {
+        Slot {
+            value: None,
+            loading: None,
+        }
+    }
+}
+
+/// Cache of values keyed by normalized request parameters
+pub struct SwrCache<V> {
+    ttl: Duration,
+    slots: Arc<Mutex<HashMap<String, Slot<V>>>>,
+    next_load: AtomicU64,
+}
+
+impl<V: Send + Sync + 'static> SwrCache<V> {
+    pub fn new(ttl: Duration) -> Self {
+        SwrCache {
+            ttl,
+            slots: Arc::new(Mutex::new(HashMap::new())),
+            next_load: AtomicU64::new(0),
+        }
+    }
+
+    /// Cached value for `key`, loading it with `load` if needed
+    /// `load` only runs when no other load for `key` is already in progress
+    pub async fn get<F, Fut>(
+        &self,
+        key: &str,
+        load: F,
+    ) -> Result<(Arc<V>, CacheStatus), Arc<anyhow::Error>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<V>> + Send + 'static,
+    {
+        let pending = {
+            let mut slots = self.slots.lock().unwrap();
+            let slot = slots.entry(key.to_string()).or_default();
+
+            if let Some((value, stored_at)) = &slot.value {
+                if stored_at.elapsed() < self.ttl {
+                    return Ok((value.clone(), CacheStatus::Hit));
+                }
+
+                let value = value.clone();
+                if slot.loading.is_none() {
+                    slot.loading = Some(self.spawn_load(key, load()));
+                }
+                return Ok((value, CacheStatus::Stale));
+            }
+
+            match &slot.loading {
+                Some((_, pending)) => pending.clone(),
+                None => {
+                    let (id, pending) = self.spawn_load(key, load());
+                    slot.loading = Some((id, pending.clone()));
+                    pending
+                }
+            }
+        };
+
+        pending.await.map(|value| (value, CacheStatus::Miss))
+    }
+
+    /// Drop every entry; loads already running won't repopulate the cache
+    pub fn clear(&self) {
+        self.slots.lock().unwrap().clear();
+    }
+
+    fn spawn_load<Fut>(&self, key: &str, load: Fut) -> (u64, Load<V>)
+    where
+        Fut: Future<Output = anyhow::Result<V>> + Send + 'static,
+    {
+        let id = self.next_load.fetch_add(1, Ordering::Relaxed);
+        let slots = self.slots.clone();
+        let key = key.to_string();
+
+        let task = tokio::spawn(async move {
+            let result = load.await.map(Arc::new).map_err(Arc::new);
+
+            let mut slots = slots.lock().unwrap();
+            if let Some(slot) = slots.get_mut(&key) {
+                if matches!(slot.loading, Some((current, _)) if current == id) {
+                    slot.loading = None;
+                    match &result {
+                        Ok(value) => slot.value = Some((value.clone(), Instant::now())),
+                        Err(e) => warn!("Cache refresh for {} failed: {}", key, e),
+                    }
+                }
+            }
+
+            result
+        });
+
+        let pending = async move {
+            task.await
+                .unwrap_or_else(|e| Err(Arc::new(anyhow::anyhow!("cache load panicked: {}", e))))
+        }
+        .boxed()
+        .shared();
+
+        (id, pending)
+    }
+}
+
+/// Caches for the landing-page aggregate endpoints
+pub struct ResponseCaches {
+    pub top_yields: SwrCache<Vec<TopYield>>,
+    pub stats: SwrCache<StatsResponse>,
+}
+
+impl ResponseCaches {
+    pub fn new(ttl: Duration) -> Self {
+        ResponseCaches {
+            top_yields: SwrCache::new(ttl),
+            stats: SwrCache::new(ttl),
+        }
+    }
+
+    /// TTL from STATS_CACHE_TTL_SECS
+    pub fn from_env() -> Self {
+        let ttl = env::var("STATS_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_TTL_SECS);
+
+        Self::new(Duration::from_secs(ttl))
+    }
+
+    /// Called once new data has been ingested
+    pub fn clear(&self) {
+        self.top_yields.clear();
+        self.stats.clear();
+    }
+}
+
+impl Default for ResponseCaches {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_CACHE_TTL_SECS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Loader that counts calls and takes a while, like an aggregate query
+    fn counting_load(
+        calls: &Arc<AtomicUsize>,
+        value: u32,
+    ) -> impl Future<Output = anyhow::Result<u32>> {
+        let calls = calls.clone();
+        async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(value)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses_share_one_load() {
+        let cache = Arc::new(SwrCache::new(Duration::from_secs(60)));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let requests = (0..20).map(|_| {
+            let cache = cache.clone();
+            let calls = calls.clone();
+            tokio::spawn(async move { cache.get("k", || counting_load(&calls, 1)).await })
+        });
+        let results = futures_util::future::join_all(requests).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        for result in results {
+            let (value, status) = result.unwrap().unwrap();
+            assert_eq!(*value, 1);
+            assert_eq!(status, CacheStatus::Miss);
+        }
+
+        let (_, status) = cache.get("k", || counting_load(&calls, 1)).await.unwrap();
+        assert_eq!(status, CacheStatus::Hit);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_during_expiry_refresh_once() {
+        let cache = Arc::new(SwrCache::new(Duration::from_millis(200)));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        cache.get("k", || counting_load(&calls, 1)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+
+        // Every caller gets the stale value straight away
+        let requests = (0..20).map(|_| {
+            let cache = cache.clone();
+            let calls = calls.clone();
+            tokio::spawn(async move { cache.get("k", || counting_load(&calls, 2)).await })
+        });
+        for result in futures_util::future::join_all(requests).await {
+            let (value, status) = result.unwrap().unwrap();
+            assert_eq!(*value, 1);
+            assert_eq!(status, CacheStatus::Stale);
+        }
+
+        // One refresh ran in the background and replaced the value
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let (value, status) = cache.get("k", || counting_load(&calls, 3)).await.unwrap();
+        assert_eq!((*value, status), (2, CacheStatus::Hit));
+    }
+
+    #[tokio::test]
+    async fn test_clear_discards_in_flight_load() {
+        let cache = Arc::new(SwrCache::new(Duration::from_secs(60)));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let early = {
+            let cache = cache.clone();
+            let calls = calls.clone();
+            tokio::spawn(async move { cache.get("k", || counting_load(&calls, 1)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        cache.clear();
+
+        // The caller still gets its answer, but it isn't cached
+        assert_eq!(*early.await.unwrap().unwrap().0, 1);
+        let (value, status) = cache.get("k", || counting_load(&calls, 2)).await.unwrap();
+        assert_eq!((*value, status), (2, CacheStatus::Miss));
+    }
+
+    #[tokio::test]
+    async fn test_failed_load_is_not_cached() {
+        let cache: SwrCache<u32> = SwrCache::new(Duration::from_secs(60));
+
+        let err = cache
+            .get("k", || async { Err(anyhow::anyhow!("db down")) })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("db down"));
+
+        let (value, status) = cache.get("k", || async { Ok(7) }).await.unwrap();
+        assert_eq!((*value, status), (7, CacheStatus::Miss));
+    }
+}
@@ -0,0 +1,359 @@
+//! Calculator endpoints - what it takes for a price or rent to reach a target
+//! yield, for hand-entered figures or a property's own
+
+use crate::api::params::{check_range, ParamError, ValidateParams, ValidatedListParams};
+use crate::api::tier::{Access, Redact, Redactor};
+use crate::api::AppState;
+use crate::{max_price_for_yield, required_weekly_rent, CalculationError};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::error;
+
+/// Highest target yield accepted, percent
+pub const MAX_TARGET_YIELD: i64 = 50;
+
+/// Highest weekly rent accepted, AUD
+const MAX_WEEKLY_RENT: i32 = 100_000;
+
+/// Query parameters for GET /api/calculators/break-even
+#[derive(Debug, Deserialize)]
+pub struct BreakEvenQuery {
+    /// Percent, above 0 and at most MAX_TARGET_YIELD
+    pub target_yield: Decimal,
+    pub price: Option<i32>,
+    pub weekly_rent: Option<i32>,
+    /// Use this property's price and rent instead of `price` and `weekly_rent`
+    pub property_id: Option<i32>,
+}
+
+impl ValidateParams for BreakEvenQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        if self.target_yield <= Decimal::ZERO || self.target_yield > Decimal::from(MAX_TARGET_YIELD)
+        {
+            return Err(ParamError::new(
+                "target_yield",
+                format!("must be above 0 and at most {}", MAX_TARGET_YIELD),
+            ));
+        }
+        check_range("price", self.price, 1..=i32::MAX)?;
+        check_range("weekly_rent", self.weekly_rent, 1..=MAX_WEEKLY_RENT)?;
+
+        match self.property_id {
+            Some(_) if self.price.is_some() || self.weekly_rent.is_some() => Err(ParamError::new(
+                "property_id",
+                "can't be combined with price or weekly_rent",
+            )),
+            None if self.price.is_none() && self.weekly_rent.is_none() => Err(ParamError::new(
+                "price",
+                "one of price, weekly_rent or property_id is required",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Response for GET /api/calculators/break-even
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BreakEven {
+    pub target_yield: Decimal,
+    pub property_id: Option<i32>,
+    pub price: Option<i64>,
+    pub weekly_rent: Option<i32>,
+    /// Lowest weekly rent that reaches the target at `price`
+    pub required_weekly_rent: Option<i32>,
+    /// Highest price at which `weekly_rent` reaches the target
+    pub max_price: Option<i64>,
+    /// weekly_rent - required_weekly_rent; negative when the rent falls short
+    pub rent_gap: Option<i32>,
+}
+
+impl BreakEven {
+    pub fn calculate(
+        target_yield: Decimal,
+        price: Option<i64>,
+        weekly_rent: Option<i32>,
+    ) -> Result<BreakEven, CalculationError> {
+        let required = price
+            .map(|price| required_weekly_rent(price, target_yield))
+            .transpose()?;
+        let max_price = weekly_rent
+            .map(|rent| max_price_for_yield(rent, target_yield))
+            .transpose()?;
+
+        Ok(BreakEven {
+            target_yield,
+            property_id: None,
+            price,
+            weekly_rent,
+            required_weekly_rent: required,
+            max_price,
+            rent_gap: weekly_rent
+                .zip(required)
+                .map(|(rent, required)| rent - required),
+        })
+    }
+}
+
+/// A 400 naming the entered figure a calculation rejected
+fn input_error(e: CalculationError) -> ParamError {
+    let field = match e {
+        CalculationError::NonPositivePrice => "price",
+        CalculationError::NegativeRent => "weekly_rent",
+        // Only a very low target can push the supported price past a Decimal
+        CalculationError::NonPositiveTarget | CalculationError::Overflow => "target_yield",
+    };
+    ParamError::new(field, e.to_string())
+}
+
+impl Redact for BreakEven {
+    fn redact(&mut self, redactor: &Redactor) {
+        // Only a property's own price is private; entered figures are echoed
+        if self.property_id.is_some() {
+            redactor.optional_price(&mut self.price);
+            // Worked from the band too, or they'd give the exact price back
+            self.required_weekly_rent = self
+                .price
+                .and_then(|price| required_weekly_rent(price, self.target_yield).ok());
+            self.rent_gap = self
+                .weekly_rent
+                .zip(self.required_weekly_rent)
+                .map(|(rent, required)| rent - required);
+        }
+    }
+}
+
+/// GET /api/calculators/break-even - the weekly rent a price needs, and the
+/// price a rent supports, to reach the target yield; 404 for unknown
+/// properties
+pub async fn get_break_even(
+    State(state): State<AppState>,
+    access: Access,
+    ValidatedListParams(params): ValidatedListParams<BreakEvenQuery>,
+) -> Result<Json<BreakEven>, Response> {
+    let Some(id) = params.property_id else {
+        let price = params.price.map(i64::from);
+        let result = BreakEven::calculate(params.target_yield, price, params.weekly_rent)
+            .map_err(|e| input_error(e).into_response())?;
+        return Ok(Json(result));
+    };
+
+    let property = sqlx::query_as::<_, (Option<i64>, Option<i32>)>(
+        "SELECT price, weekly_rent FROM properties WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&state.read_db)
+    .await
+    .map_err(|e| {
+        error!("Database error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let Some((price, weekly_rent)) = property else {
+        let body = json!({
+            "error": "not_found",
+            "message": format!("no property with id {}", id),
+        });
+        return Err((StatusCode::NOT_FOUND, Json(body)).into_response());
+    };
+
+    // The stored figures aren't the caller's to fix, so the property is blamed
+    let calculated =
+        BreakEven::calculate(params.target_yield, price, weekly_rent).map_err(|e| {
+            let message = format!("property {} can't be used: {}", id, e);
+            ParamError::new("property_id", message).into_response()
+        })?;
+    let result = BreakEven {
+        property_id: Some(id),
+        ..calculated
+    };
+    Ok(Json(access.apply(result)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use sqlx::PgPool;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn get(db: &PgPool, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn lazy_db() -> PgPool {
+        PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_invalid_params_rejected() {
+        let db = lazy_db();
+
+        let base = "/api/calculators/break-even";
+        for (query, field) in [
+            ("target_yield=0&price=500000", "target_yield"),
+            ("target_yield=50.01&price=500000", "target_yield"),
+            ("target_yield=-2&price=500000", "target_yield"),
+            ("target_yield=high&price=500000", "target_yield"),
+            ("price=500000", "target_yield"),
+            ("target_yield=5", "price"),
+            ("target_yield=5&price=0", "price"),
+            ("target_yield=5&weekly_rent=100001", "weekly_rent"),
+            ("target_yield=5&property_id=1&price=500000", "property_id"),
+            // A supported price past what a Decimal can hold
+            (
+                "target_yield=0.0000000000000000000000000001&weekly_rent=1",
+                "target_yield",
+            ),
+        ] {
+            let uri = format!("{}?{}", base, query);
+            let (status, body) = get(&db, &uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["field"], field, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_break_even_from_figures() {
+        let db = lazy_db();
+
+        let (status, body) = get(
+            &db,
+            "/api/calculators/break-even?target_yield=5&price=650000&weekly_rent=600",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({
+                "target_yield": "5",
+                "property_id": null,
+                "price": 650000,
+                "weekly_rent": 600,
+                "required_weekly_rent": 625,
+                "max_price": 624000,
+                "rent_gap": -25,
+            })
+        );
+
+        let (_, body) = get(
+            &db,
+            "/api/calculators/break-even?target_yield=4.5&price=700000",
+        )
+        .await;
+        assert_eq!(body["required_weekly_rent"], 606);
+        assert_eq!(body["max_price"], Value::Null);
+        assert_eq!(body["rent_gap"], Value::Null);
+
+        // Supported prices run past an i32, as stored prices do
+        let (status, body) = get(
+            &db,
+            "/api/calculators/break-even?target_yield=0.01&weekly_rent=100000",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["max_price"], 52_000_000_000i64);
+
+        let (status, body) = get(
+            &db,
+            "/api/calculators/break-even?target_yield=0.0000000000000000000000000001&weekly_rent=1",
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "the result is too large");
+
+        // The largest price still fits
+        let (status, body) = get(
+            &db,
+            "/api/calculators/break-even?target_yield=50&price=2147483647",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["required_weekly_rent"], 20_648_882);
+    }
+
+    const SUBURB: &str = "Break Even Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_break_even_for_property() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+
+        let id = PropertyFixture::new()
+            .address("5 Break Even St")
+            .suburb(SUBURB)
+            .price(812_345)
+            .weekly_rent(900)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let uri = format!(
+            "/api/calculators/break-even?target_yield=5&property_id={}",
+            id
+        );
+        let (status, body) = get(&db, &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["property_id"], id);
+        // The public tier sees the property's price banded, and the rent it
+        // needs worked from the band: 800,000 × 5% / 52 = 769.23, so 770 a
+        // week, which the rent clears by 130
+        assert_eq!(body["price"], 800_000);
+        assert_eq!(body["required_weekly_rent"], 770);
+        assert_eq!(body["rent_gap"], 130);
+        assert_eq!(body["max_price"], 936_000);
+
+        let (status, body) = get(
+            &db,
+            "/api/calculators/break-even?target_yield=5&property_id=-1",
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "not_found");
+
+        // A stored price of zero can't reach any yield
+        let free = PropertyFixture::new()
+            .address("7 Break Even St")
+            .suburb(SUBURB)
+            .price(0)
+            .weekly_rent(500)
+            .insert(&db)
+            .await
+            .unwrap();
+        let uri = format!(
+            "/api/calculators/break-even?target_yield=5&property_id={}",
+            free
+        );
+        let (status, body) = get(&db, &uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "property_id");
+        assert_eq!(
+            body["message"],
+            format!("property {} can't be used: price must be above zero", free)
+        );
+
+        delete_suburb(&db, SUBURB).await.unwrap();
+    }
+}
@@ -0,0 +1,413 @@
+//! Change feed endpoint - ordered property change events for downstream mirrors
+//!
+//! Every insert, content change and removal of a property is logged to
+//! change_log by a trigger, under a gap-free sequence that follows commit
+//! order. A syncer pages through the log from a cursor, fetching changed
+//! properties as it goes; once it has applied every event up to a cursor, its
+//! mirror matches properties as of that cursor. Pages are immutable once
+//! full, so they carry an ETag, and `wait` holds an empty page open until
+//! something changes.
+
+use crate::api::params::{check_range, ParamError, ValidateParams, ValidatedListParams};
+use crate::api::AppState;
+use axum::extract::State;
+use axum::http::header::{ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::error;
+
+/// Default page size
+const DEFAULT_LIMIT: i64 = 500;
+
+/// Largest page a client can request
+const MAX_LIMIT: i64 = 1000;
+
+/// Longest a request can be held open waiting for changes
+pub const MAX_WAIT_SECS: u64 = 60;
+
+/// How often a held request checks for new changes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Query parameters for GET /api/changes
+#[derive(Debug, Default, Deserialize)]
+pub struct ChangesQuery {
+    /// Opaque cursor from a previous page's `next_cursor`; from the start when absent
+    pub since: Option<String>,
+    pub limit: Option<i64>,
+    /// Seconds to hold the request when there's nothing new, up to MAX_WAIT_SECS
+    pub wait: Option<u64>,
+}
+
+impl ValidateParams for ChangesQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_range("limit", self.limit, 1..=MAX_LIMIT)?;
+        check_range("wait", self.wait, 0..=MAX_WAIT_SECS)?;
+        if self
+            .since
+            .as_deref()
+            .is_some_and(|c| ChangeCursor::decode(c).is_none())
+        {
+            return Err(ParamError::new("since", "not a valid cursor"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Position in the change feed - the seq of the last event consumed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct ChangeCursor(pub i64);
+
+impl ChangeCursor {
+    /// Encode as the decimal seq
+    pub fn encode(&self) -> String {
+        self.0.to_string()
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        cursor
+            .parse()
+            .ok()
+            .filter(|seq| *seq >= 0)
+            .map(ChangeCursor)
+    }
+}
+
+/// One change to one property
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ChangeEvent {
+    #[serde(skip)]
+    pub seq: i64,
+    pub property_id: i32,
+    /// insert, update or archive; an archived property is no longer served
+    pub change_type: String,
+    pub changed_at: NaiveDateTime,
+    /// Hash of the property's content after the change; equal hashes mean
+    /// equal content. Absent for archive.
+    pub content_hash: Option<String>,
+}
+
+/// Response for GET /api/changes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangesPage {
+    /// Oldest first
+    pub changes: Vec<ChangeEvent>,
+    /// Pass back as `since` for the following events; the request's own
+    /// cursor when there were none
+    pub next_cursor: String,
+    /// More events are already waiting after this page
+    pub has_more: bool,
+}
+
+/// GET /api/changes - a page of property change events after `since`
+/// Responds 304 when the page would match the request's If-None-Match
+pub async fn get_changes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedListParams(params): ValidatedListParams<ChangesQuery>,
+) -> Result<Response, StatusCode> {
+    // Checked by ChangesQuery::validate
+    let since = params
+        .since
+        .as_deref()
+        .and_then(ChangeCursor::decode)
+        .unwrap_or_default();
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    let deadline = Instant::now() + Duration::from_secs(params.wait.unwrap_or(0));
+    let if_none_match = headers.get(IF_NONE_MATCH).cloned();
+
+    loop {
+        let (changes, has_more) =
+            changes_after(&state.read_db, since, limit)
+                .await
+                .map_err(|e| {
+                    error!("Database error: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+        let next = changes.last().map_or(since, |c| ChangeCursor(c.seq));
+        let etag = page_etag(since, next, has_more);
+        let unchanged = if_none_match.as_ref() == Some(&etag);
+
+        if (changes.is_empty() || unchanged) && Instant::now() + POLL_INTERVAL <= deadline {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        if unchanged {
+            return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
+        }
+        let page = ChangesPage {
+            changes,
+            next_cursor: next.encode(),
+            has_more,
+        };
+        return Ok(([(ETAG, etag)], Json(page)).into_response());
+    }
+}
+
+/// Identifies a page by its bounds; events never change once logged
+fn page_etag(since: ChangeCursor, next: ChangeCursor, has_more: bool) -> HeaderValue {
+    let etag = format!(
+        "\"{}-{}{}\"",
+        since.0,
+        next.0,
+        if has_more { "+" } else { "" }
+    );
+    HeaderValue::from_str(&etag).expect("digits and punctuation are a valid header")
+}
+
+/// Up to `limit` events after `since`, and whether there are more
+pub async fn changes_after(
+    db: &PgPool,
+    since: ChangeCursor,
+    limit: i64,
+) -> Result<(Vec<ChangeEvent>, bool), sqlx::Error> {
+    let mut changes = sqlx::query_as::<_, ChangeEvent>(
+        r#"
+        SELECT seq, property_id, change_type, changed_at, content_hash
+        FROM change_log
+        WHERE seq > $1
+        ORDER BY seq
+        LIMIT $2
+        "#,
+    )
+    .bind(since.0)
+    .bind(limit + 1)
+    .fetch_all(db)
+    .await?;
+
+    let has_more = changes.len() as i64 > limit;
+    changes.truncate(limit as usize);
+
+    Ok((changes, has_more))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::types::RawData;
+    use crate::ingestion::{parse, write};
+    use crate::test_support::{delete_suburb, test_state};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    fn app(db: PgPool) -> axum::Router {
+        crate::api::router().with_state(test_state(db))
+    }
+
+    async fn get(app: &axum::Router, uri: &str, etag: Option<&HeaderValue>) -> Response {
+        let mut request = Request::get(uri);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn body(response: Response) -> Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap_or(Value::Null)
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        assert_eq!(ChangeCursor::decode("0"), Some(ChangeCursor(0)));
+        assert_eq!(
+            ChangeCursor::decode(&ChangeCursor(42).encode()),
+            Some(ChangeCursor(42))
+        );
+        assert_eq!(ChangeCursor::decode("-1"), None);
+        assert_eq!(ChangeCursor::decode("abc"), None);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_params_rejected() {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = app(db);
+
+        for (uri, field) in [
+            ("/api/changes?since=abc", "since"),
+            ("/api/changes?limit=0", "limit"),
+            ("/api/changes?wait=61", "wait"),
+        ] {
+            let response = get(&app, uri, None).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body(response).await["field"], field, "{}", uri);
+        }
+    }
+
+    const SUBURB_PREFIX: &str = "Change Feed ";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_feed_rebuilds_a_mirror() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let cleanup = |db: PgPool| async move {
+            let suburbs = sqlx::query_scalar::<_, String>(
+                "SELECT DISTINCT suburb FROM properties WHERE suburb LIKE $1",
+            )
+            .bind(format!("{}%", SUBURB_PREFIX))
+            .fetch_all(&db)
+            .await
+            .unwrap();
+            for suburb in suburbs {
+                delete_suburb(&db, &suburb).await.unwrap();
+            }
+        };
+        cleanup(db.clone()).await;
+
+        // Ingest the sales fixture into suburbs of its own
+        let fixture = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/nsw_sales.csv");
+        let mut records = parse::parse_nsw_sales(RawData::File(fixture), "nsw_sales".to_string())
+            .await
+            .unwrap()
+            .records;
+        for record in &mut records {
+            record.suburb = format!("{}{}", SUBURB_PREFIX, record.suburb);
+        }
+        let stats = write::write_properties(&db, records.clone()).await.unwrap();
+        assert_eq!(stats.inserted, records.len());
+        let suburbs = format!("{}%", SUBURB_PREFIX);
+        let fixture_ids =
+            sqlx::query_scalar::<_, i32>("SELECT id FROM properties WHERE suburb LIKE $1")
+                .bind(&suburbs)
+                .fetch_all(&db)
+                .await
+                .unwrap();
+
+        // Touching last_updated is not a change; a new price is
+        sqlx::query("UPDATE properties SET last_updated = NOW() WHERE suburb LIKE $1")
+            .bind(&suburbs)
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query(
+            "UPDATE properties SET price = price + 10000 WHERE address = $1 AND suburb = $2",
+        )
+        .bind(&records[0].address)
+        .bind(&records[0].suburb)
+        .execute(&db)
+        .await
+        .unwrap();
+        // Removed, as a merge would remove a duplicate
+        sqlx::query("DELETE FROM properties WHERE address = $1 AND suburb = $2")
+            .bind(&records[1].address)
+            .bind(&records[1].suburb)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        // Consume the whole feed from the start in small pages
+        let app = app(db.clone());
+        let mut mirror: HashMap<i64, String> = HashMap::new();
+        let mut events: Vec<(i64, String)> = Vec::new();
+        let mut cursor = "0".to_string();
+        let mut last_seq = 0;
+        loop {
+            let response = get(
+                &app,
+                &format!("/api/changes?since={}&limit=3", cursor),
+                None,
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let page = body(response).await;
+            for event in page["changes"].as_array().unwrap() {
+                let id = event["property_id"].as_i64().unwrap();
+                events.push((id, event["change_type"].as_str().unwrap().to_string()));
+                match event["change_type"].as_str().unwrap() {
+                    "insert" | "update" => {
+                        mirror.insert(id, event["content_hash"].as_str().unwrap().to_string());
+                    }
+                    "archive" => {
+                        assert!(event["content_hash"].is_null());
+                        mirror.remove(&id);
+                    }
+                    other => panic!("unexpected change type {}", other),
+                }
+            }
+            let next = page["next_cursor"].as_str().unwrap().to_string();
+            let seq = ChangeCursor::decode(&next).unwrap().0;
+            // Strictly ordered and gap-free: each page starts right after the last
+            let count = page["changes"].as_array().unwrap().len() as i64;
+            assert_eq!(seq, last_seq + count);
+            last_seq = seq;
+            cursor = next;
+            if !page["has_more"].as_bool().unwrap() {
+                break;
+            }
+        }
+
+        // The mirror holds every fixture property at its current content
+        let expected: HashMap<i64, String> = sqlx::query_as::<_, (i32, String)>(
+            r#"
+            SELECT id, md5((to_jsonb(p) - 'last_updated')::TEXT)
+            FROM properties p
+            WHERE suburb LIKE $1
+            "#,
+        )
+        .bind(&suburbs)
+        .fetch_all(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(id, hash)| (id as i64, hash))
+        .collect();
+        assert_eq!(expected.len(), records.len() - 1);
+        let mirrored: HashMap<i64, String> = mirror
+            .into_iter()
+            .filter(|(id, _)| fixture_ids.contains(&(*id as i32)))
+            .collect();
+        assert_eq!(mirrored, expected);
+
+        // One insert per record, then the price change and the removal
+        let fixture_events = |change_type: &str| {
+            events
+                .iter()
+                .filter(|(id, t)| t == change_type && fixture_ids.contains(&(*id as i32)))
+                .count()
+        };
+        assert_eq!(fixture_events("insert"), records.len());
+        assert_eq!(fixture_events("update"), 1);
+        assert_eq!(fixture_events("archive"), 1);
+
+        // A full page never changes, so its ETag holds
+        let response = get(&app, "/api/changes?since=0&limit=1", None).await;
+        let etag = response.headers().get(ETAG).unwrap().clone();
+        let response = get(&app, "/api/changes?since=0&limit=1", Some(&etag)).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get(ETAG), Some(&etag));
+
+        // A held request returns as soon as a change lands
+        let waiting = tokio::spawn({
+            let app = app.clone();
+            let uri = format!("/api/changes?since={}&wait=30", cursor);
+            async move { body(get(&app, &uri, None).await).await }
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        cleanup(db.clone()).await;
+        let page = tokio::time::timeout(Duration::from_secs(10), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!page["changes"].as_array().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,644 @@
+//! Map clustering endpoint - properties bucketed into a lat/lng grid sized by zoom
+//!
+//! Properties in the box are read in one pass and dropped into grid cells by
+//! `grid_cell`, so the browser gets a few hundred markers instead of every
+//! property. Cells holding fewer than MIN_CLUSTER_SIZE properties are sent as
+//! the individual properties, and from INDIVIDUAL_ZOOM up every property is.
+
+use crate::analytics::quadrants::median;
+use crate::api::map::fetch_map_properties;
+use crate::api::params::{check_range, ParamError, ValidateParams, ValidatedListParams};
+use crate::api::tier::{Access, Redact, Redactor};
+use crate::api::AppState;
+use crate::format::round_yield_f64;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use tracing::error;
+
+/// Highest zoom level accepted (street level on web maps)
+pub const MAX_ZOOM: i32 = 20;
+
+/// Grid cells across one web map tile, so a cluster marker covers about 64px
+const CELLS_PER_TILE: f64 = 4.0;
+
+/// Most cells a response may cover; wider boxes get a coarser grid
+pub const MAX_CELLS: u64 = 400;
+
+/// Cells with fewer properties than this are sent as individual properties
+pub const MIN_CLUSTER_SIZE: i64 = 5;
+
+/// From this zoom up a viewport holds few enough properties to send them all,
+/// up to the map endpoint's cap, so the client needs no second request
+pub const INDIVIDUAL_ZOOM: i32 = 16;
+
+/// Query parameters for GET /api/properties/clusters
+#[derive(Debug, Deserialize)]
+pub struct ClusterQuery {
+    /// `west,south,east,north` in degrees
+    pub bbox: String,
+    pub zoom: i32,
+}
+
+impl ValidateParams for ClusterQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_range("zoom", Some(self.zoom), 0..=MAX_ZOOM)?;
+        BoundingBox::try_parse(&self.bbox)
+            .map(|_| ())
+            .map_err(|message| ParamError::new("bbox", message))
+    }
+}
+
+/// Area of the map being viewed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub west: f64,
+    pub south: f64,
+    pub east: f64,
+    pub north: f64,
+}
+
+impl BoundingBox {
+    /// None unless four finite in-range values with west < east and south < north
+    pub fn parse(bbox: &str) -> Option<Self> {
+        Self::try_parse(bbox).ok()
+    }
+
+    /// As `parse`, with a message saying what's wrong for the 400 response
+    pub fn try_parse(bbox: &str) -> Result<Self, String> {
+        let values: Vec<f64> = bbox
+            .split(',')
+            .map(|v| {
+                v.trim()
+                    .parse()
+                    .ok()
+                    .filter(|v: &f64| v.is_finite())
+                    .ok_or_else(|| format!("'{}' is not a number", v.trim()))
+            })
+            .collect::<Result<_, _>>()?;
+        let [west, south, east, north] = values[..] else {
+            return Err(format!(
+                "must be 4 comma-separated numbers west,south,east,north; got {}",
+                values.len()
+            ));
+        };
+
+        if ![west, east].iter().all(|v| (-180.0..=180.0).contains(v)) {
+            return Err("longitudes must be between -180 and 180".to_string());
+        }
+        if ![south, north].iter().all(|v| (-90.0..=90.0).contains(v)) {
+            return Err("latitudes must be between -90 and 90".to_string());
+        }
+        if west >= east {
+            return Err(format!("west ({}) must be less than east ({})", west, east));
+        }
+        if south >= north {
+            return Err(format!(
+                "south ({}) must be less than north ({})",
+                south, north
+            ));
+        }
+        Ok(BoundingBox {
+            west,
+            south,
+            east,
+            north,
+        })
+    }
+}
+
+/// Edge of a grid cell in degrees at `zoom`, halving with each zoom level
+pub fn cell_size(zoom: i32) -> f64 {
+    let tiles = 2f64.powi(zoom.clamp(0, MAX_ZOOM));
+    360.0 / (tiles * CELLS_PER_TILE)
+}
+
+/// Cells of `size` degrees that `bbox` touches
+pub fn cell_count(bbox: &BoundingBox, size: f64) -> u64 {
+    let span = |min: f64, max: f64| ((max / size).floor() - (min / size).floor()) as u64 + 1;
+    span(bbox.west, bbox.east) * span(bbox.south, bbox.north)
+}
+
+/// The (x, y) grid cell holding a point, for cells of `size` degrees
+pub fn grid_cell(latitude: f64, longitude: f64, size: f64) -> (i64, i64) {
+    (
+        (longitude / size).floor() as i64,
+        (latitude / size).floor() as i64,
+    )
+}
+
+/// Cell size for `zoom`, doubled until `bbox` fits in MAX_CELLS
+pub fn grid_cell_size(bbox: &BoundingBox, zoom: i32) -> f64 {
+    let mut size = cell_size(zoom);
+    while cell_count(bbox, size) > MAX_CELLS {
+        size *= 2.0;
+    }
+    size
+}
+
+/// JSON response for GET /api/properties/clusters
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusterResponse {
+    pub zoom: i32,
+    /// Cell edge in degrees; coarser than the zoom's own when the box is very wide
+    pub cell_size: f64,
+    /// Empty from INDIVIDUAL_ZOOM up
+    pub clusters: Vec<Cluster>,
+    /// Properties in cells too sparse to cluster, or every property in the box
+    /// from INDIVIDUAL_ZOOM up; ordered by id
+    pub properties: Vec<ClusterProperty>,
+    /// From INDIVIDUAL_ZOOM up, more properties are in the box than the map cap
+    pub truncated: bool,
+}
+
+impl Redact for ClusterResponse {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.properties.redact(redactor);
+    }
+}
+
+/// A grid cell with at least MIN_CLUSTER_SIZE properties
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cluster {
+    #[serde(skip)]
+    pub cell_x: i64,
+    #[serde(skip)]
+    pub cell_y: i64,
+    pub count: i64,
+    /// Centroid of the cell's properties
+    pub latitude: f64,
+    pub longitude: f64,
+    /// None when no property in the cell has a yield
+    pub median_yield: Option<f64>,
+}
+
+/// A property shown on its own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ClusterProperty {
+    pub id: i32,
+    pub address: String,
+    pub suburb: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub price: Option<i64>,
+    pub rental_yield: Option<f64>,
+}
+
+impl Redact for ClusterProperty {
+    fn redact(&mut self, redactor: &Redactor) {
+        redactor.address(&mut self.address);
+        redactor.coordinate(&mut self.latitude);
+        redactor.coordinate(&mut self.longitude);
+        redactor.optional_price(&mut self.price);
+        // No rent here to work it again from the band, and with the
+        // property's rent from elsewhere the exact yield gives the price away
+        self.rental_yield = None;
+    }
+}
+
+/// Properties gathered into grid cells one at a time. A cell keeps its
+/// properties only until it is big enough to be a cluster, so memory grows
+/// with the number of cells rather than properties.
+#[derive(Debug)]
+pub struct ClusterGrid {
+    size: f64,
+    /// Keyed (y, x) so cells come out row by row
+    cells: BTreeMap<(i64, i64), Cell>,
+}
+
+#[derive(Debug, Default)]
+struct Cell {
+    count: i64,
+    latitude_sum: f64,
+    longitude_sum: f64,
+    yields: Vec<f64>,
+    /// Emptied once the cell reaches MIN_CLUSTER_SIZE
+    properties: Vec<ClusterProperty>,
+}
+
+impl ClusterGrid {
+    /// An empty grid of `size` degree cells
+    pub fn new(size: f64) -> Self {
+        ClusterGrid {
+            size,
+            cells: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, property: ClusterProperty) {
+        let (x, y) = grid_cell(property.latitude, property.longitude, self.size);
+        let cell = self.cells.entry((y, x)).or_default();
+        cell.count += 1;
+        cell.latitude_sum += property.latitude;
+        cell.longitude_sum += property.longitude;
+        cell.yields.extend(property.rental_yield);
+        if cell.count < MIN_CLUSTER_SIZE {
+            cell.properties.push(property);
+        } else {
+            cell.properties = Vec::new();
+        }
+    }
+
+    /// The clusters row by row, and the properties of sparse cells by id
+    pub fn finish(self) -> (Vec<Cluster>, Vec<ClusterProperty>) {
+        let mut clusters = Vec::new();
+        let mut properties = Vec::new();
+        for ((cell_y, cell_x), cell) in self.cells {
+            if cell.count < MIN_CLUSTER_SIZE {
+                properties.extend(cell.properties);
+                continue;
+            }
+            clusters.push(Cluster {
+                cell_x,
+                cell_y,
+                count: cell.count,
+                latitude: cell.latitude_sum / cell.count as f64,
+                longitude: cell.longitude_sum / cell.count as f64,
+                median_yield: median(cell.yields),
+            });
+        }
+        properties.sort_by_key(|p| p.id);
+        (clusters, properties)
+    }
+}
+
+/// GET /api/properties/clusters - property counts per grid cell within a
+/// bounding box, or the properties themselves from INDIVIDUAL_ZOOM up
+pub async fn get_clusters(
+    State(state): State<AppState>,
+    access: Access,
+    ValidatedListParams(params): ValidatedListParams<ClusterQuery>,
+) -> Result<Json<ClusterResponse>, StatusCode> {
+    let bbox = BoundingBox::parse(&params.bbox).ok_or(StatusCode::BAD_REQUEST)?;
+    let size = grid_cell_size(&bbox, params.zoom);
+
+    let response = if params.zoom >= INDIVIDUAL_ZOOM && size == cell_size(params.zoom) {
+        fetch_map_properties(&state.read_db, &bbox, state.map.max_properties)
+            .await
+            .map(|map| ClusterResponse {
+                zoom: params.zoom,
+                cell_size: size,
+                clusters: Vec::new(),
+                properties: map.properties,
+                truncated: map.truncated,
+            })
+    } else {
+        fetch_clusters(&state.read_db, &bbox, params.zoom, size).await
+    };
+
+    response
+        .map(|response| Json(access.apply(response)))
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Every property inside `bbox` gridded into `size` degree cells
+pub async fn fetch_clusters(
+    db: &PgPool,
+    bbox: &BoundingBox,
+    zoom: i32,
+    size: f64,
+) -> Result<ClusterResponse, sqlx::Error> {
+    let mut grid = ClusterGrid::new(size);
+    let mut rows = sqlx::query_as::<_, ClusterProperty>(
+        r#"
+        SELECT id, address, suburb,
+               latitude::FLOAT8 AS latitude, longitude::FLOAT8 AS longitude,
+               price, rental_yield::FLOAT8 AS rental_yield
+        FROM properties
+        WHERE longitude BETWEEN $1::NUMERIC AND $3::NUMERIC
+          AND latitude BETWEEN $2::NUMERIC AND $4::NUMERIC
+        "#,
+    )
+    .bind(bbox.west)
+    .bind(bbox.south)
+    .bind(bbox.east)
+    .bind(bbox.north)
+    .fetch(db);
+    while let Some(property) = rows.try_next().await? {
+        grid.add(property);
+    }
+    let (clusters, properties) = grid.finish();
+
+    Ok(ClusterResponse {
+        zoom,
+        cell_size: size,
+        clusters: clusters
+            .into_iter()
+            .map(|c| Cluster {
+                median_yield: c.median_yield.map(round_yield_f64),
+                ..c
+            })
+            .collect(),
+        properties: properties
+            .into_iter()
+            .map(|p| ClusterProperty {
+                rental_yield: p.rental_yield.map(round_yield_f64),
+                ..p
+            })
+            .collect(),
+        truncated: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tier::{Tier, TierConfig};
+    use crate::api::API_KEY_HEADER;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use rust_decimal::prelude::FromPrimitive;
+    use rust_decimal::Decimal;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    /// Whole of Australia, the widest box the map asks for
+    const AUSTRALIA: BoundingBox = BoundingBox {
+        west: 112.0,
+        south: -44.0,
+        east: 154.0,
+        north: -10.0,
+    };
+
+    /// Full-tier request, so addresses and coordinates come back unredacted
+    async fn get(db: &PgPool, uri: &str) -> (StatusCode, Vec<u8>) {
+        let app = crate::api::router().with_state(AppState {
+            tiers: Arc::new(TierConfig::default().with_key("clusters-test", Tier::Full)),
+            ..test_state(db.clone())
+        });
+        let response = app
+            .oneshot(
+                Request::get(uri)
+                    .header(API_KEY_HEADER, "clusters-test")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, body.to_vec())
+    }
+
+    #[test]
+    fn test_cell_size_halves_per_zoom() {
+        assert_eq!(cell_size(0), 90.0);
+        assert_eq!(cell_size(1), 45.0);
+        assert_eq!(cell_size(12), 360.0 / 16384.0);
+        for zoom in 1..=MAX_ZOOM {
+            assert_eq!(cell_size(zoom) * 2.0, cell_size(zoom - 1));
+        }
+        // Out-of-range zooms clamp rather than producing zero or huge cells
+        assert_eq!(cell_size(-3), cell_size(0));
+        assert_eq!(cell_size(40), cell_size(MAX_ZOOM));
+    }
+
+    #[test]
+    fn test_cell_budget_holds_at_every_zoom() {
+        for zoom in 0..=MAX_ZOOM {
+            let size = grid_cell_size(&AUSTRALIA, zoom);
+            assert!(cell_count(&AUSTRALIA, size) <= MAX_CELLS, "zoom {}", zoom);
+            assert!(size >= cell_size(zoom));
+        }
+
+        // A city-sized box at street zoom keeps the zoom's own grid
+        let block = BoundingBox::parse("151.20,-33.87,151.21,-33.86").unwrap();
+        assert_eq!(grid_cell_size(&block, 16), cell_size(16));
+    }
+
+    fn property(
+        id: i32,
+        latitude: f64,
+        longitude: f64,
+        rental_yield: Option<f64>,
+    ) -> ClusterProperty {
+        ClusterProperty {
+            id,
+            address: format!("{} Grid St", id),
+            suburb: "Gridville".to_string(),
+            latitude,
+            longitude,
+            price: None,
+            rental_yield,
+        }
+    }
+
+    #[test]
+    fn test_grid_cell() {
+        assert_eq!(grid_cell(-33.87, 151.21, 1.0), (151, -34));
+        assert_eq!(grid_cell(-33.87, 151.21, 0.5), (302, -68));
+        // Cells are half-open, so a point on an edge belongs to the cell above it
+        assert_eq!(grid_cell(-34.0, 151.0, 1.0), (151, -34));
+        assert_eq!(grid_cell(0.0, 0.0, cell_size(20)), (0, 0));
+        assert_eq!(grid_cell(-0.0000001, -0.0000001, cell_size(20)), (-1, -1));
+    }
+
+    #[test]
+    fn test_grid_clusters_dense_cells_only() {
+        let mut grid = ClusterGrid::new(1.0);
+        // Six in one cell, two with yields; two in the cell to the east
+        for id in 1..=6 {
+            let rental_yield = [Some(4.0), Some(5.0)]
+                .get(id as usize - 1)
+                .copied()
+                .flatten();
+            grid.add(property(id, -33.5, 151.0 + id as f64 * 0.1, rental_yield));
+        }
+        grid.add(property(8, -33.2, 152.5, Some(6.0)));
+        grid.add(property(7, -33.1, 152.6, None));
+        // One in the row to the north, which comes after
+        grid.add(property(9, -32.5, 151.5, None));
+
+        let (clusters, properties) = grid.finish();
+        assert_eq!(
+            clusters,
+            vec![Cluster {
+                cell_x: 151,
+                cell_y: -34,
+                count: 6,
+                latitude: -33.5,
+                longitude: 151.35,
+                median_yield: Some(4.5),
+            }]
+        );
+        let ids: Vec<i32> = properties.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn test_bbox_parse() {
+        assert_eq!(
+            BoundingBox::parse("150.9, -33.9, 151.3, -33.7"),
+            Some(BoundingBox {
+                west: 150.9,
+                south: -33.9,
+                east: 151.3,
+                north: -33.7,
+            })
+        );
+        for invalid in [
+            "150.9,-33.9,151.3",
+            "150.9,-33.9,151.3,-33.7,1",
+            "151.3,-33.9,150.9,-33.7",
+            "150.9,-33.7,151.3,-33.9",
+            "150.9,-95,151.3,-33.7",
+            "NaN,-33.9,151.3,-33.7",
+            "a,b,c,d",
+        ] {
+            assert_eq!(BoundingBox::parse(invalid), None, "{}", invalid);
+        }
+
+        let message = |bbox: &str| BoundingBox::try_parse(bbox).unwrap_err();
+        assert_eq!(
+            message("150.9,-33.9,151.3"),
+            "must be 4 comma-separated numbers west,south,east,north; got 3"
+        );
+        assert_eq!(message("150.9,x,151.3,-33.7"), "'x' is not a number");
+        assert_eq!(
+            message("151.3,-33.9,150.9,-33.7"),
+            "west (151.3) must be less than east (150.9)"
+        );
+        assert_eq!(
+            message("150.9,-33.7,151.3,-33.9"),
+            "south (-33.7) must be less than north (-33.9)"
+        );
+        assert_eq!(
+            message("150.9,-95,151.3,-33.7"),
+            "latitudes must be between -90 and 90"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_params() {
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+
+        for (uri, field) in [
+            ("/api/properties/clusters?bbox=1,2,3&zoom=12", "bbox"),
+            (
+                "/api/properties/clusters?bbox=151,-34,150,-33&zoom=12",
+                "bbox",
+            ),
+            (
+                "/api/properties/clusters?bbox=150,-34,151,-33&zoom=21",
+                "zoom",
+            ),
+        ] {
+            let (status, body) = get(&db, uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            let body: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body["field"], field, "{}", uri);
+        }
+    }
+
+    const SUBURB: &str = "Cluster Testville";
+
+    fn dec(value: f64) -> Decimal {
+        Decimal::from_f64(value).unwrap().round_dp(6)
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_clusters_at_two_zooms() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+
+        // A dense block of 30 a few hundred metres across, and 3 scattered
+        // points several kilometres away, in an otherwise empty patch of sea
+        for i in 0..30 {
+            PropertyFixture::new()
+                .address(&format!("{} Dense St", i + 1))
+                .suburb(SUBURB)
+                .rental_yield(if i % 2 == 0 { "4.00" } else { "5.00" })
+                .coordinates(
+                    dec(-40.001 - i as f64 * 0.0001),
+                    dec(160.001 + i as f64 * 0.0001),
+                )
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+        for (i, (lat, lng)) in [(-40.05, 160.05), (-40.07, 160.02), (-40.02, 160.09)]
+            .into_iter()
+            .enumerate()
+        {
+            PropertyFixture::new()
+                .address(&format!("{} Sparse Road", i + 1))
+                .suburb(SUBURB)
+                .coordinates(dec(lat), dec(lng))
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+        let bbox = "159.99,-40.1,160.1,-39.99";
+
+        // Zoom 8: 0.35 degree cells, so everything falls in one cluster
+        let (status, body) = get(
+            &db,
+            &format!("/api/properties/clusters?bbox={}&zoom=8", bbox),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let coarse: ClusterResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(coarse.clusters.len(), 1);
+        assert_eq!(coarse.clusters[0].count, 33);
+        assert_eq!(coarse.clusters[0].median_yield, Some(4.5));
+        assert!(coarse.properties.is_empty());
+
+        // Zoom 12: 0.022 degree cells; the dense block stays clustered and the
+        // scattered points come back individually
+        let (status, body) = get(
+            &db,
+            &format!("/api/properties/clusters?bbox={}&zoom=12", bbox),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let fine: ClusterResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fine.cell_size, cell_size(12));
+        assert_eq!(fine.clusters.len(), 1);
+        assert_eq!(fine.clusters[0].count, 30);
+        let cluster = &fine.clusters[0];
+        assert!((-40.005..-40.0).contains(&cluster.latitude));
+        assert!((160.0..160.005).contains(&cluster.longitude));
+        let mut sparse: Vec<&str> = fine.properties.iter().map(|p| p.address.as_str()).collect();
+        sparse.sort();
+        assert_eq!(sparse, ["1 Sparse Road", "2 Sparse Road", "3 Sparse Road"]);
+
+        // Street zoom on the block: the properties themselves, no clusters
+        let (status, body) = get(
+            &db,
+            "/api/properties/clusters?bbox=160,-40.005,160.005,-40&zoom=16",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let street: ClusterResponse = serde_json::from_slice(&body).unwrap();
+        assert!(street.clusters.is_empty());
+        assert_eq!(street.properties.len(), 30);
+        assert!(street.properties.windows(2).all(|w| w[0].id < w[1].id));
+        assert!(!street.truncated);
+
+        // Whole of Australia at street zoom still fits the cell budget
+        let (status, body) =
+            get(&db, "/api/properties/clusters?bbox=112,-44,154,-10&zoom=18").await;
+        assert_eq!(status, StatusCode::OK);
+        let wide: ClusterResponse = serde_json::from_slice(&body).unwrap();
+        assert!(wide.clusters.len() as u64 <= MAX_CELLS);
+        assert!(wide.properties.len() as u64 <= MAX_CELLS * (MIN_CLUSTER_SIZE as u64 - 1));
+
+        delete_suburb(&db, SUBURB).await.unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+//! Response compression - gzip or brotli, whichever the client accepts, for
+//! responses large enough to benefit
+
+use axum::Router;
+use std::env;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+
+/// Responses smaller than this are sent as they are
+pub const DEFAULT_MIN_COMPRESS_BYTES: u16 = 1024;
+
+#[derive(Debug, Clone)]
+pub struct CompressionConfig {
+    /// Off to see raw responses while debugging
+    pub enabled: bool,
+    pub min_bytes: u16,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            enabled: true,
+            min_bytes: DEFAULT_MIN_COMPRESS_BYTES,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// RESPONSE_COMPRESSION ("false" or "0" turns it off) and
+    /// COMPRESSION_MIN_BYTES
+    pub fn from_env() -> Self {
+        let defaults = CompressionConfig::default();
+        CompressionConfig {
+            enabled: env::var("RESPONSE_COMPRESSION")
+                .map(|s| !(s == "false" || s == "0"))
+                .unwrap_or(defaults.enabled),
+            min_bytes: env::var("COMPRESSION_MIN_BYTES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.min_bytes),
+        }
+    }
+
+    /// `router` with compression applied, when enabled. Streamed bodies (CSV
+    /// exports) have no known size and are compressed as they stream; event
+    /// streams and images never are.
+    pub fn apply<S>(&self, router: Router<S>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        if !self.enabled {
+            return router;
+        }
+        let predicate = SizeAbove::new(self.min_bytes)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::SSE);
+        router.layer(
+            CompressionLayer::new()
+                .gzip(true)
+                .br(true)
+                .compress_when(predicate),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::export::csv_response;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body, Bytes};
+    use axum::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+    use axum::http::{Request, StatusCode};
+    use axum::response::Response;
+    use axum::routing::get;
+    use flate2::read::GzDecoder;
+    use sqlx::postgres::PgPoolOptions;
+    use sqlx::PgPool;
+    use std::io::Read;
+    use tower::ServiceExt;
+
+    fn app(db: PgPool, config: &CompressionConfig) -> Router {
+        let router = crate::api::router().route(
+            "/test/export",
+            get(|| async {
+                let rows = (0..2000).map(|n| Ok(Bytes::from(format!("{},row\n", n))));
+                csv_response("test.csv", None, futures_util::stream::iter(rows))
+            }),
+        );
+        config.apply(router).with_state(test_state(db))
+    }
+
+    async fn send(app: Router, uri: &str, encoding: Option<&str>) -> Response {
+        let mut request = Request::get(uri);
+        if let Some(encoding) = encoding {
+            request = request.header(ACCEPT_ENCODING, encoding);
+        }
+        app.oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn encoding(response: &Response) -> Option<&str> {
+        response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .map(|v| v.to_str().unwrap())
+    }
+
+    async fn body(response: Response) -> Vec<u8> {
+        to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
+    }
+
+    fn gunzip(compressed: &[u8]) -> Vec<u8> {
+        let mut plain = Vec::new();
+        GzDecoder::new(compressed).read_to_end(&mut plain).unwrap();
+        plain
+    }
+
+    fn unreachable_db() -> PgPool {
+        PgPoolOptions::new()
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_large_responses_compressed() {
+        let app = app(unreachable_db(), &CompressionConfig::default());
+        let uri = "/api/meta/openapi.json";
+
+        let plain = send(app.clone(), uri, None).await;
+        assert_eq!(encoding(&plain), None);
+        let plain = body(plain).await;
+        assert!(plain.len() > DEFAULT_MIN_COMPRESS_BYTES as usize);
+
+        let gzipped = send(app.clone(), uri, Some("gzip")).await;
+        assert_eq!(encoding(&gzipped), Some("gzip"));
+        let gzipped = body(gzipped).await;
+        assert!(gzipped.len() < plain.len());
+        assert_eq!(gunzip(&gzipped), plain);
+
+        let brotli = send(app, uri, Some("br")).await;
+        assert_eq!(encoding(&brotli), Some("br"));
+    }
+
+    #[tokio::test]
+    async fn test_small_responses_and_disabled_config_uncompressed() {
+        let app_on = app(unreachable_db(), &CompressionConfig::default());
+        let small = send(app_on, "/api/properties?state=XYZ", Some("gzip")).await;
+        assert_eq!(small.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(encoding(&small), None);
+
+        let off = CompressionConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let large = send(
+            app(unreachable_db(), &off),
+            "/api/meta/openapi.json",
+            Some("gzip"),
+        )
+        .await;
+        assert_eq!(encoding(&large), None);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_export_compressed() {
+        let app = app(unreachable_db(), &CompressionConfig::default());
+
+        let response = send(app, "/test/export", Some("gzip")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(encoding(&response), Some("gzip"));
+        let csv = String::from_utf8(gunzip(&body(response).await)).unwrap();
+        assert_eq!(csv.lines().count(), 2000);
+        assert_eq!(csv.lines().last(), Some("1999,row"));
+    }
+
+    const SUBURB: &str = "Compression Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_property_list_gzip_matches_plain() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+        for n in 0..20 {
+            PropertyFixture::new()
+                .address(&format!("{} Compression Road", n))
+                .suburb(SUBURB)
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+        let app = app(db.clone(), &CompressionConfig::default());
+        let uri = "/api/properties?suburb=Compression%20Testville&limit=100";
+
+        let plain = send(app.clone(), uri, None).await;
+        assert_eq!(plain.status(), StatusCode::OK);
+        let plain = body(plain).await;
+        assert!(plain.len() > DEFAULT_MIN_COMPRESS_BYTES as usize);
+
+        let gzipped = send(app, uri, Some("gzip")).await;
+        assert_eq!(gzipped.status(), StatusCode::OK);
+        assert_eq!(encoding(&gzipped), Some("gzip"));
+        assert_eq!(gunzip(&body(gzipped).await), plain);
+
+        delete_suburb(&db, SUBURB).await.unwrap();
+    }
+}
@@ -0,0 +1,467 @@
+//! CORS policy - which browser origins may script against the API
+//! Public routes answer allowed origins for GET only, without credentials;
+//! saved searches also take POST and DELETE with a client id header.
+//! Admin routes also refuse cross-origin requests outright unless the origin
+//! is on the admin allow-list.
+
+use crate::api::export::CONTINUE_HEADER;
+use crate::api::saved_searches::CLIENT_ID_HEADER;
+use crate::api::API_KEY_HEADER;
+use axum::extract::{Request, State};
+use axum::http::header::{CONTENT_TYPE, HOST, ORIGIN};
+use axum::http::{request, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::{debug, warn};
+
+/// How long browsers may cache a preflight response
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(600);
+
+/// One entry of an origin allow-list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OriginPattern {
+    /// `*` - any origin
+    Any,
+    /// `https://app.example.com` - that origin only
+    Exact(String),
+    /// `https://*.example.com` - any subdomain of example.com over https,
+    /// but not example.com itself
+    Subdomain { scheme: String, domain: String },
+}
+
+impl OriginPattern {
+    /// Parse one pattern; scheme and host are compared case-insensitively
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let pattern = pattern.trim().trim_end_matches('/').to_ascii_lowercase();
+        if pattern == "*" {
+            return Some(OriginPattern::Any);
+        }
+        let (scheme, host) = pattern.split_once("://")?;
+        if scheme.is_empty() || host.is_empty() {
+            return None;
+        }
+        match host.strip_prefix("*.") {
+            Some(domain) if !domain.is_empty() && !domain.contains('*') => {
+                Some(OriginPattern::Subdomain {
+                    scheme: scheme.to_string(),
+                    domain: domain.to_string(),
+                })
+            }
+            Some(_) => None,
+            None if host.contains('*') => None,
+            None => Some(OriginPattern::Exact(pattern)),
+        }
+    }
+
+    pub fn matches(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+        match self {
+            OriginPattern::Any => true,
+            OriginPattern::Exact(allowed) => origin == *allowed,
+            OriginPattern::Subdomain { scheme, domain } => origin
+                .strip_prefix(scheme.as_str())
+                .and_then(|rest| rest.strip_prefix("://"))
+                .and_then(|host| host.strip_suffix(domain.as_str()))
+                .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+        }
+    }
+}
+
+/// Comma-separated patterns; unparseable entries are skipped with a warning
+fn parse_origins(list: &str) -> Vec<OriginPattern> {
+    list.split(',')
+        .filter(|s| !s.trim().is_empty())
+        .filter_map(|s| {
+            let pattern = OriginPattern::parse(s);
+            if pattern.is_none() {
+                warn!("Ignoring invalid CORS origin pattern: {}", s.trim());
+            }
+            pattern
+        })
+        .collect()
+}
+
+/// Allowed origins, loaded from environment variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// Origins allowed on public routes
+    pub allowed_origins: Vec<OriginPattern>,
+    /// Cross-origin callers allowed on admin routes; same-origin is always allowed
+    pub admin_origins: Vec<OriginPattern>,
+    pub max_age: Duration,
+}
+
+impl Default for CorsConfig {
+    /// Any origin on public routes, for development; admin routes same-origin only
+    fn default() -> Self {
+        CorsConfig {
+            allowed_origins: vec![OriginPattern::Any],
+            admin_origins: Vec::new(),
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// CORS_ALLOWED_ORIGINS and CORS_ADMIN_ORIGINS (comma-separated patterns),
+    /// CORS_MAX_AGE_SECS
+    pub fn from_env() -> Self {
+        let defaults = CorsConfig::default();
+        CorsConfig {
+            allowed_origins: env::var("CORS_ALLOWED_ORIGINS")
+                .map(|list| parse_origins(&list))
+                .unwrap_or(defaults.allowed_origins),
+            admin_origins: env::var("CORS_ADMIN_ORIGINS")
+                .map(|list| parse_origins(&list))
+                .unwrap_or(defaults.admin_origins),
+            max_age: env::var("CORS_MAX_AGE_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.max_age),
+        }
+    }
+
+    /// CORS layer for public routes; scripts may read where a cut-short
+    /// export continues from
+    pub fn public_layer(&self) -> CorsLayer {
+        self.layer(self.allowed_origins.clone(), vec![Method::GET], &[])
+            .expose_headers([HeaderName::from_static(CONTINUE_HEADER)])
+    }
+
+    /// CORS layer for saved-search routes: the public origins, with the
+    /// methods and header a browser needs to manage its own searches
+    pub fn saved_search_layer(&self) -> CorsLayer {
+        self.layer(
+            self.allowed_origins.clone(),
+            vec![Method::GET, Method::POST, Method::DELETE],
+            &[HeaderName::from_static(CLIENT_ID_HEADER)],
+        )
+    }
+
+    /// CORS layer for admin routes
+    pub fn admin_layer(&self) -> CorsLayer {
+        self.layer(
+            self.admin_origins.clone(),
+            vec![Method::GET, Method::POST],
+            &[],
+        )
+    }
+
+    /// `extra_headers` are allowed alongside the API key and content type
+    fn layer(
+        &self,
+        origins: Vec<OriginPattern>,
+        methods: Vec<Method>,
+        extra_headers: &[HeaderName],
+    ) -> CorsLayer {
+        let allow = AllowOrigin::predicate(move |origin: &HeaderValue, parts: &request::Parts| {
+            let allowed = origin
+                .to_str()
+                .is_ok_and(|origin| origins.iter().any(|p| p.matches(origin)));
+            if !allowed {
+                debug!("Rejected CORS origin {:?} for {}", origin, parts.uri.path());
+            }
+            allowed
+        });
+
+        CorsLayer::new()
+            .allow_origin(allow)
+            .allow_methods(methods)
+            .allow_headers(
+                [HeaderName::from_static(API_KEY_HEADER), CONTENT_TYPE]
+                    .into_iter()
+                    .chain(extra_headers.iter().cloned())
+                    .collect::<Vec<_>>(),
+            )
+            .allow_credentials(false)
+            .max_age(self.max_age)
+    }
+
+    /// Whether an admin request's Origin may reach the handler: no Origin
+    /// (not a browser), the server's own origin, or an allow-listed one
+    fn admin_origin_allowed(&self, origin: Option<&str>, host: Option<&str>) -> bool {
+        let Some(origin) = origin else {
+            return true;
+        };
+        let same_origin = match (origin.split_once("://"), host) {
+            (Some((_, authority)), Some(host)) => authority.eq_ignore_ascii_case(host),
+            _ => false,
+        };
+        same_origin || self.admin_origins.iter().any(|p| p.matches(origin))
+    }
+}
+
+/// Middleware for admin routes: 403 for cross-origin browser requests from
+/// origins not on the admin allow-list
+/// CORS alone only hides responses, so this stops the request running at all
+pub async fn admin_origin_guard(
+    State(config): State<Arc<CorsConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let allowed = {
+        let headers = request.headers();
+        let origin = headers.get(ORIGIN).and_then(|v| v.to_str().ok());
+        let host = headers.get(HOST).and_then(|v| v.to_str().ok());
+        let allowed = config.admin_origin_allowed(origin, host);
+        if !allowed {
+            debug!(
+                "Rejected admin request to {} from origin {}",
+                request.uri().path(),
+                origin.unwrap_or_default()
+            );
+        }
+        allowed
+    };
+    if !allowed {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::admin::AdminConfig;
+    use crate::api::AppState;
+    use crate::test_support::test_state;
+    use axum::body::Body;
+    use axum::http::header::{
+        ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+        ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE,
+        ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD,
+    };
+    use axum::Router;
+    use sqlx::postgres::PgPoolOptions;
+    use tower::ServiceExt;
+
+    fn pattern(s: &str) -> OriginPattern {
+        OriginPattern::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_exact_origin_matching() {
+        let p = pattern("https://app.example.com/");
+        assert!(p.matches("https://app.example.com"));
+        assert!(p.matches("HTTPS://App.Example.com"));
+        assert!(!p.matches("http://app.example.com"));
+        assert!(!p.matches("https://app.example.com:8443"));
+        assert!(!p.matches("https://app.example.com.evil.io"));
+        assert!(!p.matches("https://other.example.com"));
+    }
+
+    #[test]
+    fn test_subdomain_wildcard_matching() {
+        let p = pattern("https://*.example.com");
+        assert!(p.matches("https://app.example.com"));
+        assert!(p.matches("https://a.b.example.com"));
+        assert!(!p.matches("https://example.com"));
+        assert!(!p.matches("https://.example.com"));
+        assert!(!p.matches("https://evilexample.com"));
+        assert!(!p.matches("https://example.com.evil.io"));
+        assert!(!p.matches("http://app.example.com"));
+        assert!(pattern("*").matches("https://anything.io"));
+    }
+
+    #[test]
+    fn test_invalid_patterns_rejected() {
+        for invalid in [
+            "example.com",
+            "https://",
+            "https://*.",
+            "https://a.*.com",
+            "://x",
+        ] {
+            assert_eq!(OriginPattern::parse(invalid), None, "{}", invalid);
+        }
+        assert_eq!(
+            parse_origins("https://a.io, bogus ,https://*.b.io"),
+            vec![pattern("https://a.io"), pattern("https://*.b.io")]
+        );
+    }
+
+    #[test]
+    fn test_admin_origin_allowed() {
+        let config = CorsConfig {
+            admin_origins: vec![pattern("https://ops.example.com")],
+            ..Default::default()
+        };
+        let host = Some("api.example.com");
+        assert!(config.admin_origin_allowed(None, host));
+        assert!(config.admin_origin_allowed(Some("https://api.example.com"), host));
+        assert!(config.admin_origin_allowed(Some("https://ops.example.com"), host));
+        assert!(!config.admin_origin_allowed(Some("https://evil.io"), host));
+        assert!(!config.admin_origin_allowed(Some("null"), host));
+        // The public wildcard doesn't extend to admin routes
+        assert!(!CorsConfig::default().admin_origin_allowed(Some("https://evil.io"), host));
+    }
+
+    fn app(cors: CorsConfig) -> Router {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        crate::api::router_with_cors(&cors).with_state(AppState {
+            admin: Arc::new(AdminConfig {
+                api_key: Some("admin-key".to_string()),
+                ..Default::default()
+            }),
+            ..test_state(db)
+        })
+    }
+
+    fn preflight(uri: &str, origin: &str, method: &str) -> Request {
+        axum::http::Request::options(uri)
+            .header(ORIGIN, origin)
+            .header(ACCESS_CONTROL_REQUEST_METHOD, method)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_origin_preflight_refused() {
+        let app = app(CorsConfig {
+            allowed_origins: vec![pattern("https://*.example.com")],
+            ..Default::default()
+        });
+
+        let response = app
+            .clone()
+            .oneshot(preflight("/api/properties", "https://evil.io", "GET"))
+            .await
+            .unwrap();
+        assert!(response
+            .headers()
+            .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+
+        let response = app
+            .oneshot(preflight(
+                "/api/properties",
+                "https://app.example.com",
+                "GET",
+            ))
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(
+            headers[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_METHODS], "GET");
+        assert_eq!(headers[ACCESS_CONTROL_MAX_AGE], "600");
+        assert!(headers.get(ACCESS_CONTROL_ALLOW_CREDENTIALS).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_saved_search_preflight_allows_writes_and_client_id() {
+        let app = app(CorsConfig {
+            allowed_origins: vec![pattern("https://app.example.com")],
+            ..Default::default()
+        });
+
+        for (uri, method) in [
+            ("/api/searches", "POST"),
+            (
+                "/api/searches/1d6e9f3a-4c1b-4f7e-9a2d-3b5c7d9e1f20",
+                "DELETE",
+            ),
+        ] {
+            let mut request = preflight(uri, "https://app.example.com", method);
+            request.headers_mut().insert(
+                ACCESS_CONTROL_REQUEST_HEADERS,
+                HeaderValue::from_static("x-client-id,content-type"),
+            );
+            let response = app.clone().oneshot(request).await.unwrap();
+            let headers = response.headers();
+            assert_eq!(
+                headers[ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com",
+                "{}",
+                uri
+            );
+            assert_eq!(
+                headers[ACCESS_CONTROL_ALLOW_METHODS], "GET,POST,DELETE",
+                "{}",
+                uri
+            );
+            let allowed = headers[ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap();
+            assert!(allowed.contains(CLIENT_ID_HEADER), "{}", allowed);
+        }
+
+        // Other public routes stay read-only
+        let response = app
+            .clone()
+            .oneshot(preflight(
+                "/api/properties",
+                "https://app.example.com",
+                "GET",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[ACCESS_CONTROL_ALLOW_METHODS], "GET");
+
+        // Saved searches don't widen the origin allow-list
+        let response = app
+            .oneshot(preflight("/api/searches", "https://evil.io", "POST"))
+            .await
+            .unwrap();
+        assert!(response
+            .headers()
+            .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_need_same_origin_or_allow_list() {
+        let app = app(CorsConfig {
+            admin_origins: vec![pattern("https://ops.example.com")],
+            ..Default::default()
+        });
+        let get = |origin: Option<&str>| {
+            let mut request = axum::http::Request::get("/api/admin/ingestion/runs")
+                .header(HOST, "api.example.com");
+            if let Some(origin) = origin {
+                request = request.header(ORIGIN, origin);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        // Cross-origin callers never reach the handler...
+        let response = app
+            .clone()
+            .oneshot(get(Some("https://evil.io")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // ...while same-origin, allow-listed and non-browser callers get as far
+        // as the admin key check
+        for origin in [
+            None,
+            Some("https://api.example.com"),
+            Some("https://ops.example.com"),
+        ] {
+            let response = app.clone().oneshot(get(origin)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{:?}", origin);
+        }
+
+        // The public wildcard doesn't answer admin preflights
+        let response = app
+            .oneshot(preflight(
+                "/api/admin/properties/1/refresh",
+                "https://evil.io",
+                "POST",
+            ))
+            .await
+            .unwrap();
+        assert!(response
+            .headers()
+            .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+}
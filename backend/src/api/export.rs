@@ -0,0 +1,308 @@
+//! CSV export helpers - stream large result sets without buffering them in memory
+
+use axum::body::Body;
+use axum::http::{header, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use bytes::Bytes;
+use futures_util::Stream;
+use serde::Serialize;
+use std::env;
+use std::fmt::Display;
+use std::future::Future;
+
+/// Serialize a page of rows into a CSV chunk
+/// The header row is only written for the first chunk of an export
+pub fn csv_chunk<T: Serialize>(rows: &[T], with_header: bool) -> Result<Bytes, csv::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(with_header)
+        .from_writer(Vec::new());
+
+    for row in rows {
+        writer.serialize(row)?;
+    }
+
+    let buffer = writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))?;
+
+    Ok(Bytes::from(buffer))
+}
+
+/// Default cap on rows in one export response
+pub const DEFAULT_EXPORT_MAX_ROWS: usize = 100_000;
+
+/// Default cap on bytes in one export response
+pub const DEFAULT_EXPORT_MAX_BYTES: usize = 32 * 1024 * 1024;
+
+/// Start of the trailing line written when an export stops at its budget;
+/// the rest of the line is the cursor to pass back as `continue_from`
+pub const CONTINUE_PREFIX: &str = "# continue_from=";
+
+/// Header on a 206 export response that stops at its row budget, carrying
+/// the same cursor as the trailing `# continue_from=` line
+pub const CONTINUE_HEADER: &str = "x-continue-from";
+
+/// Limits on how much one export response may contain, so long exports
+/// finish within proxy timeouts and resume from a cursor instead
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportBudget {
+    pub max_rows: usize,
+    /// Checked between pages, so a response can overshoot by up to one page
+    pub max_bytes: usize,
+}
+
+impl Default for ExportBudget {
+    fn default() -> Self {
+        ExportBudget {
+            max_rows: DEFAULT_EXPORT_MAX_ROWS,
+            max_bytes: DEFAULT_EXPORT_MAX_BYTES,
+        }
+    }
+}
+
+impl ExportBudget {
+    /// Budget from EXPORT_MAX_ROWS and EXPORT_MAX_BYTES
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: usize| {
+            env::var(name)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(default)
+        };
+
+        ExportBudget {
+            max_rows: read("EXPORT_MAX_ROWS", DEFAULT_EXPORT_MAX_ROWS),
+            max_bytes: read("EXPORT_MAX_BYTES", DEFAULT_EXPORT_MAX_BYTES),
+        }
+    }
+
+    /// Lower the row cap to what the client asked for, never raise it
+    pub fn with_max_rows(self, requested: Option<usize>) -> Self {
+        ExportBudget {
+            max_rows: requested.map_or(self.max_rows, |n| n.min(self.max_rows)),
+            ..self
+        }
+    }
+
+    /// Drop the byte cap once a response has promised its cut-off row in
+    /// `CONTINUE_HEADER`, so the body can't stop short of that row
+    pub fn rows_only(self) -> Self {
+        ExportBudget {
+            max_bytes: usize::MAX,
+            ..self
+        }
+    }
+}
+
+struct ExportState<F, C> {
+    fetch_page: F,
+    cursor: Option<C>,
+    with_header: bool,
+    rows: usize,
+    bytes: usize,
+}
+
+/// Stream pages of a keyset-paginated query as CSV chunks, up to `budget`
+/// `fetch_page` receives the cursor of the previous page (`start` for the
+/// first) and the most rows it may return, and returns that page's rows plus
+/// the cursor for the next one (None when done). When the budget runs out
+/// with rows still to come, a final `# continue_from=<cursor>` line is written.
+pub fn paged_csv_stream<T, C, F, Fut>(
+    fetch_page: F,
+    start: Option<C>,
+    with_header: bool,
+    budget: ExportBudget,
+) -> impl Stream<Item = anyhow::Result<Bytes>> + Send + 'static
+where
+    T: Serialize + Send + 'static,
+    C: Display + Clone + Send + 'static,
+    F: FnMut(Option<C>, usize) -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<(Vec<T>, Option<C>)>> + Send + 'static,
+{
+    let initial = ExportState {
+        fetch_page,
+        cursor: start,
+        with_header,
+        rows: 0,
+        bytes: 0,
+    };
+
+    futures_util::stream::unfold(Some(initial), move |state| async move {
+        let mut state = state?;
+        let remaining = budget.max_rows.saturating_sub(state.rows);
+
+        if remaining == 0 || state.bytes >= budget.max_bytes {
+            // Only point the client onwards if something is actually left
+            let cursor = state.cursor.clone()?;
+            return match (state.fetch_page)(Some(cursor.clone()), 1).await {
+                Ok((rows, _)) if rows.is_empty() => None,
+                Ok(_) => {
+                    let line = format!("{}{}\n", CONTINUE_PREFIX, cursor);
+                    Some((Ok(Bytes::from(line)), None))
+                }
+                Err(e) => Some((Err(e), None)),
+            };
+        }
+
+        match (state.fetch_page)(state.cursor.take(), remaining).await {
+            Ok((rows, next)) => {
+                let chunk = csv_chunk(&rows, state.with_header).map_err(anyhow::Error::from);
+                if let Ok(bytes) = &chunk {
+                    state.bytes += bytes.len();
+                }
+                state.rows += rows.len();
+                state.with_header = false;
+                state.cursor = next;
+
+                let next_state = state.cursor.is_some().then_some(state);
+                Some((chunk, next_state))
+            }
+            // Yield the error once, then end the stream
+            Err(e) => Some((Err(e), None)),
+        }
+    })
+}
+
+/// Wrap a stream of CSV chunks in a downloadable response
+/// With `continue_from` the response is a 206 naming where the next one
+/// starts in `CONTINUE_HEADER`, since headers go out before the trailing line
+pub fn csv_response<S>(filename: &str, continue_from: Option<String>, chunks: S) -> Response
+where
+    S: Stream<Item = anyhow::Result<Bytes>> + Send + 'static,
+{
+    let mut response = (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response();
+
+    if let Some(value) = continue_from.and_then(|c| HeaderValue::from_str(&c).ok()) {
+        *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(CONTINUE_HEADER), value);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[derive(Serialize)]
+    struct Row {
+        id: i32,
+        suburb: String,
+    }
+
+    #[test]
+    fn test_csv_chunk_header_only_when_requested() {
+        let rows = vec![Row {
+            id: 1,
+            suburb: "Sydney".to_string(),
+        }];
+
+        let first = csv_chunk(&rows, true).unwrap();
+        assert_eq!(&first[..], b"id,suburb\n1,Sydney\n");
+
+        let later = csv_chunk(&rows, false).unwrap();
+        assert_eq!(&later[..], b"1,Sydney\n");
+    }
+
+    /// One page of ids 0..total, at most 3 rows at a time, like a keyset query
+    fn numbered_page(
+        total: i32,
+        cursor: Option<i32>,
+        max_rows: usize,
+    ) -> anyhow::Result<(Vec<Row>, Option<i32>)> {
+        let from = cursor.map_or(0, |c| c + 1);
+        let to = (from + max_rows.min(3) as i32).min(total);
+        let rows: Vec<Row> = (from..to)
+            .map(|id| Row {
+                id,
+                suburb: format!("Suburb {}", id),
+            })
+            .collect();
+        let next = (to < total && !rows.is_empty()).then_some(to - 1);
+        Ok((rows, next))
+    }
+
+    async fn collect(start: Option<i32>, with_header: bool, budget: ExportBudget) -> String {
+        let stream = paged_csv_stream(
+            |cursor, max_rows| futures_util::future::ready(numbered_page(10, cursor, max_rows)),
+            start,
+            with_header,
+            budget,
+        );
+        let chunks: Vec<Bytes> = stream.map(|c| c.unwrap()).collect().await;
+        String::from_utf8(chunks.concat()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_paged_csv_stream_follows_cursor() {
+        let body = collect(None, true, ExportBudget::default()).await;
+
+        assert!(body.starts_with("id,suburb\n0,Suburb 0\n1,Suburb 1\n"));
+        assert!(body.ends_with("9,Suburb 9\n"));
+        assert_eq!(body.lines().count(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_budgeted_export_resumes_to_full_export() {
+        let full = collect(None, true, ExportBudget::default()).await;
+        let budget = ExportBudget::default().with_max_rows(Some(4));
+
+        let mut resumed = String::new();
+        let mut start = None;
+        let mut responses = 0;
+        loop {
+            let body = collect(start, start.is_none(), budget).await;
+            responses += 1;
+
+            let (data, trailer) = match body.rfind(CONTINUE_PREFIX) {
+                Some(pos) => (&body[..pos], Some(&body[pos + CONTINUE_PREFIX.len()..])),
+                None => (body.as_str(), None),
+            };
+            resumed.push_str(data);
+
+            match trailer {
+                Some(cursor) => start = Some(cursor.trim().parse().unwrap()),
+                None => break,
+            }
+        }
+
+        assert_eq!(responses, 3);
+        assert_eq!(resumed, full);
+    }
+
+    #[test]
+    fn test_csv_response_names_cutoff_in_header() {
+        let chunks = || futures_util::stream::iter([Ok(Bytes::from("id\n1\n"))]);
+
+        let complete = csv_response("rows.csv", None, chunks());
+        assert_eq!(complete.status(), StatusCode::OK);
+        assert!(complete.headers().get(CONTINUE_HEADER).is_none());
+
+        let partial = csv_response("rows.csv", Some("2024-01-01_7".to_string()), chunks());
+        assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(partial.headers()[CONTINUE_HEADER], "2024-01-01_7");
+    }
+
+    #[tokio::test]
+    async fn test_no_continuation_when_budget_ends_with_data() {
+        let body = collect(None, false, ExportBudget::default().with_max_rows(Some(10))).await;
+
+        assert!(!body.contains(CONTINUE_PREFIX));
+        assert_eq!(body.lines().count(), 10);
+    }
+}
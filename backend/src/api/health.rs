@@ -0,0 +1,196 @@
+//! Health checks - a liveness probe, and a readiness check that reports
+//! whether both database pools are reachable and how fresh the data is
+
+use crate::api::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::warn;
+
+/// How long a pool gets to answer before it counts as unreachable
+pub const DB_CHECK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Response for GET /
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LivenessResponse {
+    pub message: String,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub message: String,
+    pub status: String,
+    /// "ok" when both pools answer, "unreachable" otherwise
+    pub database: String,
+    /// "ok" or "unreachable"
+    pub primary_db: String,
+    /// "ok" or "unreachable"; mirrors the primary when no replica is configured
+    pub read_db: String,
+    /// Seconds since the latest ingestion run completed; None when none has,
+    /// or the database can't say
+    pub last_ingestion_age_secs: Option<i64>,
+}
+
+/// GET / - liveness only; never touches the database, so a database outage
+/// doesn't get the process restarted
+pub async fn liveness() -> Json<LivenessResponse> {
+    Json(LivenessResponse {
+        message: "Realtor API is running!".to_string(),
+        status: "ok".to_string(),
+    })
+}
+
+/// GET /api/health - 200 when both pools answer within DB_CHECK_TIMEOUT, 503
+/// otherwise
+pub async fn health_check(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let (primary_ok, read_ok) =
+        tokio::join!(ping(&state.db, "primary"), ping(&state.read_db, "read"));
+    let label = |ok: bool| if ok { "ok" } else { "unreachable" }.to_string();
+
+    let last_ingestion_age_secs = if read_ok {
+        last_ingestion_age(&state.read_db).await
+    } else {
+        None
+    };
+
+    let (status, summary, message) = if primary_ok && read_ok {
+        (StatusCode::OK, "ok", "Realtor API is running!")
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "degraded",
+            "Database unreachable",
+        )
+    };
+
+    (
+        status,
+        Json(HealthResponse {
+            message: message.to_string(),
+            status: summary.to_string(),
+            database: label(primary_ok && read_ok),
+            primary_db: label(primary_ok),
+            read_db: label(read_ok),
+            last_ingestion_age_secs,
+        }),
+    )
+}
+
+async fn ping(db: &PgPool, name: &str) -> bool {
+    match timeout(DB_CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(db)).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(e)) => {
+            warn!("Health check: {} database unreachable: {}", name, e);
+            false
+        }
+        Err(_) => {
+            warn!(
+                "Health check: {} database didn't answer within {:?}",
+                name, DB_CHECK_TIMEOUT
+            );
+            false
+        }
+    }
+}
+
+/// Age of the newest completed ingestion run, measured by the database clock
+/// that stamped it
+async fn last_ingestion_age(db: &PgPool) -> Option<i64> {
+    let query = sqlx::query_scalar::<_, Option<i64>>(
+        r#"
+        SELECT EXTRACT(EPOCH FROM NOW()::timestamp - MAX(completed_at))::BIGINT
+        FROM ingestion_runs
+        WHERE status = 'completed'
+        "#,
+    )
+    .fetch_one(db);
+
+    match timeout(DB_CHECK_TIMEOUT, query).await {
+        Ok(Ok(age)) => age,
+        Ok(Err(e)) => {
+            warn!("Health check: couldn't read ingestion runs: {}", e);
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_state;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_health_reports_unreachable_pool() {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = crate::api::router().with_state(test_state(db));
+
+        let response = app
+            .oneshot(Request::get("/api/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let health: HealthResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health.status, "degraded");
+        assert_eq!(health.database, "unreachable");
+        assert_eq!(health.primary_db, "unreachable");
+        assert_eq!(health.read_db, "unreachable");
+        assert_eq!(health.last_ingestion_age_secs, None);
+    }
+
+    #[tokio::test]
+    async fn test_unresponsive_pool_times_out() {
+        // Accepts connections but never speaks Postgres
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_secs(30))
+            .connect_lazy(&format!("postgres://nobody@{}/none", addr))
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        assert!(!ping(&db, "primary").await);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_liveness_skips_the_database() {
+        let Json(live) = liveness().await;
+        assert_eq!(live.status, "ok");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a reachable database
+    async fn test_health_ok() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let state = test_state(db);
+
+        let (status, Json(health)) = health_check(State(state)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(health.database, "ok");
+        assert_eq!(health.read_db, "ok");
+    }
+}
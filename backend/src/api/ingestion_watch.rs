@@ -0,0 +1,147 @@
+//! Clears the response caches once an ingestion run completes
+//! Ingestion runs in its own process, so the API server polls for completed
+//! runs. This always runs, whether or not the statistics refresh is enabled.
+
+use crate::api::cache::ResponseCaches;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+/// Default seconds between checks for a completed run
+pub const DEFAULT_INGESTION_WATCH_SECS: u64 = 60;
+
+/// Interval from INGESTION_WATCH_INTERVAL (seconds)
+pub fn interval_from_env() -> Duration {
+    let secs = env::var("INGESTION_WATCH_INTERVAL")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_INGESTION_WATCH_SECS);
+
+    Duration::from_secs(secs)
+}
+
+/// Spawn the watch loop, which exits once `shutdown` flips to true (or its
+/// sender is dropped)
+pub fn spawn_ingestion_watch(
+    db: PgPool,
+    caches: Arc<ResponseCaches>,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        // When the newest completed run finished, as of the last check
+        let mut seen = None;
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.changed() => break,
+            }
+            if *shutdown.borrow() {
+                break;
+            }
+
+            if let Err(e) = clear_on_completed_run(&db, &caches, &mut seen).await {
+                warn!("Checking for completed ingestion runs failed: {}", e);
+            }
+        }
+    })
+}
+
+/// Clear the caches if a run has completed since `seen`, which is moved on to
+/// it; true when they were cleared
+pub async fn clear_on_completed_run(
+    db: &PgPool,
+    caches: &ResponseCaches,
+    seen: &mut Option<NaiveDateTime>,
+) -> Result<bool> {
+    let latest = sqlx::query_scalar::<_, Option<NaiveDateTime>>(
+        r#"
+        SELECT MAX(completed_at)
+        FROM ingestion_runs
+        WHERE status IN ('completed', 'completed_with_warnings')
+        "#,
+    )
+    .fetch_one(db)
+    .await?;
+
+    if latest <= *seen {
+        return Ok(false);
+    }
+    // The first check also clears, as a run may have finished while the
+    // caches were filling
+    caches.clear();
+    if seen.is_some() {
+        info!("Ingestion run completed, cleared response caches");
+    }
+    *seen = latest;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::cache::CacheStatus;
+
+    async fn top_yields_status(caches: &ResponseCaches) -> CacheStatus {
+        let (_, status) = caches
+            .top_yields
+            .get("ingestion-watch", || async { Ok(Vec::new()) })
+            .await
+            .unwrap();
+        status
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_completed_run_clears_caches() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let caches = ResponseCaches::default();
+        let mut seen = None;
+        clear_on_completed_run(&db, &caches, &mut seen)
+            .await
+            .unwrap();
+
+        // Nothing new has completed, so cached responses stay
+        assert_eq!(top_yields_status(&caches).await, CacheStatus::Miss);
+        assert!(!clear_on_completed_run(&db, &caches, &mut seen)
+            .await
+            .unwrap());
+        assert_eq!(top_yields_status(&caches).await, CacheStatus::Hit);
+
+        let id = sqlx::query_scalar::<_, i32>(
+            r#"
+            INSERT INTO ingestion_runs (source_id, status, started_at, completed_at)
+            VALUES ('ingestion_watch_test', 'completed', NOW(), NOW() + INTERVAL '1 minute')
+            RETURNING id
+            "#,
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap();
+
+        assert!(clear_on_completed_run(&db, &caches, &mut seen)
+            .await
+            .unwrap());
+        assert_eq!(top_yields_status(&caches).await, CacheStatus::Miss);
+
+        sqlx::query("DELETE FROM ingestion_runs WHERE id = $1")
+            .bind(id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+//! Request logging - a tracing span per request carrying its id, route, status
+//! and latency, with the id echoed in the x-request-id response header
+
+use axum::body::Body;
+use axum::extract::MatchedPath;
+use axum::http::{Request, Response};
+use axum::Router;
+use std::env;
+use std::time::Duration;
+use tower::ServiceBuilder;
+use tower_http::request_id::{
+    MakeRequestId, PropagateRequestIdLayer, RequestId, SetRequestIdLayer,
+};
+use tower_http::trace::TraceLayer;
+use tracing::{info, info_span, Span};
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+/// Header carrying the request id, set on every response; a caller's own id
+/// is kept
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Install the global subscriber: RUST_LOG filters (info by default) and
+/// LOG_FORMAT=json switches to one JSON object per line
+pub fn init_tracing() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false);
+
+    if env::var("LOG_FORMAT").is_ok_and(|f| f.eq_ignore_ascii_case("json")) {
+        builder.json().init();
+    } else {
+        builder.init();
+    }
+}
+
+/// A random UUID per request
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MakeRequestUuid;
+
+impl MakeRequestId for MakeRequestUuid {
+    fn make_request_id<B>(&mut self, _request: &Request<B>) -> Option<RequestId> {
+        Uuid::new_v4().to_string().parse().ok().map(RequestId::new)
+    }
+}
+
+/// `router` with request ids and request logging. Events logged while a
+/// request is handled, such as database errors, carry its span's fields.
+pub fn apply<S>(router: Router<S>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(request_span)
+                    .on_request(())
+                    .on_response(log_response)
+                    .on_failure(()),
+            )
+            .layer(PropagateRequestIdLayer::x_request_id()),
+    )
+}
+
+fn request_span(request: &Request<Body>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default();
+    // The route pattern, so requests for different ids group together
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(request.uri().path(), MatchedPath::as_str);
+
+    info_span!(
+        "request",
+        request_id,
+        method = %request.method(),
+        route,
+    )
+}
+
+fn log_response<B>(response: &Response<B>, latency: Duration, _span: &Span) {
+    info!(
+        status = response.status().as_u16(),
+        latency_ms = latency.as_millis() as u64,
+        "Request completed"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        apply(Router::new().route("/items/:id", get(|| async { "ok" })))
+    }
+
+    #[tokio::test]
+    async fn test_request_id_generated() {
+        let response = app()
+            .oneshot(Request::get("/items/1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(Uuid::parse_str(id).is_ok(), "{}", id);
+
+        let other = app()
+            .oneshot(Request::get("/items/1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_ne!(other.headers()[REQUEST_ID_HEADER], id);
+    }
+
+    #[tokio::test]
+    async fn test_caller_request_id_kept() {
+        for uri in ["/items/1", "/missing"] {
+            let response = app()
+                .oneshot(
+                    Request::get(uri)
+                        .header(REQUEST_ID_HEADER, "support-ticket-42")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                response.headers()[REQUEST_ID_HEADER],
+                "support-ticket-42",
+                "{}",
+                uri
+            );
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Map viewport endpoint - the individual properties inside a bounding box,
+//! capped so a zoomed-out view can't pull the whole table
+
+use crate::api::clusters::{BoundingBox, ClusterProperty};
+use crate::api::params::{ParamError, ValidateParams, ValidatedListParams};
+use crate::api::tier::{Access, Redact, Redactor};
+use crate::api::AppState;
+use crate::format::round_yield_f64;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::env;
+use tracing::error;
+
+/// Most properties in one map response by default
+pub const DEFAULT_MAP_MAX_PROPERTIES: i64 = 2000;
+
+/// Map response limits, loaded from environment variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapConfig {
+    pub max_properties: i64,
+}
+
+impl Default for MapConfig {
+    fn default() -> Self {
+        MapConfig {
+            max_properties: DEFAULT_MAP_MAX_PROPERTIES,
+        }
+    }
+}
+
+impl MapConfig {
+    /// Cap from MAP_MAX_PROPERTIES
+    pub fn from_env() -> Self {
+        MapConfig {
+            max_properties: env::var("MAP_MAX_PROPERTIES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(DEFAULT_MAP_MAX_PROPERTIES),
+        }
+    }
+}
+
+/// Query parameters for GET /api/properties/map
+#[derive(Debug, Deserialize)]
+pub struct MapQuery {
+    /// `west,south,east,north` (minLng,minLat,maxLng,maxLat) in degrees
+    pub bbox: String,
+}
+
+impl ValidateParams for MapQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        BoundingBox::try_parse(&self.bbox)
+            .map(|_| ())
+            .map_err(|message| ParamError::new("bbox", message))
+    }
+}
+
+/// JSON response for GET /api/properties/map
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MapResponse {
+    /// Ordered by id
+    pub properties: Vec<ClusterProperty>,
+    /// More properties are in the box than the cap allows; zoom in to see them
+    pub truncated: bool,
+}
+
+impl Redact for MapResponse {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.properties.redact(redactor);
+    }
+}
+
+/// GET /api/properties/map - properties with coordinates inside a bounding box
+pub async fn get_map_properties(
+    State(state): State<AppState>,
+    access: Access,
+    ValidatedListParams(params): ValidatedListParams<MapQuery>,
+) -> Result<Json<MapResponse>, StatusCode> {
+    let bbox = BoundingBox::parse(&params.bbox).ok_or(StatusCode::BAD_REQUEST)?;
+
+    fetch_map_properties(&state.read_db, &bbox, state.map.max_properties)
+        .await
+        .map(|response| Json(access.apply(response)))
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Up to `limit` properties inside `bbox`; properties without coordinates never match
+pub async fn fetch_map_properties(
+    db: &PgPool,
+    bbox: &BoundingBox,
+    limit: i64,
+) -> Result<MapResponse, sqlx::Error> {
+    // One extra row says whether the cap was hit
+    let mut properties = sqlx::query_as::<_, ClusterProperty>(
+        r#"
+        SELECT id, address, suburb,
+               latitude::FLOAT8 AS latitude, longitude::FLOAT8 AS longitude,
+               price, rental_yield::FLOAT8 AS rental_yield
+        FROM properties
+        WHERE latitude IS NOT NULL
+          AND longitude IS NOT NULL
+          AND longitude BETWEEN $1::NUMERIC AND $3::NUMERIC
+          AND latitude BETWEEN $2::NUMERIC AND $4::NUMERIC
+        ORDER BY id
+        LIMIT $5
+        "#,
+    )
+    .bind(bbox.west)
+    .bind(bbox.south)
+    .bind(bbox.east)
+    .bind(bbox.north)
+    .bind(limit + 1)
+    .fetch_all(db)
+    .await?;
+
+    let truncated = properties.len() as i64 > limit;
+    properties.truncate(limit as usize);
+
+    Ok(MapResponse {
+        properties: properties
+            .into_iter()
+            .map(|p| ClusterProperty {
+                rental_yield: p.rental_yield.map(round_yield_f64),
+                ..p
+            })
+            .collect(),
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tier::{Tier, TierConfig};
+    use crate::api::API_KEY_HEADER;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use rust_decimal::Decimal;
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn get(db: &PgPool, map: MapConfig, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(AppState {
+            tiers: Arc::new(TierConfig::default().with_key("map-test", Tier::Full)),
+            map,
+            ..test_state(db.clone())
+        });
+        let response = app
+            .oneshot(
+                Request::get(uri)
+                    .header(API_KEY_HEADER, "map-test")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_malformed_bbox_rejected_with_400() {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+
+        for (bbox, message) in [
+            (
+                "150,-34,151",
+                "must be 4 comma-separated numbers west,south,east,north; got 3",
+            ),
+            (
+                "150,-34,151,-33,0",
+                "must be 4 comma-separated numbers west,south,east,north; got 5",
+            ),
+            ("151,-34,150,-33", "west (151) must be less than east (150)"),
+            (
+                "150,-33,151,-34",
+                "south (-33) must be less than north (-34)",
+            ),
+            ("150,north,151,-33", "'north' is not a number"),
+        ] {
+            let uri = format!("/api/properties/map?bbox={}", bbox);
+            let (status, body) = get(&db, MapConfig::default(), &uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", bbox);
+            assert_eq!(body["field"], "bbox", "{}", bbox);
+            assert_eq!(body["message"], message, "{}", bbox);
+        }
+
+        let (status, _) = get(&db, MapConfig::default(), "/api/properties/map").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    const SUBURB: &str = "Map Viewport Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_map_properties_in_bbox() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+
+        // An empty stretch of ocean, so only the seeded properties are inside
+        let coordinate = |v: &str| v.parse::<Decimal>().unwrap();
+        let mut inside = Vec::new();
+        for (n, lng) in ["165.01", "165.02", "165.03"].iter().enumerate() {
+            let id = PropertyFixture::new()
+                .address(&format!("{} Map St", n + 1))
+                .suburb(SUBURB)
+                .price(500_000)
+                .weekly_rent(500)
+                .coordinates(coordinate("-45.05"), coordinate(lng))
+                .insert(&db)
+                .await
+                .unwrap();
+            inside.push(id as i64);
+        }
+        // Outside the box, and without coordinates at all
+        PropertyFixture::new()
+            .address("9 Map St")
+            .suburb(SUBURB)
+            .coordinates(coordinate("-45.05"), coordinate("165.5"))
+            .insert(&db)
+            .await
+            .unwrap();
+        PropertyFixture::new()
+            .address("10 Map St")
+            .suburb(SUBURB)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let uri = "/api/properties/map?bbox=164.99,-45.1,165.1,-44.99";
+        let ids = |body: &Value| -> Vec<i64> {
+            body["properties"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["id"].as_i64().unwrap())
+                .collect()
+        };
+
+        let (status, body) = get(&db, MapConfig::default(), uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&body), inside);
+        assert_eq!(body["truncated"], false);
+        assert_eq!(body["properties"][0]["latitude"], -45.05);
+
+        // Exactly at the cap isn't truncated; one under it is
+        let capped = |max_properties| MapConfig { max_properties };
+        let (_, body) = get(&db, capped(3), uri).await;
+        assert_eq!(ids(&body), inside);
+        assert_eq!(body["truncated"], false);
+        let (_, body) = get(&db, capped(2), uri).await;
+        assert_eq!(ids(&body), inside[..2]);
+        assert_eq!(body["truncated"], true);
+
+        delete_suburb(&db, SUBURB).await.unwrap();
+    }
+}
@@ -0,0 +1,1257 @@
+//! Data dictionary endpoints - what each response field means, its type and
+//! unit, from the one table below; the OpenAPI schemas are generated from it
+//! too, so the two can't drift apart, with paths for the core endpoints and
+//! a Swagger UI page over the document
+
+use axum::response::Html;
+use axum::Json;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// A field's JSON type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "values", rename_all = "snake_case")]
+pub enum FieldType {
+    Integer,
+    Number,
+    /// Exact decimal sent as a JSON string, e.g. "0.95"
+    Decimal,
+    String,
+    Boolean,
+    /// YYYY-MM-DD
+    Date,
+    /// One of the listed strings
+    Enum(&'static [&'static str]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Unit {
+    #[serde(rename = "AUD")]
+    Aud,
+    #[serde(rename = "AUD/week")]
+    AudPerWeek,
+    #[serde(rename = "percent")]
+    Percent,
+    #[serde(rename = "degrees")]
+    Degrees,
+    #[serde(rename = "count")]
+    Count,
+    /// 0 to 1
+    #[serde(rename = "ratio")]
+    Ratio,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FieldDef {
+    pub name: &'static str,
+    #[serde(flatten)]
+    pub field_type: FieldType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<Unit>,
+    pub nullable: bool,
+    pub description: &'static str,
+}
+
+/// One kind of record the API returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Resource {
+    pub name: &'static str,
+    /// Component name in the OpenAPI schemas
+    #[serde(skip)]
+    pub schema: &'static str,
+    pub description: &'static str,
+    pub fields: &'static [FieldDef],
+}
+
+const fn field(
+    name: &'static str,
+    field_type: FieldType,
+    unit: Option<Unit>,
+    nullable: bool,
+    description: &'static str,
+) -> FieldDef {
+    FieldDef {
+        name,
+        field_type,
+        unit,
+        nullable,
+        description,
+    }
+}
+
+const STATES: FieldType = FieldType::Enum(&["NSW", "VIC", "QLD", "WA", "SA", "TAS", "ACT", "NT"]);
+
+const PROPERTY_TYPES: FieldType = FieldType::Enum(&[
+    "House",
+    "Unit",
+    "Townhouse",
+    "VacantLand",
+    "Commercial",
+    "Other",
+]);
+
+const DATA_QUALITIES: FieldType =
+    FieldType::Enum(&["Individual", "Aggregated", "Estimated", "Listing"]);
+
+use FieldType::{Boolean, Date, Decimal, Integer, Number};
+use Unit::{Aud, AudPerWeek, Count, Degrees, Percent, Ratio};
+
+/// Every resource in the dictionary
+pub const RESOURCES: &[Resource] = &[
+    Resource {
+        name: "property",
+        schema: "Property",
+        description: "One property, from GET /api/properties/:id; the list and map \
+            endpoints return a subset of these fields",
+        fields: &[
+            field("id", Integer, None, false, "Property id"),
+            field(
+                "address",
+                FieldType::String,
+                None,
+                false,
+                "Street address, with units as unit/house street; the public tier \
+                 sees the street name only",
+            ),
+            field("suburb", FieldType::String, None, false, "Suburb name"),
+            field(
+                "state",
+                STATES,
+                None,
+                false,
+                "Australian state or territory",
+            ),
+            field(
+                "postcode",
+                FieldType::String,
+                None,
+                true,
+                "Four-digit postcode",
+            ),
+            field(
+                "district",
+                FieldType::String,
+                None,
+                true,
+                "Three-digit NSW Valuer General district code, from the NSW sales data",
+            ),
+            field(
+                "district_name",
+                FieldType::String,
+                None,
+                true,
+                "Council the district covers; the code itself when the name isn't known",
+            ),
+            field(
+                "property_type",
+                PROPERTY_TYPES,
+                None,
+                true,
+                "Kind of dwelling",
+            ),
+            field(
+                "bedrooms",
+                Integer,
+                Some(Count),
+                true,
+                "Bedrooms, sourced or estimated from the property type",
+            ),
+            field(
+                "price",
+                Integer,
+                Some(Aud),
+                true,
+                "Most recent sale price; the public tier sees it rounded to the price band",
+            ),
+            field(
+                "sale_date",
+                Date,
+                None,
+                true,
+                "Date of the most recent sale",
+            ),
+            field(
+                "weekly_rent",
+                Integer,
+                Some(AudPerWeek),
+                true,
+                "Median weekly rent for the property's postcode and bedrooms",
+            ),
+            field(
+                "price_per_sqm",
+                Decimal,
+                None,
+                true,
+                "Sale price per square metre of land, AUD to the cent; houses and \
+                 vacant land only, as strata properties carry the whole lot's area",
+            ),
+            field(
+                "latitude",
+                Decimal,
+                Some(Degrees),
+                true,
+                "Latitude; coarsened for the public tier",
+            ),
+            field(
+                "longitude",
+                Decimal,
+                Some(Degrees),
+                true,
+                "Longitude; coarsened for the public tier",
+            ),
+            field(
+                "rental_yield",
+                Number,
+                Some(Percent),
+                true,
+                "Gross rental yield, weekly_rent * 52 / price * 100, to 2 decimal places",
+            ),
+            field(
+                "annualized_growth_pct",
+                Number,
+                Some(Percent),
+                true,
+                "Compound annual growth from the first recorded sale price to the \
+                 last, to 2 decimal places; negative when the price fell. Absent \
+                 without two sales at least 90 days apart",
+            ),
+            field(
+                "data_source",
+                FieldType::String,
+                None,
+                true,
+                "Source the row was last written from, e.g. nsw_sales",
+            ),
+            field(
+                "data_quality",
+                DATA_QUALITIES,
+                None,
+                true,
+                "Individual: a real property record; Aggregated: built from suburb or \
+                 postcode medians; Estimated: derived values; Listing: a current \
+                 market listing. When sources disagree, higher quality wins",
+            ),
+            field(
+                "confidence_score",
+                Decimal,
+                Some(Ratio),
+                true,
+                "How far the source's values can be trusted, 0 to 1; weighs \
+                 data_quality when sources disagree",
+            ),
+            field(
+                "external_id",
+                FieldType::String,
+                None,
+                true,
+                "The source's own identifier for the property",
+            ),
+        ],
+    },
+    Resource {
+        name: "suburb_statistics",
+        schema: "SuburbStatistics",
+        description: "Latest statistics for one suburb, postcode and bedroom group, \
+            from GET /api/suburbs/stats",
+        fields: &[
+            field("suburb", FieldType::String, None, false, "Suburb name"),
+            field(
+                "postcode",
+                FieldType::String,
+                None,
+                true,
+                "Four-digit postcode",
+            ),
+            field(
+                "state",
+                STATES,
+                None,
+                false,
+                "Australian state or territory",
+            ),
+            field(
+                "bedrooms",
+                Integer,
+                Some(Count),
+                true,
+                "Bedrooms of the group's properties",
+            ),
+            field(
+                "median_price",
+                Integer,
+                Some(Aud),
+                true,
+                "Median sale price",
+            ),
+            field(
+                "median_weekly_rent",
+                Integer,
+                Some(AudPerWeek),
+                true,
+                "Median weekly rent",
+            ),
+            field(
+                "median_rental_yield",
+                Number,
+                Some(Percent),
+                true,
+                "Median gross rental yield",
+            ),
+            field(
+                "min_yield",
+                Number,
+                Some(Percent),
+                true,
+                "Lowest gross rental yield",
+            ),
+            field(
+                "max_yield",
+                Number,
+                Some(Percent),
+                true,
+                "Highest gross rental yield",
+            ),
+            field(
+                "property_count",
+                Integer,
+                Some(Count),
+                true,
+                "Properties the statistics were calculated from",
+            ),
+            field(
+                "calculated_date",
+                Date,
+                None,
+                false,
+                "When the statistics were calculated",
+            ),
+            field(
+                "suppressed",
+                Boolean,
+                None,
+                false,
+                "Too few properties to publish; the medians and yield range are null",
+            ),
+            field(
+                "rolled_up_to",
+                FieldType::String,
+                None,
+                true,
+                "SA3 whose combined medians replace a small group's; no yield range",
+            ),
+        ],
+    },
+    Resource {
+        name: "rental_median",
+        schema: "RentalMedian",
+        description: "One period's median rent, from GET /api/properties/:id/rent-history",
+        fields: &[
+            field(
+                "period",
+                Date,
+                None,
+                false,
+                "First day of the month or quarter the median covers",
+            ),
+            field(
+                "median_rent",
+                Integer,
+                Some(AudPerWeek),
+                false,
+                "Median weekly rent of new bonds lodged in the period",
+            ),
+            field(
+                "sample_size",
+                Integer,
+                Some(Count),
+                true,
+                "Bonds lodged behind the median",
+            ),
+        ],
+    },
+    Resource {
+        name: "net_yield",
+        schema: "NetYield",
+        description: "The net_yield block of GET /api/properties/:id, present when an \
+            expense parameter is passed and the property has a price and rent",
+        fields: &[
+            field(
+                "net_yield",
+                Number,
+                Some(Percent),
+                false,
+                "(annual_rent - annual_expenses) / price * 100, to 2 decimal places; \
+                 negative when the expenses exceed the rent",
+            ),
+            field("annual_rent", Integer, Some(Aud), false, "weekly_rent * 52"),
+            field(
+                "annual_expenses",
+                Integer,
+                Some(Aud),
+                false,
+                "Every expense below plus the management fee on annual_rent",
+            ),
+            field(
+                "council_rates",
+                Integer,
+                Some(Aud),
+                false,
+                "Council rates and levies for a year",
+            ),
+            field(
+                "council_rates_source",
+                FieldType::Enum(&["requested", "council", "default"]),
+                None,
+                false,
+                "requested: the council_rates parameter; council: the average for \
+                 the property's council; default: a flat assumption",
+            ),
+            field(
+                "strata",
+                Integer,
+                Some(Aud),
+                false,
+                "Strata levies for a year",
+            ),
+            field(
+                "insurance",
+                Integer,
+                Some(Aud),
+                false,
+                "Landlord insurance for a year",
+            ),
+            field(
+                "management_fee_pct",
+                Decimal,
+                Some(Percent),
+                false,
+                "Property management fee as a percent of rent",
+            ),
+            field(
+                "maintenance",
+                Integer,
+                Some(Aud),
+                false,
+                "Maintenance allowance for a year",
+            ),
+        ],
+    },
+];
+
+/// The body of 400 and 404 responses
+pub const ERROR_RESPONSE: Resource = Resource {
+    name: "error",
+    schema: "Error",
+    description: "Why a request was refused",
+    fields: &[
+        field(
+            "error",
+            FieldType::String,
+            None,
+            false,
+            "Machine-readable reason, e.g. invalid_parameter or not_found",
+        ),
+        field(
+            "field",
+            FieldType::String,
+            None,
+            true,
+            "The parameter at fault; only present on invalid_parameter",
+        ),
+        field(
+            "message",
+            FieldType::String,
+            None,
+            false,
+            "What was wrong, for people",
+        ),
+    ],
+};
+
+/// Where a parameter is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamLocation {
+    Path,
+    Query,
+}
+
+/// A parameter of a documented endpoint; path parameters are required,
+/// query parameters optional
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamDef {
+    pub name: &'static str,
+    pub location: ParamLocation,
+    pub field_type: FieldType,
+    pub description: &'static str,
+}
+
+const fn query(name: &'static str, field_type: FieldType, description: &'static str) -> ParamDef {
+    ParamDef {
+        name,
+        location: ParamLocation::Query,
+        field_type,
+        description,
+    }
+}
+
+const ID_PARAM: ParamDef = ParamDef {
+    name: "id",
+    location: ParamLocation::Path,
+    field_type: Integer,
+    description: "Property id",
+};
+
+/// What a successful response carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseBody {
+    /// One resource, by schema name
+    Resource(&'static str),
+    /// An object holding `fields` and a list of resources under `key`
+    List {
+        key: &'static str,
+        item: &'static str,
+        fields: &'static [FieldDef],
+    },
+}
+
+/// A documented GET endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Endpoint {
+    /// OpenAPI path, with {braces} around path parameters
+    pub path: &'static str,
+    pub summary: &'static str,
+    pub params: &'static [ParamDef],
+    pub body: ResponseBody,
+    /// Responds 404 for unknown ids
+    pub not_found: bool,
+}
+
+/// The endpoints described in the OpenAPI paths
+pub const ENDPOINTS: &[Endpoint] = &[
+    Endpoint {
+        path: "/api/properties",
+        summary: "One page of properties, filtered and sorted; list items carry a \
+            subset of the Property fields",
+        params: &[
+            query(
+                "region",
+                FieldType::String,
+                "SA3 code, SA4 code or SA3 name",
+            ),
+            query(
+                "suburb",
+                FieldType::String,
+                "Suburb names, any case, comma separated",
+            ),
+            query(
+                "postcode",
+                FieldType::String,
+                "4-digit postcodes, comma separated, e.g. 2000,2026",
+            ),
+            query(
+                "district",
+                FieldType::String,
+                "NSW district codes, comma separated, e.g. 001,033",
+            ),
+            query(
+                "state",
+                FieldType::String,
+                "States or territories, comma separated, e.g. NSW,QLD",
+            ),
+            query("bedrooms", Integer, "Exact bedroom count"),
+            query("min_price", Integer, "Lowest price, AUD; needs an API key"),
+            query("max_price", Integer, "Highest price, AUD; needs an API key"),
+            query("min_weekly_rent", Integer, "Lowest weekly rent, AUD"),
+            query("max_weekly_rent", Integer, "Highest weekly rent, AUD"),
+            query(
+                "min_yield",
+                Number,
+                "Lowest rental yield, percent, as displayed; needs an API key",
+            ),
+            query(
+                "sort",
+                FieldType::Enum(&[
+                    "id",
+                    "rental_yield",
+                    "price",
+                    "weekly_rent",
+                    "sale_date",
+                    "suburb",
+                ]),
+                "Sort column; id by default. price needs an API key",
+            ),
+            query(
+                "order",
+                FieldType::Enum(&["asc", "desc"]),
+                "Sort direction; asc by default",
+            ),
+            query(
+                "page",
+                Integer,
+                "1-based page number, for browsing; not with cursor",
+            ),
+            query("page_size", Integer, "Properties per page, 1 to 500"),
+            query(
+                "cursor",
+                FieldType::String,
+                "next_cursor from the previous page. The way to read every \
+                 property: unlike deep pages, it neither skips nor repeats \
+                 properties while ingestion writes. Only with sort=id or \
+                 rental_yield, and rental_yield needs an API key",
+            ),
+        ],
+        body: ResponseBody::List {
+            key: "properties",
+            item: "Property",
+            fields: &[
+                field("page", Integer, None, false, "This page's number"),
+                field("page_size", Integer, None, false, "Properties per page"),
+                field(
+                    "total",
+                    Integer,
+                    Some(Count),
+                    false,
+                    "Properties matching the filters across all pages",
+                ),
+                field(
+                    "total_pages",
+                    Integer,
+                    Some(Count),
+                    false,
+                    "Pages at this page size; zero when nothing matches",
+                ),
+                field(
+                    "next_cursor",
+                    FieldType::String,
+                    None,
+                    true,
+                    "Pass back as cursor for the following page; null on the last \
+                     page and for sorts without cursor support",
+                ),
+            ],
+        },
+        not_found: false,
+    },
+    Endpoint {
+        path: "/api/properties/{id}",
+        summary: "One property in full; passing any expense parameter adds a \
+            NetYield under net_yield, with the other expenses defaulted",
+        params: &[
+            ID_PARAM,
+            query(
+                "council_rates",
+                Integer,
+                "Council rates for a year, AUD; the property's council's \
+                 average by default, else 1800",
+            ),
+            query(
+                "strata",
+                Integer,
+                "Strata levies for a year, AUD; 0 by default",
+            ),
+            query(
+                "insurance",
+                Integer,
+                "Landlord insurance for a year, AUD; 1500 by default",
+            ),
+            query(
+                "management_fee_pct",
+                Decimal,
+                "Management fee, percent of rent, 0 to 100; 7 by default",
+            ),
+            query(
+                "maintenance",
+                Integer,
+                "Maintenance allowance for a year, AUD; 2000 by default",
+            ),
+        ],
+        body: ResponseBody::Resource("Property"),
+        not_found: true,
+    },
+    Endpoint {
+        path: "/api/properties/{id}/rent-history",
+        summary: "Median rent for the property's postcode and bedrooms over the last \
+            8 quarters",
+        params: &[ID_PARAM],
+        body: ResponseBody::List {
+            key: "points",
+            item: "RentalMedian",
+            fields: &[
+                field("property_id", Integer, None, false, "Property id"),
+                field("state", STATES, None, false, "Property's state"),
+                field(
+                    "postcode",
+                    FieldType::String,
+                    None,
+                    false,
+                    "Property's postcode",
+                ),
+                field("bedrooms", Integer, None, false, "Property's bedrooms"),
+                field(
+                    "bedrooms_estimated",
+                    Boolean,
+                    None,
+                    false,
+                    "The bedroom count was estimated during ingestion",
+                ),
+                field(
+                    "yield_period",
+                    Date,
+                    None,
+                    true,
+                    "Period of the median behind the property's rent and yield",
+                ),
+            ],
+        },
+        not_found: true,
+    },
+    Endpoint {
+        path: "/api/suburbs/stats",
+        summary: "Latest statistics for each suburb, postcode and bedroom group",
+        params: &[
+            query("suburb", FieldType::String, "Suburb name, any case"),
+            query("postcode", FieldType::String, "Postcode"),
+            query(
+                "district",
+                FieldType::String,
+                "NSW district code; groups with properties in the district",
+            ),
+            query("state", STATES, "State or territory"),
+            query("bedrooms", Integer, "Bedroom group"),
+        ],
+        body: ResponseBody::List {
+            key: "groups",
+            item: "SuburbStatistics",
+            fields: &[],
+        },
+        not_found: false,
+    },
+];
+
+/// Response for GET /api/meta/fields
+#[derive(Debug, Serialize)]
+pub struct DataDictionary {
+    pub resources: &'static [Resource],
+}
+
+/// GET /api/meta/fields - the data dictionary
+pub async fn get_fields() -> Json<DataDictionary> {
+    Json(DataDictionary {
+        resources: RESOURCES,
+    })
+}
+
+/// GET /api/meta/openapi.json and /api/openapi.json - the OpenAPI document
+pub async fn get_openapi() -> Json<Value> {
+    Json(openapi_document())
+}
+
+/// GET /api/docs - Swagger UI for the OpenAPI document
+pub async fn get_docs() -> Html<&'static str> {
+    Html(SWAGGER_UI_PAGE)
+}
+
+/// Loads Swagger UI from a CDN, so the server ships no assets for it
+const SWAGGER_UI_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Realtor API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+/// An OpenAPI 3.0 document whose paths come from ENDPOINTS and component
+/// schemas from RESOURCES and ERROR_RESPONSE
+pub fn openapi_document() -> Value {
+    let schemas: Map<String, Value> = RESOURCES
+        .iter()
+        .chain([&ERROR_RESPONSE])
+        .map(|resource| (resource.schema.to_string(), openapi_schema(resource)))
+        .collect();
+    let paths: Map<String, Value> = ENDPOINTS
+        .iter()
+        .map(|endpoint| (endpoint.path.to_string(), openapi_path(endpoint)))
+        .collect();
+
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": "Realtor API", "version": env!("CARGO_PKG_VERSION") },
+        "paths": paths,
+        "components": { "schemas": schemas },
+    })
+}
+
+fn schema_ref(schema: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", schema) })
+}
+
+fn openapi_path(endpoint: &Endpoint) -> Value {
+    let parameters: Vec<Value> = endpoint
+        .params
+        .iter()
+        .map(|param| {
+            let location = match param.location {
+                ParamLocation::Path => "path",
+                ParamLocation::Query => "query",
+            };
+            json!({
+                "name": param.name,
+                "in": location,
+                "required": param.location == ParamLocation::Path,
+                "description": param.description,
+                "schema": openapi_type(param.field_type),
+            })
+        })
+        .collect();
+
+    let body = match endpoint.body {
+        ResponseBody::Resource(schema) => schema_ref(schema),
+        ResponseBody::List { key, item, fields } => {
+            let mut schema = object_schema(None, fields);
+            schema["required"]
+                .as_array_mut()
+                .expect("object_schema lists required fields")
+                .push(json!(key));
+            schema["properties"][key] = json!({ "type": "array", "items": schema_ref(item) });
+            schema
+        }
+    };
+    let error = |description: &str| {
+        json!({
+            "description": description,
+            "content": { "application/json": { "schema": schema_ref("Error") } },
+        })
+    };
+
+    let mut responses = json!({
+        "200": {
+            "description": "OK",
+            "content": { "application/json": { "schema": body } },
+        },
+        "400": error("A parameter is invalid"),
+        "500": { "description": "Database error" },
+    });
+    if endpoint.not_found {
+        responses["404"] = error("No such property");
+    }
+
+    json!({
+        "get": {
+            "summary": endpoint.summary,
+            "parameters": parameters,
+            "responses": responses,
+        }
+    })
+}
+
+fn openapi_type(field_type: FieldType) -> Value {
+    match field_type {
+        FieldType::Integer => json!({ "type": "integer", "format": "int32" }),
+        FieldType::Number => json!({ "type": "number", "format": "double" }),
+        FieldType::Decimal => json!({ "type": "string", "format": "decimal" }),
+        FieldType::String => json!({ "type": "string" }),
+        FieldType::Boolean => json!({ "type": "boolean" }),
+        FieldType::Date => json!({ "type": "string", "format": "date" }),
+        FieldType::Enum(values) => json!({ "type": "string", "enum": values }),
+    }
+}
+
+fn openapi_schema(resource: &Resource) -> Value {
+    object_schema(Some(resource.description), resource.fields)
+}
+
+fn object_schema(description: Option<&str>, fields: &[FieldDef]) -> Value {
+    let properties: Map<String, Value> = fields
+        .iter()
+        .map(|field| {
+            let mut property = openapi_type(field.field_type);
+            property["nullable"] = json!(field.nullable);
+            property["description"] = json!(field.description);
+            if let Some(unit) = field.unit {
+                property["x-unit"] = json!(unit);
+            }
+            (field.name.to_string(), property)
+        })
+        .collect();
+    let required: Vec<&str> = fields
+        .iter()
+        .filter(|field| !field.nullable)
+        .map(|field| field.name)
+        .collect();
+
+    let mut schema = json!({
+        "type": "object",
+        "required": required,
+        "properties": properties,
+    });
+    if let Some(description) = description {
+        schema["description"] = json!(description);
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::params::ParamError;
+    use crate::api::properties::{NetYield, PropertiesPage, Property, PropertyDetail, RatesSource};
+    use crate::api::rent_history::{RentHistory, RentPoint};
+    use crate::api::suburb_stats::{SuburbStatistics, SuburbStatisticsResponse};
+    use crate::ingestion::types::{DataQuality, PropertyType, State as AusState};
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::Router;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use serde::de::DeserializeOwned;
+    use std::collections::BTreeSet;
+    use tower::ServiceExt;
+
+    fn resource(name: &str) -> &'static Resource {
+        RESOURCES.iter().find(|r| r.name == name).unwrap()
+    }
+
+    fn documented(name: &str) -> BTreeSet<&'static str> {
+        resource(name).fields.iter().map(|f| f.name).collect()
+    }
+
+    fn serialized<T: Serialize>(value: &T) -> BTreeSet<String> {
+        serde_json::to_value(value)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    fn detail() -> PropertyDetail {
+        PropertyDetail {
+            id: 1,
+            address: "2/10 Smith Street".to_string(),
+            suburb: "Testville".to_string(),
+            state: AusState::NSW,
+            postcode: Some("2999".to_string()),
+            district: Some("001".to_string()),
+            district_name: Some("City of Sydney".to_string()),
+            property_type: Some(PropertyType::Unit),
+            bedrooms: Some(2),
+            price: Some(600_000),
+            sale_date: NaiveDate::from_ymd_opt(2024, 3, 1),
+            weekly_rent: Some(600),
+            price_per_sqm: None,
+            latitude: Some(Decimal::new(-33_8688, 4)),
+            longitude: Some(Decimal::new(151_2093, 4)),
+            rental_yield: Some(5.2),
+            annualized_growth_pct: Some(7.18),
+            data_source: Some("nsw_sales".to_string()),
+            data_quality: Some(DataQuality::Individual),
+            confidence_score: Some(Decimal::ONE),
+            external_id: Some("123".to_string()),
+            net_yield: None,
+        }
+    }
+
+    #[test]
+    fn test_every_property_field_documented() {
+        let documented: BTreeSet<String> = documented("property")
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let detail_fields = serialized(&detail());
+        assert_eq!(detail_fields, documented);
+
+        let list_item = Property {
+            id: 1,
+            address: String::new(),
+            suburb: String::new(),
+            state: AusState::NSW,
+            bedrooms: None,
+            price: None,
+            weekly_rent: None,
+            latitude: None,
+            longitude: None,
+            rental_yield: None,
+        };
+        let undocumented: Vec<String> = serialized(&list_item)
+            .difference(&documented)
+            .cloned()
+            .collect();
+        assert!(undocumented.is_empty(), "{:?}", undocumented);
+    }
+
+    #[test]
+    fn test_every_statistics_and_rent_field_documented() {
+        let stats = SuburbStatistics {
+            suburb: String::new(),
+            postcode: None,
+            state: AusState::NSW,
+            bedrooms: None,
+            median_price: None,
+            median_weekly_rent: None,
+            median_rental_yield: None,
+            min_yield: None,
+            max_yield: None,
+            property_count: None,
+            calculated_date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            sa3_code: None,
+            suppressed: false,
+            rolled_up_to: Some("12504".to_string()),
+        };
+        let rent = RentPoint {
+            period: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            median_rent: 600,
+            sample_size: None,
+        };
+
+        let names = |set: BTreeSet<&str>| set.into_iter().map(String::from).collect();
+        assert_eq!(serialized(&stats), names(documented("suburb_statistics")));
+        assert_eq!(serialized(&rent), names(documented("rental_median")));
+    }
+
+    #[test]
+    fn test_every_net_yield_field_documented() {
+        let net_yield = NetYield::calculate(
+            Some(600_000),
+            Some(600),
+            Default::default(),
+            RatesSource::Default,
+        )
+        .unwrap();
+        let documented: BTreeSet<String> = documented("net_yield")
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(serialized(&net_yield), documented);
+
+        let with_net_yield = PropertyDetail {
+            net_yield: Some(net_yield),
+            ..detail()
+        };
+        let extra: Vec<String> = serialized(&with_net_yield)
+            .difference(&serialized(&detail()))
+            .cloned()
+            .collect();
+        assert_eq!(extra, vec!["net_yield".to_string()]);
+    }
+
+    #[test]
+    fn test_enum_values_match_serialization() {
+        fn check<T: DeserializeOwned + Serialize>(field_type: FieldType) -> Vec<T> {
+            let FieldType::Enum(values) = field_type else {
+                panic!("not an enum");
+            };
+            values
+                .iter()
+                .map(|v| {
+                    let parsed: T = serde_json::from_value(json!(v)).unwrap();
+                    assert_eq!(serde_json::to_value(&parsed).unwrap(), json!(v));
+                    parsed
+                })
+                .collect()
+        }
+
+        assert_eq!(check::<AusState>(STATES), AusState::ALL.to_vec());
+        assert_eq!(check::<PropertyType>(PROPERTY_TYPES).len(), 6);
+        assert_eq!(check::<DataQuality>(DATA_QUALITIES).len(), 4);
+        let net_yield = resource("net_yield").fields;
+        let source = net_yield
+            .iter()
+            .find(|f| f.name == "council_rates_source")
+            .unwrap();
+        assert_eq!(check::<RatesSource>(source.field_type).len(), 3);
+    }
+
+    #[test]
+    fn test_dictionary_serialization() {
+        let fields = serde_json::to_value(resource("property").fields).unwrap();
+        let field = |name: &str| {
+            fields
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|f| f["name"] == name)
+                .unwrap()
+                .clone()
+        };
+
+        assert_eq!(
+            field("weekly_rent"),
+            json!({
+                "name": "weekly_rent",
+                "type": "integer",
+                "unit": "AUD/week",
+                "nullable": true,
+                "description": "Median weekly rent for the property's postcode and bedrooms",
+            })
+        );
+        assert_eq!(field("state")["type"], "enum");
+        assert_eq!(field("state")["values"][0], "NSW");
+        assert_eq!(field("rental_yield")["unit"], "percent");
+        assert!(field("id").get("unit").is_none());
+    }
+
+    #[test]
+    fn test_openapi_generated_from_dictionary() {
+        let document = openapi_document();
+        for resource in RESOURCES {
+            let schema = &document["components"]["schemas"][resource.schema];
+            assert_eq!(schema["description"], resource.description);
+            for field in resource.fields {
+                let property = &schema["properties"][field.name];
+                assert_eq!(property["description"], field.description, "{}", field.name);
+                assert_eq!(property["nullable"], field.nullable, "{}", field.name);
+            }
+        }
+
+        let property = &document["components"]["schemas"]["Property"];
+        assert_eq!(property["properties"]["price"]["x-unit"], "AUD");
+        assert_eq!(
+            property["properties"]["confidence_score"]["format"],
+            "decimal"
+        );
+        assert_eq!(property["properties"]["state"]["enum"][7], "NT");
+        let required = property["required"].as_array().unwrap();
+        assert!(required.contains(&json!("id")));
+        assert!(!required.contains(&json!("price")));
+    }
+
+    fn endpoint(path: &str) -> &'static Endpoint {
+        ENDPOINTS.iter().find(|e| e.path == path).unwrap()
+    }
+
+    /// Top-level keys a List endpoint documents
+    fn list_keys(path: &str) -> BTreeSet<String> {
+        let ResponseBody::List { key, fields, .. } = endpoint(path).body else {
+            panic!("{} is not a list", path);
+        };
+        fields
+            .iter()
+            .map(|f| f.name.to_string())
+            .chain([key.to_string()])
+            .collect()
+    }
+
+    #[test]
+    fn test_list_responses_documented() {
+        let page = PropertiesPage {
+            properties: Vec::new(),
+            page: 1,
+            page_size: 50,
+            total: 0,
+            total_pages: 0,
+            next_cursor: None,
+        };
+        let history = RentHistory {
+            property_id: 1,
+            state: AusState::NSW,
+            postcode: "2000".to_string(),
+            bedrooms: 2,
+            bedrooms_estimated: false,
+            yield_period: None,
+            points: Vec::new(),
+        };
+        let stats = SuburbStatisticsResponse { groups: Vec::new() };
+
+        assert_eq!(serialized(&page), list_keys("/api/properties"));
+        assert_eq!(
+            serialized(&history),
+            list_keys("/api/properties/{id}/rent-history")
+        );
+        assert_eq!(serialized(&stats), list_keys("/api/suburbs/stats"));
+    }
+
+    #[tokio::test]
+    async fn test_error_schema_matches_responses() {
+        let documented: BTreeSet<String> = ERROR_RESPONSE
+            .fields
+            .iter()
+            .map(|f| f.name.to_string())
+            .collect();
+        let response = ParamError::new("page", "too big").into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(serialized(&body), documented);
+    }
+
+    #[tokio::test]
+    async fn test_served_spec_describes_core_api() {
+        let app: Router = Router::new()
+            .route("/api/openapi.json", get(get_openapi))
+            .route("/api/docs", get(get_docs));
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get("/api/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let spec: Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(spec["openapi"], "3.0.3");
+        for path in [
+            "/api/properties",
+            "/api/properties/{id}",
+            "/api/suburbs/stats",
+        ] {
+            assert!(spec["paths"][path]["get"].is_object(), "{}", path);
+        }
+        let schemas = &spec["components"]["schemas"];
+        for schema in ["Property", "SuburbStatistics", "RentalMedian", "Error"] {
+            assert!(schemas[schema]["properties"].is_object(), "{}", schema);
+        }
+        let property = &schemas["Property"]["properties"];
+        assert_eq!(property["property_type"]["enum"][0], "House");
+        assert_eq!(property["state"]["enum"][0], "NSW");
+        assert_eq!(
+            schemas["Property"]["properties"]["bedrooms"]["nullable"],
+            true
+        );
+
+        // Every reference resolves
+        let text = serde_json::to_string(&spec).unwrap();
+        for reference in text.split("\"$ref\":\"#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(schemas[name].is_object(), "{}", name);
+        }
+
+        let by_id = &spec["paths"]["/api/properties/{id}"]["get"];
+        assert_eq!(by_id["parameters"][0]["in"], "path");
+        assert_eq!(by_id["parameters"][0]["required"], true);
+        assert_eq!(
+            by_id["responses"]["404"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/Error"
+        );
+        let list = &spec["paths"]["/api/properties"]["get"]["responses"]["200"]["content"]
+            ["application/json"]["schema"];
+        assert_eq!(
+            list["properties"]["properties"]["items"]["$ref"],
+            "#/components/schemas/Property"
+        );
+
+        let response = app
+            .oneshot(Request::get("/api/docs").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("/api/openapi.json"));
+    }
+}
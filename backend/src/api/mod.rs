@@ -0,0 +1,216 @@
+//! HTTP API module - routes and handlers served by the API server
+
+pub mod admin;
+#[cfg(feature = "admin-ui")]
+pub mod admin_ui;
+pub mod cache;
+pub mod calculators;
+pub mod changes;
+pub mod clusters;
+pub mod compression;
+pub mod cors;
+pub mod export;
+pub mod health;
+pub mod ingestion_watch;
+pub mod logging;
+pub mod map;
+pub mod meta;
+pub mod params;
+pub mod properties;
+pub mod quadrants;
+pub mod rate_limit;
+pub mod regions;
+pub mod rent_history;
+pub mod rental_medians;
+pub mod rentals;
+pub mod sales;
+pub mod saved_searches;
+pub mod search;
+pub mod server;
+pub mod share;
+pub mod stats;
+pub mod stats_refresh;
+pub mod suburb_stats;
+pub mod suburb_timeseries;
+pub mod suburbs;
+pub mod tier;
+pub mod yield_history;
+
+use crate::analytics::suppression::SuppressionConfig;
+use crate::api::admin::AdminConfig;
+use crate::api::cache::ResponseCaches;
+use crate::api::cors::CorsConfig;
+use crate::api::export::ExportBudget;
+use crate::api::map::MapConfig;
+use crate::api::properties::{ListingCacheConfig, TopYieldsConfig};
+use crate::api::rate_limit::RateLimiter;
+use crate::api::tier::TierConfig;
+use axum::middleware::from_fn_with_state;
+use axum::routing::{delete, get, post};
+use axum::Router;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Shared state passed to every handler
+#[derive(Clone)]
+pub struct AppState {
+    /// Primary database - used by every handler that writes
+    pub db: PgPool,
+    /// Used by GET handlers; a read replica when DATABASE_READ_URL is set,
+    /// otherwise the same pool as `db`
+    pub read_db: PgPool,
+    /// Small-sample suppression applied to every published aggregate
+    pub suppression: SuppressionConfig,
+    /// Limits how often each API key can create shared comparisons
+    pub share_limiter: Arc<RateLimiter>,
+    /// Stale-while-revalidate caches for the landing-page aggregates
+    pub caches: Arc<ResponseCaches>,
+    /// Admin API key and the raw archive used by admin tools
+    pub admin: Arc<AdminConfig>,
+    /// Row and byte caps for one CSV export response
+    pub export_budget: ExportBudget,
+    /// API keys' access tiers and the public tier's redaction rules
+    pub tiers: Arc<TierConfig>,
+    /// Cap on properties in one map viewport response
+    pub map: MapConfig,
+    /// Sanity ceiling on yields in the top-yields list
+    pub top_yields: TopYieldsConfig,
+    /// Cache-Control on property listings
+    pub listing_cache: ListingCacheConfig,
+}
+
+/// Header carrying the caller's API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Connect the read pool from DATABASE_READ_URL with `options`, or share the
+/// primary when unset
+pub async fn read_pool_from_env(
+    primary: &PgPool,
+    options: PgPoolOptions,
+) -> Result<PgPool, sqlx::Error> {
+    match std::env::var("DATABASE_READ_URL") {
+        Ok(url) if !url.trim().is_empty() => options.connect(&url).await,
+        _ => Ok(primary.clone()),
+    }
+}
+
+/// Routes implemented in the library (merged into the server router in main.rs),
+/// without a CORS policy
+pub fn router() -> Router<AppState> {
+    public_routes()
+        .merge(saved_search_routes())
+        .merge(admin_routes())
+}
+
+/// `router()` with the CORS policy applied: the public policy on public routes,
+/// which saved searches widen to their methods and client id header, and the
+/// admin policy and origin guard on admin routes
+pub fn router_with_cors(cors: &CorsConfig) -> Router<AppState> {
+    let guard = from_fn_with_state(Arc::new(cors.clone()), cors::admin_origin_guard);
+    public_routes()
+        .layer(cors.public_layer())
+        .merge(saved_search_routes().layer(cors.saved_search_layer()))
+        .merge(admin_routes().route_layer(guard).layer(cors.admin_layer()))
+}
+
+fn public_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/health", get(health::health_check))
+        .route(
+            "/api/calculators/break-even",
+            get(calculators::get_break_even),
+        )
+        .route("/api/changes", get(changes::get_changes))
+        .route("/api/meta/fields", get(meta::get_fields))
+        .route("/api/meta/openapi.json", get(meta::get_openapi))
+        .route("/api/openapi.json", get(meta::get_openapi))
+        .route("/api/docs", get(meta::get_docs))
+        .route("/api/sales", get(sales::get_sales))
+        .route("/api/stats", get(stats::get_stats))
+        .route("/api/properties", get(properties::get_properties))
+        .route(
+            "/api/properties/changes",
+            get(properties::get_property_changes),
+        )
+        .route("/api/properties/clusters", get(clusters::get_clusters))
+        .route("/api/properties/map", get(map::get_map_properties))
+        .route("/api/properties/search", get(search::search_properties))
+        .route(
+            "/api/properties/top-yields",
+            get(properties::get_top_yield_properties),
+        )
+        .route("/api/properties/:id", get(properties::get_property_by_id))
+        .route(
+            "/api/properties/:id/rent-history",
+            get(rent_history::get_rent_history),
+        )
+        .route(
+            "/api/properties/:id/yield-history",
+            get(yield_history::get_yield_history),
+        )
+        .route("/api/rentals/observations", get(rentals::get_observations))
+        .route(
+            "/api/rental-medians",
+            get(rental_medians::get_median_history),
+        )
+        .route(
+            "/api/rental-medians/latest",
+            get(rental_medians::get_latest_medians),
+        )
+        .route("/api/regions", get(regions::get_regions))
+        .route("/api/suburbs", get(suburbs::get_suburbs))
+        .route("/api/suburbs/quadrants", get(quadrants::get_quadrants))
+        .route("/api/suburbs/top-yields", get(stats::get_top_yields))
+        .route(
+            "/api/suburbs/stats",
+            get(suburb_stats::get_suburb_statistics),
+        )
+        .route(
+            "/api/suburbs/:suburb/timeseries",
+            get(suburb_timeseries::get_suburb_timeseries),
+        )
+        .route("/api/share", post(share::create_share))
+        .route("/api/share/:token", get(share::get_share))
+}
+
+fn saved_search_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/searches",
+            get(saved_searches::list_searches).post(saved_searches::create_search),
+        )
+        .route("/api/searches/:id", delete(saved_searches::delete_search))
+        .route(
+            "/api/searches/:id/results",
+            get(saved_searches::get_search_results),
+        )
+}
+
+fn admin_routes() -> Router<AppState> {
+    let router = Router::new()
+        .route(
+            "/api/admin/properties/:id/refresh",
+            post(admin::refresh_property),
+        )
+        .route("/api/admin/ingestion/runs", get(admin::list_ingestion_runs))
+        .route(
+            "/api/admin/ingestion/runs/:id",
+            get(admin::get_ingestion_run),
+        )
+        .route("/api/admin/quality", get(admin::get_quality_report))
+        .route("/api/admin/staging", get(admin::list_staging_batches))
+        .route(
+            "/api/admin/staging/:id/approve",
+            post(admin::approve_staging_batch),
+        )
+        .route(
+            "/api/admin/staging/:id/reject",
+            post(admin::reject_staging_batch),
+        );
+
+    #[cfg(feature = "admin-ui")]
+    let router = router.merge(admin_ui::router());
+
+    router
+}
@@ -0,0 +1,404 @@
+//! Query parameter validation shared by list-style endpoints
+//! Rejects hostile or malformed query strings with a 400 naming the offending
+//! field, before anything reaches the database
+
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+/// Longest raw query string accepted
+pub const MAX_QUERY_LENGTH: usize = 4096;
+
+/// Longest single raw value accepted, before per-field limits apply
+pub const MAX_VALUE_LENGTH: usize = 1024;
+
+/// Most parameters accepted in one query string
+pub const MAX_PARAMS: usize = 32;
+
+/// Times a single parameter may appear (all current params are scalar)
+pub const MAX_REPEATS: usize = 1;
+
+/// Default cap for free-text filters such as suburb names
+pub const MAX_STRING_LENGTH: usize = 100;
+
+/// Structured 400 response naming the parameter that failed validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParamError {
+    pub field: String,
+    pub message: String,
+}
+
+impl ParamError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        ParamError {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ParamError {
+    fn into_response(self) -> Response {
+        #[derive(Serialize)]
+        struct Body {
+            error: &'static str,
+            field: String,
+            message: String,
+        }
+
+        let body = Body {
+            error: "invalid_parameter",
+            field: self.field,
+            message: self.message,
+        };
+        (StatusCode::BAD_REQUEST, Json(body)).into_response()
+    }
+}
+
+/// Per-endpoint checks run after the query string has been deserialized
+pub trait ValidateParams {
+    fn validate(&self) -> Result<(), ParamError>;
+}
+
+/// Query extractor that checks the raw query string, deserializes it into `T`,
+/// and then runs `T::validate`
+#[derive(Debug, Clone)]
+pub struct ValidatedListParams<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedListParams<T>
+where
+    T: DeserializeOwned + ValidateParams,
+    S: Send + Sync,
+{
+    type Rejection = ParamError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        check_query_string(query)?;
+
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        let params: T = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let message = e.inner().to_string();
+            let field = match e.path().to_string().as_str() {
+                "." => field_from_message(&message).unwrap_or("query").to_string(),
+                path => path.to_string(),
+            };
+            ParamError::new(field, message)
+        })?;
+
+        params.validate()?;
+        Ok(ValidatedListParams(params))
+    }
+}
+
+/// Structural checks on the raw query string: length, parameter count,
+/// repeated keys, and that every key/value decodes to clean UTF-8
+pub fn check_query_string(query: &str) -> Result<(), ParamError> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for (i, (key, value)) in form_urlencoded::parse(query.as_bytes()).enumerate() {
+        if i >= MAX_PARAMS {
+            return Err(ParamError::new(
+                "query",
+                format!("more than {} parameters", MAX_PARAMS),
+            ));
+        }
+
+        if key.len() > MAX_VALUE_LENGTH {
+            return Err(ParamError::new(
+                "query",
+                format!("parameter name longer than {} bytes", MAX_VALUE_LENGTH),
+            ));
+        }
+
+        if value.len() > MAX_VALUE_LENGTH {
+            return Err(ParamError::new(
+                key.to_string(),
+                format!("longer than {} bytes", MAX_VALUE_LENGTH),
+            ));
+        }
+
+        // Invalid UTF-8 is decoded lossily to U+FFFD
+        for part in [&key, &value] {
+            if part.contains('\u{FFFD}') || part.chars().any(char::is_control) {
+                return Err(ParamError::new(
+                    key.to_string(),
+                    "invalid percent-encoding or control characters",
+                ));
+            }
+        }
+
+        let count = seen.entry(key.to_string()).or_insert(0);
+        *count += 1;
+        if *count > MAX_REPEATS {
+            return Err(ParamError::new(key.to_string(), "parameter repeated"));
+        }
+    }
+
+    if query.len() > MAX_QUERY_LENGTH {
+        return Err(ParamError::new(
+            "query",
+            format!("query string longer than {} bytes", MAX_QUERY_LENGTH),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Most values one comma-separated list parameter may hold
+pub const MAX_LIST_VALUES: usize = 50;
+
+/// The comma-separated values of a list parameter such as `state=NSW,QLD`,
+/// each trimmed and parsed by `parse`; None when the parameter is absent
+/// The error names the first value `parse` rejects, with its reason.
+pub fn parse_list<T>(
+    field: &str,
+    value: Option<&str>,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Option<Vec<T>>, ParamError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let items: Vec<&str> = value.split(',').map(str::trim).collect();
+    if items.len() > MAX_LIST_VALUES {
+        return Err(ParamError::new(
+            field,
+            format!("more than {} values", MAX_LIST_VALUES),
+        ));
+    }
+
+    items
+        .into_iter()
+        .map(|item| {
+            if item.is_empty() {
+                return Err(ParamError::new(field, "contains an empty value"));
+            }
+            parse(item).map_err(|reason| ParamError::new(field, reason))
+        })
+        .collect::<Result<Vec<T>, ParamError>>()
+        .map(Some)
+}
+
+/// Reject strings longer than `max` characters
+pub fn check_length(field: &str, value: Option<&str>, max: usize) -> Result<(), ParamError> {
+    match value {
+        Some(value) if value.chars().count() > max => Err(ParamError::new(
+            field,
+            format!("must be at most {} characters", max),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reject numbers outside `range`
+pub fn check_range<T>(
+    field: &str,
+    value: Option<T>,
+    range: RangeInclusive<T>,
+) -> Result<(), ParamError>
+where
+    T: PartialOrd + Display,
+{
+    match value {
+        Some(value) if !range.contains(&value) => Err(ParamError::new(
+            field,
+            format!("must be between {} and {}", range.start(), range.end()),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reject NaN/infinite floats as well as values outside `range`
+pub fn check_finite_range(
+    field: &str,
+    value: Option<f64>,
+    range: RangeInclusive<f64>,
+) -> Result<(), ParamError> {
+    match value {
+        Some(value) if !value.is_finite() => Err(ParamError::new(field, "must be a finite number")),
+        _ => check_range(field, value, range),
+    }
+}
+
+/// Pull the field name out of serde messages like "missing field `state`"
+fn field_from_message(message: &str) -> Option<&str> {
+    let start = message.find('`')? + 1;
+    let end = start + message[start..].find('`')?;
+    Some(&message[start..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_state;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_check_query_string() {
+        assert!(check_query_string("state=NSW&suburb=Box%20Hill").is_ok());
+        assert!(check_query_string("").is_ok());
+
+        let repeated = check_query_string("state=NSW&state=VIC").unwrap_err();
+        assert_eq!(repeated.field, "state");
+
+        let bad_utf8 = check_query_string("suburb=%FF%FE").unwrap_err();
+        assert_eq!(bad_utf8.field, "suburb");
+
+        let nul = check_query_string("suburb=a%00b").unwrap_err();
+        assert_eq!(nul.field, "suburb");
+
+        let many: Vec<String> = (0..=MAX_PARAMS).map(|i| format!("p{}=1", i)).collect();
+        assert_eq!(
+            check_query_string(&many.join("&")).unwrap_err().field,
+            "query"
+        );
+
+        let long_value = format!("suburb={}", "a".repeat(MAX_VALUE_LENGTH + 1));
+        assert_eq!(check_query_string(&long_value).unwrap_err().field, "suburb");
+
+        let long: Vec<String> = (0..10)
+            .map(|i| format!("p{}={}", i, "a".repeat(500)))
+            .collect();
+        assert_eq!(
+            check_query_string(&long.join("&")).unwrap_err().field,
+            "query"
+        );
+    }
+
+    #[test]
+    fn test_checks() {
+        assert!(check_length("suburb", Some("Box Hill"), 10).is_ok());
+        assert_eq!(
+            check_length("suburb", Some("Box Hill North"), 10)
+                .unwrap_err()
+                .field,
+            "suburb"
+        );
+
+        assert!(check_range("limit", Some(10), 1..=100).is_ok());
+        assert!(check_range("limit", Some(0), 1..=100).is_err());
+        assert!(check_range::<i64>("limit", None, 1..=100).is_ok());
+
+        assert!(check_finite_range("yield", Some(5.0), 0.0..=100.0).is_ok());
+        assert!(check_finite_range("yield", Some(f64::NAN), 0.0..=100.0).is_err());
+        assert!(check_finite_range("yield", Some(1e308), 0.0..=100.0).is_err());
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let digits = |v: &str| {
+            v.parse::<u32>()
+                .map_err(|_| format!("'{}' is not a number", v))
+        };
+
+        assert_eq!(parse_list("n", None, digits), Ok(None));
+        assert_eq!(
+            parse_list("n", Some("1, 2 ,3"), digits),
+            Ok(Some(vec![1, 2, 3]))
+        );
+        assert_eq!(parse_list("n", Some("7"), digits), Ok(Some(vec![7])));
+
+        for (value, message) in [
+            ("1,x,3", "'x' is not a number"),
+            ("1,,3", "contains an empty value"),
+            ("", "contains an empty value"),
+            (&"1,".repeat(MAX_LIST_VALUES + 1), "more than 50 values"),
+        ] {
+            assert_eq!(
+                parse_list("n", Some(value), digits),
+                Err(ParamError::new("n", message)),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_field_from_message() {
+        assert_eq!(field_from_message("missing field `state`"), Some("state"));
+        assert_eq!(field_from_message("invalid digit found in string"), None);
+    }
+
+    /// Status and body for a request against the library router, with a
+    /// database that can't be reached - validation must fail before any query
+    async fn request(uri: &str) -> (StatusCode, Value) {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = crate::api::router().with_state(test_state(db));
+
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_hostile_inputs_rejected_with_400() {
+        let long_suburb = "a".repeat(50 * 1024);
+        let cases: Vec<(String, &str)> = vec![
+            ("/api/sales?limit=4294967295".into(), "limit"),
+            ("/api/sales?limit=-1".into(), "limit"),
+            ("/api/sales?limit=99999999999999999999999".into(), "limit"),
+            ("/api/sales?limit=abc".into(), "limit"),
+            (format!("/api/sales?suburb={}", long_suburb), "suburb"),
+            (format!("/api/sales?suburb={}", "a".repeat(500)), "suburb"),
+            ("/api/sales?suburb=%FF%FE%FD".into(), "suburb"),
+            ("/api/sales?suburb=%25%00%25".into(), "suburb"),
+            ("/api/sales?postcode=20000000000000".into(), "postcode"),
+            ("/api/sales?state=NSW&state=NSW".into(), "state"),
+            ("/api/sales?state=ZZZ".into(), "state"),
+            ("/api/sales?sold_after=2024-13-45".into(), "sold_after"),
+            (format!("/api/sales?cursor={}", "9".repeat(1000)), "cursor"),
+            ("/api/sales?format=xml".into(), "format"),
+            ("/api/suburbs/quadrants".into(), "state"),
+            (
+                "/api/suburbs/quadrants?state=NSW&bedrooms=4294967295".into(),
+                "bedrooms",
+            ),
+            (
+                "/api/suburbs/quadrants?state=NSW&bedrooms=-3".into(),
+                "bedrooms",
+            ),
+            (
+                "/api/suburbs/quadrants?state=NSW&yield_threshold=1e308".into(),
+                "yield_threshold",
+            ),
+            (
+                "/api/suburbs/quadrants?state=NSW&yield_threshold=NaN".into(),
+                "yield_threshold",
+            ),
+            (
+                "/api/suburbs/quadrants?state=NSW&growth_threshold=-inf".into(),
+                "growth_threshold",
+            ),
+        ];
+
+        for (uri, field) in cases {
+            let (status, body) = request(&uri).await;
+            let shown = &uri[..uri.len().min(80)];
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", shown);
+            assert_eq!(body["error"], "invalid_parameter", "{}", shown);
+            assert_eq!(body["field"], field, "{}", shown);
+        }
+    }
+}
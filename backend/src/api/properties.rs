@@ -0,0 +1,2993 @@
+//! Properties endpoints - one page of properties with their rental yields,
+//! optionally filtered by location, size, price, rent and yield and sorted by
+//! one of a fixed set of columns, and a single property in full detail
+
+use crate::analytics::council_rates::council_rates_for_property;
+use crate::api::params::{
+    check_length, check_range, parse_list, ParamError, ValidateParams, ValidatedListParams,
+    MAX_STRING_LENGTH,
+};
+use crate::api::regions;
+use crate::api::tier::{Access, Redact, Redactor, Tier};
+use crate::api::{AppState, API_KEY_HEADER};
+use crate::format::round_yield_f64;
+use crate::format::{round_yield_for_display, YIELD_DISPLAY_DP};
+use crate::ingestion::districts::{district_name, normalize_district};
+use crate::ingestion::types::{DataQuality, PropertyType, RentalMatchMethod, State as AusState};
+use crate::{
+    calculate_annualized_growth, calculate_net_yield, calculate_rental_yield, AnnualExpenses,
+};
+use axum::extract::rejection::PathRejection;
+use axum::extract::{Path, State};
+use axum::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH, VARY};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryAs;
+use sqlx::{PgPool, Postgres};
+use std::env;
+use tracing::error;
+
+/// Page size when none is requested
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Largest page a client can request
+pub const MAX_PAGE_SIZE: i64 = 500;
+
+/// Highest page number accepted, so the offset can't overflow
+pub const MAX_PAGE: i64 = i64::MAX / MAX_PAGE_SIZE;
+
+/// Highest bedroom count accepted as a filter
+const MAX_BEDROOMS: i32 = 50;
+
+/// Length of the top-yields list when none is requested
+pub const DEFAULT_TOP_YIELDS_LIMIT: i64 = 50;
+
+/// Longest top-yields list a client can request
+pub const MAX_TOP_YIELDS_LIMIT: i64 = 500;
+
+/// Highest yield (%) believed by default; anything above is taken as a parse error
+pub const DEFAULT_MAX_PLAUSIBLE_YIELD: i64 = 30;
+
+/// Top-yields sanity ceiling, loaded from environment variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TopYieldsConfig {
+    /// Yields (%) above this are left out of the list
+    pub max_yield: Decimal,
+}
+
+impl Default for TopYieldsConfig {
+    fn default() -> Self {
+        TopYieldsConfig {
+            max_yield: Decimal::from(DEFAULT_MAX_PLAUSIBLE_YIELD),
+        }
+    }
+}
+
+impl TopYieldsConfig {
+    /// Ceiling from TOP_YIELDS_MAX_YIELD (%)
+    pub fn from_env() -> Self {
+        let defaults = TopYieldsConfig::default();
+        TopYieldsConfig {
+            max_yield: env::var("TOP_YIELDS_MAX_YIELD")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|y: &Decimal| *y > Decimal::ZERO)
+                .unwrap_or(defaults.max_yield),
+        }
+    }
+}
+
+/// Seconds clients may reuse a property listing by default
+pub const DEFAULT_LISTING_MAX_AGE_SECS: u64 = 300;
+
+/// Caching headers on GET /api/properties, loaded from environment variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListingCacheConfig {
+    /// Sent as Cache-Control max-age
+    pub max_age_secs: u64,
+}
+
+impl Default for ListingCacheConfig {
+    fn default() -> Self {
+        ListingCacheConfig {
+            max_age_secs: DEFAULT_LISTING_MAX_AGE_SECS,
+        }
+    }
+}
+
+impl ListingCacheConfig {
+    /// Max age from PROPERTIES_CACHE_MAX_AGE (seconds)
+    pub fn from_env() -> Self {
+        let defaults = ListingCacheConfig::default();
+        ListingCacheConfig {
+            max_age_secs: env::var("PROPERTIES_CACHE_MAX_AGE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.max_age_secs),
+        }
+    }
+
+    fn cache_control(&self) -> HeaderValue {
+        HeaderValue::from_str(&format!("public, max-age={}", self.max_age_secs))
+            .expect("digits and punctuation are a valid header")
+    }
+}
+
+/// Query parameters for GET /api/properties, also stored as a saved search's filter
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PropertiesQuery {
+    /// SA3 code, SA4 code or SA3 name, resolved to postcodes via the regions lookup
+    pub region: Option<String>,
+    /// Case-insensitive; comma-separate several, as for postcode and state
+    pub suburb: Option<String>,
+    /// 4-digit postcodes, e.g. 2000,2026
+    pub postcode: Option<String>,
+    /// NSW Valuer General district codes, e.g. 001,033
+    pub district: Option<String>,
+    /// e.g. NSW,QLD
+    pub state: Option<String>,
+    pub bedrooms: Option<i32>,
+    pub min_price: Option<i64>,
+    pub max_price: Option<i64>,
+    pub min_weekly_rent: Option<i32>,
+    pub max_weekly_rent: Option<i32>,
+    /// Percent; matches yields that display at or above it
+    pub min_yield: Option<Decimal>,
+    /// Defaults to id
+    pub sort: Option<SortField>,
+    /// Defaults to ascending
+    pub order: Option<SortOrder>,
+    /// 1-based; defaults to the first page
+    pub page: Option<i64>,
+    /// Defaults to DEFAULT_PAGE_SIZE
+    pub page_size: Option<i64>,
+    /// `next_cursor` from the previous page; reads stay consistent while
+    /// ingestion writes, so this is the way to read the whole list. Only with
+    /// sort=id or rental_yield, and not with `page`.
+    pub cursor: Option<String>,
+}
+
+impl ValidateParams for PropertiesQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("region", self.region.as_deref(), MAX_STRING_LENGTH)?;
+        self.suburbs()?;
+        self.postcodes()?;
+        self.districts()?;
+        self.states()?;
+        check_range("bedrooms", self.bedrooms, 0..=MAX_BEDROOMS)?;
+        check_range("min_price", self.min_price, 0..=i64::MAX)?;
+        check_range("max_price", self.max_price, 0..=i64::MAX)?;
+        check_range("min_weekly_rent", self.min_weekly_rent, 0..=i32::MAX)?;
+        check_range("max_weekly_rent", self.max_weekly_rent, 0..=i32::MAX)?;
+        check_range(
+            "min_yield",
+            self.min_yield,
+            Decimal::ZERO..=Decimal::ONE_HUNDRED,
+        )?;
+        check_ordered("min_price", self.min_price, "max_price", self.max_price)?;
+        check_ordered(
+            "min_weekly_rent",
+            self.min_weekly_rent,
+            "max_weekly_rent",
+            self.max_weekly_rent,
+        )?;
+        check_range("page", self.page, 1..=MAX_PAGE)?;
+        check_range("page_size", self.page_size, 1..=MAX_PAGE_SIZE)?;
+
+        if let Some(cursor) = &self.cursor {
+            let cursor = PropertyCursor::decode(cursor)
+                .ok_or_else(|| ParamError::new("cursor", "not a valid cursor"))?;
+            if !self.sort().supports_cursor() {
+                return Err(ParamError::new(
+                    "sort",
+                    "must be id or rental_yield with a cursor",
+                ));
+            }
+            if (cursor.sort, cursor.order) != (self.sort(), self.order()) {
+                return Err(ParamError::new(
+                    "cursor",
+                    "was issued for a different sort or order",
+                ));
+            }
+            if self.page.is_some() {
+                return Err(ParamError::new("page", "can't be combined with cursor"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PropertiesQuery {
+    /// Public callers see banded prices, so they can't filter or sort by the
+    /// exact price, or by the yield worked from it, to narrow it down. Yield
+    /// cursors carry the exact yield, so they're refused too.
+    pub fn check_tier(&self, tier: Tier) -> Result<(), ParamError> {
+        if tier == Tier::Full {
+            return Ok(());
+        }
+        let needs_key = |field: &str| Err(ParamError::new(field, "needs an API key"));
+        if self.min_price.is_some() {
+            return needs_key("min_price");
+        }
+        if self.max_price.is_some() {
+            return needs_key("max_price");
+        }
+        if self.min_yield.is_some() {
+            return needs_key("min_yield");
+        }
+        if self.sort() == SortField::Price {
+            return needs_key("sort");
+        }
+        if self.cursor.is_some() && self.sort() == SortField::RentalYield {
+            return needs_key("cursor");
+        }
+        Ok(())
+    }
+}
+
+/// Reject a lower bound above its upper bound
+fn check_ordered<T: PartialOrd>(
+    min_field: &str,
+    min: Option<T>,
+    max_field: &str,
+    max: Option<T>,
+) -> Result<(), ParamError> {
+    match (min, max) {
+        (Some(min), Some(max)) if min > max => Err(ParamError::new(
+            min_field,
+            format!("must not be greater than {}", max_field),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Columns the list can be sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    Id,
+    RentalYield,
+    Price,
+    WeeklyRent,
+    SaleDate,
+    Suburb,
+}
+
+impl SortField {
+    /// The ORDER BY expression; only these fixed strings reach the SQL
+    /// The yield is derived as in FILTER_SQL, NULL when price or rent is missing
+    fn sql(self) -> &'static str {
+        match self {
+            SortField::Id => "id",
+            SortField::RentalYield => {
+                "CASE WHEN price > 0 THEN ROUND(weekly_rent::numeric * 5200 / price, 4) END"
+            }
+            SortField::Price => "price",
+            SortField::WeeklyRent => "weekly_rent",
+            SortField::SaleDate => "sale_date",
+            SortField::Suburb => "suburb",
+        }
+    }
+
+    /// Whether pages in this order can be read by cursor
+    pub fn supports_cursor(self) -> bool {
+        matches!(self, SortField::Id | SortField::RentalYield)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// ORDER BY clause for a page; missing values sort last in either direction,
+/// and id breaks ties so pages don't overlap
+fn order_by_sql(sort: SortField, order: SortOrder) -> String {
+    let direction = match order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    };
+    match sort {
+        SortField::Id => format!("ORDER BY id {}", direction),
+        _ => format!("ORDER BY {} {} NULLS LAST, id", sort.sql(), direction),
+    }
+}
+
+/// Keyset cursor - the sort and position of the last property on a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropertyCursor {
+    pub sort: SortField,
+    pub order: SortOrder,
+    /// The last property's derived yield with sort=rental_yield; None once
+    /// the page reached the properties without one, which sort last
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rental_yield: Option<Decimal>,
+    pub id: i32,
+}
+
+impl PropertyCursor {
+    /// URL-safe base64 of the cursor's JSON
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("cursor serializes");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+/// Condition selecting the properties after a cursor, in the order of
+/// `order_by_sql`. Binds $14 (the yield, for sort=rental_yield) and then the id.
+fn keyset_sql(sort: SortField, order: SortOrder) -> String {
+    let after = match order {
+        SortOrder::Asc => ">",
+        SortOrder::Desc => "<",
+    };
+    match sort {
+        SortField::RentalYield => {
+            let y = sort.sql();
+            // Ties and the null-yield tail are ordered by ascending id
+            format!(
+                r#"
+                AND CASE
+                    WHEN $14::numeric IS NULL THEN {y} IS NULL AND id > $15
+                    ELSE {y} {after} $14 OR ({y} = $14 AND id > $15) OR {y} IS NULL
+                END
+                "#,
+            )
+        }
+        _ => format!("AND id {} $14", after),
+    }
+}
+
+/// Filters on the properties listed, all optional
+#[derive(Debug, Clone, Default)]
+pub struct PropertyFilter {
+    /// Postcodes the region resolved to
+    pub region_postcodes: Option<Vec<String>>,
+    /// Lowercase
+    pub suburbs: Option<Vec<String>>,
+    pub postcodes: Option<Vec<String>>,
+    pub districts: Option<Vec<String>>,
+    pub states: Option<Vec<AusState>>,
+    pub bedrooms: Option<i32>,
+    pub min_price: Option<i64>,
+    pub max_price: Option<i64>,
+    pub min_weekly_rent: Option<i32>,
+    pub max_weekly_rent: Option<i32>,
+    pub min_yield: Option<Decimal>,
+}
+
+impl From<&PropertiesQuery> for PropertyFilter {
+    fn from(query: &PropertiesQuery) -> Self {
+        // Lists are checked by PropertiesQuery::validate
+        PropertyFilter {
+            region_postcodes: None,
+            suburbs: query.suburbs().ok().flatten(),
+            postcodes: query.postcodes().ok().flatten(),
+            districts: query.districts().ok().flatten(),
+            states: query.states().ok().flatten(),
+            bedrooms: query.bedrooms,
+            min_price: query.min_price,
+            max_price: query.max_price,
+            min_weekly_rent: query.min_weekly_rent,
+            max_weekly_rent: query.max_weekly_rent,
+            min_yield: query.min_yield,
+        }
+    }
+}
+
+impl PropertyFilter {
+    /// Lowest yield, at stored precision, that displays at or above `min_yield`
+    /// Displayed yields round half away from zero, so 4.995 shows as 5.00
+    pub fn yield_floor(&self) -> Option<Decimal> {
+        let min = self
+            .min_yield?
+            .round_dp_with_strategy(YIELD_DISPLAY_DP, RoundingStrategy::ToPositiveInfinity);
+        Some(min - Decimal::new(5, YIELD_DISPLAY_DP + 1))
+    }
+}
+
+/// WHERE clause for the filter, bound by `bind_filter` in the same order
+/// The yield is derived from price and rent exactly as the response derives it
+/// (calculate_rental_yield at stored precision), not read from the stored column
+const FILTER_SQL: &str = r#"
+    WHERE ($1::text[] IS NULL OR postcode = ANY($1))
+      AND ($2::text[] IS NULL OR LOWER(suburb) = ANY($2))
+      AND ($3::text[] IS NULL OR postcode = ANY($3))
+      AND ($4::state_enum[] IS NULL OR state = ANY($4))
+      AND ($5::int IS NULL OR bedrooms = $5)
+      AND ($6::bigint IS NULL OR price >= $6)
+      AND ($7::bigint IS NULL OR price <= $7)
+      AND ($8::int IS NULL OR weekly_rent >= $8)
+      AND ($9::int IS NULL OR weekly_rent <= $9)
+      AND ($10::numeric IS NULL OR (
+          price > 0 AND ROUND(weekly_rent::numeric * 5200 / price, 4) >= $10
+      ))
+      AND ($11::text[] IS NULL OR district = ANY($11))
+"#;
+
+fn bind_filter<'q, O>(
+    query: QueryAs<'q, Postgres, O, PgArguments>,
+    filter: &'q PropertyFilter,
+) -> QueryAs<'q, Postgres, O, PgArguments> {
+    query
+        .bind(filter.region_postcodes.as_deref())
+        .bind(filter.suburbs.as_deref())
+        .bind(filter.postcodes.as_deref())
+        .bind(filter.states.as_deref())
+        .bind(filter.bedrooms)
+        .bind(filter.min_price)
+        .bind(filter.max_price)
+        .bind(filter.min_weekly_rent)
+        .bind(filter.max_weekly_rent)
+        .bind(filter.yield_floor())
+        .bind(filter.districts.as_deref())
+}
+
+impl PropertiesQuery {
+    /// Suburbs asked for, lowercased; None when unfiltered
+    pub fn suburbs(&self) -> Result<Option<Vec<String>>, ParamError> {
+        parse_list("suburb", self.suburb.as_deref(), |suburb| {
+            if suburb.chars().count() > MAX_STRING_LENGTH {
+                return Err(format!(
+                    "'{}' is longer than {} characters",
+                    suburb, MAX_STRING_LENGTH
+                ));
+            }
+            Ok(suburb.to_lowercase())
+        })
+    }
+
+    /// Postcodes asked for; None when unfiltered
+    pub fn postcodes(&self) -> Result<Option<Vec<String>>, ParamError> {
+        parse_list("postcode", self.postcode.as_deref(), |postcode| {
+            if postcode.len() != 4 || !postcode.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(format!("'{}' is not a 4-digit postcode", postcode));
+            }
+            Ok(postcode.to_string())
+        })
+    }
+
+    /// District codes asked for, as stored ("1" is "001"); None when unfiltered
+    pub fn districts(&self) -> Result<Option<Vec<String>>, ParamError> {
+        parse_list("district", self.district.as_deref(), |code| {
+            normalize_district(code).ok_or_else(|| format!("'{}' is not a district code", code))
+        })
+    }
+
+    /// States asked for; None when unfiltered
+    pub fn states(&self) -> Result<Option<Vec<AusState>>, ParamError> {
+        parse_list("state", self.state.as_deref(), str::parse)
+    }
+
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1)
+    }
+
+    pub fn page_size(&self) -> i64 {
+        self.page_size.unwrap_or(DEFAULT_PAGE_SIZE)
+    }
+
+    pub fn sort(&self) -> SortField {
+        self.sort.unwrap_or_default()
+    }
+
+    pub fn order(&self) -> SortOrder {
+        self.order.unwrap_or_default()
+    }
+
+    /// Rows skipped before this page
+    pub fn offset(&self) -> i64 {
+        (self.page() - 1) * self.page_size()
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PropertyRow {
+    id: i32,
+    address: String,
+    suburb: String,
+    state: AusState,
+    bedrooms: Option<i32>,
+    price: Option<i64>,
+    weekly_rent: Option<i32>,
+    latitude: Option<Decimal>,
+    longitude: Option<Decimal>,
+    /// As sorted on, for the cursor
+    sort_yield: Option<Decimal>,
+}
+
+impl PropertyRow {
+    fn cursor(&self, sort: SortField, order: SortOrder) -> PropertyCursor {
+        PropertyCursor {
+            sort,
+            order,
+            rental_yield: match sort {
+                SortField::RentalYield => self.sort_yield,
+                _ => None,
+            },
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Property {
+    pub id: i32,
+    pub address: String,
+    pub suburb: String,
+    pub state: AusState,
+    pub bedrooms: Option<i32>,
+    pub price: Option<i64>,
+    pub weekly_rent: Option<i32>,
+    pub latitude: Option<Decimal>,
+    pub longitude: Option<Decimal>,
+    pub rental_yield: Option<f64>,
+}
+
+impl Redact for Property {
+    fn redact(&mut self, redactor: &Redactor) {
+        redactor.address(&mut self.address);
+        redactor.optional_price(&mut self.price);
+        self.rental_yield = redactor
+            .rental_yield(self.price, self.weekly_rent)
+            .and_then(|y| y.to_f64());
+        redactor.decimal_coordinate(&mut self.latitude);
+        redactor.decimal_coordinate(&mut self.longitude);
+    }
+}
+
+/// Yield as displayed, when both price and rent are known
+fn display_yield(price: Option<i64>, weekly_rent: Option<i32>) -> Option<f64> {
+    match (price, weekly_rent) {
+        (Some(price), Some(rent)) => calculate_rental_yield(price, rent)
+            .map(round_yield_for_display)
+            .and_then(|y| y.to_f64()),
+        _ => None,
+    }
+}
+
+impl From<PropertyRow> for Property {
+    fn from(p: PropertyRow) -> Self {
+        let rental_yield = display_yield(p.price, p.weekly_rent);
+
+        Property {
+            id: p.id,
+            address: p.address,
+            suburb: p.suburb,
+            state: p.state,
+            bedrooms: p.bedrooms,
+            price: p.price,
+            weekly_rent: p.weekly_rent,
+            latitude: p.latitude,
+            longitude: p.longitude,
+            rental_yield,
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PropertyDetailRow {
+    id: i32,
+    address: String,
+    suburb: String,
+    state: AusState,
+    postcode: Option<String>,
+    district: Option<String>,
+    property_type: Option<PropertyType>,
+    bedrooms: Option<i32>,
+    price: Option<i64>,
+    sale_date: Option<NaiveDate>,
+    weekly_rent: Option<i32>,
+    price_per_sqm: Option<Decimal>,
+    latitude: Option<Decimal>,
+    longitude: Option<Decimal>,
+    data_source: Option<String>,
+    data_quality: Option<DataQuality>,
+    confidence_score: Option<Decimal>,
+    external_id: Option<String>,
+}
+
+/// Response for GET /api/properties/:id - the list fields plus where the row
+/// came from and how much it can be trusted
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PropertyDetail {
+    pub id: i32,
+    pub address: String,
+    pub suburb: String,
+    pub state: AusState,
+    pub postcode: Option<String>,
+    /// NSW Valuer General district code
+    pub district: Option<String>,
+    /// The district's council, or its code when the name isn't known
+    pub district_name: Option<String>,
+    pub property_type: Option<PropertyType>,
+    pub bedrooms: Option<i32>,
+    pub price: Option<i64>,
+    pub sale_date: Option<NaiveDate>,
+    pub weekly_rent: Option<i32>,
+    pub price_per_sqm: Option<Decimal>,
+    pub latitude: Option<Decimal>,
+    pub longitude: Option<Decimal>,
+    pub rental_yield: Option<f64>,
+    /// Compound annual growth from the first recorded sale to the last; only
+    /// on GET /api/properties/:id, and only with sales far enough apart
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annualized_growth_pct: Option<f64>,
+    pub data_source: Option<String>,
+    pub data_quality: Option<DataQuality>,
+    pub confidence_score: Option<Decimal>,
+    pub external_id: Option<String>,
+    /// Only when expense parameters were passed and price and rent are known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net_yield: Option<NetYield>,
+}
+
+impl Redact for PropertyDetail {
+    fn redact(&mut self, redactor: &Redactor) {
+        redactor.address(&mut self.address);
+        redactor.optional_price(&mut self.price);
+        self.rental_yield = redactor
+            .rental_yield(self.price, self.weekly_rent)
+            .and_then(|y| y.to_f64());
+        if let Some(net) = self.net_yield.take() {
+            self.net_yield = NetYield::calculate(
+                self.price,
+                self.weekly_rent,
+                net.expenses(),
+                net.council_rates_source,
+            );
+        }
+        // Land areas are public record, so this would give the price away
+        self.price_per_sqm = None;
+        redactor.decimal_coordinate(&mut self.latitude);
+        redactor.decimal_coordinate(&mut self.longitude);
+    }
+}
+
+impl From<PropertyDetailRow> for PropertyDetail {
+    fn from(p: PropertyDetailRow) -> Self {
+        let rental_yield = display_yield(p.price, p.weekly_rent);
+
+        PropertyDetail {
+            id: p.id,
+            address: p.address,
+            suburb: p.suburb,
+            state: p.state,
+            postcode: p.postcode,
+            district_name: p.district.as_deref().map(district_name),
+            district: p.district,
+            property_type: p.property_type,
+            bedrooms: p.bedrooms,
+            price: p.price,
+            sale_date: p.sale_date,
+            weekly_rent: p.weekly_rent,
+            price_per_sqm: p.price_per_sqm,
+            latitude: p.latitude,
+            longitude: p.longitude,
+            rental_yield,
+            annualized_growth_pct: None,
+            data_source: p.data_source,
+            data_quality: p.data_quality,
+            confidence_score: p.confidence_score,
+            external_id: p.external_id,
+            net_yield: None,
+        }
+    }
+}
+
+/// Highest annual expense amount accepted, AUD
+const MAX_EXPENSE: i32 = 1_000_000;
+
+/// Expense overrides for GET /api/properties/:id; passing any of them adds a
+/// net_yield block, with the rest from `AnnualExpenses::default()`
+#[derive(Debug, Default, Deserialize)]
+pub struct PropertyDetailQuery {
+    /// Defaults to the rates of the property's council where known
+    pub council_rates: Option<i32>,
+    pub strata: Option<i32>,
+    pub insurance: Option<i32>,
+    /// Percent of annual rent
+    pub management_fee_pct: Option<Decimal>,
+    pub maintenance: Option<i32>,
+}
+
+impl ValidateParams for PropertyDetailQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_range("council_rates", self.council_rates, 0..=MAX_EXPENSE)?;
+        check_range("strata", self.strata, 0..=MAX_EXPENSE)?;
+        check_range("insurance", self.insurance, 0..=MAX_EXPENSE)?;
+        check_range(
+            "management_fee_pct",
+            self.management_fee_pct,
+            Decimal::ZERO..=Decimal::ONE_HUNDRED,
+        )?;
+        check_range("maintenance", self.maintenance, 0..=MAX_EXPENSE)
+    }
+}
+
+impl PropertyDetailQuery {
+    fn any_expense(&self) -> bool {
+        self.council_rates.is_some()
+            || self.strata.is_some()
+            || self.insurance.is_some()
+            || self.management_fee_pct.is_some()
+            || self.maintenance.is_some()
+    }
+}
+
+/// Where the council rates behind a net yield came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RatesSource {
+    /// The council_rates parameter
+    Requested,
+    /// The average rates and levies of the property's council
+    Council,
+    /// The flat assumption in `AnnualExpenses::default()`
+    Default,
+}
+
+/// Net yield of a property after the expenses it was calculated with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetYield {
+    /// Percent, to 2 decimal places; negative when expenses exceed the rent
+    pub net_yield: f64,
+    /// Widened so a year of the largest weekly rent still fits
+    pub annual_rent: i64,
+    /// Every expense including the management fee, rounded to whole dollars
+    pub annual_expenses: i64,
+    pub council_rates: i32,
+    pub council_rates_source: RatesSource,
+    pub strata: i32,
+    pub insurance: i32,
+    pub management_fee_pct: Decimal,
+    pub maintenance: i32,
+}
+
+impl NetYield {
+    /// None without a price or rent
+    pub fn calculate(
+        price: Option<i64>,
+        weekly_rent: Option<i32>,
+        expenses: AnnualExpenses,
+        council_rates_source: RatesSource,
+    ) -> Option<NetYield> {
+        let (price, weekly_rent) = (price?, weekly_rent?);
+        let net_yield = calculate_net_yield(price, weekly_rent, &expenses)?;
+        let annual_rent = i64::from(weekly_rent) * 52;
+
+        Some(NetYield {
+            net_yield: round_yield_for_display(net_yield).to_f64()?,
+            annual_rent,
+            annual_expenses: expenses
+                .total(Decimal::from(annual_rent))
+                .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+                .to_i64()?,
+            council_rates: expenses.council_rates,
+            council_rates_source,
+            strata: expenses.strata,
+            insurance: expenses.insurance,
+            management_fee_pct: expenses.management_fee_pct,
+            maintenance: expenses.maintenance,
+        })
+    }
+
+    /// The expenses this was calculated with
+    pub fn expenses(&self) -> AnnualExpenses {
+        AnnualExpenses {
+            council_rates: self.council_rates,
+            strata: self.strata,
+            insurance: self.insurance,
+            management_fee_pct: self.management_fee_pct,
+            maintenance: self.maintenance,
+        }
+    }
+}
+
+/// Response for GET /api/properties
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PropertiesPage {
+    pub properties: Vec<Property>,
+    pub page: i64,
+    pub page_size: i64,
+    /// Properties matching the filters across all pages
+    pub total: i64,
+    /// Zero when nothing matches
+    pub total_pages: i64,
+    /// Pass back as `cursor` for the following page; absent on the last page
+    /// and for sorts without cursor support
+    pub next_cursor: Option<String>,
+}
+
+impl Redact for PropertiesPage {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.properties.redact(redactor);
+        // Refused from public callers by PropertiesQuery::check_tier
+        self.next_cursor = self.next_cursor.take().filter(|cursor| {
+            PropertyCursor::decode(cursor).is_some_and(|c| c.sort != SortField::RentalYield)
+        });
+    }
+}
+
+/// GET /api/properties - properties in the requested order (id by default),
+/// a page at a time
+/// Pages past the end are empty rather than an error. Carries a weak ETag
+/// that changes when a matching property is added, removed or updated, and
+/// responds 304 when it equals the request's If-None-Match.
+pub async fn get_properties(
+    State(state): State<AppState>,
+    access: Access,
+    headers: HeaderMap,
+    ValidatedListParams(params): ValidatedListParams<PropertiesQuery>,
+) -> Result<Response, Response> {
+    let db_error = |e: sqlx::Error| {
+        error!("Database error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+
+    params
+        .check_tier(access.tier)
+        .map_err(IntoResponse::into_response)?;
+    let filter = resolve_filter(&state.read_db, &params).await?;
+    let version = fetch_listing_version(&state.read_db, &filter)
+        .await
+        .map_err(db_error)?;
+    let cache_headers = [
+        (ETAG, version.etag(access.tier)),
+        (CACHE_CONTROL, state.listing_cache.cache_control()),
+        // Tiers see different data at the same URL
+        (VARY, HeaderValue::from_static(API_KEY_HEADER)),
+    ];
+    if headers
+        .get(IF_NONE_MATCH)
+        .is_some_and(|tags| etag_matches(tags, &cache_headers[0].1))
+    {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+
+    let page = fetch_listing_page(&state.read_db, &filter, &params, version.count)
+        .await
+        .map_err(db_error)?;
+    Ok((cache_headers, Json(access.apply(page))).into_response())
+}
+
+/// The filter a query describes, with its region resolved to postcodes
+/// A 400 when the region is unknown, a 500 when the lookup fails
+pub(crate) async fn resolve_filter(
+    db: &PgPool,
+    params: &PropertiesQuery,
+) -> Result<PropertyFilter, Response> {
+    let mut filter = PropertyFilter::from(params);
+    if let Some(region) = &params.region {
+        let postcodes = regions::region_postcodes(db, region).await.map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+        if postcodes.is_empty() {
+            return Err(ParamError::new("region", "unknown region").into_response());
+        }
+        filter.region_postcodes = Some(postcodes);
+    }
+    Ok(filter)
+}
+
+/// The page of the listing a validated query asks for; `total` is how many
+/// properties match the filter
+pub(crate) async fn fetch_listing_page(
+    db: &PgPool,
+    filter: &PropertyFilter,
+    params: &PropertiesQuery,
+    total: i64,
+) -> Result<PropertiesPage, sqlx::Error> {
+    // Checked by PropertiesQuery::validate
+    let cursor = params.cursor.as_deref().and_then(PropertyCursor::decode);
+    let page_size = params.page_size();
+    let mut rows = fetch_properties_page(db, filter, params, cursor.as_ref()).await?;
+
+    // One extra row is fetched to tell whether another page follows
+    let next_cursor = if rows.len() as i64 > page_size {
+        rows.truncate(page_size as usize);
+        let sort = params.sort();
+        rows.last()
+            .filter(|_| sort.supports_cursor())
+            .map(|row| row.cursor(sort, params.order()).encode())
+    } else {
+        None
+    };
+
+    Ok(PropertiesPage {
+        properties: rows.into_iter().map(Property::from).collect(),
+        page: params.page(),
+        page_size,
+        total,
+        total_pages: (total + page_size - 1) / page_size,
+        next_cursor,
+    })
+}
+
+/// How many properties match a filter and when the latest of them changed;
+/// ingestion stamps last_updated on every write, so this moves whenever the
+/// listing would
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::FromRow)]
+pub struct ListingVersion {
+    pub count: i64,
+    pub last_updated: Option<NaiveDateTime>,
+}
+
+impl ListingVersion {
+    /// Weak, since the same data may be serialized or compressed differently
+    fn etag(&self, tier: Tier) -> HeaderValue {
+        let tier = match tier {
+            Tier::Public => "public",
+            Tier::Full => "full",
+        };
+        let etag = format!(
+            "W/\"{}-{}-{}\"",
+            tier,
+            self.count,
+            self.last_updated
+                .map_or(0, |t| t.and_utc().timestamp_micros())
+        );
+        HeaderValue::from_str(&etag).expect("digits and punctuation are a valid header")
+    }
+}
+
+/// Whether an If-None-Match header names `etag`, or is `*`
+fn etag_matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let Ok(tags) = if_none_match.to_str() else {
+        return false;
+    };
+    let Ok(etag) = etag.to_str() else {
+        return false;
+    };
+    // Weak comparison: W/"x" and "x" match
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    tags.split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+/// GET /api/properties/:id - one property in full. 400 when the id isn't a
+/// number, 404 when no property has it.
+pub async fn get_property_by_id(
+    State(state): State<AppState>,
+    access: Access,
+    id: Result<Path<i32>, PathRejection>,
+    ValidatedListParams(params): ValidatedListParams<PropertyDetailQuery>,
+) -> Result<Json<PropertyDetail>, Response> {
+    let Path(id) = id
+        .map_err(|_| ParamError::new("id", "must be a whole number property id").into_response())?;
+
+    let row = sqlx::query_as::<_, PropertyDetailRow>(
+        r#"
+        SELECT
+            id,
+            address,
+            suburb,
+            state,
+            postcode,
+            district,
+            property_type,
+            bedrooms,
+            price,
+            sale_date,
+            weekly_rent,
+            price_per_sqm,
+            latitude,
+            longitude,
+            data_source,
+            data_quality,
+            confidence_score,
+            external_id
+        FROM properties
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&state.read_db)
+    .await
+    .map_err(|e| {
+        error!("Database error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let Some(row) = row else {
+        let body = json!({
+            "error": "not_found",
+            "message": format!("no property with id {}", id),
+        });
+        return Err((StatusCode::NOT_FOUND, Json(body)).into_response());
+    };
+
+    let mut detail = PropertyDetail::from(row);
+    detail.annualized_growth_pct =
+        fetch_annualized_growth(&state.read_db, id)
+            .await
+            .map_err(|e| {
+                error!("Database error: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            })?;
+    if params.any_expense() {
+        detail.net_yield = net_yield(&state.read_db, &detail, &params)
+            .await
+            .map_err(|e| {
+                error!("Database error: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            })?;
+    }
+    Ok(Json(access.apply(detail)))
+}
+
+/// The first and last recorded sales of a property
+#[derive(Debug, sqlx::FromRow)]
+struct SaleSpan {
+    first_price: i64,
+    first_date: NaiveDate,
+    last_price: i64,
+    last_date: NaiveDate,
+}
+
+/// Annualized growth between the property's first and last sales, shown to
+/// a yield's precision. Sales on the same date are ordered as recorded, so a
+/// corrected price counts as the later sale.
+async fn fetch_annualized_growth(db: &PgPool, id: i32) -> Result<Option<f64>, sqlx::Error> {
+    let span = sqlx::query_as::<_, SaleSpan>(
+        r#"
+        SELECT
+            f.sale_price AS first_price,
+            f.sale_date AS first_date,
+            l.sale_price AS last_price,
+            l.sale_date AS last_date
+        FROM (
+            SELECT sale_price, sale_date FROM sales_history
+            WHERE property_id = $1
+            ORDER BY sale_date, id
+            LIMIT 1
+        ) f, (
+            SELECT sale_price, sale_date FROM sales_history
+            WHERE property_id = $1
+            ORDER BY sale_date DESC, id DESC
+            LIMIT 1
+        ) l
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(span.and_then(|s| {
+        calculate_annualized_growth(s.first_price, s.first_date, s.last_price, s.last_date)
+            .map(round_yield_f64)
+    }))
+}
+
+/// The property's net yield with `params` over the default expenses. Council
+/// rates not passed come from the property's council, else the default.
+async fn net_yield(
+    db: &PgPool,
+    detail: &PropertyDetail,
+    params: &PropertyDetailQuery,
+) -> Result<Option<NetYield>, sqlx::Error> {
+    if detail.price.is_none() || detail.weekly_rent.is_none() {
+        return Ok(None);
+    }
+    let defaults = AnnualExpenses::default();
+    let (council_rates, source) = match params.council_rates {
+        Some(rates) => (rates, RatesSource::Requested),
+        None => match council_rates_for_property(db, detail.id).await? {
+            Some(rates) => (rates.annual_total(), RatesSource::Council),
+            None => (defaults.council_rates, RatesSource::Default),
+        },
+    };
+    let expenses = AnnualExpenses {
+        council_rates,
+        strata: params.strata.unwrap_or(defaults.strata),
+        insurance: params.insurance.unwrap_or(defaults.insurance),
+        management_fee_pct: params
+            .management_fee_pct
+            .unwrap_or(defaults.management_fee_pct),
+        maintenance: params.maintenance.unwrap_or(defaults.maintenance),
+    };
+
+    Ok(NetYield::calculate(
+        detail.price,
+        detail.weekly_rent,
+        expenses,
+        source,
+    ))
+}
+
+/// Query parameters for GET /api/properties/top-yields
+#[derive(Debug, Default, Deserialize)]
+pub struct TopYieldsQuery {
+    pub state: Option<AusState>,
+    /// Defaults to DEFAULT_TOP_YIELDS_LIMIT
+    pub limit: Option<i64>,
+    /// Only properties whose data quality scores at least this tier's;
+    /// properties with no recorded quality are then left out too
+    pub min_quality: Option<DataQuality>,
+    /// `false` leaves out rents estimated from medians
+    pub estimated: Option<bool>,
+}
+
+impl ValidateParams for TopYieldsQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_range("limit", self.limit, 1..=MAX_TOP_YIELDS_LIMIT)
+    }
+}
+
+impl TopYieldsQuery {
+    /// Data quality levels admitted, or None when any is
+    pub fn qualities(&self) -> Option<Vec<DataQuality>> {
+        self.min_quality.map(DataQuality::at_least)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TopYieldRow {
+    #[sqlx(flatten)]
+    detail: PropertyDetailRow,
+    is_rental_estimated: Option<bool>,
+    rental_match_method: Option<RentalMatchMethod>,
+}
+
+/// One property in the top-yields list - the full detail, including where it
+/// came from, plus whether its rent was estimated and how it was matched
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TopYieldProperty {
+    #[serde(flatten)]
+    pub property: PropertyDetail,
+    pub is_rental_estimated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rental_match_method: Option<RentalMatchMethod>,
+}
+
+impl Redact for TopYieldProperty {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.property.redact(redactor);
+    }
+}
+
+/// Response for GET /api/properties/top-yields
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TopYieldsResponse {
+    /// Highest yield first
+    pub properties: Vec<TopYieldProperty>,
+}
+
+impl Redact for TopYieldsResponse {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.properties.redact(redactor);
+    }
+}
+
+/// GET /api/properties/top-yields - highest-yielding properties that pass the
+/// quality and estimation filters, with yields above the sanity ceiling
+/// treated as bad data
+pub async fn get_top_yield_properties(
+    State(state): State<AppState>,
+    access: Access,
+    ValidatedListParams(params): ValidatedListParams<TopYieldsQuery>,
+) -> Result<Json<TopYieldsResponse>, StatusCode> {
+    let rows = fetch_top_yield_properties(&state.read_db, &params, state.top_yields.max_yield)
+        .await
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let properties = rows
+        .into_iter()
+        .map(|row| TopYieldProperty {
+            property: PropertyDetail::from(row.detail),
+            is_rental_estimated: row.is_rental_estimated.unwrap_or(false),
+            rental_match_method: row.rental_match_method,
+        })
+        .collect();
+    Ok(Json(access.apply(TopYieldsResponse { properties })))
+}
+
+/// Properties by derived yield, highest first; the yield is derived from price
+/// and rent as in FILTER_SQL
+async fn fetch_top_yield_properties(
+    db: &PgPool,
+    params: &TopYieldsQuery,
+    max_yield: Decimal,
+) -> Result<Vec<TopYieldRow>, sqlx::Error> {
+    sqlx::query_as::<_, TopYieldRow>(
+        r#"
+        SELECT
+            id, address, suburb, state, postcode, district, property_type, bedrooms,
+            price, sale_date, weekly_rent, price_per_sqm, latitude, longitude,
+            data_source, data_quality, confidence_score, external_id, is_rental_estimated,
+            rental_match_method
+        FROM (
+            SELECT *, ROUND(weekly_rent::numeric * 5200 / price, 4) AS derived_yield
+            FROM properties
+            WHERE price > 0
+              AND weekly_rent IS NOT NULL
+              AND ($1::state_enum IS NULL OR state = $1)
+              AND ($2::data_quality_enum[] IS NULL OR data_quality = ANY($2))
+              AND ($3 OR NOT COALESCE(is_rental_estimated, FALSE))
+        ) p
+        WHERE derived_yield <= $4
+        ORDER BY derived_yield DESC, id
+        LIMIT $5
+        "#,
+    )
+    .bind(params.state)
+    .bind(params.qualities())
+    .bind(params.estimated.unwrap_or(true))
+    .bind(max_yield)
+    .bind(params.limit.unwrap_or(DEFAULT_TOP_YIELDS_LIMIT))
+    .fetch_all(db)
+    .await
+}
+
+/// Query parameters for GET /api/properties/changes
+#[derive(Debug, Default, Deserialize)]
+pub struct PropertyChangesQuery {
+    /// RFC 3339 timestamp, e.g. 2025-01-01T00:00:00Z; normally the previous
+    /// sync's `server_time`
+    pub since: String,
+    /// Defaults to DEFAULT_PAGE_SIZE
+    pub page_size: Option<i64>,
+    /// `next_cursor` from the previous page of the same sync
+    pub cursor: Option<String>,
+}
+
+impl ValidateParams for PropertyChangesQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        let since = parse_rfc3339(&self.since).ok_or_else(|| {
+            ParamError::new(
+                "since",
+                "must be an RFC 3339 timestamp like 2025-01-01T00:00:00Z",
+            )
+        })?;
+        check_range("page_size", self.page_size, 1..=MAX_PAGE_SIZE)?;
+
+        if let Some(cursor) = &self.cursor {
+            let cursor = ChangesCursor::decode(cursor)
+                .ok_or_else(|| ParamError::new("cursor", "not a valid cursor"))?;
+            if cursor.since != since {
+                return Err(ParamError::new(
+                    "cursor",
+                    "was issued for a different since",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PropertyChangesQuery {
+    pub fn page_size(&self) -> i64 {
+        self.page_size.unwrap_or(DEFAULT_PAGE_SIZE)
+    }
+}
+
+/// A timestamp in strict RFC 3339 form, with a date, time and offset
+fn parse_rfc3339(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Keyset cursor for a delta sync - its window and the position of the last
+/// property on a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangesCursor {
+    pub since: DateTime<Utc>,
+    /// The first page's `server_time`; later pages stop there too
+    pub until: DateTime<Utc>,
+    pub last_updated: NaiveDateTime,
+    pub id: i32,
+}
+
+impl ChangesCursor {
+    /// URL-safe base64 of the cursor's JSON
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("cursor serializes");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ChangedPropertyRow {
+    #[sqlx(flatten)]
+    property: PropertyRow,
+    last_updated: NaiveDateTime,
+}
+
+/// A property as listed, plus when it last changed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangedProperty {
+    #[serde(flatten)]
+    pub property: Property,
+    pub last_updated: NaiveDateTime,
+}
+
+impl Redact for ChangedProperty {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.property.redact(redactor);
+    }
+}
+
+/// Response for GET /api/properties/changes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PropertyChangesPage {
+    /// Added or updated after `since`, least recently changed first
+    pub properties: Vec<ChangedProperty>,
+    /// Properties removed after `since`; on the first page only
+    pub deleted_ids: Vec<i32>,
+    /// Pass back as `cursor`, with the same `since`, for the following page;
+    /// absent on the last page
+    pub next_cursor: Option<String>,
+    /// The end of this sync's window, the same on every page; pass it as the
+    /// next sync's `since` once the last page is read
+    pub server_time: DateTime<Utc>,
+}
+
+impl Redact for PropertyChangesPage {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.properties.redact(redactor);
+    }
+}
+
+/// GET /api/properties/changes - properties whose last_updated falls after
+/// `since`, for clients keeping a local copy, a page at a time
+///
+/// A write whose transaction is still open at `server_time` can be stamped
+/// before it and so missed by both this sync and the next; mirrors that must
+/// be exact should follow /api/changes instead.
+pub async fn get_property_changes(
+    State(state): State<AppState>,
+    access: Access,
+    ValidatedListParams(params): ValidatedListParams<PropertyChangesQuery>,
+) -> Result<Json<PropertyChangesPage>, StatusCode> {
+    let db_error = |e: sqlx::Error| {
+        error!("Database error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    // Checked by PropertyChangesQuery::validate
+    let since = parse_rfc3339(&params.since).ok_or(StatusCode::BAD_REQUEST)?;
+    let cursor = params.cursor.as_deref().and_then(ChangesCursor::decode);
+    let (until, deleted_ids) = match cursor {
+        Some(cursor) => (cursor.until, Vec::new()),
+        None => {
+            let until = sqlx::query_scalar::<_, DateTime<Utc>>("SELECT NOW()")
+                .fetch_one(&state.read_db)
+                .await
+                .map_err(db_error)?;
+            let deleted = fetch_deleted_property_ids(&state.read_db, since, until)
+                .await
+                .map_err(db_error)?;
+            (until, deleted)
+        }
+    };
+
+    let page_size = params.page_size();
+    let mut rows = fetch_changed_properties(&state.read_db, since, until, cursor, page_size)
+        .await
+        .map_err(db_error)?;
+
+    // One extra row is fetched to tell whether another page follows
+    let next_cursor = if rows.len() as i64 > page_size {
+        rows.truncate(page_size as usize);
+        rows.last().map(|row| {
+            ChangesCursor {
+                since,
+                until,
+                last_updated: row.last_updated,
+                id: row.property.id,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(Json(
+        access.apply(PropertyChangesPage {
+            properties: rows
+                .into_iter()
+                .map(|row| ChangedProperty {
+                    property: Property::from(row.property),
+                    last_updated: row.last_updated,
+                })
+                .collect(),
+            deleted_ids,
+            next_cursor,
+            server_time: until,
+        }),
+    ))
+}
+
+/// Properties last updated in (since, until], after `cursor` when given, by
+/// last_updated then id; one more than `page_size` to show another page follows
+async fn fetch_changed_properties(
+    db: &PgPool,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    cursor: Option<ChangesCursor>,
+    page_size: i64,
+) -> Result<Vec<ChangedPropertyRow>, sqlx::Error> {
+    let sql = format!(
+        r#"
+        SELECT
+            id,
+            address,
+            suburb,
+            state,
+            bedrooms,
+            price,
+            weekly_rent,
+            latitude,
+            longitude,
+            {} AS sort_yield,
+            last_updated
+        FROM properties
+        WHERE last_updated > $1
+          AND last_updated <= $2
+          AND ($3::timestamp IS NULL OR (last_updated, id) > ($3, $4))
+        ORDER BY last_updated, id
+        LIMIT $5
+        "#,
+        SortField::RentalYield.sql(),
+    );
+    sqlx::query_as::<_, ChangedPropertyRow>(&sql)
+        .bind(since)
+        .bind(until)
+        .bind(cursor.map(|c| c.last_updated))
+        .bind(cursor.map_or(0, |c| c.id))
+        .bind(page_size + 1)
+        .fetch_all(db)
+        .await
+}
+
+/// Ids of properties removed in (since, until], from the change log
+async fn fetch_deleted_property_ids(
+    db: &PgPool,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<i32>, sqlx::Error> {
+    sqlx::query_scalar::<_, i32>(
+        r#"
+        SELECT DISTINCT property_id
+        FROM change_log
+        WHERE change_type = 'archive'
+          AND changed_at > $1
+          AND changed_at <= $2
+        ORDER BY property_id
+        "#,
+    )
+    .bind(since)
+    .bind(until)
+    .fetch_all(db)
+    .await
+}
+
+/// The count and latest change of the properties matching the filter
+pub(crate) async fn fetch_listing_version(
+    db: &PgPool,
+    filter: &PropertyFilter,
+) -> Result<ListingVersion, sqlx::Error> {
+    let sql = format!(
+        "SELECT COUNT(*) AS count, MAX(last_updated) AS last_updated FROM properties {}",
+        FILTER_SQL
+    );
+    bind_filter(sqlx::query_as::<_, ListingVersion>(&sql), filter)
+        .fetch_one(db)
+        .await
+}
+
+/// One page of properties in the requested order, plus the first property of
+/// the next page when there is one; after `cursor` instead of at the page's
+/// offset when given
+async fn fetch_properties_page(
+    db: &PgPool,
+    filter: &PropertyFilter,
+    params: &PropertiesQuery,
+    cursor: Option<&PropertyCursor>,
+) -> Result<Vec<PropertyRow>, sqlx::Error> {
+    let (sort, order) = (params.sort(), params.order());
+    let page_sql = format!(
+        r#"
+        SELECT
+            id,
+            address,
+            suburb,
+            state,
+            bedrooms,
+            price,
+            weekly_rent,
+            latitude,
+            longitude,
+            {} AS sort_yield
+        FROM properties
+        {}
+        {}
+        {}
+        LIMIT $12 OFFSET $13
+        "#,
+        SortField::RentalYield.sql(),
+        FILTER_SQL,
+        cursor.map_or(String::new(), |_| keyset_sql(sort, order)),
+        order_by_sql(sort, order)
+    );
+    let mut query = bind_filter(sqlx::query_as::<_, PropertyRow>(&page_sql), filter)
+        .bind(params.page_size() + 1)
+        .bind(if cursor.is_some() { 0 } else { params.offset() });
+    if let Some(cursor) = cursor {
+        if sort == SortField::RentalYield {
+            query = query.bind(cursor.rental_yield);
+        }
+        query = query.bind(cursor.id);
+    }
+    query.fetch_all(db).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tier::{Tier, TierConfig};
+    use crate::api::API_KEY_HEADER;
+    use crate::ingestion::types::PostcodeRegion;
+    use crate::ingestion::write;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use axum::middleware::map_request;
+    use axum::routing::get;
+    use axum::Router;
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const FULL_KEY: &str = "properties-test-full";
+
+    /// The API as a full-tier caller sees it: every request carries the key
+    fn full_app(db: PgPool) -> Router {
+        crate::api::router()
+            .with_state(AppState {
+                tiers: Arc::new(TierConfig::default().with_key(FULL_KEY, Tier::Full)),
+                ..test_state(db)
+            })
+            .layer(map_request(|mut request: Request<Body>| async move {
+                let key = HeaderValue::from_static(FULL_KEY);
+                request.headers_mut().insert(API_KEY_HEADER, key);
+                request
+            }))
+    }
+
+    async fn send(app: Router, uri: &str) -> (StatusCode, Value) {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    /// Echoes the page the extractor settled on, without touching a database
+    fn echo_router() -> Router {
+        Router::new().route(
+            "/",
+            get(
+                |ValidatedListParams(q): ValidatedListParams<PropertiesQuery>| async move {
+                    Json(serde_json::json!({
+                        "page": q.page(),
+                        "page_size": q.page_size(),
+                        "offset": q.offset(),
+                    }))
+                },
+            ),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_extractor_accepts_page_parameters() {
+        let cases = [
+            ("/", (1, DEFAULT_PAGE_SIZE, 0)),
+            ("/?page=3", (3, DEFAULT_PAGE_SIZE, 2 * DEFAULT_PAGE_SIZE)),
+            ("/?page_size=10", (1, 10, 0)),
+            ("/?page=4&page_size=25", (4, 25, 75)),
+            ("/?page_size=500&page=2", (2, 500, 500)),
+            ("/?region=Parramatta&page=2&page_size=1", (2, 1, 1)),
+        ];
+
+        for (uri, (page, page_size, offset)) in cases {
+            let (status, body) = send(echo_router(), uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            assert_eq!(body["page"], page, "{}", uri);
+            assert_eq!(body["page_size"], page_size, "{}", uri);
+            assert_eq!(body["offset"], offset, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_page_parameters_rejected_with_400() {
+        // Validation must fail before the unreachable database is queried
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = crate::api::router().with_state(test_state(db));
+
+        let cases = [
+            ("/api/properties?page_size=0", "page_size"),
+            ("/api/properties?page_size=501", "page_size"),
+            ("/api/properties?page_size=-5", "page_size"),
+            ("/api/properties?page_size=ten", "page_size"),
+            ("/api/properties?page=0", "page"),
+            ("/api/properties?page=-1", "page"),
+            ("/api/properties?page=1.5", "page"),
+            ("/api/properties?page=99999999999999999999", "page"),
+            ("/api/properties?page=9223372036854775807", "page"),
+            ("/api/properties?page=1&page=2", "page"),
+        ];
+
+        for (uri, field) in cases {
+            let (status, body) = send(app.clone(), uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["error"], "invalid_parameter", "{}", uri);
+            assert_eq!(body["field"], field, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_property_wire_format() {
+        let wire = json!({
+            "id": 7,
+            "address": "2/10 Smith Street",
+            "suburb": "Testville",
+            "state": "VIC",
+            "bedrooms": 2,
+            "price": 520000,
+            "weekly_rent": 500,
+            "latitude": "-37.8136",
+            "longitude": "144.9631",
+            "rental_yield": 5.0,
+        });
+        let property = Property::from(PropertyRow {
+            id: 7,
+            address: "2/10 Smith Street".to_string(),
+            suburb: "Testville".to_string(),
+            state: AusState::VIC,
+            bedrooms: Some(2),
+            price: Some(520_000),
+            weekly_rent: Some(500),
+            latitude: Some(Decimal::new(-378136, 4)),
+            longitude: Some(Decimal::new(1449631, 4)),
+            sort_yield: None,
+        });
+        assert_eq!(serde_json::to_value(&property).unwrap(), wire);
+
+        let parsed: Property = serde_json::from_value(wire.clone()).unwrap();
+        assert_eq!(parsed.state, AusState::VIC);
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), wire);
+    }
+
+    #[test]
+    fn test_property_detail_wire_format() {
+        let wire = json!({
+            "id": 7,
+            "address": "1 Hay Street",
+            "suburb": "Testville",
+            "state": "WA",
+            "postcode": "6000",
+            "district": null,
+            "district_name": null,
+            "property_type": "VacantLand",
+            "bedrooms": null,
+            "price": 300000,
+            "sale_date": "2024-03-01",
+            "weekly_rent": null,
+            "price_per_sqm": "500.00",
+            "latitude": null,
+            "longitude": null,
+            "rental_yield": null,
+            "data_source": "nsw_sales",
+            "data_quality": "Individual",
+            "confidence_score": "0.95",
+            "external_id": null,
+        });
+
+        let parsed: PropertyDetail = serde_json::from_value(wire.clone()).unwrap();
+        assert_eq!(parsed.state, AusState::WA);
+        assert_eq!(parsed.property_type, Some(PropertyType::VacantLand));
+        assert_eq!(parsed.data_quality, Some(DataQuality::Individual));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), wire);
+    }
+
+    #[test]
+    fn test_order_by_sql() {
+        assert_eq!(
+            order_by_sql(SortField::Id, SortOrder::Asc),
+            "ORDER BY id ASC"
+        );
+        assert_eq!(
+            order_by_sql(SortField::Price, SortOrder::Desc),
+            "ORDER BY price DESC NULLS LAST, id"
+        );
+        assert!(order_by_sql(SortField::RentalYield, SortOrder::Desc)
+            .ends_with("END DESC NULLS LAST, id"));
+    }
+
+    #[test]
+    fn test_yield_floor_matches_display_rounding() {
+        let floor = |min: &str| {
+            PropertyFilter {
+                min_yield: Some(min.parse().unwrap()),
+                ..Default::default()
+            }
+            .yield_floor()
+            .map(|f| f.to_string())
+        };
+
+        assert_eq!(floor("5"), Some("4.995".to_string()));
+        assert_eq!(floor("4.5"), Some("4.495".to_string()));
+        // Finer than display precision: nothing below 4.51 displays at or above 4.501
+        assert_eq!(floor("4.501"), Some("4.505".to_string()));
+        assert_eq!(PropertyFilter::default().yield_floor(), None);
+
+        // The floor admits exactly the stored yields that display at or above the minimum
+        let displayed = |stored: &str| round_yield_for_display(stored.parse().unwrap());
+        let five: Decimal = "5".parse().unwrap();
+        let five_floor: Decimal = floor("5").unwrap().parse().unwrap();
+        for stored in ["4.9949", "4.9950", "5.0000"] {
+            let admitted = stored.parse::<Decimal>().unwrap() >= five_floor;
+            assert_eq!(admitted, displayed(stored) >= five, "{}", stored);
+        }
+    }
+
+    #[test]
+    fn test_displayed_yield_matches_enriched_yield() {
+        // (price, weekly rent, stored, displayed)
+        let cases = [
+            (i64::from(i32::MAX), 1, "0.0000", 0.0),
+            (i64::from(i32::MAX), 20_000, "0.0484", 0.05),
+            (100_000_000, 1, "0.0001", 0.0),
+            (999_999, 1, "0.0052", 0.01),
+            (12_345_678, 4_321, "1.8200", 1.82),
+            (1, 1, "5200.0000", 5200.0),
+        ];
+
+        for (price, rent, stored, displayed) in cases {
+            let record = PropertyFixture::new().price(price).weekly_rent(rent);
+            let enriched = crate::ingestion::enrich::calculate_yield(record.record().clone())
+                .rental_yield
+                .unwrap();
+            assert_eq!(enriched, stored.parse().unwrap(), "{} {}", price, rent);
+
+            let shown = display_yield(Some(price), Some(rent));
+            assert_eq!(shown, Some(displayed), "{} {}", price, rent);
+            assert_eq!(
+                shown,
+                round_yield_for_display(enriched).to_f64(),
+                "{} {}",
+                price,
+                rent
+            );
+        }
+    }
+
+    #[test]
+    fn test_net_yield_annual_rent_at_i32_max() {
+        // 52 weeks of i32::MAX overflows an i32
+        let net = NetYield::calculate(
+            Some(i32::MAX.into()),
+            Some(i32::MAX),
+            AnnualExpenses::default(),
+            RatesSource::Default,
+        )
+        .unwrap();
+        assert_eq!(net.annual_rent, 111_669_149_644);
+        assert!(net.annual_expenses > i64::from(i32::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_filters_rejected_with_400() {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = crate::api::router().with_state(test_state(db));
+
+        let cases = [
+            ("/api/properties?state=XYZ", "state"),
+            ("/api/properties?state=nsw", "state"),
+            ("/api/properties?bedrooms=-1", "bedrooms"),
+            ("/api/properties?bedrooms=two", "bedrooms"),
+            ("/api/properties?min_price=-1", "min_price"),
+            ("/api/properties?max_price=cheap", "max_price"),
+            (
+                "/api/properties?min_price=900000&max_price=500000",
+                "min_price",
+            ),
+            (
+                "/api/properties?min_weekly_rent=700&max_weekly_rent=600",
+                "min_weekly_rent",
+            ),
+            ("/api/properties?min_yield=high", "min_yield"),
+            ("/api/properties?min_yield=101", "min_yield"),
+            ("/api/properties?min_yield=-1", "min_yield"),
+            ("/api/properties?postcode=12345678901", "postcode"),
+            ("/api/properties?postcode=200", "postcode"),
+            ("/api/properties?district=sydney", "district"),
+            ("/api/properties?state=NSW,,QLD", "state"),
+            ("/api/properties?sort=address", "sort"),
+            (
+                "/api/properties?sort=price;DROP%20TABLE%20properties",
+                "sort",
+            ),
+            ("/api/properties?sort=rental_yield&order=down", "order"),
+            ("/api/properties/1?strata=-1", "strata"),
+            ("/api/properties/1?insurance=1.5", "insurance"),
+            ("/api/properties/1?maintenance=1000001", "maintenance"),
+            (
+                "/api/properties/1?management_fee_pct=101",
+                "management_fee_pct",
+            ),
+            (
+                "/api/properties/1?management_fee_pct=lots",
+                "management_fee_pct",
+            ),
+        ];
+
+        for (uri, field) in cases {
+            let (status, body) = send(app.clone(), uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["error"], "invalid_parameter", "{}", uri);
+            assert_eq!(body["field"], field, "{}", uri);
+        }
+
+        // In a list, the first bad value is named
+        for (uri, field, message) in [
+            (
+                "/api/properties?state=NSW,XYZ,QLD",
+                "state",
+                "unknown state 'XYZ'",
+            ),
+            (
+                "/api/properties?postcode=2000,20a6,2026",
+                "postcode",
+                "'20a6' is not a 4-digit postcode",
+            ),
+            (
+                "/api/properties?postcode=2000,%202026,12345",
+                "postcode",
+                "'12345' is not a 4-digit postcode",
+            ),
+        ] {
+            let (status, body) = send(app.clone(), uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["field"], field, "{}", uri);
+            assert_eq!(body["message"], message, "{}", uri);
+        }
+    }
+
+    const SUBURB: &str = "Properties Page Testville";
+    const POSTCODE: &str = "2996";
+    const SA3_CODE: &str = "99996";
+
+    async fn cleanup(db: &PgPool) {
+        delete_suburb(db, SUBURB).await.unwrap();
+        sqlx::query("DELETE FROM regions WHERE sa3_code = $1")
+            .bind(SA3_CODE)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_properties_pages() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        // A region of its own, so the filter isolates the seeded properties
+        let region = PostcodeRegion {
+            postcode: POSTCODE.to_string(),
+            sa3_code: SA3_CODE.to_string(),
+            sa3_name: "Properties Page Test".to_string(),
+            sa4_code: "999".to_string(),
+            sa4_name: None,
+            state: Some(AusState::NSW),
+            ratio: 1.0,
+        };
+        write::write_postcode_regions(&db, vec![region])
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for n in 1..=5 {
+            let id = PropertyFixture::new()
+                .address(&format!("{} Page St", n))
+                .suburb(SUBURB)
+                .postcode(POSTCODE)
+                .insert(&db)
+                .await
+                .unwrap();
+            ids.push(id as i64);
+        }
+
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let mut seen = Vec::new();
+        for page in 1..=3 {
+            let uri = format!(
+                "/api/properties?region={}&page={}&page_size=2",
+                SA3_CODE, page
+            );
+            let (status, body) = send(app.clone(), &uri).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["page"], page);
+            assert_eq!(body["page_size"], 2);
+            assert_eq!(body["total"], 5);
+            assert_eq!(body["total_pages"], 3);
+            seen.extend(
+                body["properties"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| p["id"].as_i64().unwrap()),
+            );
+        }
+        assert_eq!(seen, ids);
+
+        // Past the end: no rows, same totals
+        let uri = format!("/api/properties?region={}&page=4&page_size=2", SA3_CODE);
+        let (status, body) = send(app.clone(), &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["properties"], Value::Array(vec![]));
+        assert_eq!(body["total"], 5);
+
+        cleanup(&db).await;
+    }
+
+    const FILTER_SUBURB: &str = "Properties Filter Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_properties_filters() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, FILTER_SUBURB).await.unwrap();
+
+        // (address, bedrooms, price, weekly_rent, district): yields 5.20, 3.90,
+        // 6.76, 4.9952
+        let seeded = [
+            ("1 Filter St", 2, 600_000, 600, "033"),
+            ("2 Filter St", 2, 800_000, 600, "033"),
+            ("3 Filter St", 3, 400_000, 520, "001"),
+            ("4 Filter St", 2, 520_500, 500, "999"),
+        ];
+        let mut ids = Vec::new();
+        for (address, bedrooms, price, rent, district) in seeded {
+            let id = PropertyFixture::new()
+                .address(address)
+                .suburb(FILTER_SUBURB)
+                .state(AusState::NSW)
+                .postcode("2995")
+                .district(district)
+                .bedrooms(bedrooms)
+                .price(price)
+                .weekly_rent(rent)
+                .insert(&db)
+                .await
+                .unwrap();
+            ids.push(id as i64);
+        }
+
+        let app = full_app(db.clone());
+        let base = "/api/properties?suburb=properties%20FILTER%20testville&state=NSW";
+        let cases = [
+            ("", vec![ids[0], ids[1], ids[2], ids[3]]),
+            ("&bedrooms=2", vec![ids[0], ids[1], ids[3]]),
+            ("&max_price=600000", vec![ids[0], ids[2], ids[3]]),
+            ("&min_price=500000&max_price=700000", vec![ids[0], ids[3]]),
+            // Past the 32-bit range prices once had
+            (
+                "&max_price=3000000000",
+                vec![ids[0], ids[1], ids[2], ids[3]],
+            ),
+            ("&min_price=3000000000", vec![]),
+            ("&min_weekly_rent=520&max_weekly_rent=599", vec![ids[2]]),
+            // 4.9952 displays as 5.00, so it passes a minimum of 5
+            ("&min_yield=5", vec![ids[0], ids[2], ids[3]]),
+            ("&min_yield=5.01", vec![ids[0], ids[2]]),
+            (
+                "&min_yield=5&bedrooms=2&max_price=900000",
+                vec![ids[0], ids[3]],
+            ),
+            ("&postcode=2995&min_yield=7", vec![]),
+            ("&district=033", vec![ids[0], ids[1]]),
+            ("&district=1,999", vec![ids[2], ids[3]]),
+            ("&district=033&bedrooms=3", vec![]),
+        ];
+
+        for (filters, expected) in cases {
+            let uri = format!("{}{}", base, filters);
+            let (status, body) = send(app.clone(), &uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            let found: Vec<i64> = body["properties"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["id"].as_i64().unwrap())
+                .collect();
+            assert_eq!(found, expected, "{}", uri);
+            assert_eq!(body["total"], expected.len(), "{}", uri);
+        }
+
+        // Another state filters everything out rather than erroring
+        let (status, body) = send(
+            app,
+            "/api/properties?suburb=Properties%20Filter%20Testville&state=VIC",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 0);
+
+        // The detail names the council, or passes an unknown code through
+        let app = full_app(db.clone());
+        let (_, body) = send(app.clone(), &format!("/api/properties/{}", ids[0])).await;
+        assert_eq!(body["district"], "033");
+        assert_eq!(body["district_name"], "Woollahra");
+        let (_, body) = send(app, &format!("/api/properties/{}", ids[3])).await;
+        assert_eq!(body["district_name"], "999");
+
+        delete_suburb(&db, FILTER_SUBURB).await.unwrap();
+    }
+
+    const SORT_SUBURB: &str = "Properties Sort Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_properties_sorting() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SORT_SUBURB).await.unwrap();
+
+        // (address, price, sale date, weekly_rent): yields 5.20, 3.90, 6.76 and none
+        let date = |y, m| NaiveDate::from_ymd_opt(y, m, 1).unwrap();
+        let seeded = [
+            ("1 Sort St", 600_000, date(2024, 1), Some(600)),
+            ("2 Sort St", 800_000, date(2023, 6), Some(600)),
+            ("3 Sort St", 400_000, date(2024, 6), Some(520)),
+            ("4 Sort St", 500_000, date(2022, 1), None),
+        ];
+        let mut ids = Vec::new();
+        for (address, price, sold, rent) in seeded {
+            let mut fixture = PropertyFixture::new()
+                .address(address)
+                .suburb(SORT_SUBURB)
+                .sold(price, sold);
+            if let Some(rent) = rent {
+                fixture = fixture.weekly_rent(rent);
+            }
+            ids.push(fixture.insert(&db).await.unwrap() as i64);
+        }
+        let [a, b, c, d] = [ids[0], ids[1], ids[2], ids[3]];
+
+        let app = full_app(db.clone());
+        let base = "/api/properties?suburb=Properties%20Sort%20Testville";
+        let cases = [
+            ("", vec![a, b, c, d]),
+            ("&order=desc", vec![d, c, b, a]),
+            // Missing yields sort last in both directions
+            ("&sort=rental_yield&order=desc", vec![c, a, b, d]),
+            ("&sort=rental_yield", vec![b, a, c, d]),
+            ("&sort=price&order=desc", vec![b, a, d, c]),
+            // Equal rents fall back to id
+            ("&sort=weekly_rent", vec![c, a, b, d]),
+            ("&sort=sale_date&order=desc", vec![c, a, b, d]),
+            // The top-yield pages come straight from the database
+            (
+                "&sort=rental_yield&order=desc&page=1&page_size=2",
+                vec![c, a],
+            ),
+            (
+                "&sort=rental_yield&order=desc&page=2&page_size=2",
+                vec![b, d],
+            ),
+        ];
+
+        for (params, expected) in cases {
+            let uri = format!("{}{}", base, params);
+            let (status, body) = send(app.clone(), &uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            let found: Vec<i64> = body["properties"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["id"].as_i64().unwrap())
+                .collect();
+            assert_eq!(found, expected, "{}", uri);
+            assert_eq!(body["total"], 4, "{}", uri);
+        }
+
+        delete_suburb(&db, SORT_SUBURB).await.unwrap();
+    }
+
+    const DETAIL_SUBURB: &str = "Property Detail Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_property_by_id() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, DETAIL_SUBURB).await.unwrap();
+
+        let id = PropertyFixture::new()
+            .address("7 Detail Street")
+            .suburb(DETAIL_SUBURB)
+            .postcode("2994")
+            .property_type(PropertyType::Unit)
+            .bedrooms(2)
+            .sold(500_000, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap())
+            .weekly_rent(500)
+            .data_source("nsw_valuer_general")
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let app = crate::api::router().with_state(AppState {
+            tiers: Arc::new(TierConfig::default().with_key("detail-test", Tier::Full)),
+            map: Default::default(),
+            top_yields: Default::default(),
+            listing_cache: Default::default(),
+            ..test_state(db.clone())
+        });
+        let get = |uri: String| {
+            Request::get(uri)
+                .header(API_KEY_HEADER, "detail-test")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(get(format!("/api/properties/{}", id)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let detail: PropertyDetail = serde_json::from_slice(&body).unwrap();
+        assert_eq!(detail.id, id);
+        assert_eq!(detail.address, "7 Detail Street");
+        assert_eq!(detail.postcode.as_deref(), Some("2994"));
+        assert_eq!(detail.property_type, Some(PropertyType::Unit));
+        assert_eq!(detail.price, Some(500_000));
+        assert_eq!(detail.sale_date, NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert_eq!(detail.rental_yield, Some(5.2));
+        assert_eq!(detail.data_source.as_deref(), Some("nsw_valuer_general"));
+        assert_eq!(detail.data_quality, Some(DataQuality::Individual));
+        assert_eq!(detail.confidence_score, Some(Decimal::ONE));
+        assert_eq!(detail.price_per_sqm, None);
+        assert_eq!(detail.net_yield, None);
+        // One sale has no growth
+        assert_eq!(detail.annualized_growth_pct, None);
+
+        let add_sale = |price: i32, date: &str| {
+            sqlx::query(
+                "INSERT INTO sales_history (property_id, sale_price, sale_date, data_source)
+                 VALUES ($1, $2, $3::date, 'test')",
+            )
+            .bind(id)
+            .bind(price)
+            .bind(date.to_string())
+            .execute(&db)
+        };
+        let growth = |app: Router| async move {
+            let (status, body) = send(app, &format!("/api/properties/{}", id)).await;
+            assert_eq!(status, StatusCode::OK);
+            body["annualized_growth_pct"].clone()
+        };
+
+        // 400,000 to 500,000 over four years
+        add_sale(400_000, "2020-03-01").await.unwrap();
+        assert_eq!(growth(app.clone()).await, 5.74);
+        // A later correction on the same date is the last sale, and a fall
+        // is negative growth
+        add_sale(380_000, "2024-03-01").await.unwrap();
+        assert_eq!(growth(app.clone()).await, -1.27);
+
+        // Any expense parameter adds the net yield; the postcode has no
+        // council, so its rates are the default
+        let (status, body) = send(
+            app.clone(),
+            &format!("/api/properties/{}?strata=3000&management_fee_pct=8.5", id),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        // 26,000 rent - (8,300 fixed + 2,210 management) = 15,490 / 500,000
+        assert_eq!(
+            body["net_yield"],
+            json!({
+                "net_yield": 3.1,
+                "annual_rent": 26000,
+                "annual_expenses": 10510,
+                "council_rates": 1800,
+                "council_rates_source": "default",
+                "strata": 3000,
+                "insurance": 1500,
+                "management_fee_pct": "8.5",
+                "maintenance": 2000,
+            })
+        );
+
+        // Expenses above the rent give a negative yield
+        let (_, body) = send(
+            app.clone(),
+            &format!("/api/properties/{}?council_rates=100000", id),
+        )
+        .await;
+        assert_eq!(body["net_yield"]["net_yield"], -15.86);
+        assert_eq!(body["net_yield"]["council_rates_source"], "requested");
+
+        // Unknown ids are a JSON 404, non-numeric ids a 400
+        let (status, body) = send(app.clone(), "/api/properties/-1").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "not_found");
+        let (status, body) = send(app, "/api/properties/abc").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "id");
+
+        delete_suburb(&db, DETAIL_SUBURB).await.unwrap();
+    }
+
+    const PER_SQM_SUBURB: &str = "Price Per Sqm Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_price_per_sqm_enriched_and_served() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, PER_SQM_SUBURB).await.unwrap();
+
+        let house = PropertyFixture::new()
+            .address("3 Land St")
+            .suburb(PER_SQM_SUBURB)
+            .price(1_000_000)
+            .land_area_sqm(Decimal::new(4505, 1));
+        let unit = PropertyFixture::new()
+            .address("4/3 Land St")
+            .suburb(PER_SQM_SUBURB)
+            .property_type(PropertyType::Unit)
+            .price(600_000)
+            .land_area_sqm(Decimal::from(1200));
+        let records = [house, unit]
+            .iter()
+            .map(|f| crate::ingestion::enrich::calculate_price_per_sqm(f.record().clone()))
+            .collect();
+        let stats = write::write_properties(&db, records).await.unwrap();
+        assert_eq!(stats.inserted, 2);
+
+        let ids: Vec<i32> =
+            sqlx::query_scalar("SELECT id FROM properties WHERE suburb = $1 ORDER BY address")
+                .bind(PER_SQM_SUBURB)
+                .fetch_all(&db)
+                .await
+                .unwrap();
+        let app = full_app(db.clone());
+        let mut served = Vec::new();
+        for id in ids {
+            let (status, body) = send(app.clone(), &format!("/api/properties/{}", id)).await;
+            assert_eq!(status, StatusCode::OK);
+            served.push(body["price_per_sqm"].clone());
+        }
+        // 1,000,000 / 450.5; the unit's strata lot area is ignored
+        assert_eq!(served, vec![json!("2219.76"), Value::Null]);
+
+        delete_suburb(&db, PER_SQM_SUBURB).await.unwrap();
+    }
+
+    const LOW_YIELD_SUBURB: &str = "Low Yield Testville";
+
+    /// The legacy pipeline dropped properties below MIN_RENTAL_YIELD before
+    /// storing them; the threshold now only applies at query time
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_low_yield_property_stored_and_retrievable() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, LOW_YIELD_SUBURB).await.unwrap();
+
+        // 400 * 52 / 1,040,000 = 2.00%
+        let record = PropertyFixture::new()
+            .address("2 Low Yield St")
+            .suburb(LOW_YIELD_SUBURB)
+            .price(1_040_000)
+            .weekly_rent(400)
+            .record()
+            .clone();
+        let stats = write::write_properties(&db, vec![record]).await.unwrap();
+        assert_eq!(stats.inserted, 1);
+
+        let app = full_app(db.clone());
+        let base = "/api/properties?suburb=Low%20Yield%20Testville";
+        let (status, body) = send(app.clone(), base).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 1);
+        let property = &body["properties"][0];
+        assert_eq!(property["rental_yield"], 2.0);
+
+        let uri = format!("/api/properties/{}", property["id"]);
+        let (status, body) = send(app.clone(), &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["rental_yield"], 2.0);
+
+        let (status, body) = send(app, &format!("{}&min_yield=4", base)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 0);
+
+        delete_suburb(&db, LOW_YIELD_SUBURB).await.unwrap();
+    }
+
+    #[test]
+    fn test_top_yields_quality_tiers() {
+        let query = |min_quality| TopYieldsQuery {
+            min_quality,
+            ..Default::default()
+        };
+
+        assert_eq!(query(None).qualities(), None);
+        assert_eq!(
+            query(Some(DataQuality::Aggregated)).qualities(),
+            Some(vec![
+                DataQuality::Individual,
+                DataQuality::Listing,
+                DataQuality::Aggregated
+            ])
+        );
+        assert_eq!(
+            query(Some(DataQuality::Listing)).qualities(),
+            Some(vec![DataQuality::Individual, DataQuality::Listing])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_top_yields_params_rejected() {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = crate::api::router().with_state(test_state(db));
+
+        for (uri, field) in [
+            ("/api/properties/top-yields?min_quality=junk", "min_quality"),
+            ("/api/properties/top-yields?estimated=no", "estimated"),
+            ("/api/properties/top-yields?limit=0", "limit"),
+            ("/api/properties/top-yields?limit=501", "limit"),
+        ] {
+            let (status, body) = send(app.clone(), uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["field"], field, "{}", uri);
+        }
+    }
+
+    const TOP_YIELDS_SUBURB: &str = "Top Yields Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_top_yields_quality_gating() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, TOP_YIELDS_SUBURB).await.unwrap();
+
+        // Yields on a $520,000 price: rent / 100 %
+        let mut ids = Vec::new();
+        for (n, rent, quality, estimated) in [
+            (1, 900, DataQuality::Individual, false),
+            (2, 800, DataQuality::Aggregated, true),
+            (3, 1000, DataQuality::Estimated, false),
+            (4, 700, DataQuality::Listing, false),
+            // 50%, a parse error rather than a bargain
+            (5, 5000, DataQuality::Individual, false),
+        ] {
+            let property = PropertyFixture::new()
+                .address(&format!("{} Top Yield St", n))
+                .suburb(TOP_YIELDS_SUBURB)
+                .state(AusState::ACT)
+                .postcode("2600")
+                .price(520_000)
+                .weekly_rent(rent)
+                .data_quality(quality, 1.0);
+            let property = match estimated {
+                true => property.rental_match_method(RentalMatchMethod::Suburb),
+                false => property,
+            };
+            let id = property.insert(&db).await.unwrap();
+            ids.push(id as i64);
+        }
+
+        let app = full_app(db.clone());
+        let listed = |body: &Value| -> Vec<i64> {
+            body["properties"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["id"].as_i64().unwrap())
+                .filter(|id| ids.contains(id))
+                .collect()
+        };
+        let base = "/api/properties/top-yields?state=ACT&limit=500";
+
+        let (status, body) = send(app.clone(), base).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed(&body), vec![ids[2], ids[0], ids[1], ids[3]]);
+        let top = body["properties"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["id"] == ids[2])
+            .unwrap();
+        assert_eq!(top["rental_yield"], 10.0);
+        assert_eq!(top["data_quality"], "Estimated");
+        assert_eq!(top["data_source"], "test");
+        assert_eq!(top["is_rental_estimated"], false);
+        assert!(top.get("rental_match_method").is_none());
+        let estimated = body["properties"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["id"] == ids[1])
+            .unwrap();
+        assert_eq!(estimated["is_rental_estimated"], true);
+        assert_eq!(estimated["rental_match_method"], "suburb");
+
+        let (_, body) = send(app.clone(), &format!("{}&min_quality=aggregated", base)).await;
+        assert_eq!(listed(&body), vec![ids[0], ids[1], ids[3]]);
+
+        let (_, body) = send(
+            app.clone(),
+            &format!("{}&min_quality=aggregated&estimated=false", base),
+        )
+        .await;
+        assert_eq!(listed(&body), vec![ids[0], ids[3]]);
+
+        let (_, body) = send(app.clone(), &format!("{}&min_quality=individual", base)).await;
+        assert_eq!(listed(&body), vec![ids[0]]);
+
+        // A higher ceiling lets the implausible yield through
+        let mut raised = test_state(db.clone());
+        raised.top_yields.max_yield = Decimal::from(60);
+        let (_, body) = send(crate::api::router().with_state(raised), base).await;
+        assert_eq!(listed(&body)[0], ids[4]);
+
+        delete_suburb(&db, TOP_YIELDS_SUBURB).await.unwrap();
+    }
+
+    #[test]
+    fn test_etag_matching() {
+        let etag = HeaderValue::from_static("W/\"public-3-1700000000\"");
+        for (header, matches) in [
+            ("W/\"public-3-1700000000\"", true),
+            ("\"public-3-1700000000\"", true),
+            ("\"other\", W/\"public-3-1700000000\"", true),
+            ("*", true),
+            ("W/\"public-4-1700000000\"", false),
+            ("W/\"full-3-1700000000\"", false),
+        ] {
+            let header = HeaderValue::from_str(header).unwrap();
+            assert_eq!(etag_matches(&header, &etag), matches, "{:?}", header);
+        }
+    }
+
+    const ETAG_SUBURB: &str = "Etag Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_listing_conditional_requests() {
+        use axum::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, ETAG_SUBURB).await.unwrap();
+        let mut ids = Vec::new();
+        for n in 1..=2 {
+            let id = PropertyFixture::new()
+                .address(&format!("{} Etag Street", n))
+                .suburb(ETAG_SUBURB)
+                .insert(&db)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let uri = "/api/properties?suburb=Etag%20Testville";
+        let get = |etag: Option<HeaderValue>| {
+            let mut request = Request::get(uri);
+            if let Some(etag) = etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = get(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], "public, max-age=300");
+        let etag = response.headers()[ETAG].clone();
+        assert!(etag.to_str().unwrap().starts_with("W/\"public-2-"));
+
+        let response = get(Some(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[ETAG], etag);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+
+        sqlx::query(
+            r#"
+            UPDATE properties
+            SET weekly_rent = 999, last_updated = last_updated + INTERVAL '1 second'
+            WHERE id = $1
+            "#,
+        )
+        .bind(ids[1])
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let response = get(Some(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[ETAG], etag);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(page["total"], 2);
+
+        // Removing a property changes the tag too
+        let etag = get(None).await.unwrap().headers()[ETAG].clone();
+        sqlx::query("DELETE FROM properties WHERE id = $1")
+            .bind(ids[0])
+            .execute(&db)
+            .await
+            .unwrap();
+        let response = get(Some(etag)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        delete_suburb(&db, ETAG_SUBURB).await.unwrap();
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = PropertyCursor {
+            sort: SortField::RentalYield,
+            order: SortOrder::Desc,
+            rental_yield: Some(Decimal::new(51234, 4)),
+            id: 42,
+        };
+        let encoded = cursor.encode();
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(PropertyCursor::decode(&encoded), Some(cursor));
+
+        let past_yields = PropertyCursor {
+            rental_yield: None,
+            ..cursor
+        };
+        assert_eq!(
+            PropertyCursor::decode(&past_yields.encode()),
+            Some(past_yields)
+        );
+
+        for bad in ["", "not base64!", "bm9wZQ"] {
+            assert_eq!(PropertyCursor::decode(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_cursor_params_validated() {
+        let id_cursor = PropertyCursor {
+            sort: SortField::Id,
+            order: SortOrder::Asc,
+            rental_yield: None,
+            id: 7,
+        }
+        .encode();
+        let query = |sort, page, cursor: &str| PropertiesQuery {
+            sort,
+            page,
+            cursor: Some(cursor.to_string()),
+            ..Default::default()
+        };
+
+        assert!(query(None, None, &id_cursor).validate().is_ok());
+        for (params, field) in [
+            (query(None, None, "garbage"), "cursor"),
+            (
+                query(Some(SortField::RentalYield), None, &id_cursor),
+                "cursor",
+            ),
+            (query(Some(SortField::Price), None, &id_cursor), "sort"),
+            (query(None, Some(2), &id_cursor), "page"),
+        ] {
+            assert_eq!(params.validate().unwrap_err().field, field, "{:?}", params);
+        }
+    }
+
+    const CURSOR_SUBURB: &str = "Cursor Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_cursor_walk_has_no_gaps_or_duplicates() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, CURSOR_SUBURB).await.unwrap();
+
+        // Tied yields, missing rents and a zero price, so ties and the
+        // null-yield tail are crossed by page boundaries
+        for n in 0..23 {
+            let mut fixture = PropertyFixture::new()
+                .address(&format!("{} Cursor Street", n))
+                .suburb(CURSOR_SUBURB)
+                .price(if n == 5 { 0 } else { 500_000 });
+            if n % 7 != 3 {
+                fixture = fixture.weekly_rent(400 + (n % 4) * 50);
+            }
+            fixture.insert(&db).await.unwrap();
+        }
+
+        let app = full_app(db.clone());
+        let base = "/api/properties?suburb=Cursor%20Testville";
+
+        for (sort, order) in [
+            ("id", "asc"),
+            ("id", "desc"),
+            ("rental_yield", "asc"),
+            ("rental_yield", "desc"),
+        ] {
+            let ids = |body: &Value| -> Vec<i64> {
+                body["properties"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| p["id"].as_i64().unwrap())
+                    .collect()
+            };
+            let (status, all) = send(
+                app.clone(),
+                &format!("{}&sort={}&order={}&page_size=500", base, sort, order),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            let expected = ids(&all);
+            assert_eq!(expected.len(), 23);
+            assert!(all["next_cursor"].is_null());
+
+            let mut walked = Vec::new();
+            let mut uri = format!("{}&sort={}&order={}&page_size=4", base, sort, order);
+            loop {
+                let (status, page) = send(app.clone(), &uri).await;
+                assert_eq!(status, StatusCode::OK, "{}", uri);
+                walked.extend(ids(&page));
+                match page["next_cursor"].as_str() {
+                    Some(cursor) => {
+                        uri = format!(
+                            "{}&sort={}&order={}&page_size=4&cursor={}",
+                            base, sort, order, cursor
+                        );
+                    }
+                    None => break,
+                }
+            }
+            assert_eq!(walked, expected, "sort={} order={}", sort, order);
+        }
+
+        // Writes between pages neither repeat nor skip what's already listed
+        let (_, first) = send(app.clone(), &format!("{}&page_size=10", base)).await;
+        let cursor = first["next_cursor"].as_str().unwrap().to_string();
+        PropertyFixture::new()
+            .address("0 Early Lane")
+            .suburb(CURSOR_SUBURB)
+            .insert(&db)
+            .await
+            .unwrap();
+        let (_, rest) = send(
+            app.clone(),
+            &format!("{}&page_size=500&cursor={}", base, cursor),
+        )
+        .await;
+        let mut seen: Vec<i64> = first["properties"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .chain(rest["properties"].as_array().unwrap())
+            .map(|p| p["id"].as_i64().unwrap())
+            .collect();
+        assert_eq!(seen.len(), 24);
+        seen.dedup();
+        assert_eq!(seen.len(), 24);
+
+        delete_suburb(&db, CURSOR_SUBURB).await.unwrap();
+    }
+
+    #[test]
+    fn test_changes_params_validated() {
+        let query = |since: &str, cursor: Option<String>| PropertyChangesQuery {
+            since: since.to_string(),
+            cursor,
+            ..Default::default()
+        };
+        for since in [
+            "2025-01-01T00:00:00Z",
+            "2025-01-01T10:30:00.123456+10:00",
+            "2025-01-01t00:00:00z",
+        ] {
+            assert!(query(since, None).validate().is_ok(), "{}", since);
+        }
+        for since in [
+            "",
+            "2025-01-01",
+            "2025-01-01T00:00:00",
+            "2025-01-01 00:00",
+            "1735689600",
+            "2025-13-01T00:00:00Z",
+            "yesterday",
+        ] {
+            let err = query(since, None).validate().unwrap_err();
+            assert_eq!(err.field, "since", "{}", since);
+        }
+
+        let since = parse_rfc3339("2025-01-01T00:00:00Z").unwrap();
+        let cursor = ChangesCursor {
+            since,
+            until: parse_rfc3339("2025-02-01T00:00:00Z").unwrap(),
+            last_updated: since.naive_utc(),
+            id: 7,
+        };
+        assert_eq!(ChangesCursor::decode(&cursor.encode()), Some(cursor));
+        assert!(query("2025-01-01T00:00:00Z", Some(cursor.encode()))
+            .validate()
+            .is_ok());
+        // The same instant in another offset is the same since
+        assert!(query("2025-01-01T10:00:00+10:00", Some(cursor.encode()))
+            .validate()
+            .is_ok());
+        for (since, cursor) in [
+            ("2024-12-31T00:00:00Z", cursor.encode()),
+            ("2025-01-01T00:00:00Z", "garbage".to_string()),
+        ] {
+            let err = query(since, Some(cursor)).validate().unwrap_err();
+            assert_eq!(err.field, "cursor");
+        }
+    }
+
+    const CHANGES_SUBURB: &str = "Delta Sync Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_property_changes_sync() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, CHANGES_SUBURB).await.unwrap();
+
+        // Stamped long before any other fixture, in pairs so page boundaries
+        // fall between equal timestamps
+        let base = NaiveDate::from_ymd_opt(1990, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let mut ids = Vec::new();
+        for n in 0..9 {
+            let id = PropertyFixture::new()
+                .address(&format!("{} Delta Street", n))
+                .suburb(CHANGES_SUBURB)
+                .last_updated(base + chrono::Duration::hours(n / 2))
+                .insert(&db)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let ours = |body: &Value| -> Vec<i32> {
+            body["properties"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|p| p["suburb"] == CHANGES_SUBURB)
+                .map(|p| p["id"].as_i64().unwrap() as i32)
+                .collect()
+        };
+
+        // Walk pages of 4 until every fixture is seen; the window is fixed by
+        // the first page
+        let since = "1989-12-31T00:00:00Z";
+        let mut seen = Vec::new();
+        let mut server_time = None;
+        let mut uri = format!("/api/properties/changes?since={}&page_size=4", since);
+        while seen.len() < ids.len() {
+            let (status, page) = send(app.clone(), &uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            let time = page["server_time"].as_str().unwrap().to_string();
+            assert_eq!(server_time.get_or_insert(time.clone()), &time);
+            assert!(page["properties"][0]["last_updated"].is_string());
+            seen.extend(ours(&page));
+            // The fixtures may be the newest rows of all, ending the walk
+            let Some(cursor) = page["next_cursor"].as_str() else {
+                break;
+            };
+            uri = format!(
+                "/api/properties/changes?since={}&page_size=4&cursor={}",
+                since, cursor
+            );
+        }
+        assert_eq!(seen, ids);
+
+        // A later sync from that server_time sees only what changed since
+        sqlx::query("UPDATE properties SET price = 654321, last_updated = NOW() WHERE id = $1")
+            .bind(ids[2])
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM properties WHERE id = $1")
+            .bind(ids[5])
+            .execute(&db)
+            .await
+            .unwrap();
+        let since = server_time.unwrap().replace('+', "%2B");
+        let mut changed = Vec::new();
+        let mut deleted = Vec::new();
+        let mut uri = format!("/api/properties/changes?since={}&page_size=500", since);
+        loop {
+            let (status, page) = send(app.clone(), &uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            changed.extend(ours(&page));
+            deleted.extend(
+                page["deleted_ids"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|id| id.as_i64().unwrap() as i32),
+            );
+            match page["next_cursor"].as_str() {
+                Some(cursor) => {
+                    uri = format!(
+                        "/api/properties/changes?since={}&page_size=500&cursor={}",
+                        since, cursor
+                    )
+                }
+                None => break,
+            }
+        }
+        assert_eq!(changed, vec![ids[2]]);
+        assert!(deleted.contains(&ids[5]));
+
+        let (status, body) = send(app, "/api/properties/changes?since=1990-01-01").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "since");
+
+        delete_suburb(&db, CHANGES_SUBURB).await.unwrap();
+    }
+
+    const LIST_SUBURBS: [&str; 2] = ["Multi Filter Northville", "Multi Filter Southville"];
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_multi_value_filters_with_paging_and_sorting() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        for suburb in LIST_SUBURBS {
+            delete_suburb(&db, suburb).await.unwrap();
+        }
+
+        // (suburb, state, postcode, weekly rent): yields 5.20 up to 6.24 on a
+        // $500k price, rising with the rent
+        let seeded = [
+            (LIST_SUBURBS[0], AusState::NSW, "2981", 500),
+            (LIST_SUBURBS[0], AusState::NSW, "2982", 520),
+            (LIST_SUBURBS[1], AusState::QLD, "4981", 540),
+            (LIST_SUBURBS[1], AusState::QLD, "4981", 560),
+            (LIST_SUBURBS[1], AusState::VIC, "3981", 580),
+            (LIST_SUBURBS[0], AusState::NSW, "2981", 600),
+        ];
+        let mut ids = Vec::new();
+        for (n, (suburb, state, postcode, rent)) in seeded.into_iter().enumerate() {
+            let id = PropertyFixture::new()
+                .address(&format!("{} List Street", n))
+                .suburb(suburb)
+                .state(state)
+                .postcode(postcode)
+                .price(500_000)
+                .weekly_rent(rent)
+                .insert(&db)
+                .await
+                .unwrap();
+            ids.push(id as i64);
+        }
+        let app = full_app(db.clone());
+        let ids_of = |body: &Value| -> Vec<i64> {
+            body["properties"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["id"].as_i64().unwrap())
+                .collect()
+        };
+        let suburbs = "suburb=multi%20filter%20northville,Multi%20Filter%20Southville";
+
+        let cases = [
+            (
+                "state=NSW,QLD",
+                vec![ids[0], ids[1], ids[2], ids[3], ids[5]],
+            ),
+            ("state=VIC", vec![ids[4]]),
+            ("postcode=2981,4981", vec![ids[0], ids[2], ids[3], ids[5]]),
+            ("postcode=2982&state=QLD,VIC", vec![]),
+            (
+                "postcode=4981,3981&state=QLD,VIC",
+                vec![ids[2], ids[3], ids[4]],
+            ),
+        ];
+        for (filters, expected) in cases {
+            let uri = format!("/api/properties?{}&{}", suburbs, filters);
+            let (status, body) = send(app.clone(), &uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            assert_eq!(ids_of(&body), expected, "{}", uri);
+            assert_eq!(body["total"], expected.len(), "{}", uri);
+        }
+
+        // Highest yield first, two a page, by page number and by cursor
+        let base = format!(
+            "/api/properties?{}&state=NSW,QLD&sort=rental_yield&order=desc&page_size=2",
+            suburbs
+        );
+        let expected = [vec![ids[5], ids[3]], vec![ids[2], ids[1]], vec![ids[0]]];
+        let mut uri = base.clone();
+        for (n, page_ids) in expected.iter().enumerate() {
+            let (status, page) = send(app.clone(), &format!("{}&page={}", base, n + 1)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(&ids_of(&page), page_ids, "page {}", n + 1);
+            assert_eq!(page["total"], 5);
+            assert_eq!(page["total_pages"], 3);
+
+            let (_, page) = send(app.clone(), &uri).await;
+            assert_eq!(&ids_of(&page), page_ids, "cursor page {}", n + 1);
+            if let Some(cursor) = page["next_cursor"].as_str() {
+                uri = format!("{}&cursor={}", base, cursor);
+            }
+        }
+
+        for suburb in LIST_SUBURBS {
+            delete_suburb(&db, suburb).await.unwrap();
+        }
+    }
+}
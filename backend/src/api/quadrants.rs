@@ -0,0 +1,340 @@
+//! Suburb quadrants endpoint - classify suburbs by rental yield vs price growth
+
+use crate::analytics::quadrants::{classify_suburbs, QuadrantReport, SuburbMetrics, Thresholds};
+use crate::analytics::suppression::{apply_suppression, Aggregate, SuppressionConfig};
+use crate::api::params::{
+    check_finite_range, check_range, ParamError, ValidateParams, ValidatedListParams,
+};
+use crate::api::AppState;
+use crate::format::round_yield_f64;
+use crate::ingestion::types::State as AusState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+/// Query parameters for GET /api/suburbs/quadrants
+#[derive(Debug, Deserialize)]
+pub struct QuadrantQuery {
+    pub state: AusState,
+    pub bedrooms: Option<i32>,
+    /// Yield cut-off (%); defaults to the state median
+    pub yield_threshold: Option<f64>,
+    /// Growth cut-off (%); defaults to the state median
+    pub growth_threshold: Option<f64>,
+}
+
+impl ValidateParams for QuadrantQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_range("bedrooms", self.bedrooms, 0..=20)?;
+        check_finite_range("yield_threshold", self.yield_threshold, 0.0..=100.0)?;
+        check_finite_range("growth_threshold", self.growth_threshold, -100.0..=1000.0)
+    }
+}
+
+/// JSON response for GET /api/suburbs/quadrants
+#[derive(Debug, Serialize)]
+pub struct QuadrantResponse {
+    pub state: AusState,
+    pub bedrooms: Option<i32>,
+    #[serde(flatten)]
+    pub report: QuadrantReport,
+}
+
+/// Raw per-suburb metrics and the number of records behind each
+#[derive(Debug, sqlx::FromRow)]
+pub struct SuburbMetricsRow {
+    pub suburb: String,
+    pub postcode: Option<String>,
+    pub rental_yield: Option<f64>,
+    pub yield_sample: i64,
+    pub growth: Option<f64>,
+    /// Sales in the smaller of the two 12-month windows
+    pub growth_sample: i64,
+}
+
+/// GET /api/suburbs/quadrants - yield/growth quadrant for every suburb in a state
+pub async fn get_quadrants(
+    State(state): State<AppState>,
+    ValidatedListParams(params): ValidatedListParams<QuadrantQuery>,
+) -> Result<Json<QuadrantResponse>, StatusCode> {
+    let rows = fetch_suburb_metrics(&state.read_db, params.state, params.bedrooms)
+        .await
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let metrics = suppress_small_samples(rows, &state.suppression);
+    let thresholds = Thresholds::resolve(params.yield_threshold, params.growth_threshold, &metrics);
+
+    Ok(Json(QuadrantResponse {
+        state: params.state,
+        bedrooms: params.bedrooms,
+        report: classify_suburbs(metrics, thresholds),
+    }))
+}
+
+/// Drop any metric built from too few records, leaving the suburb unclassified
+fn suppress_small_samples(
+    rows: Vec<SuburbMetricsRow>,
+    config: &SuppressionConfig,
+) -> Vec<SuburbMetrics> {
+    // No parent regions here, so nothing rolls up
+    let no_parent = |_: &usize| None::<()>;
+
+    let published = |values: Vec<Aggregate<usize>>| -> Vec<Option<f64>> {
+        apply_suppression(values, no_parent, config)
+            .into_iter()
+            .map(|p| p.value)
+            .collect()
+    };
+
+    let yields = published(
+        rows.iter()
+            .enumerate()
+            .filter_map(|(i, row)| {
+                Some(Aggregate {
+                    key: i,
+                    value: row.rental_yield?,
+                    sample_size: row.yield_sample,
+                })
+            })
+            .collect(),
+    );
+    let growths = published(
+        rows.iter()
+            .enumerate()
+            .filter_map(|(i, row)| {
+                Some(Aggregate {
+                    key: i,
+                    value: row.growth?,
+                    sample_size: row.growth_sample,
+                })
+            })
+            .collect(),
+    );
+
+    // Re-align the published values with their rows
+    let mut yields = yields.into_iter();
+    let mut growths = growths.into_iter();
+
+    rows.into_iter()
+        .map(|row| SuburbMetrics {
+            rental_yield: row
+                .rental_yield
+                .and_then(|_| yields.next().flatten())
+                .map(round_yield_f64),
+            growth: row.growth.and_then(|_| growths.next().flatten()),
+            suburb: row.suburb,
+            postcode: row.postcode,
+        })
+        .collect()
+}
+
+/// Median yield and 12-month median sale price growth per suburb
+///
+/// Growth compares the median sale price over the last 12 months with the
+/// 12 months before that, using sales_history.
+pub async fn fetch_suburb_metrics(
+    db: &PgPool,
+    state: AusState,
+    bedrooms: Option<i32>,
+) -> Result<Vec<SuburbMetricsRow>, sqlx::Error> {
+    sqlx::query_as::<_, SuburbMetricsRow>(
+        r#"
+        WITH yields AS (
+            SELECT
+                suburb,
+                postcode,
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY rental_yield) AS rental_yield,
+                COUNT(*) AS yield_sample
+            FROM properties
+            WHERE state = $1
+              AND ($2::INTEGER IS NULL OR bedrooms = $2)
+              AND rental_yield IS NOT NULL
+            GROUP BY suburb, postcode
+        ),
+        sales AS (
+            SELECT
+                p.suburb,
+                p.postcode,
+                sh.sale_price,
+                sh.sale_date > CURRENT_DATE - INTERVAL '12 months' AS recent
+            FROM sales_history sh
+            JOIN properties p ON p.id = sh.property_id
+            WHERE p.state = $1
+              AND ($2::INTEGER IS NULL OR p.bedrooms = $2)
+              AND sh.sale_date > CURRENT_DATE - INTERVAL '24 months'
+              AND sh.sale_date <= CURRENT_DATE
+        ),
+        growth AS (
+            SELECT
+                suburb,
+                postcode,
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY sale_price) FILTER (WHERE recent) AS recent_median,
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY sale_price) FILTER (WHERE NOT recent) AS prior_median,
+                COUNT(*) FILTER (WHERE recent) AS recent_sample,
+                COUNT(*) FILTER (WHERE NOT recent) AS prior_sample
+            FROM sales
+            GROUP BY suburb, postcode
+        )
+        SELECT
+            COALESCE(y.suburb, g.suburb) AS suburb,
+            COALESCE(y.postcode, g.postcode) AS postcode,
+            y.rental_yield,
+            COALESCE(y.yield_sample, 0) AS yield_sample,
+            (g.recent_median / NULLIF(g.prior_median, 0) - 1) * 100 AS growth,
+            LEAST(COALESCE(g.recent_sample, 0), COALESCE(g.prior_sample, 0)) AS growth_sample
+        FROM yields y
+        FULL OUTER JOIN growth g
+            ON g.suburb = y.suburb AND COALESCE(g.postcode, '') = COALESCE(y.postcode, '')
+        ORDER BY 1, 2
+        "#,
+    )
+    .bind(state)
+    .bind(bedrooms)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{test_state, PropertyFixture, SaleFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn row(
+        suburb: &str,
+        rental_yield: Option<f64>,
+        yield_sample: i64,
+        growth: Option<f64>,
+        growth_sample: i64,
+    ) -> SuburbMetricsRow {
+        SuburbMetricsRow {
+            suburb: suburb.to_string(),
+            postcode: None,
+            rental_yield,
+            yield_sample,
+            growth,
+            growth_sample,
+        }
+    }
+
+    #[test]
+    fn test_small_samples_suppressed_per_metric() {
+        let config = SuppressionConfig::default();
+        let metrics = suppress_small_samples(
+            vec![
+                row("A", Some(4.0), 10, Some(3.0), 10),
+                row("B", Some(5.0), 2, Some(6.0), 10), // Yield too thin
+                row("C", None, 0, Some(1.0), 4),       // Growth too thin
+                row("D", Some(3.0), 8, None, 0),
+            ],
+            &config,
+        );
+
+        let values: Vec<(Option<f64>, Option<f64>)> =
+            metrics.iter().map(|m| (m.rental_yield, m.growth)).collect();
+        assert_eq!(
+            values,
+            vec![
+                (Some(4.0), Some(3.0)),
+                (None, Some(6.0)),
+                (None, None),
+                (Some(3.0), None),
+            ]
+        );
+    }
+
+    const SUBURB: &str = "Quadrant Testville";
+
+    async fn cleanup(db: &PgPool) {
+        sqlx::query("DELETE FROM properties WHERE suburb LIKE $1")
+            .bind(format!("{}%", SUBURB))
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_quadrants_endpoint() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        // One suburb with yields and two years of sales, one with yields only
+        for (suburb, with_sales) in [
+            (format!("{} A", SUBURB), true),
+            (format!("{} B", SUBURB), false),
+        ] {
+            let id = PropertyFixture::new()
+                .address("1 Quadrant St")
+                .suburb(&suburb)
+                .state(AusState::TAS)
+                .postcode("7999")
+                .price(500_000)
+                .weekly_rent(500)
+                .rental_yield("5.20")
+                .insert(&db)
+                .await
+                .unwrap();
+
+            if with_sales {
+                let today = chrono::Utc::now().date_naive();
+                for (price, days_ago) in [(400_000, 500), (500_000, 30)] {
+                    SaleFixture::new(id, price, today - chrono::Days::new(days_ago))
+                        .insert(&db)
+                        .await
+                        .unwrap();
+                }
+            }
+        }
+
+        let app = crate::api::router().with_state(AppState {
+            suppression: SuppressionConfig {
+                min_sample_size: 1,
+                roll_up: false,
+            },
+            ..test_state(db.clone())
+        });
+        let response = app
+            .oneshot(
+                Request::get("/api/suburbs/quadrants?state=TAS&bedrooms=3&yield_threshold=5&growth_threshold=10")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+
+        let classified = json["suburbs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["suburb"] == format!("{} A", SUBURB))
+            .unwrap();
+        assert_eq!(classified["quadrant"], "high_yield_high_growth");
+        assert_eq!(classified["growth"], 25.0);
+
+        let unclassified = json["unclassified"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["suburb"] == format!("{} B", SUBURB))
+            .unwrap();
+        assert!(unclassified["growth"].is_null());
+
+        cleanup(&db).await;
+    }
+}
@@ -0,0 +1,64 @@
+//! In-memory fixed-window rate limiting, keyed by caller (e.g. API key)
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Allows at most `max_requests` per key in each `window`
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_requests: u32,
+    window: Duration,
+    /// Start of the current window and requests seen in it, per key
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        RateLimiter {
+            max_requests,
+            window,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a request for `key`; false if it's over the limit
+    pub fn check(&self, key: &str) -> bool {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> bool {
+        let mut windows = self.windows.lock().unwrap();
+
+        // Drop expired windows so the map doesn't grow without bound
+        windows.retain(|_, (started, _)| now.duration_since(*started) < self.window);
+
+        let (_, count) = windows.entry(key.to_string()).or_insert((now, 0));
+        if *count >= self.max_requests {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_per_key_and_window() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert!(limiter.check_at("a", start));
+        assert!(limiter.check_at("a", start));
+        assert!(!limiter.check_at("a", start));
+
+        // Other keys have their own allowance
+        assert!(limiter.check_at("b", start));
+
+        // A new window resets the count
+        assert!(limiter.check_at("a", start + Duration::from_secs(61)));
+    }
+}
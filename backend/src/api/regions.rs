@@ -0,0 +1,185 @@
+//! Regions endpoint - the ABS SA3/SA4 regions postcodes roll up into
+
+use crate::api::params::{check_length, ParamError, ValidateParams, ValidatedListParams};
+use crate::api::AppState;
+use crate::ingestion::types::State as AusState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+/// Query parameters for GET /api/regions
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RegionsQuery {
+    pub state: Option<AusState>,
+    /// Only SA3s within this SA4
+    pub sa4_code: Option<String>,
+}
+
+impl ValidateParams for RegionsQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("sa4_code", self.sa4_code.as_deref(), 3)
+    }
+}
+
+/// An SA3 region with the postcodes mapped to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Region {
+    pub sa3_code: String,
+    pub sa3_name: String,
+    pub sa4_code: String,
+    pub sa4_name: Option<String>,
+    pub state: Option<AusState>,
+    pub postcodes: Vec<String>,
+}
+
+/// GET /api/regions - SA3 regions and their postcodes, by name
+pub async fn get_regions(
+    State(state): State<AppState>,
+    ValidatedListParams(params): ValidatedListParams<RegionsQuery>,
+) -> Result<Json<Vec<Region>>, StatusCode> {
+    fetch_regions(&state.read_db, &params)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+pub async fn fetch_regions(db: &PgPool, filter: &RegionsQuery) -> Result<Vec<Region>, sqlx::Error> {
+    sqlx::query_as::<_, Region>(
+        r#"
+        SELECT sa3_code, sa3_name, sa4_code, MAX(sa4_name) AS sa4_name, state,
+               array_agg(postcode ORDER BY postcode) AS postcodes
+        FROM regions
+        WHERE ($1::state_enum IS NULL OR state = $1)
+          AND ($2::text IS NULL OR sa4_code = $2)
+        GROUP BY sa3_code, sa3_name, sa4_code, state
+        ORDER BY sa3_name, sa3_code
+        "#,
+    )
+    .bind(filter.state)
+    .bind(&filter.sa4_code)
+    .fetch_all(db)
+    .await
+}
+
+/// Postcodes in a region given as an SA3 code, an SA4 code or an SA3 name
+/// (case-insensitive); empty when nothing matches
+pub async fn region_postcodes(db: &PgPool, region: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT postcode
+        FROM regions
+        WHERE sa3_code = $1 OR sa4_code = $1 OR LOWER(sa3_name) = LOWER($1)
+        ORDER BY postcode
+        "#,
+    )
+    .bind(region.trim())
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::{parse, write, RawData};
+    use crate::test_support::test_state;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn get(db: &PgPool, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_filters() {
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+
+        let (status, body) = get(&db, "/api/regions?sa4_code=12345").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "sa4_code");
+
+        let (status, body) = get(&db, "/api/regions?state=XX").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "state");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_regions_from_fixture_correspondence() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+
+        let fixture = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/abs_postcode_sa3.csv");
+        let regions =
+            parse::parse_abs_postcode_regions(RawData::Bytes(std::fs::read(fixture).unwrap()))
+                .await
+                .unwrap();
+        let postcodes: Vec<String> = regions.iter().map(|r| r.postcode.clone()).collect();
+        sqlx::query("DELETE FROM regions WHERE postcode = ANY($1)")
+            .bind(&postcodes)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let stats = write::write_postcode_regions(&db, regions.clone())
+            .await
+            .unwrap();
+        assert_eq!(stats.inserted, regions.len());
+        // Reloading the same file updates in place
+        let stats = write::write_postcode_regions(&db, regions).await.unwrap();
+        assert_eq!((stats.inserted, stats.updated), (0, postcodes.len()));
+
+        let ratio = sqlx::query_scalar::<_, f64>(
+            "SELECT ratio::FLOAT8 FROM regions WHERE postcode = '2141'",
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(ratio, 0.669);
+
+        let (status, body) = get(&db, "/api/regions?state=NSW&sa4_code=125").await;
+        assert_eq!(status, StatusCode::OK);
+        let regions: Vec<Region> = serde_json::from_value(body).unwrap();
+        let parramatta = regions.iter().find(|r| r.sa3_code == "12504").unwrap();
+        assert_eq!(parramatta.sa3_name, "Parramatta");
+        assert!(parramatta.postcodes.contains(&"2141".to_string()));
+        assert!(parramatta.postcodes.contains(&"2150".to_string()));
+
+        // A region filter resolves by code or by name
+        assert_eq!(
+            region_postcodes(&db, "sydney inner city").await.unwrap(),
+            region_postcodes(&db, "11703").await.unwrap()
+        );
+        assert!(region_postcodes(&db, "11703")
+            .await
+            .unwrap()
+            .contains(&"2042".to_string()));
+        assert!(region_postcodes(&db, "Nowhere").await.unwrap().is_empty());
+
+        sqlx::query("DELETE FROM regions WHERE postcode = ANY($1)")
+            .bind(&postcodes)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+//! Rent history endpoint - rental median trend for the key a property matches
+
+use crate::api::AppState;
+use crate::ingestion::types::{RentalLookup, State as AusState};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+/// How far back the series goes (8 quarters)
+pub const RENT_HISTORY_MONTHS: i32 = 24;
+
+/// One rental median in the series
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RentPoint {
+    pub period: NaiveDate,
+    pub median_rent: i32,
+    pub sample_size: Option<i32>,
+}
+
+/// Response for GET /api/properties/:id/rent-history
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RentHistory {
+    pub property_id: i32,
+    pub state: AusState,
+    pub postcode: String,
+    pub bedrooms: i32,
+    /// The bedroom count was estimated during ingestion, not sourced
+    pub bedrooms_estimated: bool,
+    /// Period of the median used for the property's stored rent and yield
+    pub yield_period: Option<NaiveDate>,
+    /// Oldest first
+    pub points: Vec<RentPoint>,
+}
+
+/// GET /api/properties/:id/rent-history - median rent over the last 8 quarters
+/// 404 when the property is unknown, can't be keyed, or has no medians
+pub async fn get_rent_history(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<RentHistory>, StatusCode> {
+    let db_error = |e: sqlx::Error| {
+        error!("Database error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let property = sqlx::query_as::<
+        _,
+        (
+            AusState,
+            Option<String>,
+            Option<i32>,
+            Option<bool>,
+            Option<NaiveDate>,
+        ),
+    >(
+        r#"
+        SELECT state, postcode, bedrooms, is_bedrooms_estimated, rental_period
+        FROM properties
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&state.read_db)
+    .await
+    .map_err(db_error)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let (property_state, postcode, bedrooms, bedrooms_estimated, yield_period) = property;
+    let lookup = RentalLookup {
+        state: property_state,
+        postcode: postcode.ok_or(StatusCode::NOT_FOUND)?,
+        bedrooms: bedrooms.ok_or(StatusCode::NOT_FOUND)?,
+    };
+
+    let points = fetch_rent_history(&state.read_db, &lookup, RENT_HISTORY_MONTHS)
+        .await
+        .map_err(db_error)?;
+    if points.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(RentHistory {
+        property_id: id,
+        state: lookup.state,
+        postcode: lookup.postcode,
+        bedrooms: lookup.bedrooms,
+        bedrooms_estimated: bedrooms_estimated.unwrap_or(false),
+        yield_period,
+        points,
+    }))
+}
+
+/// Rental medians for a key over the last `months`, one per period
+/// Where several sources cover a period, the one with the largest sample wins
+pub async fn fetch_rent_history(
+    db: &PgPool,
+    lookup: &RentalLookup,
+    months: i32,
+) -> Result<Vec<RentPoint>, sqlx::Error> {
+    sqlx::query_as::<_, RentPoint>(
+        r#"
+        SELECT DISTINCT ON (period)
+            period,
+            median_weekly_rent AS median_rent,
+            sample_size
+        FROM rental_medians
+        WHERE state = $1
+          AND postcode = $2
+          AND bedrooms = $3
+          AND dwelling_type IS NULL
+          AND period > CURRENT_DATE - make_interval(months => $4)
+        ORDER BY period, sample_size DESC NULLS LAST
+        "#,
+    )
+    .bind(lookup.state)
+    .bind(&lookup.postcode)
+    .bind(lookup.bedrooms)
+    .bind(months)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture, RentalMedianFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    const SUBURB: &str = "Rent History Testville";
+    const POSTCODE: &str = "7998";
+
+    async fn get(db: &PgPool, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_rent_history_series() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+        sqlx::query("DELETE FROM rental_medians WHERE postcode = $1")
+            .bind(POSTCODE)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        // Two medians in the window, one too old to include
+        let today = chrono::Utc::now().date_naive();
+        for (months_ago, rent) in [(30, 400), (9, 450), (3, 480)] {
+            let period = today - chrono::Months::new(months_ago);
+            RentalMedianFixture::new(POSTCODE, period)
+                .state(AusState::TAS)
+                .rent(rent)
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+
+        let mut ids = Vec::new();
+        for (number, bedrooms, estimated) in [(1, 2, true), (2, 5, false)] {
+            let id = PropertyFixture::new()
+                .address(&format!("{} History St", number))
+                .suburb(SUBURB)
+                .state(AusState::TAS)
+                .postcode(POSTCODE)
+                .bedrooms(bedrooms)
+                .bedrooms_estimated(estimated)
+                .insert(&db)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        let (status, history) = get(&db, &format!("/api/properties/{}/rent-history", ids[0])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(history["bedrooms_estimated"], true);
+        let rents: Vec<i64> = history["points"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["median_rent"].as_i64().unwrap())
+            .collect();
+        assert_eq!(rents, vec![450, 480]);
+
+        // No medians for 5 bedrooms, and no such property
+        let (status, _) = get(&db, &format!("/api/properties/{}/rent-history", ids[1])).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get(&db, "/api/properties/-1/rent-history").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        delete_suburb(&db, SUBURB).await.unwrap();
+        sqlx::query("DELETE FROM rental_medians WHERE postcode = $1")
+            .bind(POSTCODE)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,339 @@
+//! Rental medians endpoints - median weekly rent by postcode and bedroom count
+
+use crate::analytics::suppression::{apply_suppression, Aggregate, SuppressionConfig};
+use crate::api::params::{
+    check_length, check_range, ParamError, ValidateParams, ValidatedListParams,
+};
+use crate::api::AppState;
+use crate::ingestion::types::{RentalMedian, State as AusState};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::error;
+
+/// Query parameters for GET /api/rental-medians
+#[derive(Debug, Clone, Deserialize)]
+pub struct MedianHistoryQuery {
+    pub postcode: String,
+    pub bedrooms: i32,
+    /// Only needed for the few postcodes that cross a state border
+    pub state: Option<AusState>,
+}
+
+impl ValidateParams for MedianHistoryQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("postcode", Some(&self.postcode), 10)?;
+        check_range("bedrooms", Some(self.bedrooms), 0..=20)
+    }
+}
+
+/// Query parameters for GET /api/rental-medians/latest
+#[derive(Debug, Clone, Deserialize)]
+pub struct LatestMediansQuery {
+    pub postcode: String,
+    pub state: Option<AusState>,
+}
+
+impl ValidateParams for LatestMediansQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("postcode", Some(&self.postcode), 10)
+    }
+}
+
+/// GET /api/rental-medians - every period's median for a postcode and
+/// bedroom count, oldest first; empty when there are none. Periods from too
+/// few bonds are left out.
+pub async fn get_median_history(
+    State(state): State<AppState>,
+    ValidatedListParams(params): ValidatedListParams<MedianHistoryQuery>,
+) -> Result<Json<Vec<RentalMedian>>, StatusCode> {
+    fetch_median_history(&state.read_db, &params)
+        .await
+        .map(|medians| Json(suppress_small_medians(medians, &state.suppression)))
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// GET /api/rental-medians/latest - each bedroom count's newest median for a
+/// postcode, fewest bedrooms first; empty when there are none. A bedroom
+/// count whose newest median is from too few bonds is left out.
+pub async fn get_latest_medians(
+    State(state): State<AppState>,
+    ValidatedListParams(params): ValidatedListParams<LatestMediansQuery>,
+) -> Result<Json<Vec<RentalMedian>>, StatusCode> {
+    fetch_latest_medians(&state.read_db, &params)
+        .await
+        .map(|medians| Json(suppress_small_medians(medians, &state.suppression)))
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Drop the medians from fewer bonds than the suppression minimum; one
+/// without a sample size can't show it clears it, so goes too. Each median
+/// is its own postcode's, so nothing rolls up.
+fn suppress_small_medians(
+    medians: Vec<RentalMedian>,
+    config: &SuppressionConfig,
+) -> Vec<RentalMedian> {
+    let aggregates = medians
+        .into_iter()
+        .map(|median| Aggregate {
+            value: median.median_weekly_rent as f64,
+            sample_size: median.sample_size.unwrap_or(0) as i64,
+            key: median,
+        })
+        .collect();
+
+    apply_suppression(aggregates, |_| None::<()>, config)
+        .into_iter()
+        .filter(|p| !p.suppressed)
+        .map(|p| p.key)
+        .collect()
+}
+
+/// One median per period; where several sources cover a period, the one with
+/// the largest sample wins, as in the rent history
+pub async fn fetch_median_history(
+    db: &PgPool,
+    query: &MedianHistoryQuery,
+) -> Result<Vec<RentalMedian>, sqlx::Error> {
+    sqlx::query_as::<_, RentalMedian>(
+        r#"
+        SELECT DISTINCT ON (period, state)
+            state, postcode, suburb, bedrooms, median_weekly_rent, sample_size,
+            dwelling_type, period
+        FROM rental_medians
+        WHERE postcode = $1
+          AND bedrooms = $2
+          AND ($3::state_enum IS NULL OR state = $3)
+          AND dwelling_type IS NULL
+        ORDER BY period, state, sample_size DESC NULLS LAST
+        "#,
+    )
+    .bind(&query.postcode)
+    .bind(query.bedrooms)
+    .bind(query.state)
+    .fetch_all(db)
+    .await
+}
+
+/// The newest period's median for each bedroom count, largest sample first
+/// where several sources cover it
+pub async fn fetch_latest_medians(
+    db: &PgPool,
+    query: &LatestMediansQuery,
+) -> Result<Vec<RentalMedian>, sqlx::Error> {
+    sqlx::query_as::<_, RentalMedian>(
+        r#"
+        SELECT DISTINCT ON (bedrooms, state)
+            state, postcode, suburb, bedrooms, median_weekly_rent, sample_size,
+            dwelling_type, period
+        FROM rental_medians
+        WHERE postcode = $1
+          AND ($2::state_enum IS NULL OR state = $2)
+          AND dwelling_type IS NULL
+        ORDER BY bedrooms, state, period DESC, sample_size DESC NULLS LAST
+        "#,
+    )
+    .bind(&query.postcode)
+    .bind(query.state)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{test_state, RentalMedianFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use chrono::NaiveDate;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    const POSTCODE: &str = "7996";
+
+    async fn get(db: &PgPool, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_postcode_and_bedrooms_required() {
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+
+        for (uri, field) in [
+            ("/api/rental-medians?bedrooms=2", "postcode"),
+            ("/api/rental-medians?postcode=2026", "bedrooms"),
+            ("/api/rental-medians?postcode=2026&bedrooms=99", "bedrooms"),
+            ("/api/rental-medians/latest", "postcode"),
+        ] {
+            let (status, body) = get(&db, uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["field"], field, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_small_medians_suppressed() {
+        let month = |m| NaiveDate::from_ymd_opt(2024, m, 1).unwrap();
+        let median = |period, sample_size| RentalMedian {
+            state: AusState::TAS,
+            postcode: POSTCODE.to_string(),
+            suburb: None,
+            bedrooms: 2,
+            median_weekly_rent: 500,
+            sample_size,
+            dwelling_type: None,
+            period,
+        };
+        let config = SuppressionConfig {
+            min_sample_size: 5,
+            roll_up: true,
+        };
+
+        let kept = suppress_small_medians(
+            vec![
+                median(month(1), Some(5)),
+                median(month(2), Some(4)),
+                median(month(3), None),
+                median(month(4), Some(12)),
+            ],
+            &config,
+        );
+        let periods: Vec<_> = kept.iter().map(|m| m.period).collect();
+        assert_eq!(periods, vec![month(1), month(4)]);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_history_and_latest() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM rental_medians WHERE postcode = $1")
+            .bind(POSTCODE)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let month = |m| NaiveDate::from_ymd_opt(2024, m, 1).unwrap();
+        for (bedrooms, period, rent, sample_size) in [
+            (2, month(3), 500, 40),
+            (2, month(1), 480, 35),
+            (2, month(6), 520, 30),
+            (3, month(3), 650, 20),
+            (1, month(6), 400, 10),
+            // Too few bonds to publish
+            (2, month(2), 900, 3),
+            (4, month(6), 800, 2),
+        ] {
+            RentalMedianFixture::new(POSTCODE, period)
+                .state(AusState::TAS)
+                .bedrooms(bedrooms)
+                .rent(rent)
+                .sample_size(sample_size)
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+        // A second source for one period with a smaller sample loses to the first
+        sqlx::query(
+            r#"
+            INSERT INTO rental_medians
+                (state, postcode, bedrooms, median_weekly_rent, sample_size, data_source, period)
+            VALUES ('TAS', $1, 2, 999, 5, 'other_source', $2)
+            "#,
+        )
+        .bind(POSTCODE)
+        .bind(month(6))
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let (status, body) = get(
+            &db,
+            &format!("/api/rental-medians?postcode={}&bedrooms=2", POSTCODE),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let series: Vec<(Value, Value)> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| (m["period"].clone(), m["median_weekly_rent"].clone()))
+            .collect();
+        assert_eq!(
+            series,
+            vec![
+                (json!("2024-01-01"), json!(480)),
+                (json!("2024-03-01"), json!(500)),
+                (json!("2024-06-01"), json!(520)),
+            ]
+        );
+        assert_eq!(body[0]["state"], "TAS");
+        assert_eq!(body[0]["sample_size"], 35);
+
+        let (status, body) = get(
+            &db,
+            &format!("/api/rental-medians/latest?postcode={}", POSTCODE),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let latest: Vec<(Value, Value, Value)> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| {
+                (
+                    m["bedrooms"].clone(),
+                    m["period"].clone(),
+                    m["median_weekly_rent"].clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            latest,
+            vec![
+                (json!(1), json!("2024-06-01"), json!(400)),
+                (json!(2), json!("2024-06-01"), json!(520)),
+                (json!(3), json!("2024-03-01"), json!(650)),
+            ]
+        );
+
+        // Missing combinations are empty, not 404
+        for uri in [
+            format!("/api/rental-medians?postcode={}&bedrooms=5", POSTCODE),
+            format!("/api/rental-medians?postcode={}&bedrooms=4", POSTCODE),
+            format!(
+                "/api/rental-medians?postcode={}&bedrooms=2&state=NSW",
+                POSTCODE
+            ),
+            "/api/rental-medians/latest?postcode=0000".to_string(),
+        ] {
+            let (status, body) = get(&db, &uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            assert_eq!(body, json!([]), "{}", uri);
+        }
+
+        sqlx::query("DELETE FROM rental_medians WHERE postcode = $1")
+            .bind(POSTCODE)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,338 @@
+//! Rental observations endpoint - individual bond lodgements with a rent distribution
+
+use crate::analytics::suppression::{apply_suppression, Aggregate, SuppressionConfig};
+use crate::api::params::{
+    check_length, check_range, ParamError, ValidateParams, ValidatedListParams,
+};
+use crate::api::AppState;
+use crate::ingestion::types::{PropertyType, RentalObservation, State as AusState};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+/// Query parameters for GET /api/rentals/observations
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObservationsQuery {
+    pub state: Option<AusState>,
+    pub postcode: Option<String>,
+    pub bedrooms: Option<i32>,
+    pub dwelling_type: Option<PropertyType>,
+    /// Only periods on or after this date
+    pub period_from: Option<NaiveDate>,
+    /// Only periods on or before this date
+    pub period_to: Option<NaiveDate>,
+    /// Observations listed; the summary always covers every match
+    pub limit: Option<i64>,
+}
+
+impl ValidateParams for ObservationsQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("postcode", self.postcode.as_deref(), 10)?;
+        check_range("bedrooms", self.bedrooms, 0..=20)?;
+        check_range("limit", self.limit, 1..=MAX_LIMIT)?;
+
+        if let (Some(from), Some(to)) = (self.period_from, self.period_to) {
+            if from > to {
+                return Err(ParamError::new(
+                    "period_from",
+                    "must not be after period_to",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Weekly rent distribution over every matching observation
+/// Percentiles are None when fewer observations match than the suppression minimum
+#[derive(Debug, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RentDistribution {
+    pub count: i64,
+    pub p25: Option<f64>,
+    pub median: Option<f64>,
+    pub p75: Option<f64>,
+}
+
+/// Response for GET /api/rentals/observations
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ObservationsResponse {
+    pub summary: RentDistribution,
+    /// Most recent first, up to `limit`; empty when fewer match than the
+    /// suppression minimum, as they'd give away the withheld percentiles
+    pub observations: Vec<RentalObservation>,
+}
+
+/// GET /api/rentals/observations - individual rentals and their p25/median/p75
+pub async fn get_observations(
+    State(state): State<AppState>,
+    ValidatedListParams(params): ValidatedListParams<ObservationsQuery>,
+) -> Result<Json<ObservationsResponse>, StatusCode> {
+    let db_error = |e: sqlx::Error| {
+        error!("Database error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+
+    let (summary, observations) = tokio::try_join!(
+        fetch_rent_distribution(&state.read_db, &params),
+        fetch_observations(&state.read_db, &params, limit),
+    )
+    .map_err(db_error)?;
+    let observations = if summary.count < state.suppression.min_sample_size {
+        Vec::new()
+    } else {
+        observations
+    };
+
+    Ok(Json(ObservationsResponse {
+        summary: suppress_small_distribution(summary, &state.suppression),
+        observations,
+    }))
+}
+
+/// Withhold the percentiles of a distribution over too few observations
+fn suppress_small_distribution(
+    summary: RentDistribution,
+    config: &SuppressionConfig,
+) -> RentDistribution {
+    // A single filtered group with no parent region, so nothing rolls up
+    let publish = |value: Option<f64>| {
+        let aggregate = Aggregate {
+            key: (),
+            value: value?,
+            sample_size: summary.count,
+        };
+        apply_suppression(vec![aggregate], |_| None::<()>, config)
+            .pop()?
+            .value
+    };
+
+    RentDistribution {
+        p25: publish(summary.p25),
+        median: publish(summary.median),
+        p75: publish(summary.p75),
+        ..summary
+    }
+}
+
+/// Shared WHERE clause; parameters $1-$6 follow the query's filter fields
+const OBSERVATION_FILTER: &str = r#"
+    WHERE ($1::state_enum IS NULL OR state = $1)
+      AND ($2::text IS NULL OR postcode = $2)
+      AND ($3::int IS NULL OR bedrooms = $3)
+      AND ($4::property_type_enum IS NULL OR dwelling_type = $4)
+      AND ($5::date IS NULL OR period >= $5)
+      AND ($6::date IS NULL OR period <= $6)
+"#;
+
+/// p25, median and p75 weekly rent of every matching observation
+pub async fn fetch_rent_distribution(
+    db: &PgPool,
+    filter: &ObservationsQuery,
+) -> Result<RentDistribution, sqlx::Error> {
+    sqlx::query_as::<_, RentDistribution>(&format!(
+        r#"
+        SELECT
+            COUNT(*) AS count,
+            percentile_cont(0.25) WITHIN GROUP (ORDER BY weekly_rent) AS p25,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY weekly_rent) AS median,
+            percentile_cont(0.75) WITHIN GROUP (ORDER BY weekly_rent) AS p75
+        FROM rental_observations
+        {}
+        "#,
+        OBSERVATION_FILTER
+    ))
+    .bind(filter.state)
+    .bind(&filter.postcode)
+    .bind(filter.bedrooms)
+    .bind(&filter.dwelling_type)
+    .bind(filter.period_from)
+    .bind(filter.period_to)
+    .fetch_one(db)
+    .await
+}
+
+pub async fn fetch_observations(
+    db: &PgPool,
+    filter: &ObservationsQuery,
+    limit: i64,
+) -> Result<Vec<RentalObservation>, sqlx::Error> {
+    sqlx::query_as::<_, RentalObservation>(&format!(
+        r#"
+        SELECT state, postcode, dwelling_type, bedrooms, weekly_rent, period
+        FROM rental_observations
+        {}
+        ORDER BY period DESC, id DESC
+        LIMIT $7
+        "#,
+        OBSERVATION_FILTER
+    ))
+    .bind(filter.state)
+    .bind(&filter.postcode)
+    .bind(filter.bedrooms)
+    .bind(&filter.dwelling_type)
+    .bind(filter.period_from)
+    .bind(filter.period_to)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::{parse, write, RawData};
+    use crate::test_support::test_state;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    const POSTCODE: &str = "2150";
+
+    async fn get(db: &PgPool, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    /// Linear-interpolated percentile, as percentile_cont computes it
+    fn percentile(sorted: &[i32], p: f64) -> f64 {
+        let rank = p * (sorted.len() - 1) as f64;
+        let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+        sorted[lo] as f64 + (sorted[hi] - sorted[lo]) as f64 * (rank - lo as f64)
+    }
+
+    #[test]
+    fn test_small_distribution_suppressed() {
+        let distribution = |count| RentDistribution {
+            count,
+            p25: Some(450.0),
+            median: Some(520.0),
+            p75: Some(600.0),
+        };
+        let config = SuppressionConfig::default();
+
+        assert_eq!(
+            suppress_small_distribution(distribution(5), &config),
+            distribution(5)
+        );
+        assert_eq!(
+            suppress_small_distribution(distribution(4), &config),
+            RentDistribution {
+                count: 4,
+                p25: None,
+                median: None,
+                p75: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_inverted_period_range() {
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let (status, body) = get(
+            &db,
+            "/api/rentals/observations?period_from=2024-12-01&period_to=2024-01-01",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "period_from");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_observations_distribution_from_fixture() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+
+        // A period well away from real data, cleared the way the pipeline does
+        let period = NaiveDate::from_ymd_opt(1999, 12, 1).unwrap();
+        write::clear_rental_observations(&db, period).await.unwrap();
+
+        let fixture = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/nsw_bond_lodgements.xlsx");
+        let observations = parse::parse_nsw_bond_lodgements(
+            RawData::Bytes(std::fs::read(fixture).unwrap()),
+            period,
+        )
+        .await
+        .unwrap();
+        assert!(observations.len() > 300);
+        write::write_rental_observations(&db, observations.clone())
+            .await
+            .unwrap();
+
+        let mut expected: Vec<i32> = observations
+            .iter()
+            .filter(|o| o.postcode == POSTCODE && o.bedrooms == Some(2))
+            .map(|o| o.weekly_rent)
+            .collect();
+        expected.sort_unstable();
+
+        let (status, body) = get(
+            &db,
+            &format!(
+                "/api/rentals/observations?postcode={}&bedrooms=2&period_from={p}&period_to={p}&limit=5",
+                POSTCODE,
+                p = period
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let summary: RentDistribution = serde_json::from_value(body["summary"].clone()).unwrap();
+        assert_eq!(summary.count, expected.len() as i64);
+        for (actual, p) in [
+            (summary.p25, 0.25),
+            (summary.median, 0.5),
+            (summary.p75, 0.75),
+        ] {
+            assert!((actual.unwrap() - percentile(&expected, p)).abs() < 1e-6);
+        }
+        assert_eq!(body["observations"].as_array().unwrap().len(), 5);
+
+        // A group too small to publish gets its count and nothing else
+        let mut groups: HashMap<(&str, Option<i32>), i64> = HashMap::new();
+        for o in &observations {
+            *groups.entry((o.postcode.as_str(), o.bedrooms)).or_default() += 1;
+        }
+        let ((postcode, bedrooms), count) = groups
+            .into_iter()
+            .find(|((_, bedrooms), count)| bedrooms.is_some() && *count < 5)
+            .unwrap();
+        let (status, body) = get(
+            &db,
+            &format!(
+                "/api/rentals/observations?postcode={}&bedrooms={}&period_from={p}&period_to={p}",
+                postcode,
+                bedrooms.unwrap(),
+                p = period
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["summary"]["count"], count);
+        assert_eq!(body["summary"]["median"], Value::Null);
+        assert_eq!(body["observations"], json!([]));
+
+        write::clear_rental_observations(&db, period).await.unwrap();
+    }
+}
@@ -0,0 +1,657 @@
+//! Sales history endpoint - keyset-paginated sales joined to their properties
+
+use crate::api::export::{csv_response, paged_csv_stream, ExportBudget};
+use crate::api::params::{
+    check_length, check_range, ParamError, ValidateParams, ValidatedListParams, MAX_STRING_LENGTH,
+};
+use crate::api::tier::{Access, Redact, Redactor};
+use crate::api::AppState;
+use crate::ingestion::types::{PropertyType, State as AusState};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+/// Default page size for JSON responses
+const DEFAULT_LIMIT: i64 = 100;
+
+/// Largest page a client can request
+const MAX_LIMIT: i64 = 1000;
+
+/// Page size used internally when streaming CSV exports
+const EXPORT_PAGE_SIZE: i64 = 1000;
+
+/// Query parameters for GET /api/sales
+#[derive(Debug, Default, Deserialize)]
+pub struct SalesQuery {
+    /// Only sales on or after this date
+    pub sold_after: Option<NaiveDate>,
+    /// Only sales on or before this date
+    pub sold_before: Option<NaiveDate>,
+    pub state: Option<AusState>,
+    pub suburb: Option<String>,
+    pub postcode: Option<String>,
+    /// Opaque cursor from a previous page's `next_cursor`
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    /// `json` (default) or `csv`
+    pub format: Option<String>,
+    /// CSV only: resume an export from its `X-Continue-From` header (also on
+    /// its trailing `# continue_from=` line)
+    pub continue_from: Option<String>,
+    /// CSV only: fewer rows per response than the server's export budget
+    pub max_rows: Option<i64>,
+}
+
+impl ValidateParams for SalesQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("suburb", self.suburb.as_deref(), MAX_STRING_LENGTH)?;
+        check_length("postcode", self.postcode.as_deref(), 10)?;
+        check_range("limit", self.limit, 1..=MAX_LIMIT)?;
+
+        check_range("max_rows", self.max_rows, 1..=i64::MAX)?;
+
+        for (field, cursor) in [
+            ("cursor", &self.cursor),
+            ("continue_from", &self.continue_from),
+        ] {
+            if cursor
+                .as_deref()
+                .is_some_and(|c| SalesCursor::decode(c).is_none())
+            {
+                return Err(ParamError::new(field, "not a valid cursor"));
+            }
+        }
+
+        let csv = match self.format.as_deref() {
+            None | Some("json") => false,
+            Some("csv") => true,
+            Some(_) => return Err(ParamError::new("format", "must be json or csv")),
+        };
+        if !csv {
+            for (field, set) in [
+                ("continue_from", self.continue_from.is_some()),
+                ("max_rows", self.max_rows.is_some()),
+            ] {
+                if set {
+                    return Err(ParamError::new(field, "only supported with format=csv"));
+                }
+            }
+        }
+        if self.cursor.is_some() && self.continue_from.is_some() {
+            return Err(ParamError::new(
+                "continue_from",
+                "can't be combined with cursor",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Filters shared by the JSON and CSV paths
+#[derive(Debug, Clone, Default)]
+pub struct SalesFilter {
+    pub sold_after: Option<NaiveDate>,
+    pub sold_before: Option<NaiveDate>,
+    pub state: Option<AusState>,
+    pub suburb: Option<String>,
+    pub postcode: Option<String>,
+}
+
+impl From<&SalesQuery> for SalesFilter {
+    fn from(query: &SalesQuery) -> Self {
+        SalesFilter {
+            sold_after: query.sold_after,
+            sold_before: query.sold_before,
+            state: query.state,
+            suburb: query.suburb.clone(),
+            postcode: query.postcode.clone(),
+        }
+    }
+}
+
+/// Keyset cursor - position of the last sale on the previous page
+/// The same format is used for JSON `cursor` and CSV `continue_from`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SalesCursor {
+    pub sale_date: NaiveDate,
+    pub id: i32,
+}
+
+impl SalesCursor {
+    /// Encode as `YYYY-MM-DD_id`
+    pub fn encode(&self) -> String {
+        format!("{}_{}", self.sale_date, self.id)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let (date, id) = cursor.split_once('_')?;
+
+        Some(SalesCursor {
+            sale_date: NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?,
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+impl std::fmt::Display for SalesCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+/// A sale joined to the property it belongs to
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SaleRow {
+    pub sale_id: i32,
+    pub property_id: i32,
+    pub address: String,
+    pub suburb: String,
+    pub state: AusState,
+    pub postcode: Option<String>,
+    pub property_type: Option<PropertyType>,
+    pub bedrooms: Option<i32>,
+    pub sale_price: i64,
+    pub sale_date: NaiveDate,
+}
+
+impl Redact for SaleRow {
+    fn redact(&mut self, redactor: &Redactor) {
+        redactor.address(&mut self.address);
+        redactor.price(&mut self.sale_price);
+    }
+}
+
+impl SaleRow {
+    pub fn cursor(&self) -> SalesCursor {
+        SalesCursor {
+            sale_date: self.sale_date,
+            id: self.sale_id,
+        }
+    }
+}
+
+/// JSON response for one page of sales
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SalesPage<T> {
+    pub sales: Vec<T>,
+    /// Pass back as `cursor` to fetch the next page; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl<T: Redact> Redact for SalesPage<T> {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.sales.redact(redactor);
+    }
+}
+
+/// GET /api/sales - page through sales history ordered by (sale_date, id)
+pub async fn get_sales(
+    State(state): State<AppState>,
+    access: Access,
+    ValidatedListParams(params): ValidatedListParams<SalesQuery>,
+) -> Result<Response, StatusCode> {
+    let filter = SalesFilter::from(&params);
+
+    // Cursor and format were checked by SalesQuery::validate
+    let after = params.cursor.as_deref().and_then(SalesCursor::decode);
+
+    if params.format.as_deref() == Some("csv") {
+        // A continuation carries on from an earlier response, so no header row
+        let resume = params
+            .continue_from
+            .as_deref()
+            .and_then(SalesCursor::decode);
+        let budget = state
+            .export_budget
+            .with_max_rows(params.max_rows.map(|n| n as usize));
+        return export_sales_csv(
+            state.read_db,
+            filter,
+            resume.or(after),
+            resume.is_none(),
+            budget,
+            access,
+        )
+        .await
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        });
+    }
+
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+
+    // Fetch one extra row to find out whether another page exists
+    let mut sales = fetch_sales_page(&state.read_db, &filter, after, limit + 1)
+        .await
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let next_cursor = if sales.len() as i64 > limit {
+        sales.truncate(limit as usize);
+        sales.last().map(|s| s.cursor().encode())
+    } else {
+        None
+    };
+
+    Ok(Json(access.apply(SalesPage { sales, next_cursor })).into_response())
+}
+
+/// Stream matching sales as CSV, walking the keyset one page at a time until
+/// the data or the budget runs out
+/// An export the row budget will cut short is a 206 naming its cut-off up front
+async fn export_sales_csv(
+    db: PgPool,
+    filter: SalesFilter,
+    after: Option<SalesCursor>,
+    with_header: bool,
+    budget: ExportBudget,
+    access: Access,
+) -> Result<Response, sqlx::Error> {
+    let cutoff = fetch_sales_cutoff(&db, &filter, after, budget.max_rows as i64).await?;
+    let budget = match cutoff {
+        Some(_) => budget.rows_only(),
+        None => budget,
+    };
+
+    let fetch_page = move |cursor: Option<SalesCursor>, max_rows: usize| {
+        let db = db.clone();
+        let filter = filter.clone();
+        let page_size = EXPORT_PAGE_SIZE.min(max_rows as i64);
+
+        async move {
+            let rows = fetch_sales_page(&db, &filter, cursor, page_size).await?;
+            let next = if rows.len() as i64 == page_size {
+                rows.last().map(SaleRow::cursor)
+            } else {
+                None
+            };
+            Ok((access.apply(rows), next))
+        }
+    };
+
+    Ok(csv_response(
+        "sales.csv",
+        cutoff.map(|c| c.encode()),
+        paged_csv_stream(fetch_page, after, with_header, budget),
+    ))
+}
+
+/// Fetch up to `limit` sales strictly after the cursor position
+pub async fn fetch_sales_page(
+    db: &PgPool,
+    filter: &SalesFilter,
+    after: Option<SalesCursor>,
+    limit: i64,
+) -> Result<Vec<SaleRow>, sqlx::Error> {
+    sqlx::query_as::<_, SaleRow>(
+        r#"
+        SELECT
+            sh.id AS sale_id,
+            p.id AS property_id,
+            p.address,
+            p.suburb,
+            p.state,
+            p.postcode,
+            p.property_type,
+            p.bedrooms,
+            sh.sale_price,
+            sh.sale_date
+        FROM sales_history sh
+        JOIN properties p ON p.id = sh.property_id
+        WHERE ($1::date IS NULL OR sh.sale_date >= $1)
+          AND ($2::date IS NULL OR sh.sale_date <= $2)
+          AND ($3::state_enum IS NULL OR p.state = $3)
+          AND ($4::text IS NULL OR LOWER(p.suburb) = LOWER($4))
+          AND ($5::text IS NULL OR p.postcode = $5)
+          AND ($6::date IS NULL OR (sh.sale_date, sh.id) > ($6, $7))
+        ORDER BY sh.sale_date, sh.id
+        LIMIT $8
+        "#,
+    )
+    .bind(filter.sold_after)
+    .bind(filter.sold_before)
+    .bind(filter.state)
+    .bind(&filter.suburb)
+    .bind(&filter.postcode)
+    .bind(after.map(|c| c.sale_date))
+    .bind(after.map(|c| c.id))
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Cursor of the last sale an export of `max_rows` after `after` would
+/// include, when more sales follow it; None when the export runs to the end
+async fn fetch_sales_cutoff(
+    db: &PgPool,
+    filter: &SalesFilter,
+    after: Option<SalesCursor>,
+    max_rows: i64,
+) -> Result<Option<SalesCursor>, sqlx::Error> {
+    // Same filter and order as fetch_sales_page
+    let rows: Vec<(NaiveDate, i32)> = sqlx::query_as(
+        r#"
+        SELECT sh.sale_date, sh.id
+        FROM sales_history sh
+        JOIN properties p ON p.id = sh.property_id
+        WHERE ($1::date IS NULL OR sh.sale_date >= $1)
+          AND ($2::date IS NULL OR sh.sale_date <= $2)
+          AND ($3::state_enum IS NULL OR p.state = $3)
+          AND ($4::text IS NULL OR LOWER(p.suburb) = LOWER($4))
+          AND ($5::text IS NULL OR p.postcode = $5)
+          AND ($6::date IS NULL OR (sh.sale_date, sh.id) > ($6, $7))
+        ORDER BY sh.sale_date, sh.id
+        OFFSET $8
+        LIMIT 2
+        "#,
+    )
+    .bind(filter.sold_after)
+    .bind(filter.sold_before)
+    .bind(filter.state)
+    .bind(&filter.suburb)
+    .bind(&filter.postcode)
+    .bind(after.map(|c| c.sale_date))
+    .bind(after.map(|c| c.id))
+    .bind(max_rows - 1)
+    .fetch_all(db)
+    .await?;
+
+    // The last included row, and proof that another follows it
+    Ok(match rows.as_slice() {
+        [(sale_date, id), _] => Some(SalesCursor {
+            sale_date: *sale_date,
+            id: *id,
+        }),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::export::{CONTINUE_HEADER, CONTINUE_PREFIX};
+    use crate::api::tier::{Tier, TierConfig};
+    use crate::api::API_KEY_HEADER;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture, SaleFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = SalesCursor {
+            sale_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            id: 42,
+        };
+
+        assert_eq!(cursor.encode(), "2024-01-01_42");
+        assert_eq!(SalesCursor::decode(&cursor.encode()), Some(cursor));
+    }
+
+    #[test]
+    fn test_cursor_rejects_garbage() {
+        assert_eq!(SalesCursor::decode("not-a-cursor"), None);
+        assert_eq!(SalesCursor::decode("2024-13-01_1"), None);
+        assert_eq!(SalesCursor::decode("2024-01-01_abc"), None);
+    }
+
+    const SUBURB: &str = "Sales Api Testville";
+    const CSV_SUBURB: &str = "Sales Csv Testville";
+    const RESUME_SUBURB: &str = "Sales Resume Testville";
+
+    async fn cleanup(db: &PgPool, suburb: &str) {
+        delete_suburb(db, suburb).await.unwrap();
+    }
+
+    async fn seed(db: &PgPool, suburb: &str) -> Vec<i32> {
+        cleanup(db, suburb).await;
+
+        let mut property_ids = Vec::new();
+        for (address, bedrooms) in [("1 Paging Street", 3), ("2 Paging Street", 2)] {
+            let id = PropertyFixture::new()
+                .address(address)
+                .suburb(suburb)
+                .bedrooms(bedrooms)
+                .insert(db)
+                .await
+                .unwrap();
+            property_ids.push(id);
+        }
+
+        // Sales straddling a date boundary, with three on the same day
+        let sales = [
+            (property_ids[0], 700_000, "2023-12-31"),
+            (property_ids[0], 710_000, "2024-01-01"),
+            (property_ids[1], 500_000, "2024-01-01"),
+            (property_ids[1], 505_000, "2024-01-01"),
+            (property_ids[0], 720_000, "2024-01-02"),
+        ];
+
+        let mut sale_ids = Vec::new();
+        for (property_id, price, date) in sales {
+            let id = SaleFixture::new(property_id, price, date.parse().unwrap())
+                .insert(db)
+                .await
+                .unwrap();
+            sale_ids.push(id);
+        }
+
+        sale_ids
+    }
+
+    /// GET as a full-tier caller, so rows come back unredacted
+    async fn get_json(db: &PgPool, uri: &str) -> Value {
+        let app = crate::api::router().with_state(AppState {
+            tiers: Arc::new(TierConfig::default().with_key("sales-test", Tier::Full)),
+            ..test_state(db.clone())
+        });
+        let request = Request::get(uri)
+            .header(API_KEY_HEADER, "sales-test")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_sales_pagination_across_date_boundary() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let sale_ids = seed(&db, SUBURB).await;
+
+        let base =
+            "/api/sales?sold_after=2023-12-31&state=NSW&suburb=Sales%20Api%20Testville&limit=2";
+
+        let mut seen = Vec::new();
+        let mut page_sizes = Vec::new();
+        let mut uri = base.to_string();
+        loop {
+            let page = get_json(&db, &uri).await;
+            let sales = page["sales"].as_array().unwrap();
+            page_sizes.push(sales.len());
+            seen.extend(sales.iter().map(|s| s["sale_id"].as_i64().unwrap() as i32));
+
+            match page["next_cursor"].as_str() {
+                Some(cursor) => uri = format!("{}&cursor={}", base, cursor),
+                None => break,
+            }
+        }
+
+        // Every sale exactly once, in (sale_date, id) order
+        assert_eq!(seen, sale_ids);
+        assert_eq!(page_sizes, vec![2, 2, 1]);
+
+        // Join fields come from the property
+        let first = get_json(&db, base).await;
+        let sale = &first["sales"][0];
+        assert_eq!(sale["address"], "1 Paging Street");
+        assert_eq!(sale["suburb"], SUBURB);
+        assert_eq!(sale["state"], "NSW");
+        assert_eq!(sale["postcode"], "2999");
+        assert_eq!(sale["property_type"], "House");
+        assert_eq!(sale["bedrooms"], 3);
+        assert_eq!(sale["sale_price"], 700_000);
+        assert_eq!(sale["sale_date"], "2023-12-31");
+
+        // sold_after excludes earlier sales
+        let later = get_json(
+            &db,
+            "/api/sales?sold_after=2024-01-02&suburb=Sales%20Api%20Testville",
+        )
+        .await;
+        assert_eq!(later["sales"].as_array().unwrap().len(), 1);
+
+        cleanup(&db, SUBURB).await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_sales_csv_export() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        seed(&db, CSV_SUBURB).await;
+
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(
+                Request::get("/api/sales?suburb=Sales%20Csv%20Testville&format=csv")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines[0].starts_with("sale_id,property_id,address,suburb"));
+        assert_eq!(lines.len(), 6); // header + 5 sales
+
+        cleanup(&db, CSV_SUBURB).await;
+    }
+
+    /// The body of a CSV export and its `X-Continue-From` header, if any
+    async fn get_export(db: &PgPool, uri: &str) -> (String, Option<String>) {
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let continue_from = response
+            .headers()
+            .get(CONTINUE_HEADER)
+            .map(|v| v.to_str().unwrap().to_string());
+        let expected_status = match continue_from {
+            Some(_) => StatusCode::PARTIAL_CONTENT,
+            None => StatusCode::OK,
+        };
+        assert_eq!(response.status(), expected_status);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (String::from_utf8(body.to_vec()).unwrap(), continue_from)
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_resumed_csv_export_matches_full_export() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db, RESUME_SUBURB).await;
+
+        let property_id = PropertyFixture::new()
+            .address("1 Resume St")
+            .suburb(RESUME_SUBURB)
+            .insert(&db)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO sales_history (property_id, sale_price, sale_date, data_source)
+            SELECT $1, 500000 + n, DATE '2020-01-01' + (n % 1000), 'test'
+            FROM generate_series(1, 10000) AS n
+            "#,
+        )
+        .bind(property_id)
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let base = "/api/sales?suburb=Sales%20Resume%20Testville&format=csv";
+        let (full, continue_from) = get_export(&db, base).await;
+        assert_eq!(full.lines().count(), 10_001);
+        assert!(!full.contains(CONTINUE_PREFIX));
+        assert_eq!(continue_from, None);
+
+        let mut resumed = String::new();
+        let mut uri = format!("{}&max_rows=4000", base);
+        let mut responses = 0;
+        loop {
+            let (body, continue_from) = get_export(&db, &uri).await;
+            responses += 1;
+
+            match body.rfind(CONTINUE_PREFIX) {
+                Some(pos) => {
+                    resumed.push_str(&body[..pos]);
+                    let cursor = body[pos + CONTINUE_PREFIX.len()..].trim();
+                    // The header announces the same cut-off before the body
+                    assert_eq!(continue_from.as_deref(), Some(cursor));
+                    uri = format!("{}&max_rows=4000&continue_from={}", base, cursor);
+                }
+                None => {
+                    assert_eq!(continue_from, None);
+                    resumed.push_str(&body);
+                    break;
+                }
+            }
+        }
+
+        assert_eq!(responses, 3);
+        assert_eq!(resumed, full);
+
+        cleanup(&db, RESUME_SUBURB).await;
+    }
+
+    #[test]
+    fn test_continuation_params_are_csv_only() {
+        let query = SalesQuery {
+            continue_from: Some("2024-01-01_1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query.validate().unwrap_err().field, "continue_from");
+
+        let query = SalesQuery {
+            format: Some("csv".to_string()),
+            continue_from: Some("nope".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query.validate().unwrap_err().field, "continue_from");
+
+        let query = SalesQuery {
+            format: Some("csv".to_string()),
+            continue_from: Some("2024-01-01_1".to_string()),
+            max_rows: Some(500),
+            ..Default::default()
+        };
+        assert!(query.validate().is_ok());
+    }
+}
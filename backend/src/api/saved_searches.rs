@@ -0,0 +1,587 @@
+//! Saved searches - named /api/properties filters kept per caller
+//!
+//! A caller is its API key when the key is recognised, otherwise the UUID it
+//! sends in x-client-id. Only an md5 of that identity is stored, and callers
+//! only ever see their own searches.
+
+use crate::api::params::{ParamError, ValidateParams, ValidatedListParams};
+use crate::api::properties::{
+    fetch_listing_page, fetch_listing_version, resolve_filter, PropertiesPage, PropertiesQuery,
+};
+use crate::api::tier::Access;
+use crate::api::{AppState, API_KEY_HEADER};
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Path, State};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json as SqlJson;
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+/// Header naming a caller without an API key; any UUID the client keeps
+pub const CLIENT_ID_HEADER: &str = "x-client-id";
+
+/// Most searches one caller can keep
+pub const MAX_SAVED_SEARCHES: i64 = 50;
+
+/// Longest search name accepted
+pub const MAX_SEARCH_NAME_LENGTH: usize = 100;
+
+/// Who saved a search: a recognised API key, else the client id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchOwner {
+    ApiKey(String),
+    Client(Uuid),
+}
+
+impl SearchOwner {
+    /// The identity as hashed into saved_searches.owner
+    fn identity(&self) -> String {
+        match self {
+            SearchOwner::ApiKey(key) => format!("key:{}", key),
+            SearchOwner::Client(id) => format!("client:{}", id),
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for SearchOwner {
+    type Rejection = ParamError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let header = |name| parts.headers.get(name).and_then(|v| v.to_str().ok());
+
+        if let Some(key) = header(API_KEY_HEADER).filter(|key| state.tiers.is_known(key)) {
+            return Ok(SearchOwner::ApiKey(key.to_string()));
+        }
+        match header(CLIENT_ID_HEADER) {
+            Some(id) => Uuid::parse_str(id.trim())
+                .map(SearchOwner::Client)
+                .map_err(|_| ParamError::new(CLIENT_ID_HEADER, "must be a UUID")),
+            None => Err(ParamError::new(
+                CLIENT_ID_HEADER,
+                "required without a recognised API key",
+            )),
+        }
+    }
+}
+
+/// Body for POST /api/searches
+#[derive(Debug, Deserialize)]
+pub struct CreateSearchRequest {
+    pub name: String,
+    /// As the query parameters of GET /api/properties; `page` and `cursor`
+    /// are dropped, since results are paged when read
+    pub filter: PropertiesQuery,
+}
+
+impl CreateSearchRequest {
+    /// The trimmed name and the filter to store, or why they can't be saved
+    fn validate(self) -> Result<(String, PropertiesQuery), ParamError> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_SEARCH_NAME_LENGTH {
+            return Err(ParamError::new(
+                "name",
+                format!(
+                    "must be between 1 and {} characters",
+                    MAX_SEARCH_NAME_LENGTH
+                ),
+            ));
+        }
+
+        let filter = PropertiesQuery {
+            page: None,
+            cursor: None,
+            ..self.filter
+        };
+        filter.validate().map_err(|e| ParamError {
+            field: format!("filter.{}", e.field),
+            ..e
+        })?;
+        Ok((name.to_string(), filter))
+    }
+}
+
+/// One saved search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: i32,
+    pub name: String,
+    pub filter: PropertiesQuery,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SavedSearchRow {
+    id: i32,
+    name: String,
+    filter: SqlJson<PropertiesQuery>,
+    created_at: NaiveDateTime,
+}
+
+impl From<SavedSearchRow> for SavedSearch {
+    fn from(row: SavedSearchRow) -> Self {
+        SavedSearch {
+            id: row.id,
+            name: row.name,
+            filter: row.filter.0,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Response for GET /api/searches
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedSearchesResponse {
+    /// Oldest first
+    pub searches: Vec<SavedSearch>,
+}
+
+/// Query parameters for GET /api/searches/:id/results, applied over the
+/// stored filter
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchResultsQuery {
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+impl ValidateParams for SearchResultsQuery {
+    /// Checked together with the stored filter by the handler
+    fn validate(&self) -> Result<(), ParamError> {
+        Ok(())
+    }
+}
+
+fn internal_error(e: sqlx::Error) -> Response {
+    error!("Database error: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+/// POST /api/searches - save a filter under a name
+/// 400 when the filter fails the listing's validation, 409 once the caller
+/// has MAX_SAVED_SEARCHES
+pub async fn create_search(
+    State(state): State<AppState>,
+    owner: SearchOwner,
+    Json(request): Json<CreateSearchRequest>,
+) -> Result<(StatusCode, Json<SavedSearch>), Response> {
+    let (name, filter) = request.validate().map_err(IntoResponse::into_response)?;
+
+    match insert_search(&state.db, &owner, &name, &filter)
+        .await
+        .map_err(internal_error)?
+    {
+        Some(saved) => Ok((StatusCode::CREATED, Json(saved))),
+        None => Err((
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": "too_many_searches",
+                "message": format!(
+                    "at most {} searches can be saved; delete one first",
+                    MAX_SAVED_SEARCHES
+                ),
+            })),
+        )
+            .into_response()),
+    }
+}
+
+/// GET /api/searches - the caller's saved searches
+pub async fn list_searches(
+    State(state): State<AppState>,
+    owner: SearchOwner,
+) -> Result<Json<SavedSearchesResponse>, Response> {
+    let searches = sqlx::query_as::<_, SavedSearchRow>(
+        r#"
+        SELECT id, name, filter, created_at
+        FROM saved_searches
+        WHERE owner = md5($1)
+        ORDER BY id
+        "#,
+    )
+    .bind(owner.identity())
+    // The primary, so a search shows up as soon as it's saved
+    .fetch_all(&state.db)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(SavedSearchesResponse {
+        searches: searches.into_iter().map(SavedSearch::from).collect(),
+    }))
+}
+
+/// DELETE /api/searches/:id - 204, or 404 when the caller has no such search
+pub async fn delete_search(
+    State(state): State<AppState>,
+    owner: SearchOwner,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, Response> {
+    let deleted = sqlx::query("DELETE FROM saved_searches WHERE id = $1 AND owner = md5($2)")
+        .bind(id)
+        .bind(owner.identity())
+        .execute(&state.db)
+        .await
+        .map_err(internal_error)?
+        .rows_affected();
+
+    if deleted == 0 {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/searches/:id/results - the stored filter run against the current
+/// listing, a page at a time as GET /api/properties
+pub async fn get_search_results(
+    State(state): State<AppState>,
+    access: Access,
+    owner: SearchOwner,
+    Path(id): Path<i32>,
+    ValidatedListParams(paging): ValidatedListParams<SearchResultsQuery>,
+) -> Result<Json<PropertiesPage>, Response> {
+    let search = fetch_search(&state.db, &owner, id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    let params = PropertiesQuery {
+        page: paging.page,
+        page_size: paging.page_size.or(search.filter.page_size),
+        cursor: paging.cursor,
+        ..search.filter
+    };
+    params.validate().map_err(IntoResponse::into_response)?;
+    params
+        .check_tier(access.tier)
+        .map_err(IntoResponse::into_response)?;
+
+    let filter = resolve_filter(&state.read_db, &params).await?;
+    let total = fetch_listing_version(&state.read_db, &filter)
+        .await
+        .map_err(internal_error)?
+        .count;
+    let page = fetch_listing_page(&state.read_db, &filter, &params, total)
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(access.apply(page)))
+}
+
+/// The caller's search with this id
+async fn fetch_search(
+    db: &PgPool,
+    owner: &SearchOwner,
+    id: i32,
+) -> Result<Option<SavedSearch>, sqlx::Error> {
+    let row = sqlx::query_as::<_, SavedSearchRow>(
+        r#"
+        SELECT id, name, filter, created_at
+        FROM saved_searches
+        WHERE id = $1 AND owner = md5($2)
+        "#,
+    )
+    .bind(id)
+    .bind(owner.identity())
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(SavedSearch::from))
+}
+
+/// Store a search unless the caller already has MAX_SAVED_SEARCHES; None
+/// when it has. Saves by the same caller are serialized so the cap holds.
+pub async fn insert_search(
+    db: &PgPool,
+    owner: &SearchOwner,
+    name: &str,
+    filter: &PropertiesQuery,
+) -> Result<Option<SavedSearch>, sqlx::Error> {
+    let identity = owner.identity();
+    let mut tx = db.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('saved_searches:' || md5($1)))")
+        .bind(&identity)
+        .execute(&mut *tx)
+        .await?;
+
+    let count =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM saved_searches WHERE owner = md5($1)")
+            .bind(&identity)
+            .fetch_one(&mut *tx)
+            .await?;
+    if count >= MAX_SAVED_SEARCHES {
+        return Ok(None);
+    }
+
+    let row = sqlx::query_as::<_, SavedSearchRow>(
+        r#"
+        INSERT INTO saved_searches (owner, name, filter)
+        VALUES (md5($1), $2, $3)
+        RETURNING id, name, filter, created_at
+        "#,
+    )
+    .bind(&identity)
+    .bind(name)
+    .bind(SqlJson(filter))
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Some(SavedSearch::from(row)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tier::{Tier, TierConfig};
+    use crate::ingestion::types::State as AusState;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::{json, Value};
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn app(db: PgPool) -> axum::Router {
+        crate::api::router().with_state(AppState {
+            tiers: Arc::new(TierConfig::default().with_key("searches-test", Tier::Full)),
+            ..test_state(db)
+        })
+    }
+
+    async fn send(
+        app: &axum::Router,
+        method: &str,
+        uri: &str,
+        headers: &[(&str, &str)],
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = match body {
+            Some(body) => request
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        };
+        let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[test]
+    fn test_create_request_validated() {
+        let request = |name: &str, filter: Value| -> CreateSearchRequest {
+            serde_json::from_value(json!({ "name": name, "filter": filter })).unwrap()
+        };
+
+        let (name, filter) = request(
+            "  Cheap Sydney  ",
+            json!({ "state": "NSW", "max_price": 600000, "page": 3, "sort": "rental_yield" }),
+        )
+        .validate()
+        .unwrap();
+        assert_eq!(name, "Cheap Sydney");
+        assert_eq!(filter.states().unwrap(), Some(vec![AusState::NSW]));
+        assert_eq!(filter.page, None);
+
+        for (name, filter, field) in [
+            ("", json!({}), "name"),
+            (&"x".repeat(101), json!({}), "name"),
+            ("Bad", json!({ "bedrooms": 99 }), "filter.bedrooms"),
+            (
+                "Bad",
+                json!({ "min_price": 500, "max_price": 100 }),
+                "filter.min_price",
+            ),
+        ] {
+            let err = request(name, filter).validate().unwrap_err();
+            assert_eq!(err.field, field);
+        }
+    }
+
+    #[test]
+    fn test_filter_round_trips_through_json() {
+        let filter: PropertiesQuery = serde_urlencoded::from_str(
+            "suburb=Bondi&state=NSW&min_yield=4.5&sort=rental_yield&order=desc&page_size=20",
+        )
+        .unwrap();
+        let stored = serde_json::to_value(&filter).unwrap();
+        assert_eq!(
+            serde_json::from_value::<PropertiesQuery>(stored).unwrap(),
+            filter
+        );
+    }
+
+    #[tokio::test]
+    async fn test_caller_identity_required() {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = app(db);
+
+        for headers in [
+            vec![],
+            vec![(CLIENT_ID_HEADER, "not-a-uuid")],
+            // An unrecognised key doesn't identify anyone
+            vec![(API_KEY_HEADER, "made-up")],
+        ] {
+            let (status, body) = send(&app, "GET", "/api/searches", &headers, None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{:?}", headers);
+            assert_eq!(body["field"], CLIENT_ID_HEADER);
+        }
+
+        let (status, body) = send(
+            &app,
+            "POST",
+            "/api/searches",
+            &[(CLIENT_ID_HEADER, &Uuid::new_v4().to_string())],
+            Some(json!({ "name": "Bad", "filter": { "state": "XYZ" } })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert_eq!(body["field"], "filter.state");
+    }
+
+    const SUBURB: &str = "Saved Search Testville";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_saved_search_lifecycle() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+        for n in 0..5 {
+            PropertyFixture::new()
+                .address(&format!("{} Saved Street", n))
+                .suburb(SUBURB)
+                .bedrooms(if n < 3 { 2 } else { 4 })
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+        let app = app(db.clone());
+        let client = Uuid::new_v4().to_string();
+        let me = [(CLIENT_ID_HEADER, client.as_str())];
+        let stranger_id = Uuid::new_v4().to_string();
+        let stranger = [(CLIENT_ID_HEADER, stranger_id.as_str())];
+
+        let (status, saved) = send(
+            &app,
+            "POST",
+            "/api/searches",
+            &me,
+            Some(json!({
+                "name": "Two beds",
+                "filter": { "suburb": "saved search testville", "bedrooms": 2, "page_size": 2 }
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", saved);
+        let id = saved["id"].as_i64().unwrap();
+        assert_eq!(saved["filter"]["bedrooms"], 2);
+
+        let (status, body) = send(
+            &app,
+            "POST",
+            "/api/searches",
+            &me,
+            Some(json!({ "name": "Bad", "filter": { "bedrooms": -1 } })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "filter.bedrooms");
+
+        let (_, list) = send(&app, "GET", "/api/searches", &me, None).await;
+        assert_eq!(list["searches"].as_array().unwrap().len(), 1);
+        assert_eq!(list["searches"][0]["name"], "Two beds");
+        let (_, list) = send(&app, "GET", "/api/searches", &stranger, None).await;
+        assert_eq!(list["searches"], json!([]));
+
+        // Results run the stored filter, paged by the stored page size
+        let results = format!("/api/searches/{}/results", id);
+        let (status, page) = send(&app, "GET", &results, &me, None).await;
+        assert_eq!(status, StatusCode::OK, "{}", page);
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["properties"].as_array().unwrap().len(), 2);
+        let cursor = page["next_cursor"].as_str().unwrap();
+        let (_, next) = send(
+            &app,
+            "GET",
+            &format!("{}?cursor={}", results, cursor),
+            &me,
+            None,
+        )
+        .await;
+        assert_eq!(next["properties"].as_array().unwrap().len(), 1);
+        let (status, _) = send(&app, "GET", &results, &stranger, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let search = format!("/api/searches/{}", id);
+        let (status, _) = send(&app, "DELETE", &search, &stranger, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(&app, "DELETE", &search, &me, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&app, "DELETE", &search, &me, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // The cap, counted per caller; a recognised API key is its own caller
+        let key = [(API_KEY_HEADER, "searches-test")];
+        let (_, list) = send(&app, "GET", "/api/searches", &key, None).await;
+        for search in list["searches"].as_array().unwrap() {
+            let uri = format!("/api/searches/{}", search["id"]);
+            send(&app, "DELETE", &uri, &key, None).await;
+        }
+        for n in 0..MAX_SAVED_SEARCHES {
+            let (status, _) = send(
+                &app,
+                "POST",
+                "/api/searches",
+                &key,
+                Some(json!({ "name": format!("Search {}", n), "filter": {} })),
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+        let (status, body) = send(
+            &app,
+            "POST",
+            "/api/searches",
+            &key,
+            Some(json!({ "name": "One too many", "filter": {} })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "too_many_searches");
+        let (status, _) = send(
+            &app,
+            "POST",
+            "/api/searches",
+            &me,
+            Some(json!({ "name": "Still room", "filter": {} })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        sqlx::query("DELETE FROM saved_searches WHERE owner IN (md5($1), md5($2))")
+            .bind(format!("client:{}", client))
+            .bind("key:searches-test")
+            .execute(&db)
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+    }
+}
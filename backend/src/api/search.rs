@@ -0,0 +1,286 @@
+//! Address search endpoint - free-text, case-insensitive partial matching on
+//! address and suburb, for autocomplete
+
+use crate::api::params::{
+    check_length, ParamError, ValidateParams, ValidatedListParams, MAX_STRING_LENGTH,
+};
+use crate::api::tier::{Access, Redact, Redactor};
+use crate::api::AppState;
+use crate::ingestion::types::State as AusState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+/// Shortest query that is searched; shorter ones match nothing
+pub const MIN_QUERY_LENGTH: usize = 3;
+
+/// Most matches in one response
+pub const MAX_SEARCH_RESULTS: i64 = 20;
+
+/// Query parameters for GET /api/properties/search
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchQuery {
+    /// Matched anywhere in the address or suburb, e.g. "10 George"
+    pub q: Option<String>,
+}
+
+impl ValidateParams for SearchQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("q", self.q.as_deref(), MAX_STRING_LENGTH)
+    }
+}
+
+/// One matching property, with enough location to tell similar addresses apart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SearchResult {
+    pub id: i32,
+    pub address: String,
+    pub suburb: String,
+    pub postcode: Option<String>,
+    pub state: AusState,
+}
+
+impl Redact for SearchResult {
+    fn redact(&mut self, redactor: &Redactor) {
+        redactor.address(&mut self.address);
+    }
+}
+
+/// Response for GET /api/properties/search
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResponse {
+    /// Most relevant first
+    pub results: Vec<SearchResult>,
+}
+
+impl Redact for SearchResponse {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.results.redact(redactor);
+    }
+}
+
+/// GET /api/properties/search - up to MAX_SEARCH_RESULTS properties matching `q`
+pub async fn search_properties(
+    State(state): State<AppState>,
+    access: Access,
+    ValidatedListParams(params): ValidatedListParams<SearchQuery>,
+) -> Result<Json<SearchResponse>, StatusCode> {
+    let Some(term) = params.q.as_deref().and_then(normalize_query) else {
+        return Ok(Json(SearchResponse {
+            results: Vec::new(),
+        }));
+    };
+
+    search_addresses(&state.read_db, &term, MAX_SEARCH_RESULTS)
+        .await
+        .map(|results| Json(access.apply(SearchResponse { results })))
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// The query with its whitespace collapsed, or None when it's too short to search
+fn normalize_query(q: &str) -> Option<String> {
+    let term = q.split_whitespace().collect::<Vec<_>>().join(" ");
+    (term.chars().count() >= MIN_QUERY_LENGTH).then_some(term)
+}
+
+/// Escape LIKE wildcards so they match literally
+pub(crate) fn escape_like(term: &str) -> String {
+    let mut escaped = String::with_capacity(term.len());
+    for c in term.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Properties whose address or suburb contains `term`, ignoring case
+/// Ranked: address starts with it, a word in the address does, the address
+/// contains it, then the same for the suburb, then "address suburb" matches;
+/// shorter addresses first within a rank
+pub async fn search_addresses(
+    db: &PgPool,
+    term: &str,
+    limit: i64,
+) -> Result<Vec<SearchResult>, sqlx::Error> {
+    let escaped = escape_like(term);
+
+    sqlx::query_as::<_, SearchResult>(
+        r#"
+        SELECT id, address, suburb, postcode, state
+        FROM properties
+        WHERE address ILIKE $1
+           OR suburb ILIKE $1
+           OR (address || ' ' || suburb) ILIKE $1
+        ORDER BY
+            CASE
+                WHEN address ILIKE $2 THEN 0
+                WHEN address ILIKE $3 THEN 1
+                WHEN address ILIKE $1 THEN 2
+                WHEN suburb ILIKE $2 THEN 3
+                WHEN suburb ILIKE $1 THEN 4
+                ELSE 5
+            END,
+            LENGTH(address), address, suburb, id
+        LIMIT $4
+        "#,
+    )
+    .bind(format!("%{}%", escaped))
+    .bind(format!("{}%", escaped))
+    .bind(format!("% {}%", escaped))
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tier::{Tier, TierConfig};
+    use crate::api::API_KEY_HEADER;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn get(db: &PgPool, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(AppState {
+            tiers: Arc::new(TierConfig::default().with_key("search-test", Tier::Full)),
+            ..test_state(db.clone())
+        });
+        let response = app
+            .oneshot(
+                Request::get(uri)
+                    .header(API_KEY_HEADER, "search-test")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(
+            normalize_query("  10   George "),
+            Some("10 George".to_string())
+        );
+        assert_eq!(normalize_query("abc"), Some("abc".to_string()));
+        assert_eq!(normalize_query("ab"), None);
+        assert_eq!(normalize_query("  ab  "), None);
+        assert_eq!(normalize_query(" a  b "), Some("a b".to_string()));
+        assert_eq!(normalize_query(""), None);
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("10 George"), "10 George");
+        assert_eq!(escape_like("100%_\\"), "100\\%\\_\\\\");
+    }
+
+    #[tokio::test]
+    async fn test_short_queries_skip_the_database() {
+        // Nothing listens here, so any query would fail with a 500
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+
+        for uri in [
+            "/api/properties/search",
+            "/api/properties/search?q=",
+            "/api/properties/search?q=10",
+            "/api/properties/search?q=%20%20ab%20",
+        ] {
+            let (status, body) = get(&db, uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            assert_eq!(body["results"], Value::Array(vec![]), "{}", uri);
+        }
+
+        let long = format!("/api/properties/search?q={}", "a".repeat(101));
+        let (status, body) = get(&db, &long).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "q");
+    }
+
+    const SUBURB: &str = "Search Testville";
+    const OTHER_SUBURB: &str = "Georgeton Search";
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_search_ranks_partial_matches() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        for suburb in [SUBURB, OTHER_SUBURB] {
+            delete_suburb(&db, suburb).await.unwrap();
+        }
+
+        let mut ids = Vec::new();
+        for (address, suburb) in [
+            ("2/110 George Street", SUBURB),
+            ("10 George Street", SUBURB),
+            ("10 Georgeview Lane", SUBURB),
+            ("5 Smith Street", OTHER_SUBURB),
+            ("10 Zzyzx Road", SUBURB),
+        ] {
+            let id = PropertyFixture::new()
+                .address(address)
+                .suburb(suburb)
+                .postcode("2999")
+                .insert(&db)
+                .await
+                .unwrap();
+            ids.push(id as i64);
+        }
+        let found = |body: &Value| -> Vec<i64> {
+            body["results"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["id"].as_i64().unwrap())
+                .filter(|id| ids.contains(id))
+                .collect()
+        };
+
+        // Address prefixes first, shorter first; then the match inside an address
+        let (status, body) = get(&db, "/api/properties/search?q=10%20george").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(found(&body), vec![ids[1], ids[2], ids[0]]);
+        let first = &body["results"][0];
+        assert_eq!(first["address"], "10 George Street");
+        assert_eq!(first["suburb"], SUBURB);
+        assert_eq!(first["postcode"], "2999");
+        assert_eq!(first["state"], "NSW");
+
+        // Word starts in the address come before a suburb match
+        let (_, body) = get(&db, "/api/properties/search?q=GEORGE").await;
+        assert_eq!(found(&body), vec![ids[1], ids[2], ids[0], ids[3]]);
+
+        // Across address and suburb
+        let (_, body) = get(&db, "/api/properties/search?q=zzyzx%20road%20search").await;
+        assert_eq!(found(&body), vec![ids[4]]);
+
+        // Wildcards are literal
+        let (_, body) = get(&db, "/api/properties/search?q=10%25%25").await;
+        assert_eq!(found(&body), Vec::<i64>::new());
+
+        for suburb in [SUBURB, OTHER_SUBURB] {
+            delete_suburb(&db, suburb).await.unwrap();
+        }
+    }
+}
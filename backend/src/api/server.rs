@@ -0,0 +1,188 @@
+//! Server configuration - where the API listens and how its database pools
+//! are sized
+
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use thiserror::Error;
+
+pub const DEFAULT_PORT: u16 = 3001;
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+pub const DEFAULT_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+
+/// A server setting that was set but can't be used
+#[derive(Debug, Error, PartialEq)]
+#[error("{var}={value:?} is invalid: {reason}")]
+pub struct ServerConfigError {
+    pub var: &'static str,
+    pub value: String,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub host: IpAddr,
+    pub port: u16,
+    /// Per pool; the read pool gets the same when a replica is configured
+    pub max_connections: u32,
+    /// How long a request waits for a free connection before failing
+    pub acquire_timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: DEFAULT_PORT,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            acquire_timeout: Duration::from_secs(DEFAULT_ACQUIRE_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// HOST, PORT, DATABASE_MAX_CONNECTIONS and DATABASE_ACQUIRE_TIMEOUT
+    /// (seconds); unset or empty ones keep their defaults
+    pub fn from_env() -> Result<Self, ServerConfigError> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// As `from_env`, reading variables through `var`
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, ServerConfigError> {
+        let defaults = ServerConfig::default();
+        let lookup = |name| var(name).filter(|value| !value.trim().is_empty());
+
+        Ok(ServerConfig {
+            host: parse(
+                lookup("HOST"),
+                "HOST",
+                "expected an IP address like 0.0.0.0",
+            )?
+            .unwrap_or(defaults.host),
+            port: positive(lookup("PORT"), "PORT", "expected a port from 1 to 65535")?
+                .unwrap_or(defaults.port),
+            max_connections: positive(
+                lookup("DATABASE_MAX_CONNECTIONS"),
+                "DATABASE_MAX_CONNECTIONS",
+                "expected a whole number of connections above 0",
+            )?
+            .unwrap_or(defaults.max_connections),
+            acquire_timeout: positive(
+                lookup("DATABASE_ACQUIRE_TIMEOUT"),
+                "DATABASE_ACQUIRE_TIMEOUT",
+                "expected a whole number of seconds above 0",
+            )?
+            .map(Duration::from_secs)
+            .unwrap_or(defaults.acquire_timeout),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+
+    /// Options for a pool sized by this config
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .acquire_timeout(self.acquire_timeout)
+    }
+}
+
+fn invalid(value: String, var: &'static str, reason: &'static str) -> ServerConfigError {
+    ServerConfigError { var, value, reason }
+}
+
+/// The parsed value, None when unset
+fn parse<T: std::str::FromStr>(
+    value: Option<String>,
+    var: &'static str,
+    reason: &'static str,
+) -> Result<Option<T>, ServerConfigError> {
+    match value {
+        None => Ok(None),
+        Some(raw) => match raw.trim().parse() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(_) => Err(invalid(raw, var, reason)),
+        },
+    }
+}
+
+/// As `parse`, rejecting zero
+fn positive<T: std::str::FromStr + Default + PartialEq>(
+    value: Option<String>,
+    var: &'static str,
+    reason: &'static str,
+) -> Result<Option<T>, ServerConfigError> {
+    match parse::<T>(value.clone(), var, reason)? {
+        Some(parsed) if parsed == T::default() => {
+            Err(invalid(value.unwrap_or_default(), var, reason))
+        }
+        parsed => Ok(parsed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(vars: &[(&str, &str)]) -> Result<ServerConfig, ServerConfigError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        ServerConfig::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_defaults() {
+        let defaults = config(&[]).unwrap();
+        assert_eq!(defaults, ServerConfig::default());
+        assert_eq!(defaults.addr(), "127.0.0.1:3001".parse().unwrap());
+        assert_eq!(defaults.acquire_timeout, Duration::from_secs(30));
+
+        // Empty values are unset
+        assert_eq!(
+            config(&[("HOST", ""), ("PORT", " ")]).unwrap(),
+            ServerConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overridden = config(&[
+            ("HOST", "0.0.0.0"),
+            ("PORT", "8080"),
+            ("DATABASE_MAX_CONNECTIONS", "20"),
+            ("DATABASE_ACQUIRE_TIMEOUT", " 3 "),
+        ])
+        .unwrap();
+        assert_eq!(overridden.addr(), "0.0.0.0:8080".parse().unwrap());
+        assert_eq!(overridden.max_connections, 20);
+        assert_eq!(overridden.acquire_timeout, Duration::from_secs(3));
+
+        let ipv6 = config(&[("HOST", "::")]).unwrap();
+        assert_eq!(ipv6.addr(), "[::]:3001".parse().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_values_name_the_variable() {
+        for (var, value) in [
+            ("HOST", "localhost"),
+            ("PORT", "0"),
+            ("PORT", "70000"),
+            ("PORT", "abc"),
+            ("DATABASE_MAX_CONNECTIONS", "0"),
+            ("DATABASE_MAX_CONNECTIONS", "-1"),
+            ("DATABASE_ACQUIRE_TIMEOUT", "0"),
+            ("DATABASE_ACQUIRE_TIMEOUT", "1.5"),
+        ] {
+            let err = config(&[(var, value)]).unwrap_err();
+            assert_eq!(err.var, var, "{}={}", var, value);
+            assert_eq!(err.value, value);
+            assert!(err.to_string().starts_with(&format!("{}=", var)));
+        }
+    }
+}
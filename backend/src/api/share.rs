@@ -0,0 +1,507 @@
+//! Shared comparisons - freeze a set of properties under a public token
+//! The snapshot is stored at share time so later ingestion updates don't
+//! change what was shared; current values are returned alongside for comparison
+
+use crate::api::params::{check_length, check_range, ParamError};
+use crate::api::rate_limit::RateLimiter;
+use crate::api::tier::{Access, Redact, Redactor};
+use crate::api::{AppState, API_KEY_HEADER};
+use crate::format::round_yield_for_display;
+use crate::ingestion::types::{PropertyType, State as AusState};
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json as SqlJson;
+use sqlx::PgPool;
+use std::env;
+use std::time::Duration;
+use tracing::error;
+use uuid::Uuid;
+
+/// Most properties a single share can hold
+pub const MAX_SHARE_PROPERTIES: usize = 10;
+
+/// Longest note accepted on a share
+pub const MAX_NOTE_LENGTH: usize = 500;
+
+/// Days a share stays readable when no expiry is requested
+pub const DEFAULT_EXPIRY_DAYS: i32 = 30;
+
+/// Longest expiry a caller can ask for
+pub const MAX_EXPIRY_DAYS: i32 = 90;
+
+/// Default number of shares one API key may create per hour
+pub const DEFAULT_SHARES_PER_HOUR: u32 = 20;
+
+/// Share-creation limiter, configured by SHARE_RATE_LIMIT_PER_HOUR
+pub fn share_rate_limiter_from_env() -> RateLimiter {
+    let per_hour = env::var("SHARE_RATE_LIMIT_PER_HOUR")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_SHARES_PER_HOUR);
+
+    RateLimiter::new(per_hour, Duration::from_secs(3600))
+}
+
+/// Body for POST /api/share
+#[derive(Debug, Deserialize)]
+pub struct CreateShareRequest {
+    pub property_ids: Vec<i32>,
+    pub note: Option<String>,
+    /// Defaults to DEFAULT_EXPIRY_DAYS
+    pub expires_in_days: Option<i32>,
+}
+
+impl CreateShareRequest {
+    /// Check limits and return the de-duplicated property ids
+    fn validate(&self) -> Result<Vec<i32>, ParamError> {
+        let mut ids = self.property_ids.clone();
+        ids.sort_unstable();
+        ids.dedup();
+
+        if ids.is_empty() || ids.len() > MAX_SHARE_PROPERTIES {
+            return Err(ParamError::new(
+                "property_ids",
+                format!(
+                    "must contain between 1 and {} properties",
+                    MAX_SHARE_PROPERTIES
+                ),
+            ));
+        }
+        check_length("note", self.note.as_deref(), MAX_NOTE_LENGTH)?;
+        check_range("expires_in_days", self.expires_in_days, 1..=MAX_EXPIRY_DAYS)?;
+
+        Ok(ids)
+    }
+}
+
+/// Response for POST /api/share
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareCreated {
+    pub token: String,
+    pub expires_at: NaiveDateTime,
+}
+
+/// Property values captured in a share
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SharedProperty {
+    pub id: i32,
+    pub address: String,
+    pub suburb: String,
+    pub state: AusState,
+    pub postcode: Option<String>,
+    pub property_type: Option<PropertyType>,
+    pub bedrooms: Option<i32>,
+    pub bathrooms: Option<i32>,
+    pub price: Option<i64>,
+    pub weekly_rent: Option<i32>,
+    pub rental_yield: Option<Decimal>,
+    pub sale_date: Option<NaiveDate>,
+    pub latitude: Option<Decimal>,
+    pub longitude: Option<Decimal>,
+}
+
+impl Redact for SharedProperty {
+    fn redact(&mut self, redactor: &Redactor) {
+        redactor.address(&mut self.address);
+        redactor.optional_price(&mut self.price);
+        self.rental_yield = redactor.rental_yield(self.price, self.weekly_rent);
+        redactor.decimal_coordinate(&mut self.latitude);
+        redactor.decimal_coordinate(&mut self.longitude);
+    }
+}
+
+/// One shared property: as captured, and as it is now (None if since deleted)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedComparisonEntry {
+    pub snapshot: SharedProperty,
+    pub current: Option<SharedProperty>,
+}
+
+impl Redact for SharedComparisonEntry {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.snapshot.redact(redactor);
+        self.current.redact(redactor);
+    }
+}
+
+/// Response for GET /api/share/:token
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedComparison {
+    pub note: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub properties: Vec<SharedComparisonEntry>,
+}
+
+impl Redact for SharedComparison {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.properties.redact(redactor);
+    }
+}
+
+/// POST /api/share - snapshot a set of properties under a new token
+pub async fn create_share(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateShareRequest>,
+) -> Result<(StatusCode, Json<ShareCreated>), Response> {
+    let caller = headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("anonymous");
+    if !state.share_limiter.check(caller) {
+        return Err(StatusCode::TOO_MANY_REQUESTS.into_response());
+    }
+
+    let ids = request.validate().map_err(IntoResponse::into_response)?;
+
+    let properties = fetch_shared_properties(&state.db, &ids)
+        .await
+        .map_err(internal_error)?;
+    if properties.len() != ids.len() {
+        return Err(ParamError::new("property_ids", "unknown property id").into_response());
+    }
+
+    let token = Uuid::new_v4().simple().to_string();
+    let expires_at = sqlx::query_scalar::<_, NaiveDateTime>(
+        r#"
+        INSERT INTO shared_comparisons (token, property_ids, note, snapshot, expires_at)
+        VALUES ($1, $2, $3, $4, NOW() + make_interval(days => $5))
+        RETURNING expires_at
+        "#,
+    )
+    .bind(&token)
+    .bind(&ids)
+    .bind(&request.note)
+    .bind(SqlJson(&properties))
+    .bind(request.expires_in_days.unwrap_or(DEFAULT_EXPIRY_DAYS))
+    .fetch_one(&state.db)
+    .await
+    .map_err(internal_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ShareCreated { token, expires_at }),
+    ))
+}
+
+/// GET /api/share/:token - the frozen snapshot plus current values
+/// Unknown and expired tokens are both 404
+/// Snapshots are stored in full and redacted for the reader's tier
+pub async fn get_share(
+    State(state): State<AppState>,
+    access: Access,
+    Path(token): Path<String>,
+) -> Result<Json<SharedComparison>, StatusCode> {
+    if token.len() > 64 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let share = sqlx::query_as::<
+        _,
+        (
+            Vec<i32>,
+            Option<String>,
+            SqlJson<Vec<SharedProperty>>,
+            NaiveDateTime,
+            NaiveDateTime,
+        ),
+    >(
+        r#"
+        SELECT property_ids, note, snapshot, created_at, expires_at
+        FROM shared_comparisons
+        WHERE token = $1 AND expires_at > NOW()
+        "#,
+    )
+    .bind(&token)
+    .fetch_optional(&state.read_db)
+    .await
+    .map_err(|e| {
+        error!("Database error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let (ids, note, SqlJson(snapshot), created_at, expires_at) = share;
+
+    let current = fetch_shared_properties(&state.read_db, &ids)
+        .await
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let properties = snapshot
+        .into_iter()
+        .map(|snapshot| SharedComparisonEntry {
+            current: current.iter().find(|p| p.id == snapshot.id).cloned(),
+            snapshot,
+        })
+        .collect();
+
+    Ok(Json(access.apply(SharedComparison {
+        note,
+        created_at,
+        expires_at,
+        properties,
+    })))
+}
+
+fn internal_error(e: sqlx::Error) -> Response {
+    error!("Database error: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+/// Current values for the given property ids, ordered by id
+pub async fn fetch_shared_properties(
+    db: &PgPool,
+    ids: &[i32],
+) -> Result<Vec<SharedProperty>, sqlx::Error> {
+    let mut properties = sqlx::query_as::<_, SharedProperty>(
+        r#"
+        SELECT
+            id, address, suburb, state, postcode, property_type, bedrooms, bathrooms,
+            price, weekly_rent, rental_yield, sale_date, latitude, longitude
+        FROM properties
+        WHERE id = ANY($1)
+        ORDER BY id
+        "#,
+    )
+    .bind(ids)
+    .fetch_all(db)
+    .await?;
+
+    // Yields are stored at four places; snapshots and responses show two
+    for property in &mut properties {
+        property.rental_yield = property.rental_yield.map(round_yield_for_display);
+    }
+    Ok(properties)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn request(property_ids: Vec<i32>) -> CreateShareRequest {
+        CreateShareRequest {
+            property_ids,
+            note: None,
+            expires_in_days: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_share_request() {
+        assert_eq!(request(vec![3, 1, 3]).validate().unwrap(), vec![1, 3]);
+
+        assert_eq!(
+            request(vec![]).validate().unwrap_err().field,
+            "property_ids"
+        );
+        let too_many = (1..=MAX_SHARE_PROPERTIES as i32 + 1).collect();
+        assert_eq!(
+            request(too_many).validate().unwrap_err().field,
+            "property_ids"
+        );
+
+        let mut long_note = request(vec![1]);
+        long_note.note = Some("x".repeat(MAX_NOTE_LENGTH + 1));
+        assert_eq!(long_note.validate().unwrap_err().field, "note");
+
+        let mut forever = request(vec![1]);
+        forever.expires_in_days = Some(MAX_EXPIRY_DAYS + 1);
+        assert_eq!(forever.validate().unwrap_err().field, "expires_in_days");
+    }
+
+    const SUBURB: &str = "Share Testville";
+
+    async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn post_share(body: Value) -> Request<Body> {
+        Request::post("/api/share")
+            .header("content-type", "application/json")
+            .header(API_KEY_HEADER, "share-test")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_share_snapshot_survives_property_update() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+
+        let id = PropertyFixture::new()
+            .address("1 Share St")
+            .suburb(SUBURB)
+            .price(700_000)
+            .weekly_rent(600)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let app = crate::api::router().with_state(AppState {
+            share_limiter: Arc::new(RateLimiter::new(100, Duration::from_secs(3600))),
+            ..test_state(db.clone())
+        });
+
+        let (status, created) = send(
+            &app,
+            post_share(serde_json::json!({ "property_ids": [id], "note": "Have a look" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let token = created["token"].as_str().unwrap().to_string();
+
+        // A later ingestion run changes the property
+        sqlx::query("UPDATE properties SET price = 750000 WHERE id = $1")
+            .bind(id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let uri = format!("/api/share/{}", token);
+        let (status, shared) = send(&app, Request::get(&uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(shared["note"], "Have a look");
+        assert_eq!(shared["properties"][0]["snapshot"]["price"], 700000);
+        assert_eq!(shared["properties"][0]["current"]["price"], 750000);
+
+        // Unknown property ids are rejected
+        let (status, body) = send(
+            &app,
+            post_share(serde_json::json!({ "property_ids": [-1] })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "property_ids");
+
+        // Expired and unknown tokens are both 404
+        sqlx::query("UPDATE shared_comparisons SET expires_at = NOW() - INTERVAL '1 second' WHERE token = $1")
+            .bind(&token)
+            .execute(&db)
+            .await
+            .unwrap();
+        for uri in [uri.as_str(), "/api/share/not-a-token"] {
+            let (status, _) = send(&app, Request::get(uri).body(Body::empty()).unwrap()).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+        }
+
+        sqlx::query("DELETE FROM shared_comparisons WHERE token = $1")
+            .bind(&token)
+            .execute(&db)
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_reads_and_writes_use_expected_pool() {
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let db = PgPool::connect(&url).await.unwrap();
+
+        // Stand-in replica: an empty copy of the tables in another schema
+        for statement in [
+            "DROP SCHEMA IF EXISTS read_pool_test CASCADE",
+            "CREATE SCHEMA read_pool_test",
+            "CREATE TABLE read_pool_test.properties (LIKE public.properties INCLUDING ALL)",
+            "CREATE TABLE read_pool_test.shared_comparisons (LIKE public.shared_comparisons INCLUDING ALL)",
+        ] {
+            sqlx::query(statement).execute(&db).await.unwrap();
+        }
+        let read_db = sqlx::postgres::PgPoolOptions::new()
+            .after_connect(|conn, _| {
+                Box::pin(async move {
+                    sqlx::query("SET search_path TO read_pool_test, public")
+                        .execute(conn)
+                        .await
+                        .map(|_| ())
+                })
+            })
+            .connect(&url)
+            .await
+            .unwrap();
+
+        let id = PropertyFixture::new()
+            .address("2 Replica St")
+            .suburb(SUBURB)
+            .bedrooms(2)
+            .price(500_000)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let app = crate::api::router().with_state(AppState {
+            read_db,
+            share_limiter: Arc::new(RateLimiter::new(100, Duration::from_secs(3600))),
+            ..test_state(db.clone())
+        });
+
+        // The write validates and inserts against the primary
+        let (status, created) = send(
+            &app,
+            post_share(serde_json::json!({ "property_ids": [id] })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let token = created["token"].as_str().unwrap().to_string();
+
+        // The read goes to the replica, which hasn't seen the share
+        let uri = format!("/api/share/{}", token);
+        let (status, _) = send(&app, Request::get(&uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        sqlx::query("DELETE FROM shared_comparisons WHERE token = $1")
+            .bind(&token)
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM properties WHERE id = $1")
+            .bind(id)
+            .execute(&db)
+            .await
+            .unwrap();
+        sqlx::query("DROP SCHEMA read_pool_test CASCADE")
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_share_creation_rate_limited() {
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let app = crate::api::router().with_state(AppState {
+            share_limiter: Arc::new(RateLimiter::new(1, Duration::from_secs(3600))),
+            ..test_state(db)
+        });
+
+        // The first request is allowed through (and rejected on validation)
+        let (status, _) = send(&app, post_share(serde_json::json!({ "property_ids": [] }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = send(&app, post_share(serde_json::json!({ "property_ids": [] }))).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+}
@@ -0,0 +1,464 @@
+//! Landing-page aggregates - top-yielding suburbs and market summary
+//! Both are expensive queries, so responses go through the SWR cache
+
+use crate::analytics::suppression::{apply_suppression, Aggregate, SuppressionConfig};
+use crate::api::cache::CacheStatus;
+use crate::api::params::{check_range, ParamError, ValidateParams, ValidatedListParams};
+use crate::api::AppState;
+use crate::format::round_yield_f64;
+use crate::ingestion::types::{PropertyType, State as AusState};
+use axum::extract::State;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use tracing::error;
+
+/// Default number of suburbs in the top-yields list
+const DEFAULT_TOP_LIMIT: i64 = 20;
+
+/// Longest top-yields list a client can request
+const MAX_TOP_LIMIT: i64 = 100;
+
+/// Yields (%) outside this range are left out of the yield aggregates, so one
+/// bad row can't skew the mean
+pub const SANE_YIELD_RANGE: (f64, f64) = (0.0, 30.0);
+
+const X_CACHE: HeaderName = HeaderName::from_static("x-cache");
+
+/// Query parameters for GET /api/suburbs/top-yields
+#[derive(Debug, Deserialize)]
+pub struct TopYieldsQuery {
+    pub state: Option<AusState>,
+    pub bedrooms: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+impl ValidateParams for TopYieldsQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_range("bedrooms", self.bedrooms, 0..=20)?;
+        check_range("limit", self.limit, 1..=MAX_TOP_LIMIT)
+    }
+}
+
+impl TopYieldsQuery {
+    /// Cache key with defaults applied, so equivalent queries share an entry
+    pub fn cache_key(&self) -> String {
+        format!(
+            "state={}&bedrooms={}&limit={}",
+            self.state.map(|s| s.to_string()).unwrap_or_default(),
+            self.bedrooms.map(|b| b.to_string()).unwrap_or_default(),
+            self.limit.unwrap_or(DEFAULT_TOP_LIMIT)
+        )
+    }
+}
+
+/// Query parameters for GET /api/stats
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    pub state: Option<AusState>,
+}
+
+impl ValidateParams for StatsQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        Ok(())
+    }
+}
+
+impl StatsQuery {
+    pub fn cache_key(&self) -> String {
+        format!(
+            "state={}",
+            self.state.map(|s| s.to_string()).unwrap_or_default()
+        )
+    }
+}
+
+/// One suburb/bedroom group from the latest suburb statistics
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TopYield {
+    pub suburb: String,
+    pub postcode: Option<String>,
+    pub state: AusState,
+    pub bedrooms: Option<i32>,
+    pub median_rental_yield: Option<f64>,
+    pub median_price: Option<i32>,
+    pub median_weekly_rent: Option<i32>,
+    pub property_count: Option<i32>,
+}
+
+/// Market-wide summary
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MarketStats {
+    pub property_count: i64,
+    pub suburb_count: i64,
+    pub median_price: Option<f64>,
+    pub median_weekly_rent: Option<f64>,
+    /// Over yields within SANE_YIELD_RANGE
+    pub median_rental_yield: Option<f64>,
+    /// Over yields within SANE_YIELD_RANGE
+    pub mean_rental_yield: Option<f64>,
+    /// Most recent sale date of any property
+    pub last_sale_date: Option<NaiveDate>,
+    pub last_updated: Option<NaiveDateTime>,
+}
+
+/// Response for GET /api/stats - the market summary with its breakdowns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsResponse {
+    #[serde(flatten)]
+    pub market: MarketStats,
+    /// Property count per state, e.g. {"NSW": 1200}
+    pub count_by_state: BTreeMap<String, i64>,
+    /// Property count per property type; untyped properties aren't counted
+    pub count_by_property_type: BTreeMap<String, i64>,
+    /// When the most recent successful ingestion run finished, for any source
+    pub last_ingestion_at: Option<NaiveDateTime>,
+}
+
+/// JSON body with the X-Cache header attached
+fn cached_response<T: Serialize>(value: &T, status: CacheStatus) -> Response {
+    let mut response = Json(value).into_response();
+    response
+        .headers_mut()
+        .insert(X_CACHE, HeaderValue::from_static(status.as_header()));
+    response
+}
+
+/// GET /api/suburbs/top-yields - highest median yields from suburb statistics
+pub async fn get_top_yields(
+    State(state): State<AppState>,
+    ValidatedListParams(params): ValidatedListParams<TopYieldsQuery>,
+) -> Result<Response, StatusCode> {
+    let db = state.read_db.clone();
+    let suppression = state.suppression;
+    let limit = params.limit.unwrap_or(DEFAULT_TOP_LIMIT);
+
+    let (top, status) = state
+        .caches
+        .top_yields
+        .get(&params.cache_key(), || async move {
+            let rows = fetch_top_yields(
+                &db,
+                params.state,
+                params.bedrooms,
+                suppression.min_sample_size,
+                limit,
+            )
+            .await?;
+            Ok(suppress_small_yields(rows, &suppression))
+        })
+        .await
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(cached_response(&*top, status))
+}
+
+/// GET /api/stats - property, price, rent and yield summary, with counts by
+/// state and type and how fresh the data is
+pub async fn get_stats(
+    State(state): State<AppState>,
+    ValidatedListParams(params): ValidatedListParams<StatsQuery>,
+) -> Result<Response, StatusCode> {
+    let db = state.read_db.clone();
+
+    let (stats, status) = state
+        .caches
+        .stats
+        .get(&params.cache_key(), || async move {
+            Ok(fetch_stats(&db, params.state).await?)
+        })
+        .await
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(cached_response(&*stats, status))
+}
+
+/// The rows that clear the suppression threshold, in order
+/// Small groups are never rolled up here, as a region's yield would be ranked
+/// as the suburb's
+pub fn suppress_small_yields(rows: Vec<TopYield>, config: &SuppressionConfig) -> Vec<TopYield> {
+    let aggregates = rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(Aggregate {
+                value: row.median_rental_yield?,
+                sample_size: row.property_count.unwrap_or(0) as i64,
+                key: row,
+            })
+        })
+        .collect();
+
+    apply_suppression(aggregates, |_| None::<()>, config)
+        .into_iter()
+        .filter(|p| !p.suppressed)
+        .map(|p| p.key)
+        .collect()
+}
+
+/// Top groups by median yield from the most recent calculation of each
+/// Groups below the suppression threshold are left out of the ranking
+pub async fn fetch_top_yields(
+    db: &PgPool,
+    state: Option<AusState>,
+    bedrooms: Option<i32>,
+    min_sample_size: i64,
+    limit: i64,
+) -> Result<Vec<TopYield>, sqlx::Error> {
+    sqlx::query_as::<_, TopYield>(
+        r#"
+        SELECT * FROM (
+            SELECT DISTINCT ON (suburb, postcode, state, bedrooms)
+                suburb,
+                postcode,
+                state,
+                bedrooms,
+                median_rental_yield::FLOAT8 AS median_rental_yield,
+                median_price,
+                median_weekly_rent,
+                property_count
+            FROM suburb_statistics
+            WHERE ($1::state_enum IS NULL OR state = $1)
+              AND ($2::INTEGER IS NULL OR bedrooms = $2)
+              AND property_count >= $3
+              AND median_rental_yield IS NOT NULL
+            ORDER BY suburb, postcode, state, bedrooms, calculated_date DESC
+        ) latest
+        ORDER BY median_rental_yield DESC, suburb
+        LIMIT $4
+        "#,
+    )
+    .bind(state)
+    .bind(bedrooms)
+    .bind(min_sample_size)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// The market summary, breakdowns and last ingestion, queried concurrently
+pub async fn fetch_stats(
+    db: &PgPool,
+    state: Option<AusState>,
+) -> Result<StatsResponse, sqlx::Error> {
+    let (market, count_by_state, count_by_property_type, last_ingestion_at) = tokio::try_join!(
+        fetch_market_stats(db, state),
+        fetch_counts_by_state(db, state),
+        fetch_counts_by_property_type(db, state),
+        sqlx::query_scalar::<_, Option<NaiveDateTime>>(
+            "SELECT MAX(completed_at) FROM ingestion_runs WHERE status = 'completed'",
+        )
+        .fetch_one(db),
+    )?;
+
+    Ok(StatsResponse {
+        market,
+        count_by_state,
+        count_by_property_type,
+        last_ingestion_at,
+    })
+}
+
+pub async fn fetch_market_stats(
+    db: &PgPool,
+    state: Option<AusState>,
+) -> Result<MarketStats, sqlx::Error> {
+    sqlx::query_as::<_, MarketStats>(
+        r#"
+        SELECT
+            COUNT(*) AS property_count,
+            COUNT(DISTINCT (suburb, state)) AS suburb_count,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY price) AS median_price,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY weekly_rent) AS median_weekly_rent,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY rental_yield)
+                FILTER (WHERE rental_yield BETWEEN $2 AND $3) AS median_rental_yield,
+            AVG(rental_yield::FLOAT8)
+                FILTER (WHERE rental_yield BETWEEN $2 AND $3) AS mean_rental_yield,
+            MAX(sale_date) AS last_sale_date,
+            MAX(last_updated) AS last_updated
+        FROM properties
+        WHERE $1::state_enum IS NULL OR state = $1
+        "#,
+    )
+    .bind(state)
+    .bind(SANE_YIELD_RANGE.0)
+    .bind(SANE_YIELD_RANGE.1)
+    .fetch_one(db)
+    .await
+    .map(|stats| MarketStats {
+        median_rental_yield: stats.median_rental_yield.map(round_yield_f64),
+        mean_rental_yield: stats.mean_rental_yield.map(round_yield_f64),
+        ..stats
+    })
+}
+
+/// Property count per state, keyed as the API writes states
+async fn fetch_counts_by_state(
+    db: &PgPool,
+    state: Option<AusState>,
+) -> Result<BTreeMap<String, i64>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (AusState, i64)>(
+        r#"
+        SELECT state, COUNT(*)
+        FROM properties
+        WHERE $1::state_enum IS NULL OR state = $1
+        GROUP BY state
+        "#,
+    )
+    .bind(state)
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows.into_iter().map(|(s, n)| (s.to_string(), n)).collect())
+}
+
+/// Property count per property type, keyed as the API writes types
+async fn fetch_counts_by_property_type(
+    db: &PgPool,
+    state: Option<AusState>,
+) -> Result<BTreeMap<String, i64>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (PropertyType, i64)>(
+        r#"
+        SELECT property_type, COUNT(*)
+        FROM properties
+        WHERE ($1::state_enum IS NULL OR state = $1)
+          AND property_type IS NOT NULL
+        GROUP BY property_type
+        "#,
+    )
+    .bind(state)
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(t, n)| match serde_json::to_value(t) {
+            Ok(serde_json::Value::String(name)) => Some((name, n)),
+            _ => None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, PropertyFixture};
+
+    #[test]
+    fn test_cache_keys_normalized() {
+        let explicit = TopYieldsQuery {
+            state: Some(AusState::NSW),
+            bedrooms: None,
+            limit: Some(DEFAULT_TOP_LIMIT),
+        };
+        let defaulted = TopYieldsQuery {
+            state: Some(AusState::NSW),
+            bedrooms: None,
+            limit: None,
+        };
+
+        assert_eq!(explicit.cache_key(), defaulted.cache_key());
+        assert_eq!(defaulted.cache_key(), "state=NSW&bedrooms=&limit=20");
+        assert_eq!(StatsQuery { state: None }.cache_key(), "state=");
+    }
+
+    #[test]
+    fn test_top_yields_skip_small_groups() {
+        let row = |suburb: &str, median_rental_yield, property_count| TopYield {
+            suburb: suburb.to_string(),
+            postcode: Some("2000".to_string()),
+            state: AusState::NSW,
+            bedrooms: Some(2),
+            median_rental_yield: Some(median_rental_yield),
+            median_price: Some(700_000),
+            median_weekly_rent: Some(650),
+            property_count,
+        };
+        let rows = vec![
+            row("Tiny", 9.5, Some(4)),
+            row("High", 6.1, Some(5)),
+            row("Unknown", 5.8, None),
+            row("Middle", 5.2, Some(40)),
+            row("Low", 4.0, Some(12)),
+        ];
+        // Roll-up doesn't apply to the ranking
+        let config = SuppressionConfig {
+            min_sample_size: 5,
+            roll_up: true,
+        };
+
+        let top = suppress_small_yields(rows, &config);
+        let suburbs: Vec<&str> = top.iter().map(|t| t.suburb.as_str()).collect();
+        assert_eq!(suburbs, vec!["High", "Middle", "Low"]);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_stats_breakdowns_and_yield_range() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let suburb = "Stats Testville";
+        delete_suburb(&db, suburb).await.unwrap();
+
+        let sold = |d| NaiveDate::from_ymd_opt(2024, 5, d).unwrap();
+        for (n, property_type, rental_yield, sale) in [
+            (1, PropertyType::House, Some("4.00"), Some(sold(1))),
+            (2, PropertyType::House, Some("6.00"), Some(sold(20))),
+            (3, PropertyType::Unit, Some("5.00"), None),
+            // Out of range either way, so left out of the yield aggregates
+            (4, PropertyType::Unit, Some("95.00"), None),
+            (5, PropertyType::Unit, Some("-2.00"), None),
+            (6, PropertyType::Townhouse, None, None),
+        ] {
+            let mut fixture = PropertyFixture::new()
+                .address(&format!("{} Stats St", n))
+                .suburb(suburb)
+                .state(AusState::NT)
+                .postcode("0800")
+                .property_type(property_type);
+            if let Some(rental_yield) = rental_yield {
+                fixture = fixture.rental_yield(rental_yield);
+            }
+            if let Some(date) = sale {
+                fixture = fixture.sold(500_000, date);
+            }
+            fixture.insert(&db).await.unwrap();
+        }
+
+        let stats = fetch_stats(&db, Some(AusState::NT)).await.unwrap();
+        assert_eq!(stats.market.property_count, 6);
+        assert_eq!(stats.market.median_rental_yield, Some(5.0));
+        assert_eq!(stats.market.mean_rental_yield, Some(5.0));
+        assert_eq!(stats.market.last_sale_date, Some(sold(20)));
+        assert_eq!(
+            stats.count_by_state,
+            BTreeMap::from([("NT".to_string(), 6)])
+        );
+        assert_eq!(
+            stats.count_by_property_type,
+            BTreeMap::from([
+                ("House".to_string(), 2),
+                ("Townhouse".to_string(), 1),
+                ("Unit".to_string(), 3),
+            ])
+        );
+
+        // The breakdowns sit beside the summary fields
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["property_count"], 6);
+        assert_eq!(json["count_by_state"]["NT"], 6);
+        assert!(json.get("last_ingestion_at").is_some());
+
+        delete_suburb(&db, suburb).await.unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+//! Background refresh of suburb statistics inside the API server
+//! Enabled by setting STATS_REFRESH_INTERVAL (seconds)
+
+use crate::analytics::suburb_stats::{
+    changed_states, last_updated_by_state, refresh_suburb_statistics,
+};
+use crate::api::cache::ResponseCaches;
+use crate::ingestion::types::State;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
+
+/// Spawn the refresh loop
+///
+/// Cycles run sequentially in a single task, so a slow refresh delays the next
+/// tick instead of overlapping with it. The loop exits once `shutdown` flips
+/// to true (or its sender is dropped); a cycle already in progress finishes first.
+pub fn spawn_stats_refresh(
+    db: PgPool,
+    caches: Arc<ResponseCaches>,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        // Latest last_updated per state as of the last successful computation
+        let mut seen: HashMap<State, NaiveDateTime> = HashMap::new();
+
+        info!("Suburb statistics refresh running every {:?}", interval);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.changed() => break,
+            }
+            if *shutdown.borrow() {
+                break;
+            }
+
+            let started = Instant::now();
+            match refresh_cycle(&db, &mut seen).await {
+                Ok(refreshed) if refreshed.is_empty() => {
+                    debug!("Suburb statistics unchanged, skipping refresh");
+                }
+                Ok(refreshed) => {
+                    // New data has landed, so cached aggregates are out of date
+                    caches.clear();
+                    let summary: Vec<String> = refreshed
+                        .iter()
+                        .map(|(state, groups)| format!("{} ({} groups)", state, groups))
+                        .collect();
+                    info!(
+                        "Refreshed suburb statistics in {:?}: {}",
+                        started.elapsed(),
+                        summary.join(", ")
+                    );
+                }
+                Err(e) => warn!("Suburb statistics refresh failed: {}", e),
+            }
+        }
+
+        info!("Suburb statistics refresh stopped");
+    })
+}
+
+/// Recompute statistics for every state that changed since the last cycle
+/// Only successfully refreshed states are marked as seen, so failures retry next cycle
+async fn refresh_cycle(
+    db: &PgPool,
+    seen: &mut HashMap<State, NaiveDateTime>,
+) -> Result<Vec<(State, u64)>> {
+    let current = last_updated_by_state(db).await?;
+    let mut refreshed = Vec::new();
+
+    for state in changed_states(seen, &current) {
+        let groups = refresh_suburb_statistics(db, state).await?;
+        seen.insert(state, current[&state]);
+        refreshed.push((state, groups));
+    }
+
+    Ok(refreshed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, PropertyFixture};
+
+    const SUBURB: &str = "Stats Refresh Testville";
+
+    async fn stats_count(db: &PgPool) -> i64 {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM suburb_statistics WHERE suburb = $1 AND calculated_date = CURRENT_DATE",
+        )
+        .bind(SUBURB)
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_property_insert_reflected_within_two_cycles() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+        sqlx::query("DELETE FROM suburb_statistics WHERE suburb = $1")
+            .bind(SUBURB)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let interval = Duration::from_millis(300);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let handle = spawn_stats_refresh(db.clone(), Default::default(), interval, shutdown_rx);
+
+        // Let the first cycle record the current state of the table
+        tokio::time::sleep(interval).await;
+
+        PropertyFixture::new()
+            .address("1 Refresh St")
+            .suburb(SUBURB)
+            .state(State::TAS)
+            .postcode("7000")
+            .price(500_000)
+            .weekly_rent(500)
+            .rental_yield("5.20")
+            .insert(&db)
+            .await
+            .unwrap();
+
+        tokio::time::sleep(interval * 2 + Duration::from_millis(100)).await;
+        assert_eq!(stats_count(&db).await, 1);
+
+        shutdown_tx.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("refresh task should stop on shutdown")
+            .unwrap();
+
+        delete_suburb(&db, SUBURB).await.unwrap();
+        sqlx::query("DELETE FROM suburb_statistics WHERE suburb = $1")
+            .bind(SUBURB)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+}
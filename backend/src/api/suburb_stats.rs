@@ -0,0 +1,465 @@
+//! Suburb statistics endpoint - the latest calculated medians for each
+//! suburb/postcode/bedroom group, for comparing suburbs side by side
+
+use crate::analytics::suppression::{apply_suppression, Aggregate, Published, SuppressionConfig};
+use crate::api::params::{
+    check_length, check_range, ParamError, ValidateParams, ValidatedListParams, MAX_STRING_LENGTH,
+};
+use crate::api::AppState;
+use crate::format::round_yield_f64;
+use crate::ingestion::districts::normalize_district;
+use crate::ingestion::types::State as AusState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::error;
+
+/// Query parameters for GET /api/suburbs/stats
+#[derive(Debug, Default, Deserialize)]
+pub struct SuburbStatisticsQuery {
+    /// Case-insensitive
+    pub suburb: Option<String>,
+    pub postcode: Option<String>,
+    /// NSW Valuer General district code; groups with properties in it
+    pub district: Option<String>,
+    pub state: Option<AusState>,
+    pub bedrooms: Option<i32>,
+}
+
+impl ValidateParams for SuburbStatisticsQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("suburb", self.suburb.as_deref(), MAX_STRING_LENGTH)?;
+        check_length("postcode", self.postcode.as_deref(), 10)?;
+        if let Some(code) = &self.district {
+            if normalize_district(code).is_none() {
+                return Err(ParamError::new("district", "not a district code"));
+            }
+        }
+        check_range("bedrooms", self.bedrooms, 0..=20)
+    }
+}
+
+/// One group from its most recent calculation
+/// The statistics are None when the group is suppressed. A small group rolled
+/// up to its SA3 carries the SA3's combined medians and no yield range.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SuburbStatistics {
+    pub suburb: String,
+    pub postcode: Option<String>,
+    pub state: AusState,
+    pub bedrooms: Option<i32>,
+    pub median_price: Option<i32>,
+    pub median_weekly_rent: Option<i32>,
+    pub median_rental_yield: Option<f64>,
+    pub min_yield: Option<f64>,
+    pub max_yield: Option<f64>,
+    pub property_count: Option<i32>,
+    pub calculated_date: NaiveDate,
+    /// SA3 of the postcode, the region small groups roll up into
+    #[serde(skip)]
+    pub sa3_code: Option<String>,
+    /// Fewer properties than the suppression minimum, and not rolled up
+    #[sqlx(skip)]
+    pub suppressed: bool,
+    /// SA3 whose combined medians were published in place of this group's
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rolled_up_to: Option<String>,
+}
+
+/// Response for GET /api/suburbs/stats
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuburbStatisticsResponse {
+    /// Ordered by suburb, postcode and bedrooms
+    pub groups: Vec<SuburbStatistics>,
+}
+
+/// GET /api/suburbs/stats - latest suburb statistics, optionally filtered
+pub async fn get_suburb_statistics(
+    State(state): State<AppState>,
+    ValidatedListParams(params): ValidatedListParams<SuburbStatisticsQuery>,
+) -> Result<Json<SuburbStatisticsResponse>, StatusCode> {
+    let groups = fetch_suburb_statistics(&state.read_db, &params)
+        .await
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(SuburbStatisticsResponse {
+        groups: suppress_small_groups(groups, &state.suppression),
+    }))
+}
+
+/// Withhold the statistics of groups built from too few properties, or
+/// publish their SA3's combined medians when roll-up is enabled
+/// Small groups only roll up with groups of the same bedroom count.
+fn suppress_small_groups(
+    groups: Vec<SuburbStatistics>,
+    config: &SuppressionConfig,
+) -> Vec<SuburbStatistics> {
+    let parent_of = |i: &usize| {
+        let group = &groups[*i];
+        Some((group.sa3_code.clone()?, group.bedrooms))
+    };
+    let publish = |metric: fn(&SuburbStatistics) -> Option<f64>| {
+        let aggregates = groups
+            .iter()
+            .enumerate()
+            .filter_map(|(i, group)| {
+                Some(Aggregate {
+                    key: i,
+                    value: metric(group)?,
+                    sample_size: group.property_count.unwrap_or(0) as i64,
+                })
+            })
+            .collect();
+        apply_suppression(aggregates, parent_of, config)
+            .into_iter()
+            .map(|p| (p.key, p))
+            .collect::<HashMap<usize, Published<usize, (String, Option<i32>)>>>()
+    };
+
+    let prices = publish(|g| g.median_price.map(f64::from));
+    let rents = publish(|g| g.median_weekly_rent.map(f64::from));
+    let yields = publish(|g| g.median_rental_yield);
+    let min_yields = publish(|g| g.min_yield);
+    let max_yields = publish(|g| g.max_yield);
+
+    groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let published = [&prices, &rents, &yields].map(|metric| metric.get(&i));
+            let rolled_up_to = published
+                .iter()
+                .flatten()
+                .find_map(|p| p.rolled_up_to.as_ref())
+                .map(|(sa3_code, _)| sa3_code.clone());
+            let value = |metric: &HashMap<usize, Published<_, _>>| metric.get(&i)?.value;
+            // A combined minimum or maximum isn't the region's range
+            let own_value = |metric: &HashMap<usize, Published<_, _>>| {
+                metric.get(&i).filter(|p| p.rolled_up_to.is_none())?.value
+            };
+            SuburbStatistics {
+                median_price: value(&prices).map(|v| v.round() as i32),
+                median_weekly_rent: value(&rents).map(|v| v.round() as i32),
+                median_rental_yield: value(&yields).map(round_yield_f64),
+                min_yield: own_value(&min_yields),
+                max_yield: own_value(&max_yields),
+                suppressed: (group.property_count.unwrap_or(0) as i64) < config.min_sample_size
+                    && rolled_up_to.is_none(),
+                rolled_up_to,
+                ..group.clone()
+            }
+        })
+        .collect()
+}
+
+/// The most recent calculation of each matching group
+/// A district matches the groups it has properties in, as statistics aren't
+/// kept per district
+pub async fn fetch_suburb_statistics(
+    db: &PgPool,
+    filter: &SuburbStatisticsQuery,
+) -> Result<Vec<SuburbStatistics>, sqlx::Error> {
+    sqlx::query_as::<_, SuburbStatistics>(
+        r#"
+        SELECT DISTINCT ON (suburb, postcode, state, bedrooms)
+            suburb,
+            postcode,
+            state,
+            bedrooms,
+            median_price,
+            median_weekly_rent,
+            median_rental_yield::FLOAT8 AS median_rental_yield,
+            min_yield::FLOAT8 AS min_yield,
+            max_yield::FLOAT8 AS max_yield,
+            property_count,
+            calculated_date,
+            (SELECT r.sa3_code FROM regions r WHERE r.postcode = s.postcode) AS sa3_code
+        FROM suburb_statistics s
+        WHERE ($1::text IS NULL OR LOWER(suburb) = LOWER($1))
+          AND ($2::text IS NULL OR postcode = $2)
+          AND ($3::state_enum IS NULL OR state = $3)
+          AND ($4::int IS NULL OR bedrooms = $4)
+          AND ($5::text IS NULL OR EXISTS (
+              SELECT 1 FROM properties p
+              WHERE p.district = $5
+                AND p.suburb = s.suburb
+                AND p.state = s.state
+                AND p.postcode IS NOT DISTINCT FROM s.postcode
+          ))
+        ORDER BY suburb, postcode, state, bedrooms, calculated_date DESC
+        "#,
+    )
+    .bind(filter.suburb.as_deref())
+    .bind(filter.postcode.as_deref())
+    .bind(filter.state)
+    .bind(filter.bedrooms)
+    .bind(filter.district.as_deref().and_then(normalize_district))
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture, SuburbStatsFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use chrono::Days;
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn app(db: PgPool) -> axum::Router {
+        crate::api::router().with_state(test_state(db))
+    }
+
+    async fn send(app: axum::Router, uri: &str) -> (StatusCode, Value) {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn group(postcode: &str, bedrooms: i32, count: Option<i32>) -> SuburbStatistics {
+        SuburbStatistics {
+            suburb: "Testville".to_string(),
+            postcode: Some(postcode.to_string()),
+            state: AusState::NSW,
+            bedrooms: Some(bedrooms),
+            median_price: Some(700_000),
+            median_weekly_rent: Some(650),
+            median_rental_yield: Some(4.83),
+            min_yield: Some(3.1),
+            max_yield: Some(6.2),
+            property_count: count,
+            calculated_date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            sa3_code: Some("12504".to_string()),
+            suppressed: false,
+            rolled_up_to: None,
+        }
+    }
+
+    #[test]
+    fn test_small_groups_suppressed() {
+        let config = SuppressionConfig::default();
+        let groups = suppress_small_groups(
+            vec![
+                group("2999", 2, Some(5)),
+                group("2999", 2, Some(4)),
+                group("2999", 2, None),
+            ],
+            &config,
+        );
+
+        assert_eq!(groups[0], group("2999", 2, Some(5)));
+        for suppressed in &groups[1..] {
+            assert!(suppressed.suppressed);
+            assert_eq!(suppressed.median_price, None);
+            assert_eq!(suppressed.median_rental_yield, None);
+            assert_eq!(suppressed.max_yield, None);
+            assert_eq!(suppressed.rolled_up_to, None);
+            assert_eq!(suppressed.suburb, "Testville");
+        }
+    }
+
+    #[test]
+    fn test_small_groups_rolled_up_to_sa3() {
+        let config = SuppressionConfig {
+            min_sample_size: 5,
+            roll_up: true,
+        };
+        let small = |postcode, bedrooms, count, price, rent| SuburbStatistics {
+            median_price: Some(price),
+            median_weekly_rent: Some(rent),
+            ..group(postcode, bedrooms, Some(count))
+        };
+        let mut unmapped = small("2997", 2, 4, 650_000, 600);
+        unmapped.sa3_code = None;
+
+        let groups = suppress_small_groups(
+            vec![
+                small("2998", 2, 2, 600_000, 500),
+                small("2999", 2, 3, 700_000, 600),
+                // Same SA3 but another bedroom count, so it isn't combined
+                small("2999", 3, 4, 900_000, 800),
+                unmapped,
+            ],
+            &config,
+        );
+
+        // 2 + 3 properties: (600k * 2 + 700k * 3) / 5 = 660k
+        for rolled_up in &groups[..2] {
+            assert!(!rolled_up.suppressed);
+            assert_eq!(rolled_up.rolled_up_to.as_deref(), Some("12504"));
+            assert_eq!(rolled_up.median_price, Some(660_000));
+            assert_eq!(rolled_up.median_weekly_rent, Some(560));
+            assert_eq!(rolled_up.median_rental_yield, Some(4.83));
+            assert_eq!(rolled_up.min_yield, None);
+            assert_eq!(rolled_up.max_yield, None);
+        }
+        // The group's own count is kept
+        assert_eq!(groups[1].property_count, Some(3));
+
+        for suppressed in &groups[2..] {
+            assert!(suppressed.suppressed);
+            assert_eq!(suppressed.rolled_up_to, None);
+            assert_eq!(suppressed.median_price, None);
+        }
+
+        let json = serde_json::to_value(&groups[0]).unwrap();
+        assert_eq!(json["rolled_up_to"], "12504");
+        assert!(json.get("sa3_code").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_filters_rejected_with_400() {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+
+        let cases = [
+            ("/api/suburbs/stats?state=XYZ", "state"),
+            ("/api/suburbs/stats?bedrooms=-1", "bedrooms"),
+            ("/api/suburbs/stats?bedrooms=21", "bedrooms"),
+            ("/api/suburbs/stats?postcode=12345678901", "postcode"),
+            ("/api/suburbs/stats?district=1001", "district"),
+        ];
+        for (uri, field) in cases {
+            let (status, body) = send(app(db.clone()), uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["field"], field, "{}", uri);
+        }
+    }
+
+    const NORTH: &str = "Suburb Stats Northville";
+    const SOUTH: &str = "Suburb Stats Southville";
+
+    async fn cleanup(db: &PgPool) {
+        sqlx::query("DELETE FROM suburb_statistics WHERE suburb = ANY($1)")
+            .bind([NORTH, SOUTH])
+            .execute(db)
+            .await
+            .unwrap();
+        delete_suburb(db, NORTH).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_suburb_statistics_filters() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        let today = chrono::Utc::now().date_naive();
+        let last_week = today.checked_sub_days(Days::new(7)).unwrap();
+        let groups = [
+            (NORTH, "2991", AusState::NSW, 2, "4.10", today),
+            (NORTH, "2991", AusState::NSW, 3, "3.80", today),
+            (SOUTH, "2992", AusState::NSW, 2, "5.20", today),
+            (SOUTH, "3992", AusState::VIC, 2, "4.60", today),
+        ];
+        for (suburb, postcode, state, bedrooms, rental_yield, date) in groups {
+            SuburbStatsFixture::new(suburb)
+                .postcode(postcode)
+                .state(state)
+                .bedrooms(bedrooms)
+                .median_rental_yield(rental_yield)
+                .yield_range("2.50", "7.00")
+                .calculated_date(date)
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+        // Superseded by today's calculation of the same group
+        SuburbStatsFixture::new(NORTH)
+            .postcode("2991")
+            .bedrooms(2)
+            .median_rental_yield("9.99")
+            .calculated_date(last_week)
+            .insert(&db)
+            .await
+            .unwrap();
+        // Northville has a property in district 033
+        PropertyFixture::new()
+            .suburb(NORTH)
+            .postcode("2991")
+            .district("033")
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let cases = [
+            (
+                "suburb=suburb%20stats%20northville",
+                vec![(NORTH, 2), (NORTH, 3)],
+            ),
+            (
+                "suburb=Suburb%20Stats%20Northville&bedrooms=2",
+                vec![(NORTH, 2)],
+            ),
+            ("postcode=2992", vec![(SOUTH, 2)]),
+            (
+                "suburb=Suburb%20Stats%20Southville",
+                vec![(SOUTH, 2), (SOUTH, 2)],
+            ),
+            (
+                "suburb=Suburb%20Stats%20Southville&state=VIC",
+                vec![(SOUTH, 2)],
+            ),
+            ("postcode=2991&state=VIC", vec![]),
+            ("district=33", vec![(NORTH, 2), (NORTH, 3)]),
+            ("district=033&bedrooms=3", vec![(NORTH, 3)]),
+            ("district=001", vec![]),
+        ];
+        for (query, expected) in cases {
+            let uri = format!("/api/suburbs/stats?{}", query);
+            let (status, body) = send(app(db.clone()), &uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            let found: Vec<(String, i64)> = body["groups"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|g| {
+                    (
+                        g["suburb"].as_str().unwrap().to_string(),
+                        g["bedrooms"].as_i64().unwrap(),
+                    )
+                })
+                .collect();
+            let expected: Vec<(String, i64)> = expected
+                .into_iter()
+                .map(|(suburb, bedrooms)| (suburb.to_string(), bedrooms))
+                .collect();
+            assert_eq!(found, expected, "{}", uri);
+        }
+
+        // The latest calculation wins, with its yield range
+        let (_, body) = send(
+            app(db.clone()),
+            "/api/suburbs/stats?postcode=2991&bedrooms=2",
+        )
+        .await;
+        let group = &body["groups"][0];
+        assert_eq!(group["median_rental_yield"], 4.1);
+        assert_eq!(group["min_yield"], 2.5);
+        assert_eq!(group["max_yield"], 7.0);
+        assert_eq!(group["calculated_date"], today.to_string());
+        assert_eq!(group["suppressed"], false);
+
+        cleanup(&db).await;
+    }
+}
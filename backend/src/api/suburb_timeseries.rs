@@ -0,0 +1,387 @@
+//! Suburb time-series endpoint - one median from every calculation of a
+//! suburb/postcode/bedroom group, oldest first, for charting
+
+use crate::analytics::suppression::{apply_suppression, Aggregate, SuppressionConfig};
+use crate::api::params::{
+    check_length, check_range, ParamError, ValidateParams, ValidatedListParams,
+};
+use crate::api::AppState;
+use crate::ingestion::types::State as AusState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+/// Most points `max_points` can ask for
+pub const MAX_TIMESERIES_POINTS: usize = 1000;
+
+/// The suburb_statistics column a series is drawn from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeseriesMetric {
+    MedianPrice,
+    MedianWeeklyRent,
+    MedianRentalYield,
+}
+
+impl TimeseriesMetric {
+    /// The selected expression; only these fixed strings reach the SQL
+    fn sql(self) -> &'static str {
+        match self {
+            TimeseriesMetric::MedianPrice => "median_price::FLOAT8",
+            TimeseriesMetric::MedianWeeklyRent => "median_weekly_rent::FLOAT8",
+            TimeseriesMetric::MedianRentalYield => "median_rental_yield::FLOAT8",
+        }
+    }
+}
+
+/// Query parameters for GET /api/suburbs/:suburb/timeseries
+#[derive(Debug, Deserialize)]
+pub struct TimeseriesQuery {
+    pub postcode: String,
+    pub bedrooms: i32,
+    pub metric: TimeseriesMetric,
+    /// Only needed where a suburb and postcode span two states
+    pub state: Option<AusState>,
+    /// Thin the series to at most this many points, keeping the first and last
+    pub max_points: Option<usize>,
+}
+
+impl ValidateParams for TimeseriesQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("postcode", Some(&self.postcode), 10)?;
+        check_range("bedrooms", Some(self.bedrooms), 0..=20)?;
+        check_range("max_points", self.max_points, 2..=MAX_TIMESERIES_POINTS)
+    }
+}
+
+/// The metric as calculated on one date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TimeseriesPoint {
+    pub date: NaiveDate,
+    pub value: f64,
+}
+
+/// Response for GET /api/suburbs/:suburb/timeseries
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuburbTimeseries {
+    pub suburb: String,
+    pub postcode: String,
+    pub bedrooms: i32,
+    pub metric: TimeseriesMetric,
+    /// Oldest first. Dates without a calculation, without the metric or with
+    /// a suppressed group are absent rather than interpolated.
+    pub points: Vec<TimeseriesPoint>,
+}
+
+/// GET /api/suburbs/:suburb/timeseries - the metric's history for one group;
+/// an empty series when the suburb has no statistics
+pub async fn get_suburb_timeseries(
+    State(state): State<AppState>,
+    Path(suburb): Path<String>,
+    ValidatedListParams(params): ValidatedListParams<TimeseriesQuery>,
+) -> Result<Json<SuburbTimeseries>, StatusCode> {
+    let points = fetch_timeseries(&state.read_db, &suburb, &params, &state.suppression)
+        .await
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let points = match params.max_points {
+        Some(max_points) => downsample(points, max_points),
+        None => points,
+    };
+
+    Ok(Json(SuburbTimeseries {
+        suburb,
+        postcode: params.postcode,
+        bedrooms: params.bedrooms,
+        metric: params.metric,
+        points,
+    }))
+}
+
+/// Every calculation of the group with the metric present, oldest first.
+/// Calculations from fewer properties than the suppression minimum are left out.
+pub async fn fetch_timeseries(
+    db: &PgPool,
+    suburb: &str,
+    filter: &TimeseriesQuery,
+    suppression: &SuppressionConfig,
+) -> Result<Vec<TimeseriesPoint>, sqlx::Error> {
+    let sql = format!(
+        r#"
+        SELECT calculated_date AS date, {metric} AS value, COALESCE(property_count, 0)::INT8
+        FROM suburb_statistics
+        WHERE LOWER(suburb) = LOWER($1)
+          AND postcode = $2
+          AND bedrooms = $3
+          AND ($4::state_enum IS NULL OR state = $4)
+          AND {metric} IS NOT NULL
+        ORDER BY calculated_date, state
+        "#,
+        metric = filter.metric.sql(),
+    );
+
+    let rows = sqlx::query_as::<_, (NaiveDate, f64, i64)>(&sql)
+        .bind(suburb)
+        .bind(&filter.postcode)
+        .bind(filter.bedrooms)
+        .bind(filter.state)
+        .fetch_all(db)
+        .await?;
+
+    Ok(suppress_small_points(rows, suppression))
+}
+
+/// Drop the points calculated from too few properties
+/// Each point is the group's own history, so nothing rolls up
+fn suppress_small_points(
+    rows: Vec<(NaiveDate, f64, i64)>,
+    config: &SuppressionConfig,
+) -> Vec<TimeseriesPoint> {
+    let aggregates = rows
+        .into_iter()
+        .map(|(date, value, sample_size)| Aggregate {
+            key: date,
+            value,
+            sample_size,
+        })
+        .collect();
+
+    apply_suppression(aggregates, |_| None::<()>, config)
+        .into_iter()
+        .filter_map(|p| {
+            Some(TimeseriesPoint {
+                date: p.key,
+                value: p.value?,
+            })
+        })
+        .collect()
+}
+
+/// At most `max_points` points spread evenly through `points` by position,
+/// always keeping the first and last
+pub fn downsample(points: Vec<TimeseriesPoint>, max_points: usize) -> Vec<TimeseriesPoint> {
+    let len = points.len();
+    if len <= max_points || max_points < 2 {
+        return points;
+    }
+    let last = len - 1;
+    let keep: Vec<usize> = (0..max_points)
+        .map(|n| n * last / (max_points - 1))
+        .collect();
+
+    points
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| keep.binary_search(index).is_ok())
+        .map(|(_, point)| point)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{test_state, SuburbStatsFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use chrono::Days;
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn send(db: &PgPool, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn series(len: usize) -> Vec<TimeseriesPoint> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        (0..len)
+            .map(|n| TimeseriesPoint {
+                date: start.checked_add_days(Days::new(n as u64)).unwrap(),
+                value: n as f64,
+            })
+            .collect()
+    }
+
+    fn values(points: &[TimeseriesPoint]) -> Vec<f64> {
+        points.iter().map(|p| p.value).collect()
+    }
+
+    #[test]
+    fn test_downsample_keeps_ends_and_spreads_evenly() {
+        assert_eq!(values(&downsample(series(10), 4)), vec![0.0, 3.0, 6.0, 9.0]);
+        assert_eq!(values(&downsample(series(5), 2)), vec![0.0, 4.0]);
+        assert_eq!(
+            values(&downsample(series(7), 6)),
+            vec![0.0, 1.0, 2.0, 3.0, 4.0, 6.0]
+        );
+
+        // Sparse series are left alone
+        assert_eq!(downsample(series(3), 3), series(3));
+        assert_eq!(downsample(series(0), 2), series(0));
+    }
+
+    #[test]
+    fn test_small_points_suppressed() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let config = SuppressionConfig {
+            min_sample_size: 5,
+            roll_up: true,
+        };
+
+        let points = suppress_small_points(
+            vec![(day(1), 600.0, 5), (day(2), 700.0, 4), (day(3), 640.0, 0)],
+            &config,
+        );
+        assert_eq!(
+            points,
+            vec![TimeseriesPoint {
+                date: day(1),
+                value: 600.0,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_params_rejected() {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+
+        let base = "/api/suburbs/Bondi/timeseries?postcode=2026&bedrooms=2";
+        let cases = [
+            (format!("{}&metric=median_rent", base), "metric"),
+            (
+                format!("{}&metric=median_price&max_points=1", base),
+                "max_points",
+            ),
+            (format!("{}&metric=median_price&state=XYZ", base), "state"),
+            (
+                "/api/suburbs/Bondi/timeseries?postcode=2026&bedrooms=21&metric=median_price"
+                    .to_string(),
+                "bedrooms",
+            ),
+        ];
+        for (uri, field) in cases {
+            let (status, body) = send(&db, &uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["field"], field, "{}", uri);
+        }
+
+        let (status, _) = send(&db, "/api/suburbs/Bondi/timeseries?postcode=2026").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    const SUBURB: &str = "Timeseries Testville";
+
+    async fn cleanup(db: &PgPool) {
+        sqlx::query("DELETE FROM suburb_statistics WHERE suburb = $1")
+            .bind(SUBURB)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_suburb_timeseries() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let month = |n: u32| start.checked_add_months(chrono::Months::new(n)).unwrap();
+        // Month 2 is missing, month 3 lacks a rent and month 4 is suppressed
+        for (n, rent, count) in [
+            (0, 600, 10),
+            (1, 620, 10),
+            (3, 0, 10),
+            (4, 700, 2),
+            (5, 640, 10),
+        ] {
+            let mut stats = SuburbStatsFixture::new(SUBURB)
+                .postcode("2994")
+                .bedrooms(2)
+                .median_price(800_000 + n as i32 * 1000)
+                .property_count(count)
+                .calculated_date(month(n));
+            if rent > 0 {
+                stats = stats.median_weekly_rent(rent);
+            }
+            stats.insert(&db).await.unwrap();
+        }
+        // Another bedroom count in the same suburb
+        SuburbStatsFixture::new(SUBURB)
+            .postcode("2994")
+            .bedrooms(3)
+            .median_weekly_rent(900)
+            .calculated_date(month(2))
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let uri = "/api/suburbs/timeseries%20testville/timeseries\
+                   ?postcode=2994&bedrooms=2&metric=median_weekly_rent";
+        let (status, body) = send(&db, uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["suburb"], "timeseries testville");
+        assert_eq!(body["metric"], "median_weekly_rent");
+        let points: Vec<(String, f64)> = body["points"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| {
+                (
+                    p["date"].as_str().unwrap().to_string(),
+                    p["value"].as_f64().unwrap(),
+                )
+            })
+            .collect();
+        let expected: Vec<(String, f64)> = [(0, 600.0), (1, 620.0), (5, 640.0)]
+            .into_iter()
+            .map(|(n, rent)| (month(n).to_string(), rent))
+            .collect();
+        assert_eq!(points, expected);
+
+        let (_, body) = send(
+            &db,
+            "/api/suburbs/Timeseries%20Testville/timeseries\
+             ?postcode=2994&bedrooms=2&metric=median_price&max_points=2",
+        )
+        .await;
+        let dates: Vec<&str> = body["points"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["date"].as_str().unwrap())
+            .collect();
+        assert_eq!(dates, vec![month(0).to_string(), month(5).to_string()]);
+
+        // No statistics at all is an empty series, not a 404
+        let (status, body) = send(
+            &db,
+            "/api/suburbs/Nowhere%20Testville/timeseries\
+             ?postcode=2994&bedrooms=2&metric=median_rental_yield",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["points"], serde_json::json!([]));
+
+        cleanup(&db).await;
+    }
+}
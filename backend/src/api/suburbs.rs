@@ -0,0 +1,215 @@
+//! Suburb autocomplete endpoint - suburbs matching a name prefix, for the
+//! filter UI
+
+use crate::api::params::{
+    check_length, ParamError, ValidateParams, ValidatedListParams, MAX_STRING_LENGTH,
+};
+use crate::api::search::escape_like;
+use crate::api::AppState;
+use crate::ingestion::types::State as AusState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+/// Most suburbs in one response
+pub const MAX_SUBURB_RESULTS: i64 = 20;
+
+/// Query parameters for GET /api/suburbs
+#[derive(Debug, Default, Deserialize)]
+pub struct SuburbsQuery {
+    /// Start of the suburb name, e.g. "bon"; empty matches every suburb
+    pub q: Option<String>,
+    pub state: Option<AusState>,
+}
+
+impl ValidateParams for SuburbsQuery {
+    fn validate(&self) -> Result<(), ParamError> {
+        check_length("q", self.q.as_deref(), MAX_STRING_LENGTH)
+    }
+}
+
+/// One suburb, postcode and state with properties in it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SuburbMatch {
+    pub suburb: String,
+    pub postcode: Option<String>,
+    pub state: AusState,
+    pub property_count: i64,
+}
+
+/// Response for GET /api/suburbs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuburbsResponse {
+    /// Most properties first
+    pub suburbs: Vec<SuburbMatch>,
+}
+
+/// GET /api/suburbs - up to MAX_SUBURB_RESULTS suburbs whose name starts with `q`
+pub async fn get_suburbs(
+    State(state): State<AppState>,
+    ValidatedListParams(params): ValidatedListParams<SuburbsQuery>,
+) -> Result<Json<SuburbsResponse>, StatusCode> {
+    let prefix = params.q.as_deref().unwrap_or("").trim();
+
+    fetch_suburbs(&state.read_db, prefix, params.state, MAX_SUBURB_RESULTS)
+        .await
+        .map(|suburbs| Json(SuburbsResponse { suburbs }))
+        .map_err(|e| {
+            error!("Database error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Distinct suburb/postcode/state tuples whose suburb starts with `prefix`,
+/// ignoring case, by property count descending then name
+pub async fn fetch_suburbs(
+    db: &PgPool,
+    prefix: &str,
+    state: Option<AusState>,
+    limit: i64,
+) -> Result<Vec<SuburbMatch>, sqlx::Error> {
+    sqlx::query_as::<_, SuburbMatch>(
+        r#"
+        SELECT suburb, postcode, state, COUNT(*) AS property_count
+        FROM properties
+        WHERE suburb ILIKE $1
+          AND ($2::state_enum IS NULL OR state = $2)
+        GROUP BY suburb, postcode, state
+        ORDER BY property_count DESC, suburb, postcode, state
+        LIMIT $3
+        "#,
+    )
+    .bind(format!("{}%", escape_like(prefix)))
+    .bind(state)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, test_state, PropertyFixture};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn get(db: &PgPool, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_invalid_params_rejected() {
+        let db = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+
+        let long = format!("/api/suburbs?q={}", "a".repeat(101));
+        for (uri, field) in [(long.as_str(), "q"), ("/api/suburbs?state=XYZ", "state")] {
+            let (status, body) = get(&db, uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["field"], field, "{}", uri);
+        }
+    }
+
+    const SUBURBS: [&str; 3] = ["Qzbonville", "QZBONDI Test", "Qzbo_Test"];
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_suburbs_by_prefix() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        for suburb in SUBURBS {
+            delete_suburb(&db, suburb).await.unwrap();
+        }
+
+        for (n, suburb, postcode, state) in [
+            (1, SUBURBS[0], "2993", AusState::NSW),
+            (2, SUBURBS[1], "2993", AusState::NSW),
+            (3, SUBURBS[1], "2993", AusState::NSW),
+            (4, SUBURBS[1], "2993", AusState::NSW),
+            (5, SUBURBS[0], "2993", AusState::NSW),
+            (6, SUBURBS[0], "3993", AusState::VIC),
+            (7, SUBURBS[2], "2993", AusState::NSW),
+        ] {
+            PropertyFixture::new()
+                .address(&format!("{} Autocomplete St", n))
+                .suburb(suburb)
+                .postcode(postcode)
+                .state(state)
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+        let found = |body: &Value| -> Vec<(String, String, String, i64)> {
+            body["suburbs"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|s| {
+                    (
+                        s["suburb"].as_str().unwrap().to_string(),
+                        s["postcode"].as_str().unwrap_or_default().to_string(),
+                        s["state"].as_str().unwrap().to_string(),
+                        s["property_count"].as_i64().unwrap(),
+                    )
+                })
+                .collect()
+        };
+        let tuple = |suburb: &str, postcode: &str, state: &str, count| {
+            (
+                suburb.to_string(),
+                postcode.to_string(),
+                state.to_string(),
+                count,
+            )
+        };
+
+        // Case-insensitive, grouped by postcode and state, busiest first
+        let (status, body) = get(&db, "/api/suburbs?q=qzBON").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            found(&body),
+            vec![
+                tuple(SUBURBS[1], "2993", "NSW", 3),
+                tuple(SUBURBS[0], "2993", "NSW", 2),
+                tuple(SUBURBS[0], "3993", "VIC", 1),
+            ]
+        );
+
+        let (_, body) = get(&db, "/api/suburbs?q=qzbonv&state=VIC").await;
+        assert_eq!(found(&body), vec![tuple(SUBURBS[0], "3993", "VIC", 1)]);
+
+        // Prefix only, and wildcards are literal
+        let (_, body) = get(&db, "/api/suburbs?q=bonville").await;
+        assert!(found(&body).iter().all(|s| s.0 != SUBURBS[0]));
+        let (_, body) = get(&db, "/api/suburbs?q=qzbo_").await;
+        assert_eq!(found(&body), vec![tuple(SUBURBS[2], "2993", "NSW", 1)]);
+
+        // No prefix: the most populous suburbs
+        let (status, body) = get(&db, "/api/suburbs?q=").await;
+        assert_eq!(status, StatusCode::OK);
+        let counts: Vec<i64> = found(&body).iter().map(|s| s.3).collect();
+        assert!(!counts.is_empty() && counts.len() <= MAX_SUBURB_RESULTS as usize);
+        assert!(counts.windows(2).all(|w| w[0] >= w[1]));
+
+        for suburb in SUBURBS {
+            delete_suburb(&db, suburb).await.unwrap();
+        }
+    }
+}
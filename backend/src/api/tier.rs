@@ -0,0 +1,479 @@
+//! Access tiers - how much detail each API key sees
+//!
+//! Callers without a recognised key are on the free public tier: addresses are
+//! cut to the street name, prices rounded to a band and coordinates coarsened,
+//! so individual sales can't be read off the API. Yields shown beside a banded
+//! price are worked from the band, and the listing can't be filtered or sorted
+//! by price, so the exact figure can't be recovered either. Paying keys see
+//! everything.
+//!
+//! Redaction is applied once, on the way out: response types implement
+//! `Redact` to hand their sensitive fields to a `Redactor`, which owns the
+//! rules, and handlers pass the finished response through `Access::apply`.
+
+use crate::api::{AppState, API_KEY_HEADER};
+use crate::calculate_rental_yield;
+use crate::format::round_yield_for_display;
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::env;
+use std::str::FromStr;
+
+/// Prices are shown to the public tier rounded to the nearest multiple of this
+pub const DEFAULT_PRICE_BAND: i32 = 25_000;
+
+/// Decimal places of latitude/longitude shown to the public tier (~100m)
+pub const DEFAULT_COORDINATE_DP: u32 = 3;
+
+/// Leading address words that name the unit rather than the street
+const UNIT_WORDS: [&str; 8] = [
+    "unit",
+    "apartment",
+    "apt",
+    "flat",
+    "shop",
+    "suite",
+    "level",
+    "lot",
+];
+
+/// What a caller is allowed to see
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    /// Street names, banded prices and coarse coordinates
+    Public,
+    /// Everything as stored
+    Full,
+}
+
+impl FromStr for Tier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "public" => Ok(Tier::Public),
+            "full" => Ok(Tier::Full),
+            other => Err(format!("unknown tier '{}'", other)),
+        }
+    }
+}
+
+/// How the public tier's sensitive fields are coarsened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedactionRules {
+    pub price_band: i32,
+    pub coordinate_dp: u32,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        RedactionRules {
+            price_band: DEFAULT_PRICE_BAND,
+            coordinate_dp: DEFAULT_COORDINATE_DP,
+        }
+    }
+}
+
+/// API keys with their tiers, and the rules for the public tier
+#[derive(Debug, Clone, Default)]
+pub struct TierConfig {
+    keys: HashMap<String, Tier>,
+    pub rules: RedactionRules,
+}
+
+impl TierConfig {
+    /// Keys from API_KEYS (`key:tier` pairs, comma separated), rules from
+    /// PUBLIC_PRICE_BAND and PUBLIC_COORDINATE_DP
+    /// Entries with an unknown tier are skipped with a warning
+    pub fn from_env() -> Self {
+        let mut keys = HashMap::new();
+        for entry in env::var("API_KEYS").unwrap_or_default().split(',') {
+            let Some((key, tier)) = entry.split_once(':') else {
+                continue;
+            };
+            match tier.parse::<Tier>() {
+                Ok(tier) if !key.trim().is_empty() => {
+                    keys.insert(key.trim().to_string(), tier);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Ignoring API_KEYS entry: {}", e),
+            }
+        }
+
+        let defaults = RedactionRules::default();
+        TierConfig {
+            keys,
+            rules: RedactionRules {
+                price_band: env::var("PUBLIC_PRICE_BAND")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|band| *band > 0)
+                    .unwrap_or(defaults.price_band),
+                coordinate_dp: env::var("PUBLIC_COORDINATE_DP")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(defaults.coordinate_dp),
+            },
+        }
+    }
+
+    /// Add a key, replacing its tier if already present
+    pub fn with_key(mut self, key: &str, tier: Tier) -> Self {
+        self.keys.insert(key.to_string(), tier);
+        self
+    }
+
+    /// Whether `key` is one of the configured API keys
+    pub fn is_known(&self, key: &str) -> bool {
+        self.keys.contains_key(key)
+    }
+
+    /// The tier for a request's key; missing and unknown keys are public
+    pub fn tier_for(&self, key: Option<&str>) -> Tier {
+        key.and_then(|key| self.keys.get(key))
+            .copied()
+            .unwrap_or(Tier::Public)
+    }
+}
+
+/// Coarsens sensitive fields according to the redaction rules
+#[derive(Debug, Clone, Copy)]
+pub struct Redactor {
+    rules: RedactionRules,
+}
+
+impl Redactor {
+    pub fn new(rules: RedactionRules) -> Self {
+        Redactor { rules }
+    }
+
+    /// Replace a street address with just its street name
+    pub fn address(&self, address: &mut String) {
+        *address = street_name(address);
+    }
+
+    pub fn price(&self, price: &mut i64) {
+        *price = band_price(*price, self.rules.price_band);
+    }
+
+    pub fn optional_price(&self, price: &mut Option<i64>) {
+        if let Some(price) = price {
+            self.price(price);
+        }
+    }
+
+    /// The displayed yield of an already-banded price, so the exact price
+    /// can't be worked back out of the rent and a yield; None without both
+    pub fn rental_yield(
+        &self,
+        banded_price: Option<i64>,
+        weekly_rent: Option<i32>,
+    ) -> Option<Decimal> {
+        calculate_rental_yield(banded_price?, weekly_rent?).map(round_yield_for_display)
+    }
+
+    pub fn coordinate(&self, value: &mut f64) {
+        let scale = 10f64.powi(self.rules.coordinate_dp as i32);
+        *value = (*value * scale).round() / scale;
+    }
+
+    pub fn decimal_coordinate(&self, value: &mut Option<Decimal>) {
+        if let Some(value) = value {
+            *value = value.round_dp(self.rules.coordinate_dp);
+        }
+    }
+}
+
+/// A response that carries property addresses, prices or coordinates
+pub trait Redact {
+    /// Pass each sensitive field through `redactor`
+    fn redact(&mut self, redactor: &Redactor);
+}
+
+impl<T: Redact> Redact for Vec<T> {
+    fn redact(&mut self, redactor: &Redactor) {
+        for item in self {
+            item.redact(redactor);
+        }
+    }
+}
+
+impl<T: Redact> Redact for Option<T> {
+    fn redact(&mut self, redactor: &Redactor) {
+        if let Some(item) = self {
+            item.redact(redactor);
+        }
+    }
+}
+
+/// Extractor for the caller's tier, from the API key header
+#[derive(Debug, Clone, Copy)]
+pub struct Access {
+    pub tier: Tier,
+    rules: RedactionRules,
+}
+
+impl Access {
+    pub fn new(tier: Tier, rules: RedactionRules) -> Self {
+        Access { tier, rules }
+    }
+
+    /// The response as this caller may see it
+    pub fn apply<T: Redact>(&self, mut response: T) -> T {
+        if self.tier == Tier::Public {
+            response.redact(&Redactor::new(self.rules));
+        }
+        response
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Access {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let key = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok());
+
+        Ok(Access::new(state.tiers.tier_for(key), state.tiers.rules))
+    }
+}
+
+/// The street part of an address, e.g. "5/12 Smith Street" -> "Smith Street"
+/// Unit and street numbers are dropped from the front; if nothing is left the
+/// result is empty rather than a number that could identify the property
+pub fn street_name(address: &str) -> String {
+    let street = address.rsplit('/').next().unwrap_or(address);
+    let words: Vec<&str> = street
+        .split_whitespace()
+        .skip_while(|word| {
+            let word = word.trim_matches(|c: char| c == ',' || c == '#');
+            word.is_empty()
+                || word.chars().any(|c| c.is_ascii_digit())
+                || UNIT_WORDS.contains(&word.to_lowercase().as_str())
+        })
+        .collect();
+    words.join(" ")
+}
+
+/// Round a price to the nearest multiple of `band`, halves up
+pub fn band_price(price: i64, band: i32) -> i64 {
+    if band <= 0 {
+        return price;
+    }
+    let band = i64::from(band);
+    price.saturating_add(band / 2).div_euclid(band) * band
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::enrich::calculate_price_per_sqm;
+    use crate::ingestion::write;
+    use crate::test_support::{
+        delete_suburb, test_state, PropertyFixture, RentalMedianFixture, SaleFixture,
+    };
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_street_name() {
+        assert_eq!(street_name("10 Test St"), "Test St");
+        assert_eq!(street_name("5/12 Smith Street"), "Smith Street");
+        assert_eq!(street_name("Unit 3, 45 George Rd"), "George Rd");
+        assert_eq!(street_name("12-14 King St"), "King St");
+        assert_eq!(street_name("Lot 7 Mountain Hwy"), "Mountain Hwy");
+        assert_eq!(street_name("The Esplanade"), "The Esplanade");
+        assert_eq!(street_name("42"), "");
+    }
+
+    #[test]
+    fn test_band_price() {
+        assert_eq!(band_price(812_345, 25_000), 800_000);
+        assert_eq!(band_price(812_500, 25_000), 825_000);
+        assert_eq!(band_price(700_000, 25_000), 700_000);
+        assert_eq!(band_price(5_000, 25_000), 0);
+        assert_eq!(band_price(812_345, 0), 812_345);
+        assert_eq!(band_price(i32::MAX.into(), 25_000), 2_147_475_000);
+        assert_eq!(band_price(2_612_345_678, 25_000), 2_612_350_000);
+        assert_eq!(band_price(i64::MAX, 25_000) % 25_000, 0);
+    }
+
+    #[test]
+    fn test_tier_for_key() {
+        let config = TierConfig::default()
+            .with_key("paid", Tier::Full)
+            .with_key("free", Tier::Public);
+
+        assert_eq!(config.tier_for(Some("paid")), Tier::Full);
+        assert_eq!(config.tier_for(Some("free")), Tier::Public);
+        assert_eq!(config.tier_for(Some("unknown")), Tier::Public);
+        assert_eq!(config.tier_for(None), Tier::Public);
+    }
+
+    #[test]
+    fn test_redactor_coarsens_coordinates() {
+        let redactor = Redactor::new(RedactionRules::default());
+
+        let mut latitude = -33.868_812;
+        redactor.coordinate(&mut latitude);
+        assert_eq!(latitude, -33.869);
+
+        let mut longitude = Some("151.209296".parse::<Decimal>().unwrap());
+        redactor.decimal_coordinate(&mut longitude);
+        assert_eq!(longitude, Some("151.209".parse().unwrap()));
+    }
+
+    const SUBURB: &str = "Tier Testville";
+    const POSTCODE: &str = "2998";
+    const FULL_KEY: &str = "tier-test-full";
+
+    /// Status and body text of a GET, with the full-tier key if `full`
+    async fn get(app: &Router, uri: &str, full: bool) -> (StatusCode, String) {
+        let mut request = Request::get(uri);
+        if full {
+            request = request.header(API_KEY_HEADER, FULL_KEY);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn cleanup(db: &PgPool) {
+        delete_suburb(db, SUBURB).await.unwrap();
+        sqlx::query("DELETE FROM rental_medians WHERE postcode = $1")
+            .bind(POSTCODE)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_public_tier_never_sees_raw_values() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        let date = |s: &str| s.parse().unwrap();
+        RentalMedianFixture::new(POSTCODE, date("2024-06-01"))
+            .bedrooms(3)
+            .rent(650)
+            .insert(&db)
+            .await
+            .unwrap();
+        let property = PropertyFixture::new()
+            .address("12 Leak Street")
+            .suburb(SUBURB)
+            .postcode(POSTCODE)
+            .bedrooms(3)
+            .price(812_345)
+            .weekly_rent(650)
+            .land_area_sqm(Decimal::from(500))
+            .coordinates("-33.861234".parse().unwrap(), "151.212345".parse().unwrap());
+        let record = calculate_price_per_sqm(property.record().clone());
+        let id = write::insert_property(&mut db.acquire().await.unwrap(), &record)
+            .await
+            .unwrap();
+        SaleFixture::new(id, 812_345, date("2024-01-15"))
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let app = crate::api::router().with_state(AppState {
+            tiers: Arc::new(TierConfig::default().with_key(FULL_KEY, Tier::Full)),
+            ..test_state(db.clone())
+        });
+
+        let share = Request::post("/api/share")
+            .header("content-type", "application/json")
+            .body(Body::from(format!("{{\"property_ids\": [{}]}}", id)))
+            .unwrap();
+        let response = app.clone().oneshot(share).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let token = created["token"].as_str().unwrap();
+
+        let endpoints = [
+            "/api/sales?suburb=Tier%20Testville".to_string(),
+            "/api/sales?suburb=Tier%20Testville&format=csv".to_string(),
+            "/api/properties/clusters?bbox=151.2,-33.87,151.22,-33.85&zoom=18".to_string(),
+            format!("/api/share/{}", token),
+            format!("/api/properties/{}/yield-history", id),
+            format!("/api/properties/{}?strata=0", id),
+            "/api/properties?suburb=Tier%20Testville".to_string(),
+        ];
+        // The exact yield and price per square metre would give the price
+        // back from the rent and land area
+        let raw = [
+            "12 Leak",
+            "812345",
+            "33.861234",
+            "151.212345",
+            "4.16",
+            "1624.69",
+        ];
+
+        for uri in &endpoints {
+            let (status, public) = get(&app, uri, false).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            let (status, full) = get(&app, uri, true).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+
+            // The same row, rendered per tier
+            if full.contains("12 Leak Street") {
+                assert!(public.contains("\"Leak Street\"") || public.contains(",Leak Street,"));
+            }
+            assert!(public.contains("800000"), "{} public: {}", uri, public);
+            assert!(full.contains("812345"), "{} full: {}", uri, full);
+            for value in raw {
+                assert!(
+                    !public.contains(value),
+                    "{} leaks {}: {}",
+                    uri,
+                    value,
+                    public
+                );
+            }
+        }
+
+        // Nor can the listing be used to close in on the price
+        for param in [
+            "min_price=800000",
+            "max_price=825000",
+            "min_yield=4.2",
+            "sort=price",
+        ] {
+            let uri = format!("/api/properties?suburb=Tier%20Testville&{}", param);
+            let (status, public) = get(&app, &uri, false).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert!(public.contains("needs an API key"), "{}", public);
+            let (status, _) = get(&app, &uri, true).await;
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+        }
+
+        cleanup(&db).await;
+    }
+}
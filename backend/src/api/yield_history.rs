@@ -0,0 +1,235 @@
+//! Yield history endpoint - the property's yield recomputed for each rental period
+
+use crate::analytics::yield_history::{yield_history, HistoricalRent, HistoricalSale, YieldPoint};
+use crate::api::tier::{Access, Redact, Redactor};
+use crate::api::AppState;
+use crate::format::round_yield_for_display;
+use crate::ingestion::types::{RentalLookup, State as AusState};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+/// Response for GET /api/properties/:id/yield-history
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YieldHistory {
+    pub property_id: i32,
+    pub state: AusState,
+    pub postcode: String,
+    pub bedrooms: i32,
+    /// Oldest first; periods without an earlier sale are omitted
+    pub points: Vec<YieldPoint>,
+}
+
+impl Redact for YieldPoint {
+    fn redact(&mut self, redactor: &Redactor) {
+        redactor.price(&mut self.sale.sale_price);
+        self.rental_yield = redactor
+            .rental_yield(Some(self.sale.sale_price), Some(self.rent.median_rent))
+            .unwrap_or_default();
+    }
+}
+
+impl Redact for YieldHistory {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.points.redact(redactor);
+    }
+}
+
+/// GET /api/properties/:id/yield-history - yield per rental period against the
+/// price at the time. 404 when the property is unknown or can't be keyed.
+pub async fn get_yield_history(
+    State(state): State<AppState>,
+    access: Access,
+    Path(id): Path<i32>,
+) -> Result<Json<YieldHistory>, StatusCode> {
+    let db_error = |e: sqlx::Error| {
+        error!("Database error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let (property_state, postcode, bedrooms) =
+        sqlx::query_as::<_, (AusState, Option<String>, Option<i32>)>(
+            "SELECT state, postcode, bedrooms FROM properties WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&state.read_db)
+        .await
+        .map_err(db_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let lookup = RentalLookup {
+        state: property_state,
+        postcode: postcode.ok_or(StatusCode::NOT_FOUND)?,
+        bedrooms: bedrooms.ok_or(StatusCode::NOT_FOUND)?,
+    };
+
+    let sales = fetch_sales(&state.read_db, id).await.map_err(db_error)?;
+    let rents = fetch_all_rents(&state.read_db, &lookup)
+        .await
+        .map_err(db_error)?;
+
+    let points = yield_history(&sales, &rents)
+        .into_iter()
+        .map(|point| YieldPoint {
+            rental_yield: round_yield_for_display(point.rental_yield),
+            ..point
+        })
+        .collect();
+
+    Ok(Json(access.apply(YieldHistory {
+        property_id: id,
+        state: lookup.state,
+        postcode: lookup.postcode,
+        bedrooms: lookup.bedrooms,
+        points,
+    })))
+}
+
+pub async fn fetch_sales(
+    db: &PgPool,
+    property_id: i32,
+) -> Result<Vec<HistoricalSale>, sqlx::Error> {
+    sqlx::query_as::<_, HistoricalSale>(
+        r#"
+        SELECT id AS sale_id, sale_date, sale_price
+        FROM sales_history
+        WHERE property_id = $1
+        ORDER BY sale_date, id
+        "#,
+    )
+    .bind(property_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Every rental median for a key, one per period, chosen as in the rent history
+pub async fn fetch_all_rents(
+    db: &PgPool,
+    lookup: &RentalLookup,
+) -> Result<Vec<HistoricalRent>, sqlx::Error> {
+    sqlx::query_as::<_, HistoricalRent>(
+        r#"
+        SELECT DISTINCT ON (period)
+            period,
+            median_weekly_rent AS median_rent,
+            sample_size
+        FROM rental_medians
+        WHERE state = $1
+          AND postcode = $2
+          AND bedrooms = $3
+          AND dwelling_type IS NULL
+        ORDER BY period, sample_size DESC NULLS LAST
+        "#,
+    )
+    .bind(lookup.state)
+    .bind(&lookup.postcode)
+    .bind(lookup.bedrooms)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        delete_suburb, test_state, PropertyFixture, RentalMedianFixture, SaleFixture,
+    };
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use chrono::NaiveDate;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    const SUBURB: &str = "Yield History Testville";
+    const POSTCODE: &str = "7997";
+
+    async fn get(db: &PgPool, uri: &str) -> (StatusCode, Value) {
+        let app = crate::api::router().with_state(test_state(db.clone()));
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    async fn cleanup(db: &PgPool) {
+        delete_suburb(db, SUBURB).await.unwrap();
+        sqlx::query("DELETE FROM rental_medians WHERE postcode = $1")
+            .bind(POSTCODE)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_yield_history_series() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        let date = |s: &str| s.parse::<NaiveDate>().unwrap();
+        for (period, rent) in [
+            ("2017-01-01", 380),
+            ("2019-01-01", 500),
+            ("2022-01-01", 600),
+        ] {
+            RentalMedianFixture::new(POSTCODE, date(period))
+                .state(AusState::TAS)
+                .bedrooms(3)
+                .rent(rent)
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+        let id = PropertyFixture::new()
+            .address("1 Yield St")
+            .suburb(SUBURB)
+            .state(AusState::TAS)
+            .postcode(POSTCODE)
+            .bedrooms(3)
+            .insert(&db)
+            .await
+            .unwrap();
+        for (price, sold) in [(650_000, "2018-03-01"), (800_000, "2021-06-15")] {
+            SaleFixture::new(id, price, date(sold))
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+
+        let (status, history) = get(&db, &format!("/api/properties/{}/yield-history", id)).await;
+        assert_eq!(status, StatusCode::OK);
+        let points = history["points"].as_array().unwrap();
+        // 2017 predates the first sale
+        let series: Vec<(&str, &str, i64)> = points
+            .iter()
+            .map(|p| {
+                (
+                    p["period"].as_str().unwrap(),
+                    p["rental_yield"].as_str().unwrap(),
+                    p["sale"]["sale_price"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            series,
+            vec![
+                ("2019-01-01", "4.00", 650_000),
+                ("2022-01-01", "3.90", 800_000)
+            ]
+        );
+        assert_eq!(points[1]["rent"]["median_rent"], 600);
+
+        let (status, _) = get(&db, "/api/properties/-1/yield-history").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        cleanup(&db).await;
+    }
+}
@@ -1,15 +1,61 @@
 //! Data ingestion orchestrator - runs fetch, parse, enrich, write pipelines
 
-use anyhow::Result;
-use chrono::{NaiveDate, Utc};
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDate, Utc, Weekday};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use real_estate_backend::analytics::{digest, suburb_stats};
+use real_estate_backend::ingestion::anomaly::{AnomalyThresholds, RecordTally, RunMetrics};
+use real_estate_backend::ingestion::archive::{self, RawArchive};
+use real_estate_backend::ingestion::batch;
+use real_estate_backend::ingestion::enrich::RentalMatching;
+use real_estate_backend::ingestion::geocode::{
+    ExternalGeocoder, ExternalGeocoderConfig, GeocodeCache, GeocoderChain, GnafGeocoder,
+    LocalGeocoder,
+};
+use real_estate_backend::ingestion::legacy;
+use real_estate_backend::ingestion::notify::NotificationHook;
+use real_estate_backend::ingestion::renormalize::{self, RenormalizeOptions};
+use real_estate_backend::ingestion::fetch::{DomainListingsConfig, NswSalesApiConfig, NswSalesMode};
+use real_estate_backend::ingestion::runs::{self, ProgressWriter};
+use real_estate_backend::ingestion::throttle::{self, Throttle, ThrottleConfig};
+use real_estate_backend::ingestion::utils::Fetcher;
+use real_estate_backend::ingestion::parse::{ParseReport, RowError};
+use real_estate_backend::ingestion::validate::{
+    self, ValidationConfig, ValidationFailed, ValidationReport,
+};
 use real_estate_backend::ingestion::{
-    enrich, fetch, parse, write, PropertyRecord, RentalMedian, WriteStats,
+    enrich, fetch, parse, staging, watermark, write, PropertyRecord, RawData, WriteStats,
 };
+use serde_json::json;
 use sqlx::PgPool;
 use std::env;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 use tracing::{error, info, warn};
 
+/// Records enriched and written per chunk; progress is reported between chunks
+const CHUNK_SIZE: usize = 1000;
+
+/// Stages every run reports progress through: fetch, parse, then load or write
+const STAGES: u32 = 3;
+
+/// Every source a run can be asked for
+const KNOWN_SOURCES: &[&str] = &[
+    "abs_postcode_regions",
+    "abs_lga_correspondence",
+    "council_rates",
+    "nsw_sales",
+    "nsw_sales_api",
+    "wa_sales",
+    "vic_sales",
+    "sa_medians",
+    "domain_listings",
+    "nsw_rentals",
+    "qld_rentals",
+    "nsw_bond_lodgements",
+];
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging
@@ -22,41 +68,204 @@ async fn main() -> Result<()> {
     info!("Starting data ingestion pipeline");
 
     // Load configuration from environment
-    let config = Config::from_env()?;
+    let mut config = Config::from_env()?;
     info!("Configuration loaded");
 
     // Connect to database
     let db = PgPool::connect(&config.database_url).await?;
     info!("Database connected");
 
-    // Determine which sources to run (from command line args or run all)
+    let thresholds = AnomalyThresholds::from_env();
+    let notifications = NotificationHook::from_env()?;
+
     let args: Vec<String> = env::args().collect();
-    let sources = if args.len() > 1 {
-        args[1..].to_vec()
+    match args.get(1).map(String::as_str) {
+        Some("digest") => {
+            return run_digest(&db, &notifications, DigestArgs::parse(&args[2..])?).await;
+        }
+        Some("renormalize-addresses") => {
+            return run_renormalize(&db, RenormalizeArgs::parse(&args[2..])?).await;
+        }
+        Some("renormalize-postcodes") => {
+            // renormalize-postcodes [--dry-run]
+            let dry_run = match args.get(2).map(String::as_str) {
+                None => false,
+                Some("--dry-run") => true,
+                Some(other) => bail!("Unknown renormalize-postcodes option: {}", other),
+            };
+            renormalize::renormalize_postcodes(&db, dry_run).await?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // --force re-downloads sources even when the server says they're unchanged;
+    // --no-geocode leaves coordinates alone; --replay <path> loads an archived
+    // file instead of fetching
+    let mut named = Vec::new();
+    let mut replay = None;
+    let mut rest = args.into_iter().skip(1);
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--force" => config.force_download = true,
+            "--no-geocode" => config.geocoding = false,
+            "--replay" => {
+                let path = rest.next().context("--replay needs the path of an archived file")?;
+                replay = Some(PathBuf::from(path));
+            }
+            _ => named.push(arg),
+        }
+    }
+    if let Some(path) = replay {
+        let replay = Replay::new(path, &named)?;
+        info!("Replaying {} from {}", replay.source_id, replay.path.display());
+        named = vec![replay.source_id.clone()];
+        config.replay = Some(replay);
+    }
+
+    // Determine which sources to run (from command line args or run all)
+    let sources = if !named.is_empty() {
+        named
     } else {
-        vec!["nsw_sales".to_string(), "nsw_rentals".to_string()]
+        // Optional sources run only when their file is configured; regions and LGAs
+        // go first so the sales run's statistics refresh can roll up by region and
+        // its new properties get an LGA
+        let mut sources = Vec::new();
+        if config.abs_postcode_regions_url.is_some() {
+            sources.push("abs_postcode_regions".to_string());
+        }
+        if config.lga_correspondence_url.is_some() {
+            sources.push("abs_lga_correspondence".to_string());
+        }
+        if config.council_rates_url.is_some() {
+            sources.push("council_rates".to_string());
+        }
+        sources.push("nsw_sales".to_string());
+        if config.nsw_sales_api.is_some() {
+            sources.push("nsw_sales_api".to_string());
+        }
+        if config.wa_sales_url.is_some() {
+            sources.push("wa_sales".to_string());
+        }
+        if config.vic_sales_url.is_some() {
+            sources.push("vic_sales".to_string());
+        }
+        if config.sa_medians_url.is_some() {
+            sources.push("sa_medians".to_string());
+        }
+        if config.domain_listings.is_some() {
+            sources.push("domain_listings".to_string());
+        }
+        sources.push("nsw_rentals".to_string());
+        if config.qld_rentals_url.is_some() {
+            sources.push("qld_rentals".to_string());
+        }
+        if config.nsw_bond_lodgements_url.is_some() {
+            sources.push("nsw_bond_lodgements".to_string());
+        }
+        sources
     };
 
     // Run each source
     for source_id in sources {
         info!("Running ingestion for: {}", source_id);
 
-        let result = match source_id.as_str() {
-            "nsw_sales" => run_nsw_sales(&config, &db).await,
-            "nsw_rentals" => run_nsw_rentals(&config, &db).await,
-            _ => {
-                warn!("Unknown source: {}", source_id);
+        if !KNOWN_SOURCES.contains(&source_id.as_str()) {
+            warn!("Unknown source: {}", source_id);
+            continue;
+        }
+
+        let run_id = match runs::start_run(&db, &source_id).await {
+            Ok(id) => id,
+            Err(e) => {
+                error!("✗ {} failed: could not record run: {}", source_id, e);
                 continue;
             }
         };
+        let mut progress = ProgressWriter::new(db.clone(), run_id, STAGES);
+        let mut throttle = Throttle::new(config.throttle.clone());
 
-        match result {
-            Ok(stats) => {
+        let result = match source_id.as_str() {
+            "nsw_sales" => run_nsw_sales(&config, &db, &mut progress, &mut throttle).await,
+            "nsw_sales_api" => run_nsw_sales_api(&config, &db, &mut progress, &mut throttle).await,
+            "wa_sales" => run_wa_sales(&config, &db, &mut progress, &mut throttle).await,
+            "vic_sales" => run_vic_sales(&config, &db, &mut progress, &mut throttle).await,
+            "sa_medians" => run_sa_medians(&config, &db, &mut progress, &mut throttle).await,
+            "domain_listings" => {
+                run_domain_listings(&config, &db, &mut progress, &mut throttle).await
+            }
+            "nsw_rentals" => {
+                run_rentals(RentalsSource::Nsw, &config, &db, &mut progress, &mut throttle).await
+            }
+            "qld_rentals" => {
+                run_rentals(RentalsSource::Qld, &config, &db, &mut progress, &mut throttle).await
+            }
+            "nsw_bond_lodgements" => {
+                run_nsw_bond_lodgements(&config, &db, &mut progress, &mut throttle).await
+            }
+            "abs_lga_correspondence" => {
+                run_abs_lga_correspondence(&config, &db, &mut progress, &mut throttle).await
+            }
+            "council_rates" => run_council_rates(&config, &db, &mut progress, &mut throttle).await,
+            "abs_postcode_regions" => {
+                run_abs_postcode_regions(&config, &db, &mut progress, &mut throttle).await
+            }
+            other => Err(anyhow::anyhow!("no runner for source {}", other)),
+        };
+
+        let recorded = match result {
+            Ok((stats, mut metrics)) => {
                 info!("✓ {} completed: {}", source_id, stats);
+                metrics.throttle = Some(throttle.metrics());
+                match legacy::yield_filter_note(&db, &source_id).await {
+                    Ok(Some(note)) if !note.suburbs_needing_reingestion.is_empty() => {
+                        warn!(
+                            "{} suburbs still lack properties the legacy yield filter dropped; \
+                             re-ingest them in full",
+                            note.suburbs_needing_reingestion.len()
+                        );
+                        metrics.legacy_yield_filter = Some(note);
+                    }
+                    Ok(note) => metrics.legacy_yield_filter = note,
+                    Err(e) => warn!("Failed to check for legacy yield filter runs: {}", e),
+                }
+                progress.finish().await;
+                match runs::complete_run(&db, run_id, &stats, &metrics, &thresholds).await {
+                    Ok(anomalies) if !anomalies.is_empty() => {
+                        let details = json!({
+                            "run_id": run_id,
+                            "source_id": source_id,
+                            "anomalies": anomalies,
+                        });
+                        notifications.notify("ingestion_anomaly", details).await;
+                        Ok(())
+                    }
+                    other => other.map(|_| ()),
+                }
             }
             Err(e) => {
                 error!("✗ {} failed: {}", source_id, e);
+                // Keep the checks that stopped the run next to its error
+                let metrics = e.downcast_ref::<ValidationFailed>().map(|failed| RunMetrics {
+                    validation: Some(failed.report.clone()),
+                    ..Default::default()
+                });
+                runs::fail_run(&db, run_id, &e.to_string(), metrics.as_ref()).await
             }
+        };
+        if let Err(e) = recorded {
+            warn!("Failed to record outcome of run {}: {}", run_id, e);
+        }
+    }
+
+    // A replay leaves the archive as it found it
+    if config.replay.is_none() {
+        match config.archive.prune(Utc::now().date_naive()) {
+            Ok(removed) if !removed.is_empty() => {
+                info!("Pruned {} archived days: {}", removed.len(), removed.join(", "))
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to prune the raw archive: {}", e),
         }
     }
 
@@ -65,62 +274,1249 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// An archived file to run parse, enrich and write from, instead of fetching
+#[derive(Debug, Clone)]
+struct Replay {
+    path: PathBuf,
+    source_id: String,
+    /// The day the file was archived, standing in for today
+    date: NaiveDate,
+}
+
+impl Replay {
+    /// The source is the one named alongside --replay, or else the one the
+    /// file's `{source_id}/{YYYY-MM-DD}` archive directories name
+    fn new(path: PathBuf, named: &[String]) -> Result<Self> {
+        if !path.is_file() {
+            bail!("Nothing to replay at {}", path.display());
+        }
+        let archived = archive::archived_source(&path);
+        let source_id = match (named, &archived) {
+            ([source_id], _) => source_id.clone(),
+            ([], Some((source_id, _))) => source_id.clone(),
+            ([], None) => bail!(
+                "Can't tell which source {} is from; name the source after --replay <path>",
+                path.display()
+            ),
+            _ => bail!("--replay runs one source at a time"),
+        };
+        if source_id == "nsw_sales_api" || source_id == "domain_listings" {
+            bail!("{} pages aren't archived, so there's nothing to replay", source_id);
+        }
+
+        Ok(Replay {
+            date: archived.map_or_else(|| Utc::now().date_naive(), |(_, date)| date),
+            path,
+            source_id,
+        })
+    }
+}
+
+/// Options for the `digest` subcommand:
+/// `digest [--week-ending YYYY-MM-DD] [--send] [--output PATH]`
+struct DigestArgs {
+    /// Defaults to today
+    week_ending: NaiveDate,
+    /// Push through the notification webhook instead of writing a file
+    send: bool,
+    /// JSON when the path ends in .json, HTML otherwise
+    output: Option<PathBuf>,
+}
+
+impl DigestArgs {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut parsed = DigestArgs {
+            week_ending: Utc::now().date_naive(),
+            send: false,
+            output: None,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--week-ending" => {
+                    let value = args.next().context("--week-ending needs a date")?;
+                    parsed.week_ending = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                        .with_context(|| format!("Invalid --week-ending {}", value))?;
+                }
+                "--send" => parsed.send = true,
+                "--output" => {
+                    let value = args.next().context("--output needs a path")?;
+                    parsed.output = Some(value.into());
+                }
+                other => bail!("Unknown digest option: {}", other),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Build the weekly digest and send it, or write it to a file
+/// Sending falls back to a file when no webhook is configured
+async fn run_digest(
+    db: &PgPool,
+    notifications: &NotificationHook,
+    args: DigestArgs,
+) -> Result<()> {
+    info!("Building digest for the week ending {}", args.week_ending);
+    let digest = digest::weekly_digest(db, args.week_ending).await?;
+    if digest.ingestion.runs == 0 {
+        warn!("No ingestion runs in the week ending {}", args.week_ending);
+    }
+
+    let sent = args.send && notifications.has_webhook();
+    if sent {
+        let details = json!({
+            "week_ending": digest.week_ending,
+            "digest": digest,
+            "html": digest::render_html(&digest),
+        });
+        notifications.notify("weekly_digest", details).await;
+    } else if args.send {
+        warn!("ALERT_WEBHOOK_URL is not set; writing the digest to a file instead");
+    }
+
+    if !sent || args.output.is_some() {
+        let path = args
+            .output
+            .unwrap_or_else(|| format!("digest-{}.html", args.week_ending).into());
+        let rendered = if path.extension().is_some_and(|ext| ext == "json") {
+            digest::render_json(&digest)
+        } else {
+            digest::render_html(&digest)
+        };
+        std::fs::write(&path, rendered)
+            .with_context(|| format!("Failed to write digest to {}", path.display()))?;
+        info!("Digest written to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Options for the `renormalize-addresses` subcommand:
+/// `renormalize-addresses [--dry-run] [--restart] [--suburb NAME] [--batch-size N]
+/// [--output PATH]`
+struct RenormalizeArgs {
+    options: RenormalizeOptions,
+    /// Where to write the JSON report
+    output: Option<PathBuf>,
+}
+
+impl RenormalizeArgs {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut parsed = RenormalizeArgs {
+            options: RenormalizeOptions::default(),
+            output: None,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dry-run" => parsed.options.dry_run = true,
+                "--restart" => parsed.options.restart = true,
+                "--suburb" => {
+                    let value = args.next().context("--suburb needs a name")?;
+                    parsed.options.suburb = Some(value.clone());
+                }
+                "--batch-size" => {
+                    let value = args.next().context("--batch-size needs a number")?;
+                    parsed.options.batch_size = value
+                        .parse()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .with_context(|| format!("Invalid --batch-size {}", value))?;
+                }
+                "--output" => {
+                    let value = args.next().context("--output needs a path")?;
+                    parsed.output = Some(value.into());
+                }
+                other => bail!("Unknown renormalize-addresses option: {}", other),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Move stored addresses to the current format, merging or flagging collisions
+/// Run with --dry-run first: it reports the collisions without writing anything
+async fn run_renormalize(db: &PgPool, args: RenormalizeArgs) -> Result<()> {
+    let report = renormalize::renormalize_addresses(db, &args.options).await?;
+    info!("Address renormalization: {}", report);
+    for collision in &report.collisions {
+        info!(
+            "  {} property {} \"{}\" -> \"{}\" collides with {} \"{}\"{}",
+            collision.action,
+            collision.property_id,
+            collision.address,
+            collision.new_address,
+            collision.with_id,
+            collision.with_address,
+            collision
+                .reason
+                .as_deref()
+                .map(|r| format!(" ({})", r))
+                .unwrap_or_default()
+        );
+    }
+    if report.collision_count() > report.collisions.len() as u64 {
+        info!(
+            "  ... and {} more",
+            report.collision_count() - report.collisions.len() as u64
+        );
+    }
+
+    if let Some(path) = args.output {
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(&path, json)
+            .with_context(|| format!("Failed to write report to {}", path.display()))?;
+        info!("Report written to {}", path.display());
+    }
+
+    Ok(())
+}
+
 /// Run NSW sales data ingestion
-async fn run_nsw_sales(config: &Config, db: &PgPool) -> Result<WriteStats> {
+async fn run_nsw_sales(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
     info!("=== NSW Sales Pipeline ===");
 
     // Step 1: Fetch raw data
-    info!("Step 1/4: Fetching data...");
-    let raw_data = fetch::fetch_nsw_sales(&config.nsw_sales_url, &config.temp_dir).await?;
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "bytes", None).await;
+    let bar = download_bar();
+    let fetcher = match &bar {
+        Some(bar) => {
+            let bar = bar.clone();
+            config.fetcher.clone().with_progress(move |done, total| {
+                // Content-Length turns the spinner into a bar
+                if let (Some(total), None) = (total, bar.length()) {
+                    bar.set_style(bar_style(
+                        "{bytes}/{total_bytes} [{wide_bar}] {bytes_per_sec}, {eta} left",
+                    ));
+                    bar.set_length(total);
+                }
+                bar.set_position(done);
+            })
+        }
+        None => config.fetcher.clone(),
+    };
+    let weekly = config.nsw_sales_mode == NswSalesMode::Weekly;
+    let fetched = if let Some(replay) = &config.replay {
+        fetch::replay_nsw_sales(&replay.path, &config.temp_dir)
+            .map(|(raw_data, validation)| (raw_data, validation, None, Vec::new()))
+    } else if weekly {
+        fetch_weekly_sales(config, db, &fetcher).await
+    } else {
+        fetch_full_sales(config, &fetcher).await
+    };
+    if let Some(bar) = bar {
+        bar.finish_and_clear();
+    }
+    let (raw_data, mut validation, loaded_through, archives) = fetched?;
+    report_downloaded(&raw_data, progress).await;
+    let today = config.run_date();
+    for zip in &archives {
+        archive_raw_file(config, db, progress, "nsw_sales", |archive| {
+            archive.archive_file("nsw_sales", today, zip)
+        })
+        .await;
+    }
+    info!("✓ Fetch complete");
+
+    // Step 2: Check the CSVs parse, and to enough records
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    let paths = raw_data.as_file_paths()?;
+    // Keep the raw files so single properties can be re-ingested later
+    let source_files: Vec<_> = paths
+        .iter()
+        .map(|path| match config.archive.store("nsw_sales", today, path) {
+            Ok(key) => Some(key),
+            Err(e) => {
+                warn!("Failed to archive raw sales file {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+    let reader = || {
+        parse::NswSalesReader::new(paths, "nsw_sales").with_source_files(source_files.clone())
+    };
+
+    // A short file parses cleanly, so only the count gives it away; a week's
+    // archive is always short, and a replayed file passed when it was fetched.
+    // The checks take a read of their own so nothing is written before them.
+    let min_records = config.nsw_sales_validation.min_records;
+    let check_count = !weekly && config.replay.is_none() && min_records > 0;
+    let check_errors = config.max_parse_error_rate < 1.0;
+    let mut total = None;
+    if check_count || check_errors {
+        let mut checking = reader();
+        for record in checking.by_ref() {
+            record?;
+        }
+        checking.log_summary();
+        progress.set_total(checking.records() as u64).await;
+        progress.advance(checking.records() as u64).await;
+        info!("✓ Parsed {} records", checking.records());
+        if check_count {
+            // Counted as sales, before a property's repeats were collapsed
+            let sales = checking.records() + checking.repeat_sales();
+            validation.check_record_count(sales, min_records);
+        }
+        let (errors, total_rows) = (checking.errors(), checking.total_rows());
+        report_parse_errors(config, db, progress, "nsw_sales", errors, total_rows).await;
+        validation.check_parse_errors(errors.len(), total_rows, config.max_parse_error_rate);
+        total = Some(checking.records() as u64);
+    }
+    validation.log();
+    let validation = validation.into_result()?;
+
+    // Limit to first N records for testing (optional)
+    let limit = (config.limit_records > 0).then_some(config.limit_records);
+    if let Some(limit) = limit {
+        warn!("Limiting to first {} records (testing mode)", limit);
+        total = total.map(|total| total.min(limit as u64));
+    }
+
+    let mut records = reader();
+    let batches = batch::batches(records.by_ref(), config.batch_size, limit);
+    let (stats, mut metrics) =
+        enrich_and_write(config, db, progress, throttle, "nsw_sales", batches, total).await?;
+    records.log_summary();
+    if !check_count && !check_errors {
+        let (errors, total_rows) = (records.errors(), records.total_rows());
+        report_parse_errors(config, db, progress, "nsw_sales", errors, total_rows).await;
+    }
+    metrics.validation = Some(validation);
+
+    // Only now are the weeks fetched stored, so the next run carries on after them
+    if let Some(week) = loaded_through {
+        watermark::advance_weekly_watermark(db, "nsw_sales", week).await?;
+        info!("✓ Weekly archives loaded through {}", week);
+    }
+    Ok((stats, metrics))
+}
+
+/// Fetched sales CSVs, their checks, the week a weekly run loaded through and
+/// the ZIPs downloaded, for the raw archive
+type SalesFetch = (RawData, ValidationReport, Option<NaiveDate>, Vec<PathBuf>);
+
+/// The whole NSW sales archive, unless it's unchanged since the last download
+async fn fetch_full_sales(config: &Config, fetcher: &Fetcher) -> Result<SalesFetch> {
+    let fetched = fetch::fetch_nsw_sales(
+        &config.nsw_sales_url,
+        &config.temp_dir,
+        fetcher,
+        config.force_download,
+        &config.nsw_sales_validation,
+    )
+    .await?;
+    if fetched.unchanged {
+        info!("Source unchanged, skipping download");
+    }
+    Ok((fetched.raw_data, fetched.validation, None, fetched.archives))
+}
+
+/// The weekly NSW sales archives after the last week loaded, or after the last
+/// successful run when none has been; also returns the week to resume after
+async fn fetch_weekly_sales(config: &Config, db: &PgPool, fetcher: &Fetcher) -> Result<SalesFetch> {
+    let Some(since) = watermark::weekly_resume_date(db, "nsw_sales").await? else {
+        bail!(
+            "NSW_SALES_MODE=weekly needs an earlier nsw_sales run to carry on from; \
+             load the full archive first"
+        );
+    };
+    let fetched = fetch::fetch_nsw_sales_weekly(
+        &config.nsw_sales_weekly_url,
+        since,
+        Utc::now().date_naive(),
+        &config.temp_dir,
+        fetcher,
+    )
+    .await?;
+    if !fetched.missing.is_empty() {
+        warn!(
+            "No archive for the weeks of {:?}; they were skipped",
+            fetched.missing
+        );
+    }
+
+    // Nothing fetched still moves the resume point off the last run's date,
+    // which this run's own completion would otherwise replace
+    let loaded_through = fetched
+        .latest()
+        .unwrap_or_else(|| since.week(Weekday::Mon).first_day());
+    Ok((
+        fetched.raw_data,
+        fetched.validation,
+        Some(loaded_through),
+        fetched.archives,
+    ))
+}
+
+/// Run NSW sales ingestion from the JSON API - only sales updated since the last run
+async fn run_nsw_sales_api(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    info!("=== NSW Sales API Pipeline ===");
+
+    let api = config
+        .nsw_sales_api
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("NSW_SALES_API_URL is not set"))?;
+    let since = watermark::get_watermark(db, "nsw_sales_api").await?;
+
+    // Step 1: Fetch every page since the watermark
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "pages", None).await;
+    let fetched = fetch::fetch_nsw_sales_api(api, &config.fetcher, since).await?;
+    progress.set_total(fetched.pages as u64).await;
+    progress.advance(fetched.pages as u64).await;
     info!("✓ Fetch complete");
 
     // Step 2: Parse into PropertyRecord structs
-    info!("Step 2/4: Parsing data...");
-    let records = parse::parse_nsw_sales(raw_data, "nsw_sales".to_string()).await?;
-    info!("✓ Parsed {} records", records.len());
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    let parsed = parse::parse_nsw_sales_api(fetched.raw_data, "nsw_sales_api".to_string()).await?;
+    let validation = report_parse(config, db, progress, "nsw_sales_api", &parsed, "records").await?;
+
+    let total = Some(parsed.records.len() as u64);
+    let batches = batch::batches(parsed.records.into_iter().map(Ok), config.batch_size, None);
+    let (stats, mut metrics) =
+        enrich_and_write(config, db, progress, throttle, "nsw_sales_api", batches, total).await?;
+    metrics.validation = Some(validation);
+
+    // Only now is everything up to the latest update stored
+    if let Some(latest) = fetched.latest {
+        watermark::advance_watermark(db, "nsw_sales_api", latest).await?;
+        info!("✓ Watermark advanced to {}", latest);
+    }
+
+    Ok((stats, metrics))
+}
+
+/// Run WA sales ingestion from a Landgate extract
+async fn run_wa_sales(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    info!("=== WA Sales Pipeline ===");
+
+    let url = config
+        .wa_sales_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("WA_SALES_URL is not set"))?;
+
+    // Step 1: Fetch raw data
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "bytes", None).await;
+    let fetched = fetch::fetch_wa_sales(url, &config.fetcher);
+    let raw_data = fetch_or_replay(config, db, progress, "wa_sales", url, fetched).await?;
+    report_downloaded(&raw_data, progress).await;
+    info!("✓ Fetch complete");
+
+    // Step 2: Parse into PropertyRecord structs
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    let parsed = parse::parse_wa_sales(raw_data, "wa_sales".to_string()).await?;
+    let validation = report_parse(config, db, progress, "wa_sales", &parsed, "records").await?;
 
     // Limit to first N records for testing (optional)
-    let records = if config.limit_records > 0 {
-        let limit = config.limit_records.min(records.len());
+    let mut total = parsed.records.len();
+    let limit = (config.limit_records > 0).then_some(config.limit_records);
+    if let Some(limit) = limit {
         warn!("Limiting to first {} records (testing mode)", limit);
-        records.into_iter().take(limit).collect()
+        total = total.min(limit);
+    }
+
+    let records = parsed.records.into_iter().map(Ok);
+    let batches = batch::batches(records, config.batch_size, limit);
+    let total = Some(total as u64);
+    let (stats, mut metrics) =
+        enrich_and_write(config, db, progress, throttle, "wa_sales", batches, total).await?;
+    metrics.validation = Some(validation);
+
+    Ok((stats, metrics))
+}
+
+/// Run Domain listings ingestion - current asking prices and rents
+/// A run that spends its call budget loads what it fetched; the next carries on
+/// from the page it stopped at, and only a complete fetch starts over
+async fn run_domain_listings(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    info!("=== Domain Listings Pipeline ===");
+
+    let api = config
+        .domain_listings
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("DOMAIN_API_KEY is not set"))?;
+
+    // Step 1: Fetch every search page not already saved
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "pages", None).await;
+    let fetched = fetch::fetch_domain_listings(api, &config.fetcher, &config.temp_dir).await?;
+    let pages = (fetched.pages_fetched + fetched.pages_reused) as u64;
+    progress.set_total(pages).await;
+    progress.advance(pages).await;
+    info!("✓ Fetch complete");
+
+    // Step 2: Parse into PropertyRecord structs
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    let parsed =
+        parse::parse_domain_listings(fetched.raw_data, "domain_listings".to_string()).await?;
+    let validation =
+        report_parse(config, db, progress, "domain_listings", &parsed, "listings").await?;
+
+    let total = Some(parsed.records.len() as u64);
+    let records = parsed.records.into_iter().map(Ok);
+    let batches = batch::batches(records, config.batch_size, None);
+    let (stats, mut metrics) =
+        enrich_and_write(config, db, progress, throttle, "domain_listings", batches, total).await?;
+    metrics.validation = Some(validation);
+
+    // Only now are the saved pages no longer needed
+    if fetched.complete {
+        fetch::clear_domain_pages(&config.temp_dir)?;
     } else {
-        records
-    };
+        warn!("Domain listings are incomplete; the next run fetches the rest");
+    }
+
+    Ok((stats, metrics))
+}
+
+/// Step 3 of the sales pipelines: enrich and write each batch of records in
+/// turn, then refresh the statistics; `total` is how many records are coming, when known
+/// Staged sources stage the enriched records for review instead, as one batch
+/// once every record is enriched
+async fn enrich_and_write<I>(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+    source_id: &str,
+    batches: I,
+    total: Option<u64>,
+) -> Result<(WriteStats, RunMetrics)>
+where
+    I: Iterator<Item = Result<Vec<PropertyRecord>>>,
+{
+    let staged = config.staged_sources.iter().any(|s| s == source_id);
+    info!(
+        "Step 3/3: Enriching and writing in batches of {}...",
+        config.batch_size
+    );
+    progress.start_stage("load", "rows", total).await;
+    progress.set_throttle(throttle.status()).await;
+    let geocoders = build_geocoders(config, db)?;
+    let rentals = enrich::RentalIndex::load(db, config.rental_matching).await?;
 
-    // Step 3: Enrich (estimate bedrooms, match rentals, calculate yields)
-    info!("Step 3/4: Enriching data...");
-    let enriched = enrich::enrich_all(records, db).await?;
-    info!("✓ Enriched {} records", enriched.len());
+    let mut tally = RecordTally::default();
+    let mut states = Vec::new();
+    let mut stats = WriteStats::default();
+    let mut to_stage = Vec::new();
+    let mut loaded = 0;
+    for (index, batch) in batches.enumerate() {
+        // Commercial and other sales would only skew yields
+        let mut batch = batch?;
+        if config.rental_matching.residential_only {
+            let (kept, dropped) = enrich::drop_non_residential(batch);
+            stats.excluded += dropped;
+            batch = kept;
+        }
+
+        // Enrich (estimate bedrooms, match rentals, calculate yields, geocode)
+        let mut enriched = Vec::new();
+        let mut remaining = batch.into_iter().peekable();
+        while remaining.peek().is_some() {
+            let chunk = remaining.by_ref().take(CHUNK_SIZE).collect();
+            let chunk =
+                enrich::enrich_all_with(chunk, &rentals, &geocoders, config.rental_matching)
+                    .await?;
+            enriched.extend(chunk);
+        }
+        tally.add(&enriched);
+        for record in &enriched {
+            if !states.contains(&record.state) {
+                states.push(record.state);
+            }
+        }
 
-    // Step 4: Write to database
-    info!("Step 4/4: Writing to database...");
-    let stats = write::write_properties(db, enriched).await?;
+        if staged {
+            loaded += enriched.len() as u64;
+            to_stage.extend(enriched);
+            progress.advance(loaded).await;
+        } else {
+            let mut written = loaded;
+            let report = |rows| {
+                written += rows as u64;
+                written
+            };
+            stats += write_chunks(db, enriched, progress, throttle, report, |chunk| {
+                write::write_properties(db, chunk)
+            })
+            .await?;
+            loaded = written;
+        }
+        info!(
+            "✓ Batch {}: {} records enriched so far ({})",
+            index + 1,
+            tally.records(),
+            stats
+        );
+    }
+    let mut metrics = tally.into_metrics();
+
+    if staged {
+        let batch_id = staging::stage_records(db, source_id, to_stage).await?;
+        info!("✓ Staged as batch {}; approve it at /api/admin/staging", batch_id);
+        let stats = WriteStats {
+            excluded: stats.excluded,
+            ..WriteStats::default()
+        };
+        return Ok((stats, metrics));
+    }
+    metrics.records_inserted = stats.inserted as u64;
+    metrics.drift = Some(stats.drift.clone());
     info!("✓ Write complete");
 
-    Ok(stats)
+    let assigned = write::assign_property_lgas(db).await?;
+    if assigned > 0 {
+        info!("✓ Assigned LGAs to {} properties", assigned);
+    }
+
+    // Keep suburb_statistics in step with the new properties
+    for state in states {
+        let groups = suburb_stats::refresh_suburb_statistics(db, state).await?;
+        info!("✓ Refreshed {} {} suburb statistics groups", groups, state);
+    }
+
+    Ok((stats, metrics))
 }
 
-/// Run NSW rental bond data ingestion
-async fn run_nsw_rentals(config: &Config, db: &PgPool) -> Result<WriteStats> {
-    info!("=== NSW Rentals Pipeline ===");
+/// The address CSV if one is configured, G-NAF, then the external provider if
+/// one is configured; none at all when geocoding is off
+/// Built per run so the external call budget applies to each run separately
+fn build_geocoders(config: &Config, db: &PgPool) -> Result<GeocoderChain> {
+    if !config.geocoding {
+        info!("Geocoding disabled");
+        return Ok(GeocoderChain::new());
+    }
+
+    let mut chain = GeocoderChain::new();
+    if let Some(path) = &config.geocode_csv {
+        chain = chain.with(LocalGeocoder::from_path(path)?);
+    }
+    let chain = chain.with(GnafGeocoder::new(db.clone()));
+
+    match &config.external_geocoder {
+        Some(external) => {
+            info!(
+                "External geocoding fallback enabled (max {} calls)",
+                external.max_calls
+            );
+            let cache = GeocodeCache::persistent(db.clone());
+            Ok(chain.with(ExternalGeocoder::new(external.clone(), cache)?))
+        }
+        None => Ok(chain),
+    }
+}
+
+/// Run VIC median sales ingestion - suburb aggregates, written without enrichment
+/// since they have no street address to geocode or postcode to match rents by
+async fn run_vic_sales(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    info!("=== VIC Sales Pipeline ===");
+
+    let url = config
+        .vic_sales_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("VIC_SALES_URL is not set"))?;
+
+    // Step 1: Fetch raw data
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "bytes", None).await;
+    let fetched = fetch::fetch_vic_sales(url, &config.fetcher);
+    let raw_data = fetch_or_replay(config, db, progress, "vic_sales", url, fetched).await?;
+    report_downloaded(&raw_data, progress).await;
+    info!("✓ Fetch complete");
+
+    // Step 2: Parse into aggregate PropertyRecord structs
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    let parsed = parse::parse_vic_sales(raw_data, "vic_sales".to_string()).await?;
+    let validation =
+        report_parse(config, db, progress, "vic_sales", &parsed, "median sales").await?;
+
+    let mut tally = RecordTally::default();
+    tally.add(&parsed.records);
+    let mut metrics = tally.into_metrics();
+    metrics.validation = Some(validation);
+
+    // Step 3: Write to database
+    info!("Step 3/3: Writing to database...");
+    let stats = write_in_chunks(db, parsed.records, progress, throttle, |chunk| {
+        write::write_properties(db, chunk)
+    })
+    .await?;
+    info!("✓ Write complete");
+
+    metrics.records_inserted = stats.inserted as u64;
+    metrics.drift = Some(stats.drift.clone());
+    Ok((stats, metrics))
+}
+
+/// Run South Australian median sales ingestion
+/// The medians go through the usual enrichment, so they pick up SA rents
+/// once there are any; until then they are written without
+async fn run_sa_medians(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    info!("=== SA Medians Pipeline ===");
+
+    let url = config
+        .sa_medians_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("SA_MEDIANS_URL is not set"))?;
 
     // Step 1: Fetch raw data
     info!("Step 1/3: Fetching data...");
-    let raw_data = fetch::fetch_nsw_rentals(&config.nsw_rentals_url).await?;
+    progress.start_stage("fetch", "bytes", None).await;
+    let fetched = fetch::fetch_sa_medians(url, &config.fetcher);
+    let raw_data = fetch_or_replay(config, db, progress, "sa_medians", url, fetched).await?;
+    report_downloaded(&raw_data, progress).await;
+    info!("✓ Fetch complete");
+
+    // Step 2: Parse into aggregate PropertyRecord structs
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    let parsed = parse::parse_sa_medians(raw_data, "sa_medians".to_string()).await?;
+    let validation =
+        report_parse(config, db, progress, "sa_medians", &parsed, "median sales").await?;
+
+    let total = Some(parsed.records.len() as u64);
+    let records = parsed.records.into_iter().map(Ok);
+    let batches = batch::batches(records, config.batch_size, None);
+    let (stats, mut metrics) =
+        enrich_and_write(config, db, progress, throttle, "sa_medians", batches, total).await?;
+    metrics.validation = Some(validation);
+
+    Ok((stats, metrics))
+}
+
+/// A source of median rents, loaded by `run_rentals`
+#[derive(Debug, Clone, Copy)]
+enum RentalsSource {
+    /// NSW rental bond data, dated by the run
+    Nsw,
+    /// QLD median rents, each row dated by its own quarter
+    Qld,
+}
+
+impl RentalsSource {
+    fn source_id(self) -> &'static str {
+        match self {
+            RentalsSource::Nsw => "nsw_rentals",
+            RentalsSource::Qld => "qld_rentals",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            RentalsSource::Nsw => "NSW Rentals",
+            RentalsSource::Qld => "QLD Rentals",
+        }
+    }
+
+    fn url(self, config: &Config) -> Result<&str> {
+        match self {
+            RentalsSource::Nsw => Ok(&config.nsw_rentals_url),
+            RentalsSource::Qld => config
+                .qld_rentals_url
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("QLD_RENTALS_URL is not set")),
+        }
+    }
+}
+
+/// Run a median rents ingestion
+async fn run_rentals(
+    source: RentalsSource,
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    let source_id = source.source_id();
+    info!("=== {} Pipeline ===", source.name());
+    let url = source.url(config)?;
+
+    // Step 1: Fetch raw data
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "bytes", None).await;
+    let fetched = async {
+        match source {
+            RentalsSource::Nsw => fetch::fetch_nsw_rentals(url, &config.fetcher).await,
+            RentalsSource::Qld => fetch::fetch_qld_rentals(url, &config.fetcher).await,
+        }
+    };
+    let raw_data = fetch_or_replay(config, db, progress, source_id, url, fetched).await?;
+    report_downloaded(&raw_data, progress).await;
     info!("✓ Fetch complete");
 
     // Step 2: Parse into RentalMedian structs
     info!("Step 2/3: Parsing data...");
-    let period = Utc::now().naive_utc().date();
-    let rentals = parse::parse_nsw_rentals(raw_data, period).await?;
-    info!("✓ Parsed {} rental medians", rentals.len());
+    progress.start_stage("parse", "rows", None).await;
+    let parsed = match source {
+        RentalsSource::Nsw => parse::parse_nsw_rentals(raw_data, config.run_date()).await?,
+        RentalsSource::Qld => parse::parse_qld_rentals(raw_data).await?,
+    };
+    let validation =
+        report_parse(config, db, progress, source_id, &parsed, "rental medians").await?;
+    let rentals = parsed.records;
+
+    // Step 3: Write to database
+    info!("Step 3/3: Writing to database...");
+    let records_parsed = rentals.len() as u64;
+    let stats = write_in_chunks(db, rentals, progress, throttle, |chunk| {
+        write::write_rental_medians(db, chunk, source_id)
+    })
+    .await?;
+    info!("✓ Write complete");
+
+    let metrics = RunMetrics {
+        records_parsed,
+        records_inserted: stats.inserted as u64,
+        validation: Some(validation),
+        ..Default::default()
+    };
+    Ok((stats, metrics))
+}
+
+/// Run NSW bond lodgement ingestion - individual rental events, not medians
+async fn run_nsw_bond_lodgements(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    info!("=== NSW Bond Lodgements Pipeline ===");
+
+    let url = config
+        .nsw_bond_lodgements_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("NSW_BOND_LODGEMENTS_URL is not set"))?;
+
+    // Step 1: Fetch raw data
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "bytes", None).await;
+    let fetched = fetch::fetch_nsw_rentals(url, &config.fetcher);
+    let raw_data =
+        fetch_or_replay(config, db, progress, "nsw_bond_lodgements", url, fetched).await?;
+    report_downloaded(&raw_data, progress).await;
+    info!("✓ Fetch complete");
+
+    // Step 2: Parse into RentalObservation structs
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    // One file per month, so observations are keyed by the first of the month
+    let today = config.run_date();
+    let period = today - chrono::Duration::days(today.day0() as i64);
+    let observations = parse::parse_nsw_bond_lodgements(raw_data, period).await?;
+    progress.set_total(observations.len() as u64).await;
+    progress.advance(observations.len() as u64).await;
+    info!("✓ Parsed {} bond lodgements", observations.len());
+
+    // Step 3: Write to database, replacing any earlier load of this period
+    info!("Step 3/3: Writing to database...");
+    let records_parsed = observations.len() as u64;
+    let replaced = write::clear_rental_observations(db, period).await?;
+    if replaced > 0 {
+        info!("Replacing {} observations already stored for {}", replaced, period);
+    }
+    let stats = write_in_chunks(db, observations, progress, throttle, |chunk| {
+        write::write_rental_observations(db, chunk)
+    })
+    .await?;
+    info!("✓ Write complete");
+
+    let metrics = RunMetrics {
+        records_parsed,
+        records_inserted: stats.inserted as u64,
+        ..Default::default()
+    };
+    Ok((stats, metrics))
+}
+
+/// Load the ABS postcode to SA3/SA4 correspondence into the regions lookup
+async fn run_abs_postcode_regions(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    info!("=== ABS Postcode Regions Pipeline ===");
+
+    let url = config
+        .abs_postcode_regions_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("ABS_POSTCODE_REGIONS_URL is not set"))?;
+
+    // Step 1: Fetch raw data
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "bytes", None).await;
+    let fetched = fetch::fetch_abs_correspondence(url, &config.fetcher);
+    let raw_data =
+        fetch_or_replay(config, db, progress, "abs_postcode_regions", url, fetched).await?;
+    report_downloaded(&raw_data, progress).await;
+    info!("✓ Fetch complete");
+
+    // Step 2: Parse, keeping each postcode's highest-ratio SA3
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    let regions = parse::parse_abs_postcode_regions(raw_data).await?;
+    progress.set_total(regions.len() as u64).await;
+    progress.advance(regions.len() as u64).await;
+    info!("✓ Mapped {} postcodes", regions.len());
+
+    // Step 3: Write to database
+    info!("Step 3/3: Writing to database...");
+    let records_parsed = regions.len() as u64;
+    let stats = write_in_chunks(db, regions, progress, throttle, |chunk| {
+        write::write_postcode_regions(db, chunk)
+    })
+    .await?;
+    info!("✓ Write complete");
+
+    let metrics = RunMetrics {
+        records_parsed,
+        records_inserted: (stats.inserted + stats.updated) as u64,
+        ..Default::default()
+    };
+    Ok((stats, metrics))
+}
+
+/// Load the ABS suburb or postcode to LGA correspondence, then re-derive every
+/// property's LGA so council rates follow the new mapping
+async fn run_abs_lga_correspondence(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    info!("=== ABS LGA Correspondence Pipeline ===");
+
+    let url = config
+        .lga_correspondence_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("LGA_CORRESPONDENCE_URL is not set"))?;
+
+    // Step 1: Fetch raw data
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "bytes", None).await;
+    let fetched = fetch::fetch_abs_correspondence(url, &config.fetcher);
+    let raw_data =
+        fetch_or_replay(config, db, progress, "abs_lga_correspondence", url, fetched).await?;
+    report_downloaded(&raw_data, progress).await;
+    info!("✓ Fetch complete");
+
+    // Step 2: Parse, keeping each area's highest-ratio LGA
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    let mappings = parse::parse_lga_correspondence(raw_data).await?;
+    progress.set_total(mappings.len() as u64).await;
+    progress.advance(mappings.len() as u64).await;
+    info!("✓ Mapped {} areas", mappings.len());
+
+    // Step 3: Write to database
+    info!("Step 3/3: Writing to database...");
+    let records_parsed = mappings.len() as u64;
+    let stats = write_in_chunks(db, mappings, progress, throttle, |chunk| {
+        write::write_lga_correspondence(db, chunk)
+    })
+    .await?;
+    info!("✓ Write complete");
+
+    let assigned = write::assign_property_lgas(db).await?;
+    info!("✓ {} properties changed LGA", assigned);
+
+    let metrics = RunMetrics {
+        records_parsed,
+        records_inserted: (stats.inserted + stats.updated) as u64,
+        ..Default::default()
+    };
+    Ok((stats, metrics))
+}
+
+/// Load average residential rates and levies per council
+async fn run_council_rates(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+) -> Result<(WriteStats, RunMetrics)> {
+    info!("=== Council Rates Pipeline ===");
+
+    let url = config
+        .council_rates_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("COUNCIL_RATES_URL is not set"))?;
+
+    // Step 1: Fetch raw data
+    info!("Step 1/3: Fetching data...");
+    progress.start_stage("fetch", "bytes", None).await;
+    let fetched = fetch::fetch_council_rates(url, &config.fetcher);
+    let raw_data = fetch_or_replay(config, db, progress, "council_rates", url, fetched).await?;
+    report_downloaded(&raw_data, progress).await;
+    info!("✓ Fetch complete");
+
+    // Step 2: Parse, keeping each council's latest financial year
+    info!("Step 2/3: Parsing data...");
+    progress.start_stage("parse", "rows", None).await;
+    let councils = parse::parse_council_rates(raw_data).await?;
+    progress.set_total(councils.len() as u64).await;
+    progress.advance(councils.len() as u64).await;
+    info!("✓ Parsed rates for {} councils", councils.len());
 
     // Step 3: Write to database
     info!("Step 3/3: Writing to database...");
-    let stats = write::write_rental_medians(db, rentals).await?;
+    let records_parsed = councils.len() as u64;
+    let stats = write_in_chunks(db, councils, progress, throttle, |chunk| {
+        write::write_council_rates(db, chunk)
+    })
+    .await?;
     info!("✓ Write complete");
 
+    let metrics = RunMetrics {
+        records_parsed,
+        records_inserted: (stats.inserted + stats.updated) as u64,
+        ..Default::default()
+    };
+    Ok((stats, metrics))
+}
+
+/// A progress spinner on stderr for the sales download; None when stderr
+/// isn't a terminal, where the downloader's periodic log lines are enough
+fn download_bar() -> Option<ProgressBar> {
+    if !std::io::stderr().is_terminal() {
+        return None;
+    }
+    let bar = ProgressBar::new_spinner();
+    bar.set_style(bar_style("{spinner} {bytes} {bytes_per_sec}"));
+    Some(bar)
+}
+
+fn bar_style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .map(|style| style.progress_chars("=> "))
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+}
+
+/// A single-file source's bytes, read back from the replayed file or else
+/// fetched and kept in the raw archive
+async fn fetch_or_replay(
+    config: &Config,
+    db: &PgPool,
+    progress: &ProgressWriter,
+    source_id: &str,
+    url: &str,
+    fetch: impl std::future::Future<Output = Result<RawData>>,
+) -> Result<RawData> {
+    if let Some(replay) = &config.replay {
+        let bytes = std::fs::read(&replay.path)
+            .with_context(|| format!("Failed to read {}", replay.path.display()))?;
+        return Ok(RawData::Bytes(bytes));
+    }
+
+    let raw_data = fetch.await?;
+    if let RawData::Bytes(bytes) = &raw_data {
+        let filename = archive::file_name_from_url(url, source_id);
+        archive_raw_file(config, db, progress, source_id, |archive| {
+            archive.archive_bytes(source_id, config.run_date(), &filename, bytes)
+        })
+        .await;
+    }
+    Ok(raw_data)
+}
+
+/// Keep a fetched file in the raw archive and record it on the run
+/// Failing to is logged but doesn't fail the run, which has its data already
+async fn archive_raw_file(
+    config: &Config,
+    db: &PgPool,
+    progress: &ProgressWriter,
+    source_id: &str,
+    store: impl FnOnce(&RawArchive) -> Result<archive::ArchivedFile>,
+) {
+    let archived = match store(&config.archive) {
+        Ok(archived) => archived,
+        Err(e) => {
+            warn!("Failed to archive raw {} file: {}", source_id, e);
+            return;
+        }
+    };
+    if let Err(e) = runs::record_raw_files(db, progress.run_id(), &[archived]).await {
+        warn!("Failed to record archived file on run {}: {}", progress.run_id(), e);
+    }
+}
+
+/// Count a parse's records as the parse stage's progress, report its row
+/// errors and check there weren't too many of them; the passed check is kept
+/// on the run's metrics
+async fn report_parse<T>(
+    config: &Config,
+    db: &PgPool,
+    progress: &mut ProgressWriter,
+    source_id: &str,
+    parsed: &ParseReport<T>,
+    what: &str,
+) -> Result<ValidationReport> {
+    let records = parsed.records.len() as u64;
+    progress.set_total(records).await;
+    progress.advance(records).await;
+    info!("✓ Parsed {} {}", records, what);
+    let (errors, total_rows) = (&parsed.errors, parsed.total_rows);
+    report_parse_errors(config, db, progress, source_id, errors, total_rows).await;
+    let mut validation = ValidationReport::default();
+    validation.check_parse_errors(errors.len(), total_rows, config.max_parse_error_rate);
+    validation.log();
+    Ok(validation.into_result()?)
+}
+
+/// Record how many rows failed to parse on the run, and write the first of
+/// them to a sidecar file in the temp directory when there are any
+/// Failing to is logged but doesn't fail the run
+async fn report_parse_errors(
+    config: &Config,
+    db: &PgPool,
+    progress: &ProgressWriter,
+    source_id: &str,
+    errors: &[RowError],
+    total_rows: usize,
+) {
+    if let Err(e) = runs::record_parse_errors(db, progress.run_id(), errors.len()).await {
+        warn!("Failed to record parse errors on run {}: {}", progress.run_id(), e);
+    }
+    if errors.is_empty() {
+        return;
+    }
+    match parse::write_error_sidecar(&config.temp_dir, source_id, errors, total_rows) {
+        Ok(path) => info!("Row errors written to {:?}", path),
+        Err(e) => warn!("Failed to write {} row errors: {}", source_id, e),
+    }
+}
+
+/// Record the size of the fetched file or files as the fetch stage's progress
+async fn report_downloaded(raw_data: &RawData, progress: &mut ProgressWriter) {
+    let size = match raw_data {
+        RawData::File(path) => std::fs::metadata(path).map(|m| m.len()).ok(),
+        RawData::Files(paths) => paths
+            .iter()
+            .map(|path| std::fs::metadata(path).map(|m| m.len()).ok())
+            .sum(),
+        RawData::Bytes(bytes) => Some(bytes.len() as u64),
+        _ => None,
+    };
+    if let Some(size) = size {
+        progress.set_total(size).await;
+        progress.advance(size).await;
+    }
+}
+
+/// Write `items` CHUNK_SIZE at a time, reporting chunks written as progress
+async fn write_in_chunks<T, F, Fut>(
+    db: &PgPool,
+    items: Vec<T>,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+    write_chunk: F,
+) -> Result<WriteStats>
+where
+    F: FnMut(Vec<T>) -> Fut,
+    Fut: std::future::Future<Output = Result<WriteStats>>,
+{
+    let chunks_total = items.len().div_ceil(CHUNK_SIZE);
+    progress
+        .start_stage("write", "chunks", Some(chunks_total as u64))
+        .await;
+    progress.set_throttle(throttle.status()).await;
+
+    let mut written = 0;
+    let report = |_| {
+        written += 1;
+        written
+    };
+    write_chunks(db, items, progress, throttle, report, write_chunk).await
+}
+
+/// Write `items` CHUNK_SIZE at a time within the current stage; `report` turns
+/// the rows of each chunk written into the stage's progress
+/// Up to the throttle's concurrency chunks are in flight at once; in nice mode
+/// latency is probed after each chunk and new chunks wait out the throttle's delay
+async fn write_chunks<T, R, F, Fut>(
+    db: &PgPool,
+    items: Vec<T>,
+    progress: &mut ProgressWriter,
+    throttle: &mut Throttle,
+    mut report: R,
+    mut write_chunk: F,
+) -> Result<WriteStats>
+where
+    R: FnMut(usize) -> u64,
+    F: FnMut(Vec<T>) -> Fut,
+    Fut: std::future::Future<Output = Result<WriteStats>>,
+{
+    let mut stats = WriteStats::default();
+    let mut remaining = items.into_iter().peekable();
+    let mut in_flight = FuturesUnordered::new();
+    loop {
+        while in_flight.len() < throttle.concurrency() && remaining.peek().is_some() {
+            let chunk: Vec<T> = remaining.by_ref().take(CHUNK_SIZE).collect();
+            let rows = chunk.len();
+            let delay = throttle.delay();
+            throttle.record_delay(delay);
+            let write = write_chunk(chunk);
+            in_flight.push(async move {
+                tokio::time::sleep(delay).await;
+                write.await.map(|stats| (rows, stats))
+            });
+        }
+
+        let Some(result) = in_flight.next().await else {
+            break;
+        };
+        let (rows, written) = result?;
+        stats += written;
+        progress.advance(report(rows)).await;
+
+        if throttle.is_adaptive() && remaining.peek().is_some() {
+            throttle.observe(throttle::probe_latency(db).await);
+            progress.set_throttle(throttle.status()).await;
+        }
+    }
+
     Ok(stats)
 }
 
@@ -130,8 +1526,56 @@ struct Config {
     database_url: String,
     temp_dir: PathBuf,
     nsw_sales_url: String,
+    /// Full archive or the weekly ones since the last run (NSW_SALES_MODE)
+    nsw_sales_mode: NswSalesMode,
+    /// Weekly archive URL with a `{date}` placeholder for the week's Monday
+    nsw_sales_weekly_url: String,
     nsw_rentals_url: String,
+    /// Queensland RTA quarterly median rents CSV; QLD rents aren't loaded when unset
+    qld_rentals_url: Option<String>,
+    /// Detailed monthly lodgement file; individual observations are skipped when unset
+    nsw_bond_lodgements_url: Option<String>,
+    /// ABS postcode to SA3 correspondence CSV; regions aren't loaded when unset
+    abs_postcode_regions_url: Option<String>,
+    /// ABS suburb (SAL) or postcode to LGA correspondence CSV; properties get no LGA when unset
+    lga_correspondence_url: Option<String>,
+    /// Average council rates per LGA CSV; the rates aren't loaded when unset
+    council_rates_url: Option<String>,
+    /// Recent sales JSON API; only the bulk file is loaded when unset
+    nsw_sales_api: Option<NswSalesApiConfig>,
+    /// WA Landgate sales extract CSV; WA isn't loaded when unset
+    wa_sales_url: Option<String>,
+    /// Victorian Valuer-General median sales XLSX; VIC isn't loaded when unset
+    vic_sales_url: Option<String>,
+    /// South Australian quarterly suburb median sales XLSX; SA isn't loaded when unset
+    sa_medians_url: Option<String>,
+    /// Domain listings search; current listings aren't loaded when unset
+    domain_listings: Option<DomainListingsConfig>,
+    /// One HTTP client, and its retry rules, for every source
+    fetcher: Fetcher,
+    /// Ignore the validators of previous downloads (FORCE_DOWNLOAD or --force)
+    force_download: bool,
+    /// Checks of the NSW sales download and how many rows it must parse to
+    nsw_sales_validation: ValidationConfig,
+    /// Share of rows that may fail to parse before a run fails (PARSE_MAX_ERROR_RATE)
+    max_parse_error_rate: f64,
+    /// Nice (adaptive) or fast writes
+    throttle: ThrottleConfig,
+    rental_matching: RentalMatching,
     limit_records: usize, // 0 = no limit
+    /// Records parsed, enriched and written at a time (INGEST_BATCH_SIZE)
+    batch_size: usize,
+    /// Sources whose records are staged for admin review instead of written
+    staged_sources: Vec<String>,
+    /// Look up coordinates for records without them (GEOCODING, or --no-geocode)
+    geocoding: bool,
+    /// Pre-downloaded address to coordinate CSV, looked up first (GEOCODE_CSV_PATH)
+    geocode_csv: Option<PathBuf>,
+    external_geocoder: Option<ExternalGeocoderConfig>,
+    /// Where fetched files are kept, and for how long (ARCHIVE_DIR, ARCHIVE_RETENTION_DAYS)
+    archive: RawArchive,
+    /// Load this archived file instead of fetching (--replay)
+    replay: Option<Replay>,
 }
 
 impl Config {
@@ -147,16 +1591,102 @@ impl Config {
             nsw_sales_url: env::var("NSW_SALES_URL")
                 .unwrap_or_else(|_| "https://nswpropertysalesdata.com/data/archive.zip".to_string()),
 
+            nsw_sales_mode: NswSalesMode::from_env(),
+
+            nsw_sales_weekly_url: env::var("NSW_SALES_WEEKLY_URL")
+                .ok()
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| fetch::DEFAULT_NSW_SALES_WEEKLY_URL.to_string()),
+
             nsw_rentals_url: env::var("NSW_RENTALS_URL")
                 .unwrap_or_else(|_| {
                     // Default to a recent monthly file - user should update this
                     "https://www.nsw.gov.au/sites/default/files/2024-12/rental-bond-data-december-2024.xlsx".to_string()
                 }),
 
+            qld_rentals_url: env::var("QLD_RENTALS_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+
+            nsw_bond_lodgements_url: env::var("NSW_BOND_LODGEMENTS_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+
+            abs_postcode_regions_url: env::var("ABS_POSTCODE_REGIONS_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+
+            lga_correspondence_url: env::var("LGA_CORRESPONDENCE_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+
+            council_rates_url: env::var("COUNCIL_RATES_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+
+            nsw_sales_api: NswSalesApiConfig::from_env()?,
+
+            wa_sales_url: env::var("WA_SALES_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+
+            vic_sales_url: env::var("VIC_SALES_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+
+            sa_medians_url: env::var("SA_MEDIANS_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+
+            domain_listings: DomainListingsConfig::from_env()?,
+
+            fetcher: Fetcher::from_env()?,
+
+            force_download: env::var("FORCE_DOWNLOAD")
+                .is_ok_and(|s| s == "1" || s.eq_ignore_ascii_case("true")),
+
+            nsw_sales_validation: ValidationConfig::nsw_sales_from_env(),
+
+            max_parse_error_rate: validate::max_parse_error_rate_from_env(),
+
+            throttle: ThrottleConfig::from_env(),
+
+            rental_matching: RentalMatching::from_env(),
+
             limit_records: env::var("LIMIT_RECORDS")
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(0),
+
+            batch_size: batch::batch_size_from_env(),
+
+            staged_sources: env::var("STAGED_SOURCES")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+
+            geocoding: !env::var("GEOCODING")
+                .is_ok_and(|s| s == "0" || s.eq_ignore_ascii_case("false")),
+
+            geocode_csv: env::var("GEOCODE_CSV_PATH")
+                .ok()
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from),
+
+            external_geocoder: ExternalGeocoderConfig::from_env(),
+
+            archive: RawArchive::from_env(),
+
+            replay: None,
         })
     }
+
+    /// The replayed file's archive date, or else today
+    fn run_date(&self) -> NaiveDate {
+        self.replay
+            .as_ref()
+            .map_or_else(|| Utc::now().date_naive(), |replay| replay.date)
+    }
 }
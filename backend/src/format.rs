@@ -0,0 +1,132 @@
+//! Rounding and formatting policy for yields and money
+//!
+//! Yields are calculated once, rounded half-up to four decimal places, and
+//! stored at that precision; everything shown to people (API responses,
+//! digests, admin pages, logs) rounds half-up to two places from there.
+//! Money is whole dollars with thousands separators.
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Decimal places a yield (%) is stored with
+pub const YIELD_STORAGE_DP: u32 = 4;
+/// Decimal places a yield (%) is shown with
+pub const YIELD_DISPLAY_DP: u32 = 2;
+
+/// Round a freshly calculated yield (%) to its stored precision, half-up
+pub fn round_yield_for_storage(value: Decimal) -> Decimal {
+    value.round_dp_with_strategy(YIELD_STORAGE_DP, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Round a yield (%) to its displayed precision, half-up
+/// Always carries exactly that many places, so 4 serializes as "4.00"
+pub fn round_yield_for_display(value: Decimal) -> Decimal {
+    let mut rounded =
+        value.round_dp_with_strategy(YIELD_DISPLAY_DP, RoundingStrategy::MidpointAwayFromZero);
+    rounded.rescale(YIELD_DISPLAY_DP);
+    rounded
+}
+
+/// `round_yield_for_display` for yields aggregated in SQL as FLOAT8
+/// Values that aren't finite are returned unchanged
+pub fn round_yield_f64(value: f64) -> f64 {
+    Decimal::from_f64(value)
+        .map(round_yield_for_display)
+        .and_then(|d| d.to_f64())
+        .unwrap_or(value)
+}
+
+/// Yield for display, e.g. "4.40%"
+pub fn format_yield(value: Decimal) -> String {
+    format!("{:.2}%", round_yield_for_display(value))
+}
+
+/// Change in yield with its sign, e.g. "+0.25 pts"
+pub fn format_yield_change(points: Decimal) -> String {
+    let rounded = round_yield_for_display(points);
+    let sign = if rounded.is_sign_negative() && !rounded.is_zero() {
+        "-"
+    } else {
+        "+"
+    };
+    format!("{}{:.2} pts", sign, rounded.abs())
+}
+
+/// Whole dollars with thousands separators, e.g. "$1,250,000" or "-$500"
+pub fn format_money(amount: i64) -> String {
+    let digits = amount.unsigned_abs().to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    format!("{}${}", if amount < 0 { "-" } else { "" }, grouped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_format_yield_rounds_half_up() {
+        assert_eq!(format_yield(dec("4.005")), "4.01%");
+        assert_eq!(format_yield(dec("4.0049")), "4.00%");
+        assert_eq!(format_yield(dec("4.015")), "4.02%");
+        assert_eq!(format_yield(dec("4.4")), "4.40%");
+        assert_eq!(format_yield(dec("10")), "10.00%");
+        assert_eq!(format_yield(dec("-0.125")), "-0.13%");
+    }
+
+    #[test]
+    fn test_storage_rounding_keeps_four_places() {
+        assert_eq!(round_yield_for_storage(dec("4.00005")), dec("4.0001"));
+        assert_eq!(round_yield_for_storage(dec("4.000049")), dec("4.0000"));
+        assert_eq!(round_yield_for_storage(dec("3.9")), dec("3.9"));
+    }
+
+    #[test]
+    fn test_display_rounding_pads_to_two_places() {
+        assert_eq!(round_yield_for_display(dec("4")).to_string(), "4.00");
+        assert_eq!(round_yield_for_display(dec("3.9")).to_string(), "3.90");
+        assert_eq!(round_yield_for_display(dec("4.4571")).to_string(), "4.46");
+    }
+
+    #[test]
+    fn test_stored_and_displayed_yields_agree() {
+        // 4.005 stored is 4.005 displayed as 4.01, however it is reached
+        let stored = round_yield_for_storage(dec("4.00499999"));
+        assert_eq!(stored, dec("4.0050"));
+        assert_eq!(format_yield(stored), "4.01%");
+    }
+
+    #[test]
+    fn test_round_yield_f64() {
+        assert_eq!(round_yield_f64(4.3999996), 4.4);
+        assert_eq!(round_yield_f64(4.005), 4.01);
+        assert_eq!(round_yield_f64(2.675), 2.68);
+        assert!(round_yield_f64(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_format_yield_change() {
+        assert_eq!(format_yield_change(dec("0.25")), "+0.25 pts");
+        assert_eq!(format_yield_change(dec("-0.445")), "-0.45 pts");
+        assert_eq!(format_yield_change(dec("-0.001")), "+0.00 pts");
+    }
+
+    #[test]
+    fn test_format_money() {
+        assert_eq!(format_money(0), "$0");
+        assert_eq!(format_money(950), "$950");
+        assert_eq!(format_money(1_000), "$1,000");
+        assert_eq!(format_money(750_000), "$750,000");
+        assert_eq!(format_money(1_250_000), "$1,250,000");
+        assert_eq!(format_money(-500), "-$500");
+    }
+}
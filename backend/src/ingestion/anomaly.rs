@@ -0,0 +1,348 @@
+//! Anomaly detection for ingestion runs
+//!
+//! Compares a run's key metrics with the trailing average of recent successful
+//! runs for the same source. A large swing usually means the upstream format
+//! changed rather than the market.
+
+use crate::ingestion::drift::DriftReport;
+use crate::ingestion::enrich::RentalMatchCounts;
+use crate::ingestion::legacy::LegacyYieldFilterNote;
+use crate::ingestion::throttle::ThrottleMetrics;
+use crate::ingestion::types::{IngestionRun, PropertyRecord};
+use crate::ingestion::validate::ValidationReport;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+pub const DEFAULT_ANOMALY_WINDOW: usize = 5;
+/// Fewer successful runs than this and there is no baseline to compare against
+pub const DEFAULT_ANOMALY_MIN_HISTORY: usize = 3;
+pub const DEFAULT_COUNT_THRESHOLD: f64 = 0.5;
+pub const DEFAULT_PRICE_THRESHOLD: f64 = 0.3;
+pub const DEFAULT_MATCH_RATE_THRESHOLD: f64 = 0.3;
+
+/// Metrics recorded for each run; stored in ingestion_runs.metrics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunMetrics {
+    pub records_parsed: u64,
+    pub records_inserted: u64,
+    /// None for sources without prices
+    pub median_price: Option<f64>,
+    /// Share of records matched to a rental median; None for sources that aren't matched
+    pub rental_match_rate: Option<f64>,
+    /// Records matched at each step of the rental fallback chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rental_matches: Option<RentalMatchCounts>,
+    /// Drift of updated properties' values; None for sources that don't update properties
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift: Option<DriftReport>,
+    /// How much the write stage was throttled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<ThrottleMetrics>,
+    /// Set while the source's data may still lack properties the legacy
+    /// yield filter dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legacy_yield_filter: Option<LegacyYieldFilterNote>,
+    /// Integrity checks of the fetched file, for sources that run them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validation: Option<ValidationReport>,
+}
+
+impl RunMetrics {
+    /// Metrics for a property run from its enriched records
+    /// `records_inserted` is left at zero for the caller to fill in after writing
+    pub fn from_records(records: &[PropertyRecord]) -> Self {
+        let mut tally = RecordTally::default();
+        tally.add(records);
+        tally.into_metrics()
+    }
+
+    fn values(&self) -> [(&'static str, Option<f64>); 4] {
+        [
+            ("records_parsed", Some(self.records_parsed as f64)),
+            ("records_inserted", Some(self.records_inserted as f64)),
+            ("median_price", self.median_price),
+            ("rental_match_rate", self.rental_match_rate),
+        ]
+    }
+}
+
+/// What a property run's metrics are computed from, added up a batch of
+/// enriched records at a time
+/// Only sale prices are kept per record, for the median
+#[derive(Debug, Clone, Default)]
+pub struct RecordTally {
+    prices: Vec<i64>,
+    records: usize,
+    matched: usize,
+    matches: RentalMatchCounts,
+}
+
+impl RecordTally {
+    pub fn add(&mut self, records: &[PropertyRecord]) {
+        self.prices
+            .extend(records.iter().filter_map(|r| r.sale_price));
+        self.records += records.len();
+        self.matched += records.iter().filter(|r| r.weekly_rent.is_some()).count();
+        for record in records {
+            self.matches.add(record);
+        }
+    }
+
+    /// Records added so far
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// `records_inserted` is left at zero for the caller to fill in after writing
+    pub fn into_metrics(mut self) -> RunMetrics {
+        self.prices.sort_unstable();
+        let prices = &self.prices;
+        let median_price = match prices.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(prices[n / 2] as f64),
+            n => Some((prices[n / 2 - 1] + prices[n / 2]) as f64 / 2.0),
+        };
+        let rental_match_rate =
+            (self.records > 0).then(|| self.matched as f64 / self.records as f64);
+
+        RunMetrics {
+            records_parsed: self.records as u64,
+            records_inserted: 0,
+            median_price,
+            rental_match_rate,
+            rental_matches: (self.records > 0).then_some(self.matches),
+            drift: None,
+            throttle: None,
+            legacy_yield_filter: None,
+            validation: None,
+        }
+    }
+}
+
+/// Largest relative change from the baseline each metric may make before it is flagged
+#[derive(Debug, Clone)]
+pub struct AnomalyThresholds {
+    /// Number of previous successful runs averaged into the baseline
+    pub window: usize,
+    pub min_history: usize,
+    /// Applies to records parsed and records inserted
+    pub count: f64,
+    pub price: f64,
+    pub match_rate: f64,
+}
+
+impl Default for AnomalyThresholds {
+    fn default() -> Self {
+        AnomalyThresholds {
+            window: DEFAULT_ANOMALY_WINDOW,
+            min_history: DEFAULT_ANOMALY_MIN_HISTORY,
+            count: DEFAULT_COUNT_THRESHOLD,
+            price: DEFAULT_PRICE_THRESHOLD,
+            match_rate: DEFAULT_MATCH_RATE_THRESHOLD,
+        }
+    }
+}
+
+impl AnomalyThresholds {
+    /// Thresholds from ANOMALY_WINDOW, ANOMALY_COUNT_THRESHOLD,
+    /// ANOMALY_PRICE_THRESHOLD and ANOMALY_MATCH_RATE_THRESHOLD
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            env::var(name)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        }
+
+        AnomalyThresholds {
+            window: var("ANOMALY_WINDOW", DEFAULT_ANOMALY_WINDOW),
+            min_history: DEFAULT_ANOMALY_MIN_HISTORY,
+            count: var("ANOMALY_COUNT_THRESHOLD", DEFAULT_COUNT_THRESHOLD),
+            price: var("ANOMALY_PRICE_THRESHOLD", DEFAULT_PRICE_THRESHOLD),
+            match_rate: var("ANOMALY_MATCH_RATE_THRESHOLD", DEFAULT_MATCH_RATE_THRESHOLD),
+        }
+    }
+
+    fn for_metric(&self, metric: &str) -> f64 {
+        match metric {
+            "median_price" => self.price,
+            "rental_match_rate" => self.match_rate,
+            _ => self.count,
+        }
+    }
+}
+
+/// A metric that moved further from its baseline than its threshold allows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub metric: String,
+    pub value: f64,
+    /// Average over the previous successful runs
+    pub baseline: f64,
+    /// Relative change from the baseline; -0.9 is a 90% drop
+    pub change: f64,
+}
+
+/// Compare `current` with the runs in `history` (most recent first)
+/// Runs without recorded metrics are ignored; no anomalies are reported until
+/// there are `min_history` runs to compare against
+pub fn detect_anomalies(
+    current: &RunMetrics,
+    history: &[IngestionRun],
+    thresholds: &AnomalyThresholds,
+) -> Vec<Anomaly> {
+    let previous: Vec<RunMetrics> = history
+        .iter()
+        .filter_map(|run| run.metrics.clone())
+        .filter_map(|metrics| serde_json::from_value(metrics).ok())
+        .take(thresholds.window)
+        .collect();
+    if previous.len() < thresholds.min_history.max(1) {
+        return Vec::new();
+    }
+
+    let mut anomalies = Vec::new();
+    for (i, (metric, value)) in current.values().into_iter().enumerate() {
+        let Some(value) = value else {
+            continue;
+        };
+        let past: Vec<f64> = previous.iter().filter_map(|m| m.values()[i].1).collect();
+        if past.is_empty() {
+            continue;
+        }
+        let baseline = past.iter().sum::<f64>() / past.len() as f64;
+        if baseline == 0.0 {
+            continue;
+        }
+
+        let change = (value - baseline) / baseline;
+        if change.abs() > thresholds.for_metric(metric) {
+            anomalies.push(Anomaly {
+                metric: metric.to_string(),
+                value,
+                baseline,
+                change,
+            });
+        }
+    }
+
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(metrics: &RunMetrics) -> IngestionRun {
+        IngestionRun {
+            id: 0,
+            source_id: "nsw_sales".to_string(),
+            status: "completed".to_string(),
+            started_at: chrono::Utc::now().naive_utc(),
+            completed_at: None,
+            records_fetched: 0,
+            records_inserted: 0,
+            records_updated: 0,
+            records_skipped: 0,
+            error_message: None,
+            progress: None,
+            metrics: Some(serde_json::to_value(metrics).unwrap()),
+            anomalies: None,
+            raw_files: None,
+            parse_errors: None,
+        }
+    }
+
+    fn metrics(parsed: u64, inserted: u64, price: f64, match_rate: f64) -> RunMetrics {
+        RunMetrics {
+            records_parsed: parsed,
+            records_inserted: inserted,
+            median_price: Some(price),
+            rental_match_rate: Some(match_rate),
+            rental_matches: None,
+            drift: None,
+            throttle: None,
+            legacy_yield_filter: None,
+            validation: None,
+        }
+    }
+
+    fn steady_history() -> Vec<IngestionRun> {
+        [
+            metrics(10_000, 1_000, 800_000.0, 0.80),
+            metrics(10_400, 1_100, 820_000.0, 0.82),
+            metrics(9_600, 900, 780_000.0, 0.78),
+        ]
+        .iter()
+        .map(run)
+        .collect()
+    }
+
+    #[test]
+    fn test_normal_run_has_no_anomalies() {
+        let current = metrics(10_200, 1_050, 810_000.0, 0.79);
+        let anomalies = detect_anomalies(&current, &steady_history(), &Default::default());
+        assert!(anomalies.is_empty(), "{:?}", anomalies);
+    }
+
+    #[test]
+    fn test_drop_in_inserted_rows_is_flagged() {
+        let current = metrics(10_000, 100, 800_000.0, 0.80);
+        let anomalies = detect_anomalies(&current, &steady_history(), &Default::default());
+
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].metric, "records_inserted");
+        assert_eq!(anomalies[0].baseline, 1_000.0);
+        assert!((anomalies[0].change + 0.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_halved_median_price_is_flagged() {
+        let current = metrics(10_000, 1_000, 400_000.0, 0.80);
+        let anomalies = detect_anomalies(&current, &steady_history(), &Default::default());
+
+        let metrics: Vec<_> = anomalies.iter().map(|a| a.metric.as_str()).collect();
+        assert_eq!(metrics, vec!["median_price"]);
+    }
+
+    #[test]
+    fn test_short_history_is_not_compared() {
+        let current = metrics(0, 0, 1.0, 0.0);
+        let history = &steady_history()[..2];
+        assert!(detect_anomalies(&current, history, &Default::default()).is_empty());
+    }
+
+    #[test]
+    fn test_only_window_runs_form_the_baseline() {
+        // An old run with very different numbers falls outside the window
+        let mut history = steady_history();
+        history.push(run(&metrics(100, 10, 100_000.0, 0.1)));
+        let thresholds = AnomalyThresholds {
+            window: 3,
+            ..Default::default()
+        };
+
+        let current = metrics(10_000, 1_000, 800_000.0, 0.80);
+        assert!(detect_anomalies(&current, &history, &thresholds).is_empty());
+    }
+
+    #[test]
+    fn test_metrics_missing_from_source_are_skipped() {
+        // Rental runs have no prices or match rate
+        let rentals = |parsed| RunMetrics {
+            records_parsed: parsed,
+            records_inserted: parsed,
+            ..Default::default()
+        };
+        let history: Vec<_> = [rentals(500), rentals(520), rentals(480)]
+            .iter()
+            .map(run)
+            .collect();
+
+        assert!(detect_anomalies(&rentals(505), &history, &Default::default()).is_empty());
+        assert_eq!(
+            detect_anomalies(&rentals(50), &history, &Default::default()).len(),
+            2
+        );
+    }
+}
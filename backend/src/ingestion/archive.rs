@@ -0,0 +1,353 @@
+//! Raw file archive - keeps fetched source files so records can be re-parsed later
+//!
+//! Files are stored as `{root}/{source_id}/{YYYY-MM-DD}/{filename}`. The path
+//! relative to the root is the archive key recorded against each property.
+//! Days older than the retention period are removed by `prune`; a property
+//! whose file has gone can no longer be refreshed from it.
+
+use crate::ingestion::validate::sha256_hex;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::{info, warn};
+
+/// Default archive location when ARCHIVE_DIR is not set
+pub const DEFAULT_ARCHIVE_DIR: &str = "/tmp/real_estate_ingestion/archive";
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone)]
+pub struct RawArchive {
+    root: PathBuf,
+    /// Days each day's files are kept; None keeps them forever
+    retention_days: Option<u32>,
+}
+
+/// A fetched file kept in the archive; stored in ingestion_runs.raw_files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedFile {
+    pub key: String,
+    pub sha256: String,
+    pub bytes: u64,
+}
+
+impl RawArchive {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        RawArchive {
+            root: root.into(),
+            retention_days: None,
+        }
+    }
+
+    pub fn with_retention_days(mut self, days: u32) -> Self {
+        self.retention_days = Some(days);
+        self
+    }
+
+    /// Archive rooted at ARCHIVE_DIR, pruned after ARCHIVE_RETENTION_DAYS
+    /// (unset or 0 keeps everything)
+    pub fn from_env() -> Self {
+        let archive =
+            Self::new(env::var("ARCHIVE_DIR").unwrap_or_else(|_| DEFAULT_ARCHIVE_DIR.to_string()));
+        match env::var("ARCHIVE_RETENTION_DAYS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+        {
+            Some(days) if days > 0 => archive.with_retention_days(days),
+            _ => archive,
+        }
+    }
+
+    /// Copy `file` into the archive and return its key
+    pub fn store(&self, source_id: &str, date: NaiveDate, file: &Path) -> Result<String> {
+        let filename = file
+            .file_name()
+            .and_then(|f| f.to_str())
+            .ok_or_else(|| anyhow!("Cannot archive {:?}: no file name", file))?;
+        let (key, target) = self.target(source_id, date, filename)?;
+
+        // A replayed file may already be the archived one
+        if !same_file(file, &target) {
+            fs::copy(file, &target)?;
+        }
+        info!("Archived {:?} as {}", file, key);
+
+        Ok(key)
+    }
+
+    /// Copy `file` into the archive, hashing the archived copy
+    pub fn archive_file(
+        &self,
+        source_id: &str,
+        date: NaiveDate,
+        file: &Path,
+    ) -> Result<ArchivedFile> {
+        let key = self.store(source_id, date, file)?;
+        self.describe(key)
+    }
+
+    /// Write `bytes` into the archive as `filename`
+    pub fn archive_bytes(
+        &self,
+        source_id: &str,
+        date: NaiveDate,
+        filename: &str,
+        bytes: &[u8],
+    ) -> Result<ArchivedFile> {
+        let (key, target) = self.target(source_id, date, filename)?;
+        fs::write(&target, bytes)?;
+        info!("Archived {} bytes as {}", bytes.len(), key);
+        self.describe(key)
+    }
+
+    /// Path of an archived file, or None if the key is invalid or the file is gone
+    pub fn resolve(&self, key: &str) -> Option<PathBuf> {
+        self.path_for(key).filter(|path| path.is_file())
+    }
+
+    /// Key of a file inside the archive; None for paths elsewhere
+    pub fn key_of(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let key = relative
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?
+            .join("/");
+        self.path_for(&key).map(|_| key)
+    }
+
+    /// Remove the days older than the retention period and return their
+    /// `{source_id}/{YYYY-MM-DD}` keys; nothing is removed without a retention
+    /// period. Directories not named for a date are left alone.
+    pub fn prune(&self, today: NaiveDate) -> Result<Vec<String>> {
+        let Some(days) = self.retention_days else {
+            return Ok(Vec::new());
+        };
+        let cutoff = today - Duration::days(days as i64);
+
+        let mut removed = Vec::new();
+        let sources = match fs::read_dir(&self.root) {
+            Ok(sources) => sources,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(removed),
+            Err(e) => return Err(e.into()),
+        };
+        for source in sources {
+            let source = source?;
+            if !source.file_type()?.is_dir() {
+                continue;
+            }
+            for day in fs::read_dir(source.path())? {
+                let day = day?;
+                let date = day
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| NaiveDate::parse_from_str(name, DATE_FORMAT).ok());
+                if !day.file_type()?.is_dir() || date.is_none_or(|date| date >= cutoff) {
+                    continue;
+                }
+
+                let key = format!(
+                    "{}/{}",
+                    source.file_name().to_string_lossy(),
+                    day.file_name().to_string_lossy()
+                );
+                match fs::remove_dir_all(day.path()) {
+                    Ok(()) => removed.push(key),
+                    Err(e) => warn!("Failed to prune archived {}: {}", key, e),
+                }
+            }
+        }
+        removed.sort();
+        Ok(removed)
+    }
+
+    /// Key and path for `filename` on `date`, with the day's directory created
+    fn target(
+        &self,
+        source_id: &str,
+        date: NaiveDate,
+        filename: &str,
+    ) -> Result<(String, PathBuf)> {
+        let key = format!("{}/{}/{}", source_id, date.format(DATE_FORMAT), filename);
+        let target = self
+            .path_for(&key)
+            .ok_or_else(|| anyhow!("Invalid archive key {}", key))?;
+        fs::create_dir_all(target.parent().unwrap())?;
+        Ok((key, target))
+    }
+
+    fn describe(&self, key: String) -> Result<ArchivedFile> {
+        let path = self.root.join(&key);
+        Ok(ArchivedFile {
+            sha256: sha256_hex(&path)?,
+            bytes: fs::metadata(&path)?.len(),
+            key,
+        })
+    }
+
+    /// Keys are relative paths; anything that could escape the root is rejected
+    fn path_for(&self, key: &str) -> Option<PathBuf> {
+        let relative = Path::new(key);
+        let safe = !key.is_empty()
+            && relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+
+        safe.then(|| self.root.join(relative))
+    }
+}
+
+/// Source and date of a file laid out as `{source_id}/{YYYY-MM-DD}/{filename}`,
+/// whether or not it's still under the archive root
+pub fn archived_source(path: &Path) -> Option<(String, NaiveDate)> {
+    let day = path.parent()?;
+    let date = NaiveDate::parse_from_str(day.file_name()?.to_str()?, DATE_FORMAT).ok()?;
+    let source_id = day.parent()?.file_name()?.to_str()?;
+    Some((source_id.to_string(), date))
+}
+
+/// Name to archive a download from `url` under: its last path segment, or
+/// `fallback` when the URL doesn't end in one
+pub fn file_name_from_url(url: &str, fallback: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let name = match path.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').and_then(|(_, p)| p.rsplit('/').next()),
+        None => Path::new(path).file_name().and_then(|name| name.to_str()),
+    };
+    match name {
+        Some(name) if !name.is_empty() && name != ".." && name != "." => name.to_string(),
+        _ => fallback.to_string(),
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_store_and_resolve() {
+        let source = tempdir().unwrap();
+        let file = source.path().join("nsw_sales.csv");
+        fs::write(&file, "header\nrow\n").unwrap();
+
+        let root = tempdir().unwrap();
+        let archive = RawArchive::new(root.path());
+        let date = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+
+        let key = archive.store("nsw_sales", date, &file).unwrap();
+        assert_eq!(key, "nsw_sales/2024-12-01/nsw_sales.csv");
+
+        let path = archive.resolve(&key).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "header\nrow\n");
+
+        assert!(archive
+            .resolve("nsw_sales/2024-12-02/nsw_sales.csv")
+            .is_none());
+
+        // Storing the archived file itself, as a replay does, leaves it intact
+        assert_eq!(archive.store("nsw_sales", date, &path).unwrap(), key);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "header\nrow\n");
+    }
+
+    #[test]
+    fn test_archived_files_are_hashed_and_traced_back() {
+        let root = tempdir().unwrap();
+        let archive = RawArchive::new(root.path());
+        let date = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+
+        let archived = archive
+            .archive_bytes("nsw_rentals", date, "rental-bond-data.xlsx", b"abc")
+            .unwrap();
+        assert_eq!(
+            archived,
+            ArchivedFile {
+                key: "nsw_rentals/2025-03-04/rental-bond-data.xlsx".to_string(),
+                sha256: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+                    .to_string(),
+                bytes: 3,
+            }
+        );
+
+        let path = archive.resolve(&archived.key).unwrap();
+        assert_eq!(archive.key_of(&path), Some(archived.key.clone()));
+        assert_eq!(archive.key_of(Path::new("/elsewhere/file.xlsx")), None);
+        assert_eq!(
+            archived_source(&path),
+            Some(("nsw_rentals".to_string(), date))
+        );
+        assert_eq!(archived_source(Path::new("/downloads/file.xlsx")), None);
+    }
+
+    #[test]
+    fn test_file_name_from_url() {
+        for (url, name) in [
+            (
+                "https://www.nsw.gov.au/files/2024-12/rental-bond-data.xlsx",
+                "rental-bond-data.xlsx",
+            ),
+            (
+                "s3://mirror/abs/correspondence.csv?versionId=3",
+                "correspondence.csv",
+            ),
+            ("https://data.example.com/", "council_rates"),
+            ("https://data.example.com", "council_rates"),
+            ("/data/rates.csv", "rates.csv"),
+            ("rates.csv", "rates.csv"),
+        ] {
+            assert_eq!(file_name_from_url(url, "council_rates"), name, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_prune_removes_days_past_retention() {
+        let root = tempdir().unwrap();
+        let file = root.path().join("upload.zip");
+        fs::write(&file, "zip").unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+
+        let archive = RawArchive::new(root.path().join("archive"));
+        // Nothing archived yet
+        assert!(archive.prune(day(31)).unwrap().is_empty());
+        for d in [1, 10, 20] {
+            archive.store("nsw_sales", day(d), &file).unwrap();
+        }
+        archive.store("nsw_rentals", day(2), &file).unwrap();
+        fs::create_dir_all(root.path().join("archive/nsw_sales/notes")).unwrap();
+
+        // No retention period keeps everything
+        assert!(archive.prune(day(31)).unwrap().is_empty());
+
+        let archive = archive.with_retention_days(20);
+        assert_eq!(
+            archive.prune(day(30)).unwrap(),
+            ["nsw_rentals/2025-01-02", "nsw_sales/2025-01-01"]
+        );
+        assert!(archive.resolve("nsw_sales/2025-01-10/upload.zip").is_some());
+        assert!(archive.resolve("nsw_sales/2025-01-01/upload.zip").is_none());
+        assert!(root.path().join("archive/nsw_sales/notes").is_dir());
+    }
+
+    #[test]
+    fn test_resolve_rejects_paths_outside_root() {
+        let archive = RawArchive::new("/srv/archive");
+
+        for key in ["", "../secrets", "/etc/passwd", "nsw_sales/../../x"] {
+            assert!(
+                archive.path_for(key).is_none(),
+                "{} should be rejected",
+                key
+            );
+        }
+    }
+}
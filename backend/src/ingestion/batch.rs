@@ -0,0 +1,162 @@
+//! Fixed-size batches over a stream of parsed records, so a large file is
+//! parsed, enriched and written a batch at a time instead of all at once
+
+use anyhow::Result;
+use std::env;
+
+/// Records per batch when INGEST_BATCH_SIZE is not set
+pub const DEFAULT_INGEST_BATCH_SIZE: usize = 10_000;
+
+/// Batch size from INGEST_BATCH_SIZE; 0 or unparseable falls back to the default
+pub fn batch_size_from_env() -> usize {
+    env::var("INGEST_BATCH_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&size| size > 0)
+        .unwrap_or(DEFAULT_INGEST_BATCH_SIZE)
+}
+
+/// Batches of up to `size` items from `items`, stopping after `limit` items
+/// when one is given
+/// An error ends the batches; items gathered before it are dropped with it.
+pub fn batches<I, T>(items: I, size: usize, limit: Option<usize>) -> Batches<I>
+where
+    I: Iterator<Item = Result<T>>,
+{
+    Batches {
+        items,
+        size: size.max(1),
+        remaining: limit,
+        failed: false,
+    }
+}
+
+pub struct Batches<I> {
+    items: I,
+    size: usize,
+    /// Items left before the limit; None when there is none
+    remaining: Option<usize>,
+    failed: bool,
+}
+
+impl<I, T> Iterator for Batches<I>
+where
+    I: Iterator<Item = Result<T>>,
+{
+    type Item = Result<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let take = self.remaining.map_or(self.size, |r| r.min(self.size));
+        let mut batch = Vec::with_capacity(take);
+        // Never pulls past the limit, so a lazy source stops reading there
+        while batch.len() < take {
+            match self.items.next() {
+                Some(Ok(item)) => batch.push(item),
+                Some(Err(e)) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+                None => break,
+            }
+        }
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= batch.len();
+        }
+        (!batch.is_empty()).then_some(Ok(batch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::parse::NswSalesReader;
+    use crate::ingestion::PropertyRecord;
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// `rows` valid NSW sales rows, each a different house
+    fn write_sales_csv(path: &Path, rows: usize) {
+        let mut file = fs::File::create(path).unwrap();
+        writeln!(
+            file,
+            "Property ID,Property unit number,Property house number,Property street name,\
+             Property locality,Property post code,Purchase price,Settlement date,\
+             Contract date,Nature of property"
+        )
+        .unwrap();
+        for i in 0..rows {
+            writeln!(
+                file,
+                "{},,{},Smith Street,Sydney,2000,{},15/06/2023,01/05/2023,Residential - House",
+                100_000 + i,
+                i + 1,
+                500_000 + i
+            )
+            .unwrap();
+        }
+    }
+
+    /// Batch sizes as a sink saw them, standing in for enrich and write
+    #[derive(Default)]
+    struct CountingSink {
+        batches: Vec<usize>,
+    }
+
+    impl CountingSink {
+        fn write(&mut self, batch: Vec<PropertyRecord>) {
+            self.batches.push(batch.len());
+        }
+    }
+
+    #[test]
+    fn test_large_csv_in_small_batches() {
+        let temp = tempdir().unwrap();
+        let first = temp.path().join("first.csv");
+        let second = temp.path().join("second.csv");
+        write_sales_csv(&first, 3_000);
+        write_sales_csv(&second, 2_050);
+        let paths = [first, second];
+
+        // Batches run across the file boundary
+        let mut reader = NswSalesReader::new(&paths, "nsw_sales");
+        let mut sink = CountingSink::default();
+        for batch in batches(reader.by_ref(), 1_000, None) {
+            sink.write(batch.unwrap());
+        }
+        assert_eq!(sink.batches, vec![1_000, 1_000, 1_000, 1_000, 1_000, 50]);
+        assert_eq!(reader.records(), 5_050);
+        assert!(reader.errors().is_empty());
+
+        // The limit stops reading, not just writing
+        let mut reader = NswSalesReader::new(&paths, "nsw_sales");
+        let mut sink = CountingSink::default();
+        for batch in batches(reader.by_ref(), 400, Some(1_000)) {
+            sink.write(batch.unwrap());
+        }
+        assert_eq!(sink.batches, vec![400, 400, 200]);
+        assert_eq!(reader.records(), 1_000);
+    }
+
+    #[test]
+    fn test_error_ends_batches() {
+        let items = vec![
+            Ok(1),
+            Ok(2),
+            Ok(3),
+            Err(anyhow::anyhow!("unreadable")),
+            Ok(5),
+        ];
+        let mut batches = batches(items.into_iter(), 2, None);
+        assert_eq!(batches.next().unwrap().unwrap(), vec![1, 2]);
+        assert!(batches.next().unwrap().is_err());
+        assert!(batches.next().is_none());
+
+        let empty = std::iter::empty::<Result<u8>>();
+        assert!(super::batches(empty, 2, None).next().is_none());
+    }
+}
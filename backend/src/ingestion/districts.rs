@@ -0,0 +1,86 @@
+//! NSW Valuer General districts - the council area code each sale in the
+//! bulk sales data is filed under
+//!
+//! The codes are three digits, "001" and on. Names come from the table below,
+//! which covers the districts the app is mostly used for; a code missing from
+//! it is shown as given rather than dropped.
+
+/// District codes and the councils they cover
+const DISTRICTS: &[(&str, &str)] = &[
+    ("001", "City of Sydney"),
+    ("002", "Albury City"),
+    ("003", "Armidale Regional"),
+    ("004", "Ballina Shire"),
+    ("005", "Bathurst Regional"),
+    ("006", "Bayside"),
+    ("007", "Blacktown City"),
+    ("008", "Blue Mountains City"),
+    ("009", "Burwood"),
+    ("010", "Camden"),
+    ("011", "Campbelltown City"),
+    ("012", "Canterbury-Bankstown"),
+    ("013", "Central Coast"),
+    ("014", "Cumberland City"),
+    ("015", "Georges River"),
+    ("016", "Hornsby Shire"),
+    ("017", "Inner West"),
+    ("018", "Ku-ring-gai"),
+    ("019", "Lake Macquarie City"),
+    ("020", "Liverpool City"),
+    ("021", "Newcastle City"),
+    ("022", "North Sydney"),
+    ("023", "Northern Beaches"),
+    ("024", "Parramatta City"),
+    ("025", "Penrith City"),
+    ("026", "Randwick City"),
+    ("027", "Ryde City"),
+    ("028", "Sutherland Shire"),
+    ("029", "The Hills Shire"),
+    ("030", "Waverley"),
+    ("031", "Willoughby City"),
+    ("032", "Wollongong City"),
+    ("033", "Woollahra"),
+];
+
+/// A district code as its three digits: "1" and " 001 " are "001"; None when
+/// blank or not a number
+pub fn normalize_district(code: &str) -> Option<String> {
+    let code = code.trim();
+    if code.is_empty() || code.len() > 3 || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{:0>3}", code))
+}
+
+/// The council a district code names, or the code itself when it isn't known
+pub fn district_name(code: &str) -> String {
+    let normalized = normalize_district(code);
+    DISTRICTS
+        .iter()
+        .find(|(known, _)| Some(*known) == normalized.as_deref())
+        .map_or_else(|| code.to_string(), |(_, name)| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_district() {
+        assert_eq!(normalize_district("001"), Some("001".to_string()));
+        assert_eq!(normalize_district(" 1 "), Some("001".to_string()));
+        assert_eq!(normalize_district("215"), Some("215".to_string()));
+        for code in ["", "  ", "1001", "A12", "1.0"] {
+            assert_eq!(normalize_district(code), None, "{:?}", code);
+        }
+    }
+
+    #[test]
+    fn test_district_name() {
+        assert_eq!(district_name("001"), "City of Sydney");
+        assert_eq!(district_name("33"), "Woollahra");
+        // Unknown codes pass through as given
+        assert_eq!(district_name("999"), "999");
+        assert_eq!(district_name("X1"), "X1");
+    }
+}
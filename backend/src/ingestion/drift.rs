@@ -0,0 +1,311 @@
+//! Drift between a property's stored values and the update that replaces them
+//!
+//! A large price change on an update is either a resale we didn't recognise
+//! as one or bad data. Each update's relative change in price, rent and yield
+//! is bucketed into histograms reported with the run, and changes above a
+//! threshold are sampled into ingestion_errors as `suspicious_update`.
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::ops::AddAssign;
+
+/// Upper bounds of the histogram buckets, as a fraction of the old value
+/// A final bucket takes anything above the last bound
+pub const DRIFT_BUCKET_BOUNDS: [f64; 6] = [0.01, 0.05, 0.10, 0.25, 0.50, 1.00];
+
+pub const DEFAULT_DRIFT_THRESHOLD: f64 = 0.5;
+pub const DEFAULT_DRIFT_MAX_SAMPLES: usize = 100;
+
+/// How one field moved between the stored row and the update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Drift {
+    /// Missing before and after, or the same value
+    Unchanged,
+    /// Missing (or zero) before, so there is no base to measure against
+    Appeared,
+    /// Present before, missing after
+    Cleared,
+    /// Relative change from the old value; -0.25 is a 25% drop
+    Changed(f64),
+}
+
+impl Drift {
+    pub fn between(before: Option<f64>, after: Option<f64>) -> Self {
+        match (before, after) {
+            (None, None) => Drift::Unchanged,
+            (Some(_), None) => Drift::Cleared,
+            (None, Some(_)) => Drift::Appeared,
+            (Some(b), Some(a)) if a == b => Drift::Unchanged,
+            (Some(0.0), Some(_)) => Drift::Appeared,
+            (Some(b), Some(a)) => Drift::Changed((a - b) / b.abs()),
+        }
+    }
+
+    /// The relative change when it is over `threshold` either way
+    pub fn exceeds(&self, threshold: f64) -> Option<f64> {
+        match *self {
+            Drift::Changed(change) if change.abs() > threshold => Some(change),
+            _ => None,
+        }
+    }
+}
+
+/// Counts of updates by how far one field moved
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DriftHistogram {
+    pub unchanged: u64,
+    pub null_to_value: u64,
+    pub value_to_null: u64,
+    /// Value-to-value changes by size, bucketed on DRIFT_BUCKET_BOUNDS
+    pub changed: [u64; DRIFT_BUCKET_BOUNDS.len() + 1],
+}
+
+impl DriftHistogram {
+    pub fn record(&mut self, drift: Drift) {
+        match drift {
+            Drift::Unchanged => self.unchanged += 1,
+            Drift::Appeared => self.null_to_value += 1,
+            Drift::Cleared => self.value_to_null += 1,
+            Drift::Changed(change) => {
+                let bucket = DRIFT_BUCKET_BOUNDS
+                    .iter()
+                    .position(|bound| change.abs() <= *bound)
+                    .unwrap_or(DRIFT_BUCKET_BOUNDS.len());
+                self.changed[bucket] += 1;
+            }
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.unchanged + self.null_to_value + self.value_to_null + self.changed.iter().sum::<u64>()
+    }
+}
+
+impl AddAssign<&DriftHistogram> for DriftHistogram {
+    fn add_assign(&mut self, other: &DriftHistogram) {
+        self.unchanged += other.unchanged;
+        self.null_to_value += other.null_to_value;
+        self.value_to_null += other.value_to_null;
+        for (count, other) in self.changed.iter_mut().zip(other.changed) {
+            *count += other;
+        }
+    }
+}
+
+/// Drift histograms for the fields watched on update
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub price: DriftHistogram,
+    pub weekly_rent: DriftHistogram,
+    pub rental_yield: DriftHistogram,
+}
+
+impl DriftReport {
+    pub fn record(&mut self, drift: &PropertyDrift) {
+        self.price.record(drift.price);
+        self.weekly_rent.record(drift.weekly_rent);
+        self.rental_yield.record(drift.rental_yield);
+    }
+
+    /// Number of updates recorded
+    pub fn updates(&self) -> u64 {
+        self.price.total()
+    }
+}
+
+impl AddAssign<&DriftReport> for DriftReport {
+    fn add_assign(&mut self, other: &DriftReport) {
+        self.price += &other.price;
+        self.weekly_rent += &other.weekly_rent;
+        self.rental_yield += &other.rental_yield;
+    }
+}
+
+/// The watched fields of one property before and after an update
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PropertyValues {
+    pub price: Option<i64>,
+    pub weekly_rent: Option<i32>,
+    pub rental_yield: Option<Decimal>,
+}
+
+/// How each watched field moved in one update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PropertyDrift {
+    pub before: PropertyValues,
+    pub after: PropertyValues,
+    pub price: Drift,
+    pub weekly_rent: Drift,
+    pub rental_yield: Drift,
+}
+
+impl PropertyDrift {
+    pub fn between(before: PropertyValues, after: PropertyValues) -> Self {
+        let price = |v: Option<i64>| v.map(|p| p as f64);
+        let int = |v: Option<i32>| v.map(f64::from);
+        let dec = |v: Option<Decimal>| v.and_then(|d| d.to_f64());
+
+        PropertyDrift {
+            before,
+            after,
+            price: Drift::between(price(before.price), price(after.price)),
+            weekly_rent: Drift::between(int(before.weekly_rent), int(after.weekly_rent)),
+            rental_yield: Drift::between(dec(before.rental_yield), dec(after.rental_yield)),
+        }
+    }
+
+    /// Fields whose value-to-value change is over `threshold`, with the change
+    pub fn suspicious(&self, threshold: f64) -> Vec<(&'static str, f64)> {
+        [
+            ("price", self.price),
+            ("weekly_rent", self.weekly_rent),
+            ("rental_yield", self.rental_yield),
+        ]
+        .into_iter()
+        .filter_map(|(field, drift)| Some((field, drift.exceeds(threshold)?)))
+        .collect()
+    }
+}
+
+/// When an update is flagged, and how many flags one write keeps
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriftConfig {
+    /// Relative change (0.5 = 50%) above which an update is suspicious
+    pub threshold: f64,
+    /// Suspicious updates recorded per write; the rest are only counted
+    pub max_samples: usize,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        DriftConfig {
+            threshold: DEFAULT_DRIFT_THRESHOLD,
+            max_samples: DEFAULT_DRIFT_MAX_SAMPLES,
+        }
+    }
+}
+
+impl DriftConfig {
+    /// Defaults overridden by DRIFT_FLAG_THRESHOLD and DRIFT_MAX_SAMPLES
+    pub fn from_env() -> Self {
+        let defaults = DriftConfig::default();
+        DriftConfig {
+            threshold: env::var("DRIFT_FLAG_THRESHOLD")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|t: &f64| t.is_finite() && *t >= 0.0)
+                .unwrap_or(defaults.threshold),
+            max_samples: env::var("DRIFT_MAX_SAMPLES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.max_samples),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(price: Option<i64>, rent: Option<i32>, rental_yield: Option<&str>) -> PropertyValues {
+        PropertyValues {
+            price,
+            weekly_rent: rent,
+            rental_yield: rental_yield.map(|y| y.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_null_transitions_are_not_changes() {
+        assert_eq!(Drift::between(None, None), Drift::Unchanged);
+        assert_eq!(Drift::between(None, Some(500.0)), Drift::Appeared);
+        assert_eq!(Drift::between(Some(500.0), None), Drift::Cleared);
+        assert_eq!(Drift::between(Some(0.0), Some(500.0)), Drift::Appeared);
+        assert_eq!(Drift::between(Some(500.0), Some(500.0)), Drift::Unchanged);
+        assert_eq!(
+            Drift::between(Some(500.0), Some(400.0)),
+            Drift::Changed(-0.2)
+        );
+        assert_eq!(Drift::between(None, Some(500.0)).exceeds(0.0), None);
+    }
+
+    #[test]
+    fn test_bucket_counts_over_update_batch() {
+        let batch = [
+            // Price unchanged, rent appears
+            (
+                values(Some(800_000), None, None),
+                values(Some(800_000), Some(600), Some("3.90")),
+            ),
+            // 0.5% and 3% moves
+            (
+                values(Some(800_000), Some(600), None),
+                values(Some(804_000), Some(618), None),
+            ),
+            // 20% price rise; rent cleared
+            (
+                values(Some(500_000), Some(500), Some("5.20")),
+                values(Some(600_000), None, None),
+            ),
+            // Price doubles and a half: over 100%
+            (
+                values(Some(400_000), None, None),
+                values(Some(1_000_000), None, None),
+            ),
+            // Price appears from nothing
+            (values(None, None, None), values(Some(700_000), None, None)),
+            // Exactly on a bound goes in the lower bucket
+            (
+                values(Some(1_000_000), None, None),
+                values(Some(900_000), None, None),
+            ),
+        ];
+
+        let mut report = DriftReport::default();
+        for (before, after) in batch {
+            report.record(&PropertyDrift::between(before, after));
+        }
+
+        assert_eq!(
+            report.price,
+            DriftHistogram {
+                unchanged: 1,
+                null_to_value: 1,
+                value_to_null: 0,
+                changed: [1, 0, 1, 1, 0, 0, 1],
+            }
+        );
+        assert_eq!(
+            report.weekly_rent,
+            DriftHistogram {
+                unchanged: 3,
+                null_to_value: 1,
+                value_to_null: 1,
+                changed: [0, 1, 0, 0, 0, 0, 0],
+            }
+        );
+        assert_eq!(report.rental_yield.null_to_value, 1);
+        assert_eq!(report.rental_yield.value_to_null, 1);
+        assert_eq!(report.updates(), 6);
+
+        let mut merged = report.clone();
+        merged += &report;
+        assert_eq!(merged.price.changed, [2, 0, 2, 2, 0, 0, 2]);
+        assert_eq!(merged.updates(), 12);
+    }
+
+    #[test]
+    fn test_suspicious_fields() {
+        let drift = PropertyDrift::between(
+            values(Some(400_000), Some(500), Some("6.50")),
+            values(Some(1_000_000), Some(520), Some("2.70")),
+        );
+
+        let flagged: Vec<&str> = drift.suspicious(0.5).iter().map(|(f, _)| *f).collect();
+        assert_eq!(flagged, ["price", "rental_yield"]);
+        assert_eq!(drift.suspicious(0.5)[0].1, 1.5);
+        assert!(drift.suspicious(2.0).is_empty());
+    }
+}
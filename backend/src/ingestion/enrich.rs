@@ -1,10 +1,109 @@
 //! Enrichment functions - add calculated/matched data to property records
 
-use crate::ingestion::types::{PropertyRecord, PropertyType, RentalMedian, SourceMetadata};
+use crate::ingestion::geocode::{AddressQuery, GeocoderChain};
+use crate::ingestion::types::{
+    DataQuality, PropertyRecord, PropertyType, RentalLookup, RentalMatchMethod, RentalMedian,
+    SourceMetadata, State,
+};
+use crate::ingestion::utils::normalize_suburb;
+use crate::{calculate_rental_yield, price_per_sqm};
+use crate::format::{format_money, format_yield};
 use anyhow::Result;
-use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
-use tracing::{debug, info};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use tracing::{debug, info, warn};
+
+/// Fewest observations an observation-derived median is built from
+pub const MIN_OBSERVATIONS_FOR_MEDIAN: i64 = 5;
+
+/// Confidence kept by a rent matched from a postcode's median
+pub const MATCHED_RENT_CONFIDENCE: f32 = 0.85;
+
+/// Further confidence kept when the median covers all dwelling types rather
+/// than the property's own
+pub const COMBINED_MEDIAN_CONFIDENCE: f32 = 0.9;
+
+/// Further confidence kept when the median is from another postcode of the
+/// property's suburb
+pub const SUBURB_MATCH_CONFIDENCE: f32 = 0.8;
+
+/// Further confidence kept when the rent is interpolated between the medians
+/// for the bedroom counts either side
+pub const INTERPOLATED_RENT_CONFIDENCE: f32 = 0.75;
+
+/// Further confidence kept when the rent is scaled from the median for the
+/// nearest bedroom count
+pub const NEAREST_BEDROOMS_CONFIDENCE: f32 = 0.7;
+
+/// Default change in rent per bedroom when scaling from the nearest count
+pub const DEFAULT_BEDROOM_ADJUSTMENT: f64 = 0.12;
+
+/// Interpolated rents below this many dollars a week are noise, not a rent
+pub const MIN_INTERPOLATED_RENT: i32 = 50;
+
+/// How properties are matched to rents
+#[derive(Debug, Clone, Copy)]
+pub struct RentalMatching {
+    /// Use the median of bond lodgement observations when there is no official median
+    pub observation_fallback: bool,
+    /// Leave vacant land out of rental matching and yields; loaders also drop
+    /// commercial and other sales with `drop_non_residential`
+    pub residential_only: bool,
+    /// Change in rent per bedroom when scaling from the nearest bedroom count,
+    /// e.g. 0.12 for 12%
+    pub bedroom_adjustment: f64,
+}
+
+impl Default for RentalMatching {
+    fn default() -> Self {
+        RentalMatching {
+            observation_fallback: false,
+            residential_only: true,
+            bedroom_adjustment: DEFAULT_BEDROOM_ADJUSTMENT,
+        }
+    }
+}
+
+impl RentalMatching {
+    /// Observation fallback is enabled by RENTAL_OBSERVATION_FALLBACK=true;
+    /// residential only is on unless RESIDENTIAL_ONLY=false; the bedroom
+    /// adjustment is RENTAL_BEDROOM_ADJUSTMENT, a fraction from 0 to 1
+    pub fn from_env() -> Self {
+        RentalMatching {
+            observation_fallback: env::var("RENTAL_OBSERVATION_FALLBACK")
+                .is_ok_and(|v| v == "true" || v == "1"),
+            residential_only: !env::var("RESIDENTIAL_ONLY").is_ok_and(|v| v == "false" || v == "0"),
+            bedroom_adjustment: env::var("RENTAL_BEDROOM_ADJUSTMENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|adjustment| (0.0..1.0).contains(adjustment))
+                .unwrap_or(DEFAULT_BEDROOM_ADJUSTMENT),
+        }
+    }
+
+    /// Whether a record is matched to a rent and given a yield
+    fn earns_rent(&self, record: &PropertyRecord) -> bool {
+        !(self.residential_only && record.property_type == PropertyType::VacantLand)
+    }
+}
+
+/// Drop commercial and other sales, whose prices a residential rent can't
+/// yield on; vacant land is kept. Returns the records kept and how many went
+pub fn drop_non_residential(records: Vec<PropertyRecord>) -> (Vec<PropertyRecord>, usize) {
+    let total = records.len();
+    let kept: Vec<_> = records
+        .into_iter()
+        .filter(|r| !matches!(r.property_type, PropertyType::Commercial | PropertyType::Other))
+        .collect();
+    let dropped = total - kept.len();
+    if dropped > 0 {
+        debug!("Dropped {} non-residential records", dropped);
+    }
+    (kept, dropped)
+}
 
 /// Estimate bedrooms based on property characteristics
 /// Pure function - no side effects
@@ -21,7 +120,7 @@ pub fn estimate_bedrooms(record: PropertyRecord) -> PropertyRecord {
 
         // Houses - generally larger
         (PropertyType::House, Some(price)) if price < 500_000 => 2,
-        (PropertyType::House, Some(price)) if price < 800_000 => 3,
+        (PropertyType::House, Some(price)) if price <= 800_000 => 3,
         (PropertyType::House, Some(price)) if price < 1_200_000 => 4,
         (PropertyType::House, _) => 4, // Default for houses
 
@@ -44,6 +143,7 @@ pub fn estimate_bedrooms(record: PropertyRecord) -> PropertyRecord {
     PropertyRecord {
         bedrooms: Some(estimated),
         source_metadata: SourceMetadata {
+            is_bedrooms_estimated: true,
             confidence_score: record.source_metadata.confidence_score * 0.7, // Reduce confidence
             ..record.source_metadata
         },
@@ -52,84 +152,632 @@ pub fn estimate_bedrooms(record: PropertyRecord) -> PropertyRecord {
 }
 
 /// Match property to rental data by postcode + bedrooms
-/// Requires database access to query rental_medians table
-pub async fn match_rental(record: PropertyRecord, db: &PgPool) -> Result<PropertyRecord> {
+/// The median for the property's dwelling type is preferred; the all
+/// dwellings median, or the observation fallback, costs confidence. Failing
+/// those, the chain falls back to another postcode of the suburb, then to a
+/// rent interpolated from the postcode's other bedroom counts, each for less
+/// confidence again.
+/// Requires database access to query rental_medians table; a batch is
+/// matched with `RentalIndex` instead
+pub async fn match_rental(
+    record: PropertyRecord,
+    db: &PgPool,
+    matching: RentalMatching,
+) -> Result<PropertyRecord> {
+    let Some(lookup) = rental_lookup(&record, matching) else {
+        return Ok(record);
+    };
+    let dwelling_type = dwelling_type(&record);
+
+    // Query most recent rental median for this postcode + bedroom combo,
+    // for the property's dwelling type where the bond data has one
+    let rental = match postcode_rental_median(db, &lookup, dwelling_type.clone()).await? {
+        Some(median) => Some((median, RentalMatchMethod::Postcode)),
+        None if matching.observation_fallback => observation_rental_median(db, &lookup)
+            .await?
+            .map(|median| (median, RentalMatchMethod::Observations)),
+        None => None,
+    };
+    let rental = match rental {
+        None => suburb_rental_median(db, &lookup, &record.suburb, dwelling_type.clone())
+            .await?
+            .map(|median| (median, RentalMatchMethod::Suburb)),
+        found => found,
+    };
+    let rental = match rental {
+        None => {
+            let medians = other_bedrooms_rental_medians(db, &lookup, dwelling_type).await?;
+            let medians: Vec<_> = medians.iter().collect();
+            interpolated_median(&lookup, &medians, matching.bedroom_adjustment)
+        }
+        found => found,
+    };
+
+    Ok(with_rental(record, &lookup, rental))
+}
+
+/// The key a record is matched to a rent on, None when it isn't matched
+fn rental_lookup(record: &PropertyRecord, matching: RentalMatching) -> Option<RentalLookup> {
     if record.weekly_rent.is_some() {
-        return Ok(record); // Already has rental data
+        return None; // Already has rental data
+    }
+    if !matching.earns_rent(record) {
+        return None;
     }
 
     // Need postcode and bedrooms to match
-    let (postcode, bedrooms) = match (&record.postcode, record.bedrooms) {
-        (Some(pc), Some(br)) => (pc, br),
-        _ => {
-            debug!(
-                "Cannot match rental for {} - missing postcode or bedrooms",
-                record.address
-            );
-            return Ok(record);
+    let lookup = RentalLookup::from_record(record);
+    if lookup.is_none() {
+        debug!(
+            "Cannot match rental for {} - missing postcode or bedrooms",
+            record.address
+        );
+    }
+    lookup
+}
+
+/// The dwelling type the bond data reports medians for, if the record is one
+fn dwelling_type(record: &PropertyRecord) -> Option<PropertyType> {
+    match record.property_type {
+        PropertyType::House | PropertyType::Unit | PropertyType::Townhouse => {
+            Some(record.property_type.clone())
         }
+        _ => None,
+    }
+}
+
+/// Confidence kept by a rent matched this way from this median
+fn match_confidence(median: &RentalMedian, method: RentalMatchMethod) -> f32 {
+    let dwelling = match median.dwelling_type {
+        Some(_) => 1.0,
+        None => COMBINED_MEDIAN_CONFIDENCE,
+    };
+    let fallback = match method {
+        RentalMatchMethod::Postcode | RentalMatchMethod::Observations => 1.0,
+        RentalMatchMethod::Suburb => SUBURB_MATCH_CONFIDENCE,
+        RentalMatchMethod::Interpolated => INTERPOLATED_RENT_CONFIDENCE,
+        RentalMatchMethod::NearestBedrooms => NEAREST_BEDROOMS_CONFIDENCE,
     };
+    MATCHED_RENT_CONFIDENCE * dwelling * fallback
+}
 
-    // Query most recent rental median for this postcode + bedroom combo
-    let rental = sqlx::query_as::<_, RentalMedian>(
-        r#"
-        SELECT state, postcode, suburb, bedrooms, median_weekly_rent, sample_size, period
-        FROM rental_medians
-        WHERE state = $1 AND postcode = $2 AND bedrooms = $3
-        ORDER BY period DESC
-        LIMIT 1
-        "#,
-    )
-    .bind(&record.state)
-    .bind(postcode)
-    .bind(bedrooms)
-    .fetch_optional(db)
-    .await?;
+/// Apply a matched median to a record, or leave it be when there is none
+fn with_rental(
+    record: PropertyRecord,
+    lookup: &RentalLookup,
+    rental: Option<(RentalMedian, RentalMatchMethod)>,
+) -> PropertyRecord {
+    let (postcode, bedrooms) = (&lookup.postcode, lookup.bedrooms);
 
     match rental {
-        Some(rental) => {
+        Some((rental, method)) => {
             debug!(
-                "Matched rental for {}: ${}/week (postcode: {}, bedrooms: {})",
-                record.address, rental.median_weekly_rent, postcode, bedrooms
+                "Matched rental for {} by {:?}: {}/week (postcode: {}, bedrooms: {}, type: {:?})",
+                record.address,
+                method,
+                format_money(rental.median_weekly_rent.into()),
+                rental.postcode,
+                rental.bedrooms,
+                rental.dwelling_type
             );
 
-            Ok(PropertyRecord {
+            PropertyRecord {
                 weekly_rent: Some(rental.median_weekly_rent),
                 source_metadata: SourceMetadata {
                     is_rental_estimated: true,
-                    confidence_score: record.source_metadata.confidence_score * 0.85,
+                    rental_period: Some(rental.period),
+                    rental_match_method: Some(method),
+                    confidence_score: record.source_metadata.confidence_score
+                        * match_confidence(&rental, method),
                     ..record.source_metadata
                 },
                 ..record
-            })
+            }
         }
         None => {
             debug!(
                 "No rental data found for {} (postcode: {}, bedrooms: {})",
                 record.address, postcode, bedrooms
             );
-            Ok(record)
+            record
         }
     }
 }
 
+/// A rent worked out for a bedroom count without a median of its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterpolatedRent {
+    pub weekly_rent: i32,
+    /// The nearest fewer bedrooms with a rent, if used
+    pub lower: Option<i32>,
+    /// The nearest more bedrooms with a rent, if used
+    pub upper: Option<i32>,
+}
+
+/// Weekly rent for a bedroom count from the rents (by bedrooms) of the counts
+/// either side of it: interpolated linearly between the two, or the one there
+/// is moved `adjustment` per bedroom, e.g. 12% more per extra bedroom
+/// Studios neither get nor give a rent, and one under MIN_INTERPOLATED_RENT
+/// is dropped as noise. Pure function - no side effects
+pub fn interpolate_rent(
+    rents: &BTreeMap<i32, i32>,
+    bedrooms: i32,
+    adjustment: f64,
+) -> Option<InterpolatedRent> {
+    if bedrooms < 1 {
+        return None;
+    }
+
+    let lower = rents.range(1..bedrooms).next_back();
+    let upper = rents.range(bedrooms + 1..).next();
+    let rent = match (lower, upper) {
+        (Some((&low, &low_rent)), Some((&high, &high_rent))) => {
+            let share = f64::from(bedrooms - low) / f64::from(high - low);
+            f64::from(low_rent) + share * f64::from(high_rent - low_rent)
+        }
+        (Some((&low, &rent)), None) => f64::from(rent) * (1.0 + adjustment).powi(bedrooms - low),
+        (None, Some((&high, &rent))) => f64::from(rent) * (1.0 - adjustment).powi(high - bedrooms),
+        (None, None) => return None,
+    };
+
+    let weekly_rent = rent.round() as i32;
+    (weekly_rent >= MIN_INTERPOLATED_RENT).then_some(InterpolatedRent {
+        weekly_rent,
+        lower: lower.map(|(&bedrooms, _)| bedrooms),
+        upper: upper.map(|(&bedrooms, _)| bedrooms),
+    })
+}
+
+/// A median for the lookup's bedroom count made by `interpolate_rent` from the
+/// postcode's medians for other counts, one per count
+/// It covers all dwellings unless every median it came from is typed, and is
+/// as old as the oldest of them.
+fn interpolated_median(
+    lookup: &RentalLookup,
+    medians: &[&RentalMedian],
+    adjustment: f64,
+) -> Option<(RentalMedian, RentalMatchMethod)> {
+    let by_bedrooms: HashMap<i32, &RentalMedian> =
+        medians.iter().map(|median| (median.bedrooms, *median)).collect();
+    let rents: BTreeMap<i32, i32> = by_bedrooms
+        .iter()
+        .map(|(&bedrooms, median)| (bedrooms, median.median_weekly_rent))
+        .collect();
+    let rent = interpolate_rent(&rents, lookup.bedrooms, adjustment)?;
+
+    let used: Vec<&RentalMedian> = [rent.lower, rent.upper]
+        .into_iter()
+        .flatten()
+        .map(|bedrooms| by_bedrooms[&bedrooms])
+        .collect();
+    let method = match used.len() {
+        2 => RentalMatchMethod::Interpolated,
+        _ => RentalMatchMethod::NearestBedrooms,
+    };
+    let dwelling_type = used
+        .iter()
+        .map(|median| median.dwelling_type.clone())
+        .reduce(|a, b| if a == b { a } else { None })
+        .flatten();
+
+    Some((
+        RentalMedian {
+            state: lookup.state,
+            postcode: lookup.postcode.clone(),
+            suburb: None,
+            bedrooms: lookup.bedrooms,
+            median_weekly_rent: rent.weekly_rent,
+            sample_size: None,
+            dwelling_type,
+            period: used.iter().map(|median| median.period).min()?,
+        },
+        method,
+    ))
+}
+
+/// Suburb index key: state, normalized suburb, bedrooms and dwelling type
+type SuburbKey = (State, String, i32, Option<PropertyType>);
+
+/// The latest rental medians, held in memory so a batch of records is matched
+/// with one query rather than one or more per record
+/// Each median keeps the period it describes.
+#[derive(Debug, Default)]
+pub struct RentalIndex {
+    /// Latest median per lookup key and dwelling type, None meaning all dwellings
+    medians: HashMap<(RentalLookup, Option<PropertyType>), RentalMedian>,
+    /// Latest observation-derived median per lookup key, when the fallback is on
+    observed: HashMap<RentalLookup, RentalMedian>,
+    /// Latest median per suburb across its postcodes, for medians that name one
+    suburbs: HashMap<SuburbKey, RentalMedian>,
+    /// Bedroom counts with a median, per state and postcode
+    bedrooms: HashMap<(State, String), Vec<i32>>,
+}
+
+/// A row of the index query: a median, and whether it came from observations
+#[derive(sqlx::FromRow)]
+struct IndexedMedian {
+    #[sqlx(flatten)]
+    median: RentalMedian,
+    observed: bool,
+}
+
+/// Whether `a` is preferred to `b` among medians for the same key: the later
+/// period, then the larger sample, then the lower postcode
+fn is_preferred(a: &RentalMedian, b: &RentalMedian) -> bool {
+    let rank = |m: &RentalMedian| (m.period, m.sample_size, Reverse(m.postcode.clone()));
+    rank(a) > rank(b)
+}
+
+impl RentalIndex {
+    /// Load the latest median for every key, plus the observation-derived
+    /// ones when `matching` falls back to them
+    pub async fn load(db: &PgPool, matching: RentalMatching) -> Result<Self, sqlx::Error> {
+        let rows = sqlx::query_as::<_, IndexedMedian>(
+            r#"
+            SELECT * FROM (
+                SELECT DISTINCT ON (state, postcode, bedrooms, dwelling_type)
+                    state, postcode, suburb, bedrooms, median_weekly_rent, sample_size,
+                    dwelling_type, period, FALSE AS observed
+                FROM rental_medians
+                ORDER BY state, postcode, bedrooms, dwelling_type,
+                    period DESC, sample_size DESC NULLS LAST
+            ) medians
+            UNION ALL
+            SELECT * FROM (
+                SELECT DISTINCT ON (state, postcode, bedrooms)
+                    state,
+                    postcode,
+                    NULL::varchar AS suburb,
+                    bedrooms,
+                    (percentile_cont(0.5) WITHIN GROUP (ORDER BY weekly_rent))::int
+                        AS median_weekly_rent,
+                    COUNT(*)::int AS sample_size,
+                    NULL::property_type_enum AS dwelling_type,
+                    period,
+                    TRUE AS observed
+                FROM rental_observations
+                WHERE $1 AND bedrooms IS NOT NULL
+                GROUP BY state, postcode, bedrooms, period
+                HAVING COUNT(*) >= $2
+                ORDER BY state, postcode, bedrooms, period DESC
+            ) observations
+            "#,
+        )
+        .bind(matching.observation_fallback)
+        .bind(MIN_OBSERVATIONS_FOR_MEDIAN)
+        .fetch_all(db)
+        .await?;
+
+        let mut index = RentalIndex::default();
+        for IndexedMedian { median, observed } in rows {
+            let lookup = RentalLookup {
+                state: median.state,
+                postcode: median.postcode.clone(),
+                bedrooms: median.bedrooms,
+            };
+            if observed {
+                index.observed.insert(lookup, median);
+                continue;
+            }
+
+            let bedrooms = index
+                .bedrooms
+                .entry((median.state, median.postcode.clone()))
+                .or_default();
+            if !bedrooms.contains(&median.bedrooms) {
+                bedrooms.push(median.bedrooms);
+            }
+            let suburb = median.suburb.as_deref().map(normalize_suburb);
+            if let Some(suburb) = suburb.filter(|suburb| !suburb.is_empty()) {
+                let key = (
+                    median.state,
+                    suburb,
+                    median.bedrooms,
+                    median.dwelling_type.clone(),
+                );
+                if !matches!(index.suburbs.get(&key), Some(kept) if !is_preferred(&median, kept)) {
+                    index.suburbs.insert(key, median.clone());
+                }
+            }
+            index
+                .medians
+                .insert((lookup, median.dwelling_type.clone()), median);
+        }
+        debug!(
+            "Loaded {} rental medians and {} observation medians",
+            index.medians.len(),
+            index.observed.len()
+        );
+
+        Ok(index)
+    }
+
+    /// Number of medians held, observation-derived ones included
+    pub fn len(&self) -> usize {
+        self.medians.len() + self.observed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Latest median for a lookup key and dwelling type, None meaning the
+    /// median across all dwellings
+    pub fn get(
+        &self,
+        lookup: &RentalLookup,
+        dwelling_type: Option<PropertyType>,
+    ) -> Option<&RentalMedian> {
+        self.medians.get(&(lookup.clone(), dwelling_type))
+    }
+
+    /// The dwelling type's median for a lookup key, else the all dwellings one
+    fn postcode_median(
+        &self,
+        lookup: &RentalLookup,
+        dwelling_type: Option<PropertyType>,
+    ) -> Option<&RentalMedian> {
+        dwelling_type
+            .and_then(|dwelling_type| self.get(lookup, Some(dwelling_type)))
+            .or_else(|| self.get(lookup, None))
+    }
+
+    /// The suburb's median for the bedroom count, from any of its postcodes
+    fn suburb_median(
+        &self,
+        lookup: &RentalLookup,
+        suburb: &str,
+        dwelling_type: Option<PropertyType>,
+    ) -> Option<&RentalMedian> {
+        let suburb = normalize_suburb(suburb);
+        if suburb.is_empty() {
+            return None;
+        }
+        let key = |dwelling_type| (lookup.state, suburb.clone(), lookup.bedrooms, dwelling_type);
+        dwelling_type
+            .and_then(|dwelling_type| self.suburbs.get(&key(Some(dwelling_type))))
+            .or_else(|| self.suburbs.get(&key(None)))
+    }
+
+    /// The postcode's median for each other bedroom count with one
+    fn other_bedrooms_medians(
+        &self,
+        lookup: &RentalLookup,
+        dwelling_type: Option<PropertyType>,
+    ) -> Vec<&RentalMedian> {
+        let Some(counts) = self.bedrooms.get(&(lookup.state, lookup.postcode.clone())) else {
+            return Vec::new();
+        };
+        counts
+            .iter()
+            .filter(|&&bedrooms| bedrooms != lookup.bedrooms)
+            .filter_map(|&bedrooms| {
+                let other = RentalLookup {
+                    bedrooms,
+                    ..lookup.clone()
+                };
+                self.postcode_median(&other, dwelling_type.clone())
+            })
+            .collect()
+    }
+
+    /// Match a record the way `match_rental` does, from the index
+    pub fn match_rental(&self, record: PropertyRecord, matching: RentalMatching) -> PropertyRecord {
+        let Some(lookup) = rental_lookup(&record, matching) else {
+            return record;
+        };
+        let dwelling_type = dwelling_type(&record);
+
+        let observed = || match matching.observation_fallback {
+            true => self.observed.get(&lookup),
+            false => None,
+        };
+        let rental = self
+            .postcode_median(&lookup, dwelling_type.clone())
+            .map(|median| (median.clone(), RentalMatchMethod::Postcode))
+            .or_else(|| {
+                observed().map(|median| (median.clone(), RentalMatchMethod::Observations))
+            })
+            .or_else(|| {
+                self.suburb_median(&lookup, &record.suburb, dwelling_type.clone())
+                    .map(|median| (median.clone(), RentalMatchMethod::Suburb))
+            })
+            .or_else(|| {
+                let medians = self.other_bedrooms_medians(&lookup, dwelling_type.clone());
+                interpolated_median(&lookup, &medians, matching.bedroom_adjustment)
+            });
+
+        with_rental(record, &lookup, rental)
+    }
+}
+
+/// Records matched to a rent at each step of the fallback chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RentalMatchCounts {
+    pub postcode: u64,
+    pub observations: u64,
+    pub suburb: u64,
+    pub interpolated: u64,
+    pub nearest_bedrooms: u64,
+}
+
+impl RentalMatchCounts {
+    pub fn add(&mut self, record: &PropertyRecord) {
+        let count = match record.source_metadata.rental_match_method {
+            Some(RentalMatchMethod::Postcode) => &mut self.postcode,
+            Some(RentalMatchMethod::Observations) => &mut self.observations,
+            Some(RentalMatchMethod::Suburb) => &mut self.suburb,
+            Some(RentalMatchMethod::Interpolated) => &mut self.interpolated,
+            Some(RentalMatchMethod::NearestBedrooms) => &mut self.nearest_bedrooms,
+            None => return,
+        };
+        *count += 1;
+    }
+}
+
+impl std::fmt::Display for RentalMatchCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} by postcode, {} by observations, {} by suburb, {} interpolated, \
+             {} by nearest bedrooms",
+            self.postcode,
+            self.observations,
+            self.suburb,
+            self.interpolated,
+            self.nearest_bedrooms
+        )
+    }
+}
+
+/// The dwelling type's most recent median for a lookup key, else the all
+/// dwellings one
+async fn postcode_rental_median(
+    db: &PgPool,
+    lookup: &RentalLookup,
+    dwelling_type: Option<PropertyType>,
+) -> Result<Option<RentalMedian>, sqlx::Error> {
+    if let Some(dwelling_type) = dwelling_type {
+        if let Some(typed) = latest_rental_median(db, lookup, Some(dwelling_type)).await? {
+            return Ok(Some(typed));
+        }
+    }
+    latest_rental_median(db, lookup, None).await
+}
+
+/// Most recent rental median for a lookup key and dwelling type, None
+/// meaning the median across all dwellings
+pub async fn latest_rental_median(
+    db: &PgPool,
+    lookup: &RentalLookup,
+    dwelling_type: Option<PropertyType>,
+) -> Result<Option<RentalMedian>, sqlx::Error> {
+    sqlx::query_as::<_, RentalMedian>(
+        r#"
+        SELECT state, postcode, suburb, bedrooms, median_weekly_rent, sample_size,
+            dwelling_type, period
+        FROM rental_medians
+        WHERE state = $1 AND postcode = $2 AND bedrooms = $3
+          AND dwelling_type IS NOT DISTINCT FROM $4
+        ORDER BY period DESC, sample_size DESC NULLS LAST
+        LIMIT 1
+        "#,
+    )
+    .bind(lookup.state)
+    .bind(&lookup.postcode)
+    .bind(lookup.bedrooms)
+    .bind(dwelling_type)
+    .fetch_optional(db)
+    .await
+}
+
+/// Most recent median for the bedroom count from another postcode of the
+/// suburb, the dwelling type's before the all dwellings one
+/// Only medians whose source names a suburb can match.
+pub async fn suburb_rental_median(
+    db: &PgPool,
+    lookup: &RentalLookup,
+    suburb: &str,
+    dwelling_type: Option<PropertyType>,
+) -> Result<Option<RentalMedian>, sqlx::Error> {
+    let suburb = normalize_suburb(suburb);
+    if suburb.is_empty() {
+        return Ok(None);
+    }
+
+    sqlx::query_as::<_, RentalMedian>(
+        r#"
+        SELECT state, postcode, suburb, bedrooms, median_weekly_rent, sample_size,
+            dwelling_type, period
+        FROM rental_medians
+        WHERE state = $1 AND postcode <> $2 AND bedrooms = $3
+          AND (dwelling_type IS NULL OR dwelling_type = $4)
+          AND UPPER(REGEXP_REPLACE(TRIM(suburb), '\s+', ' ', 'g')) = $5
+        ORDER BY dwelling_type IS NULL, period DESC, sample_size DESC NULLS LAST, postcode
+        LIMIT 1
+        "#,
+    )
+    .bind(lookup.state)
+    .bind(&lookup.postcode)
+    .bind(lookup.bedrooms)
+    .bind(dwelling_type)
+    .bind(suburb)
+    .fetch_optional(db)
+    .await
+}
+
+/// Most recent median in the lookup's postcode for each other bedroom count,
+/// the dwelling type's before the all dwellings one
+pub async fn other_bedrooms_rental_medians(
+    db: &PgPool,
+    lookup: &RentalLookup,
+    dwelling_type: Option<PropertyType>,
+) -> Result<Vec<RentalMedian>, sqlx::Error> {
+    sqlx::query_as::<_, RentalMedian>(
+        r#"
+        SELECT DISTINCT ON (bedrooms)
+            state, postcode, suburb, bedrooms, median_weekly_rent, sample_size,
+            dwelling_type, period
+        FROM rental_medians
+        WHERE state = $1 AND postcode = $2 AND bedrooms <> $3
+          AND (dwelling_type IS NULL OR dwelling_type = $4)
+        ORDER BY bedrooms, dwelling_type IS NULL, period DESC, sample_size DESC NULLS LAST
+        "#,
+    )
+    .bind(lookup.state)
+    .bind(&lookup.postcode)
+    .bind(lookup.bedrooms)
+    .bind(dwelling_type)
+    .fetch_all(db)
+    .await
+}
+
+/// Median of the most recent month of bond lodgements for a lookup key
+/// None unless that month has at least MIN_OBSERVATIONS_FOR_MEDIAN lodgements
+pub async fn observation_rental_median(
+    db: &PgPool,
+    lookup: &RentalLookup,
+) -> Result<Option<RentalMedian>, sqlx::Error> {
+    sqlx::query_as::<_, RentalMedian>(
+        r#"
+        SELECT
+            state,
+            postcode,
+            NULL::varchar AS suburb,
+            bedrooms,
+            (percentile_cont(0.5) WITHIN GROUP (ORDER BY weekly_rent))::int AS median_weekly_rent,
+            COUNT(*)::int AS sample_size,
+            NULL::property_type_enum AS dwelling_type,
+            period
+        FROM rental_observations
+        WHERE state = $1 AND postcode = $2 AND bedrooms = $3
+        GROUP BY state, postcode, bedrooms, period
+        HAVING COUNT(*) >= $4
+        ORDER BY period DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(lookup.state)
+    .bind(&lookup.postcode)
+    .bind(lookup.bedrooms)
+    .bind(MIN_OBSERVATIONS_FOR_MEDIAN)
+    .fetch_optional(db)
+    .await
+}
+
 /// Calculate rental yield based on price and rent
 /// Pure function - no side effects
 pub fn calculate_yield(record: PropertyRecord) -> PropertyRecord {
     let yield_pct = match (record.sale_price, record.weekly_rent) {
-        (Some(price), Some(rent)) if price > 0 => {
-            // Formula: (weekly_rent * 52 / price) * 100
-            let annual_rent = Decimal::from(rent) * Decimal::from(52);
-            let price_decimal = Decimal::from(price);
-            Some((annual_rent / price_decimal) * Decimal::from(100))
-        }
+        (Some(price), Some(rent)) => calculate_rental_yield(price, rent),
         _ => None,
     };
 
     if let Some(yield_val) = yield_pct {
         debug!(
-            "Calculated yield for {}: {:.2}%",
-            record.address, yield_val
+            "Calculated yield for {}: {}",
+            record.address,
+            format_yield(yield_val)
         );
     }
 
@@ -139,30 +787,118 @@ pub fn calculate_yield(record: PropertyRecord) -> PropertyRecord {
     }
 }
 
+/// Calculate price per square metre of land
+/// Houses and vacant land only: a strata unit or townhouse carries the whole
+/// lot's area, not its own. Pure function - no side effects
+pub fn calculate_price_per_sqm(record: PropertyRecord) -> PropertyRecord {
+    let per_sqm = match (&record.property_type, record.sale_price, record.land_area_sqm) {
+        (PropertyType::House | PropertyType::VacantLand, Some(price), Some(area)) => {
+            price_per_sqm(price, area)
+        }
+        _ => None,
+    };
+
+    PropertyRecord {
+        price_per_sqm: per_sqm,
+        ..record
+    }
+}
+
+/// Fill in coordinates from the first geocoder in the chain that knows the address
+/// Aggregate medians are left alone: their addresses are synthetic. A geocoder
+/// that fails leaves the coordinates empty and the rest of the record as it was
+pub async fn geocode_record(
+    record: PropertyRecord,
+    geocoders: &GeocoderChain,
+) -> Result<PropertyRecord> {
+    if record.latitude.is_some() && record.longitude.is_some() {
+        return Ok(record); // Already geocoded
+    }
+    if record.source_metadata.data_quality == DataQuality::Aggregated {
+        return Ok(record);
+    }
+
+    match geocoders.geocode(&AddressQuery::from_record(&record)).await {
+        Ok(Some(result)) => {
+            debug!(
+                "Geocoded {} via {} ({:?})",
+                record.address, result.provider, result.precision
+            );
+
+            Ok(PropertyRecord {
+                latitude: Some(result.latitude),
+                longitude: Some(result.longitude),
+                ..record
+            })
+        }
+        Ok(None) => {
+            debug!("No coordinates found for {}", record.address);
+            Ok(record)
+        }
+        Err(e) => {
+            warn!("Geocoding failed for {}: {}", record.address, e);
+            Ok(record)
+        }
+    }
+}
+
 /// Run all enrichment functions in sequence
-/// This is a convenience function that composes the enrichers
+/// This is a convenience function that composes the enrichers; rents are
+/// matched from a `RentalIndex` loaded once for the batch
 pub async fn enrich_all(
     records: Vec<PropertyRecord>,
     db: &PgPool,
+    geocoders: &GeocoderChain,
+    matching: RentalMatching,
+) -> Result<Vec<PropertyRecord>> {
+    let index = RentalIndex::load(db, matching).await?;
+    enrich_all_with(records, &index, geocoders, matching).await
+}
+
+/// Run all enrichment functions in sequence against an index already loaded,
+/// so a run spanning many batches loads it once
+pub async fn enrich_all_with(
+    records: Vec<PropertyRecord>,
+    index: &RentalIndex,
+    geocoders: &GeocoderChain,
+    matching: RentalMatching,
 ) -> Result<Vec<PropertyRecord>> {
     info!("Enriching {} records", records.len());
 
     let mut enriched = Vec::new();
+    let mut matches = RentalMatchCounts::default();
 
     for record in records {
         // Step 1: Estimate bedrooms if missing
         let record = estimate_bedrooms(record);
 
         // Step 2: Match rental data
-        let record = match_rental(record, db).await?;
+        let record = index.match_rental(record, matching);
+
+        // Step 3: Calculate yield, which land has none of
+        let record = match matching.earns_rent(&record) {
+            true => calculate_yield(record),
+            false => PropertyRecord {
+                rental_yield: None,
+                ..record
+            },
+        };
+
+        // Step 4: Price per square metre where there is land
+        let record = calculate_price_per_sqm(record);
 
-        // Step 3: Calculate yield
-        let record = calculate_yield(record);
+        // Step 5: Geocode if coordinates are missing
+        let record = geocode_record(record, geocoders).await?;
 
+        matches.add(&record);
         enriched.push(record);
     }
 
-    info!("Enrichment complete: {} records", enriched.len());
+    info!(
+        "Enrichment complete: {} records; rents matched {}",
+        enriched.len(),
+        matches
+    );
 
     Ok(enriched)
 }
@@ -170,7 +906,8 @@ pub async fn enrich_all(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ingestion::types::{DataQuality, SourceMetadata, State};
+    use crate::ingestion::types::State;
+    use crate::test_support::RentalMedianFixture;
     use chrono::Utc;
 
     fn mock_record() -> PropertyRecord {
@@ -180,6 +917,7 @@ mod tests {
             suburb: "Sydney".to_string(),
             state: State::NSW,
             postcode: Some("2000".to_string()),
+            district: None,
             property_type: PropertyType::House,
             bedrooms: None,
             bathrooms: None,
@@ -187,7 +925,10 @@ mod tests {
             sale_price: Some(800_000),
             sale_date: None,
             weekly_rent: None,
+            rent_frequency: None,
             rental_yield: None,
+            price_per_sqm: None,
+            prior_sales: Vec::new(),
             latitude: None,
             longitude: None,
             source_metadata: SourceMetadata {
@@ -195,6 +936,11 @@ mod tests {
                 data_quality: DataQuality::Individual,
                 fetched_at: Utc::now(),
                 is_rental_estimated: false,
+                is_bedrooms_estimated: false,
+                rental_period: None,
+                rental_match_method: None,
+                source_file: None,
+                source_row: None,
                 confidence_score: 1.0,
             },
         }
@@ -206,6 +952,7 @@ mod tests {
         let enriched = estimate_bedrooms(record);
 
         assert_eq!(enriched.bedrooms, Some(3)); // $800k house = 3br
+        assert!(enriched.source_metadata.is_bedrooms_estimated);
         assert!(enriched.source_metadata.confidence_score < 1.0); // Confidence reduced
     }
 
@@ -231,7 +978,7 @@ mod tests {
         // (600 * 52 / 800000) * 100 = 3.9%
         assert!(enriched.rental_yield.is_some());
         let yield_val = enriched.rental_yield.unwrap();
-        assert!(yield_val > Decimal::from(3) && yield_val < Decimal::from(4));
+        assert_eq!(yield_val, rust_decimal::Decimal::new(39, 1));
     }
 
     #[test]
@@ -241,4 +988,517 @@ mod tests {
 
         assert!(enriched.rental_yield.is_none());
     }
+
+    #[test]
+    fn test_drop_non_residential() {
+        let records = [
+            PropertyType::House,
+            PropertyType::Commercial,
+            PropertyType::VacantLand,
+            PropertyType::Other,
+            PropertyType::Unit,
+        ]
+        .map(|property_type| PropertyRecord {
+            property_type,
+            ..mock_record()
+        });
+
+        let (kept, dropped) = drop_non_residential(records.to_vec());
+        let kept: Vec<_> = kept.into_iter().map(|r| r.property_type).collect();
+        assert_eq!(
+            kept,
+            [PropertyType::House, PropertyType::VacantLand, PropertyType::Unit]
+        );
+        assert_eq!(dropped, 2);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_only_residential_records_carry_yields() {
+        const POSTCODE: &str = "2992";
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let clear = || {
+            sqlx::query("DELETE FROM rental_medians WHERE postcode = $1")
+                .bind(POSTCODE)
+                .execute(&db)
+        };
+        clear().await.unwrap();
+        let period = chrono::NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        RentalMedianFixture::new(POSTCODE, period)
+            .bedrooms(3)
+            .rent(700)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        // A warehouse, a house, land with a rent its listing quoted, and a unit
+        let record = |property_type, price, weekly_rent| PropertyRecord {
+            postcode: Some(POSTCODE.to_string()),
+            property_type,
+            bedrooms: Some(3),
+            sale_price: Some(price),
+            weekly_rent,
+            ..mock_record()
+        };
+        let mixed = vec![
+            record(PropertyType::Commercial, 40_000_000, None),
+            record(PropertyType::House, 900_000, None),
+            record(PropertyType::VacantLand, 450_000, Some(100)),
+            record(PropertyType::Other, 2_000_000, None),
+            record(PropertyType::Unit, 700_000, None),
+        ];
+
+        let matching = RentalMatching::default();
+        let (kept, dropped) = drop_non_residential(mixed);
+        assert_eq!(dropped, 2);
+        let enriched = enrich_all(kept, &db, &GeocoderChain::new(), matching)
+            .await
+            .unwrap();
+
+        let yields: Vec<_> = enriched
+            .iter()
+            .map(|r| (r.property_type.clone(), r.rental_yield.is_some()))
+            .collect();
+        assert_eq!(
+            yields,
+            [
+                (PropertyType::House, true),
+                (PropertyType::VacantLand, false),
+                (PropertyType::Unit, true),
+            ]
+        );
+
+        clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_match_rental_prefers_dwelling_type_median() {
+        const POSTCODE: &str = "2989";
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let clear = || {
+            sqlx::query("DELETE FROM rental_medians WHERE postcode = $1")
+                .bind(POSTCODE)
+                .execute(&db)
+        };
+        clear().await.unwrap();
+        let period = chrono::NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let median = || RentalMedianFixture::new(POSTCODE, period).bedrooms(2);
+        median().rent(600).insert(&db).await.unwrap();
+        median()
+            .rent(750)
+            .dwelling_type(PropertyType::House)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let matched = |property_type| {
+            let record = PropertyRecord {
+                postcode: Some(POSTCODE.to_string()),
+                property_type,
+                bedrooms: Some(2),
+                ..mock_record()
+            };
+            match_rental(record, &db, RentalMatching::default())
+        };
+
+        // The house median for a house
+        let house = matched(PropertyType::House).await.unwrap();
+        assert_eq!(house.weekly_rent, Some(750));
+        assert_eq!(
+            house.source_metadata.confidence_score,
+            MATCHED_RENT_CONFIDENCE
+        );
+
+        // No unit median, so the all dwellings one, for less confidence
+        let unit = matched(PropertyType::Unit).await.unwrap();
+        assert_eq!(unit.weekly_rent, Some(600));
+        assert_eq!(
+            unit.source_metadata.confidence_score,
+            MATCHED_RENT_CONFIDENCE * COMBINED_MEDIAN_CONFIDENCE
+        );
+
+        clear().await.unwrap();
+    }
+
+    /// Counts the statements sqlx runs while it's the thread's subscriber
+    struct QueryCounter(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for QueryCounter {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if event.metadata().target() == "sqlx::query" {
+                self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_rental_index_matches_like_per_record_queries() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        const POSTCODE: &str = "2988";
+        // One connection, so the enum types it has looked up are cached for
+        // the counted batch
+        let db = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let clear = || async {
+            for table in ["rental_medians", "rental_observations"] {
+                sqlx::query(&format!("DELETE FROM {} WHERE postcode = $1", table))
+                    .bind(POSTCODE)
+                    .execute(&db)
+                    .await
+                    .unwrap();
+            }
+        };
+        clear().await;
+
+        let month = |m| chrono::NaiveDate::from_ymd_opt(2024, m, 1).unwrap();
+        let median = |bedrooms, m| RentalMedianFixture::new(POSTCODE, month(m)).bedrooms(bedrooms);
+        // Houses: an older and a newer 3 bedroom median, and the combined one
+        median(3, 3)
+            .rent(650)
+            .dwelling_type(PropertyType::House)
+            .insert(&db)
+            .await
+            .unwrap();
+        median(3, 6)
+            .rent(700)
+            .dwelling_type(PropertyType::House)
+            .insert(&db)
+            .await
+            .unwrap();
+        median(3, 6).rent(640).insert(&db).await.unwrap();
+        // 2 bedrooms: two sources for the same month, the larger sample wins
+        median(2, 6).rent(500).sample_size(10).insert(&db).await.unwrap();
+        median(2, 6)
+            .rent(520)
+            .sample_size(40)
+            .data_source("rental_index_test")
+            .insert(&db)
+            .await
+            .unwrap();
+        // 1 bedroom: only bond lodgements
+        let observations = (0..MIN_OBSERVATIONS_FOR_MEDIAN)
+            .map(|i| crate::ingestion::types::RentalObservation {
+                state: State::NSW,
+                postcode: POSTCODE.to_string(),
+                dwelling_type: PropertyType::Unit,
+                bedrooms: Some(1),
+                weekly_rent: 400 + i as i32 * 10,
+                period: month(5),
+            })
+            .collect();
+        crate::ingestion::write::write_rental_observations(&db, observations)
+            .await
+            .unwrap();
+
+        let record = |property_type, bedrooms, weekly_rent| PropertyRecord {
+            postcode: Some(POSTCODE.to_string()),
+            property_type,
+            bedrooms,
+            sale_price: Some(750_000),
+            weekly_rent,
+            ..mock_record()
+        };
+        let records = vec![
+            record(PropertyType::House, Some(3), None),
+            record(PropertyType::Unit, Some(3), None),
+            record(PropertyType::Townhouse, Some(2), None),
+            record(PropertyType::Unit, Some(1), None),
+            record(PropertyType::House, Some(5), None),
+            record(PropertyType::House, None, None),
+            record(PropertyType::VacantLand, Some(0), None),
+            record(PropertyType::House, Some(3), Some(900)),
+        ];
+
+        for observation_fallback in [false, true] {
+            let matching = RentalMatching {
+                observation_fallback,
+                ..RentalMatching::default()
+            };
+            let index = RentalIndex::load(&db, matching).await.unwrap();
+            let key = RentalLookup {
+                state: State::NSW,
+                postcode: POSTCODE.to_string(),
+                bedrooms: 3,
+            };
+            let house = index.get(&key, Some(PropertyType::House)).unwrap();
+            assert_eq!((house.median_weekly_rent, house.period), (700, month(6)));
+
+            for record in records.iter().cloned().map(estimate_bedrooms) {
+                let expected = match_rental(record.clone(), &db, matching).await.unwrap();
+                let bulk = index.match_rental(record, matching);
+                assert_eq!(
+                    serde_json::to_value(&bulk).unwrap(),
+                    serde_json::to_value(&expected).unwrap(),
+                    "fallback {}",
+                    observation_fallback
+                );
+            }
+
+            // The whole batch takes a single query
+            let queries = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let subscriber = tracing_subscriber::registry().with(QueryCounter(queries.clone()));
+            let enriched = {
+                let _guard = tracing::subscriber::set_default(subscriber);
+                enrich_all(records.clone(), &db, &GeocoderChain::new(), matching)
+                    .await
+                    .unwrap()
+            };
+            assert_eq!(queries.load(std::sync::atomic::Ordering::SeqCst), 1);
+            let rents: Vec<_> = enriched.iter().map(|r| r.weekly_rent).collect();
+            // Without observations the 1 bedroom unit is scaled down from the
+            // 2 bedroom median, and the 5 bedroom house always up from the 3
+            let unit_rent = if observation_fallback { 420 } else { 458 };
+            assert_eq!(
+                rents,
+                [
+                    Some(700),
+                    Some(640),
+                    Some(520),
+                    Some(unit_rent),
+                    Some(878),
+                    Some(700),
+                    None,
+                    Some(900)
+                ]
+            );
+        }
+
+        clear().await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_match_rental_fallback_chain() {
+        const POSTCODE: &str = "2987";
+        const NEIGHBOUR: &str = "2986";
+        const SUBURB: &str = "Fallback Testville";
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let clear = || {
+            sqlx::query("DELETE FROM rental_medians WHERE postcode = ANY($1)")
+                .bind([POSTCODE, NEIGHBOUR])
+                .execute(&db)
+        };
+        clear().await.unwrap();
+        let period = chrono::NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let median = |postcode, bedrooms, rent| {
+            RentalMedianFixture::new(postcode, period)
+                .bedrooms(bedrooms)
+                .rent(rent)
+        };
+        // The property's postcode has 2 and 4 bedroom rents only; the other
+        // postcode of its suburb, written differently, has 2 and 3 bedroom ones
+        median(POSTCODE, 2, 500).suburb(SUBURB).insert(&db).await.unwrap();
+        median(POSTCODE, 4, 700).suburb(SUBURB).insert(&db).await.unwrap();
+        let neighbour = |bedrooms, rent| {
+            median(NEIGHBOUR, bedrooms, rent).suburb("FALLBACK  TESTVILLE")
+        };
+        neighbour(2, 450).insert(&db).await.unwrap();
+        neighbour(3, 650).insert(&db).await.unwrap();
+        neighbour(3, 700)
+            .dwelling_type(PropertyType::House)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let record = |property_type, bedrooms, suburb: &str| PropertyRecord {
+            postcode: Some(POSTCODE.to_string()),
+            suburb: suburb.to_string(),
+            property_type,
+            bedrooms: Some(bedrooms),
+            ..mock_record()
+        };
+        let records = vec![
+            record(PropertyType::Unit, 2, SUBURB),
+            record(PropertyType::House, 3, SUBURB),
+            record(PropertyType::Unit, 3, SUBURB),
+            record(PropertyType::House, 3, "Elsewhere"),
+            record(PropertyType::House, 5, "Elsewhere"),
+        ];
+        let combined = MATCHED_RENT_CONFIDENCE * COMBINED_MEDIAN_CONFIDENCE;
+        let expected = [
+            (500, RentalMatchMethod::Postcode, combined),
+            (
+                700,
+                RentalMatchMethod::Suburb,
+                MATCHED_RENT_CONFIDENCE * SUBURB_MATCH_CONFIDENCE,
+            ),
+            (650, RentalMatchMethod::Suburb, combined * SUBURB_MATCH_CONFIDENCE),
+            // Halfway between 2 and 4 bedrooms
+            (
+                600,
+                RentalMatchMethod::Interpolated,
+                combined * INTERPOLATED_RENT_CONFIDENCE,
+            ),
+            // 12% more than 4 bedrooms
+            (
+                784,
+                RentalMatchMethod::NearestBedrooms,
+                combined * NEAREST_BEDROOMS_CONFIDENCE,
+            ),
+        ];
+
+        let matching = RentalMatching::default();
+        let index = RentalIndex::load(&db, matching).await.unwrap();
+        for (record, (rent, method, confidence)) in records.iter().zip(expected) {
+            let single = match_rental(record.clone(), &db, matching).await.unwrap();
+            let bulk = index.match_rental(record.clone(), matching);
+            for matched in [single, bulk] {
+                assert_eq!(matched.weekly_rent, Some(rent), "{:?}", method);
+                let metadata = &matched.source_metadata;
+                assert_eq!(metadata.rental_match_method, Some(method));
+                assert_eq!(metadata.confidence_score, confidence, "{:?}", method);
+                assert!(metadata.is_rental_estimated);
+            }
+        }
+
+        let mut counts = RentalMatchCounts::default();
+        for record in enrich_all(records, &db, &GeocoderChain::new(), matching)
+            .await
+            .unwrap()
+        {
+            counts.add(&record);
+        }
+        assert_eq!(
+            counts,
+            RentalMatchCounts {
+                postcode: 1,
+                observations: 0,
+                suburb: 2,
+                interpolated: 1,
+                nearest_bedrooms: 1,
+            }
+        );
+
+        clear().await.unwrap();
+    }
+
+    #[test]
+    fn test_interpolate_rent() {
+        let rents = BTreeMap::from([(2, 500), (4, 700), (5, 820)]);
+        let rent = |bedrooms| interpolate_rent(&rents, bedrooms, DEFAULT_BEDROOM_ADJUSTMENT);
+
+        // Between two counts
+        assert_eq!(
+            rent(3),
+            Some(InterpolatedRent {
+                weekly_rent: 600,
+                lower: Some(2),
+                upper: Some(4),
+            })
+        );
+        // Beyond the last count, 12% a bedroom either way
+        assert_eq!(rent(6).map(|r| (r.weekly_rent, r.lower, r.upper)), Some((918, Some(5), None)));
+        assert_eq!(rent(1).map(|r| (r.weekly_rent, r.lower, r.upper)), Some((440, None, Some(2))));
+        let far = BTreeMap::from([(2, 500)]);
+        assert_eq!(interpolate_rent(&far, 4, 0.12).unwrap().weekly_rent, 627);
+        assert_eq!(interpolate_rent(&far, 3, 0.2).unwrap().weekly_rent, 600);
+
+        // Counts further away are only used when nearer ones are missing
+        let gap = BTreeMap::from([(1, 400), (5, 800)]);
+        assert_eq!(interpolate_rent(&gap, 2, 0.12).unwrap().weekly_rent, 500);
+
+        // Nothing to go on
+        assert_eq!(interpolate_rent(&BTreeMap::new(), 3, 0.12), None);
+    }
+
+    #[test]
+    fn test_interpolate_rent_edge_cases() {
+        // Studios neither get a rent nor give one
+        let rents = BTreeMap::from([(0, 300), (2, 500)]);
+        assert_eq!(interpolate_rent(&rents, 0, 0.12), None);
+        let one = interpolate_rent(&rents, 1, 0.12).unwrap();
+        assert_eq!((one.weekly_rent, one.lower), (440, None));
+        assert_eq!(interpolate_rent(&BTreeMap::from([(0, 300)]), 1, 0.12), None);
+
+        // Under $50 a week is noise
+        let cheap = BTreeMap::from([(3, 60)]);
+        assert_eq!(interpolate_rent(&cheap, 2, 0.12).unwrap().weekly_rent, 53);
+        assert_eq!(interpolate_rent(&cheap, 1, 0.12), None);
+    }
+
+    /// Geocoder whose lookups always fail
+    struct Failing;
+
+    #[async_trait::async_trait]
+    impl crate::ingestion::geocode::Geocoder for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn geocode(
+            &self,
+            _query: &AddressQuery<'_>,
+        ) -> Result<Option<crate::ingestion::geocode::GeocodeResult>> {
+            anyhow::bail!("provider unavailable")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_geocode_failure_leaves_record_alone() {
+        let record = PropertyRecord {
+            weekly_rent: Some(600),
+            ..mock_record()
+        };
+        let geocoded = geocode_record(record.clone(), &GeocoderChain::new().with(Failing))
+            .await
+            .unwrap();
+
+        assert_eq!((geocoded.latitude, geocoded.longitude), (None, None));
+        assert_eq!(
+            serde_json::to_value(&geocoded).unwrap(),
+            serde_json::to_value(&record).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_calculate_price_per_sqm() {
+        let mut record = mock_record();
+        record.land_area_sqm = Some(rust_decimal::Decimal::from(400));
+
+        let enriched = calculate_price_per_sqm(record.clone());
+        // 800,000 / 400
+        assert_eq!(enriched.price_per_sqm, Some(rust_decimal::Decimal::from(2000)));
+
+        record.property_type = PropertyType::VacantLand;
+        let enriched = calculate_price_per_sqm(record);
+        assert_eq!(enriched.price_per_sqm, Some(rust_decimal::Decimal::from(2000)));
+    }
+
+    #[test]
+    fn test_calculate_price_per_sqm_skipped() {
+        // Units and townhouses report the strata lot's area
+        for property_type in [PropertyType::Unit, PropertyType::Townhouse] {
+            let mut record = mock_record();
+            record.property_type = property_type;
+            record.land_area_sqm = Some(rust_decimal::Decimal::from(1200));
+            assert!(calculate_price_per_sqm(record).price_per_sqm.is_none());
+        }
+
+        // No area, a zero or implausibly small area, or no price
+        for area in [None, Some(0), Some(9)] {
+            let mut record = mock_record();
+            record.land_area_sqm = area.map(rust_decimal::Decimal::from);
+            assert!(calculate_price_per_sqm(record).price_per_sqm.is_none());
+        }
+        let mut record = mock_record();
+        record.sale_price = None;
+        record.land_area_sqm = Some(rust_decimal::Decimal::from(400));
+        assert!(calculate_price_per_sqm(record).price_per_sqm.is_none());
+    }
 }
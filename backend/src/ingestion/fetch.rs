@@ -1,60 +1,1520 @@
 //! Fetch functions - retrieve raw data from various sources
 
 use crate::ingestion::types::RawData;
-use crate::ingestion::utils::{extract_csv_from_zip, http_get};
-use anyhow::Result;
+use crate::ingestion::utils::{
+    check_local_source, extract_sales_files_from_zip, fetch_url, is_http_status,
+    is_sales_file_name, local_source_path, read_source, Fetcher, FileDownload,
+};
+use crate::ingestion::validate::{ValidationConfig, ValidationReport};
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc, Weekday};
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::env;
 use std::fs;
-use std::path::Path;
-use tracing::info;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
-/// Fetch NSW property sales data (ZIP containing CSV)
-pub async fn fetch_nsw_sales(url: &str, temp_dir: &Path) -> Result<RawData> {
-    info!("Fetching NSW sales data from {}", url);
+/// Sidecar in temp_dir recording the validators of the last NSW sales download
+pub const NSW_SALES_VALIDATORS_FILE: &str = "nsw_sales.validators.json";
+
+/// What the server said identifies the last download, and the CSVs extracted from it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub extracted: Vec<PathBuf>,
+}
+
+impl DownloadValidators {
+    fn from_headers(headers: &HeaderMap, extracted: Vec<PathBuf>) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        DownloadValidators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            extracted,
+        }
+    }
+
+    /// The sidecar at `path`, if it holds a validator and its files still exist
+    fn load(path: &Path) -> Option<Self> {
+        let validators: Self = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+        let usable = (validators.etag.is_some() || validators.last_modified.is_some())
+            && !validators.extracted.is_empty()
+            && validators.extracted.iter().all(|path| path.is_file());
+        usable.then_some(validators)
+    }
+}
 
-    // Download ZIP file
-    let zip_bytes = http_get(url).await?;
+/// A download that may have been skipped because the source hadn't changed
+#[derive(Debug)]
+pub struct ConditionalFetch {
+    pub raw_data: RawData,
+    /// The server answered 304 and the previous download was reused
+    pub unchanged: bool,
+    /// Checks of the downloaded file; empty when the download was reused
+    pub validation: ValidationReport,
+    /// The ZIP as fetched, for the raw archive; empty when the download was reused
+    pub archives: Vec<PathBuf>,
+}
+
+/// Fetch NSW property sales data (ZIP of per-district CSVs)
+///
+/// Unless `force` is set, the ETag and Last-Modified of the previous download
+/// are sent back, and on 304 Not Modified its extracted CSVs are reused.
+/// A new download is checked before extraction and fails with
+/// `ValidationFailed` if it is truncated, isn't the expected file or holds no CSV.
+/// The download is split across `fetcher.segments` connections when the
+/// server takes range requests.
+/// A `file://` URL or plain path is copied from disk instead, without the
+/// conditional request.
+pub async fn fetch_nsw_sales(
+    url: &str,
+    temp_dir: &Path,
+    fetcher: &Fetcher,
+    force: bool,
+    validation: &ValidationConfig,
+) -> Result<ConditionalFetch> {
+    info!("Fetching NSW sales data from {}", url);
 
-    // Save to temp directory
+    fs::create_dir_all(temp_dir)?;
+    // Copied so the CSVs are extracted into temp_dir, not beside the original
     let zip_path = temp_dir.join("nsw_sales.zip");
-    fs::write(&zip_path, zip_bytes)?;
+    if let Some(path) = local_source_path(url) {
+        check_local_source(&path)?;
+        fs::copy(&path, &zip_path)?;
+        info!("Copied {:?} to {:?}", path, zip_path);
+
+        let (csv_paths, report) =
+            validate_and_extract(&zip_path, ValidationReport::default(), validation)?;
+        return Ok(ConditionalFetch {
+            raw_data: RawData::Files(csv_paths),
+            unchanged: false,
+            validation: report,
+            archives: vec![zip_path],
+        });
+    }
+
+    let sidecar = temp_dir.join(NSW_SALES_VALIDATORS_FILE);
+    let previous = if force {
+        None
+    } else {
+        DownloadValidators::load(&sidecar)
+    };
+
+    // A presigned URL for object storage sources, which take validators too
+    let url = &fetcher.resolve_url(url).await?;
+    let mut request = fetcher.client.get(url);
+    if let Some(previous) = &previous {
+        if let Some(etag) = &previous.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &previous.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let (headers, content_length, downloaded) =
+        match fetcher.download_to_file(request, url, &zip_path).await? {
+            FileDownload::NotModified => {
+                let Some(previous) = previous else {
+                    bail!("NSW sales server answered 304 to an unconditional request");
+                };
+                info!("NSW sales unchanged, reusing {:?}", previous.extracted);
+                return Ok(ConditionalFetch {
+                    raw_data: RawData::Files(previous.extracted),
+                    unchanged: true,
+                    validation: ValidationReport::default(),
+                    archives: Vec::new(),
+                });
+            }
+            FileDownload::Saved {
+                headers,
+                expected,
+                downloaded,
+            } => (headers, expected, downloaded),
+        };
     info!("Saved ZIP to {:?}", zip_path);
 
-    // Extract CSV from ZIP
-    let csv_path = extract_csv_from_zip(&zip_path)?;
+    // A failed download leaves the sidecar alone, so the next run doesn't reuse it
+    let mut report = ValidationReport::default();
+    report.check_content_length(downloaded, content_length);
+    let (csv_paths, report) = validate_and_extract(&zip_path, report, validation)?;
+
+    // A sidecar without validators would only make the next request unconditional
+    let validators = DownloadValidators::from_headers(&headers, csv_paths.clone());
+    let saved = if validators.etag.is_some() || validators.last_modified.is_some() {
+        serde_json::to_vec(&validators)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(fs::write(&sidecar, json)?))
+    } else {
+        match fs::remove_file(&sidecar) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    };
+    if let Err(e) = saved {
+        warn!("Failed to update {:?}: {}", sidecar, e);
+    }
+
+    Ok(ConditionalFetch {
+        raw_data: RawData::Files(csv_paths),
+        unchanged: false,
+        validation: report,
+        archives: vec![zip_path],
+    })
+}
+
+/// Finish `report` with the checks of the sales ZIP, then extract its CSVs
+/// NSW sales from a file kept in the raw archive, without fetching: a ZIP is
+/// copied into temp_dir, checked and extracted, and a CSV or DAT file is parsed as it is
+pub fn replay_nsw_sales(path: &Path, temp_dir: &Path) -> Result<(RawData, ValidationReport)> {
+    check_local_source(path)?;
+    if is_sales_file_name(&path.to_string_lossy()) {
+        return Ok((
+            RawData::Files(vec![path.to_path_buf()]),
+            ValidationReport::default(),
+        ));
+    }
+
+    fs::create_dir_all(temp_dir)?;
+    let zip_path = temp_dir.join("nsw_sales_replay.zip");
+    fs::copy(path, &zip_path)?;
+    info!("Replaying {:?}", path);
+    let (csv_paths, report) = validate_and_extract(
+        &zip_path,
+        ValidationReport::default(),
+        &ValidationConfig::default(),
+    )?;
+    Ok((RawData::Files(csv_paths), report))
+}
+
+fn validate_and_extract(
+    zip_path: &Path,
+    mut report: ValidationReport,
+    validation: &ValidationConfig,
+) -> Result<(Vec<PathBuf>, ValidationReport)> {
+    report.check_sha256(zip_path, validation.expected_sha256.as_deref());
+    report.check_zip(zip_path);
+    report.log();
+    let report = report.into_result()?;
+
+    Ok((extract_sales_files_from_zip(zip_path)?, report))
+}
+
+/// Where the weekly NSW sales archives are published; `{date}` is replaced by
+/// the week's Monday as YYYYMMDD
+pub const DEFAULT_NSW_SALES_WEEKLY_URL: &str =
+    "https://www.valuergeneral.nsw.gov.au/__psi/weekly/{date}.zip";
+
+/// Which NSW sales archives a run loads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NswSalesMode {
+    /// The whole archive at NSW_SALES_URL
+    #[default]
+    Full,
+    /// The weekly archives published since the last run
+    Weekly,
+}
+
+impl NswSalesMode {
+    /// NSW_SALES_MODE=weekly for the weekly archives; the full one otherwise
+    pub fn from_env() -> Self {
+        match env::var("NSW_SALES_MODE") {
+            Ok(mode) if mode.eq_ignore_ascii_case("weekly") => NswSalesMode::Weekly,
+            _ => NswSalesMode::Full,
+        }
+    }
+}
+
+/// The Monday of every week after the one holding `since`, up to `today`,
+/// oldest first
+pub fn weekly_archive_dates(since: NaiveDate, today: NaiveDate) -> Vec<NaiveDate> {
+    let monday = since.week(Weekday::Mon).first_day();
+    monday
+        .iter_weeks()
+        .skip(1)
+        .take_while(|week| *week <= today)
+        .collect()
+}
+
+/// The weekly archive URL for `week`, from a template such as DEFAULT_NSW_SALES_WEEKLY_URL
+pub fn weekly_archive_url(template: &str, week: NaiveDate) -> String {
+    template.replace("{date}", &week.format("%Y%m%d").to_string())
+}
+
+/// The weekly archives fetched by one run
+#[derive(Debug)]
+pub struct WeeklyFetch {
+    /// CSVs of every week fetched, oldest week first
+    pub raw_data: RawData,
+    /// Weeks fetched, oldest first
+    pub weeks: Vec<NaiveDate>,
+    /// Weeks without an archive, skipped
+    pub missing: Vec<NaiveDate>,
+    /// Checks of every archive fetched
+    pub validation: ValidationReport,
+    /// Each week's ZIP as fetched, for the raw archive
+    pub archives: Vec<PathBuf>,
+}
 
-    Ok(RawData::File(csv_path))
+impl WeeklyFetch {
+    /// The last week fetched - where the next run carries on from
+    pub fn latest(&self) -> Option<NaiveDate> {
+        self.weeks.last().copied()
+    }
 }
 
-/// Fetch NSW rental bond data (XLSX)
-pub async fn fetch_nsw_rentals(url: &str) -> Result<RawData> {
+/// Fetch the weekly NSW sales archives published after the week of `since`
+///
+/// A week the server answers 404 for is logged and skipped, as is a missing
+/// local file when `url_template` is a path. Any other failure, a failed
+/// check included, fails the whole fetch.
+pub async fn fetch_nsw_sales_weekly(
+    url_template: &str,
+    since: NaiveDate,
+    today: NaiveDate,
+    temp_dir: &Path,
+    fetcher: &Fetcher,
+) -> Result<WeeklyFetch> {
+    let dates = weekly_archive_dates(since, today);
+    info!(
+        "Fetching {} weekly NSW sales archives after {}",
+        dates.len(),
+        since
+    );
+    fs::create_dir_all(temp_dir)?;
+
+    let mut fetched = WeeklyFetch {
+        raw_data: RawData::Files(Vec::new()),
+        weeks: Vec::new(),
+        missing: Vec::new(),
+        validation: ValidationReport::default(),
+        archives: Vec::new(),
+    };
+    let mut csv_paths = Vec::new();
+    for week in dates {
+        let url = weekly_archive_url(url_template, week);
+        let zip_path = temp_dir.join(format!("nsw_sales_{}.zip", week.format("%Y%m%d")));
+
+        let mut report = ValidationReport::default();
+        if let Some(path) = local_source_path(&url) {
+            if !path.is_file() {
+                warn!("No weekly NSW sales archive at {:?}, skipping", path);
+                fetched.missing.push(week);
+                continue;
+            }
+            fs::copy(&path, &zip_path)?;
+        } else {
+            let download = fetch_url(fetcher, &url, &zip_path).await;
+            match download {
+                Ok(FileDownload::Saved {
+                    expected,
+                    downloaded,
+                    ..
+                }) => report.check_content_length(downloaded, expected),
+                Ok(FileDownload::NotModified) => {
+                    bail!("{} answered 304 to an unconditional request", url)
+                }
+                Err(e) if is_http_status(&e, StatusCode::NOT_FOUND) => {
+                    warn!("No weekly NSW sales archive at {} (404), skipping", url);
+                    fetched.missing.push(week);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let (paths, report) =
+            validate_and_extract(&zip_path, report, &ValidationConfig::default())?;
+        csv_paths.extend(paths);
+        fetched.validation.checks.extend(report.checks);
+        fetched.weeks.push(week);
+        fetched.archives.push(zip_path);
+    }
+
+    info!(
+        "Fetched {} weekly archives, {} missing",
+        fetched.weeks.len(),
+        fetched.missing.len()
+    );
+    fetched.raw_data = RawData::Files(csv_paths);
+    Ok(fetched)
+}
+
+/// Fetch NSW rental bond data (XLSX), from disk for a `file://` URL or plain path
+pub async fn fetch_nsw_rentals(url: &str, fetcher: &Fetcher) -> Result<RawData> {
     info!("Fetching NSW rental bond data from {}", url);
 
-    let bytes = http_get(url).await?;
+    let bytes = read_source(fetcher, url).await?;
+
+    Ok(RawData::Bytes(bytes))
+}
+
+/// Fetch a WA Landgate sales extract (CSV)
+pub async fn fetch_wa_sales(url: &str, fetcher: &Fetcher) -> Result<RawData> {
+    info!("Fetching WA Landgate sales from {}", url);
+
+    let bytes = read_source(fetcher, url).await?;
+
+    Ok(RawData::Bytes(bytes))
+}
+
+/// Fetch the Queensland RTA quarterly median rents (CSV)
+pub async fn fetch_qld_rentals(url: &str, fetcher: &Fetcher) -> Result<RawData> {
+    info!("Fetching QLD median rents from {}", url);
+
+    let bytes = read_source(fetcher, url).await?;
+
+    Ok(RawData::Bytes(bytes))
+}
+
+/// Fetch the Victorian Valuer-General median sales spreadsheet (XLSX)
+pub async fn fetch_vic_sales(url: &str, fetcher: &Fetcher) -> Result<RawData> {
+    info!("Fetching VIC median sales from {}", url);
+
+    let bytes = read_source(fetcher, url).await?;
+
+    Ok(RawData::Bytes(bytes))
+}
+
+/// Fetch the South Australian quarterly suburb median sales spreadsheet (XLSX)
+pub async fn fetch_sa_medians(url: &str, fetcher: &Fetcher) -> Result<RawData> {
+    info!("Fetching SA median sales from {}", url);
+
+    let bytes = read_source(fetcher, url).await?;
+
+    Ok(RawData::Bytes(bytes))
+}
+
+/// Fetch an ABS correspondence file, postcode to SA3 or suburb/postcode to LGA (CSV)
+pub async fn fetch_abs_correspondence(url: &str, fetcher: &Fetcher) -> Result<RawData> {
+    info!("Fetching ABS correspondence from {}", url);
+
+    let bytes = read_source(fetcher, url).await?;
 
     Ok(RawData::Bytes(bytes))
 }
 
+/// Fetch the council rates dataset (CSV keyed by LGA code)
+pub async fn fetch_council_rates(url: &str, fetcher: &Fetcher) -> Result<RawData> {
+    info!("Fetching council rates from {}", url);
+
+    let bytes = read_source(fetcher, url).await?;
+
+    Ok(RawData::Bytes(bytes))
+}
+
+/// Records requested per page from the NSW sales API
+pub const DEFAULT_NSW_SALES_API_PAGE_SIZE: usize = 500;
+
+/// NSW recent sales JSON API settings, loaded from environment variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NswSalesApiConfig {
+    pub url: String,
+    /// Sent as X-Api-Key
+    pub api_key: String,
+    pub page_size: usize,
+}
+
+impl NswSalesApiConfig {
+    /// None unless NSW_SALES_API_URL is set; the key is then required
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(url) = env::var("NSW_SALES_API_URL").ok().filter(|s| !s.is_empty()) else {
+            return Ok(None);
+        };
+        let Some(api_key) = env::var("NSW_SALES_API_KEY").ok().filter(|s| !s.is_empty()) else {
+            bail!("NSW_SALES_API_URL is set but NSW_SALES_API_KEY is not");
+        };
+
+        Ok(Some(NswSalesApiConfig {
+            url,
+            api_key,
+            page_size: env::var("NSW_SALES_API_PAGE_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(DEFAULT_NSW_SALES_API_PAGE_SIZE),
+        }))
+    }
+}
+
+/// Records collected from a cursor-paged API
+#[derive(Debug)]
+pub struct DeltaFetch {
+    /// JSON array of the records, in the order the API returned them
+    pub raw_data: RawData,
+    pub records: usize,
+    pub pages: usize,
+    /// Latest `updatedAt` among the records - the next watermark
+    pub latest: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiPage {
+    /// Left as raw JSON; only `updatedAt` is read here
+    records: Vec<Box<RawValue>>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdatedAt {
+    updated_at: DateTime<Utc>,
+}
+
+/// Fetch NSW sales updated after `since` from the JSON API, following its cursor
+///
+/// Fails if any page fails after retries, so a caller only ever sees complete
+/// deltas and a partial fetch can never move the watermark forward.
+pub async fn fetch_nsw_sales_api(
+    config: &NswSalesApiConfig,
+    fetcher: &Fetcher,
+    since: Option<DateTime<Utc>>,
+) -> Result<DeltaFetch> {
+    match since {
+        Some(since) => info!(
+            "Fetching NSW sales updated after {} from {}",
+            since, config.url
+        ),
+        None => info!("Fetching all NSW sales from {}", config.url),
+    }
+
+    let policy = &fetcher.policy;
+    // The kept records as one JSON array, for parse to stream through
+    let mut records = b"[".to_vec();
+    let mut kept = 0;
+    let mut latest: Option<DateTime<Utc>> = None;
+    let mut cursor: Option<String> = None;
+    let mut pages = 0;
+    let mut undated = 0;
+
+    loop {
+        if pages > 0 && !policy.min_delay.is_zero() {
+            tokio::time::sleep(policy.min_delay).await;
+        }
+
+        let mut query = vec![("limit", config.page_size.to_string())];
+        if let Some(since) = since {
+            query.push((
+                "updatedSince",
+                since.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ));
+        }
+        if let Some(cursor) = &cursor {
+            query.push(("cursor", cursor.clone()));
+        }
+        let request = fetcher
+            .client
+            .get(&config.url)
+            .header("X-Api-Key", &config.api_key)
+            .query(&query);
+
+        let page: ApiPage = policy
+            .send(request)
+            .await
+            .map_err(|e| anyhow::anyhow!("Page {} of NSW sales API failed: {}", pages + 1, e))?
+            .json()
+            .await?;
+        pages += 1;
+
+        for record in page.records {
+            // The API's filter is inclusive; records at the watermark were already loaded
+            let Ok(UpdatedAt { updated_at }) = serde_json::from_str(record.get()) else {
+                undated += 1;
+                continue;
+            };
+            if since.is_some_and(|since| updated_at <= since) {
+                continue;
+            }
+            latest = latest.max(Some(updated_at));
+            if kept > 0 {
+                records.push(b',');
+            }
+            records.extend_from_slice(record.get().as_bytes());
+            kept += 1;
+        }
+
+        match page.next_cursor {
+            Some(next) if cursor.as_ref() == Some(&next) => {
+                bail!("NSW sales API returned the same cursor twice: {}", next)
+            }
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    if undated > 0 {
+        warn!(
+            "Skipped {} NSW sales API records without an updatedAt",
+            undated
+        );
+    }
+    records.push(b']');
+    info!("Fetched {} NSW sales in {} pages", kept, pages);
+
+    Ok(DeltaFetch {
+        records: kept,
+        raw_data: RawData::Json(records),
+        pages,
+        latest,
+    })
+}
+
+/// Domain's residential listings search
+pub const DEFAULT_DOMAIN_LISTINGS_URL: &str =
+    "https://api.domain.com.au/v1/listings/residential/_search";
+
+/// Listings requested per page; Domain allows up to 200
+pub const DEFAULT_DOMAIN_PAGE_SIZE: usize = 100;
+
+/// Search requests one run may send when DOMAIN_MAX_CALLS is not set; the
+/// free tier allows 500 a day
+pub const DEFAULT_DOMAIN_MAX_CALLS: usize = 450;
+
+/// Spacing between search requests when DOMAIN_MIN_DELAY_MS is not set
+pub const DEFAULT_DOMAIN_MIN_DELAY_MS: u64 = 500;
+
+/// Most listings Domain returns for one search, however it is paged
+pub const DOMAIN_MAX_SEARCH_RESULTS: usize = 1000;
+
+/// Directory in temp_dir holding the Domain search pages fetched so far
+pub const DOMAIN_PAGES_DIR: &str = "domain_listings";
+
+/// Domain listings API settings, loaded from environment variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainListingsConfig {
+    pub url: String,
+    /// Sent as X-Api-Key
+    pub api_key: String,
+    /// Searched one at a time, in this order
+    pub postcodes: Vec<String>,
+    /// "Sale", "Rent" or both, each searched for every postcode
+    pub listing_types: Vec<String>,
+    pub page_size: usize,
+    /// Hard cap on requests sent during one run
+    pub max_calls: usize,
+    /// Minimum time between consecutive requests
+    pub min_delay: Duration,
+}
+
+impl DomainListingsConfig {
+    /// None unless DOMAIN_API_KEY is set; DOMAIN_POSTCODES is then required
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(api_key) = env::var("DOMAIN_API_KEY").ok().filter(|s| !s.is_empty()) else {
+            return Ok(None);
+        };
+        let list = |name: &str| -> Vec<String> {
+            env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let postcodes = list("DOMAIN_POSTCODES");
+        if postcodes.is_empty() {
+            bail!("DOMAIN_API_KEY is set but DOMAIN_POSTCODES is not");
+        }
+        let listing_types = match list("DOMAIN_LISTING_TYPES") {
+            types if types.is_empty() => vec!["Sale".to_string(), "Rent".to_string()],
+            types => types,
+        };
+
+        Ok(Some(DomainListingsConfig {
+            url: env::var("DOMAIN_LISTINGS_URL")
+                .ok()
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| DEFAULT_DOMAIN_LISTINGS_URL.to_string()),
+            api_key,
+            postcodes,
+            listing_types,
+            page_size: env::var("DOMAIN_PAGE_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(DEFAULT_DOMAIN_PAGE_SIZE),
+            max_calls: env::var("DOMAIN_MAX_CALLS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_DOMAIN_MAX_CALLS),
+            min_delay: Duration::from_millis(
+                env::var("DOMAIN_MIN_DELAY_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(DEFAULT_DOMAIN_MIN_DELAY_MS),
+            ),
+        }))
+    }
+}
+
+/// Listings gathered from the Domain search, possibly over several runs
+#[derive(Debug)]
+pub struct ListingsFetch {
+    /// JSON array of the listings, in the order the searches returned them
+    pub raw_data: RawData,
+    pub listings: usize,
+    /// Pages requested from the API this run
+    pub pages_fetched: usize,
+    /// Pages an earlier, unfinished run had already fetched
+    pub pages_reused: usize,
+    /// Every search was paged to its end; false when the call budget ran out first
+    pub complete: bool,
+}
+
+/// One search result: a listing, or a project holding several
+#[derive(Debug, Deserialize)]
+struct DomainSearchResult {
+    listing: Option<Box<RawValue>>,
+    #[serde(default)]
+    listings: Vec<Box<RawValue>>,
+}
+
+/// Fetch current listings for every configured postcode and listing type
+///
+/// Each page is saved under DOMAIN_PAGES_DIR in `temp_dir` as it arrives, and
+/// a later run reads saved pages back instead of requesting them again, so a
+/// run that fails or spends its `max_calls` budget part way is carried on by
+/// the next. Once a fetch is complete, `clear_domain_pages` starts the next
+/// one afresh. Requests are spaced by `min_delay`; retries of a request don't
+/// count against the budget.
+pub async fn fetch_domain_listings(
+    config: &DomainListingsConfig,
+    fetcher: &Fetcher,
+    temp_dir: &Path,
+) -> Result<ListingsFetch> {
+    let pages_dir = temp_dir.join(DOMAIN_PAGES_DIR);
+    fs::create_dir_all(&pages_dir)?;
+    info!(
+        "Fetching Domain listings for {} postcodes from {}",
+        config.postcodes.len(),
+        config.url
+    );
+
+    // The listings as one JSON array, for parse to stream through
+    let mut listings = b"[".to_vec();
+    let mut fetched = ListingsFetch {
+        raw_data: RawData::Json(Vec::new()),
+        listings: 0,
+        pages_fetched: 0,
+        pages_reused: 0,
+        complete: true,
+    };
+    let max_pages = (DOMAIN_MAX_SEARCH_RESULTS / config.page_size).max(1);
+    let mut last_call: Option<Instant> = None;
+
+    'searches: for listing_type in &config.listing_types {
+        for postcode in &config.postcodes {
+            for number in 1..=max_pages {
+                let page = DomainPage {
+                    listing_type,
+                    postcode,
+                    page_size: config.page_size,
+                    number,
+                };
+                let path = pages_dir.join(page.file_name());
+                let saved = fs::read(&path)
+                    .ok()
+                    .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+                let results: Vec<DomainSearchResult> = match saved {
+                    Some(results) => {
+                        fetched.pages_reused += 1;
+                        results
+                    }
+                    None if fetched.pages_fetched >= config.max_calls => {
+                        warn!(
+                            "Domain call budget of {} spent; the next run carries on from {}",
+                            config.max_calls, page
+                        );
+                        fetched.complete = false;
+                        break 'searches;
+                    }
+                    None => {
+                        if let Some(elapsed) = last_call.map(|at| at.elapsed()) {
+                            if elapsed < config.min_delay {
+                                tokio::time::sleep(config.min_delay - elapsed).await;
+                            }
+                        }
+                        last_call = Some(Instant::now());
+
+                        let bytes = request_domain_page(config, fetcher, &page)
+                            .await
+                            .map_err(|e| anyhow::anyhow!("{} failed: {}", page, e))?;
+                        let results = serde_json::from_slice(&bytes).map_err(|e| {
+                            anyhow::anyhow!("{} is not a search result: {}", page, e)
+                        })?;
+                        fs::write(&path, &bytes)?;
+                        fetched.pages_fetched += 1;
+                        results
+                    }
+                };
+
+                let count = results.len();
+                for result in results {
+                    for listing in result.listing.into_iter().chain(result.listings) {
+                        if fetched.listings > 0 {
+                            listings.push(b',');
+                        }
+                        listings.extend_from_slice(listing.get().as_bytes());
+                        fetched.listings += 1;
+                    }
+                }
+                // A short page is the last one
+                if count < config.page_size {
+                    break;
+                }
+                if number == max_pages {
+                    warn!(
+                        "More {} listings in {} than one search returns; only the first {} were fetched",
+                        listing_type, postcode, DOMAIN_MAX_SEARCH_RESULTS
+                    );
+                }
+            }
+        }
+    }
+
+    listings.push(b']');
+    info!(
+        "Fetched {} Domain listings ({} pages requested, {} reused)",
+        fetched.listings, fetched.pages_fetched, fetched.pages_reused
+    );
+    fetched.raw_data = RawData::Json(listings);
+    Ok(fetched)
+}
+
+/// One page of one Domain search
+struct DomainPage<'a> {
+    listing_type: &'a str,
+    postcode: &'a str,
+    page_size: usize,
+    number: usize,
+}
+
+impl DomainPage<'_> {
+    /// Where the page is saved; a different page size makes different pages
+    fn file_name(&self) -> String {
+        format!(
+            "{}_{}_{}_{}.json",
+            self.listing_type.to_lowercase(),
+            self.postcode,
+            self.page_size,
+            self.number
+        )
+    }
+}
+
+impl std::fmt::Display for DomainPage<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "page {} of Domain {} listings in {}",
+            self.number, self.listing_type, self.postcode
+        )
+    }
+}
+
+/// The body of one search page, as the API sent it
+async fn request_domain_page(
+    config: &DomainListingsConfig,
+    fetcher: &Fetcher,
+    page: &DomainPage<'_>,
+) -> Result<bytes::Bytes> {
+    let body = serde_json::json!({
+        "listingType": page.listing_type,
+        "locations": [{ "postCode": page.postcode, "includeSurroundingSuburbs": false }],
+        "pageSize": page.page_size,
+        "pageNumber": page.number,
+    });
+    let request = fetcher
+        .client
+        .post(&config.url)
+        .header("X-Api-Key", &config.api_key)
+        .json(&body);
+
+    Ok(fetcher.policy.send(request).await?.bytes().await?)
+}
+
+/// Forget the pages of a finished Domain fetch, so the next run fetches
+/// current listings instead of reading these back
+pub fn clear_domain_pages(temp_dir: &Path) -> Result<()> {
+    match fs::remove_dir_all(temp_dir.join(DOMAIN_PAGES_DIR)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ingestion::utils::{HttpConfig, HttpPolicy};
+    use crate::ingestion::validate::ValidationFailed;
+    use axum::extract::Query;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use axum::{routing::get, Json, Router};
+    use serde_json::json;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
     use tempfile::tempdir;
 
+    fn sale(id: &str, updated_at: &str) -> Value {
+        json!({ "propertyId": id, "updatedAt": updated_at })
+    }
+
+    /// Stub sales API: three pages keyed by cursor, 429 on the first request and
+    /// 401 without the key; with `failing` the last page is a permanent 500
+    async fn stub_server(failing: bool) -> (String, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+
+        let app = Router::new().route(
+            "/sales",
+            get(
+                move |headers: HeaderMap, Query(params): Query<HashMap<String, String>>| {
+                    let counter = counter.clone();
+                    async move {
+                        if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                            return (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "0")])
+                                .into_response();
+                        }
+                        if headers.get("x-api-key").and_then(|v| v.to_str().ok()) != Some("secret")
+                        {
+                            return StatusCode::UNAUTHORIZED.into_response();
+                        }
+                        let page = match params.get("cursor").map(String::as_str) {
+                            None => json!({
+                                "records": [
+                                    sale("1", "2025-10-01T00:00:00Z"),
+                                    sale("2", "2025-10-02T08:30:00Z"),
+                                ],
+                                "nextCursor": "p2",
+                            }),
+                            Some("p2") => json!({
+                                // 00:00 UTC, before a watermark of 08:30 that day
+                                "records": [
+                                    sale("3", "2025-10-02T10:00:00+10:00"),
+                                    { "propertyId": "4" },
+                                ],
+                                "nextCursor": "p3",
+                            }),
+                            Some("p3") if !failing => json!({
+                                "records": [sale("5", "2025-10-03T12:00:00Z")],
+                                "nextCursor": null,
+                            }),
+                            _ => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+                        };
+                        Json(page).into_response()
+                    }
+                },
+            ),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{}/sales", addr), hits)
+    }
+
+    fn fetcher() -> Fetcher {
+        let config = HttpConfig {
+            timeout: Duration::from_secs(5),
+            ..Default::default()
+        };
+        let policy = HttpPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            min_delay: Duration::ZERO,
+        };
+        Fetcher::new(&config, policy).unwrap()
+    }
+
+    fn config(url: String, page_size: usize) -> NswSalesApiConfig {
+        NswSalesApiConfig {
+            url,
+            api_key: "secret".to_string(),
+            page_size,
+        }
+    }
+
+    fn ids(fetched: &DeltaFetch) -> Vec<String> {
+        let records: Vec<serde_json::Value> =
+            serde_json::from_slice(fetched.raw_data.as_json().unwrap()).unwrap();
+        records
+            .iter()
+            .map(|r| r["propertyId"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sales_api_pages_through_cursor() {
+        let (url, hits) = stub_server(false).await;
+
+        let fetched = fetch_nsw_sales_api(&config(url, 500), &fetcher(), None)
+            .await
+            .unwrap();
+
+        // The 429 is retried; the record without updatedAt is dropped
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+        assert_eq!(fetched.pages, 3);
+        assert_eq!(ids(&fetched), ["1", "2", "3", "5"]);
+        assert_eq!(fetched.records, 4);
+        assert_eq!(
+            fetched.latest,
+            Some("2025-10-03T12:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sales_api_skips_records_at_or_before_watermark() {
+        let (url, _) = stub_server(false).await;
+        let since = "2025-10-02T08:30:00Z".parse().unwrap();
+
+        let fetched = fetch_nsw_sales_api(&config(url, 500), &fetcher(), Some(since))
+            .await
+            .unwrap();
+
+        assert_eq!(ids(&fetched), ["5"]);
+        assert_eq!(
+            fetched.latest,
+            Some("2025-10-03T12:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sales_api_failed_page_fails_whole_fetch() {
+        let (url, hits) = stub_server(true).await;
+
+        let result = fetch_nsw_sales_api(&config(url, 500), &fetcher(), None).await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("Page 3"), "{}", error);
+        assert!(error.contains("500"), "{}", error);
+        // 429 retry, two good pages, then the failing page and its two retries
+        assert_eq!(hits.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn test_sales_api_rejects_wrong_key() {
+        let (url, _) = stub_server(false).await;
+        let mut config = config(url, 500);
+        config.api_key = "wrong".to_string();
+
+        let error = fetch_nsw_sales_api(&config, &fetcher(), None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("401"), "{}", error);
+    }
+
+    /// Stub Domain search serving the recorded pages: two pages of sales, one
+    /// of rentals; 401 without the key, and a 500 for rentals while `failing`
+    async fn domain_server(failing: Arc<AtomicBool>) -> (String, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let recorded = |name: &str| {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(format!("domain_search_{}.json", name));
+            serde_json::from_slice::<Value>(&fs::read(path).unwrap()).unwrap()
+        };
+        let pages: HashMap<(String, u64), Value> = [
+            (("Sale".to_string(), 1), recorded("sale_page1")),
+            (("Sale".to_string(), 2), recorded("sale_page2")),
+            (("Rent".to_string(), 1), recorded("rent_page1")),
+        ]
+        .into_iter()
+        .collect();
+        let pages = Arc::new(pages);
+
+        let app = Router::new().route(
+            "/_search",
+            axum::routing::post(move |headers: HeaderMap, Json(body): Json<Value>| {
+                let (counter, failing, pages) = (counter.clone(), failing.clone(), pages.clone());
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    if headers.get("x-api-key").and_then(|v| v.to_str().ok()) != Some("secret") {
+                        return StatusCode::UNAUTHORIZED.into_response();
+                    }
+                    assert_eq!(body["locations"][0]["postCode"], "2010");
+                    let listing_type = body["listingType"].as_str().unwrap().to_string();
+                    if listing_type == "Rent" && failing.load(Ordering::SeqCst) {
+                        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                    }
+                    let number = body["pageNumber"].as_u64().unwrap();
+                    let page = pages.get(&(listing_type, number)).cloned();
+                    Json(page.unwrap_or_else(|| json!([]))).into_response()
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{}/_search", addr), hits)
+    }
+
+    fn domain_config(url: String, max_calls: usize) -> DomainListingsConfig {
+        DomainListingsConfig {
+            url,
+            api_key: "secret".to_string(),
+            postcodes: vec!["2010".to_string()],
+            listing_types: vec!["Sale".to_string(), "Rent".to_string()],
+            page_size: 2,
+            max_calls,
+            min_delay: Duration::ZERO,
+        }
+    }
+
+    fn listing_ids(fetched: &ListingsFetch) -> Vec<u64> {
+        let listings: Vec<Value> =
+            serde_json::from_slice(fetched.raw_data.as_json().unwrap()).unwrap();
+        listings.iter().map(|l| l["id"].as_u64().unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_domain_listings_page_until_short_page() {
+        let (url, hits) = domain_server(Arc::new(AtomicBool::new(false))).await;
+        let temp = tempdir().unwrap();
+
+        let fetched = fetch_domain_listings(&domain_config(url, 10), &fetcher(), temp.path())
+            .await
+            .unwrap();
+
+        // The project's two listings come out alongside the single ones
+        assert_eq!(
+            listing_ids(&fetched),
+            [2019400101, 2019400102, 2019400103, 2019400104, 2019400201]
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert_eq!(fetched.pages_fetched, 3);
+        assert!(fetched.complete);
+    }
+
+    #[tokio::test]
+    async fn test_domain_listings_resume_across_runs() {
+        let failing = Arc::new(AtomicBool::new(true));
+        let (url, hits) = domain_server(failing.clone()).await;
+        let temp = tempdir().unwrap();
+
+        // Both sales pages are kept when the rentals page fails
+        let error = fetch_domain_listings(&domain_config(url.clone(), 10), &fetcher(), temp.path())
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("page 1 of Domain Rent listings in 2010"),
+            "{}",
+            error
+        );
+        let sent = hits.load(Ordering::SeqCst);
+
+        // The next run only asks for the rentals, and stops at its budget of one
+        failing.store(false, Ordering::SeqCst);
+        let fetched =
+            fetch_domain_listings(&domain_config(url.clone(), 1), &fetcher(), temp.path())
+                .await
+                .unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), sent + 1);
+        assert_eq!((fetched.pages_reused, fetched.pages_fetched), (2, 1));
+        assert_eq!(listing_ids(&fetched).len(), 5);
+        assert!(fetched.complete);
+
+        // A budget spent before the searches end leaves the fetch incomplete
+        clear_domain_pages(temp.path()).unwrap();
+        assert!(!temp.path().join(DOMAIN_PAGES_DIR).exists());
+        let fetched = fetch_domain_listings(&domain_config(url, 1), &fetcher(), temp.path())
+            .await
+            .unwrap();
+        assert_eq!(listing_ids(&fetched), [2019400101, 2019400102, 2019400103]);
+        assert!(!fetched.complete);
+    }
+
+    #[tokio::test]
+    async fn test_domain_listings_reject_wrong_key() {
+        let (url, _) = domain_server(Arc::new(AtomicBool::new(false))).await;
+        let temp = tempdir().unwrap();
+        let mut config = domain_config(url, 10);
+        config.api_key = "wrong".to_string();
+
+        let error = fetch_domain_listings(&config, &fetcher(), temp.path())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("401"), "{}", error);
+    }
+
+    /// What the stub sales file server serves; `None` validators aren't sent
+    struct StubFile {
+        etag: Option<&'static str>,
+        last_modified: Option<&'static str>,
+        csv: &'static str,
+        /// If-None-Match and If-Modified-Since of each request
+        seen: Vec<(Option<String>, Option<String>)>,
+    }
+
+    fn zipped(csv: &str) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("sales.csv", zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(csv.as_bytes()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// Stub file server answering conditional requests the way a CDN would
+    async fn file_server(file: StubFile) -> (String, Arc<std::sync::Mutex<StubFile>>) {
+        let file = Arc::new(std::sync::Mutex::new(file));
+        let state = file.clone();
+
+        let app = Router::new().route(
+            "/archive.zip",
+            get(move |headers: HeaderMap| {
+                let state = state.clone();
+                async move {
+                    let header =
+                        |name: &str| headers.get(name).map(|v| v.to_str().unwrap().to_string());
+                    let mut file = state.lock().unwrap();
+                    let if_none_match = header("if-none-match");
+                    let if_modified_since = header("if-modified-since");
+                    file.seen
+                        .push((if_none_match.clone(), if_modified_since.clone()));
+
+                    let unchanged = match (file.etag, file.last_modified) {
+                        (Some(etag), _) if if_none_match.is_some() => {
+                            if_none_match.as_deref() == Some(etag)
+                        }
+                        (_, Some(modified)) => if_modified_since.as_deref() == Some(modified),
+                        _ => false,
+                    };
+                    if unchanged {
+                        return StatusCode::NOT_MODIFIED.into_response();
+                    }
+
+                    let mut response = zipped(file.csv).into_response();
+                    if let Some(etag) = file.etag {
+                        response.headers_mut().insert("etag", etag.parse().unwrap());
+                    }
+                    if let Some(modified) = file.last_modified {
+                        response
+                            .headers_mut()
+                            .insert("last-modified", modified.parse().unwrap());
+                    }
+                    response
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{}/archive.zip", addr), file)
+    }
+
+    fn csv_of(fetched: &ConditionalFetch) -> String {
+        fs::read_to_string(&fetched.raw_data.as_file_paths().unwrap()[0]).unwrap()
+    }
+
+    const MODIFIED: &str = "Mon, 06 Oct 2025 00:00:00 GMT";
+
+    #[tokio::test]
+    async fn test_unchanged_sales_file_reused() {
+        let temp = tempdir().unwrap();
+        let (url, file) = file_server(StubFile {
+            etag: Some("\"v1\""),
+            last_modified: Some(MODIFIED),
+            csv: "header\nweek 1\n",
+            seen: Vec::new(),
+        })
+        .await;
+
+        let first = fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &Default::default())
+            .await
+            .unwrap();
+        assert!(!first.unchanged);
+        assert_eq!(csv_of(&first), "header\nweek 1\n");
+
+        let second = fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &Default::default())
+            .await
+            .unwrap();
+        assert!(second.unchanged);
+        assert_eq!(
+            second.raw_data.as_file_paths().unwrap(),
+            first.raw_data.as_file_paths().unwrap()
+        );
+        assert_eq!(csv_of(&second), "header\nweek 1\n");
+
+        // Forced downloads don't send the validators
+        let forced = fetch_nsw_sales(&url, temp.path(), &fetcher(), true, &Default::default())
+            .await
+            .unwrap();
+        assert!(!forced.unchanged);
+
+        let seen = file.lock().unwrap().seen.clone();
+        assert_eq!(
+            seen,
+            vec![
+                (None, None),
+                (Some("\"v1\"".to_string()), Some(MODIFIED.to_string())),
+                (None, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_changed_sales_file_downloaded() {
+        let temp = tempdir().unwrap();
+        let (url, file) = file_server(StubFile {
+            etag: Some("\"v1\""),
+            last_modified: None,
+            csv: "header\nweek 1\n",
+            seen: Vec::new(),
+        })
+        .await;
+
+        fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &Default::default())
+            .await
+            .unwrap();
+        {
+            let mut file = file.lock().unwrap();
+            file.etag = Some("\"v2\"");
+            file.csv = "header\nweek 2\n";
+        }
+
+        let changed = fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &Default::default())
+            .await
+            .unwrap();
+        assert!(!changed.unchanged);
+        assert_eq!(csv_of(&changed), "header\nweek 2\n");
+
+        // The new ETag is the one sent next time
+        let again = fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &Default::default())
+            .await
+            .unwrap();
+        assert!(again.unchanged);
+        assert_eq!(csv_of(&again), "header\nweek 2\n");
+        let seen = file.lock().unwrap().seen.clone();
+        assert_eq!(seen[1].0.as_deref(), Some("\"v1\""));
+        assert_eq!(seen[2].0.as_deref(), Some("\"v2\""));
+    }
+
+    #[tokio::test]
+    async fn test_sales_file_without_validators_always_downloaded() {
+        let temp = tempdir().unwrap();
+        let (url, file) = file_server(StubFile {
+            etag: None,
+            last_modified: None,
+            csv: "header\nweek 1\n",
+            seen: Vec::new(),
+        })
+        .await;
+        // Left by a run when the server still sent validators
+        let stale = DownloadValidators {
+            etag: Some("\"old\"".to_string()),
+            last_modified: None,
+            extracted: vec![temp.path().join("sales.csv")],
+        };
+        fs::write(temp.path().join("sales.csv"), "header\nold\n").unwrap();
+        fs::write(
+            temp.path().join(NSW_SALES_VALIDATORS_FILE),
+            serde_json::to_vec(&stale).unwrap(),
+        )
+        .unwrap();
+
+        for _ in 0..2 {
+            let fetched =
+                fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &Default::default())
+                    .await
+                    .unwrap();
+            assert!(!fetched.unchanged);
+            assert_eq!(csv_of(&fetched), "header\nweek 1\n");
+        }
+
+        // The stale validators go once, and nothing replaces them
+        let seen = file.lock().unwrap().seen.clone();
+        assert_eq!(
+            seen,
+            vec![(Some("\"old\"".to_string()), None), (None, None)]
+        );
+        assert!(!temp.path().join(NSW_SALES_VALIDATORS_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn test_sales_file_failing_checks_not_extracted() {
+        let temp = tempdir().unwrap();
+        let (url, _) = file_server(StubFile {
+            etag: Some("\"v1\""),
+            last_modified: None,
+            csv: "header\nweek 1\n",
+            seen: Vec::new(),
+        })
+        .await;
+        let validation = ValidationConfig {
+            expected_sha256: Some("0".repeat(64)),
+            min_records: 0,
+        };
+
+        let error = fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &validation)
+            .await
+            .unwrap_err();
+        let failed = error.downcast_ref::<ValidationFailed>().unwrap();
+        let names: Vec<_> = failed.report.checks.iter().map(|c| &c.name).collect();
+        assert_eq!(names, ["content_length", "sha256", "zip"]);
+        assert_eq!(failed.report.failures().len(), 1);
+        assert!(failed.report.failures()[0].starts_with("sha256: "));
+
+        // Nothing a later run could mistake for a good download
+        assert!(!temp.path().join("nsw_sales_csv").exists());
+        assert!(!temp.path().join(NSW_SALES_VALIDATORS_FILE).exists());
+
+        let fetched = fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &Default::default())
+            .await
+            .unwrap();
+        let names: Vec<_> = fetched.validation.checks.iter().map(|c| &c.name).collect();
+        assert_eq!(names, ["content_length", "zip"]);
+    }
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_weekly_archive_dates_cross_months_and_years() {
+        let weeks = weekly_archive_dates(day("2024-12-18"), day("2025-01-14"));
+        let urls: Vec<_> = weeks
+            .iter()
+            .map(|week| weekly_archive_url(DEFAULT_NSW_SALES_WEEKLY_URL, *week))
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "https://www.valuergeneral.nsw.gov.au/__psi/weekly/20241223.zip",
+                "https://www.valuergeneral.nsw.gov.au/__psi/weekly/20241230.zip",
+                "https://www.valuergeneral.nsw.gov.au/__psi/weekly/20250106.zip",
+                "https://www.valuergeneral.nsw.gov.au/__psi/weekly/20250113.zip",
+            ]
+        );
+
+        // Leap day: Monday 26 February is followed by Monday 4 March
+        assert_eq!(
+            weekly_archive_dates(day("2024-02-26"), day("2024-03-11")),
+            vec![day("2024-03-04"), day("2024-03-11")]
+        );
+        // Resuming from a week already loaded starts at the next one
+        assert_eq!(
+            weekly_archive_dates(day("2025-01-13"), day("2025-01-19")),
+            vec![]
+        );
+        assert_eq!(
+            weekly_archive_dates(day("2025-01-13"), day("2025-01-20")),
+            vec![day("2025-01-20")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_weekly_archives_skip_missing_weeks() {
+        let app = Router::new().route(
+            "/weekly/:file",
+            get(
+                |axum::extract::Path(file): axum::extract::Path<String>| async move {
+                    match file.as_str() {
+                        "20241230.zip" => zipped("header\nweek of 30 December\n").into_response(),
+                        "20250113.zip" => zipped("header\nweek of 13 January\n").into_response(),
+                        _ => StatusCode::NOT_FOUND.into_response(),
+                    }
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let template = format!("http://{}/weekly/{{date}}.zip", addr);
+        let temp = tempdir().unwrap();
+
+        let fetched = fetch_nsw_sales_weekly(
+            &template,
+            day("2024-12-18"),
+            day("2025-01-14"),
+            temp.path(),
+            &fetcher(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(fetched.weeks, [day("2024-12-30"), day("2025-01-13")]);
+        assert_eq!(fetched.missing, [day("2024-12-23"), day("2025-01-06")]);
+        assert_eq!(fetched.latest(), Some(day("2025-01-13")));
+
+        let csvs: Vec<_> = fetched
+            .raw_data
+            .as_file_paths()
+            .unwrap()
+            .iter()
+            .map(|path| fs::read_to_string(path).unwrap())
+            .collect();
+        assert_eq!(
+            csvs,
+            [
+                "header\nweek of 30 December\n",
+                "header\nweek of 13 January\n"
+            ]
+        );
+        let names: Vec<_> = fetched.validation.checks.iter().map(|c| &c.name).collect();
+        assert_eq!(names, ["content_length", "zip", "content_length", "zip"]);
+
+        // Nothing published since
+        let fetched = fetch_nsw_sales_weekly(
+            &template,
+            day("2025-01-13"),
+            day("2025-01-22"),
+            temp.path(),
+            &fetcher(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(fetched.latest(), None);
+        assert_eq!(fetched.missing, [day("2025-01-20")]);
+    }
+
     #[tokio::test]
     #[ignore] // Ignore by default since it hits real API
     async fn test_fetch_nsw_sales() {
         let temp = tempdir().unwrap();
         let url = "https://nswpropertysalesdata.com/data/archive.zip";
 
-        let result = fetch_nsw_sales(url, temp.path()).await;
+        let result = fetch_nsw_sales(
+            url,
+            temp.path(),
+            &Fetcher::from_env().unwrap(),
+            true,
+            &ValidationConfig::nsw_sales_from_env(),
+        )
+        .await;
         assert!(result.is_ok());
 
-        let raw_data = result.unwrap();
+        let raw_data = result.unwrap().raw_data;
         match raw_data {
-            RawData::File(path) => {
-                assert!(path.exists());
-                assert!(path.extension().unwrap() == "csv");
+            RawData::Files(paths) => {
+                assert!(!paths.is_empty());
+                for path in paths {
+                    assert!(path.exists());
+                    assert!(path.extension().unwrap() == "csv");
+                }
             }
-            _ => panic!("Expected File variant"),
+            _ => panic!("Expected Files variant"),
         }
     }
 }
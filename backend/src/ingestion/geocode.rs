@@ -0,0 +1,873 @@
+//! Geocoding - resolve property addresses to coordinates
+//! A pre-downloaded address CSV and G-NAF are the primary sources; an optional
+//! external provider covers addresses they don't have yet (e.g. new
+//! subdivisions) under a strict per-run budget
+
+use crate::ingestion::types::{PropertyRecord, State};
+use crate::ingestion::utils::{normalize_address, normalize_suburb};
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use sqlx::{PgPool, Type};
+use std::collections::HashMap;
+use std::env;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// How precisely a coordinate locates the address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[sqlx(type_name = "geocode_precision_enum", rename_all = "snake_case")]
+pub enum GeocodePrecision {
+    Address,
+    Street,
+    Locality,
+}
+
+/// A resolved coordinate and where it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeocodeResult {
+    pub latitude: Decimal,
+    pub longitude: Decimal,
+    pub precision: GeocodePrecision,
+    pub provider: String,
+}
+
+/// Address to geocode, borrowed from a property record
+#[derive(Debug, Clone, Copy)]
+pub struct AddressQuery<'a> {
+    pub address: &'a str,
+    pub suburb: &'a str,
+    pub state: State,
+    pub postcode: Option<&'a str>,
+}
+
+impl<'a> AddressQuery<'a> {
+    pub fn from_record(record: &'a PropertyRecord) -> Self {
+        AddressQuery {
+            address: &record.address,
+            suburb: &record.suburb,
+            state: record.state,
+            postcode: record.postcode.as_deref(),
+        }
+    }
+
+    /// Normalised single-line form, used as the cache key
+    pub fn cache_key(&self) -> String {
+        self.to_string()
+            .to_uppercase()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The address in its normalized form, with suburb and state but not
+    /// postcode: "Unit 2, 10 Smith St" in Surry Hills is
+    /// "2/10 SMITH STREET, SURRY HILLS NSW"
+    pub fn lookup_key(&self) -> String {
+        format!(
+            "{}, {} {}",
+            normalize_address(self.address).to_uppercase(),
+            normalize_suburb(self.suburb),
+            self.state
+        )
+    }
+}
+
+impl std::fmt::Display for AddressQuery<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, {} {}", self.address, self.suburb, self.state)?;
+        if let Some(postcode) = self.postcode {
+            write!(f, " {}", postcode)?;
+        }
+        Ok(())
+    }
+}
+
+/// A source of coordinates for addresses
+#[async_trait]
+pub trait Geocoder: Send + Sync {
+    /// Short provider name, recorded on every result
+    fn name(&self) -> &str;
+
+    /// Resolve an address, or None when this provider doesn't know it
+    async fn geocode(&self, query: &AddressQuery<'_>) -> Result<Option<GeocodeResult>>;
+}
+
+/// Geocoders tried in order until one returns a result
+#[derive(Default)]
+pub struct GeocoderChain {
+    geocoders: Vec<Box<dyn Geocoder>>,
+}
+
+impl GeocoderChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a geocoder to the end of the chain
+    pub fn with(mut self, geocoder: impl Geocoder + 'static) -> Self {
+        self.geocoders.push(Box::new(geocoder));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.geocoders.is_empty()
+    }
+
+    /// A geocoder that fails is logged and skipped; the error is only returned
+    /// when every geocoder in the chain failed
+    pub async fn geocode(&self, query: &AddressQuery<'_>) -> Result<Option<GeocodeResult>> {
+        let mut last_error = None;
+        let mut answered = false;
+        for geocoder in &self.geocoders {
+            match geocoder.geocode(query).await {
+                Ok(Some(result)) => return Ok(Some(result)),
+                Ok(None) => answered = true,
+                Err(e) => {
+                    warn!("Geocoder {} failed for {}: {}", geocoder.name(), query, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if !answered => Err(e),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Primary geocoder - exact lookup against the gnaf_addresses table
+pub struct GnafGeocoder {
+    db: PgPool,
+}
+
+impl GnafGeocoder {
+    pub fn new(db: PgPool) -> Self {
+        GnafGeocoder { db }
+    }
+}
+
+#[async_trait]
+impl Geocoder for GnafGeocoder {
+    fn name(&self) -> &str {
+        "gnaf"
+    }
+
+    async fn geocode(&self, query: &AddressQuery<'_>) -> Result<Option<GeocodeResult>> {
+        let row = sqlx::query_as::<_, (Decimal, Decimal, GeocodePrecision)>(
+            r#"
+            SELECT latitude, longitude, precision
+            FROM gnaf_addresses
+            WHERE state = $1
+              AND locality_name = UPPER($2)
+              AND street_address = UPPER($3)
+              AND ($4::VARCHAR IS NULL OR postcode = $4)
+            LIMIT 1
+            "#,
+        )
+        .bind(query.state)
+        .bind(query.suburb)
+        .bind(query.address)
+        .bind(query.postcode)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.map(|(latitude, longitude, precision)| GeocodeResult {
+            latitude,
+            longitude,
+            precision,
+            provider: self.name().to_string(),
+        }))
+    }
+}
+
+/// One row of a pre-downloaded address CSV
+#[derive(Debug, Deserialize)]
+struct LocalAddressRow {
+    address: String,
+    suburb: String,
+    state: State,
+    latitude: Decimal,
+    longitude: Decimal,
+}
+
+/// Primary geocoder - a pre-downloaded address CSV held in memory
+///
+/// The CSV has address, suburb, state, latitude and longitude columns (others
+/// are ignored). Addresses are keyed by `AddressQuery::lookup_key`, so one
+/// written "Unit 2, 10 Smith St" finds a row for "2/10 SMITH STREET".
+#[derive(Debug, Default)]
+pub struct LocalGeocoder {
+    addresses: HashMap<String, (Decimal, Decimal)>,
+}
+
+impl LocalGeocoder {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| anyhow::anyhow!("Can't open {}: {}", path.display(), e))?;
+        let geocoder = Self::from_reader(file)?;
+        info!(
+            "Loaded {} addresses for geocoding from {}",
+            geocoder.len(),
+            path.display()
+        );
+        Ok(geocoder)
+    }
+
+    /// Rows that don't parse are skipped; the first of two rows for an
+    /// address wins
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+        let mut addresses = HashMap::new();
+        let mut skipped = 0;
+        for row in reader.deserialize::<LocalAddressRow>() {
+            let Ok(row) = row else {
+                skipped += 1;
+                continue;
+            };
+            let query = AddressQuery {
+                address: &row.address,
+                suburb: &row.suburb,
+                state: row.state,
+                postcode: None,
+            };
+            addresses
+                .entry(query.lookup_key())
+                .or_insert((row.latitude, row.longitude));
+        }
+        if skipped > 0 {
+            warn!("Skipped {} unreadable rows of the geocoding CSV", skipped);
+        }
+
+        Ok(LocalGeocoder { addresses })
+    }
+
+    /// Number of distinct addresses held
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+#[async_trait]
+impl Geocoder for LocalGeocoder {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn geocode(&self, query: &AddressQuery<'_>) -> Result<Option<GeocodeResult>> {
+        Ok(self
+            .addresses
+            .get(&query.lookup_key())
+            .map(|&(latitude, longitude)| GeocodeResult {
+                latitude,
+                longitude,
+                precision: GeocodePrecision::Address,
+                provider: self.name().to_string(),
+            }))
+    }
+}
+
+/// Default cap on external calls per ingestion run
+pub const DEFAULT_MAX_EXTERNAL_CALLS: usize = 100;
+
+/// Default spacing between external calls (Nominatim's usage policy is 1/s)
+pub const DEFAULT_MIN_DELAY_MS: u64 = 1000;
+
+/// External geocoder settings, loaded from environment variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalGeocoderConfig {
+    /// Base URL of a Nominatim-compatible endpoint (`/search` is appended)
+    pub url: String,
+    pub api_key: Option<String>,
+    /// Hard cap on requests sent during one run
+    pub max_calls: usize,
+    /// Minimum time between consecutive requests
+    pub min_delay: Duration,
+}
+
+impl ExternalGeocoderConfig {
+    /// None unless GEOCODER_URL is set - the external fallback is opt-in
+    pub fn from_env() -> Option<Self> {
+        let url = env::var("GEOCODER_URL").ok().filter(|s| !s.is_empty())?;
+
+        Some(ExternalGeocoderConfig {
+            url,
+            api_key: env::var("GEOCODER_API_KEY").ok().filter(|s| !s.is_empty()),
+            max_calls: env::var("GEOCODER_MAX_CALLS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_MAX_EXTERNAL_CALLS),
+            min_delay: Duration::from_millis(
+                env::var("GEOCODER_MIN_DELAY_MS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(DEFAULT_MIN_DELAY_MS),
+            ),
+        })
+    }
+}
+
+/// Results from the external provider, keyed by `AddressQuery::cache_key`
+/// Misses are cached too, so an unknown address isn't retried every run
+pub struct GeocodeCache {
+    db: Option<PgPool>,
+    memory: std::sync::Mutex<HashMap<String, Option<GeocodeResult>>>,
+}
+
+impl GeocodeCache {
+    /// Cache backed by the geocode_cache table
+    pub fn persistent(db: PgPool) -> Self {
+        GeocodeCache {
+            db: Some(db),
+            memory: Default::default(),
+        }
+    }
+
+    /// Cache that only lives for this process (dry runs and tests)
+    pub fn in_memory() -> Self {
+        GeocodeCache {
+            db: None,
+            memory: Default::default(),
+        }
+    }
+
+    /// Outer None means "never looked up"; Some(None) is a cached miss
+    pub async fn get(&self, key: &str) -> Result<Option<Option<GeocodeResult>>> {
+        if let Some(cached) = self.memory.lock().unwrap().get(key) {
+            return Ok(Some(cached.clone()));
+        }
+
+        let Some(db) = &self.db else {
+            return Ok(None);
+        };
+
+        let row = sqlx::query_as::<
+            _,
+            (Option<Decimal>, Option<Decimal>, Option<GeocodePrecision>, String),
+        >(
+            "SELECT latitude, longitude, precision, provider FROM geocode_cache WHERE query_key = $1",
+        )
+        .bind(key)
+        .fetch_optional(db)
+        .await?;
+
+        let cached = row.map(|row| match row {
+            (Some(latitude), Some(longitude), precision, provider) => Some(GeocodeResult {
+                latitude,
+                longitude,
+                precision: precision.unwrap_or(GeocodePrecision::Locality),
+                provider,
+            }),
+            _ => None,
+        });
+
+        if let Some(cached) = &cached {
+            self.memory
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), cached.clone());
+        }
+
+        Ok(cached)
+    }
+
+    pub async fn put(
+        &self,
+        key: &str,
+        provider: &str,
+        result: Option<&GeocodeResult>,
+    ) -> Result<()> {
+        self.memory
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), result.cloned());
+
+        if let Some(db) = &self.db {
+            sqlx::query(
+                r#"
+                INSERT INTO geocode_cache (query_key, latitude, longitude, precision, provider)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (query_key) DO UPDATE SET
+                    latitude = EXCLUDED.latitude,
+                    longitude = EXCLUDED.longitude,
+                    precision = EXCLUDED.precision,
+                    provider = EXCLUDED.provider,
+                    created_at = NOW()
+                "#,
+            )
+            .bind(key)
+            .bind(result.map(|r| r.latitude))
+            .bind(result.map(|r| r.longitude))
+            .bind(result.map(|r| r.precision))
+            .bind(provider)
+            .execute(db)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Calls made so far this run, and when the last one was sent
+#[derive(Debug, Default)]
+struct Budget {
+    calls: usize,
+    last_call: Option<Instant>,
+}
+
+/// One entry of a Nominatim `/search?format=jsonv2` response
+#[derive(Debug, Deserialize)]
+struct NominatimPlace {
+    lat: String,
+    lon: String,
+    #[serde(default)]
+    addresstype: Option<String>,
+}
+
+/// Fallback geocoder - Nominatim-compatible HTTP endpoint
+///
+/// Every lookup goes through the cache first, so each address is sent to the
+/// provider at most once. Requests are serialised, spaced by `min_delay`, and
+/// stop entirely once `max_calls` is reached; addresses over budget return None
+/// without being cached so a later run can pick them up.
+pub struct ExternalGeocoder {
+    client: Client,
+    config: ExternalGeocoderConfig,
+    cache: GeocodeCache,
+    budget: Mutex<Budget>,
+}
+
+impl ExternalGeocoder {
+    pub fn new(config: ExternalGeocoderConfig, cache: GeocodeCache) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(concat!("real-estate-backend/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(ExternalGeocoder {
+            client,
+            config,
+            cache,
+            budget: Mutex::new(Budget::default()),
+        })
+    }
+
+    /// Number of external requests sent so far
+    pub async fn calls_made(&self) -> usize {
+        self.budget.lock().await.calls
+    }
+
+    async fn request(&self, query: &AddressQuery<'_>) -> Result<Option<GeocodeResult>> {
+        let url = format!("{}/search", self.config.url.trim_end_matches('/'));
+        let mut params = vec![
+            ("q", query.to_string()),
+            ("format", "jsonv2".to_string()),
+            ("limit", "1".to_string()),
+            ("countrycodes", "au".to_string()),
+        ];
+        if let Some(key) = &self.config.api_key {
+            params.push(("key", key.clone()));
+        }
+
+        let places: Vec<NominatimPlace> = self
+            .client
+            .get(&url)
+            .query(&params)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let Some(place) = places.into_iter().next() else {
+            return Ok(None);
+        };
+
+        let precision = match place.addresstype.as_deref() {
+            Some("house" | "building" | "place") => GeocodePrecision::Address,
+            Some("road") => GeocodePrecision::Street,
+            _ => GeocodePrecision::Locality,
+        };
+
+        Ok(Some(GeocodeResult {
+            latitude: Decimal::from_str(&place.lat)?,
+            longitude: Decimal::from_str(&place.lon)?,
+            precision,
+            provider: self.name().to_string(),
+        }))
+    }
+}
+
+#[async_trait]
+impl Geocoder for ExternalGeocoder {
+    fn name(&self) -> &str {
+        "external"
+    }
+
+    async fn geocode(&self, query: &AddressQuery<'_>) -> Result<Option<GeocodeResult>> {
+        let key = query.cache_key();
+        if let Some(cached) = self.cache.get(&key).await? {
+            debug!("Geocode cache hit for {}", key);
+            return Ok(cached);
+        }
+
+        // Held across the request so calls never overlap and spacing is exact
+        let mut budget = self.budget.lock().await;
+
+        if budget.calls >= self.config.max_calls {
+            debug!("External geocoding budget exhausted, skipping {}", key);
+            return Ok(None);
+        }
+
+        if let Some(last_call) = budget.last_call {
+            let elapsed = last_call.elapsed();
+            if elapsed < self.config.min_delay {
+                tokio::time::sleep(self.config.min_delay - elapsed).await;
+            }
+        }
+
+        budget.calls += 1;
+        budget.last_call = Some(Instant::now());
+        if budget.calls == self.config.max_calls {
+            info!(
+                "External geocoding budget of {} calls reached",
+                self.config.max_calls
+            );
+        }
+
+        match self.request(query).await {
+            Ok(result) => {
+                self.cache.put(&key, self.name(), result.as_ref()).await?;
+                Ok(result)
+            }
+            Err(e) => {
+                // Not cached - a transient failure shouldn't hide the address forever
+                warn!("External geocoding failed for {}: {}", key, e);
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Query, routing::get, Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn query(address: &str) -> AddressQuery<'_> {
+        AddressQuery {
+            address,
+            suburb: "Box Hill",
+            state: State::NSW,
+            postcode: Some("2765"),
+        }
+    }
+
+    /// Stub Nominatim endpoint that counts requests; "Nowhere" addresses miss
+    async fn stub_server() -> (String, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+
+        let app = Router::new().route(
+            "/search",
+            get(move |Query(params): Query<HashMap<String, String>>| {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    if params["q"].contains("Nowhere") {
+                        Json(serde_json::json!([]))
+                    } else {
+                        Json(serde_json::json!([
+                            { "lat": "-33.6520", "lon": "150.9071", "addresstype": "house" }
+                        ]))
+                    }
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{}", addr), hits)
+    }
+
+    fn external(url: String, max_calls: usize, min_delay_ms: u64) -> ExternalGeocoder {
+        let config = ExternalGeocoderConfig {
+            url,
+            api_key: None,
+            max_calls,
+            min_delay: Duration::from_millis(min_delay_ms),
+        };
+        ExternalGeocoder::new(config, GeocodeCache::in_memory()).unwrap()
+    }
+
+    /// Geocoder that always returns the same answer
+    struct Fixed(Option<GeocodeResult>);
+
+    #[async_trait]
+    impl Geocoder for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn geocode(&self, _query: &AddressQuery<'_>) -> Result<Option<GeocodeResult>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    /// Geocoder whose lookups always error, like an unreachable database
+    struct Failing;
+
+    #[async_trait]
+    impl Geocoder for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn geocode(&self, _query: &AddressQuery<'_>) -> Result<Option<GeocodeResult>> {
+            anyhow::bail!("connection refused")
+        }
+    }
+
+    const LOCAL_CSV: &str = "\
+address,suburb,state,postcode,latitude,longitude
+2/10 SMITH STREET,SURRY HILLS,NSW,2010,-33.8850,151.2100
+12 Boundary Road,Box Hill,NSW,2765,-33.6500,150.9000
+12 Boundary Road,Box Hill,NSW,2765,-1,1
+1 Broken Row,Box Hill,NSW,2765,not a number,150.9
+";
+
+    #[test]
+    fn test_lookup_key_normalises_address() {
+        let unit = AddressQuery {
+            address: "Unit 2, 10 Smith St",
+            suburb: " surry  hills",
+            state: State::NSW,
+            postcode: Some("2010"),
+        };
+        assert_eq!(unit.lookup_key(), "2/10 SMITH STREET, SURRY HILLS NSW");
+
+        // Postcode plays no part
+        let without_postcode = AddressQuery {
+            postcode: None,
+            ..unit
+        };
+        assert_eq!(without_postcode.lookup_key(), unit.lookup_key());
+        assert_eq!(query("12 Boundary Rd").lookup_key(), "12 BOUNDARY ROAD, BOX HILL NSW");
+    }
+
+    #[tokio::test]
+    async fn test_local_geocoder_matches_normalised_addresses() {
+        let geocoder = LocalGeocoder::from_reader(LOCAL_CSV.as_bytes()).unwrap();
+        // The broken row is skipped and the repeated address kept once
+        assert_eq!(geocoder.len(), 2);
+
+        let unit = AddressQuery {
+            address: "Unit 2, 10 Smith St.",
+            suburb: "Surry Hills",
+            state: State::NSW,
+            postcode: None,
+        };
+        let result = geocoder.geocode(&unit).await.unwrap().unwrap();
+        assert_eq!(result.latitude, Decimal::from_str("-33.8850").unwrap());
+        assert_eq!(result.longitude, Decimal::from_str("151.2100").unwrap());
+        assert_eq!(result.precision, GeocodePrecision::Address);
+        assert_eq!(result.provider, "local");
+
+        // The first row for an address wins
+        let road = geocoder.geocode(&query("12 BOUNDARY RD")).await.unwrap().unwrap();
+        assert_eq!(road.latitude, Decimal::from_str("-33.6500").unwrap());
+
+        // Another suburb or state is another address
+        assert!(geocoder.geocode(&query("1 Broken Row")).await.unwrap().is_none());
+        let elsewhere = AddressQuery {
+            state: State::VIC,
+            ..query("12 Boundary Rd")
+        };
+        assert!(geocoder.geocode(&elsewhere).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_local_geocoder_ahead_of_external() {
+        let (url, hits) = stub_server().await;
+        let chain = GeocoderChain::new()
+            .with(LocalGeocoder::from_reader(LOCAL_CSV.as_bytes()).unwrap())
+            .with(external(url, 10, 0));
+
+        let known = chain.geocode(&query("12 Boundary Road")).await.unwrap().unwrap();
+        assert_eq!(known.provider, "local");
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+        let unknown = chain.geocode(&query("1 New Estate Rd")).await.unwrap().unwrap();
+        assert_eq!(unknown.provider, "external");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_persistent_cache_survives_runs() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let (url, hits) = stub_server().await;
+        let addresses = ["7 Cache Test Rd", "7 Nowhere Cache Test Rd"];
+        let clear = || {
+            let keys: Vec<String> = addresses.iter().map(|a| query(a).cache_key()).collect();
+            sqlx::query("DELETE FROM geocode_cache WHERE query_key = ANY($1)")
+                .bind(keys)
+                .execute(&db)
+        };
+        clear().await.unwrap();
+
+        // Each run builds its own geocoder, with nothing in memory
+        let run = || {
+            let config = ExternalGeocoderConfig {
+                url: url.clone(),
+                api_key: None,
+                max_calls: 10,
+                min_delay: Duration::ZERO,
+            };
+            ExternalGeocoder::new(config, GeocodeCache::persistent(db.clone())).unwrap()
+        };
+
+        let first = run();
+        let found = first.geocode(&query(addresses[0])).await.unwrap();
+        assert!(found.is_some());
+        assert!(first.geocode(&query(addresses[1])).await.unwrap().is_none());
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // The next run finds both, the miss included, without asking again
+        let second = run();
+        assert_eq!(second.geocode(&query(addresses[0])).await.unwrap(), found);
+        assert!(second.geocode(&query(addresses[1])).await.unwrap().is_none());
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert_eq!(second.calls_made().await, 0);
+
+        clear().await.unwrap();
+    }
+
+    #[test]
+    fn test_cache_key_normalised() {
+        let a = query("10  Smith st");
+        let b = query("10 SMITH ST");
+        assert_eq!(a.cache_key(), b.cache_key());
+        assert_eq!(b.cache_key(), "10 SMITH ST, BOX HILL NSW 2765");
+    }
+
+    #[tokio::test]
+    async fn test_external_result_parsed() {
+        let (url, _) = stub_server().await;
+        let geocoder = external(url, 10, 0);
+
+        let result = geocoder.geocode(&query("1 New Estate Rd")).await.unwrap().unwrap();
+
+        assert_eq!(result.latitude, Decimal::from_str("-33.6520").unwrap());
+        assert_eq!(result.longitude, Decimal::from_str("150.9071").unwrap());
+        assert_eq!(result.precision, GeocodePrecision::Address);
+        assert_eq!(result.provider, "external");
+    }
+
+    #[tokio::test]
+    async fn test_external_budget_caps_calls() {
+        let (url, hits) = stub_server().await;
+        let geocoder = external(url, 2, 0);
+
+        assert!(geocoder.geocode(&query("1 New Estate Rd")).await.unwrap().is_some());
+        assert!(geocoder.geocode(&query("2 New Estate Rd")).await.unwrap().is_some());
+        assert!(geocoder.geocode(&query("3 New Estate Rd")).await.unwrap().is_none());
+
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert_eq!(geocoder.calls_made().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_external_min_delay() {
+        let (url, hits) = stub_server().await;
+        let geocoder = external(url, 10, 200);
+
+        let started = Instant::now();
+        geocoder.geocode(&query("1 New Estate Rd")).await.unwrap();
+        geocoder.geocode(&query("2 New Estate Rd")).await.unwrap();
+
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_external_cache_prevents_repeat_calls() {
+        let (url, hits) = stub_server().await;
+        let geocoder = external(url, 10, 0);
+
+        let first = geocoder.geocode(&query("1 New Estate Rd")).await.unwrap();
+        let second = geocoder.geocode(&query("1  NEW ESTATE RD")).await.unwrap();
+        assert_eq!(first, second);
+
+        // Misses are cached as well
+        assert!(geocoder.geocode(&query("1 Nowhere Rd")).await.unwrap().is_none());
+        assert!(geocoder.geocode(&query("1 Nowhere Rd")).await.unwrap().is_none());
+
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_chain_falls_through_in_order() {
+        let (url, hits) = stub_server().await;
+        let hit = GeocodeResult {
+            latitude: Decimal::from(-34),
+            longitude: Decimal::from(151),
+            precision: GeocodePrecision::Address,
+            provider: "fixed".to_string(),
+        };
+
+        // Primary answers - external never called
+        let chain = GeocoderChain::new()
+            .with(Fixed(Some(hit.clone())))
+            .with(external(url.clone(), 10, 0));
+        assert_eq!(chain.geocode(&query("1 Old St")).await.unwrap(), Some(hit));
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+        // Primary misses - falls back to external
+        let chain = GeocoderChain::new()
+            .with(Fixed(None))
+            .with(external(url, 10, 0));
+        let result = chain.geocode(&query("1 New Estate Rd")).await.unwrap().unwrap();
+        assert_eq!(result.provider, "external");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_chain_continues_past_failing_geocoder() {
+        let hit = GeocodeResult {
+            latitude: Decimal::from(-34),
+            longitude: Decimal::from(151),
+            precision: GeocodePrecision::Address,
+            provider: "fixed".to_string(),
+        };
+
+        let chain = GeocoderChain::new()
+            .with(Failing)
+            .with(Fixed(Some(hit.clone())));
+        assert_eq!(chain.geocode(&query("1 Old St")).await.unwrap(), Some(hit));
+
+        // A miss after a failure is still a miss
+        let chain = GeocoderChain::new().with(Failing).with(Fixed(None));
+        assert_eq!(chain.geocode(&query("1 Old St")).await.unwrap(), None);
+
+        // Only when every geocoder fails is the error returned
+        let chain = GeocoderChain::new().with(Failing).with(Failing);
+        assert!(chain.geocode(&query("1 Old St")).await.is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Backfill awareness for the retired legacy ingestion binary, which dropped
+//! every property below MIN_RENTAL_YIELD before storing it. Suburbs it wrote
+//! are missing their low-yield properties until they're re-ingested in full.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+/// Legacy ingestion_logs job names and the source that replaced each
+const LEGACY_FILTERED_JOBS: &[(&str, &str)] = &[("nsw_sales_ingestion", "nsw_sales")];
+
+/// Recorded in a run's metrics when its source previously ran with the yield filter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegacyYieldFilterNote {
+    pub job_name: String,
+    /// When the last legacy run finished (or started, if it never finished)
+    pub last_filtered_run: NaiveDateTime,
+    /// Suburbs with no property from this source written since the last legacy
+    /// run; empty once every affected suburb has been re-ingested
+    pub suburbs_needing_reingestion: Vec<String>,
+}
+
+fn legacy_job_for(source_id: &str) -> Option<&'static str> {
+    LEGACY_FILTERED_JOBS
+        .iter()
+        .find(|(_, source)| *source == source_id)
+        .map(|(job, _)| *job)
+}
+
+/// The backfill note for a source, or None when it never ran with the filter
+pub async fn yield_filter_note(
+    db: &PgPool,
+    source_id: &str,
+) -> Result<Option<LegacyYieldFilterNote>, sqlx::Error> {
+    let Some(job_name) = legacy_job_for(source_id) else {
+        return Ok(None);
+    };
+
+    let (last_filtered_run,) = sqlx::query_as::<_, (Option<NaiveDateTime>,)>(
+        "SELECT MAX(COALESCE(completed_at, started_at)) FROM ingestion_logs WHERE job_name = $1",
+    )
+    .bind(job_name)
+    .fetch_one(db)
+    .await?;
+    let Some(last_filtered_run) = last_filtered_run else {
+        return Ok(None);
+    };
+
+    let suburbs = sqlx::query_as::<_, (String,)>(
+        r#"
+        SELECT suburb
+        FROM properties
+        WHERE data_source = $1
+        GROUP BY suburb
+        HAVING MAX(last_updated) <= $2
+        ORDER BY suburb
+        "#,
+    )
+    .bind(source_id)
+    .bind(last_filtered_run)
+    .fetch_all(db)
+    .await?;
+
+    Ok(Some(LegacyYieldFilterNote {
+        job_name: job_name.to_string(),
+        last_filtered_run,
+        suburbs_needing_reingestion: suburbs.into_iter().map(|(s,)| s).collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{delete_suburb, PropertyFixture};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_legacy_job_for_source() {
+        assert_eq!(legacy_job_for("nsw_sales"), Some("nsw_sales_ingestion"));
+        assert_eq!(legacy_job_for("nsw_sales_api"), None);
+        assert_eq!(legacy_job_for("nsw_rentals"), None);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_yield_filter_note() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let stale = "Legacy Filter Staleville";
+        let fresh = "Legacy Filter Freshville";
+        for suburb in [stale, fresh] {
+            delete_suburb(&db, suburb).await.unwrap();
+        }
+        sqlx::query("DELETE FROM ingestion_logs WHERE job_name = 'nsw_sales_ingestion'")
+            .execute(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(yield_filter_note(&db, "nsw_sales").await.unwrap(), None);
+
+        let at = |day| {
+            NaiveDate::from_ymd_opt(2024, 1, day)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO ingestion_logs (job_name, state, status, started_at, completed_at)
+            VALUES ('nsw_sales_ingestion', 'NSW', 'completed', $1, $2)
+            "#,
+        )
+        .bind(at(1))
+        .bind(at(2))
+        .execute(&db)
+        .await
+        .unwrap();
+
+        // Written by the legacy run and never since, versus re-ingested after it
+        for (suburb, last_updated) in [(stale, at(1)), (fresh, at(20))] {
+            PropertyFixture::new()
+                .suburb(suburb)
+                .data_source("nsw_sales")
+                .last_updated(last_updated)
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+
+        let note = yield_filter_note(&db, "nsw_sales").await.unwrap().unwrap();
+        assert_eq!(note.job_name, "nsw_sales_ingestion");
+        assert_eq!(note.last_filtered_run, at(2));
+        let suburbs = &note.suburbs_needing_reingestion;
+        assert!(suburbs.contains(&stale.to_string()));
+        assert!(!suburbs.contains(&fresh.to_string()));
+
+        // Sources the legacy binary never ran get no note
+        assert_eq!(yield_filter_note(&db, "nsw_sales_api").await.unwrap(), None);
+
+        sqlx::query("DELETE FROM ingestion_logs WHERE job_name = 'nsw_sales_ingestion'")
+            .execute(&db)
+            .await
+            .unwrap();
+        for suburb in [stale, fresh] {
+            delete_suburb(&db, suburb).await.unwrap();
+        }
+    }
+}
@@ -1,10 +1,27 @@
 //! Data ingestion module - functional pipeline for multi-source property data
 
+pub mod anomaly;
+pub mod archive;
+pub mod batch;
+pub mod districts;
+pub mod drift;
 pub mod enrich;
 pub mod fetch;
+pub mod geocode;
+pub mod legacy;
+pub mod notify;
+pub mod object_store;
 pub mod parse;
+pub mod quality;
+pub mod refresh;
+pub mod renormalize;
+pub mod runs;
+pub mod staging;
+pub mod throttle;
 pub mod types;
 pub mod utils;
+pub mod validate;
+pub mod watermark;
 pub mod write;
 
 pub use types::*;
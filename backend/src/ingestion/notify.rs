@@ -0,0 +1,61 @@
+//! Notification hook - tells operators about ingestion runs that need attention
+//!
+//! Events are always logged; when ALERT_WEBHOOK_URL is set they are also
+//! POSTed there as JSON. Delivery failures are logged and otherwise ignored.
+
+use anyhow::Result;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::env;
+use std::time::Duration;
+use tracing::warn;
+
+#[derive(Debug, Clone)]
+pub struct NotificationHook {
+    client: Client,
+    webhook_url: Option<String>,
+}
+
+impl NotificationHook {
+    pub fn new(webhook_url: Option<String>) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(concat!("real-estate-backend/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(NotificationHook {
+            client,
+            webhook_url,
+        })
+    }
+
+    /// Webhook from ALERT_WEBHOOK_URL, or log-only when unset
+    pub fn from_env() -> Result<Self> {
+        Self::new(env::var("ALERT_WEBHOOK_URL").ok().filter(|s| !s.is_empty()))
+    }
+
+    /// Whether events are delivered anywhere besides the log
+    pub fn has_webhook(&self) -> bool {
+        self.webhook_url.is_some()
+    }
+
+    /// Send `event` with `details`; never fails
+    pub async fn notify(&self, event: &str, details: Value) {
+        warn!("Notification {}: {}", event, details);
+
+        let Some(url) = &self.webhook_url else {
+            return;
+        };
+        let body = json!({ "event": event, "details": details });
+        let result = self
+            .client
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            warn!("Failed to deliver {} notification: {}", event, e);
+        }
+    }
+}
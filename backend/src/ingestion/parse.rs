@@ -1,16 +1,107 @@
 //! Parse functions - transform raw data into PropertyRecord structs
 
+use crate::ingestion::districts::normalize_district;
 use crate::ingestion::types::{
-    DataQuality, PropertyRecord, RawData, RentalMedian, SourceMetadata, State,
+    CouncilRates, DataQuality, LgaAreaType, LgaCorrespondence, PostcodeRegion, PropertyRecord,
+    PropertyType, RawData, RentalMedian, RentalObservation, SourceMetadata, State,
 };
-use crate::ingestion::utils::{format_nsw_address, parse_nsw_property_type};
+use crate::ingestion::utils::{
+    format_nsw_address, format_wa_address, is_dat_name, parse_asking_price,
+    parse_domain_property_type, parse_json_array_stream, parse_nsw_property_type, parse_price,
+    is_implausible_price, normalize_postcode, parse_rent_frequency, parse_wa_property_type, AskingPrice, PriceParseError,
+};
+use crate::ingestion::validate::error_rate;
+use crate::RentFrequency;
 use anyhow::Result;
 use calamine::{open_workbook_auto_from_rs, Reader, Data};
-use chrono::{NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
 use csv;
-use serde::Deserialize;
-use std::io::Cursor;
-use tracing::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Cursor, Read};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Row errors written to a parse's error sidecar; the count covers the rest
+const SIDECAR_ERRORS: usize = 100;
+
+/// A row a parser could not use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowError {
+    /// File or sheet the row is in
+    pub source: String,
+    /// Line of the file, or row of the sheet, counting the header
+    pub row_number: usize,
+    /// Header of the offending column, when the error is down to one
+    pub column: Option<String>,
+    pub message: String,
+}
+
+/// What a parse yielded, and every row it could not use
+#[derive(Debug)]
+pub struct ParseReport<T = PropertyRecord> {
+    pub records: Vec<T>,
+    pub errors: Vec<RowError>,
+    /// Data rows read, whether they parsed or not
+    pub total_rows: usize,
+    /// Records whose source left the price blank, as opposed to rows failing
+    /// on a price that couldn't be read
+    pub blank_prices: usize,
+    /// Records kept with a price over `MAX_PLAUSIBLE_PRICE`, each warned about
+    /// for a person to check
+    pub implausible_prices: usize,
+    /// Postcodes given that aren't one of the state's, dropped from their
+    /// records or failing their rows
+    pub rejected_postcodes: usize,
+}
+
+/// Records priced over `MAX_PLAUSIBLE_PRICE`, warning about each; they're
+/// kept, as the rest of the row read fine and a few real sales run that high
+fn flag_implausible_prices(records: &[PropertyRecord]) -> usize {
+    records.iter().filter(|record| flag_implausible_price(record)).count()
+}
+
+fn flag_implausible_price(record: &PropertyRecord) -> bool {
+    match record.sale_price {
+        Some(price) if is_implausible_price(price) => {
+            warn!(
+                "Implausible price ${} kept for {}, {}; check it against the source",
+                price, record.address, record.suburb
+            );
+            true
+        }
+        _ => false,
+    }
+}
+
+impl<T> ParseReport<T> {
+    /// Share of rows that failed; 0 when there were none
+    pub fn error_rate(&self) -> f64 {
+        error_rate(self.errors.len(), self.total_rows)
+    }
+}
+
+/// Write the first row errors of a parse to `{source_id}_parse_errors.json` in
+/// `dir`, for debugging a file that parsed badly
+pub fn write_error_sidecar(
+    dir: &Path,
+    source_id: &str,
+    errors: &[RowError],
+    total_rows: usize,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}_parse_errors.json", source_id));
+    let sidecar = serde_json::json!({
+        "source_id": source_id,
+        "total_rows": total_rows,
+        "error_count": errors.len(),
+        "errors": &errors[..errors.len().min(SIDECAR_ERRORS)],
+    });
+    std::fs::write(&path, serde_json::to_vec_pretty(&sidecar)?)?;
+    Ok(path)
+}
 
 /// NSW Sales CSV row structure
 #[derive(Debug, Deserialize)]
@@ -40,68 +131,460 @@ struct NswSalesRow {
     settlement_date: String, // Format: DD/MM/YYYY
 
     #[serde(rename = "Contract date")]
+    #[allow(dead_code)]
     contract_date: Option<String>,
 
     #[serde(rename = "Nature of property")]
     nature_of_property: String,
+
+    /// Only in some releases, so not among NSW_SALES_COLUMNS
+    #[serde(rename = "District code", default)]
+    district_code: Option<String>,
 }
 
-/// Parse NSW sales CSV into PropertyRecord structs
-pub async fn parse_nsw_sales(raw: RawData, source_id: String) -> Result<Vec<PropertyRecord>> {
-    let csv_path = raw.as_file_path()?;
-    info!("Parsing NSW sales CSV from {:?}", csv_path);
+/// Columns an NSW sales CSV must have; files without them are skipped
+const NSW_SALES_COLUMNS: [&str; 10] = [
+    "Property ID",
+    "Property unit number",
+    "Property house number",
+    "Property street name",
+    "Property locality",
+    "Property post code",
+    "Purchase price",
+    "Settlement date",
+    "Contract date",
+    "Nature of property",
+];
 
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .from_path(csv_path)?;
+/// Parse NSW sales CSVs into PropertyRecord structs, file by file
+/// A file with other columns is skipped with a warning instead of failing the rest.
+/// Besides files, the CSV can be given as a string or bytes. Files ending in
+/// .DAT are read as Valuer General bulk files, as by `parse_nsw_sales_dat`.
+/// A property's repeat sales within a file are collapsed into one record.
+/// Releases with a District code column, and .DAT files, give each record
+/// its district.
+pub async fn parse_nsw_sales(raw: RawData, source_id: String) -> Result<ParseReport> {
+    let mut reader = NswSalesReader::from_raw(raw, &source_id);
+    let records = reader.by_ref().collect::<Result<Vec<_>>>()?;
+    reader.log_summary();
 
-    let mut records = Vec::new();
-    let mut parse_errors = 0;
-
-    for (idx, result) in reader.deserialize::<NswSalesRow>().enumerate() {
-        match result {
-            Ok(row) => {
-                match parse_nsw_row(row, &source_id) {
-                    Ok(record) => records.push(record),
-                    Err(e) => {
-                        parse_errors += 1;
-                        if parse_errors <= 10 {
-                            // Only log first 10 errors
-                            warn!("Failed to parse row {}: {}", idx, e);
+    Ok(ParseReport {
+        records,
+        errors: reader.errors,
+        total_rows: reader.total_rows,
+        blank_prices: reader.blank_prices,
+        implausible_prices: reader.implausible_prices,
+        rejected_postcodes: reader.rejected_postcodes,
+    })
+}
+
+/// NSW sales records read a file at a time from a list of CSVs, so the whole
+/// dataset never has to be held at once
+///
+/// Rows that fail to parse are collected as `RowError`s rather than yielded,
+/// and a file with other columns is skipped with a warning. Only failing to
+/// open a file ends the stream with an error.
+///
+/// Each file's records are held until it ends so repeated sales of a property
+/// can be collapsed into one record, as by `collapse_repeat_sales`; the bulk
+/// archives keep each file to a district's week. A .DAT file, whose sales span
+/// rows, is parsed whole when it is reached.
+pub struct NswSalesReader {
+    source_id: String,
+    /// CSVs not yet opened
+    sources: std::vec::IntoIter<RawData>,
+    /// Archive key recorded as each CSV's records' source_file
+    source_files: Vec<Option<String>>,
+    next_source: usize,
+    current: Option<NswSalesFile>,
+    /// Collapsed records of the last file read, still to be yielded
+    queued: std::vec::IntoIter<PropertyRecord>,
+    records: usize,
+    /// Sales folded into a later record of the same property
+    repeat_sales: usize,
+    blank_prices: usize,
+    implausible_prices: usize,
+    rejected_postcodes: usize,
+    total_rows: usize,
+    errors: Vec<RowError>,
+    skipped_files: usize,
+}
+
+/// The CSV being read and its counts so far
+struct NswSalesFile {
+    /// File name, for row errors
+    name: String,
+    /// Path, or what the CSV is held as, for logs
+    label: String,
+    source_file: Option<String>,
+    headers: csv::StringRecord,
+    rows: csv::DeserializeRecordsIntoIter<Box<dyn Read + Send>, NswSalesRow>,
+    next_row: usize,
+    /// Records parsed so far, collapsed once the file ends
+    parsed: Vec<PropertyRecord>,
+    errors: usize,
+}
+
+impl NswSalesReader {
+    pub fn new(paths: &[PathBuf], source_id: &str) -> Self {
+        Self::from_sources(paths.iter().cloned().map(RawData::File).collect(), source_id)
+    }
+
+    /// Each file of `File` or `Files`, or else the one CSV `raw` holds
+    pub fn from_raw(raw: RawData, source_id: &str) -> Self {
+        Self::from_sources(single_sources(raw), source_id)
+    }
+
+    fn from_sources(sources: Vec<RawData>, source_id: &str) -> Self {
+        NswSalesReader {
+            source_id: source_id.to_string(),
+            sources: sources.into_iter(),
+            source_files: Vec::new(),
+            next_source: 0,
+            current: None,
+            queued: Vec::new().into_iter(),
+            records: 0,
+            repeat_sales: 0,
+            blank_prices: 0,
+            implausible_prices: 0,
+            rejected_postcodes: 0,
+            total_rows: 0,
+            errors: Vec::new(),
+            skipped_files: 0,
+        }
+    }
+
+    /// Tag each CSV's records with its archive key, given in the same order
+    pub fn with_source_files(mut self, source_files: Vec<Option<String>>) -> Self {
+        self.source_files = source_files;
+        self
+    }
+
+    /// Records yielded so far
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Sales so far folded into a later record of the same property, which
+    /// together with `records` are all the sales read
+    pub fn repeat_sales(&self) -> usize {
+        self.repeat_sales
+    }
+
+    /// Data rows read so far, whether they parsed or not
+    pub fn total_rows(&self) -> usize {
+        self.total_rows
+    }
+
+    /// Rows that failed to parse so far
+    pub fn errors(&self) -> &[RowError] {
+        &self.errors
+    }
+
+    /// Records yielded so far whose price was left blank
+    pub fn blank_prices(&self) -> usize {
+        self.blank_prices
+    }
+
+    /// Records yielded so far priced over `MAX_PLAUSIBLE_PRICE`
+    pub fn implausible_prices(&self) -> usize {
+        self.implausible_prices
+    }
+
+    /// Postcodes dropped so far as not being NSW's
+    pub fn rejected_postcodes(&self) -> usize {
+        self.rejected_postcodes
+    }
+
+    pub fn log_summary(&self) {
+        info!(
+            "Parsed {} records from {} NSW sales CSVs ({} repeat sales collapsed, {} unpriced, {} implausibly priced, {} bad postcodes, {} errors, {} files skipped)",
+            self.records,
+            self.next_source,
+            self.repeat_sales,
+            self.blank_prices,
+            self.implausible_prices,
+            self.rejected_postcodes,
+            self.errors.len(),
+            self.skipped_files
+        );
+    }
+
+    /// Queue a file's records once repeat sales are collapsed
+    fn queue(&mut self, records: Vec<PropertyRecord>) {
+        let sales = records.len();
+        let records = collapse_repeat_sales(records);
+        self.repeat_sales += sales - records.len();
+        self.queued = records.into_iter();
+    }
+
+    fn count(&mut self, record: &PropertyRecord) {
+        self.records += 1;
+        if record.sale_price.is_none() {
+            self.blank_prices += 1;
+        }
+        if flag_implausible_price(record) {
+            self.implausible_prices += 1;
+        }
+    }
+
+    /// Start on the next CSV; false once there are none left
+    fn open_next(&mut self) -> Result<bool> {
+        let Some(raw) = self.sources.next() else {
+            return Ok(false);
+        };
+        let source_file = self.source_files.get(self.next_source).cloned().flatten();
+        self.next_source += 1;
+
+        let (name, label) = source_names(&raw);
+        if is_dat_source(&raw) {
+            info!("Parsing NSW sales DAT from {}", label);
+            let dat = read_nsw_sales_dat(raw.into_reader()?, &name, &self.source_id)?;
+            info!(
+                "Parsed {} records from {} ({} errors)",
+                dat.records.len(),
+                label,
+                dat.errors.len()
+            );
+            self.total_rows += dat.total_rows;
+            self.rejected_postcodes += dat.rejected_postcodes;
+            self.errors.extend(dat.errors);
+            let mut records = dat.records;
+            for record in &mut records {
+                record.source_metadata.source_file = source_file.clone();
+            }
+            self.queue(records);
+            return Ok(true);
+        }
+        match open_nsw_sales(raw.into_reader()?, &label)? {
+            Some(mut reader) => {
+                self.current = Some(NswSalesFile {
+                    name,
+                    label,
+                    source_file,
+                    headers: reader.headers()?.clone(),
+                    rows: reader.into_deserialize(),
+                    next_row: 0,
+                    parsed: Vec::new(),
+                    errors: 0,
+                })
+            }
+            None => self.skipped_files += 1,
+        }
+        Ok(true)
+    }
+}
+
+impl Iterator for NswSalesReader {
+    type Item = Result<PropertyRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.queued.next() {
+                self.count(&record);
+                return Some(Ok(record));
+            }
+            let Some(file) = self.current.as_mut() else {
+                match self.open_next() {
+                    Ok(true) => continue,
+                    Ok(false) => return None,
+                    Err(e) => return Some(Err(e)),
+                }
+            };
+            let Some(result) = file.rows.next() else {
+                info!(
+                    "Parsed {} records from {} ({} errors)",
+                    file.parsed.len(),
+                    file.label,
+                    file.errors
+                );
+                let parsed = std::mem::take(&mut file.parsed);
+                self.current = None;
+                self.queue(parsed);
+                continue;
+            };
+
+            let idx = file.next_row;
+            file.next_row += 1;
+            self.total_rows += 1;
+            // The header is line 1, and a record spans one line unless quoted
+            // fields break it, in which case the reader's position is used
+            let mut line = idx + 2;
+            let (column, message) = match result {
+                Ok(row) => {
+                    let postcode_given = !row.property_post_code.trim().is_empty();
+                    match parse_nsw_row(row, &self.source_id) {
+                        Ok(mut record) => {
+                            record.source_metadata.source_file = file.source_file.clone();
+                            if postcode_given && record.postcode.is_none() {
+                                self.rejected_postcodes += 1;
+                            }
+                            file.parsed.push(with_source_row(record, idx));
+                            continue;
                         }
+                        Err(e) => (Some("Purchase price".to_string()), e.to_string()),
                     }
                 }
-            }
-            Err(e) => {
-                parse_errors += 1;
-                if parse_errors <= 10 {
-                    warn!("Failed to deserialize row {}: {}", idx, e);
+                Err(e) => {
+                    if let Some(position) = e.position() {
+                        line = position.line() as usize;
+                    }
+                    let header = |i: u64| file.headers.get(i as usize).map(str::to_string);
+                    match e.kind() {
+                        csv::ErrorKind::Deserialize { err, .. } => {
+                            (err.field().and_then(header), err.kind().to_string())
+                        }
+                        csv::ErrorKind::Utf8 { err, .. } => {
+                            (header(err.field() as u64), err.to_string())
+                        }
+                        _ => (None, e.to_string()),
+                    }
                 }
+            };
+            file.errors += 1;
+            // Only log first 10 errors of each file
+            if file.errors <= 10 {
+                warn!(
+                    "Failed to parse line {} of {}: {}",
+                    line, file.label, message
+                );
             }
+            self.errors.push(RowError {
+                source: file.name.clone(),
+                row_number: line,
+                column,
+                message,
+            });
         }
     }
+}
 
-    info!(
-        "Parsed {} records from NSW sales CSV ({} errors)",
-        records.len(),
-        parse_errors
-    );
+/// A reader positioned after the header, or None when the header doesn't
+/// have the NSW sales columns
+fn open_nsw_sales(
+    csv: Box<dyn Read + Send>,
+    label: &str,
+) -> Result<Option<csv::Reader<Box<dyn Read + Send>>>> {
+    info!("Parsing NSW sales CSV from {}", label);
+
+    let mut reader = csv::ReaderBuilder::new().has_headers(true).from_reader(csv);
+
+    let headers = reader.headers()?.clone();
+    let missing: Vec<&str> = NSW_SALES_COLUMNS
+        .into_iter()
+        .filter(|column| !headers.iter().any(|h| h == *column))
+        .collect();
+    if !missing.is_empty() {
+        warn!(
+            "Skipping {}: not an NSW sales CSV, missing {}",
+            label,
+            missing.join(", ")
+        );
+        return Ok(None);
+    }
+
+    Ok(Some(reader))
+}
+
+/// Parse a single data row of an NSW sales CSV, or sale of a .DAT file, for
+/// re-ingesting one property
+/// Returns None when the file has fewer rows than `row`, or the sale didn't parse
+pub async fn parse_nsw_sales_row(
+    raw: RawData,
+    source_id: String,
+    row: usize,
+) -> Result<Option<PropertyRecord>> {
+    // A .DAT file's rows are its sales, which have to be assembled in order
+    if is_dat_source(&raw) {
+        let (name, label) = source_names(&raw);
+        info!("Parsing sale {} of NSW sales DAT {}", row, label);
+        let dat = read_nsw_sales_dat(raw.into_reader()?, &name, &source_id)?;
+        let row = i32::try_from(row).ok();
+        return Ok(dat
+            .records
+            .into_iter()
+            .find(|record| record.source_metadata.source_row == row));
+    }
+
+    let csv_path = raw.as_file_path()?;
+    info!("Parsing row {} of NSW sales CSV {:?}", row, csv_path);
 
-    Ok(records)
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(csv_path)?;
+
+    match reader.deserialize::<NswSalesRow>().nth(row) {
+        Some(result) => {
+            let record = parse_nsw_row(result?, &source_id)?;
+            Ok(Some(with_source_row(record, row)))
+        }
+        None => Ok(None),
+    }
+}
+
+fn with_source_row(mut record: PropertyRecord, row: usize) -> PropertyRecord {
+    record.source_metadata.source_row = i32::try_from(row).ok();
+    record
+}
+
+/// Fold records sharing an external_id into one of the latest sale, the
+/// earlier sales becoming its prior_sales
+///
+/// The NSW archives repeat a property for every sale in its history. The
+/// latest is the one with the latest sale date, a tie going to the higher
+/// price as the likelier correction; a price given for a date that has a
+/// higher one is dropped the same way. Records keep the place of their
+/// property's first row, and those without an external_id are left alone.
+pub fn collapse_repeat_sales(records: Vec<PropertyRecord>) -> Vec<PropertyRecord> {
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut collapsed: Vec<PropertyRecord> = Vec::with_capacity(records.len());
+    for mut record in records {
+        let Some(id) = record.external_id.clone() else {
+            collapsed.push(record);
+            continue;
+        };
+        let Some(&i) = positions.get(&id) else {
+            positions.insert(id, collapsed.len());
+            collapsed.push(record);
+            continue;
+        };
+        let kept = &mut collapsed[i];
+        if (record.sale_date, record.sale_price) > (kept.sale_date, kept.sale_price) {
+            std::mem::swap(kept, &mut record);
+        }
+        // `record` is now the earlier sale
+        kept.prior_sales.append(&mut record.prior_sales);
+        if let (Some(price), Some(date)) = (record.sale_price, record.sale_date) {
+            kept.prior_sales.push((price, date));
+        }
+    }
+
+    for record in &mut collapsed {
+        if record.prior_sales.is_empty() {
+            continue;
+        }
+        let latest = record.sale_date;
+        record.prior_sales.retain(|(_, date)| Some(*date) != latest);
+        // Latest first, so a date's highest price is the one kept
+        record.prior_sales.sort_by_key(|&(price, date)| std::cmp::Reverse((date, price)));
+        record.prior_sales.dedup_by_key(|(_, date)| *date);
+        record.prior_sales.reverse();
+    }
+    collapsed
 }
 
-fn parse_nsw_row(row: NswSalesRow, source_id: &str) -> Result<PropertyRecord> {
-    // Parse price (remove $ and commas)
-    let price_str = row
-        .purchase_price
-        .replace("$", "")
-        .replace(",", "")
-        .trim()
-        .to_string();
-    let sale_price = price_str.parse::<i32>().ok();
+/// The record in a CSV row; a price column holding anything but a price or a
+/// blank is an error, as the rest of the row can't be trusted either
+fn parse_nsw_row(row: NswSalesRow, source_id: &str) -> Result<PropertyRecord, PriceParseError> {
+    let sale_price = parse_price(&row.purchase_price)?;
 
-    // Parse settlement date (DD/MM/YYYY)
-    let sale_date = parse_date(&row.settlement_date);
+    // Parse settlement date (DD/MM/YYYY, or a format a spreadsheet left it in)
+    let settlement_date = read_date(&row.settlement_date);
+    let sale_date = settlement_date.map(|read| read.date);
+    let mut confidence_score = 0.9; // High confidence for government data
+    if settlement_date.is_some_and(|read| read.fallback) {
+        confidence_score -= FALLBACK_DATE_PENALTY;
+    }
 
     // Format address
     let address = format_nsw_address(
@@ -118,7 +601,8 @@ fn parse_nsw_row(row: NswSalesRow, source_id: &str) -> Result<PropertyRecord> {
         address,
         suburb: row.property_locality,
         state: State::NSW,
-        postcode: Some(row.property_post_code),
+        postcode: normalize_postcode(&row.property_post_code, State::NSW),
+        district: row.district_code.as_deref().and_then(normalize_district),
         property_type,
         bedrooms: None, // Will be estimated in enrichment
         bathrooms: None,
@@ -126,7 +610,10 @@ fn parse_nsw_row(row: NswSalesRow, source_id: &str) -> Result<PropertyRecord> {
         sale_price,
         sale_date,
         weekly_rent: None, // Will be matched in enrichment
+        rent_frequency: None,
         rental_yield: None,
+        price_per_sqm: None,
+        prior_sales: Vec::new(),
         latitude: None,
         longitude: None,
         source_metadata: SourceMetadata {
@@ -134,7 +621,12 @@ fn parse_nsw_row(row: NswSalesRow, source_id: &str) -> Result<PropertyRecord> {
             data_quality: DataQuality::Individual,
             fetched_at: Utc::now(),
             is_rental_estimated: false,
-            confidence_score: 0.9, // High confidence for government data
+            is_bedrooms_estimated: false,
+            rental_period: None,
+            rental_match_method: None,
+            source_file: None,
+            source_row: None,
+            confidence_score,
         },
     })
 }
@@ -144,127 +636,3276 @@ fn parse_date(date_str: &str) -> Option<NaiveDate> {
     NaiveDate::parse_from_str(date_str, "%d/%m/%Y").ok()
 }
 
-/// Parse NSW rental bond XLSX into RentalMedian structs
-pub async fn parse_nsw_rentals(raw: RawData, period: NaiveDate) -> Result<Vec<RentalMedian>> {
-    let bytes = raw.as_bytes()?;
-    info!("Parsing NSW rental bond XLSX ({} bytes)", bytes.len());
+/// Two-digit years below this are 20xx, the rest 19xx
+const TWO_DIGIT_YEAR_PIVOT: i32 = 50;
 
-    let cursor = Cursor::new(bytes);
-    let mut workbook = open_workbook_auto_from_rs(cursor)?;
+/// Earliest year a parsed date may fall in, so "5-3-24" isn't taken as 24 AD
+const MIN_DATE_YEAR: i32 = 1900;
 
-    // NSW rental bond files typically have data in first sheet
-    let sheet_names = workbook.sheet_names();
-    if sheet_names.is_empty() {
-        return Err(anyhow::anyhow!("No sheets found in workbook"));
+/// Largest Excel serial number, 31 December 9999
+const MAX_EXCEL_SERIAL: f64 = 2_958_465.0;
+
+/// Formats tried, in order, once a date isn't in DD/MM/YYYY or D/M/YY
+const FALLBACK_DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d", "%Y/%m/%d", "%d-%m-%Y", "%d.%m.%Y", "%d-%b-%Y", "%d %b %Y", "%d %B %Y", "%Y%m%d",
+];
+
+/// Confidence taken off a sale whose date needed a fallback format
+const FALLBACK_DATE_PENALTY: f32 = 0.1;
+
+/// A date read by `read_date`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FlexibleDate {
+    date: NaiveDate,
+    /// Whether it was in some format other than DD/MM/YYYY
+    fallback: bool,
+}
+
+/// Parse a date in DD/MM/YYYY, or else in one of the formats files that have
+/// been through a spreadsheet turn up with: D/M/YY, ISO, "05-Mar-2024",
+/// YYYYMMDD, an Excel serial number, or any of those with a time after them
+pub fn parse_flexible_date(date_str: &str) -> Option<NaiveDate> {
+    read_date(date_str).map(|read| read.date)
+}
+
+fn read_date(date_str: &str) -> Option<FlexibleDate> {
+    let trimmed = date_str.trim();
+    let date_str = without_time(trimmed);
+    let timed = date_str.len() < trimmed.len();
+
+    if let Some(read) = slashed_date(date_str) {
+        return Some(FlexibleDate {
+            fallback: read.fallback || timed,
+            ..read
+        });
     }
+    let date = FALLBACK_DATE_FORMATS
+        .iter()
+        .filter_map(|format| NaiveDate::parse_from_str(date_str, format).ok())
+        .find(|date| date.year() >= MIN_DATE_YEAR)
+        .or_else(|| excel_serial_to_date(serial_number(date_str)?))?;
+    Some(FlexibleDate {
+        date,
+        fallback: true,
+    })
+}
 
-    let sheet_name = &sheet_names[0];
-    info!("Reading sheet: {}", sheet_name);
+/// The date before a time of day like "0:00" or "T10:30:00", if there is one
+fn without_time(date_str: &str) -> &str {
+    match date_str.rsplit_once([' ', 'T']) {
+        Some((date, time)) if time.contains(':') => date.trim_end(),
+        _ => date_str,
+    }
+}
 
-    let range = workbook.worksheet_range(sheet_name)?;
+/// A day-first date with a four or two-digit year. All-two-digit dates whose
+/// every part could be the month, like "03/04/05", are None: day-first,
+/// month-first and year-first files would each read them differently.
+fn slashed_date(date_str: &str) -> Option<FlexibleDate> {
+    let parts: Vec<&str> = date_str.split('/').collect();
+    let [day, month, year] = parts[..] else {
+        return None;
+    };
+    let digits = |part: &str, max_len| {
+        (1..=max_len).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_digit())
+    };
+    if !digits(day, 2) || !digits(month, 2) || !digits(year, 4) {
+        return None;
+    }
+    let (d, m, y): (u32, u32, i32) = (day.parse().ok()?, month.parse().ok()?, year.parse().ok()?);
+    let (year, fallback) = match year.len() {
+        4 => (y, false),
+        2 if d <= 12 && m <= 12 && y <= 12 => return None,
+        2 if y < TWO_DIGIT_YEAR_PIVOT => (2000 + y, true),
+        2 => (1900 + y, true),
+        _ => return None,
+    };
+    let date = NaiveDate::from_ymd_opt(year, m, d)?;
+    Some(FlexibleDate { date, fallback })
+}
 
-    let mut rentals = Vec::new();
+/// A five-digit whole number, optionally with a fraction: the span of Excel
+/// serials from 1927 to 2173, leaving shorter numbers like a bare year alone
+fn serial_number(date_str: &str) -> Option<f64> {
+    let whole = date_str
+        .split_once('.')
+        .map_or(date_str, |(whole, _)| whole);
+    if whole.len() != 5 || !whole.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    date_str.parse().ok()
+}
 
-    // Skip header row (assuming first row is headers)
-    for (idx, row) in range.rows().enumerate().skip(1) {
-        if row.len() < 4 {
-            continue; // Skip incomplete rows
-        }
+/// The date of an Excel serial number, 1 being 1 January 1900, with any time
+/// of day dropped. Excel has a 29 February 1900, so serial 60 is None and
+/// later serials are a day ahead of a plain count.
+pub fn excel_serial_to_date(serial: f64) -> Option<NaiveDate> {
+    if !(1.0..MAX_EXCEL_SERIAL + 1.0).contains(&serial) {
+        return None;
+    }
+    let days = serial.floor() as i64;
+    let epoch = match days {
+        60 => return None,
+        1..=59 => NaiveDate::from_ymd_opt(1899, 12, 31)?,
+        _ => NaiveDate::from_ymd_opt(1899, 12, 30)?,
+    };
+    epoch.checked_add_signed(chrono::Duration::days(days))
+}
 
-        // Expected columns: Postcode, Suburb, Bedrooms, Median Weekly Rent
-        // Note: Actual format may vary, adjust as needed
-        let postcode = match &row[0] {
-            Data::String(s) => s.trim().to_string(),
-            Data::Int(i) => i.to_string(),
-            Data::Float(f) => format!("{:.0}", f),
-            _ => continue,
-        };
+/// The date in an XLSX cell: a date cell, a serial number, or text
+/// `parse_flexible_date` reads
+fn cell_date(cell: &Data) -> Option<NaiveDate> {
+    match cell {
+        Data::DateTime(date) => excel_serial_to_date(date.as_f64()),
+        Data::Float(f) => excel_serial_to_date(*f),
+        Data::Int(i) => excel_serial_to_date(*i as f64),
+        Data::String(s) | Data::DateTimeIso(s) => parse_flexible_date(s),
+        _ => None,
+    }
+}
 
-        let suburb = match &row[1] {
-            Data::String(s) => Some(s.trim().to_string()),
-            _ => None,
-        };
+/// Fields of a B (sale) row in a Valuer General .DAT file, counting the record
+/// type; any after them are ignored
+const DAT_SALE_FIELDS: usize = 24;
 
-        let bedrooms = match &row[2] {
-            Data::Int(i) => *i as i32,
-            Data::Float(f) => *f as i32,
-            Data::String(s) => match s.trim().parse::<i32>() {
-                Ok(b) => b,
-                Err(_) => continue,
-            },
-            _ => continue,
+/// Parse NSW Valuer General bulk sales .DAT files into PropertyRecord structs
+///
+/// The files are semicolon-delimited with a record type first. A B row is a
+/// property's sale, and C rows carry on the address of the B row before them
+/// where it runs over. A (header), D (purchaser and vendor) and Z (trailer)
+/// rows are passed over.
+///
+/// The records are those the CSV path makes of the same sale, plus the land
+/// area and a property type from the nature of property and zoning; the
+/// contract date stands in for a missing settlement date. A property's repeat
+/// sales within a file are collapsed into one record, as by `collapse_repeat_sales`.
+pub async fn parse_nsw_sales_dat(raw: RawData, source_id: String) -> Result<ParseReport> {
+    let mut report = ParseReport {
+        records: Vec::new(),
+        errors: Vec::new(),
+        total_rows: 0,
+        blank_prices: 0,
+        implausible_prices: 0,
+        rejected_postcodes: 0,
+    };
+    for source in single_sources(raw) {
+        let (name, label) = source_names(&source);
+        info!("Parsing NSW sales DAT from {}", label);
+        let file = read_nsw_sales_dat(source.into_reader()?, &name, &source_id)?;
+        info!(
+            "Parsed {} records from {} ({} errors)",
+            file.records.len(),
+            label,
+            file.errors.len()
+        );
+        let records = collapse_repeat_sales(file.records);
+        report.implausible_prices += flag_implausible_prices(&records);
+        report.records.extend(records);
+        report.errors.extend(file.errors);
+        report.total_rows += file.total_rows;
+        report.blank_prices += file.blank_prices;
+        report.rejected_postcodes += file.rejected_postcodes;
+    }
+
+    Ok(report)
+}
+
+/// Each file of `Files` on its own, or else `raw` itself
+fn single_sources(raw: RawData) -> Vec<RawData> {
+    match raw {
+        RawData::Files(paths) => paths.into_iter().map(RawData::File).collect(),
+        raw => vec![raw],
+    }
+}
+
+/// The file name for row errors and a label for logs
+fn source_names(raw: &RawData) -> (String, String) {
+    match raw {
+        RawData::File(path) => (
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            format!("{:?}", path),
+        ),
+        _ => ("in-memory CSV".to_string(), "in-memory CSV".to_string()),
+    }
+}
+
+fn is_dat_source(raw: &RawData) -> bool {
+    matches!(raw, RawData::File(path) if is_dat_name(&path.to_string_lossy()))
+}
+
+/// Every sale in one .DAT file; a sale's source_row is its place among the B rows
+fn read_nsw_sales_dat(dat: impl Read, name: &str, source_id: &str) -> Result<ParseReport> {
+    let mut report = ParseReport {
+        records: Vec::new(),
+        errors: Vec::new(),
+        total_rows: 0,
+        blank_prices: 0,
+        implausible_prices: 0,
+        rejected_postcodes: 0,
+    };
+    // Property ID and sale counter of the last B row, and the sale it made if it parsed
+    let mut current: Option<(String, String)> = None;
+    let mut pending: Option<DatSale> = None;
+    let mut sales = 0;
+
+    for (i, line) in std::io::BufReader::new(dat).split(b'\n').enumerate() {
+        let error = |column: Option<&str>, message: String| RowError {
+            source: name.to_string(),
+            row_number: i + 1,
+            column: column.map(str::to_string),
+            message,
         };
+        let line = match String::from_utf8(line?) {
+            Ok(line) => line,
+            Err(e) => {
+                report.total_rows += 1;
+                report.errors.push(error(None, e.to_string()));
+                continue;
+            }
+        };
+        let fields: Vec<&str> = line.trim_end_matches('\r').split(';').collect();
 
-        let median_rent = match &row[3] {
-            Data::Int(i) => *i as i32,
-            Data::Float(f) => *f as i32,
-            Data::String(s) => {
-                let clean = s.replace("$", "").replace(",", "").trim().to_string();
-                match clean.parse::<i32>() {
-                    Ok(r) => r,
-                    Err(_) => continue,
+        match fields[0] {
+            "B" => {
+                report.total_rows += 1;
+                if let Some(sale) = pending.take() {
+                    push_dat_sale(&mut report, sale, source_id)?;
+                }
+                current = Some((field(&fields, 2).to_string(), field(&fields, 3).to_string()));
+                match DatSale::from_fields(&fields, sales) {
+                    Ok(sale) => pending = Some(sale),
+                    Err((column, message)) => report.errors.push(error(column, message)),
                 }
+                sales += 1;
             }
-            _ => continue,
-        };
+            "C" => {
+                report.total_rows += 1;
+                let key = (field(&fields, 2), field(&fields, 3));
+                let continues = current
+                    .as_ref()
+                    .is_some_and(|(id, counter)| (id.as_str(), counter.as_str()) == key);
+                if !continues {
+                    let message = format!("continues property {} but follows no B row for it", key.0);
+                    report.errors.push(error(None, message));
+                } else if let Some(sale) = &mut pending {
+                    // A C row of a B row that failed is dropped with it
+                    sale.street_name.push(' ');
+                    sale.street_name.push_str(field(&fields, 5));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(sale) = pending {
+        push_dat_sale(&mut report, sale, source_id)?;
+    }
+    report.blank_prices = report.records.iter().filter(|r| r.sale_price.is_none()).count();
 
-        rentals.push(RentalMedian {
-            state: State::NSW,
-            postcode,
-            suburb,
-            bedrooms,
-            median_weekly_rent: median_rent,
-            sample_size: None,
-            period,
-        });
+    for error in report.errors.iter().take(10) {
+        warn!("Failed to parse line {} of {}: {}", error.row_number, name, error.message);
     }
+    Ok(report)
+}
 
-    info!("Parsed {} rental medians from XLSX", rentals.len());
+/// Add a sale's record, counting its postcode if it was dropped
+fn push_dat_sale(report: &mut ParseReport, sale: DatSale, source_id: &str) -> Result<()> {
+    let postcode_given = !sale.post_code.is_empty();
+    let record = sale.into_record(source_id)?;
+    if postcode_given && record.postcode.is_none() {
+        report.rejected_postcodes += 1;
+    }
+    report.records.push(record);
+    Ok(())
+}
 
-    Ok(rentals)
+/// A field of a split .DAT row, trimmed; empty when the row is short
+fn field<'a>(fields: &[&'a str], i: usize) -> &'a str {
+    fields.get(i).map_or("", |f| f.trim())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// A B row of a .DAT file, its street name grown by any C rows after it
+struct DatSale {
+    /// Place among the file's B rows
+    index: usize,
+    district_code: String,
+    property_id: String,
+    unit_number: String,
+    house_number: String,
+    street_name: String,
+    locality: String,
+    post_code: String,
+    sale_price: Option<i64>,
+    sale_date: Option<NaiveDate>,
+    land_area_sqm: Option<Decimal>,
+    property_type: PropertyType,
+}
 
-    #[test]
-    fn test_parse_date() {
-        assert_eq!(
-            parse_date("25/12/2023"),
-            Some(NaiveDate::from_ymd_opt(2023, 12, 25).unwrap())
-        );
+impl DatSale {
+    /// The sale in a B row's fields, or the column and problem that stop it
+    fn from_fields(fields: &[&str], index: usize) -> Result<Self, (Option<&'static str>, String)> {
+        if fields.len() < DAT_SALE_FIELDS {
+            return Err((
+                None,
+                format!("found {} fields, expected at least {}", fields.len(), DAT_SALE_FIELDS),
+            ));
+        }
+        let f = |i| field(fields, i);
 
-        assert_eq!(
-            parse_date("01/01/2024"),
-            Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())
-        );
+        let date = |i, column| match f(i) {
+            "" => Ok(None),
+            s => NaiveDate::parse_from_str(s, "%Y%m%d")
+                .map(Some)
+                .map_err(|_| (Some(column), format!("not a date: {:?}", s))),
+        };
+        let contract_date = date(13, "Contract date")?;
+        let settlement_date = date(14, "Settlement date")?;
 
-        assert_eq!(parse_date("invalid"), None);
+        let sale_price =
+            parse_price(f(15)).map_err(|e| (Some("Purchase price"), e.to_string()))?;
+
+        // Area is in square metres, or hectares when its type is H
+        let land_area_sqm = match f(11) {
+            "" => None,
+            s => {
+                let area = s
+                    .parse::<Decimal>()
+                    .map_err(|_| (Some("Area"), format!("not an area: {:?}", s)))?;
+                Some(if f(12) == "H" { area * Decimal::from(10_000) } else { area })
+            }
+        };
+
+        // Nature of property is R (residence), V (vacant land) or 3 (other)
+        let property_type = match f(17) {
+            "V" => PropertyType::VacantLand,
+            "R" if !f(6).is_empty() || !f(19).is_empty() => PropertyType::Unit,
+            "R" => PropertyType::House,
+            _ if f(16).starts_with('B') => PropertyType::Commercial,
+            _ => parse_nsw_property_type(f(18)),
+        };
+
+        Ok(DatSale {
+            index,
+            district_code: f(1).to_string(),
+            property_id: f(2).to_string(),
+            unit_number: f(6).to_string(),
+            house_number: f(7).to_string(),
+            street_name: f(8).to_string(),
+            locality: f(9).to_string(),
+            post_code: f(10).to_string(),
+            sale_price,
+            sale_date: settlement_date.or(contract_date),
+            land_area_sqm,
+            property_type,
+        })
     }
 
-    #[test]
-    fn test_parse_nsw_row() {
+    /// The record the CSV path makes of the same sale, with the DAT's extras
+    fn into_record(self, source_id: &str) -> Result<PropertyRecord> {
         let row = NswSalesRow {
-            property_id: "12345".to_string(),
-            property_unit_number: None,
-            property_house_number: Some("10".to_string()),
-            property_street_name: "Smith Street".to_string(),
-            property_locality: "Sydney".to_string(),
-            property_post_code: "2000".to_string(),
-            purchase_price: "$750,000".to_string(),
-            settlement_date: "15/06/2023".to_string(),
+            property_id: self.property_id,
+            property_unit_number: Some(self.unit_number),
+            property_house_number: Some(self.house_number),
+            property_street_name: self.street_name,
+            property_locality: self.locality,
+            property_post_code: self.post_code,
+            purchase_price: self.sale_price.map(|p| p.to_string()).unwrap_or_default(),
+            settlement_date: self
+                .sale_date
+                .map(|d| d.format("%d/%m/%Y").to_string())
+                .unwrap_or_default(),
             contract_date: None,
-            nature_of_property: "Residential - House".to_string(),
+            nature_of_property: String::new(),
+            district_code: Some(self.district_code),
         };
+        let mut record = parse_nsw_row(row, source_id)?;
+        record.property_type = self.property_type;
+        record.land_area_sqm = self.land_area_sqm;
+        Ok(with_source_row(record, self.index))
+    }
+}
 
-        let record = parse_nsw_row(row, "nsw_sales").unwrap();
+/// One sale from the NSW recent sales JSON API
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NswSalesApiRecord {
+    /// Same identifier as the bulk files' "Property ID"
+    property_id: String,
+    address: NswSalesApiAddress,
+    nature_of_property: Option<String>,
+    /// A number, or a string such as "750000.00", read as `parse_price` reads
+    /// the bulk files' prices
+    purchase_price: Option<serde_json::Value>,
+    settlement_date: Option<NaiveDate>,
+    area_sqm: Option<Decimal>,
+    /// Only read by the fetch step, for the watermark, but required here too
+    #[allow(dead_code)]
+    updated_at: DateTime<Utc>,
+}
 
-        assert_eq!(record.address, "10 Smith Street");
-        assert_eq!(record.suburb, "Sydney");
-        assert_eq!(record.state, State::NSW);
-        assert_eq!(record.postcode, Some("2000".to_string()));
-        assert_eq!(record.sale_price, Some(750_000));
-        assert_eq!(record.property_type, PropertyType::House);
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NswSalesApiAddress {
+    unit_number: Option<String>,
+    house_number: Option<String>,
+    street_name: String,
+    locality: String,
+    postcode: Option<String>,
+}
+
+/// Source name of NSW sales API row errors, whose row numbers count the
+/// records from 1
+const NSW_SALES_API_SOURCE: &str = "NSW sales API";
+
+/// Parse the records collected from the NSW sales JSON API into PropertyRecord structs
+pub async fn parse_nsw_sales_api(raw: RawData, source_id: String) -> Result<ParseReport> {
+    let bytes = raw.as_json()?;
+    let sales = parse_json_array_stream::<NswSalesApiRecord>(bytes)
+        .map_err(|e| anyhow::anyhow!("{} of NSW sales", e))?;
+    info!("Parsing {} bytes of NSW sales API records", bytes.len());
+
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    let mut total_rows = 0;
+
+    for sale in sales {
+        total_rows += 1;
+        let (column, message) = match sale {
+            Ok(sale) => match nsw_sales_api_record(sale, &source_id) {
+                Ok(record) => {
+                    records.push(record);
+                    continue;
+                }
+                Err(e) => (Some("purchasePrice".to_string()), e.to_string()),
+            },
+            Err(e) => (None, e.to_string()),
+        };
+        if errors.len() < 10 {
+            warn!("Failed to parse API record {}: {}", total_rows, message);
+        }
+        errors.push(RowError {
+            source: NSW_SALES_API_SOURCE.to_string(),
+            row_number: total_rows,
+            column,
+            message,
+        });
+    }
+
+    let blank_prices = records.iter().filter(|r| r.sale_price.is_none()).count();
+    let implausible_prices = flag_implausible_prices(&records);
+    info!(
+        "Parsed {} records from NSW sales API ({} unpriced, {} errors)",
+        records.len(),
+        blank_prices,
+        errors.len()
+    );
+
+    Ok(ParseReport {
+        blank_prices,
+        implausible_prices,
+        records,
+        errors,
+        total_rows,
+        rejected_postcodes: 0,
+    })
+}
+
+/// The record of an API sale, or why its price couldn't be read
+fn nsw_sales_api_record(
+    sale: NswSalesApiRecord,
+    source_id: &str,
+) -> Result<PropertyRecord, PriceParseError> {
+    let sale_price = match sale.purchase_price {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(text)) => parse_price(&text)?,
+        Some(value) => parse_price(&value.to_string())?,
+    };
+    let address = format_nsw_address(
+        sale.address.unit_number.as_deref(),
+        sale.address.house_number.as_deref(),
+        &sale.address.street_name,
+    );
+    let property_type = parse_nsw_property_type(sale.nature_of_property.as_deref().unwrap_or(""));
+
+    Ok(PropertyRecord {
+        external_id: Some(sale.property_id),
+        address,
+        suburb: sale.address.locality,
+        state: State::NSW,
+        postcode: sale.address.postcode.filter(|p| !p.trim().is_empty()),
+        district: None,
+        property_type,
+        bedrooms: None, // Will be estimated in enrichment
+        bathrooms: None,
+        land_area_sqm: sale.area_sqm,
+        sale_price,
+        sale_date: sale.settlement_date,
+        weekly_rent: None, // Will be matched in enrichment
+        rent_frequency: None,
+        rental_yield: None,
+        price_per_sqm: None,
+        prior_sales: Vec::new(),
+        latitude: None,
+        longitude: None,
+        source_metadata: SourceMetadata {
+            source_id: source_id.to_string(),
+            data_quality: DataQuality::Individual,
+            fetched_at: Utc::now(),
+            is_rental_estimated: false,
+            is_bedrooms_estimated: false,
+            rental_period: None,
+            rental_match_method: None,
+            source_file: None,
+            source_row: None,
+            confidence_score: 0.9, // Same register as the bulk files
+        },
+    })
+}
+
+/// Confidence of a listing whose asking price is a single figure
+const LISTING_EXACT_CONFIDENCE: f32 = 0.9;
+/// Confidence of a listing priced as a range, taken at its middle
+const LISTING_MIDPOINT_CONFIDENCE: f32 = 0.7;
+/// Confidence of a listing priced only as a floor ("Offers over $1.2m")
+const LISTING_LOWER_BOUND_CONFIDENCE: f32 = 0.6;
+
+/// One listing from the Domain residential search
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DomainListing {
+    id: u64,
+    /// "Sale" or "Rent"
+    listing_type: String,
+    price_details: Option<DomainPriceDetails>,
+    property_details: DomainPropertyDetails,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DomainPriceDetails {
+    display_price: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DomainPropertyDetails {
+    state: Option<String>,
+    property_type: Option<String>,
+    bedrooms: Option<f64>,
+    bathrooms: Option<f64>,
+    unit_number: Option<String>,
+    street_number: Option<String>,
+    street: Option<String>,
+    suburb: Option<String>,
+    postcode: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    land_area: Option<f64>,
+}
+
+/// Parse the listings collected from the Domain search into listing records
+///
+/// Bedrooms, bathrooms and coordinates are the agent's own. The asking price
+/// is read from the display text; a range is taken at its middle and a floor
+/// as it stands, each with less confidence than a single figure. A rental's
+/// asking rent becomes its weekly rent. Listings without a dollar figure
+/// ("Contact agent") are passed over; one listed twice is kept once.
+pub async fn parse_domain_listings(raw: RawData, source_id: String) -> Result<ParseReport> {
+    let bytes = raw.as_json()?;
+    let listings = parse_json_array_stream::<DomainListing>(bytes)
+        .map_err(|e| anyhow::anyhow!("{} of Domain listings", e))?;
+    info!("Parsing {} bytes of Domain listings", bytes.len());
+
+    let mut records = Vec::new();
+    let mut seen = HashSet::new();
+    let mut errors = Vec::new();
+    let mut total_rows = 0;
+
+    for (i, listing) in listings.enumerate() {
+        total_rows += 1;
+        let error = |column: Option<&str>, message: String| RowError {
+            source: "Domain listings".to_string(),
+            row_number: i + 1,
+            column: column.map(str::to_string),
+            message,
+        };
+        let listing = match listing {
+            Ok(listing) => listing,
+            Err(e) => {
+                errors.push(error(None, e.to_string()));
+                continue;
+            }
+        };
+        match domain_listing_record(listing, &source_id) {
+            Ok(Some(record)) => {
+                if seen.insert(record.external_id.clone()) {
+                    records.push(record);
+                }
+            }
+            Ok(None) => {}
+            Err((column, message)) => errors.push(error(Some(column), message)),
+        }
+    }
+
+    for error in errors.iter().take(10) {
+        warn!(
+            "Failed to parse Domain listing {}: {}",
+            error.row_number, error.message
+        );
+    }
+    info!(
+        "Parsed {} Domain listings ({} of {} failed)",
+        records.len(),
+        errors.len(),
+        total_rows
+    );
+
+    Ok(ParseReport {
+        records,
+        errors,
+        total_rows,
+        blank_prices: 0,
+        implausible_prices: 0,
+        rejected_postcodes: 0,
+    })
+}
+
+/// The listing as a record, None when it gives no usable price, or the field
+/// and problem when it can't be placed
+fn domain_listing_record(
+    listing: DomainListing,
+    source_id: &str,
+) -> Result<Option<PropertyRecord>, (&'static str, String)> {
+    let details = listing.property_details;
+    let text = |field: Option<String>| {
+        field
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+
+    let state = match text(details.state) {
+        Some(state) => state
+            .parse::<State>()
+            .map_err(|_| ("propertyDetails.state", format!("not a state: {:?}", state)))?,
+        None => return Err(("propertyDetails.state", "no state".to_string())),
+    };
+    let Some(suburb) = text(details.suburb) else {
+        return Err(("propertyDetails.suburb", "no suburb".to_string()));
+    };
+    let address = format_nsw_address(
+        details.unit_number.as_deref(),
+        details.street_number.as_deref(),
+        details.street.as_deref().unwrap_or(""),
+    );
+    if address.is_empty() {
+        return Err(("propertyDetails.street", "no street address".to_string()));
+    }
+
+    let display_price = listing
+        .price_details
+        .and_then(|p| p.display_price)
+        .unwrap_or_default();
+    let Some(price) = parse_asking_price(&display_price) else {
+        debug!(
+            "No asking price in {:?} for Domain listing {}",
+            display_price, listing.id
+        );
+        return Ok(None);
+    };
+    let (sale_price, weekly_rent, rent_frequency) = match listing.listing_type.as_str() {
+        "Sale" => (Some(i64::from(price.amount())), None, None),
+        "Rent" => {
+            let frequency = parse_rent_frequency(&display_price);
+            let quoted = (frequency != RentFrequency::Weekly).then_some(frequency);
+            (None, Some(frequency.to_weekly(price.amount())), quoted)
+        }
+        other => {
+            debug!("Skipping Domain {} listing {}", other, listing.id);
+            return Ok(None);
+        }
+    };
+    let confidence_score = match price {
+        AskingPrice::Exact(_) => LISTING_EXACT_CONFIDENCE,
+        AskingPrice::Midpoint(_) => LISTING_MIDPOINT_CONFIDENCE,
+        AskingPrice::LowerBound(_) => LISTING_LOWER_BOUND_CONFIDENCE,
+    };
+    let decimal = |value: Option<f64>| value.and_then(Decimal::from_f64);
+
+    Ok(Some(PropertyRecord {
+        external_id: Some(format!("domain:{}", listing.id)),
+        address,
+        suburb,
+        state,
+        postcode: text(details.postcode),
+        district: None,
+        property_type: parse_domain_property_type(details.property_type.as_deref().unwrap_or("")),
+        bedrooms: details.bedrooms.map(|n| n as i32),
+        bathrooms: details.bathrooms.map(|n| n as i32),
+        land_area_sqm: decimal(details.land_area.filter(|&area| area > 0.0)),
+        sale_price,
+        sale_date: None, // Still on the market
+        weekly_rent,
+        rent_frequency,
+        rental_yield: None,
+        price_per_sqm: None,
+        prior_sales: Vec::new(),
+        latitude: decimal(details.latitude),
+        longitude: decimal(details.longitude),
+        source_metadata: SourceMetadata {
+            source_id: source_id.to_string(),
+            data_quality: DataQuality::Listing,
+            fetched_at: Utc::now(),
+            is_rental_estimated: false,
+            is_bedrooms_estimated: false,
+            rental_period: None,
+            rental_match_method: None,
+            source_file: None,
+            source_row: None,
+            confidence_score,
+        },
+    }))
+}
+
+/// Parse a WA Landgate sales extract (CSV) into PropertyRecord structs
+///
+/// Columns are located by header name. Landgate writes dates as "15-Mar-2024"
+/// and puts lot and plan numbers in the address fields; those are taken out
+/// of the display address and, where both are given, become the external_id
+/// ("wa_lot:502:30012"), falling back to the Landgate ID. The strata
+/// indicator and land use give the property type.
+///
+/// Rows with a field that can't be read are reported as row errors. A blank
+/// price, or a marker like "POA", leaves the record without one.
+pub async fn parse_wa_sales(raw: RawData, source_id: String) -> Result<ParseReport> {
+    let (name, label) = source_names(&raw);
+    info!("Parsing WA Landgate sales CSV from {}", label);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(raw.into_reader()?);
+    let columns = WaSalesColumns::from_header(reader.headers()?).map_err(|missing| {
+        anyhow::anyhow!("No {} column in WA sales header", missing.join(", "))
+    })?;
+
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    let mut total_rows = 0;
+
+    for result in reader.records() {
+        total_rows += 1;
+        let (row_number, outcome) = match result {
+            Ok(row) => (
+                row.position().map_or(0, |p| p.line() as usize),
+                columns.record(&row, &source_id),
+            ),
+            Err(e) => (
+                e.position().map_or(0, |p| p.line() as usize),
+                Err((None, e.to_string())),
+            ),
+        };
+        match outcome {
+            Ok(record) => records.push(with_source_row(record, total_rows - 1)),
+            Err((column, message)) => errors.push(RowError {
+                source: name.clone(),
+                row_number,
+                column: column.map(str::to_string),
+                message,
+            }),
+        }
+    }
+
+    for error in errors.iter().take(10) {
+        warn!(
+            "Failed to parse line {} of {}: {}",
+            error.row_number, error.source, error.message
+        );
+    }
+    let blank_prices = records.iter().filter(|r| r.sale_price.is_none()).count();
+    let implausible_prices = flag_implausible_prices(&records);
+    info!(
+        "Parsed {} WA sales from {} rows ({} unpriced, {} errors)",
+        records.len(),
+        total_rows,
+        blank_prices,
+        errors.len()
+    );
+
+    Ok(ParseReport {
+        blank_prices,
+        implausible_prices,
+        records,
+        errors,
+        total_rows,
+        rejected_postcodes: 0,
+    })
+}
+
+/// Header names each Landgate column has been published under
+const WA_ID_HEADERS: &[&str] = &["Landgate ID", "Land ID"];
+const WA_UNIT_HEADERS: &[&str] = &["Unit No", "Unit Number"];
+const WA_HOUSE_HEADERS: &[&str] = &["House No", "House Number", "Street Number"];
+const WA_STREET_HEADERS: &[&str] = &["Street Name", "Street"];
+const WA_SUBURB_HEADERS: &[&str] = &["Suburb", "Locality"];
+const WA_POSTCODE_HEADERS: &[&str] = &["Postcode", "Post Code"];
+const WA_DATE_HEADERS: &[&str] = &["Contract Date", "Sale Date"];
+const WA_PRICE_HEADERS: &[&str] = &["Sale Price", "Price"];
+const WA_LAND_USE_HEADERS: &[&str] = &["Land Use", "Property Use"];
+const WA_STRATA_HEADERS: &[&str] = &["Strata Lot", "Strata", "Strata Indicator"];
+const WA_AREA_HEADERS: &[&str] = &["Land Area (m2)", "Land Area", "Area (m2)"];
+
+/// Column positions in the Landgate sales extract
+#[derive(Debug, PartialEq, Eq)]
+struct WaSalesColumns {
+    landgate_id: Option<usize>,
+    unit: Option<usize>,
+    house_number: Option<usize>,
+    street_name: usize,
+    suburb: usize,
+    postcode: Option<usize>,
+    contract_date: usize,
+    sale_price: usize,
+    land_use: Option<usize>,
+    strata: Option<usize>,
+    land_area: Option<usize>,
+}
+
+impl WaSalesColumns {
+    /// The columns of the header, or the names of the required ones it lacks
+    fn from_header(header: &csv::StringRecord) -> Result<Self, Vec<&'static str>> {
+        let find = |aliases: &[&str]| {
+            header
+                .iter()
+                .position(|name| aliases.iter().any(|a| name.trim().eq_ignore_ascii_case(a)))
+        };
+
+        let street_name = find(WA_STREET_HEADERS);
+        let suburb = find(WA_SUBURB_HEADERS);
+        let contract_date = find(WA_DATE_HEADERS);
+        let sale_price = find(WA_PRICE_HEADERS);
+        match (street_name, suburb, contract_date, sale_price) {
+            (Some(street_name), Some(suburb), Some(contract_date), Some(sale_price)) => {
+                Ok(WaSalesColumns {
+                    landgate_id: find(WA_ID_HEADERS),
+                    unit: find(WA_UNIT_HEADERS),
+                    house_number: find(WA_HOUSE_HEADERS),
+                    street_name,
+                    suburb,
+                    postcode: find(WA_POSTCODE_HEADERS),
+                    contract_date,
+                    sale_price,
+                    land_use: find(WA_LAND_USE_HEADERS),
+                    strata: find(WA_STRATA_HEADERS),
+                    land_area: find(WA_AREA_HEADERS),
+                })
+            }
+            _ => Err([
+                (street_name, WA_STREET_HEADERS[0]),
+                (suburb, WA_SUBURB_HEADERS[0]),
+                (contract_date, WA_DATE_HEADERS[0]),
+                (sale_price, WA_PRICE_HEADERS[0]),
+            ]
+            .into_iter()
+            .filter(|(found, _)| found.is_none())
+            .map(|(_, name)| name)
+            .collect()),
+        }
+    }
+
+    /// The sale in a data row, or the column (when it is one) and problem
+    fn record(
+        &self,
+        row: &csv::StringRecord,
+        source_id: &str,
+    ) -> Result<PropertyRecord, (Option<&'static str>, String)> {
+        let field = |i: usize| row.get(i).map(str::trim).unwrap_or("");
+        let optional = |i: Option<usize>| i.map(field).filter(|s| !s.is_empty());
+        let problem = |what: &str, value: &str| match value {
+            "" => format!("no {}", what),
+            value => format!("not a {}: {:?}", what, value),
+        };
+
+        let (address, lot_plan) = format_wa_address(
+            optional(self.unit),
+            optional(self.house_number),
+            field(self.street_name),
+        );
+        if address.is_empty() {
+            let problem = problem("street address", field(self.street_name));
+            return Err((Some(WA_STREET_HEADERS[0]), problem));
+        }
+
+        let suburb = field(self.suburb);
+        if suburb.is_empty() {
+            return Err((Some(WA_SUBURB_HEADERS[0]), problem("suburb", suburb)));
+        }
+
+        // Every WA postcode is 6xxx; anything else is a column mix-up
+        let postcode = match optional(self.postcode) {
+            None => None,
+            Some(p) if p.len() == 4 && p.starts_with('6') && p.parse::<u16>().is_ok() => {
+                Some(p.to_string())
+            }
+            Some(p) => return Err((Some(WA_POSTCODE_HEADERS[0]), problem("WA postcode", p))),
+        };
+
+        let date = field(self.contract_date);
+        let Some(sale_date) = NaiveDate::parse_from_str(date, "%d-%b-%Y")
+            .ok()
+            .or_else(|| parse_date(date))
+        else {
+            return Err((Some(WA_DATE_HEADERS[0]), problem("date", date)));
+        };
+
+        let sale_price = parse_price(field(self.sale_price))
+            .map_err(|e| (Some(WA_PRICE_HEADERS[0]), e.to_string()))?;
+
+        let strata = optional(self.strata)
+            .is_some_and(|s| matches!(s.to_ascii_uppercase().as_str(), "Y" | "YES" | "STRATA"));
+        let property_type = parse_wa_property_type(optional(self.land_use).unwrap_or(""), strata);
+
+        let land_area_sqm = optional(self.land_area)
+            .and_then(|area| area.replace(',', "").parse::<Decimal>().ok())
+            .filter(|area| *area > Decimal::ZERO);
+
+        Ok(PropertyRecord {
+            external_id: lot_plan
+                .external_id()
+                .or_else(|| optional(self.landgate_id).map(str::to_string)),
+            address,
+            suburb: suburb.to_uppercase(),
+            state: State::WA,
+            postcode,
+            district: None,
+            property_type,
+            bedrooms: None, // Will be estimated in enrichment
+            bathrooms: None,
+            land_area_sqm,
+            sale_price,
+            sale_date: Some(sale_date),
+            weekly_rent: None, // Will be matched in enrichment
+            rent_frequency: None,
+            rental_yield: None,
+            price_per_sqm: None,
+            prior_sales: Vec::new(),
+            latitude: None,
+            longitude: None,
+            source_metadata: SourceMetadata {
+                source_id: source_id.to_string(),
+                data_quality: DataQuality::Individual,
+                fetched_at: Utc::now(),
+                is_rental_estimated: false,
+                is_bedrooms_estimated: false,
+                rental_period: None,
+                rental_match_method: None,
+                source_file: None,
+                source_row: None,
+                confidence_score: 0.9, // Same register as the NSW bulk files
+            },
+        })
+    }
+}
+
+/// Fewer sales than this and an aggregate median is given half the confidence
+const AGGREGATE_THIN_MARKET_SALES: i32 = 10;
+
+/// A published median sale price, stored as one aggregate record
+///
+/// Medians have no street address, so each gets a synthetic one
+/// ("RICHMOND MEDIAN (3BR HOUSE)") and an external_id no individual sale
+/// shares ("vic_median:RICHMOND:house:3:2023"). Bedrooms stay unknown unless
+/// the file breaks its medians down by them.
+#[derive(Debug, Clone, PartialEq)]
+struct AggregateMedian {
+    /// First part of the external_id, e.g. "vic_median"
+    scheme: &'static str,
+    state: State,
+    locality: String,
+    postcode: Option<String>,
+    property_type: PropertyType,
+    bedrooms: Option<i32>,
+    /// The period as it appears in the external_id, e.g. "2023" or "2024Q3"
+    period: String,
+    /// Last day of the period, which the median is dated
+    period_end: NaiveDate,
+    median_price: i32,
+    sales: Option<i32>,
+}
+
+impl AggregateMedian {
+    /// Synthetic address, e.g. "RICHMOND MEDIAN (3BR HOUSE)"
+    fn address(&self) -> String {
+        let kind = self
+            .property_type
+            .to_string()
+            .replace('_', " ")
+            .to_uppercase();
+        match self.bedrooms {
+            Some(bedrooms) => format!("{} MEDIAN ({}BR {})", self.locality, bedrooms, kind),
+            None => format!("{} MEDIAN ({})", self.locality, kind),
+        }
+    }
+
+    /// External id, e.g. "vic_median:RICHMOND:house:all:2023"
+    fn external_id(&self) -> String {
+        let bedrooms = self
+            .bedrooms
+            .map_or_else(|| "all".to_string(), |b| b.to_string());
+        format!(
+            "{}:{}:{}:{}:{}",
+            self.scheme, self.locality, self.property_type, bedrooms, self.period
+        )
+    }
+
+    fn into_record(self, source_id: &str) -> PropertyRecord {
+        let confidence_score = match self.sales {
+            Some(sales) if sales < AGGREGATE_THIN_MARKET_SALES => 0.4,
+            _ => 0.8,
+        };
+
+        PropertyRecord {
+            external_id: Some(self.external_id()),
+            address: self.address(),
+            suburb: self.locality,
+            state: self.state,
+            postcode: self.postcode,
+            district: None,
+            property_type: self.property_type,
+            bedrooms: self.bedrooms,
+            bathrooms: None,
+            land_area_sqm: None,
+            sale_price: Some(i64::from(self.median_price)),
+            sale_date: Some(self.period_end),
+            weekly_rent: None,
+            rent_frequency: None,
+            rental_yield: None,
+            price_per_sqm: None,
+            prior_sales: Vec::new(),
+            latitude: None,
+            longitude: None,
+            source_metadata: SourceMetadata {
+                source_id: source_id.to_string(),
+                data_quality: DataQuality::Aggregated,
+                fetched_at: Utc::now(),
+                is_rental_estimated: false,
+                is_bedrooms_estimated: false,
+                rental_period: None,
+                rental_match_method: None,
+                source_file: None,
+                source_row: None,
+                confidence_score,
+            },
+        }
+    }
+}
+
+/// Parse every sheet of a median sales workbook with a header `from_header`
+/// recognises, reading each data row with `median`
+///
+/// `what` names the medians in logs and errors, e.g. "VIC median sales".
+/// Sheets without such a header are passed over, as are blank rows; a median
+/// repeated on a later sheet is only kept once.
+fn parse_median_workbook<C>(
+    raw: RawData,
+    source_id: &str,
+    what: &str,
+    from_header: impl Fn(&[Data]) -> Result<C, Vec<&'static str>>,
+    median: impl Fn(&C, &[Data]) -> Result<Option<AggregateMedian>, (&'static str, String)>,
+) -> Result<ParseReport> {
+    let bytes = raw.as_bytes()?;
+    info!("Parsing {} XLSX ({} bytes)", what, bytes.len());
+
+    let cursor = Cursor::new(bytes);
+    let mut workbook = open_workbook_auto_from_rs(cursor)?;
+
+    let sheet_names = workbook.sheet_names();
+    let mut records = Vec::new();
+    let mut seen = HashSet::new();
+    let mut not_tables = Vec::new();
+    let mut errors = Vec::new();
+    let mut total_rows = 0;
+
+    for sheet_name in &sheet_names {
+        let range = workbook.worksheet_range(sheet_name)?;
+        let mut rows = range.rows();
+        let columns = match locate_header(&mut rows, &from_header) {
+            Ok(columns) => columns,
+            Err(e) => {
+                debug!("Skipping {} sheet {}: {}", what, sheet_name, e);
+                not_tables.push(format!("{}: {}", sheet_name, e));
+                continue;
+            }
+        };
+        info!("Reading sheet: {}", sheet_name);
+
+        // 1-based sheet row of the first row after the header
+        let first_row =
+            range.start().map_or(0, |(row, _)| row as usize) + range.height() - rows.len() + 1;
+        for (i, row) in rows.enumerate() {
+            if row.iter().all(is_blank) {
+                continue;
+            }
+            total_rows += 1;
+            match median(&columns, row) {
+                Ok(Some(median)) => {
+                    if seen.insert(median.external_id()) {
+                        records.push(median.into_record(source_id));
+                    }
+                }
+                Ok(None) => {}
+                Err((column, message)) => errors.push(RowError {
+                    source: sheet_name.clone(),
+                    row_number: first_row + i,
+                    column: Some(column.to_string()),
+                    message,
+                }),
+            }
+        }
+    }
+
+    if not_tables.len() == sheet_names.len() {
+        anyhow::bail!("No {} sheet; {}", what, not_tables.join("; "));
+    }
+    for error in errors.iter().take(10) {
+        warn!(
+            "Failed to parse row {} of sheet {}: {}",
+            error.row_number, error.source, error.message
+        );
+    }
+    if !errors.is_empty() {
+        warn!(
+            "{} of {} {} rows failed to parse",
+            errors.len(),
+            total_rows,
+            what
+        );
+    }
+    info!("Parsed {} {} from XLSX", records.len(), what);
+
+    Ok(ParseReport {
+        records,
+        errors,
+        total_rows,
+        blank_prices: 0,
+        implausible_prices: 0,
+        rejected_postcodes: 0,
+    })
+}
+
+/// A median price cell; None where the median is suppressed (blank, "-" or
+/// "NA"), or the problem when it can't be read
+fn median_price_cell(cell: &Data) -> Result<Option<i32>, String> {
+    match cell {
+        Data::Empty => Ok(None),
+        Data::String(s) if matches!(s.trim().to_uppercase().as_str(), "" | "-" | "NA" | "N/A") => {
+            Ok(None)
+        }
+        other => match whole_number(other).and_then(|p| i32::try_from(p).ok()) {
+            Some(price) => Ok(Some(price)),
+            None => Err(unreadable("price", other)),
+        },
+    }
+}
+
+/// An optional whole-number cell such as a sales count; None when there is
+/// no such column or the cell is empty
+fn optional_count(cell: Option<&Data>, what: &str) -> Result<Option<i32>, String> {
+    match cell {
+        None | Some(Data::Empty) => Ok(None),
+        Some(other) => match whole_number(other).and_then(|n| i32::try_from(n).ok()) {
+            Some(n) => Ok(Some(n)),
+            None => Err(unreadable(what, other)),
+        },
+    }
+}
+
+/// Parse the Victorian Valuer-General median sales XLSX into one aggregate
+/// record per locality, property type and year
+///
+/// Columns are located by header name, as in the rental bond workbook; a
+/// Bedrooms column is optional. A year's median is dated the last day of
+/// that year.
+///
+/// Rows with a cell that can't be read are reported as row errors; blank rows
+/// and medians suppressed for too few sales are passed over.
+pub async fn parse_vic_sales(raw: RawData, source_id: String) -> Result<ParseReport> {
+    parse_median_workbook(
+        raw,
+        &source_id,
+        "VIC median sales",
+        VicSalesColumns::from_header,
+        VicSalesColumns::median,
+    )
+}
+
+/// Header names each VIC median sales column has been published under
+const VIC_LOCALITY_HEADERS: &[&str] = &["Locality", "Suburb"];
+const VIC_TYPE_HEADERS: &[&str] = &["Property Type", "Type"];
+const VIC_MEDIAN_HEADERS: &[&str] = &["Median Price", "Median", "Median Sale Price"];
+const VIC_SALES_HEADERS: &[&str] = &["Sales", "No. of Sales", "Number of Sales", "Transactions"];
+const VIC_YEAR_HEADERS: &[&str] = &["Year", "Calendar Year"];
+const VIC_BEDROOMS_HEADERS: &[&str] = &["Bedrooms", "Number of Bedrooms"];
+
+/// Column positions in the VIC median sales sheet
+#[derive(Debug, PartialEq, Eq)]
+struct VicSalesColumns {
+    locality: usize,
+    property_type: usize,
+    median_price: usize,
+    year: usize,
+    sales: Option<usize>,
+    bedrooms: Option<usize>,
+}
+
+impl VicSalesColumns {
+    /// The columns of a header row, or the names of the required ones it lacks
+    fn from_header(row: &[Data]) -> Result<Self, Vec<&'static str>> {
+        let find = |aliases: &[&str]| header_position(row, aliases);
+
+        let locality = find(VIC_LOCALITY_HEADERS);
+        let property_type = find(VIC_TYPE_HEADERS);
+        let median_price = find(VIC_MEDIAN_HEADERS);
+        let year = find(VIC_YEAR_HEADERS);
+        match (locality, property_type, median_price, year) {
+            (Some(locality), Some(property_type), Some(median_price), Some(year)) => {
+                Ok(VicSalesColumns {
+                    locality,
+                    property_type,
+                    median_price,
+                    year,
+                    sales: find(VIC_SALES_HEADERS),
+                    bedrooms: find(VIC_BEDROOMS_HEADERS),
+                })
+            }
+            _ => Err([
+                (locality, VIC_LOCALITY_HEADERS[0]),
+                (property_type, VIC_TYPE_HEADERS[0]),
+                (median_price, VIC_MEDIAN_HEADERS[0]),
+                (year, VIC_YEAR_HEADERS[0]),
+            ]
+            .into_iter()
+            .filter(|(found, _)| found.is_none())
+            .map(|(_, name)| name)
+            .collect()),
+        }
+    }
+
+    /// The median in a data row, None where it is suppressed, or the column
+    /// and problem when a cell can't be read
+    fn median(&self, row: &[Data]) -> Result<Option<AggregateMedian>, (&'static str, String)> {
+        let cell = |i: usize| row.get(i).unwrap_or(&Data::Empty);
+
+        let locality = match cell(self.locality) {
+            Data::String(s) if !s.trim().is_empty() => s.trim().to_uppercase(),
+            other => return Err((VIC_LOCALITY_HEADERS[0], unreadable("locality", other))),
+        };
+
+        let property_type = match cell(self.property_type) {
+            Data::String(s) => parse_vic_property_type(s),
+            _ => None,
+        };
+        let Some(property_type) = property_type else {
+            let problem = unreadable("property type", cell(self.property_type));
+            return Err((VIC_TYPE_HEADERS[0], problem));
+        };
+
+        let year = whole_number(cell(self.year)).and_then(|y| i32::try_from(y).ok());
+        let Some((year, period_end)) =
+            year.and_then(|y| Some((y, NaiveDate::from_ymd_opt(y, 12, 31)?)))
+        else {
+            return Err((VIC_YEAR_HEADERS[0], unreadable("year", cell(self.year))));
+        };
+
+        let median_price = match median_price_cell(cell(self.median_price)) {
+            Ok(Some(price)) => price,
+            Ok(None) => return Ok(None),
+            Err(problem) => return Err((VIC_MEDIAN_HEADERS[0], problem)),
+        };
+
+        let sales = optional_count(self.sales.map(cell), "sales count")
+            .map_err(|problem| (VIC_SALES_HEADERS[0], problem))?;
+        let bedrooms = optional_count(self.bedrooms.map(cell), "bedroom count")
+            .map_err(|problem| (VIC_BEDROOMS_HEADERS[0], problem))?;
+
+        Ok(Some(AggregateMedian {
+            scheme: "vic_median",
+            state: State::VIC,
+            locality,
+            postcode: None,
+            property_type,
+            bedrooms,
+            period: year.to_string(),
+            period_end,
+            median_price,
+            sales,
+        }))
+    }
+}
+
+/// "House", "Unit/Apartment", "Vacant Land" and the like; None for a type
+/// the file hasn't used before
+fn parse_vic_property_type(s: &str) -> Option<PropertyType> {
+    let s = s.trim().to_lowercase();
+    if s.contains("townhouse") {
+        Some(PropertyType::Townhouse)
+    } else if s.contains("house") {
+        Some(PropertyType::House)
+    } else if s.contains("unit") || s.contains("flat") || s.contains("apartment") {
+        Some(PropertyType::Unit)
+    } else if s.contains("land") {
+        Some(PropertyType::VacantLand)
+    } else {
+        None
+    }
+}
+
+/// A whole number from a numeric cell or text like "$1,250,000"
+fn whole_number(cell: &Data) -> Option<i64> {
+    match cell {
+        Data::Int(i) => Some(*i),
+        Data::Float(f) if f.fract() == 0.0 => Some(*f as i64),
+        Data::String(s) => s.replace(['$', ','], "").trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Parse the South Australian quarterly suburb median sales XLSX into one
+/// aggregate record per suburb, property type and quarter
+///
+/// Columns are located by header name. Postcode and Property Type columns
+/// are optional; without the latter every median is taken to be for houses,
+/// which is all the Valuer-General publishes. A quarter's median is dated the
+/// last day of the quarter.
+///
+/// Rows with a cell that can't be read are reported as row errors; blank rows
+/// and medians suppressed for too few sales are passed over.
+pub async fn parse_sa_medians(raw: RawData, source_id: String) -> Result<ParseReport> {
+    parse_median_workbook(
+        raw,
+        &source_id,
+        "SA median sales",
+        SaMedianColumns::from_header,
+        SaMedianColumns::median,
+    )
+}
+
+/// Header names each SA median sales column has been published under
+const SA_SUBURB_HEADERS: &[&str] = &["Suburb", "Locality"];
+const SA_POSTCODE_HEADERS: &[&str] = &["Postcode", "Post Code"];
+const SA_QUARTER_HEADERS: &[&str] = &["Quarter", "Period", "Quarter Ending"];
+const SA_MEDIAN_HEADERS: &[&str] = &["Median Price", "Median", "Median Sale Price"];
+const SA_SALES_HEADERS: &[&str] = &["Sales", "No. of Sales", "Number of Sales"];
+const SA_TYPE_HEADERS: &[&str] = &["Property Type", "Type"];
+
+/// Column positions in the SA median sales sheet
+#[derive(Debug, PartialEq, Eq)]
+struct SaMedianColumns {
+    suburb: usize,
+    quarter: usize,
+    median_price: usize,
+    postcode: Option<usize>,
+    sales: Option<usize>,
+    property_type: Option<usize>,
+}
+
+impl SaMedianColumns {
+    /// The columns of a header row, or the names of the required ones it lacks
+    fn from_header(row: &[Data]) -> Result<Self, Vec<&'static str>> {
+        let find = |aliases: &[&str]| header_position(row, aliases);
+
+        let suburb = find(SA_SUBURB_HEADERS);
+        let quarter = find(SA_QUARTER_HEADERS);
+        let median_price = find(SA_MEDIAN_HEADERS);
+        match (suburb, quarter, median_price) {
+            (Some(suburb), Some(quarter), Some(median_price)) => Ok(SaMedianColumns {
+                suburb,
+                quarter,
+                median_price,
+                postcode: find(SA_POSTCODE_HEADERS),
+                sales: find(SA_SALES_HEADERS),
+                property_type: find(SA_TYPE_HEADERS),
+            }),
+            _ => Err([
+                (suburb, SA_SUBURB_HEADERS[0]),
+                (quarter, SA_QUARTER_HEADERS[0]),
+                (median_price, SA_MEDIAN_HEADERS[0]),
+            ]
+            .into_iter()
+            .filter(|(found, _)| found.is_none())
+            .map(|(_, name)| name)
+            .collect()),
+        }
+    }
+
+    /// The median in a data row, None where it is suppressed, or the column
+    /// and problem when a cell can't be read
+    fn median(&self, row: &[Data]) -> Result<Option<AggregateMedian>, (&'static str, String)> {
+        let cell = |i: usize| row.get(i).unwrap_or(&Data::Empty);
+
+        let locality = match cell(self.suburb) {
+            Data::String(s) if !s.trim().is_empty() => s.trim().to_uppercase(),
+            other => return Err((SA_SUBURB_HEADERS[0], unreadable("suburb", other))),
+        };
+
+        // Every SA postcode is 5xxx; anything else is a column mix-up
+        let postcode = match self.postcode.map(cell) {
+            None | Some(Data::Empty) => None,
+            Some(other) => match whole_number(other) {
+                Some(p @ 5000..=5999) => Some(p.to_string()),
+                _ => return Err((SA_POSTCODE_HEADERS[0], unreadable("SA postcode", other))),
+            },
+        };
+
+        let type_cell = self.property_type.map_or(&Data::Empty, cell);
+        let property_type = match type_cell {
+            Data::Empty => Some(PropertyType::House),
+            Data::String(s) => parse_vic_property_type(s),
+            _ => None,
+        };
+        let Some(property_type) = property_type else {
+            let problem = unreadable("property type", type_cell);
+            return Err((SA_TYPE_HEADERS[0], problem));
+        };
+
+        let period_end = match cell(self.quarter) {
+            Data::String(s) => parse_quarter_label(s),
+            _ => None,
+        };
+        let Some(period_end) = period_end else {
+            let problem = unreadable("quarter", cell(self.quarter));
+            return Err((SA_QUARTER_HEADERS[0], problem));
+        };
+
+        let median_price = match median_price_cell(cell(self.median_price)) {
+            Ok(Some(price)) => price,
+            Ok(None) => return Ok(None),
+            Err(problem) => return Err((SA_MEDIAN_HEADERS[0], problem)),
+        };
+
+        let sales = optional_count(self.sales.map(cell), "sales count")
+            .map_err(|problem| (SA_SALES_HEADERS[0], problem))?;
+
+        Ok(Some(AggregateMedian {
+            scheme: "sa_median",
+            state: State::SA,
+            locality,
+            postcode,
+            property_type,
+            bedrooms: None,
+            period: format!("{}Q{}", period_end.year(), period_end.month() / 3),
+            period_end,
+            median_price,
+            sales,
+        }))
+    }
+}
+
+/// Parse NSW rental bond XLSX into RentalMedian structs
+///
+/// The monthly files move columns around between releases, so columns are
+/// located by header name, allowing for the aliases each has been published
+/// under; other columns are ignored. Medians are dated `period` unless the
+/// sheet has a Month column, read as a date cell, an Excel serial number or
+/// text in any format `parse_flexible_date` takes.
+///
+/// Each median is kept under its dwelling type, taken from the Dwelling Type
+/// column or else the sheet name; all dwellings and total rows or sheets, and
+/// sheets named for no dwelling type, give the combined median, stored
+/// without one.
+///
+/// Every sheet with a median table is read, and sheets of notes are skipped.
+/// Where sheets repeat a postcode, bedroom count, dwelling type and month,
+/// the first sheet to list it wins. A workbook without
+/// any sheet of postcode, bedrooms and median rent columns fails, naming the
+/// headers each sheet has.
+///
+/// Rows with a cell that can't be read are reported as row errors; blank rows
+/// and suppressed medians are passed over.
+pub async fn parse_nsw_rentals(
+    raw: RawData,
+    period: NaiveDate,
+) -> Result<ParseReport<RentalMedian>> {
+    let bytes = raw.as_bytes()?;
+    info!("Parsing NSW rental bond XLSX ({} bytes)", bytes.len());
+
+    let cursor = Cursor::new(bytes);
+    let mut workbook = open_workbook_auto_from_rs(cursor)?;
+
+    let sheet_names = workbook.sheet_names();
+    if sheet_names.is_empty() {
+        return Err(anyhow::anyhow!("No sheets found in workbook"));
+    }
+
+    let mut rentals: Vec<RentalMedian> = Vec::new();
+    let mut seen: HashSet<(String, i32, Option<PropertyType>, NaiveDate)> = HashSet::new();
+    let mut not_tables = Vec::new();
+    let mut errors = Vec::new();
+    let mut total_rows = 0;
+    let mut duplicates = 0;
+    let mut rejected_postcodes = 0;
+
+    for sheet_name in &sheet_names {
+        let range = workbook.worksheet_range(sheet_name)?;
+        let mut rows = range.rows();
+        let columns = match RentalColumns::locate(&mut rows) {
+            Ok(columns) => columns,
+            Err(e) => {
+                debug!("Skipping rental bond sheet {}: {}", sheet_name, e);
+                not_tables.push(format!("{}: {}", sheet_name, e));
+                continue;
+            }
+        };
+        let sheet_dwelling_type = sheet_dwelling_type(sheet_name);
+        info!("Reading sheet: {}", sheet_name);
+
+        // 1-based sheet row of the first row after the header
+        let first_row = range.start().map_or(0, |(row, _)| row as usize) + range.height()
+            - rows.len()
+            + 1;
+        for (i, row) in rows.enumerate() {
+            if row.iter().all(is_blank) {
+                continue;
+            }
+            total_rows += 1;
+            let rental = match columns.median(row, period, sheet_dwelling_type.clone()) {
+                Ok(Some(rental)) => rental,
+                Ok(None) => continue,
+                Err((column, message)) => {
+                    let given = row.get(columns.postcode).is_some_and(|cell| !is_blank(cell));
+                    if column == RENTAL_POSTCODE_HEADERS[0] && given {
+                        rejected_postcodes += 1;
+                    }
+                    errors.push(RowError {
+                        source: sheet_name.clone(),
+                        row_number: first_row + i,
+                        column: Some(column.to_string()),
+                        message,
+                    });
+                    continue;
+                }
+            };
+            let key = (
+                rental.postcode.clone(),
+                rental.bedrooms,
+                rental.dwelling_type.clone(),
+                rental.period,
+            );
+            if seen.insert(key) {
+                rentals.push(rental);
+            } else {
+                duplicates += 1;
+            }
+        }
+    }
+
+    if not_tables.len() == sheet_names.len() {
+        anyhow::bail!("No rental median sheet; {}", not_tables.join("; "));
+    }
+    for error in errors.iter().take(10) {
+        warn!(
+            "Failed to parse row {} of sheet {}: {}",
+            error.row_number, error.source, error.message
+        );
+    }
+    if !errors.is_empty() {
+        warn!("{} of {} rental median rows failed to parse", errors.len(), total_rows);
+    }
+    if duplicates > 0 {
+        info!("Dropped {} rental medians repeated across sheets", duplicates);
+    }
+    info!("Parsed {} rental medians from XLSX", rentals.len());
+
+    Ok(ParseReport {
+        records: rentals,
+        errors,
+        total_rows,
+        blank_prices: 0,
+        implausible_prices: 0,
+        rejected_postcodes,
+    })
+}
+
+/// "All Dwellings", "Total" and the like, naming a sheet or row whose medians
+/// cover every dwelling type
+fn is_all_dwellings(name: &str) -> bool {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word == "all" || word == "total")
+}
+
+/// The dwelling type a rental median sheet is named for, like "Houses" or
+/// "Flats/Units"; None for all dwellings and names of no dwelling type
+fn sheet_dwelling_type(name: &str) -> Option<PropertyType> {
+    if is_all_dwellings(name) {
+        return None;
+    }
+    match parse_nsw_property_type(name) {
+        t @ (PropertyType::House | PropertyType::Unit | PropertyType::Townhouse) => Some(t),
+        _ => None,
+    }
+}
+
+/// Header names each rental median column has been published under
+const RENTAL_POSTCODE_HEADERS: &[&str] = &["Postcode", "Post Code"];
+const RENTAL_SUBURB_HEADERS: &[&str] = &["Suburb", "Locality"];
+const RENTAL_BEDROOMS_HEADERS: &[&str] = &["Bedrooms", "Number of Bedrooms"];
+const RENTAL_MEDIAN_HEADERS: &[&str] = &["Median Rent", "Median Weekly Rent"];
+const RENTAL_SAMPLE_HEADERS: &[&str] = &["New Bonds", "Sample"];
+const RENTAL_MONTH_HEADERS: &[&str] = &["Month", "Period"];
+const RENTAL_DWELLING_HEADERS: &[&str] = &["Dwelling Type", "Dwelling"];
+
+/// Column positions in the rental median sheet
+#[derive(Debug, PartialEq, Eq)]
+struct RentalColumns {
+    postcode: usize,
+    suburb: Option<usize>,
+    bedrooms: usize,
+    median_rent: usize,
+    sample_size: Option<usize>,
+    month: Option<usize>,
+    dwelling_type: Option<usize>,
+}
+
+impl RentalColumns {
+    /// Consume rows up to and including the header
+    fn locate<'a>(rows: &mut impl Iterator<Item = &'a [Data]>) -> Result<Self> {
+        locate_header(rows, Self::from_header)
+    }
+
+    /// The columns of a header row, or the names of the required ones it lacks
+    fn from_header(row: &[Data]) -> Result<Self, Vec<&'static str>> {
+        let find = |aliases: &[&str]| header_position(row, aliases);
+
+        let postcode = find(RENTAL_POSTCODE_HEADERS);
+        let bedrooms = find(RENTAL_BEDROOMS_HEADERS);
+        let median_rent = find(RENTAL_MEDIAN_HEADERS);
+        match (postcode, bedrooms, median_rent) {
+            (Some(postcode), Some(bedrooms), Some(median_rent)) => Ok(RentalColumns {
+                postcode,
+                suburb: find(RENTAL_SUBURB_HEADERS),
+                bedrooms,
+                median_rent,
+                sample_size: find(RENTAL_SAMPLE_HEADERS),
+                month: find(RENTAL_MONTH_HEADERS),
+                dwelling_type: find(RENTAL_DWELLING_HEADERS),
+            }),
+            _ => Err([
+                (postcode, RENTAL_POSTCODE_HEADERS[0]),
+                (bedrooms, RENTAL_BEDROOMS_HEADERS[0]),
+                (median_rent, RENTAL_MEDIAN_HEADERS[0]),
+            ]
+            .into_iter()
+            .filter(|(found, _)| found.is_none())
+            .map(|(_, name)| name)
+            .collect()),
+        }
+    }
+
+    /// The median in a data row, None where it is suppressed ("-" or "s" when
+    /// too few bonds were lodged), or the column and problem when a required
+    /// cell can't be read; rows without a dwelling type take the sheet's
+    fn median(
+        &self,
+        row: &[Data],
+        period: NaiveDate,
+        sheet_dwelling_type: Option<PropertyType>,
+    ) -> Result<Option<RentalMedian>, (&'static str, String)> {
+        let cell = |i: usize| row.get(i).unwrap_or(&Data::Empty);
+
+        let postcode = match cell(self.postcode) {
+            Data::String(s) if !s.trim().is_empty() => normalize_postcode(s, State::NSW),
+            Data::Int(i) => normalize_postcode(&i.to_string(), State::NSW),
+            Data::Float(f) => normalize_postcode(&f.to_string(), State::NSW),
+            _ => None,
+        };
+        let Some(postcode) = postcode else {
+            let problem = unreadable("NSW postcode", cell(self.postcode));
+            return Err((RENTAL_POSTCODE_HEADERS[0], problem));
+        };
+
+        let suburb = match self.suburb.and_then(|i| row.get(i)) {
+            Some(Data::String(s)) => Some(s.trim().to_string()),
+            _ => None,
+        };
+
+        let bedrooms = match cell(self.bedrooms) {
+            Data::Int(i) => Some(*i as i32),
+            Data::Float(f) => Some(*f as i32),
+            Data::String(s) => s.trim().parse().ok(),
+            _ => None,
+        };
+        let Some(bedrooms) = bedrooms else {
+            let problem = unreadable("bedroom count", cell(self.bedrooms));
+            return Err((RENTAL_BEDROOMS_HEADERS[0], problem));
+        };
+
+        let median_weekly_rent = match cell(self.median_rent) {
+            Data::Int(i) => Some(*i as i32),
+            Data::Float(f) => Some(*f as i32),
+            Data::String(s) if matches!(s.trim(), "-" | "s") => return Ok(None),
+            Data::String(s) => s.replace(['$', ','], "").trim().parse().ok(),
+            _ => None,
+        };
+        let Some(median_weekly_rent) = median_weekly_rent else {
+            let problem = unreadable("rent", cell(self.median_rent));
+            return Err((RENTAL_MEDIAN_HEADERS[0], problem));
+        };
+
+        let sample_size = match self.sample_size.and_then(|i| row.get(i)) {
+            Some(Data::Int(i)) => Some(*i as i32),
+            Some(Data::Float(f)) => Some(*f as i32),
+            Some(Data::String(s)) => s.replace(',', "").trim().parse().ok(),
+            _ => None,
+        };
+
+        let dwelling_type = match self.dwelling_type.and_then(|i| row.get(i)) {
+            Some(Data::String(s)) if is_all_dwellings(s) => None,
+            Some(Data::String(s)) if !s.trim().is_empty() => Some(parse_nsw_property_type(s)),
+            _ => sheet_dwelling_type,
+        };
+
+        // Files covering several months date each row; the rest take the file's
+        let period = match self.month.map(cell) {
+            None | Some(Data::Empty) => period,
+            Some(month) => cell_date(month)
+                .ok_or_else(|| (RENTAL_MONTH_HEADERS[0], unreadable("date", month)))?,
+        };
+
+        Ok(Some(RentalMedian {
+            state: State::NSW,
+            postcode,
+            suburb,
+            bedrooms,
+            median_weekly_rent,
+            sample_size,
+            dwelling_type,
+            period,
+        }))
+    }
+}
+
+/// Consume rows up to and including the first that `from_header` accepts,
+/// looking no further than HEADER_SEARCH_ROWS; the error names the required
+/// columns missing from the closest row and the headers it has
+fn locate_header<'a, T>(
+    rows: &mut impl Iterator<Item = &'a [Data]>,
+    from_header: impl Fn(&[Data]) -> Result<T, Vec<&'static str>>,
+) -> Result<T> {
+    let mut closest: Option<(Vec<&str>, Vec<String>)> = None;
+    for row in rows.by_ref().take(HEADER_SEARCH_ROWS) {
+        let missing = match from_header(row) {
+            Ok(columns) => return Ok(columns),
+            Err(missing) => missing,
+        };
+        let headers: Vec<String> = row
+            .iter()
+            .map(|cell| cell.to_string().trim().to_string())
+            .filter(|header| !header.is_empty())
+            .collect();
+        let closer = closest
+            .as_ref()
+            .is_none_or(|(fewest, _)| missing.len() < fewest.len());
+        if !headers.is_empty() && closer {
+            closest = Some((missing, headers));
+        }
+    }
+
+    match closest {
+        Some((missing, headers)) => anyhow::bail!(
+            "no {} column; headers found: {}",
+            missing.join(", "),
+            headers.join(", ")
+        ),
+        None => anyhow::bail!("no header row found"),
+    }
+}
+
+/// Position of the first cell naming one of `aliases`, ignoring case
+fn header_position(row: &[Data], aliases: &[&str]) -> Option<usize> {
+    row.iter().position(|cell| match cell {
+        Data::String(s) => aliases.iter().any(|a| s.trim().eq_ignore_ascii_case(a)),
+        _ => false,
+    })
+}
+
+fn is_blank(cell: &Data) -> bool {
+    match cell {
+        Data::Empty => true,
+        Data::String(s) => s.trim().is_empty(),
+        _ => false,
+    }
+}
+
+/// "no postcode" for an empty cell, otherwise "not a postcode: ..."
+fn unreadable(what: &str, cell: &Data) -> String {
+    match cell {
+        Data::Empty => format!("no {}", what),
+        other => format!("not a {}: {:?}", what, other.to_string()),
+    }
+}
+
+/// Parse the Queensland RTA quarterly median rents CSV into RentalMedian structs
+///
+/// Each row is one quarter's median for a postcode, bedroom count and
+/// dwelling type, the quarter labelled like "Sep-24" and dated the last day
+/// of that quarter. Only the all dwellings rows are kept, since a median is
+/// stored per postcode and bedroom count; house, unit and townhouse rows,
+/// and rows totalling every bedroom count, are passed over.
+///
+/// Rows with a cell that can't be read are reported as row errors; blank rows
+/// and suppressed medians are passed over.
+pub async fn parse_qld_rentals(raw: RawData) -> Result<ParseReport<RentalMedian>> {
+    let (name, label) = source_names(&raw);
+    info!("Parsing QLD median rents CSV from {}", label);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(raw.into_reader()?);
+    let columns = QldRentalColumns::from_header(reader.headers()?).map_err(|missing| {
+        anyhow::anyhow!(
+            "No {} column in QLD median rents header",
+            missing.join(", ")
+        )
+    })?;
+
+    let mut rentals = Vec::new();
+    let mut errors = Vec::new();
+    let mut total_rows = 0;
+    let mut other_dwellings = 0;
+
+    for result in reader.records() {
+        // The reader skips blank lines, so lines are counted from its position
+        let row = match result {
+            Ok(row) => row,
+            Err(e) => {
+                total_rows += 1;
+                errors.push(RowError {
+                    source: name.clone(),
+                    row_number: e.position().map_or(0, |p| p.line() as usize),
+                    column: None,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if row.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        total_rows += 1;
+        let row_number = row.position().map_or(0, |p| p.line() as usize);
+        match columns.median(&row) {
+            Ok(QldRentalRow::Median(rental)) => rentals.push(rental),
+            Ok(QldRentalRow::OtherDwellings) => other_dwellings += 1,
+            Ok(QldRentalRow::Suppressed) => {}
+            Err((column, message)) => errors.push(RowError {
+                source: name.clone(),
+                row_number,
+                column: Some(column.to_string()),
+                message,
+            }),
+        }
+    }
+
+    for error in errors.iter().take(10) {
+        warn!(
+            "Failed to parse row {} of {}: {}",
+            error.row_number, error.source, error.message
+        );
+    }
+    if !errors.is_empty() {
+        warn!(
+            "{} of {} QLD median rows failed to parse",
+            errors.len(),
+            total_rows
+        );
+    }
+    if other_dwellings > 0 {
+        debug!("Passed over {} single dwelling type rows", other_dwellings);
+    }
+    info!("Parsed {} QLD rental medians from CSV", rentals.len());
+
+    Ok(ParseReport {
+        records: rentals,
+        errors,
+        total_rows,
+        blank_prices: 0,
+        implausible_prices: 0,
+        rejected_postcodes: 0,
+    })
+}
+
+/// Header names each QLD median rents column has been published under
+const QLD_QUARTER_HEADERS: &[&str] = &["Quarter", "Period"];
+const QLD_POSTCODE_HEADERS: &[&str] = &["Postcode", "Post Code"];
+const QLD_LOCALITY_HEADERS: &[&str] = &["Locality", "Suburb"];
+const QLD_DWELLING_HEADERS: &[&str] = &["Dwelling Type", "Dwelling"];
+const QLD_BEDROOMS_HEADERS: &[&str] = &["Bedrooms", "Number of Bedrooms"];
+const QLD_MEDIAN_HEADERS: &[&str] = &["Median Rent", "Median Weekly Rent", "Median"];
+const QLD_BONDS_HEADERS: &[&str] = &["New Bonds", "Bonds"];
+
+/// Column positions in the QLD median rents file
+#[derive(Debug, PartialEq, Eq)]
+struct QldRentalColumns {
+    quarter: usize,
+    postcode: usize,
+    locality: Option<usize>,
+    dwelling_type: usize,
+    bedrooms: usize,
+    median_rent: usize,
+    new_bonds: Option<usize>,
+}
+
+/// What a data row of the QLD file holds
+#[derive(Debug)]
+enum QldRentalRow {
+    Median(RentalMedian),
+    /// One dwelling type, or every bedroom count, rather than the median kept
+    OtherDwellings,
+    /// Too few bonds for a median to be published
+    Suppressed,
+}
+
+impl QldRentalColumns {
+    /// The columns of the header, or the names of the required ones it lacks
+    fn from_header(header: &csv::StringRecord) -> Result<Self, Vec<&'static str>> {
+        let find = |aliases: &[&str]| {
+            header
+                .iter()
+                .position(|name| aliases.iter().any(|a| name.trim().eq_ignore_ascii_case(a)))
+        };
+
+        let quarter = find(QLD_QUARTER_HEADERS);
+        let postcode = find(QLD_POSTCODE_HEADERS);
+        let dwelling_type = find(QLD_DWELLING_HEADERS);
+        let bedrooms = find(QLD_BEDROOMS_HEADERS);
+        let median_rent = find(QLD_MEDIAN_HEADERS);
+        match (quarter, postcode, dwelling_type, bedrooms, median_rent) {
+            (
+                Some(quarter),
+                Some(postcode),
+                Some(dwelling_type),
+                Some(bedrooms),
+                Some(median_rent),
+            ) => Ok(QldRentalColumns {
+                quarter,
+                postcode,
+                locality: find(QLD_LOCALITY_HEADERS),
+                dwelling_type,
+                bedrooms,
+                median_rent,
+                new_bonds: find(QLD_BONDS_HEADERS),
+            }),
+            _ => Err([
+                (quarter, QLD_QUARTER_HEADERS[0]),
+                (postcode, QLD_POSTCODE_HEADERS[0]),
+                (dwelling_type, QLD_DWELLING_HEADERS[0]),
+                (bedrooms, QLD_BEDROOMS_HEADERS[0]),
+                (median_rent, QLD_MEDIAN_HEADERS[0]),
+            ]
+            .into_iter()
+            .filter(|(found, _)| found.is_none())
+            .map(|(_, name)| name)
+            .collect()),
+        }
+    }
+
+    /// The median in a data row, or the column and problem when a cell
+    /// can't be read
+    fn median(&self, row: &csv::StringRecord) -> Result<QldRentalRow, (&'static str, String)> {
+        let field = |i: usize| row.get(i).map(str::trim).unwrap_or("");
+        let problem = |what: &str, value: &str| match value {
+            "" => format!("no {}", what),
+            value => format!("not a {}: {:?}", what, value),
+        };
+
+        let dwelling_type = field(self.dwelling_type);
+        if dwelling_type.is_empty() {
+            return Err((QLD_DWELLING_HEADERS[0], problem("dwelling type", "")));
+        }
+        let bedrooms = field(self.bedrooms);
+        if !is_all_dwellings(dwelling_type) || is_all_dwellings(bedrooms) {
+            return Ok(QldRentalRow::OtherDwellings);
+        }
+
+        let Some(period) = parse_quarter_label(field(self.quarter)) else {
+            let problem = problem("quarter", field(self.quarter));
+            return Err((QLD_QUARTER_HEADERS[0], problem));
+        };
+
+        let postcode = field(self.postcode);
+        if postcode.len() != 4 || !postcode.chars().all(|c| c.is_ascii_digit()) {
+            return Err((QLD_POSTCODE_HEADERS[0], problem("postcode", postcode)));
+        }
+
+        // "4+" bedrooms is stored as 4, as NSW publishes it
+        let Some(bedrooms) = bedrooms.trim_end_matches('+').parse().ok() else {
+            return Err((QLD_BEDROOMS_HEADERS[0], problem("bedroom count", bedrooms)));
+        };
+
+        let median_rent = field(self.median_rent);
+        if matches!(median_rent, "" | "-" | "s") {
+            return Ok(QldRentalRow::Suppressed);
+        }
+        let Some(median_weekly_rent) = median_rent.replace(['$', ','], "").parse().ok() else {
+            return Err((QLD_MEDIAN_HEADERS[0], problem("rent", median_rent)));
+        };
+
+        Ok(QldRentalRow::Median(RentalMedian {
+            state: State::QLD,
+            postcode: postcode.to_string(),
+            suburb: self
+                .locality
+                .map(field)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string),
+            bedrooms,
+            median_weekly_rent,
+            sample_size: self
+                .new_bonds
+                .and_then(|i| field(i).replace(',', "").parse().ok()),
+            dwelling_type: None,
+            period,
+        }))
+    }
+}
+
+/// Last day of the quarter a label like "Sep-24", "Sep 2024", "Jun Qtr 2024"
+/// or "Q3 2024" ends with; None unless the month is March, June, September
+/// or December
+fn parse_quarter_label(label: &str) -> Option<NaiveDate> {
+    let words: Vec<&str> = label
+        .split(['-', ' '])
+        .filter(|w| !w.is_empty() && !w.eq_ignore_ascii_case("qtr"))
+        .collect();
+    let [first, second] = words[..] else {
+        return None;
+    };
+    // "Q3" before or after the year, otherwise the quarter's last month
+    let quarter = |w: &str| match w.strip_prefix(['Q', 'q'])?.parse::<u32>() {
+        Ok(q @ 1..=4) => Some(q * 3),
+        _ => None,
+    };
+    let (month, year) = match (quarter(first), quarter(second)) {
+        (Some(month), _) => (month, second),
+        (None, Some(month)) => (month, first),
+        (None, None) => (
+            first.parse::<chrono::Month>().ok()?.number_from_month(),
+            second,
+        ),
+    };
+    if month % 3 != 0 {
+        return None;
+    }
+    let year: i32 = match year {
+        year if year.len() == 2 => 2000 + year.parse::<i32>().ok()?,
+        year if year.len() == 4 => year.parse().ok()?,
+        _ => return None,
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)?
+        .checked_add_months(chrono::Months::new(1))?
+        .pred_opt()
+}
+
+/// Parse the detailed NSW bond lodgement XLSX into individual observations
+///
+/// The monthly file has a few title rows above the header, so columns are
+/// located by header name. Rows without a postcode or a usable rent are skipped.
+pub async fn parse_nsw_bond_lodgements(
+    raw: RawData,
+    period: NaiveDate,
+) -> Result<Vec<RentalObservation>> {
+    let bytes = raw.as_bytes()?;
+    info!("Parsing NSW bond lodgements XLSX ({} bytes)", bytes.len());
+
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(bytes))?;
+    let sheet_name = workbook
+        .sheet_names()
+        .first()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No sheets found in workbook"))?;
+    let range = workbook.worksheet_range(&sheet_name)?;
+
+    let mut rows = range.rows();
+    let columns = rows
+        .by_ref()
+        .take(HEADER_SEARCH_ROWS)
+        .find_map(BondColumns::from_header)
+        .ok_or_else(|| anyhow::anyhow!("No bond lodgement header row in {}", sheet_name))?;
+
+    let mut observations = Vec::new();
+    let mut skipped = 0;
+
+    for row in rows {
+        match columns.observation(row, period) {
+            Some(observation) => observations.push(observation),
+            None => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        warn!("Skipped {} bond lodgement rows", skipped);
+    }
+    info!("Parsed {} bond lodgements from XLSX", observations.len());
+
+    Ok(observations)
+}
+
+/// Title rows searched for the header in the rental bond files
+const HEADER_SEARCH_ROWS: usize = 20;
+
+/// Column positions in the lodgement sheet
+struct BondColumns {
+    postcode: usize,
+    dwelling_type: usize,
+    bedrooms: usize,
+    weekly_rent: usize,
+}
+
+impl BondColumns {
+    fn from_header(row: &[Data]) -> Option<Self> {
+        let find = |name: &str| {
+            row.iter().position(|cell| match cell {
+                Data::String(s) => s.trim().eq_ignore_ascii_case(name),
+                _ => false,
+            })
+        };
+
+        Some(BondColumns {
+            postcode: find("Postcode")?,
+            dwelling_type: find("Dwelling Type")?,
+            bedrooms: find("Bedrooms")?,
+            weekly_rent: find("Weekly Rent")?,
+        })
+    }
+
+    fn observation(&self, row: &[Data], period: NaiveDate) -> Option<RentalObservation> {
+        let postcode = match row.get(self.postcode)? {
+            Data::String(s) if !s.trim().is_empty() => s.trim().to_string(),
+            Data::Int(i) => i.to_string(),
+            Data::Float(f) => format!("{:.0}", f),
+            _ => return None,
+        };
+
+        let weekly_rent = match row.get(self.weekly_rent)? {
+            Data::Int(i) => *i as i32,
+            Data::Float(f) => *f as i32,
+            Data::String(s) => s.replace(['$', ','], "").trim().parse().ok()?,
+            _ => return None,
+        };
+        if weekly_rent <= 0 {
+            return None;
+        }
+
+        // "U" marks an unknown bedroom count
+        let bedrooms = match row.get(self.bedrooms) {
+            Some(Data::Int(i)) => Some(*i as i32),
+            Some(Data::Float(f)) => Some(*f as i32),
+            Some(Data::String(s)) => s.trim().parse().ok(),
+            _ => None,
+        };
+
+        let dwelling_type = match row.get(self.dwelling_type) {
+            Some(Data::String(s)) => parse_bond_dwelling_type(s),
+            _ => PropertyType::Other,
+        };
+
+        Some(RentalObservation {
+            state: State::NSW,
+            postcode,
+            dwelling_type,
+            bedrooms,
+            weekly_rent,
+            period,
+        })
+    }
+}
+
+/// Bond dwelling type codes: F flat/unit, H house, T terrace/townhouse
+fn parse_bond_dwelling_type(code: &str) -> PropertyType {
+    match code.trim().to_ascii_uppercase().as_str() {
+        "F" => PropertyType::Unit,
+        "H" => PropertyType::House,
+        "T" => PropertyType::Townhouse,
+        _ => PropertyType::Other,
+    }
+}
+
+/// Parse the ABS postcode to SA3 correspondence CSV into one region per postcode
+/// Postcodes split across SA3s keep the mapping with the highest ratio
+pub async fn parse_abs_postcode_regions(raw: RawData) -> Result<Vec<PostcodeRegion>> {
+    let bytes = raw.as_bytes()?;
+    info!("Parsing ABS postcode correspondence CSV ({} bytes)", bytes.len());
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(bytes);
+    let columns = CorrespondenceColumns::from_header(reader.headers()?).ok_or_else(|| {
+        anyhow::anyhow!("No postcode, SA3 and ratio columns in correspondence header")
+    })?;
+
+    let mut best: HashMap<String, PostcodeRegion> = HashMap::new();
+    let mut rows = 0;
+    let mut skipped = 0;
+
+    for result in reader.records() {
+        rows += 1;
+        let Some(region) = result.ok().and_then(|row| columns.region(&row)) else {
+            skipped += 1;
+            continue;
+        };
+
+        let replace = best.get(&region.postcode).is_none_or(|current| {
+            region.ratio > current.ratio
+                || (region.ratio == current.ratio && region.sa3_code < current.sa3_code)
+        });
+        if replace {
+            best.insert(region.postcode.clone(), region);
+        }
+    }
+
+    if skipped > 0 {
+        warn!("Skipped {} correspondence rows", skipped);
+    }
+
+    let mut regions: Vec<PostcodeRegion> = best.into_values().collect();
+    regions.sort_by(|a, b| a.postcode.cmp(&b.postcode));
+    info!(
+        "Mapped {} postcodes to SA3 regions from {} rows",
+        regions.len(),
+        rows
+    );
+
+    Ok(regions)
+}
+
+/// Column positions in the correspondence file
+/// Headers carry the ASGS edition (POA_CODE_2021, SA3_CODE_2021, ...), so match on prefix
+struct CorrespondenceColumns {
+    postcode: usize,
+    sa3_code: usize,
+    sa3_name: usize,
+    sa4_name: Option<usize>,
+    ratio: usize,
+}
+
+impl CorrespondenceColumns {
+    fn from_header(header: &csv::StringRecord) -> Option<Self> {
+        let find = |prefix: &str| {
+            header
+                .iter()
+                .position(|name| name.trim().to_ascii_uppercase().starts_with(prefix))
+        };
+
+        Some(CorrespondenceColumns {
+            postcode: find("POA_CODE")?,
+            sa3_code: find("SA3_CODE")?,
+            sa3_name: find("SA3_NAME")?,
+            sa4_name: find("SA4_NAME"),
+            ratio: find("RATIO")?,
+        })
+    }
+
+    fn region(&self, row: &csv::StringRecord) -> Option<PostcodeRegion> {
+        let field = |i: usize| row.get(i).map(str::trim).filter(|s| !s.is_empty());
+
+        // Older editions prefix postcodes with "POA"
+        let postcode = field(self.postcode)?.trim_start_matches("POA");
+        if postcode.len() != 4 || !postcode.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let sa3_code = field(self.sa3_code)?;
+        if sa3_code.len() != 5 || !sa3_code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let ratio: f64 = field(self.ratio)?.parse().ok()?;
+        if !(ratio > 0.0 && ratio <= 1.0) {
+            return None;
+        }
+
+        Some(PostcodeRegion {
+            postcode: postcode.to_string(),
+            sa3_code: sa3_code.to_string(),
+            sa3_name: field(self.sa3_name)?.to_string(),
+            sa4_code: sa3_code[..3].to_string(),
+            sa4_name: self.sa4_name.and_then(field).map(str::to_string),
+            state: state_from_asgs_code(sa3_code),
+            ratio,
+        })
+    }
+}
+
+/// State from the leading digit of an ASGS region code
+fn state_from_asgs_code(code: &str) -> Option<State> {
+    match code.chars().next()? {
+        '1' => Some(State::NSW),
+        '2' => Some(State::VIC),
+        '3' => Some(State::QLD),
+        '4' => Some(State::SA),
+        '5' => Some(State::WA),
+        '6' => Some(State::TAS),
+        '7' => Some(State::NT),
+        '8' => Some(State::ACT),
+        _ => None,
+    }
+}
+
+/// Parse an ABS suburb (SAL) or postcode (POA) to LGA correspondence CSV into
+/// one LGA per area; areas split across LGAs keep the mapping with the highest ratio
+pub async fn parse_lga_correspondence(raw: RawData) -> Result<Vec<LgaCorrespondence>> {
+    let bytes = raw.as_bytes()?;
+    info!("Parsing ABS LGA correspondence CSV ({} bytes)", bytes.len());
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(bytes);
+    let columns = LgaCorrespondenceColumns::from_header(reader.headers()?).ok_or_else(|| {
+        anyhow::anyhow!("No suburb or postcode, LGA and ratio columns in correspondence header")
+    })?;
+
+    let mut best: HashMap<(String, State), LgaCorrespondence> = HashMap::new();
+    let mut rows = 0;
+    let mut skipped = 0;
+
+    for result in reader.records() {
+        rows += 1;
+        let Some(mapping) = result.ok().and_then(|row| columns.mapping(&row)) else {
+            skipped += 1;
+            continue;
+        };
+
+        let key = (mapping.area.clone(), mapping.state);
+        let replace = best.get(&key).is_none_or(|current| {
+            mapping.ratio > current.ratio
+                || (mapping.ratio == current.ratio && mapping.lga_code < current.lga_code)
+        });
+        if replace {
+            best.insert(key, mapping);
+        }
+    }
+
+    if skipped > 0 {
+        warn!("Skipped {} LGA correspondence rows", skipped);
+    }
+
+    let mut mappings: Vec<LgaCorrespondence> = best.into_values().collect();
+    mappings.sort_by(|a, b| (a.state as u8, &a.area).cmp(&(b.state as u8, &b.area)));
+    info!(
+        "Mapped {} {}s to LGAs from {} rows",
+        mappings.len(),
+        columns.area_type.as_str(),
+        rows
+    );
+
+    Ok(mappings)
+}
+
+/// Column positions in an LGA correspondence file, matched on prefix like the SA3 file
+struct LgaCorrespondenceColumns {
+    area_type: LgaAreaType,
+    area: usize,
+    lga_code: usize,
+    lga_name: usize,
+    ratio: usize,
+}
+
+impl LgaCorrespondenceColumns {
+    fn from_header(header: &csv::StringRecord) -> Option<Self> {
+        let find = |prefix: &str| {
+            header
+                .iter()
+                .position(|name| name.trim().to_ascii_uppercase().starts_with(prefix))
+        };
+
+        let (area_type, area) = match find("SAL_NAME") {
+            Some(i) => (LgaAreaType::Suburb, i),
+            None => (LgaAreaType::Postcode, find("POA_CODE")?),
+        };
+
+        Some(LgaCorrespondenceColumns {
+            area_type,
+            area,
+            lga_code: find("LGA_CODE")?,
+            lga_name: find("LGA_NAME")?,
+            ratio: find("RATIO")?,
+        })
+    }
+
+    fn mapping(&self, row: &csv::StringRecord) -> Option<LgaCorrespondence> {
+        let field = |i: usize| row.get(i).map(str::trim).filter(|s| !s.is_empty());
+
+        let area = field(self.area)?;
+        let area = match self.area_type {
+            LgaAreaType::Suburb => normalize_locality_name(area)?,
+            LgaAreaType::Postcode => {
+                let postcode = area.trim_start_matches("POA");
+                if postcode.len() != 4 || !postcode.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                postcode.to_string()
+            }
+        };
+        let lga_code = field(self.lga_code)?;
+        if lga_code.len() != 5 || !lga_code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let ratio: f64 = field(self.ratio)?.parse().ok()?;
+        if !(ratio > 0.0 && ratio <= 1.0) {
+            return None;
+        }
+
+        Some(LgaCorrespondence {
+            area_type: self.area_type,
+            area,
+            state: state_from_asgs_code(lga_code)?,
+            lga_code: lga_code.to_string(),
+            lga_name: field(self.lga_name)?.to_string(),
+            ratio,
+        })
+    }
+}
+
+/// Upper-case locality name without the ABS disambiguation suffix:
+/// "Springfield (Gosford - NSW)" becomes "SPRINGFIELD"
+fn normalize_locality_name(name: &str) -> Option<String> {
+    let name = match name.find(" (") {
+        Some(i) if name.ends_with(')') => &name[..i],
+        _ => name,
+    };
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_uppercase())
+}
+
+/// Parse a council rates CSV keyed by LGA code into one row per council
+/// LGAs listed for several financial years keep the latest
+pub async fn parse_council_rates(raw: RawData) -> Result<Vec<CouncilRates>> {
+    let bytes = raw.as_bytes()?;
+    info!("Parsing council rates CSV ({} bytes)", bytes.len());
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(bytes);
+    let columns = CouncilRatesColumns::from_header(reader.headers()?).ok_or_else(|| {
+        anyhow::anyhow!("No LGA code, LGA name and average rates columns in council rates header")
+    })?;
+
+    let mut latest: HashMap<String, CouncilRates> = HashMap::new();
+    let mut rows = 0;
+    let mut skipped = 0;
+
+    for result in reader.records() {
+        rows += 1;
+        let Some(rates) = result.ok().and_then(|row| columns.rates(&row)) else {
+            skipped += 1;
+            continue;
+        };
+
+        let replace = latest
+            .get(&rates.lga_code)
+            .is_none_or(|current| rates.financial_year > current.financial_year);
+        if replace {
+            latest.insert(rates.lga_code.clone(), rates);
+        }
+    }
+
+    if skipped > 0 {
+        warn!("Skipped {} council rates rows", skipped);
+    }
+
+    let mut councils: Vec<CouncilRates> = latest.into_values().collect();
+    councils.sort_by(|a, b| a.lga_code.cmp(&b.lga_code));
+    info!(
+        "Parsed rates for {} councils from {} rows",
+        councils.len(),
+        rows
+    );
+
+    Ok(councils)
+}
+
+/// Column positions in the council rates file
+/// Header spellings vary between publishers, so match on normalized prefix
+struct CouncilRatesColumns {
+    lga_code: usize,
+    lga_name: usize,
+    financial_year: Option<usize>,
+    average_rates: usize,
+    average_levies: Option<usize>,
+}
+
+impl CouncilRatesColumns {
+    fn from_header(header: &csv::StringRecord) -> Option<Self> {
+        let normalized: Vec<String> = header
+            .iter()
+            .map(|name| name.trim().to_ascii_uppercase().replace([' ', '-'], "_"))
+            .collect();
+        let find = |prefixes: &[&str]| {
+            normalized
+                .iter()
+                .position(|name| prefixes.iter().any(|prefix| name.starts_with(prefix)))
+        };
+
+        Some(CouncilRatesColumns {
+            lga_code: find(&["LGA_CODE"])?,
+            lga_name: find(&["LGA_NAME", "COUNCIL"])?,
+            financial_year: find(&["FINANCIAL_YEAR", "YEAR"]),
+            average_rates: find(&["AVERAGE_RESIDENTIAL_RATE", "AVERAGE_RATE"])?,
+            average_levies: find(&["AVERAGE_RESIDENTIAL_LEV", "AVERAGE_LEV"]),
+        })
+    }
+
+    fn rates(&self, row: &csv::StringRecord) -> Option<CouncilRates> {
+        let field = |i: usize| row.get(i).map(str::trim).filter(|s| !s.is_empty());
+
+        let lga_code = field(self.lga_code)?;
+        if lga_code.len() != 5 || !lga_code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let average_rates = parse_annual_dollars(field(self.average_rates)?)?;
+
+        Some(CouncilRates {
+            lga_code: lga_code.to_string(),
+            lga_name: field(self.lga_name)?.to_string(),
+            state: state_from_asgs_code(lga_code),
+            financial_year: self.financial_year.and_then(field).map(str::to_string),
+            average_rates,
+            average_levies: self
+                .average_levies
+                .and_then(field)
+                .and_then(parse_annual_dollars),
+        })
+    }
+}
+
+/// Whole dollars from "$1,234.56"; None for zero, negative or unreadable amounts
+fn parse_annual_dollars(value: &str) -> Option<i32> {
+    let cleaned: String = value
+        .chars()
+        .filter(|c| !matches!(c, '$' | ',' | ' '))
+        .collect();
+    let dollars: f64 = cleaned.parse().ok()?;
+    (dollars > 0.0 && dollars < i32::MAX as f64).then(|| dollars.round() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("25/12/2023"),
+            Some(NaiveDate::from_ymd_opt(2023, 12, 25).unwrap())
+        );
+
+        assert_eq!(
+            parse_date("01/01/2024"),
+            Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())
+        );
+
+        assert_eq!(parse_date("invalid"), None);
+    }
+
+    #[test]
+    fn test_parse_flexible_date() {
+        let day = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        // (input, date, whether it took a fallback format)
+        let cases = [
+            ("25/12/2023", Some(("2023-12-25", false))),
+            ("5/3/2024", Some(("2024-03-05", false))),
+            (" 01/01/2024 ", Some(("2024-01-01", false))),
+            ("5/03/2024 0:00", Some(("2024-03-05", true))),
+            ("5/3/24", Some(("2024-03-05", true))),
+            ("25/12/99", Some(("1999-12-25", true))),
+            ("31/1/49", Some(("2049-01-31", true))),
+            ("2024-03-05", Some(("2024-03-05", true))),
+            ("2024-03-05T10:30:00", Some(("2024-03-05", true))),
+            ("2024/03/05", Some(("2024-03-05", true))),
+            ("05-03-2024", Some(("2024-03-05", true))),
+            ("05.03.2024", Some(("2024-03-05", true))),
+            ("05-Mar-2024", Some(("2024-03-05", true))),
+            ("5 March 2024", Some(("2024-03-05", true))),
+            ("20240305", Some(("2024-03-05", true))),
+            ("45356", Some(("2024-03-05", true))),
+            ("45356.75", Some(("2024-03-05", true))),
+            // Day, month and year order can't be told apart
+            ("03/04/05", None),
+            ("12/1/10", None),
+            ("31/02/2024", None),
+            ("5-3-24", None),
+            ("2024", None),
+            ("", None),
+            ("invalid", None),
+        ];
+        for (input, expected) in cases {
+            let expected = expected.map(|(date, fallback)| FlexibleDate {
+                date: day(date),
+                fallback,
+            });
+            assert_eq!(read_date(input), expected, "{:?}", input);
+            assert_eq!(
+                parse_flexible_date(input),
+                expected.map(|read| read.date),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_excel_serial_to_date() {
+        let day = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok();
+        assert_eq!(excel_serial_to_date(1.0), day("1900-01-01"));
+        assert_eq!(excel_serial_to_date(59.0), day("1900-02-28"));
+        // Excel's 29 February 1900 never happened
+        assert_eq!(excel_serial_to_date(60.0), None);
+        assert_eq!(excel_serial_to_date(61.0), day("1900-03-01"));
+        assert_eq!(excel_serial_to_date(45292.0), day("2024-01-01"));
+        assert_eq!(excel_serial_to_date(45292.99), day("2024-01-01"));
+        assert_eq!(excel_serial_to_date(2_958_465.0), day("9999-12-31"));
+        assert_eq!(excel_serial_to_date(0.0), None);
+        assert_eq!(excel_serial_to_date(-3.0), None);
+        assert_eq!(excel_serial_to_date(f64::NAN), None);
+        assert_eq!(excel_serial_to_date(2_958_466.0), None);
+    }
+
+    #[test]
+    fn test_parse_nsw_row() {
+        let row = NswSalesRow {
+            property_id: "12345".to_string(),
+            property_unit_number: None,
+            property_house_number: Some("10".to_string()),
+            property_street_name: "Smith Street".to_string(),
+            property_locality: "Sydney".to_string(),
+            property_post_code: "2000".to_string(),
+            purchase_price: "$750,000".to_string(),
+            settlement_date: "15/06/2023".to_string(),
+            contract_date: None,
+            nature_of_property: "Residential - House".to_string(),
+            district_code: Some(" 1".to_string()),
+        };
+
+        let record = parse_nsw_row(row, "nsw_sales").unwrap();
+
+        assert_eq!(record.address, "10 Smith Street");
+        assert_eq!(record.suburb, "Sydney");
+        assert_eq!(record.state, State::NSW);
+        assert_eq!(record.postcode, Some("2000".to_string()));
+        assert_eq!(record.sale_price, Some(750_000));
+        assert_eq!(record.property_type, PropertyType::House);
+        assert_eq!(record.district.as_deref(), Some("001"));
+        assert_eq!(record.source_metadata.confidence_score, 0.9);
+    }
+
+    #[test]
+    fn test_parse_nsw_row_fallback_date() {
+        let row = |settlement_date: &str| NswSalesRow {
+            property_id: "12345".to_string(),
+            property_unit_number: None,
+            property_house_number: Some("10".to_string()),
+            property_street_name: "Smith Street".to_string(),
+            property_locality: "Sydney".to_string(),
+            property_post_code: "2000".to_string(),
+            purchase_price: "$750,000".to_string(),
+            settlement_date: settlement_date.to_string(),
+            contract_date: None,
+            nature_of_property: "Residential - House".to_string(),
+            district_code: None,
+        };
+
+        let record = parse_nsw_row(row("2023-06-15"), "nsw_sales").unwrap();
+        assert_eq!(record.sale_date, NaiveDate::from_ymd_opt(2023, 6, 15));
+        assert_eq!(record.source_metadata.confidence_score, 0.9 - FALLBACK_DATE_PENALTY);
+
+        let record = parse_nsw_row(row("03/04/05"), "nsw_sales").unwrap();
+        assert_eq!(record.sale_date, None);
+        assert_eq!(record.source_metadata.confidence_score, 0.9);
+    }
+
+    #[test]
+    fn test_collapse_repeat_sales() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        let sale = |id: Option<&str>, price: i64, sale_date| {
+            let mut record = parse_nsw_row(
+                NswSalesRow {
+                    property_id: String::new(),
+                    property_unit_number: None,
+                    property_house_number: Some("10".to_string()),
+                    property_street_name: "Smith Street".to_string(),
+                    property_locality: "Sydney".to_string(),
+                    property_post_code: "2000".to_string(),
+                    purchase_price: price.to_string(),
+                    settlement_date: String::new(),
+                    contract_date: None,
+                    nature_of_property: "R".to_string(),
+                    district_code: None,
+                },
+                "nsw_sales",
+            )
+            .unwrap();
+            record.external_id = id.map(str::to_string);
+            record.sale_date = sale_date;
+            record
+        };
+
+        // Three sales of one property out of order, the middle one corrected
+        let records = vec![
+            sale(Some("1"), 690_000, date(2018, 7, 2)),
+            sale(Some("2"), 1_100_000, date(2020, 1, 1)),
+            sale(Some("1"), 910_000, date(2023, 5, 19)),
+            sale(None, 400_000, date(2019, 1, 1)),
+            sale(Some("1"), 520_000, date(2012, 3, 9)),
+            sale(Some("1"), 69_000, date(2018, 7, 2)),
+            sale(None, 400_000, date(2019, 1, 1)),
+        ];
+        let collapsed = collapse_repeat_sales(records);
+
+        let summary: Vec<_> = collapsed
+            .iter()
+            .map(|r| (r.external_id.as_deref(), r.sale_price, r.sale_date))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("1"), Some(910_000), date(2023, 5, 19)),
+                (Some("2"), Some(1_100_000), date(2020, 1, 1)),
+                (None, Some(400_000), date(2019, 1, 1)),
+                (None, Some(400_000), date(2019, 1, 1)),
+            ]
+        );
+        assert_eq!(
+            collapsed[0].prior_sales,
+            vec![
+                (520_000, date(2012, 3, 9).unwrap()),
+                (690_000, date(2018, 7, 2).unwrap()),
+            ]
+        );
+        assert!(collapsed[1].prior_sales.is_empty());
+
+        // A tie on the latest date keeps the higher price, and drops the other
+        let tied = collapse_repeat_sales(vec![
+            sale(Some("1"), 910_000, date(2023, 5, 19)),
+            sale(Some("1"), 950_000, date(2023, 5, 19)),
+        ]);
+        assert_eq!(tied.len(), 1);
+        assert_eq!(tied[0].sale_price, Some(950_000));
+        assert!(tied[0].prior_sales.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_nsw_sales_reader_collapses_repeat_sales() {
+        let csv = format!(
+            "{}\n{}\n{}\n{}\n{}\n",
+            NSW_SALES_COLUMNS.join(","),
+            "7,,3,King Street,Newtown,2042,$1100000,01/02/2020,,R",
+            "7,,3,King Street,Newtown,2042,$1450000,14/11/2024,,R",
+            "8,,5,King Street,Newtown,2042,$900000,01/03/2021,,R",
+            "7,,3,King Street,Newtown,2042,$640000,30/06/2009,,R"
+        );
+        let mut reader = NswSalesReader::from_raw(RawData::from(csv.as_str()), "nsw_sales");
+        let records = reader.by_ref().collect::<Result<Vec<_>>>().unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].sale_price, Some(1_450_000));
+        assert_eq!(records[0].source_metadata.source_row, Some(1));
+        let prior: Vec<_> = records[0].prior_sales.iter().map(|(price, _)| *price).collect();
+        assert_eq!(prior, vec![640_000, 1_100_000]);
+        assert_eq!((reader.records(), reader.repeat_sales()), (2, 2));
+    }
+
+    #[tokio::test]
+    async fn test_parse_nsw_sales_rejected_postcodes() {
+        let csv = format!(
+            "{}\n{}\n{}\n{}\n",
+            NSW_SALES_COLUMNS.join(","),
+            "1,,10,Smith Street,Sydney,2000.0,750000,15/06/2023,,R",
+            "2,,12,Smith Street,Sydney,UNKNOWN,750000,15/06/2023,,R",
+            "3,,14,Smith Street,Sydney,,750000,15/06/2023,,R"
+        );
+        let report = parse_nsw_sales(RawData::from(csv.as_str()), "nsw_sales".to_string())
+            .await
+            .unwrap();
+
+        let postcodes: Vec<_> = report.records.iter().map(|r| r.postcode.as_deref()).collect();
+        assert_eq!(postcodes, vec![Some("2000"), None, None]);
+        // A blank postcode was never given, so isn't counted
+        assert_eq!(report.rejected_postcodes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_parse_nsw_sales_district_column() {
+        let csv = format!(
+            "{},District code\n{}\n{}\n{}\n",
+            NSW_SALES_COLUMNS.join(","),
+            "1,,10,Smith Street,Sydney,2000,750000,15/06/2023,,R,1",
+            "2,,12,Smith Street,Sydney,2000,750000,15/06/2023,,R,",
+            "3,,14,Smith Street,Sydney,2000,750000,15/06/2023,,R,ABC"
+        );
+        let report = parse_nsw_sales(RawData::from(csv.as_str()), "nsw_sales".to_string())
+            .await
+            .unwrap();
+
+        let districts: Vec<_> = report.records.iter().map(|r| r.district.as_deref()).collect();
+        assert_eq!(districts, vec![Some("001"), None, None]);
+
+        // Releases without the column still parse
+        let csv = format!(
+            "{}\n{}\n",
+            NSW_SALES_COLUMNS.join(","),
+            "1,,10,Smith Street,Sydney,2000,750000,15/06/2023,,R"
+        );
+        let report = parse_nsw_sales(RawData::from(csv.as_str()), "nsw_sales".to_string())
+            .await
+            .unwrap();
+        assert_eq!(report.records.len(), 1);
+        assert_eq!(report.records[0].district, None);
+    }
+
+    #[tokio::test]
+    async fn test_parse_nsw_sales_api_nested_address() {
+        let json = serde_json::json!([
+            {
+                "propertyId": "4172839",
+                "address": {
+                    "unitNumber": "2",
+                    "houseNumber": "10",
+                    "streetName": "Smith Street",
+                    "locality": "Parramatta",
+                    "postcode": ""
+                },
+                "natureOfProperty": "Unit",
+                "purchasePrice": "2450000.00",
+                "settlementDate": "2025-09-29",
+                "updatedAt": "2025-10-01T03:12:45+10:00"
+            },
+            { "propertyId": "missing address", "updatedAt": "2025-10-01T00:00:00Z" }
+        ]);
+        let raw = RawData::Json(serde_json::to_vec(&json).unwrap());
+
+        let report = parse_nsw_sales_api(raw, "nsw_sales_api".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(report.total_rows, 2);
+        assert_eq!(report.records.len(), 1);
+        let record = &report.records[0];
+        assert_eq!(record.external_id.as_deref(), Some("4172839"));
+        assert_eq!(record.address, "2/10 Smith Street");
+        assert_eq!(record.property_type, PropertyType::Unit);
+        assert_eq!(record.postcode, None);
+        assert_eq!(record.sale_price, Some(2_450_000));
+        assert_eq!(record.sale_date, NaiveDate::from_ymd_opt(2025, 9, 29));
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].row_number, 2);
+        assert_eq!(report.errors[0].column, None);
+    }
+
+    #[tokio::test]
+    async fn test_parse_nsw_sales_skips_files_with_other_headers() {
+        let temp = tempfile::tempdir().unwrap();
+        let header = NSW_SALES_COLUMNS.join(",");
+        let sales = temp.path().join("district_001.csv");
+        std::fs::write(
+            &sales,
+            format!(
+                "{}\n1,,3,King Street,Newtown,2042,$1200000,01/02/2024,,Residential - House\n",
+                header
+            ),
+        )
+        .unwrap();
+        let other = temp.path().join("district_index.csv");
+        std::fs::write(&other, "District code,District name\n1,Sydney\n").unwrap();
+
+        let raw = RawData::Files(vec![other, sales]);
+        let records = parse_nsw_sales(raw, "nsw_sales".to_string()).await.unwrap().records;
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].suburb, "Newtown");
+        assert_eq!(records[0].sale_price, Some(1_200_000));
+    }
+
+    #[tokio::test]
+    async fn test_parse_nsw_sales_quoted_prices_from_string() {
+        let csv = format!(
+            "{}\n\
+             1,,10,Smith Street,Sydney,2000,\"$1,250,000\",15/06/2023,,Residential - House\n\
+             2,4,22,George St,Parramatta,2150,$520000,01/07/2023,,Residential - Unit\n\
+             3,,88,Market St,Sydney,2000,POA,05/02/2024,,Commercial - Retail\n",
+            NSW_SALES_COLUMNS.join(",")
+        );
+
+        let report = parse_nsw_sales(RawData::from(csv.as_str()), "nsw_sales".to_string())
+            .await
+            .unwrap();
+        let prices: Vec<_> = report.records.iter().map(|r| r.sale_price).collect();
+        assert_eq!(prices, vec![Some(1_250_000), Some(520_000), None]);
+        assert_eq!(report.records[1].address, "4/22 George Street");
+        assert!(report.errors.is_empty());
+
+        // The same CSV as bytes
+        let bytes = RawData::from(csv.into_bytes());
+        let report = parse_nsw_sales(bytes, "nsw_sales".to_string()).await.unwrap();
+        assert_eq!(report.records.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_parse_nsw_sales_missing_column_from_string() {
+        // No Purchase price column, so the CSV isn't taken for a sales file
+        let header: Vec<_> = NSW_SALES_COLUMNS
+            .into_iter()
+            .filter(|c| *c != "Purchase price")
+            .collect();
+        let csv = format!(
+            "{}\n1,,10,Smith Street,Sydney,2000,15/06/2023,,Residential - House\n",
+            header.join(",")
+        );
+
+        let report = parse_nsw_sales(RawData::from(csv.as_str()), "nsw_sales".to_string())
+            .await
+            .unwrap();
+        assert!(report.records.is_empty());
+        assert!(report.errors.is_empty());
+        assert_eq!(report.total_rows, 0);
+    }
+
+    #[test]
+    fn test_rental_columns_by_header_alias() {
+        let text = |cells: &[&str]| -> Vec<Data> {
+            cells.iter().map(|c| Data::String(c.to_string())).collect()
+        };
+        let rows = [
+            text(&["Rental Bond Data"]),
+            text(&["New Bonds", "Median Rent", "Post Code", "Number of Bedrooms"]),
+            text(&["12", "$500", "2000", "1"]),
+        ];
+        let mut iter = rows.iter().map(Vec::as_slice);
+        assert_eq!(
+            RentalColumns::locate(&mut iter).unwrap(),
+            RentalColumns {
+                postcode: 2,
+                suburb: None,
+                bedrooms: 3,
+                median_rent: 1,
+                sample_size: Some(0),
+                month: None,
+                dwelling_type: None,
+            }
+        );
+        // Left at the first data row
+        assert_eq!(iter.next(), Some(rows[2].as_slice()));
+
+        let rows = [
+            text(&["Rental Bond Data"]),
+            text(&["Dwelling Type", "Postcode", "Beds", "Weekly Rent"]),
+            vec![Data::Empty, Data::Int(2000)],
+        ];
+        let error = RentalColumns::locate(&mut rows.iter().map(Vec::as_slice)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "no Bedrooms, Median Rent column; headers found: Dwelling Type, Postcode, Beds, \
+             Weekly Rent"
+        );
+    }
+
+    #[test]
+    fn test_rental_median_month_column() {
+        let columns = RentalColumns {
+            postcode: 0,
+            suburb: None,
+            bedrooms: 1,
+            median_rent: 2,
+            sample_size: None,
+            month: Some(3),
+            dwelling_type: None,
+        };
+        let file_period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+        let period = |month: Data| {
+            let row = [Data::Int(2000), Data::Int(2), Data::Int(650), month];
+            columns.median(&row, file_period, None).map(|m| m.unwrap().period)
+        };
+        let march = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+
+        assert_eq!(period(Data::Float(45352.0)), Ok(march));
+        assert_eq!(period(Data::String("1/03/2024".to_string())), Ok(march));
+        assert_eq!(period(Data::String("2024-03-01".to_string())), Ok(march));
+        assert_eq!(period(Data::Empty), Ok(file_period));
+        assert_eq!(
+            period(Data::String("March".to_string())),
+            Err(("Month", "not a date: \"March\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rental_median_postcode() {
+        let columns = RentalColumns {
+            postcode: 0,
+            suburb: None,
+            bedrooms: 1,
+            median_rent: 2,
+            sample_size: None,
+            month: None,
+            dwelling_type: None,
+        };
+        let period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+        let postcode = |postcode: Data| {
+            let row = [postcode, Data::Int(2), Data::Int(650)];
+            columns.median(&row, period, None).map(|m| m.unwrap().postcode)
+        };
+
+        assert_eq!(postcode(Data::Float(2000.0)), Ok("2000".to_string()));
+        assert_eq!(postcode(Data::String(" 2010 ".to_string())), Ok("2010".to_string()));
+        assert_eq!(
+            postcode(Data::Int(3000)),
+            Err(("Postcode", "not a NSW postcode: \"3000\"".to_string()))
+        );
+        assert_eq!(
+            postcode(Data::Empty),
+            Err(("Postcode", "no NSW postcode".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rental_median_dwelling_type() {
+        let columns = RentalColumns {
+            postcode: 0,
+            suburb: None,
+            bedrooms: 1,
+            median_rent: 2,
+            sample_size: Some(3),
+            month: None,
+            dwelling_type: Some(4),
+        };
+        let period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+        let median = |dwelling: Data, sheet: Option<PropertyType>| {
+            let row = [Data::Int(2000), Data::Int(2), Data::Int(650), Data::Int(38), dwelling];
+            columns.median(&row, period, sheet).unwrap().unwrap()
+        };
+        let text = |s: &str| Data::String(s.to_string());
+
+        let house = median(text("House"), None);
+        assert_eq!(house.dwelling_type, Some(PropertyType::House));
+        assert_eq!(house.sample_size, Some(38));
+        assert_eq!(median(text("Flat/Unit"), None).dwelling_type, Some(PropertyType::Unit));
+        assert_eq!(
+            median(text("Townhouse"), None).dwelling_type,
+            Some(PropertyType::Townhouse)
+        );
+        assert_eq!(median(text("Total"), None).dwelling_type, None);
+        // A blank cell takes the sheet's type
+        assert_eq!(
+            median(Data::Empty, Some(PropertyType::Unit)).dwelling_type,
+            Some(PropertyType::Unit)
+        );
+    }
+
+    #[test]
+    fn test_sheet_dwelling_type() {
+        assert_eq!(sheet_dwelling_type("Houses"), Some(PropertyType::House));
+        assert_eq!(sheet_dwelling_type("Flats/Units"), Some(PropertyType::Unit));
+        assert_eq!(sheet_dwelling_type("Townhouses"), Some(PropertyType::Townhouse));
+        for name in ["All Dwellings", "Total", "Rents", "Sheet1"] {
+            assert_eq!(sheet_dwelling_type(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_all_dwellings_names() {
+        for name in ["All Dwellings", "ALL", "Total", "Postcode totals - all"] {
+            assert!(is_all_dwellings(name), "{}", name);
+        }
+        for name in ["Houses", "Flats/Units", "Allawah", "Notes"] {
+            assert!(!is_all_dwellings(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_parse_quarter_label() {
+        let day = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(parse_quarter_label("Sep-24"), Some(day("2024-09-30")));
+        assert_eq!(parse_quarter_label("Mar-24"), Some(day("2024-03-31")));
+        assert_eq!(parse_quarter_label(" Dec 2023 "), Some(day("2023-12-31")));
+        assert_eq!(parse_quarter_label("June-25"), Some(day("2025-06-30")));
+        assert_eq!(parse_quarter_label("Jun Qtr 2024"), Some(day("2024-06-30")));
+        assert_eq!(parse_quarter_label("Q3 2024"), Some(day("2024-09-30")));
+        assert_eq!(parse_quarter_label("2024 q1"), Some(day("2024-03-31")));
+        for label in ["Aug-24", "Sep-", "Sep-124", "2024-09", "Q5 2024", "Q3", ""] {
+            assert_eq!(parse_quarter_label(label), None, "{}", label);
+        }
+    }
+
+    #[test]
+    fn test_aggregate_median_conventions() {
+        let vic = AggregateMedian {
+            scheme: "vic_median",
+            state: State::VIC,
+            locality: "RICHMOND".to_string(),
+            postcode: None,
+            property_type: PropertyType::VacantLand,
+            bedrooms: None,
+            period: "2023".to_string(),
+            period_end: NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+            median_price: 1_250_000,
+            sales: Some(9),
+        };
+        assert_eq!(vic.address(), "RICHMOND MEDIAN (VACANT LAND)");
+        assert_eq!(vic.external_id(), "vic_median:RICHMOND:vacant_land:all:2023");
+
+        let sa = AggregateMedian {
+            scheme: "sa_median",
+            state: State::SA,
+            postcode: Some("5067".to_string()),
+            locality: "NORWOOD".to_string(),
+            property_type: PropertyType::House,
+            bedrooms: Some(3),
+            period: "2024Q3".to_string(),
+            period_end: NaiveDate::from_ymd_opt(2024, 9, 30).unwrap(),
+            sales: None,
+            ..vic.clone()
+        };
+        assert_eq!(sa.address(), "NORWOOD MEDIAN (3BR HOUSE)");
+        assert_eq!(sa.external_id(), "sa_median:NORWOOD:house:3:2024Q3");
+
+        // Thin markets get less confidence; an unknown count is not thin
+        let vic = vic.into_record("vic_sales");
+        assert_eq!(vic.source_metadata.confidence_score, 0.4);
+        assert_eq!(vic.source_metadata.data_quality, DataQuality::Aggregated);
+        assert_eq!(vic.sale_date, NaiveDate::from_ymd_opt(2023, 12, 31));
+        let sa = sa.into_record("sa_medians");
+        assert_eq!(sa.source_metadata.confidence_score, 0.8);
+        assert_eq!(sa.postcode.as_deref(), Some("5067"));
+        assert_eq!(sa.suburb, "NORWOOD");
+    }
+
+    #[test]
+    fn test_parse_bond_dwelling_type() {
+        assert_eq!(parse_bond_dwelling_type("F"), PropertyType::Unit);
+        assert_eq!(parse_bond_dwelling_type(" h "), PropertyType::House);
+        assert_eq!(parse_bond_dwelling_type("T"), PropertyType::Townhouse);
+        assert_eq!(parse_bond_dwelling_type("U"), PropertyType::Other);
+    }
+
+    #[tokio::test]
+    async fn test_split_postcode_keeps_highest_ratio() {
+        let csv = "POA_CODE_2021,SA3_CODE_2021,SA3_NAME_2021,RATIO_FROM_TO\n\
+                   2141,12503,Auburn,0.331\n\
+                   2141,12504,Parramatta,0.669\n\
+                   2151,12504,Parramatta,0.5\n\
+                   2151,12501,Carlingford,0.5\n";
+        let regions = parse_abs_postcode_regions(RawData::Bytes(csv.as_bytes().to_vec()))
+            .await
+            .unwrap();
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].postcode, "2141");
+        assert_eq!(regions[0].sa3_name, "Parramatta");
+        assert_eq!(regions[0].sa4_code, "125");
+        assert_eq!(regions[0].ratio, 0.669);
+        assert_eq!(regions[0].state, Some(State::NSW));
+        // Ties go to the lower SA3 code so reloads are stable
+        assert_eq!(regions[1].sa3_code, "12501");
+    }
+
+    #[tokio::test]
+    async fn test_split_suburb_keeps_highest_ratio_lga() {
+        let csv = "SAL_CODE_2021,SAL_NAME_2021,LGA_CODE_2021,LGA_NAME_2021,RATIO_FROM_TO\n\
+                   10001,Springfield (Gosford - NSW),11650,Central Coast (NSW),0.9\n\
+                   10001,Springfield (Gosford - NSW),18400,Wollondilly,0.1\n\
+                   30002,Springfield (Qld),33960,Ipswich,1.0\n\
+                   90001,Christmas Island,90300,Christmas Island,1.0\n";
+        let mappings = parse_lga_correspondence(RawData::Bytes(csv.as_bytes().to_vec()))
+            .await
+            .unwrap();
+
+        // Same name in two states stays two areas; Other Territories are dropped
+        assert_eq!(mappings.len(), 2);
+        assert_eq!(mappings[0].area_type, LgaAreaType::Suburb);
+        assert_eq!(mappings[0].area, "SPRINGFIELD");
+        assert_eq!(mappings[0].state, State::NSW);
+        assert_eq!(mappings[0].lga_code, "11650");
+        assert_eq!(mappings[1].state, State::QLD);
+        assert_eq!(mappings[1].lga_name, "Ipswich");
+    }
+
+    #[tokio::test]
+    async fn test_council_rates_keeps_latest_year() {
+        let csv =
+            "LGA Code,Council,Financial Year,Average Residential Rate,Average Residential Levies\n\
+                   11650,Central Coast,2022-23,\"$1,180.40\",$520\n\
+                   11650,Central Coast,2023-24,\"$1,251.90\",$545\n\
+                   18400,Wollondilly,2023-24,\"$1,702.00\",\n\
+                   1742,Bad code,2023-24,$900,\n\
+                   18050,No rates,2023-24,,\n";
+        let councils = parse_council_rates(RawData::Bytes(csv.as_bytes().to_vec()))
+            .await
+            .unwrap();
+
+        assert_eq!(councils.len(), 2);
+        assert_eq!(councils[0].lga_code, "11650");
+        assert_eq!(councils[0].financial_year.as_deref(), Some("2023-24"));
+        assert_eq!(councils[0].average_rates, 1252);
+        assert_eq!(councils[0].average_levies, Some(545));
+        assert_eq!(councils[0].annual_total(), 1797);
+        assert_eq!(councils[0].state, Some(State::NSW));
+        assert_eq!(councils[1].average_levies, None);
+    }
+
+    #[test]
+    fn test_normalize_locality_name() {
+        assert_eq!(
+            normalize_locality_name("Parramatta"),
+            Some("PARRAMATTA".to_string())
+        );
+        assert_eq!(
+            normalize_locality_name("Springfield (Gosford - NSW)"),
+            Some("SPRINGFIELD".to_string())
+        );
+        assert_eq!(
+            normalize_locality_name("Kings Park (NSW)"),
+            Some("KINGS PARK".to_string())
+        );
+        assert_eq!(normalize_locality_name(" (NSW)"), None);
+    }
+
+    #[test]
+    fn test_parse_annual_dollars() {
+        assert_eq!(parse_annual_dollars("$1,251.90"), Some(1252));
+        assert_eq!(parse_annual_dollars("980"), Some(980));
+        assert_eq!(parse_annual_dollars("$0"), None);
+        assert_eq!(parse_annual_dollars("n/a"), None);
+    }
+
+    #[test]
+    fn test_state_from_asgs_code() {
+        assert_eq!(state_from_asgs_code("11703"), Some(State::NSW));
+        assert_eq!(state_from_asgs_code("40101"), Some(State::SA));
+        assert_eq!(state_from_asgs_code("50101"), Some(State::WA));
+        assert_eq!(state_from_asgs_code("80105"), Some(State::ACT));
+        assert_eq!(state_from_asgs_code("90102"), None);
+    }
+
+    #[test]
+    fn test_error_sidecar_keeps_first_errors() {
+        let temp = tempfile::tempdir().unwrap();
+        let errors: Vec<RowError> = (0..150)
+            .map(|i| RowError {
+                source: "sales.csv".to_string(),
+                row_number: i + 2,
+                column: None,
+                message: "found record with 5 fields".to_string(),
+            })
+            .collect();
+
+        let path = write_error_sidecar(temp.path(), "nsw_sales", &errors, 1_000).unwrap();
+        assert_eq!(path, temp.path().join("nsw_sales_parse_errors.json"));
+        let sidecar: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(sidecar["total_rows"], 1_000);
+        assert_eq!(sidecar["error_count"], 150);
+        let written: Vec<RowError> = serde_json::from_value(sidecar["errors"].clone()).unwrap();
+        assert_eq!(written, errors[..SIDECAR_ERRORS]);
     }
 }
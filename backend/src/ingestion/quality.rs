@@ -0,0 +1,64 @@
+//! Data quality report - how complete the stored properties are, per data source
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// Completeness counts for the properties from one data source
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct SourceQuality {
+    /// "unknown" for properties without a recorded source
+    pub data_source: String,
+    pub properties: i64,
+    pub missing_price: i64,
+    pub missing_rent: i64,
+    /// Rents taken from a median rather than observed for the property
+    pub estimated_rent: i64,
+    pub missing_coordinates: i64,
+    pub last_updated: Option<NaiveDateTime>,
+}
+
+impl SourceQuality {
+    /// `count` as a percentage of this source's properties
+    pub fn percent(&self, count: i64) -> f64 {
+        if self.properties == 0 {
+            return 0.0;
+        }
+        count as f64 * 100.0 / self.properties as f64
+    }
+}
+
+/// Response for GET /api/admin/quality
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityReport {
+    /// Largest source first
+    pub sources: Vec<SourceQuality>,
+}
+
+impl QualityReport {
+    pub fn total_properties(&self) -> i64 {
+        self.sources.iter().map(|s| s.properties).sum()
+    }
+}
+
+pub async fn quality_report(db: &PgPool) -> Result<QualityReport, sqlx::Error> {
+    let sources = sqlx::query_as::<_, SourceQuality>(
+        r#"
+        SELECT
+            COALESCE(data_source, 'unknown') AS data_source,
+            COUNT(*) AS properties,
+            COUNT(*) FILTER (WHERE price IS NULL) AS missing_price,
+            COUNT(*) FILTER (WHERE weekly_rent IS NULL) AS missing_rent,
+            COUNT(*) FILTER (WHERE is_rental_estimated) AS estimated_rent,
+            COUNT(*) FILTER (WHERE latitude IS NULL OR longitude IS NULL) AS missing_coordinates,
+            MAX(last_updated) AS last_updated
+        FROM properties
+        GROUP BY 1
+        ORDER BY properties DESC, data_source
+        "#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(QualityReport { sources })
+}
@@ -0,0 +1,128 @@
+//! Single-property refresh - re-ingest one property from its archived raw file
+//!
+//! Looks up where the property was parsed from, re-parses just that row,
+//! reruns enrichment against current medians and overwrites the property
+//! through the audited write path.
+
+use crate::ingestion::archive::RawArchive;
+use crate::ingestion::enrich::{self, RentalMatching};
+use crate::ingestion::geocode::GeocoderChain;
+use crate::ingestion::parse;
+use crate::ingestion::types::RawData;
+use crate::ingestion::write::{self, FieldChange};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use thiserror::Error;
+
+/// Audit log action recorded for refreshes
+pub const REFRESH_ACTION: &str = "refresh_from_source";
+
+#[derive(Debug, Error)]
+pub enum RefreshError {
+    #[error("property not found")]
+    NotFound,
+    #[error("property has no recorded source file and row")]
+    NoProvenance,
+    #[error("source {0} can't be re-parsed one row at a time")]
+    UnsupportedSource(String),
+    #[error("archived file {0} is no longer available")]
+    ArchiveMissing(String),
+    #[error("row {row} not found in archived file {file}")]
+    RowMissing { file: String, row: i32 },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for RefreshError {
+    fn from(e: sqlx::Error) -> Self {
+        RefreshError::Other(e.into())
+    }
+}
+
+/// Result of a refresh, including what changed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshOutcome {
+    pub property_id: i32,
+    pub source_id: String,
+    pub source_file: String,
+    pub source_row: i32,
+    pub changes: Vec<FieldChange>,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Re-ingest property `id` from the archived row it was parsed from
+pub async fn refresh_property(
+    db: &PgPool,
+    archive: &RawArchive,
+    id: i32,
+    actor: &str,
+) -> Result<RefreshOutcome, RefreshError> {
+    let (source_id, source_file, source_row, latitude, longitude) = sqlx::query_as::<
+        _,
+        (
+            Option<String>,
+            Option<String>,
+            Option<i32>,
+            Option<Decimal>,
+            Option<Decimal>,
+        ),
+    >(
+        r#"
+        SELECT data_source, source_file, source_row, latitude, longitude
+        FROM properties
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?
+    .ok_or(RefreshError::NotFound)?;
+
+    let (Some(source_id), Some(source_file), Some(source_row)) =
+        (source_id, source_file, source_row)
+    else {
+        return Err(RefreshError::NoProvenance);
+    };
+
+    let path = archive
+        .resolve(&source_file)
+        .ok_or_else(|| RefreshError::ArchiveMissing(source_file.clone()))?;
+    let row = usize::try_from(source_row).map_err(|_| RefreshError::NoProvenance)?;
+
+    let parsed = match source_id.as_str() {
+        "nsw_sales" => {
+            parse::parse_nsw_sales_row(RawData::File(path), source_id.clone(), row).await?
+        }
+        _ => return Err(RefreshError::UnsupportedSource(source_id)),
+    };
+    let mut record = parsed.ok_or_else(|| RefreshError::RowMissing {
+        file: source_file.clone(),
+        row: source_row,
+    })?;
+    record.source_metadata.source_file = Some(source_file.clone());
+
+    // Coordinates don't come from the source file, so keep the geocoded ones
+    record.latitude = latitude;
+    record.longitude = longitude;
+    let matching = RentalMatching::from_env();
+    let record = enrich::enrich_all(vec![record], db, &GeocoderChain::new(), matching)
+        .await?
+        .remove(0);
+
+    let written = write::write_property_audited(db, id, &record, REFRESH_ACTION, actor)
+        .await?
+        .ok_or(RefreshError::NotFound)?;
+
+    Ok(RefreshOutcome {
+        property_id: id,
+        source_id,
+        source_file,
+        source_row,
+        changes: written.changes,
+        before: written.before,
+        after: written.after,
+    })
+}
@@ -0,0 +1,1045 @@
+//! Address renormalization - moves stored addresses to the current format
+//!
+//! Addresses with a unit were stored as "2 10 Smith Street" before the format
+//! became "2/10 Smith Street", and others as their sources wrote them ("Unit 2,
+//! 10 Smith St") before writes normalized them, so old rows stopped matching
+//! new records. This
+//! walks properties in id order and rewrites legacy addresses. Where the new
+//! address lands on another property's normalized key, the two are merged if
+//! nothing about them disagrees, and flagged in ingestion_errors if something
+//! does. Renames and merges are audited, and the last id finished is kept as a
+//! watermark so an interrupted run resumes. A dry run plans the same changes
+//! and reports them without writing anything.
+//!
+//! Postcodes stored before parsing normalized them ("2000.0", "800", "UNKNOWN")
+//! are moved to the form parsers now give in one pass of their own.
+
+use crate::ingestion::types::{PropertyRow, State};
+use crate::ingestion::utils::{is_house_number, normalize_address, normalize_postcode};
+use crate::ingestion::{watermark, write};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt;
+use tracing::info;
+
+/// Watermark task name; suburb-scoped runs append the suburb
+pub const TASK: &str = "renormalize_addresses";
+
+/// Actor recorded in property_audit_log
+pub const AUDIT_ACTOR: &str = "renormalize-addresses";
+
+pub const RENAME_ACTION: &str = "address_renormalize";
+pub const MERGE_ACTION: &str = "address_merge";
+
+/// ingestion_errors kind for collisions left for review
+pub const COLLISION_KIND: &str = "address_collision";
+
+pub const DEFAULT_BATCH_SIZE: i64 = 500;
+
+/// Collisions listed individually in the report; the rest are only counted
+pub const MAX_REPORTED_COLLISIONS: usize = 100;
+
+/// The current form of a stored address
+/// Legacy "unit house street" becomes "unit/house street" when the first token
+/// looks like a unit ("2", "G01", "12A") and the second like a house number
+/// ("10", "5B", "2-6"). "Lot 12 Boundary Road" and "10 Smith Street" are left
+/// alone, apart from what `normalize_address` does to every address.
+pub fn renormalize_address(address: &str) -> String {
+    let address = normalize_address(address);
+    let tokens: Vec<&str> = address.split_whitespace().collect();
+    match tokens.as_slice() {
+        [unit, house, street @ ..]
+            if !street.is_empty() && is_unit(unit) && is_house_number(house) =>
+        {
+            format!("{}/{} {}", unit, house, street.join(" "))
+        }
+        _ => tokens.join(" "),
+    }
+}
+
+fn is_unit(token: &str) -> bool {
+    token.len() <= 6
+        && token.chars().all(|c| c.is_ascii_alphanumeric())
+        && token.chars().any(|c| c.is_ascii_digit())
+}
+
+/// The unique_property columns, compared case- and whitespace-insensitively
+/// Two rows with one key are the same property as far as matching goes
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AddressKey {
+    pub address: String,
+    pub suburb: String,
+    pub state: State,
+    pub postcode: Option<String>,
+}
+
+impl AddressKey {
+    pub fn new(address: &str, suburb: &str, state: State, postcode: Option<&str>) -> Self {
+        let fold = |s: &str| {
+            s.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        };
+        AddressKey {
+            address: fold(address),
+            suburb: fold(suburb),
+            state,
+            postcode: postcode.map(|p| p.trim().to_string()),
+        }
+    }
+
+    /// The key `row` would have at `address`
+    pub fn at(row: &PropertyRow, address: &str) -> Self {
+        AddressKey::new(address, &row.suburb, row.state, row.postcode.as_deref())
+    }
+}
+
+/// Why two properties on one key can't be merged automatically, if they can't
+/// Differing values where both rows have one suggest different dwellings, e.g.
+/// a unit and the house it was subdivided from
+pub fn merge_conflict(survivor: &PropertyRow, duplicate: &PropertyRow) -> Option<String> {
+    fn differ<T: PartialEq + fmt::Debug>(
+        field: &str,
+        a: &Option<T>,
+        b: &Option<T>,
+    ) -> Option<String> {
+        match (a, b) {
+            (Some(a), Some(b)) if a != b => Some(format!("{} differs: {:?} and {:?}", field, a, b)),
+            _ => None,
+        }
+    }
+
+    differ(
+        "property_type",
+        &survivor.property_type,
+        &duplicate.property_type,
+    )
+    .or_else(|| differ("bedrooms", &survivor.bedrooms, &duplicate.bedrooms))
+    .or_else(|| differ("external_id", &survivor.external_id, &duplicate.external_id))
+}
+
+/// What happens to one row
+#[derive(Debug, Clone, PartialEq)]
+pub enum Plan {
+    /// Already in the current format
+    Unchanged,
+    Rename(String),
+    /// The new address is another property's; fold this row into it
+    Merge {
+        address: String,
+        into: i32,
+    },
+    /// The new address is another property's, but the two disagree
+    Flag {
+        address: String,
+        with: i32,
+        reason: String,
+    },
+}
+
+/// Plan for `row`, given its renormalized address and the other property
+/// already holding that address's key, if any
+pub fn plan(row: &PropertyRow, address: String, holder: Option<&PropertyRow>) -> Plan {
+    if address == row.address {
+        return Plan::Unchanged;
+    }
+    match holder {
+        None => Plan::Rename(address),
+        Some(holder) => match merge_conflict(holder, row) {
+            None => Plan::Merge {
+                address,
+                into: holder.id,
+            },
+            Some(reason) => Plan::Flag {
+                address,
+                with: holder.id,
+                reason,
+            },
+        },
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenormalizeOptions {
+    /// Plan and report only
+    pub dry_run: bool,
+    pub batch_size: i64,
+    /// Only this suburb, with a watermark of its own; for trialling the rollout
+    pub suburb: Option<String>,
+    /// Ignore the watermark and start from the first id
+    pub restart: bool,
+}
+
+impl Default for RenormalizeOptions {
+    fn default() -> Self {
+        RenormalizeOptions {
+            dry_run: false,
+            batch_size: DEFAULT_BATCH_SIZE,
+            suburb: None,
+            restart: false,
+        }
+    }
+}
+
+impl RenormalizeOptions {
+    /// Watermark task name for this scope
+    pub fn task(&self) -> String {
+        match &self.suburb {
+            Some(suburb) => format!("{}:{}", TASK, suburb.trim().to_lowercase()),
+            None => TASK.to_string(),
+        }
+    }
+}
+
+/// A renormalized address that landed on another property
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Collision {
+    pub property_id: i32,
+    pub address: String,
+    pub new_address: String,
+    pub with_id: i32,
+    pub with_address: String,
+    /// "merge" or "flag"
+    pub action: &'static str,
+    /// Why a flagged pair wasn't merged
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RenormalizeReport {
+    pub dry_run: bool,
+    pub suburb: Option<String>,
+    /// Watermark the run started after; None when it started from the first id
+    pub resumed_after: Option<i32>,
+    /// Last id processed
+    pub last_id: Option<i32>,
+    pub scanned: u64,
+    pub unchanged: u64,
+    pub renamed: u64,
+    pub merged: u64,
+    pub flagged: u64,
+    /// The first MAX_REPORTED_COLLISIONS collisions
+    pub collisions: Vec<Collision>,
+}
+
+impl RenormalizeReport {
+    /// Rows whose new address collided with another property
+    pub fn collision_count(&self) -> u64 {
+        self.merged + self.flagged
+    }
+
+    fn record_collision(&mut self, collision: Collision) {
+        if self.collisions.len() < MAX_REPORTED_COLLISIONS {
+            self.collisions.push(collision);
+        }
+    }
+}
+
+impl fmt::Display for RenormalizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}scanned {}: {} renamed, {} collisions ({} merged, {} flagged), {} unchanged",
+            if self.dry_run { "[dry run] " } else { "" },
+            self.scanned,
+            self.renamed,
+            self.collision_count(),
+            self.merged,
+            self.flagged,
+            self.unchanged
+        )
+    }
+}
+
+/// Renormalize every property after the watermark, a batch at a time
+/// Each row's change is its own transaction, and the watermark advances after
+/// each batch, so rows of an interrupted batch are planned again on resume;
+/// renamed and merged rows come back unchanged, flagged rows are flagged again
+pub async fn renormalize_addresses(
+    db: &PgPool,
+    options: &RenormalizeOptions,
+) -> Result<RenormalizeReport> {
+    let task = options.task();
+    if options.restart && !options.dry_run {
+        watermark::reset_id_watermark(db, &task).await?;
+    }
+    let resumed_after = match options.restart {
+        true => None,
+        false => watermark::get_id_watermark(db, &task).await?,
+    };
+
+    let mut report = RenormalizeReport {
+        dry_run: options.dry_run,
+        suburb: options.suburb.clone(),
+        resumed_after,
+        ..Default::default()
+    };
+    // A dry run writes nothing, so keys its planned renames would take are
+    // tracked here for later rows to collide with
+    let mut planned: HashMap<AddressKey, PropertyRow> = HashMap::new();
+
+    let mut after = resumed_after.unwrap_or(0);
+    loop {
+        let batch = fetch_batch(db, after, options).await?;
+        let Some(last_id) = batch.last().map(|row| row.id) else {
+            break;
+        };
+
+        for row in batch {
+            report.scanned += 1;
+            let address = renormalize_address(&row.address);
+            if address == row.address {
+                report.unchanged += 1;
+                continue;
+            }
+
+            let key = AddressKey::at(&row, &address);
+            let holder = match planned.get(&key) {
+                Some(holder) => Some(holder.clone()),
+                None => find_holder(db, &row, &address).await?,
+            };
+
+            match plan(&row, address, holder.as_ref()) {
+                Plan::Unchanged => report.unchanged += 1,
+                Plan::Rename(address) => {
+                    if options.dry_run {
+                        let mut renamed = row.clone();
+                        renamed.address = address;
+                        planned.insert(key, renamed);
+                    } else {
+                        rename(db, row.id, &address).await?;
+                    }
+                    report.renamed += 1;
+                }
+                Plan::Merge { address, into } => {
+                    if !options.dry_run {
+                        merge_into(db, into, row.id).await?;
+                    }
+                    report.merged += 1;
+                    report.record_collision(Collision {
+                        property_id: row.id,
+                        address: row.address,
+                        new_address: address,
+                        with_id: into,
+                        with_address: holder.map(|h| h.address).unwrap_or_default(),
+                        action: "merge",
+                        reason: None,
+                    });
+                }
+                Plan::Flag {
+                    address,
+                    with,
+                    reason,
+                } => {
+                    let collision = Collision {
+                        property_id: row.id,
+                        address: row.address,
+                        new_address: address,
+                        with_id: with,
+                        with_address: holder.map(|h| h.address).unwrap_or_default(),
+                        action: "flag",
+                        reason: Some(reason),
+                    };
+                    if !options.dry_run {
+                        record_collision(db, &collision).await?;
+                    }
+                    report.flagged += 1;
+                    report.record_collision(collision);
+                }
+            }
+        }
+
+        after = last_id;
+        report.last_id = Some(last_id);
+        if !options.dry_run {
+            watermark::advance_id_watermark(db, &task, last_id).await?;
+        }
+        info!("Renormalized through property {}: {}", last_id, report);
+    }
+
+    Ok(report)
+}
+
+async fn fetch_batch(
+    db: &PgPool,
+    after: i32,
+    options: &RenormalizeOptions,
+) -> Result<Vec<PropertyRow>> {
+    let rows = sqlx::query_as::<_, PropertyRow>(
+        r#"
+        SELECT * FROM properties
+        WHERE id > $1
+          AND ($2::text IS NULL OR LOWER(suburb) = LOWER($2))
+        ORDER BY id
+        LIMIT $3
+        "#,
+    )
+    .bind(after)
+    .bind(options.suburb.as_deref())
+    .bind(options.batch_size)
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Another property with `row`'s key at `address`; the lowest id if several
+async fn find_holder(db: &PgPool, row: &PropertyRow, address: &str) -> Result<Option<PropertyRow>> {
+    let holder = sqlx::query_as::<_, PropertyRow>(
+        r#"
+        SELECT * FROM properties
+        WHERE LOWER(address) = LOWER($1)
+          AND LOWER(suburb) = LOWER($2)
+          AND state = $3
+          AND postcode IS NOT DISTINCT FROM $4
+          AND id <> $5
+        ORDER BY id
+        LIMIT 1
+        "#,
+    )
+    .bind(address)
+    .bind(&row.suburb)
+    .bind(row.state)
+    .bind(&row.postcode)
+    .bind(row.id)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(holder)
+}
+
+/// Change a property's address, auditing the before and after rows
+async fn rename(db: &PgPool, id: i32, address: &str) -> Result<()> {
+    let mut tx = db.begin().await?;
+
+    let before = write::property_snapshot(&mut *tx, id)
+        .await?
+        .with_context(|| format!("Property {} disappeared before rename", id))?;
+    sqlx::query("UPDATE properties SET address = $2, last_updated = NOW() WHERE id = $1")
+        .bind(id)
+        .bind(address)
+        .execute(&mut *tx)
+        .await?;
+    let after = write::property_snapshot(&mut *tx, id)
+        .await?
+        .with_context(|| format!("Property {} disappeared during rename", id))?;
+    write::insert_audit_entry(&mut *tx, id, RENAME_ACTION, AUDIT_ACTOR, &before, &after).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Statements moving everything that refers to the duplicate ($2) onto the
+/// survivor ($1), run in order; sales and price points the survivor already
+/// has are dropped rather than doubled
+const MERGE_STATEMENTS: &[&str] = &[
+    r#"
+    UPDATE properties s SET
+        bedrooms = COALESCE(s.bedrooms, d.bedrooms),
+        bathrooms = COALESCE(s.bathrooms, d.bathrooms),
+        land_area_sqm = COALESCE(s.land_area_sqm, d.land_area_sqm),
+        property_type = COALESCE(s.property_type, d.property_type),
+        external_id = COALESCE(s.external_id, d.external_id),
+        latitude = CASE WHEN s.latitude IS NULL OR s.longitude IS NULL
+                        THEN d.latitude ELSE s.latitude END,
+        longitude = CASE WHEN s.latitude IS NULL OR s.longitude IS NULL
+                         THEN d.longitude ELSE s.longitude END,
+        last_updated = NOW()
+    FROM properties d
+    WHERE s.id = $1 AND d.id = $2
+    "#,
+    r#"
+    DELETE FROM sales_history d
+    WHERE d.property_id = $2
+      AND EXISTS (
+          SELECT 1 FROM sales_history s
+          WHERE s.property_id = $1 AND s.sale_date = d.sale_date AND s.sale_price = d.sale_price
+      )
+    "#,
+    "UPDATE sales_history SET property_id = $1 WHERE property_id = $2",
+    r#"
+    DELETE FROM price_history d
+    WHERE d.property_id = $2
+      AND EXISTS (
+          SELECT 1 FROM price_history s
+          WHERE s.property_id = $1 AND s.recorded_date = d.recorded_date
+      )
+    "#,
+    "UPDATE price_history SET property_id = $1 WHERE property_id = $2",
+    "UPDATE ingestion_errors SET property_id = $1 WHERE property_id = $2",
+    "UPDATE property_audit_log SET property_id = $1 WHERE property_id = $2",
+    r#"
+    UPDATE shared_comparisons SET property_ids = array_replace(property_ids, $2, $1)
+    WHERE $2 = ANY(property_ids)
+    "#,
+    "DELETE FROM properties WHERE id = $2 AND $1 <> $2",
+];
+
+/// Fold `duplicate` into `survivor` and delete it
+/// The survivor keeps its own values and takes only descriptive columns it
+/// lacks; the duplicate's sales, history and audit trail move across. The
+/// audit entry's before is the row merged away, its after the survivor.
+async fn merge_into(db: &PgPool, survivor: i32, duplicate: i32) -> Result<()> {
+    let mut tx = db.begin().await?;
+
+    let merged = write::property_snapshot(&mut *tx, duplicate)
+        .await?
+        .with_context(|| format!("Property {} disappeared before merge", duplicate))?;
+    for statement in MERGE_STATEMENTS {
+        sqlx::query(statement)
+            .bind(survivor)
+            .bind(duplicate)
+            .execute(&mut *tx)
+            .await?;
+    }
+    let after = write::property_snapshot(&mut *tx, survivor)
+        .await?
+        .with_context(|| format!("Property {} disappeared during merge", survivor))?;
+    write::insert_audit_entry(
+        &mut *tx,
+        survivor,
+        MERGE_ACTION,
+        AUDIT_ACTOR,
+        &merged,
+        &after,
+    )
+    .await?;
+
+    tx.commit().await?;
+    info!("Merged property {} into {}", duplicate, survivor);
+    Ok(())
+}
+
+/// Set a collision aside for review; the row keeps its old address
+async fn record_collision(db: &PgPool, collision: &Collision) -> Result<()> {
+    let details: Value = json!({
+        "address": collision.address,
+        "new_address": collision.new_address,
+        "with_id": collision.with_id,
+        "with_address": collision.with_address,
+        "reason": collision.reason,
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO ingestion_errors (source_id, kind, property_id, details)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(TASK)
+    .bind(COLLISION_KIND)
+    .bind(collision.property_id)
+    .bind(details)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// What renormalizing stored postcodes did, or would do
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PostcodeReport {
+    pub dry_run: bool,
+    /// Rows given the normalized form of their postcode
+    pub rewritten: u64,
+    /// Rows whose postcode isn't one of their state's, set to NULL
+    pub cleared: u64,
+    /// Rows left alone because another property already has their address
+    /// under the normalized postcode
+    pub collisions: u64,
+}
+
+impl fmt::Display for PostcodeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} rewritten, {} cleared, {} left for collisions",
+            if self.dry_run { "[dry run] " } else { "" },
+            self.rewritten,
+            self.cleared,
+            self.collisions
+        )
+    }
+}
+
+/// Rewrite every stored postcode `normalize_postcode` would give differently
+/// Works a distinct (state, postcode) value at a time. Junk is cleared, which
+/// can't collide as NULL postcodes never clash; a rewrite skips rows whose
+/// address already has a property under the new postcode, so rerunning is safe.
+/// A dry run counts collisions against the table as it is, so two old forms
+/// of one address are both counted as rewritten.
+pub async fn renormalize_postcodes(db: &PgPool, dry_run: bool) -> Result<PostcodeReport> {
+    let values = sqlx::query_as::<_, (State, String, i64)>(
+        r#"
+        SELECT state, postcode, COUNT(*) FROM properties
+        WHERE postcode IS NOT NULL
+        GROUP BY state, postcode
+        "#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut report = PostcodeReport {
+        dry_run,
+        ..Default::default()
+    };
+    for (state, postcode, rows) in values {
+        let rows = rows as u64;
+        let normalized = normalize_postcode(&postcode, state);
+        if normalized.as_deref() == Some(postcode.as_str()) {
+            continue;
+        }
+        let Some(normalized) = normalized else {
+            if !dry_run {
+                sqlx::query(
+                    "UPDATE properties SET postcode = NULL, last_updated = NOW() \
+                     WHERE state = $1 AND postcode = $2",
+                )
+                .bind(state)
+                .bind(&postcode)
+                .execute(db)
+                .await?;
+            }
+            report.cleared += rows;
+            continue;
+        };
+
+        let rewritten = if dry_run {
+            sqlx::query_scalar::<_, i64>(&format!(
+                "SELECT COUNT(*) FROM properties p WHERE {}",
+                POSTCODE_REWRITABLE
+            ))
+            .bind(state)
+            .bind(&postcode)
+            .bind(&normalized)
+            .fetch_one(db)
+            .await? as u64
+        } else {
+            sqlx::query(&format!(
+                "UPDATE properties p SET postcode = $3, last_updated = NOW() WHERE {}",
+                POSTCODE_REWRITABLE
+            ))
+            .bind(state)
+            .bind(&postcode)
+            .bind(&normalized)
+            .execute(db)
+            .await?
+            .rows_affected()
+        };
+        report.rewritten += rewritten;
+        report.collisions += rows - rewritten;
+    }
+
+    info!("Postcode renormalization: {}", report);
+    Ok(report)
+}
+
+/// Rows of state $1 at postcode $2 that can move to postcode $3 without
+/// landing on another property's key
+const POSTCODE_REWRITABLE: &str = r#"
+    p.state = $1 AND p.postcode = $2
+      AND NOT EXISTS (
+          SELECT 1 FROM properties o
+          WHERE o.address = p.address AND o.suburb = p.suburb
+            AND o.state = p.state AND o.postcode = $3
+      )
+    "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::types::PropertyType;
+    use crate::ingestion::utils::format_nsw_address;
+    use crate::test_support::{delete_suburb, PropertyFixture, SaleFixture};
+    use rust_decimal::Decimal;
+
+    fn row(id: i32, address: &str) -> PropertyRow {
+        PropertyRow {
+            id,
+            address: address.to_string(),
+            suburb: "Sydney".to_string(),
+            state: State::NSW,
+            postcode: Some("2000".to_string()),
+            bedrooms: None,
+            price: None,
+            weekly_rent: None,
+            property_type: Some(PropertyType::Unit),
+            data_source: None,
+            data_quality: None,
+            confidence_score: None,
+            external_id: None,
+        }
+    }
+
+    #[test]
+    fn test_renormalize_legacy_addresses() {
+        let cases = [
+            ("2 10 Smith Street", "2/10 Smith Street"),
+            ("12A 5 Terrace Lane", "12A/5 Terrace Lane"),
+            ("14 2-6 Hassall Street", "14/2-6 Hassall Street"),
+            ("G01 7B Market St", "G01/7B Market Street"),
+            ("  3   21 Marsden  Street ", "3/21 Marsden Street"),
+            // Already current, or not a unit and house number
+            ("2/10 Smith Street", "2/10 Smith Street"),
+            ("10 Smith Street", "10 Smith Street"),
+            ("Lot 12 Boundary Road", "Lot 12 Boundary Road"),
+            ("10 12th Avenue", "10 12th Avenue"),
+            ("Old Northern Road", "Old Northern Road"),
+            ("3 21", "3 21"),
+        ];
+
+        for (old, new) in cases {
+            assert_eq!(renormalize_address(old), new, "{}", old);
+            // Idempotent, so a resumed run leaves renamed rows alone
+            assert_eq!(renormalize_address(new), new, "{}", new);
+        }
+    }
+
+    #[test]
+    fn test_new_format_is_already_normal() {
+        let formatted = [
+            format_nsw_address(Some("2"), Some("10"), "Smith Street"),
+            format_nsw_address(Some("12A"), Some("5"), "Terrace  Lane"),
+            format_nsw_address(None, Some("10"), "Smith Street"),
+            format_nsw_address(None, None, "Old Northern Road"),
+        ];
+        for address in formatted {
+            assert_eq!(renormalize_address(&address), address);
+        }
+    }
+
+    #[test]
+    fn test_key_ignores_case_and_spacing() {
+        let a = AddressKey::new("2/10 Smith Street", "Sydney", State::NSW, Some("2000"));
+        let b = AddressKey::new("2/10  SMITH street", " sydney ", State::NSW, Some("2000"));
+        assert_eq!(a, b);
+        assert_ne!(
+            a,
+            AddressKey::new("2/10 Smith Street", "Sydney", State::NSW, None)
+        );
+    }
+
+    #[test]
+    fn test_plan_merges_or_flags_collisions() {
+        let old = row(7, "2 10 Smith Street");
+        assert_eq!(
+            plan(&old, "2/10 Smith Street".to_string(), None),
+            Plan::Rename("2/10 Smith Street".to_string())
+        );
+
+        let holder = row(3, "2/10 Smith Street");
+        assert_eq!(
+            plan(&old, "2/10 Smith Street".to_string(), Some(&holder)),
+            Plan::Merge {
+                address: "2/10 Smith Street".to_string(),
+                into: 3
+            }
+        );
+
+        // One side missing a value isn't a disagreement
+        let described = PropertyRow {
+            bedrooms: Some(2),
+            ..old.clone()
+        };
+        assert!(merge_conflict(&holder, &described).is_none());
+
+        let house = PropertyRow {
+            property_type: Some(PropertyType::House),
+            ..holder.clone()
+        };
+        match plan(&old, "2/10 Smith Street".to_string(), Some(&house)) {
+            Plan::Flag { with, reason, .. } => {
+                assert_eq!(with, 3);
+                assert!(reason.starts_with("property_type differs"), "{}", reason);
+            }
+            other => panic!("expected a flag, got {:?}", other),
+        }
+
+        let current = row(9, "2/10 Smith Street");
+        assert_eq!(
+            plan(&current, "2/10 Smith Street".to_string(), None),
+            Plan::Unchanged
+        );
+    }
+
+    const SUBURB: &str = "Renormalize Testville";
+
+    async fn address_of(db: &PgPool, id: i32) -> Option<String> {
+        sqlx::query_scalar::<_, String>("SELECT address FROM properties WHERE id = $1")
+            .bind(id)
+            .fetch_optional(db)
+            .await
+            .unwrap()
+    }
+
+    async fn count(db: &PgPool, sql: &str, id: i32) -> i64 {
+        sqlx::query_scalar::<_, i64>(sql)
+            .bind(id)
+            .fetch_one(db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_renormalize_rollout() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+
+        let fixture = |address: &str| {
+            PropertyFixture::new()
+                .address(address)
+                .suburb(SUBURB)
+                .postcode("2994")
+        };
+        // Old format, nothing in the way
+        let plain = fixture("3 12 Renorm Street").insert(&db).await.unwrap();
+        // A collision pair that agrees: written since the format change, and its old copy
+        let current = fixture("4/20 Renorm Street").insert(&db).await.unwrap();
+        let old = fixture("4 20 Renorm Street")
+            .coordinates(Decimal::new(-338, 1), Decimal::new(1512, 1))
+            .insert(&db)
+            .await
+            .unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2020, 5, 1).unwrap();
+        SaleFixture::new(current, 700_000, date)
+            .insert(&db)
+            .await
+            .unwrap();
+        SaleFixture::new(old, 700_000, date)
+            .insert(&db)
+            .await
+            .unwrap();
+        SaleFixture::new(old, 550_000, date - chrono::Duration::days(2000))
+            .insert(&db)
+            .await
+            .unwrap();
+        // A collision pair that disagrees on bedrooms
+        let flagged_with = fixture("5/30 Renorm Street")
+            .bedrooms(2)
+            .insert(&db)
+            .await
+            .unwrap();
+        let flagged = fixture("5 30 Renorm Street")
+            .bedrooms(4)
+            .insert(&db)
+            .await
+            .unwrap();
+        // Backdated, so the real run's writes show in last_updated
+        sqlx::query("UPDATE properties SET last_updated = '2000-01-01' WHERE suburb = $1")
+            .bind(SUBURB)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let options = RenormalizeOptions {
+            dry_run: true,
+            batch_size: 2,
+            suburb: Some(SUBURB.to_string()),
+            restart: true,
+        };
+
+        // Dry run: the full plan, nothing written
+        let report = renormalize_addresses(&db, &options).await.unwrap();
+        assert_eq!(report.scanned, 5);
+        assert_eq!(report.renamed, 1);
+        assert_eq!((report.merged, report.flagged), (1, 1));
+        assert_eq!(report.collision_count(), 2);
+        let collisions: Vec<(i32, i32, &str)> = report
+            .collisions
+            .iter()
+            .map(|c| (c.property_id, c.with_id, c.action))
+            .collect();
+        assert_eq!(
+            collisions,
+            [(old, current, "merge"), (flagged, flagged_with, "flag")]
+        );
+        assert_eq!(address_of(&db, plain).await.unwrap(), "3 12 Renorm Street");
+        assert!(address_of(&db, old).await.is_some());
+        assert_eq!(
+            watermark::get_id_watermark(&db, &options.task())
+                .await
+                .unwrap(),
+            None
+        );
+
+        // For real
+        let options = RenormalizeOptions {
+            dry_run: false,
+            ..options
+        };
+        let report = renormalize_addresses(&db, &options).await.unwrap();
+        assert_eq!((report.renamed, report.merged, report.flagged), (1, 1, 1));
+
+        assert_eq!(address_of(&db, plain).await.unwrap(), "3/12 Renorm Street");
+        assert_eq!(address_of(&db, old).await, None);
+        assert_eq!(
+            address_of(&db, flagged).await.unwrap(),
+            "5 30 Renorm Street"
+        );
+
+        // The survivor gained the old copy's coordinates and its one extra sale
+        let has_coordinates = count(
+            &db,
+            "SELECT COUNT(*) FROM properties WHERE id = $1 AND latitude IS NOT NULL",
+            current,
+        )
+        .await;
+        assert_eq!(has_coordinates, 1);
+        let sales = count(
+            &db,
+            "SELECT COUNT(*) FROM sales_history WHERE property_id = $1",
+            current,
+        )
+        .await;
+        assert_eq!(sales, 2);
+
+        let audited = |action: &'static str| {
+            let db = db.clone();
+            async move {
+                sqlx::query_scalar::<_, i32>(
+                    "SELECT property_id FROM property_audit_log WHERE action = $1 AND property_id = ANY($2)",
+                )
+                .bind(action)
+                .bind(vec![plain, current, old, flagged, flagged_with])
+                .fetch_all(&db)
+                .await
+                .unwrap()
+            }
+        };
+        assert_eq!(audited(RENAME_ACTION).await, [plain]);
+        assert_eq!(audited(MERGE_ACTION).await, [current]);
+
+        // The renamed property and the survivor show up as changed
+        let touched: Vec<i32> = sqlx::query_scalar(
+            "SELECT id FROM properties WHERE suburb = $1 AND last_updated > '2000-01-01' ORDER BY id",
+        )
+        .bind(SUBURB)
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(touched, [plain, current]);
+
+        let flags = count(
+            &db,
+            "SELECT COUNT(*) FROM ingestion_errors WHERE kind = 'address_collision' AND property_id = $1",
+            flagged,
+        )
+        .await;
+        assert_eq!(flags, 1);
+
+        // Resumes after the watermark: nothing left to scan
+        assert_eq!(
+            watermark::get_id_watermark(&db, &options.task())
+                .await
+                .unwrap(),
+            Some(flagged)
+        );
+        let resumed = renormalize_addresses(
+            &db,
+            &RenormalizeOptions {
+                restart: false,
+                ..options.clone()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(resumed.resumed_after, Some(flagged));
+        assert_eq!(resumed.scanned, 0);
+
+        delete_suburb(&db, SUBURB).await.unwrap();
+        watermark::reset_id_watermark(&db, &options.task())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_renormalize_postcodes() {
+        const SUBURB: &str = "Postcode Testville";
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        delete_suburb(&db, SUBURB).await.unwrap();
+
+        let fixture = |address: &str, postcode: &str| {
+            PropertyFixture::new()
+                .address(address)
+                .suburb(SUBURB)
+                .postcode(postcode)
+        };
+        let float = fixture("1 Postcode Street", "2994.0")
+            .insert(&db)
+            .await
+            .unwrap();
+        let junk = fixture("2 Postcode Street", "UNKNOWN")
+            .insert(&db)
+            .await
+            .unwrap();
+        let unpadded = fixture("3 Postcode Street", "800")
+            .state(State::NT)
+            .insert(&db)
+            .await
+            .unwrap();
+        // Already stored under the normalized postcode
+        let current = fixture("4 Postcode Street", "2994")
+            .insert(&db)
+            .await
+            .unwrap();
+        let duplicate = fixture("4 Postcode Street", " 2994")
+            .insert(&db)
+            .await
+            .unwrap();
+        let postcode_of = |id: i32| {
+            let db = db.clone();
+            async move {
+                sqlx::query_scalar::<_, Option<String>>(
+                    "SELECT postcode FROM properties WHERE id = $1",
+                )
+                .bind(id)
+                .fetch_one(&db)
+                .await
+                .unwrap()
+            }
+        };
+
+        // Backdated, so the real run's writes show in last_updated
+        sqlx::query("UPDATE properties SET last_updated = '2000-01-01' WHERE suburb = $1")
+            .bind(SUBURB)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        // Counts are over the whole table, which other tests share
+        let report = renormalize_postcodes(&db, true).await.unwrap();
+        assert!(report.rewritten >= 2 && report.cleared >= 1 && report.collisions >= 1);
+        assert_eq!(postcode_of(float).await.as_deref(), Some("2994.0"));
+
+        renormalize_postcodes(&db, false).await.unwrap();
+        assert_eq!(postcode_of(float).await.as_deref(), Some("2994"));
+        assert_eq!(postcode_of(junk).await, None);
+        assert_eq!(postcode_of(unpadded).await.as_deref(), Some("0800"));
+        assert_eq!(postcode_of(current).await.as_deref(), Some("2994"));
+        assert_eq!(postcode_of(duplicate).await.as_deref(), Some(" 2994"));
+        let touched: Vec<i32> = sqlx::query_scalar(
+            "SELECT id FROM properties WHERE suburb = $1 AND last_updated > '2000-01-01' ORDER BY id",
+        )
+        .bind(SUBURB)
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(touched, [float, junk, unpadded]);
+
+        // Only the collision is left
+        let report = renormalize_postcodes(&db, false).await.unwrap();
+        assert_eq!((report.rewritten, report.cleared), (0, 0));
+
+        delete_suburb(&db, SUBURB).await.unwrap();
+    }
+}
@@ -0,0 +1,594 @@
+//! Ingestion run tracking - one ingestion_runs row per source per run, with
+//! progress written as the pipeline moves through its stages
+
+use crate::ingestion::anomaly::{self, Anomaly, AnomalyThresholds, RunMetrics};
+use crate::ingestion::archive::ArchivedFile;
+use crate::ingestion::throttle::ThrottleStatus;
+use crate::ingestion::types::{IngestionRun, WriteStats};
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Shortest gap between progress writes within a stage
+pub const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where a run has got to; stored in ingestion_runs.progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunProgress {
+    pub stage: String,
+    /// Zero-based position of `stage` among `stage_count` stages
+    pub stage_index: u32,
+    pub stage_count: u32,
+    /// What `done` and `total` count in this stage: bytes, rows or chunks
+    pub unit: String,
+    pub done: u64,
+    /// None while the stage's size isn't known yet
+    pub total: Option<u64>,
+    /// Whole-run estimate; each stage is weighted equally
+    pub percent: f64,
+    pub updated_at: DateTime<Utc>,
+    /// Write throttle, during the write stage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<ThrottleStatus>,
+}
+
+impl RunProgress {
+    fn compute_percent(&self) -> f64 {
+        if self.stage_count == 0 {
+            return 0.0;
+        }
+        let within = match self.total {
+            Some(total) if total > 0 => (self.done as f64 / total as f64).min(1.0),
+            _ => 0.0,
+        };
+        let percent = (self.stage_index as f64 + within) / self.stage_count as f64 * 100.0;
+        (percent * 10.0).round() / 10.0
+    }
+}
+
+/// Records progress for one run; write failures are logged and never
+/// propagated, so progress reporting can't fail the run
+pub struct ProgressWriter {
+    db: PgPool,
+    run_id: i32,
+    stage_count: u32,
+    min_interval: Duration,
+    last_write: Option<Instant>,
+    progress: Option<RunProgress>,
+}
+
+impl ProgressWriter {
+    pub fn new(db: PgPool, run_id: i32, stage_count: u32) -> Self {
+        ProgressWriter {
+            db,
+            run_id,
+            stage_count,
+            min_interval: DEFAULT_PROGRESS_INTERVAL,
+            last_write: None,
+            progress: None,
+        }
+    }
+
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    pub fn run_id(&self) -> i32 {
+        self.run_id
+    }
+
+    pub fn progress(&self) -> Option<&RunProgress> {
+        self.progress.as_ref()
+    }
+
+    /// Move to the next stage; always written
+    pub async fn start_stage(&mut self, stage: &str, unit: &str, total: Option<u64>) {
+        let stage_index = self.progress.as_ref().map_or(0, |p| {
+            (p.stage_index + 1).min(self.stage_count.saturating_sub(1))
+        });
+
+        self.progress = Some(RunProgress {
+            stage: stage.to_string(),
+            stage_index,
+            stage_count: self.stage_count,
+            unit: unit.to_string(),
+            done: 0,
+            total,
+            percent: 0.0,
+            updated_at: Utc::now(),
+            throttle: None,
+        });
+        self.persist().await;
+    }
+
+    /// Set the size of the current stage once it becomes known
+    pub async fn set_total(&mut self, total: u64) {
+        if let Some(progress) = self.progress.as_mut() {
+            progress.total = Some(total);
+        }
+        self.persist_throttled().await;
+    }
+
+    /// Record how far through the current stage the run is
+    /// Written at most once per `min_interval`; going backwards is ignored
+    pub async fn advance(&mut self, done: u64) {
+        if let Some(progress) = self.progress.as_mut() {
+            progress.done = progress.done.max(done);
+        }
+        self.persist_throttled().await;
+    }
+
+    /// Report the write throttle; written straight away when its level changes
+    pub async fn set_throttle(&mut self, status: ThrottleStatus) {
+        let Some(progress) = self.progress.as_mut() else {
+            return;
+        };
+        let level_changed = progress.throttle.as_ref().map(|t| t.level) != Some(status.level);
+        progress.throttle = Some(status);
+        if level_changed {
+            self.persist().await;
+        } else {
+            self.persist_throttled().await;
+        }
+    }
+
+    /// Mark the final stage complete; always written
+    pub async fn finish(&mut self) {
+        if let Some(progress) = self.progress.as_mut() {
+            progress.stage_index = self.stage_count.saturating_sub(1);
+            if let Some(total) = progress.total {
+                progress.done = total;
+            }
+            progress.total = Some(progress.done);
+        }
+        self.persist().await;
+    }
+
+    async fn persist_throttled(&mut self) {
+        let due = self
+            .last_write
+            .is_none_or(|last| last.elapsed() >= self.min_interval);
+        if due {
+            self.persist().await;
+        }
+    }
+
+    async fn persist(&mut self) {
+        let Some(progress) = self.progress.as_mut() else {
+            return;
+        };
+        progress.percent = progress.compute_percent();
+        progress.updated_at = Utc::now();
+        self.last_write = Some(Instant::now());
+
+        let result = sqlx::query("UPDATE ingestion_runs SET progress = $1 WHERE id = $2")
+            .bind(Json(&*progress))
+            .bind(self.run_id)
+            .execute(&self.db)
+            .await;
+        if let Err(e) = result {
+            warn!("Failed to record progress for run {}: {}", self.run_id, e);
+        }
+    }
+}
+
+const RUN_COLUMNS: &str = "id, source_id, status, started_at, completed_at, records_fetched, \
+    records_inserted, records_updated, records_skipped, error_message, progress, metrics, anomalies, \
+    raw_files, parse_errors";
+
+/// Record the start of a run and return its id
+pub async fn start_run(db: &PgPool, source_id: &str) -> Result<i32> {
+    let id = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO ingestion_runs (source_id, status, started_at)
+        VALUES ($1, 'running', NOW())
+        RETURNING id
+        "#,
+    )
+    .bind(source_id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(id)
+}
+
+/// Record a successful run's counts and metrics
+/// The metrics are compared with recent successful runs of the same source;
+/// any anomalies are stored on the run, which is marked completed_with_warnings
+pub async fn complete_run(
+    db: &PgPool,
+    id: i32,
+    stats: &WriteStats,
+    metrics: &RunMetrics,
+    thresholds: &AnomalyThresholds,
+) -> Result<Vec<Anomaly>> {
+    let source_id =
+        sqlx::query_scalar::<_, String>("SELECT source_id FROM ingestion_runs WHERE id = $1")
+            .bind(id)
+            .fetch_one(db)
+            .await?;
+    let history = recent_successful_runs(db, &source_id, id, thresholds.window).await?;
+    let anomalies = anomaly::detect_anomalies(metrics, &history, thresholds);
+
+    let status = if anomalies.is_empty() {
+        "completed"
+    } else {
+        "completed_with_warnings"
+    };
+    let fetched = stats.inserted + stats.updated + stats.skipped + stats.errors;
+
+    sqlx::query(
+        r#"
+        UPDATE ingestion_runs SET
+            status = $2, completed_at = NOW(),
+            records_fetched = $3, records_inserted = $4,
+            records_updated = $5, records_skipped = $6,
+            metrics = $7, anomalies = $8
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(status)
+    .bind(fetched as i32)
+    .bind(stats.inserted as i32)
+    .bind(stats.updated as i32)
+    .bind(stats.skipped as i32)
+    .bind(Json(metrics))
+    .bind((!anomalies.is_empty()).then_some(Json(&anomalies)))
+    .execute(db)
+    .await?;
+
+    Ok(anomalies)
+}
+
+/// Most recent successful runs of `source_id` before run `before_id`, newest first
+pub async fn recent_successful_runs(
+    db: &PgPool,
+    source_id: &str,
+    before_id: i32,
+    limit: usize,
+) -> Result<Vec<IngestionRun>, sqlx::Error> {
+    sqlx::query_as::<_, IngestionRun>(&format!(
+        r#"
+        SELECT {}
+        FROM ingestion_runs
+        WHERE source_id = $1 AND id < $2
+          AND status IN ('completed', 'completed_with_warnings')
+        ORDER BY id DESC
+        LIMIT $3
+        "#,
+        RUN_COLUMNS
+    ))
+    .bind(source_id)
+    .bind(before_id)
+    .bind(limit as i64)
+    .fetch_all(db)
+    .await
+}
+
+/// When `source_id` last completed successfully; None if it never has
+pub async fn last_completed(
+    db: &PgPool,
+    source_id: &str,
+) -> Result<Option<NaiveDateTime>, sqlx::Error> {
+    sqlx::query_scalar::<_, Option<NaiveDateTime>>(
+        r#"
+        SELECT MAX(completed_at)
+        FROM ingestion_runs
+        WHERE source_id = $1
+          AND status IN ('completed', 'completed_with_warnings')
+        "#,
+    )
+    .bind(source_id)
+    .fetch_one(db)
+    .await
+}
+
+/// Add files the run kept in the raw archive to those already recorded
+pub async fn record_raw_files(db: &PgPool, id: i32, files: &[ArchivedFile]) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE ingestion_runs
+        SET raw_files = COALESCE(raw_files, '[]'::jsonb) || $2
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(Json(files))
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// How many rows the run failed to parse, recorded before any check on them
+pub async fn record_parse_errors(db: &PgPool, id: i32, errors: usize) -> Result<()> {
+    sqlx::query("UPDATE ingestion_runs SET parse_errors = $2 WHERE id = $1")
+        .bind(id)
+        .bind(i32::try_from(errors).unwrap_or(i32::MAX))
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn fail_run(
+    db: &PgPool,
+    id: i32,
+    error: &str,
+    metrics: Option<&RunMetrics>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE ingestion_runs SET
+            status = 'failed', completed_at = NOW(), error_message = $2,
+            metrics = COALESCE($3, metrics)
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(error)
+    .bind(metrics.map(Json))
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn fetch_run(db: &PgPool, id: i32) -> Result<Option<IngestionRun>, sqlx::Error> {
+    sqlx::query_as::<_, IngestionRun>(&format!(
+        "SELECT {} FROM ingestion_runs WHERE id = $1",
+        RUN_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Most recent runs first, optionally for one source
+pub async fn list_runs(
+    db: &PgPool,
+    source_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<IngestionRun>, sqlx::Error> {
+    sqlx::query_as::<_, IngestionRun>(&format!(
+        r#"
+        SELECT {}
+        FROM ingestion_runs
+        WHERE ($1::text IS NULL OR source_id = $1)
+        ORDER BY started_at DESC, id DESC
+        LIMIT $2
+        "#,
+        RUN_COLUMNS
+    ))
+    .bind(source_id)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Every run started in `[from, to)`, oldest first
+pub async fn runs_started_between(
+    db: &PgPool,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<IngestionRun>, sqlx::Error> {
+    sqlx::query_as::<_, IngestionRun>(&format!(
+        "SELECT {} FROM ingestion_runs WHERE started_at >= $1 AND started_at < $2 ORDER BY started_at, id",
+        RUN_COLUMNS
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::validate::ValidationReport;
+    use sqlx::postgres::PgPoolOptions;
+
+    fn unreachable_pool() -> PgPool {
+        PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(50))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_progress_percent_across_stages() {
+        let mut writer =
+            ProgressWriter::new(unreachable_pool(), 1, 4).with_min_interval(Duration::ZERO);
+
+        // Writes fail against the unreachable database, but the run carries on
+        writer.start_stage("fetch", "bytes", None).await;
+        assert_eq!(writer.progress().unwrap().percent, 0.0);
+
+        writer.start_stage("parse", "rows", None).await;
+        writer.advance(5_000).await;
+        assert_eq!(writer.progress().unwrap().percent, 25.0);
+
+        writer.start_stage("enrich", "rows", Some(5_000)).await;
+        writer.advance(2_500).await;
+        assert_eq!(writer.progress().unwrap().percent, 62.5);
+
+        writer.start_stage("write", "chunks", Some(4)).await;
+        writer.advance(1).await;
+        writer.advance(0).await; // stale update is ignored
+        let progress = writer.progress().unwrap();
+        assert_eq!((progress.stage_index, progress.done), (3, 1));
+        assert_eq!(progress.percent, 81.3);
+
+        writer.finish().await;
+        assert_eq!(writer.progress().unwrap().percent, 100.0);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_progress_persisted_monotonically() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let run_id = start_run(&db, "mock_source").await.unwrap();
+        let mut writer =
+            ProgressWriter::new(db.clone(), run_id, 3).with_min_interval(Duration::ZERO);
+
+        // A mock source: 3 stages of 4 steps each
+        let mut persisted = Vec::new();
+        for stage in ["fetch", "parse", "write"] {
+            writer.start_stage(stage, "chunks", Some(4)).await;
+            for done in 1..=4 {
+                writer.advance(done).await;
+                let run = fetch_run(&db, run_id).await.unwrap().unwrap();
+                let progress: RunProgress = serde_json::from_value(run.progress.unwrap()).unwrap();
+                persisted.push(progress.percent);
+            }
+        }
+        writer.finish().await;
+        complete_run(
+            &db,
+            run_id,
+            &WriteStats::default(),
+            &RunMetrics::default(),
+            &AnomalyThresholds::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(
+            persisted.windows(2).all(|w| w[0] <= w[1]),
+            "{:?}",
+            persisted
+        );
+        assert_eq!(persisted.last(), Some(&100.0));
+
+        let run = fetch_run(&db, run_id).await.unwrap().unwrap();
+        assert_eq!(run.status, "completed");
+
+        sqlx::query("DELETE FROM ingestion_runs WHERE id = $1")
+            .bind(run_id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_run_with_90_percent_drop_is_flagged() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let source_id = "anomaly_test";
+        sqlx::query("DELETE FROM ingestion_runs WHERE source_id = $1")
+            .bind(source_id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let metrics = |inserted| RunMetrics {
+            records_parsed: 10_000,
+            records_inserted: inserted,
+            median_price: Some(800_000.0),
+            rental_match_rate: Some(0.8),
+            rental_matches: None,
+            drift: None,
+            throttle: None,
+            legacy_yield_filter: None,
+            validation: None,
+        };
+        let thresholds = AnomalyThresholds::default();
+
+        for inserted in [1_000, 1_100, 900] {
+            let id = start_run(&db, source_id).await.unwrap();
+            let anomalies = complete_run(
+                &db,
+                id,
+                &WriteStats::default(),
+                &metrics(inserted),
+                &thresholds,
+            )
+            .await
+            .unwrap();
+            assert!(anomalies.is_empty());
+        }
+
+        let id = start_run(&db, source_id).await.unwrap();
+        let anomalies = complete_run(&db, id, &WriteStats::default(), &metrics(100), &thresholds)
+            .await
+            .unwrap();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].metric, "records_inserted");
+
+        let run = fetch_run(&db, id).await.unwrap().unwrap();
+        assert_eq!(run.status, "completed_with_warnings");
+        let stored: Vec<Anomaly> = serde_json::from_value(run.anomalies.unwrap()).unwrap();
+        assert_eq!(stored, anomalies);
+
+        sqlx::query("DELETE FROM ingestion_runs WHERE source_id = $1")
+            .bind(source_id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_failed_run_keeps_validation_report() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let source_id = "validation_test";
+        sqlx::query("DELETE FROM ingestion_runs WHERE source_id = $1")
+            .bind(source_id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let mut report = ValidationReport::default();
+        report.check_record_count(40_000, 100_000);
+        let metrics = RunMetrics {
+            validation: Some(report.clone()),
+            ..Default::default()
+        };
+        let id = start_run(&db, source_id).await.unwrap();
+        // Files archived before the failure stay on the run for replaying
+        let archived = |week: &str| ArchivedFile {
+            key: format!("{}/2025-01-13/{}.zip", source_id, week),
+            sha256: "0".repeat(64),
+            bytes: 1024,
+        };
+        let files = vec![archived("20250106"), archived("20250113")];
+        record_raw_files(&db, id, &files[..1]).await.unwrap();
+        record_raw_files(&db, id, &files[1..]).await.unwrap();
+        record_parse_errors(&db, id, 40).await.unwrap();
+        fail_run(&db, id, "Validation failed", Some(&metrics))
+            .await
+            .unwrap();
+
+        let run = fetch_run(&db, id).await.unwrap().unwrap();
+        assert_eq!(run.status, "failed");
+        assert_eq!(run.error_message.as_deref(), Some("Validation failed"));
+        let stored: RunMetrics = serde_json::from_value(run.metrics.unwrap()).unwrap();
+        assert_eq!(stored.validation, Some(report));
+        let raw_files: Vec<ArchivedFile> = serde_json::from_value(run.raw_files.unwrap()).unwrap();
+        assert_eq!(raw_files, files);
+        assert_eq!(run.parse_errors, Some(40));
+
+        // Other failures leave metrics as they were
+        let id = start_run(&db, source_id).await.unwrap();
+        fail_run(&db, id, "connection reset", None).await.unwrap();
+        assert_eq!(fetch_run(&db, id).await.unwrap().unwrap().metrics, None);
+
+        sqlx::query("DELETE FROM ingestion_runs WHERE source_id = $1")
+            .bind(source_id)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,507 @@
+//! Staged writes - a review step between untrusted sources and properties
+//!
+//! A staged source writes its enriched records to properties_staging as one
+//! batch per run instead of touching properties. An admin reviews pending
+//! batches, then approves one, which merges it through the normal
+//! conflict-resolution write path in a single transaction, or rejects it,
+//! which discards its rows. Either way the batch row stays as the record of
+//! the decision.
+
+use crate::ingestion::drift::DriftConfig;
+use crate::ingestion::types::{PropertyRecord, WriteStats};
+use crate::ingestion::write;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Sample rows shown with each pending batch
+pub const STAGING_SAMPLE_ROWS: i64 = 5;
+
+/// Records inserted per statement when staging
+const STAGE_CHUNK_SIZE: usize = 1000;
+
+#[derive(Debug, Error)]
+pub enum StagingError {
+    #[error("staging batch not found")]
+    NotFound,
+    #[error("staging batch was already {0}")]
+    NotPending(String),
+    #[error("{0} records failed to merge, so none were written")]
+    MergeFailed(usize),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for StagingError {
+    fn from(e: sqlx::Error) -> Self {
+        StagingError::Other(e.into())
+    }
+}
+
+/// Completeness counts for the records in one batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BatchQuality {
+    pub records: i64,
+    pub missing_price: i64,
+    pub missing_rent: i64,
+    /// Rents taken from a median rather than observed for the property
+    pub estimated_rent: i64,
+    pub missing_coordinates: i64,
+    pub missing_postcode: i64,
+    /// Mean of the records' confidence scores, 0 to 1
+    pub average_confidence: Option<f64>,
+}
+
+/// A batch awaiting review, with its quality summary and first few records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagingBatch {
+    pub id: i32,
+    pub source_id: String,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub quality: BatchQuality,
+    /// Records as staged, in batch order
+    pub samples: Vec<Value>,
+}
+
+/// Outcome of approving or rejecting a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReview {
+    pub batch_id: i32,
+    /// approved or rejected
+    pub status: String,
+    pub reviewed_by: String,
+    /// What the merge did, for approved batches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merged: Option<WriteStats>,
+    /// Rows discarded, for rejected batches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discarded: Option<u64>,
+}
+
+/// Store `records` as a new pending batch and return its id
+pub async fn stage_records(
+    db: &PgPool,
+    source_id: &str,
+    records: Vec<PropertyRecord>,
+) -> anyhow::Result<i32> {
+    let mut tx = db.begin().await?;
+
+    let batch_id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO staging_batches (source_id, record_count) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(source_id)
+    .bind(records.len() as i32)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for (chunk_index, chunk) in records.chunks(STAGE_CHUNK_SIZE).enumerate() {
+        let offset = (chunk_index * STAGE_CHUNK_SIZE) as i32;
+        sqlx::query(
+            r#"
+            INSERT INTO properties_staging (batch_id, row_number, record)
+            SELECT $1, $2 + ordinality::INTEGER - 1, value
+            FROM jsonb_array_elements($3) WITH ORDINALITY
+            "#,
+        )
+        .bind(batch_id)
+        .bind(offset)
+        .bind(serde_json::to_value(chunk)?)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    info!(
+        "Staged {} {} records as batch {}",
+        records.len(),
+        source_id,
+        batch_id
+    );
+
+    Ok(batch_id)
+}
+
+/// Pending batches, oldest first, each with `samples` sample rows
+pub async fn list_pending_batches(
+    db: &PgPool,
+    samples: i64,
+) -> Result<Vec<StagingBatch>, sqlx::Error> {
+    let batches = sqlx::query_as::<_, (i32, String, String, NaiveDateTime)>(
+        r#"
+        SELECT id, source_id, status, created_at
+        FROM staging_batches
+        WHERE status = 'pending'
+        ORDER BY created_at, id
+        "#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut pending = Vec::with_capacity(batches.len());
+    for (id, source_id, status, created_at) in batches {
+        let quality = batch_quality(db, id).await?;
+        let samples = sqlx::query_scalar::<_, Value>(
+            r#"
+            SELECT record FROM properties_staging
+            WHERE batch_id = $1
+            ORDER BY row_number
+            LIMIT $2
+            "#,
+        )
+        .bind(id)
+        .bind(samples)
+        .fetch_all(db)
+        .await?;
+
+        pending.push(StagingBatch {
+            id,
+            source_id,
+            status,
+            created_at,
+            quality,
+            samples,
+        });
+    }
+
+    Ok(pending)
+}
+
+async fn batch_quality(db: &PgPool, batch_id: i32) -> Result<BatchQuality, sqlx::Error> {
+    sqlx::query_as::<_, BatchQuality>(
+        r#"
+        SELECT
+            COUNT(*) AS records,
+            COUNT(*) FILTER (WHERE record->>'sale_price' IS NULL) AS missing_price,
+            COUNT(*) FILTER (WHERE record->>'weekly_rent' IS NULL) AS missing_rent,
+            COUNT(*) FILTER (
+                WHERE (record->'source_metadata'->>'is_rental_estimated')::BOOLEAN
+            ) AS estimated_rent,
+            COUNT(*) FILTER (
+                WHERE record->>'latitude' IS NULL OR record->>'longitude' IS NULL
+            ) AS missing_coordinates,
+            COUNT(*) FILTER (WHERE record->>'postcode' IS NULL) AS missing_postcode,
+            AVG((record->'source_metadata'->>'confidence_score')::FLOAT8) AS average_confidence
+        FROM properties_staging
+        WHERE batch_id = $1
+        "#,
+    )
+    .bind(batch_id)
+    .fetch_one(db)
+    .await
+}
+
+/// Lock a batch for review, failing unless it's still pending
+async fn lock_pending_batch(
+    conn: &mut sqlx::PgConnection,
+    batch_id: i32,
+) -> Result<(), StagingError> {
+    let status = sqlx::query_scalar::<_, String>(
+        "SELECT status FROM staging_batches WHERE id = $1 FOR UPDATE",
+    )
+    .bind(batch_id)
+    .fetch_optional(conn)
+    .await?;
+
+    match status.as_deref() {
+        None => Err(StagingError::NotFound),
+        Some("pending") => Ok(()),
+        Some(other) => Err(StagingError::NotPending(other.to_string())),
+    }
+}
+
+/// Merge a pending batch into properties, all or nothing
+/// Each record goes through the same conflict resolution as a direct write:
+/// inserted when new, updated when better quality than the stored property,
+/// skipped otherwise
+pub async fn approve_batch(
+    db: &PgPool,
+    batch_id: i32,
+    actor: &str,
+) -> Result<BatchReview, StagingError> {
+    let mut tx = db.begin().await?;
+    lock_pending_batch(&mut tx, batch_id).await?;
+
+    let rows = sqlx::query_scalar::<_, Value>(
+        "SELECT record FROM properties_staging WHERE batch_id = $1 ORDER BY row_number",
+    )
+    .bind(batch_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let records = rows
+        .into_iter()
+        .map(serde_json::from_value::<PropertyRecord>)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("Unreadable staged record in batch {}: {}", batch_id, e))?;
+
+    let stats = write::write_properties_on(&mut tx, records, &DriftConfig::from_env()).await?;
+    if stats.errors > 0 {
+        tx.rollback().await?;
+        warn!("Rolled back merge of staging batch {}: {}", batch_id, stats);
+        return Err(StagingError::MergeFailed(stats.errors));
+    }
+
+    sqlx::query("DELETE FROM properties_staging WHERE batch_id = $1")
+        .bind(batch_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"
+        UPDATE staging_batches
+        SET status = 'approved', reviewed_at = NOW(), reviewed_by = $2, merge_stats = $3
+        WHERE id = $1
+        "#,
+    )
+    .bind(batch_id)
+    .bind(actor)
+    .bind(serde_json::to_value(&stats).map_err(anyhow::Error::from)?)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    info!("Merged staging batch {} for {}: {}", batch_id, actor, stats);
+
+    Ok(BatchReview {
+        batch_id,
+        status: "approved".to_string(),
+        reviewed_by: actor.to_string(),
+        merged: Some(stats),
+        discarded: None,
+    })
+}
+
+/// Discard a pending batch's records without writing any of them
+pub async fn reject_batch(
+    db: &PgPool,
+    batch_id: i32,
+    actor: &str,
+) -> Result<BatchReview, StagingError> {
+    let mut tx = db.begin().await?;
+    lock_pending_batch(&mut tx, batch_id).await?;
+
+    let discarded = sqlx::query("DELETE FROM properties_staging WHERE batch_id = $1")
+        .bind(batch_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    sqlx::query(
+        r#"
+        UPDATE staging_batches
+        SET status = 'rejected', reviewed_at = NOW(), reviewed_by = $2
+        WHERE id = $1
+        "#,
+    )
+    .bind(batch_id)
+    .bind(actor)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    info!(
+        "Rejected staging batch {} for {}: {} records discarded",
+        batch_id, actor, discarded
+    );
+
+    Ok(BatchReview {
+        batch_id,
+        status: "rejected".to_string(),
+        reviewed_by: actor.to_string(),
+        merged: None,
+        discarded: Some(discarded),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::types::DataQuality;
+    use crate::test_support::{delete_suburb, PropertyFixture};
+
+    const SUBURB: &str = "Staging Testville";
+    const SOURCE: &str = "staging_test";
+
+    fn property(address: &str, price: i64) -> PropertyFixture {
+        PropertyFixture::new()
+            .address(address)
+            .suburb(SUBURB)
+            .price(price)
+            .data_source(SOURCE)
+    }
+
+    async fn price_of(db: &PgPool, address: &str) -> Option<i64> {
+        sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT price FROM properties WHERE address = $1 AND suburb = $2",
+        )
+        .bind(address)
+        .bind(SUBURB)
+        .fetch_optional(db)
+        .await
+        .unwrap()
+        .flatten()
+    }
+
+    async fn cleanup(db: &PgPool) {
+        delete_suburb(db, SUBURB).await.unwrap();
+        sqlx::query("DELETE FROM staging_batches WHERE source_id = $1")
+            .bind(SOURCE)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_approve_merges_with_conflict_resolution() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        // Stored as a low-confidence estimate, so the staged record replaces it
+        property("1 Stage Street", 500_000)
+            .data_quality(DataQuality::Estimated, 0.5)
+            .insert(&db)
+            .await
+            .unwrap();
+        // Stored as an individual record, so the staged estimate is skipped
+        property("2 Stage Street", 700_000).insert(&db).await.unwrap();
+
+        let batch = stage_records(
+            &db,
+            SOURCE,
+            vec![
+                property("1 Stage Street", 550_000).record().clone(),
+                property("2 Stage Street", 10)
+                    .data_quality(DataQuality::Estimated, 1.0)
+                    .record()
+                    .clone(),
+                property("3 Stage Street", 900_000).record().clone(),
+            ],
+        )
+        .await
+        .unwrap();
+
+        // Nothing reaches properties until the batch is approved
+        assert_eq!(price_of(&db, "3 Stage Street").await, None);
+        let pending = list_pending_batches(&db, 2).await.unwrap();
+        let listed = pending.iter().find(|b| b.id == batch).unwrap();
+        assert_eq!(listed.source_id, SOURCE);
+        assert_eq!(listed.quality.records, 3);
+        assert_eq!(listed.quality.missing_price, 0);
+        assert_eq!(listed.quality.missing_rent, 3);
+        assert_eq!(listed.quality.missing_coordinates, 3);
+        assert_eq!(listed.quality.average_confidence, Some(1.0));
+        assert_eq!(listed.samples.len(), 2);
+        assert_eq!(listed.samples[0]["address"], "1 Stage Street");
+        assert_eq!(listed.samples[0]["source_metadata"]["source_id"], SOURCE);
+
+        let review = approve_batch(&db, batch, "admin").await.unwrap();
+        assert_eq!(review.status, "approved");
+        let stats = review.merged.unwrap();
+        assert_eq!(
+            (stats.inserted, stats.updated, stats.skipped, stats.errors),
+            (1, 1, 1, 0)
+        );
+        assert_eq!(price_of(&db, "1 Stage Street").await, Some(550_000));
+        assert_eq!(price_of(&db, "2 Stage Street").await, Some(700_000));
+        assert_eq!(price_of(&db, "3 Stage Street").await, Some(900_000));
+
+        // Reviewed once only, and no longer listed
+        assert!(matches!(
+            approve_batch(&db, batch, "admin").await,
+            Err(StagingError::NotPending(status)) if status == "approved"
+        ));
+        let pending = list_pending_batches(&db, 2).await.unwrap();
+        assert!(pending.iter().all(|b| b.id != batch));
+        let (rows, merge_stats): (i64, Option<Value>) = sqlx::query_as(
+            r#"
+            SELECT (SELECT COUNT(*) FROM properties_staging WHERE batch_id = $1), merge_stats
+            FROM staging_batches WHERE id = $1
+            "#,
+        )
+        .bind(batch)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(rows, 0);
+        assert_eq!(merge_stats.unwrap()["inserted"], 1);
+
+        cleanup(&db).await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_reject_discards_batch() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        let batch = stage_records(
+            &db,
+            SOURCE,
+            vec![
+                property("4 Stage Street", 600_000).record().clone(),
+                property("5 Stage Street", 610_000).record().clone(),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let review = reject_batch(&db, batch, "admin").await.unwrap();
+        assert_eq!(review.status, "rejected");
+        assert_eq!(review.discarded, Some(2));
+        assert_eq!(price_of(&db, "4 Stage Street").await, None);
+        assert!(matches!(
+            approve_batch(&db, batch, "admin").await,
+            Err(StagingError::NotPending(status)) if status == "rejected"
+        ));
+        assert!(matches!(
+            reject_batch(&db, i32::MAX, "admin").await,
+            Err(StagingError::NotFound)
+        ));
+
+        cleanup(&db).await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_failed_merge_writes_nothing() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        cleanup(&db).await;
+
+        // The second record's postcode is too long for the column
+        let batch = stage_records(
+            &db,
+            SOURCE,
+            vec![
+                property("6 Stage Street", 600_000).record().clone(),
+                property("7 Stage Street", 600_000)
+                    .postcode(&"2".repeat(50))
+                    .record()
+                    .clone(),
+            ],
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            approve_batch(&db, batch, "admin").await,
+            Err(StagingError::MergeFailed(1))
+        ));
+        assert_eq!(price_of(&db, "6 Stage Street").await, None);
+        let status: String = sqlx::query_scalar("SELECT status FROM staging_batches WHERE id = $1")
+            .bind(batch)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(status, "pending");
+
+        cleanup(&db).await;
+    }
+}
@@ -0,0 +1,322 @@
+//! Adaptive write throttle - backs ingestion off when the shared database is slow
+//!
+//! Ingestion writes to the same Postgres that serves the API. In nice mode the
+//! write stage probes query latency after every chunk; over the threshold the
+//! throttle steps up a level, halving the chunks written at once and doubling
+//! the pause before each one. A few healthy probes in a row step it back down
+//! one level, so recovery is gradual. Fast mode never throttles.
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::env;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Chunks written at once when unthrottled
+pub const DEFAULT_WRITE_CONCURRENCY: usize = 4;
+/// Probe latency above which the throttle steps up
+pub const DEFAULT_LATENCY_THRESHOLD: Duration = Duration::from_millis(200);
+/// Pause before each chunk at level 1; doubles with each level
+pub const DEFAULT_THROTTLE_DELAY: Duration = Duration::from_millis(250);
+/// Highest level; 4s between chunks at the default delay
+pub const MAX_THROTTLE_LEVEL: u32 = 5;
+/// Consecutive healthy probes needed to step down a level
+pub const RECOVERY_PROBES: u32 = 3;
+
+/// Whether the write stage adapts to database load
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThrottleMode {
+    /// Probe latency and back off under load
+    Nice,
+    /// Always write at full concurrency
+    Fast,
+}
+
+impl FromStr for ThrottleMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "nice" => Ok(ThrottleMode::Nice),
+            "fast" => Ok(ThrottleMode::Fast),
+            other => Err(format!("unknown write mode '{}'", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrottleConfig {
+    pub mode: ThrottleMode,
+    pub max_concurrency: usize,
+    pub latency_threshold: Duration,
+    pub base_delay: Duration,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        ThrottleConfig {
+            mode: ThrottleMode::Nice,
+            max_concurrency: DEFAULT_WRITE_CONCURRENCY,
+            latency_threshold: DEFAULT_LATENCY_THRESHOLD,
+            base_delay: DEFAULT_THROTTLE_DELAY,
+        }
+    }
+}
+
+impl ThrottleConfig {
+    /// Defaults overridden by INGEST_WRITE_MODE (nice or fast),
+    /// INGEST_WRITE_CONCURRENCY, INGEST_LATENCY_THRESHOLD_MS and
+    /// INGEST_THROTTLE_DELAY_MS
+    pub fn from_env() -> Self {
+        let defaults = ThrottleConfig::default();
+        let millis = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .map(Duration::from_millis)
+        };
+
+        ThrottleConfig {
+            mode: env::var("INGEST_WRITE_MODE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.mode),
+            max_concurrency: env::var("INGEST_WRITE_CONCURRENCY")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(defaults.max_concurrency),
+            latency_threshold: millis("INGEST_LATENCY_THRESHOLD_MS")
+                .unwrap_or(defaults.latency_threshold),
+            base_delay: millis("INGEST_THROTTLE_DELAY_MS").unwrap_or(defaults.base_delay),
+        }
+    }
+}
+
+/// The throttle as reported in run progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThrottleStatus {
+    pub mode: ThrottleMode,
+    /// 0 is full speed
+    pub level: u32,
+    pub concurrency: usize,
+    pub delay_ms: u64,
+    /// Latest probe; None before the first, or in fast mode
+    pub latency_ms: Option<u64>,
+}
+
+/// How much a run was throttled; recorded in its metrics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThrottleMetrics {
+    pub mode: Option<ThrottleMode>,
+    pub max_level: u32,
+    /// Probes over the latency threshold
+    pub slow_probes: u64,
+    pub probes: u64,
+    /// Total pause inserted before chunks
+    pub delay_ms: u64,
+}
+
+/// Latency-driven controller for the write stage's concurrency and pacing
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    config: ThrottleConfig,
+    level: u32,
+    healthy_streak: u32,
+    latency: Option<Duration>,
+    metrics: ThrottleMetrics,
+}
+
+impl Throttle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        let metrics = ThrottleMetrics {
+            mode: Some(config.mode),
+            ..Default::default()
+        };
+        Throttle {
+            config,
+            level: 0,
+            healthy_streak: 0,
+            latency: None,
+            metrics,
+        }
+    }
+
+    /// Whether probes are taken at all
+    pub fn is_adaptive(&self) -> bool {
+        self.config.mode == ThrottleMode::Nice
+    }
+
+    /// Adjust to a latency probe; returns true if the level changed
+    pub fn observe(&mut self, latency: Duration) -> bool {
+        if !self.is_adaptive() {
+            return false;
+        }
+        let before = self.level;
+        self.latency = Some(latency);
+        self.metrics.probes += 1;
+
+        if latency > self.config.latency_threshold {
+            self.metrics.slow_probes += 1;
+            self.healthy_streak = 0;
+            self.level = (self.level + 1).min(MAX_THROTTLE_LEVEL);
+        } else if self.level > 0 {
+            self.healthy_streak += 1;
+            if self.healthy_streak >= RECOVERY_PROBES {
+                self.healthy_streak = 0;
+                self.level -= 1;
+            }
+        }
+        self.metrics.max_level = self.metrics.max_level.max(self.level);
+
+        if self.level != before {
+            info!(
+                "Write throttle level {} -> {} (probe {}ms): {} concurrent, {}ms between chunks",
+                before,
+                self.level,
+                latency.as_millis(),
+                self.concurrency(),
+                self.delay().as_millis()
+            );
+        }
+        self.level != before
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Chunks that may be written at once; never below one
+    pub fn concurrency(&self) -> usize {
+        (self.config.max_concurrency >> self.level).max(1)
+    }
+
+    /// Pause before starting the next chunk
+    pub fn delay(&self) -> Duration {
+        match self.level {
+            0 => Duration::ZERO,
+            level => self.config.base_delay * 2u32.pow(level - 1),
+        }
+    }
+
+    /// Record a pause actually taken, for the run's metrics
+    pub fn record_delay(&mut self, delay: Duration) {
+        self.metrics.delay_ms += delay.as_millis() as u64;
+    }
+
+    pub fn status(&self) -> ThrottleStatus {
+        ThrottleStatus {
+            mode: self.config.mode,
+            level: self.level,
+            concurrency: self.concurrency(),
+            delay_ms: self.delay().as_millis() as u64,
+            latency_ms: self.latency.map(|l| l.as_millis() as u64),
+        }
+    }
+
+    pub fn metrics(&self) -> ThrottleMetrics {
+        self.metrics.clone()
+    }
+}
+
+/// Round-trip time of a trivial query, including the wait for a connection
+/// A failed probe counts as slow
+pub async fn probe_latency(db: &PgPool) -> Duration {
+    let start = Instant::now();
+    match sqlx::query("SELECT 1").execute(db).await {
+        Ok(_) => start.elapsed(),
+        Err(e) => {
+            warn!("Latency probe failed: {}", e);
+            Duration::MAX
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nice() -> ThrottleConfig {
+        ThrottleConfig {
+            mode: ThrottleMode::Nice,
+            max_concurrency: 8,
+            latency_threshold: Duration::from_millis(100),
+            base_delay: Duration::from_millis(250),
+        }
+    }
+
+    /// A probe that replays fixed latencies, in milliseconds
+    fn probe(latencies: &[u64]) -> impl FnMut() -> Duration + '_ {
+        let mut values = latencies.iter();
+        move || Duration::from_millis(*values.next().expect("probe exhausted"))
+    }
+
+    #[test]
+    fn test_throttle_backs_off_and_recovers() {
+        let mut throttle = Throttle::new(nice());
+        let mut probe = probe(&[
+            20, 30, // healthy: full speed
+            400, 350, 500, // slow: three steps up
+            40, 40, 40, // healthy streak: one step down
+            40, 40, 40, 40, 40, 40, // two more steps down
+        ]);
+
+        let mut concurrency = Vec::new();
+        for _ in 0..14 {
+            throttle.observe(probe());
+            concurrency.push(throttle.concurrency());
+        }
+
+        assert_eq!(concurrency, [8, 8, 4, 2, 1, 1, 1, 2, 2, 2, 4, 4, 4, 8]);
+        assert_eq!(throttle.level(), 0);
+        assert_eq!(throttle.delay(), Duration::ZERO);
+
+        let metrics = throttle.metrics();
+        assert_eq!(metrics.max_level, 3);
+        assert_eq!(metrics.slow_probes, 3);
+        assert_eq!(metrics.probes, 14);
+    }
+
+    #[test]
+    fn test_slow_probe_resets_recovery() {
+        let mut throttle = Throttle::new(nice());
+        let mut probe = probe(&[400, 40, 40, 400, 40, 40]);
+
+        for _ in 0..6 {
+            throttle.observe(probe());
+        }
+
+        // Two healthy probes either side of a slow one never make a streak
+        assert_eq!(throttle.level(), 2);
+        assert_eq!(throttle.delay(), Duration::from_millis(500));
+        assert_eq!(throttle.status().latency_ms, Some(40));
+    }
+
+    #[test]
+    fn test_level_is_capped() {
+        let mut throttle = Throttle::new(nice());
+        for _ in 0..20 {
+            throttle.observe(Duration::MAX);
+        }
+
+        assert_eq!(throttle.level(), MAX_THROTTLE_LEVEL);
+        assert_eq!(throttle.concurrency(), 1);
+        assert_eq!(throttle.delay(), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_fast_mode_ignores_probes() {
+        let mut throttle = Throttle::new(ThrottleConfig {
+            mode: ThrottleMode::Fast,
+            ..nice()
+        });
+
+        assert!(!throttle.observe(Duration::from_secs(5)));
+        assert_eq!(throttle.concurrency(), 8);
+        assert_eq!(throttle.delay(), Duration::ZERO);
+        assert_eq!(throttle.status().latency_ms, None);
+        assert_eq!(throttle.metrics().probes, 0);
+    }
+}
@@ -1,18 +1,25 @@
 //! Core data types for the ingestion pipeline
 //! Pure data structures with no behavior
 
-use chrono::{DateTime, NaiveDate, Utc};
+use crate::RentFrequency;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::Type;
+use std::fs::File;
+use std::io::{Cursor, Read};
 use std::path::PathBuf;
 
 /// Raw data from various sources - tagged unions
 #[derive(Debug)]
 pub enum RawData {
     File(PathBuf),
+    /// Files of the same format, such as every CSV extracted from one archive
+    Files(Vec<PathBuf>),
     Bytes(Vec<u8>),
-    Json(serde_json::Value),
+    /// A JSON array as raw bytes, deserialized a record at a time by
+    /// `utils::parse_json_array_stream` rather than held as a Value tree
+    Json(Vec<u8>),
     Csv(String),
 }
 
@@ -24,6 +31,15 @@ impl RawData {
         }
     }
 
+    /// The files of `File` or `Files`, in order
+    pub fn as_file_paths(&self) -> anyhow::Result<&[PathBuf]> {
+        match self {
+            RawData::File(path) => Ok(std::slice::from_ref(path)),
+            RawData::Files(paths) => Ok(paths),
+            _ => Err(anyhow::anyhow!("Expected File or Files, got {:?}", self)),
+        }
+    }
+
     pub fn as_bytes(&self) -> anyhow::Result<&[u8]> {
         match self {
             RawData::Bytes(bytes) => Ok(bytes),
@@ -31,16 +47,42 @@ impl RawData {
         }
     }
 
-    pub fn as_json(&self) -> anyhow::Result<&serde_json::Value> {
+    pub fn as_json(&self) -> anyhow::Result<&[u8]> {
         match self {
             RawData::Json(json) => Ok(json),
             _ => Err(anyhow::anyhow!("Expected Json, got {:?}", self)),
         }
     }
+
+    /// The content of a single file, bytes or string to read from
+    /// `Files` is refused, as its files can't be read as one
+    pub fn into_reader(self) -> anyhow::Result<Box<dyn Read + Send>> {
+        match self {
+            RawData::File(path) => Ok(Box::new(File::open(&path).map_err(|e| {
+                anyhow::anyhow!("Failed to open {:?}: {}", path, e)
+            })?)),
+            RawData::Bytes(bytes) | RawData::Json(bytes) => Ok(Box::new(Cursor::new(bytes))),
+            RawData::Csv(csv) => Ok(Box::new(Cursor::new(csv.into_bytes()))),
+            RawData::Files(_) => Err(anyhow::anyhow!("Expected a single source, got {:?}", self)),
+        }
+    }
+}
+
+/// A CSV held as a string
+impl From<&str> for RawData {
+    fn from(csv: &str) -> Self {
+        RawData::Csv(csv.to_string())
+    }
+}
+
+impl From<Vec<u8>> for RawData {
+    fn from(bytes: Vec<u8>) -> Self {
+        RawData::Bytes(bytes)
+    }
 }
 
 /// Australian states
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
 #[sqlx(type_name = "state_enum", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum State {
     NSW,
@@ -53,6 +95,20 @@ pub enum State {
     NT,
 }
 
+impl State {
+    /// Every variant, in the same order as the Postgres state_enum labels
+    pub const ALL: [State; 8] = [
+        State::NSW,
+        State::VIC,
+        State::QLD,
+        State::WA,
+        State::SA,
+        State::TAS,
+        State::ACT,
+        State::NT,
+    ];
+}
+
 impl std::fmt::Display for State {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -68,8 +124,28 @@ impl std::fmt::Display for State {
     }
 }
 
+/// Parses the abbreviations Display writes, e.g. "NSW"; the same labels serde
+/// and Postgres use, so lowercase is rejected as it is there
+impl std::str::FromStr for State {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        State::ALL
+            .into_iter()
+            .find(|state| state.to_string() == s.trim())
+            .ok_or_else(|| format!("unknown state '{}'", s.trim()))
+    }
+}
+
+/// Lets a list of states bind as state_enum[]
+impl sqlx::postgres::PgHasArrayType for State {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_state_enum")
+    }
+}
+
 /// Property types
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
 #[sqlx(type_name = "property_type_enum", rename_all = "snake_case")]
 pub enum PropertyType {
     House,
@@ -95,16 +171,46 @@ impl std::fmt::Display for PropertyType {
 }
 
 /// Data quality levels
+/// Written as "Individual"; read as "individual" too, as query parameters
+/// give it
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
 #[sqlx(type_name = "data_quality_enum", rename_all = "snake_case")]
+#[serde(rename_all(deserialize = "snake_case"))]
 pub enum DataQuality {
+    #[serde(alias = "Individual")]
     Individual,  // Real property records (NSW, WA)
+    #[serde(alias = "Aggregated")]
     Aggregated,  // Suburb/postcode medians (VIC, SA)
+    #[serde(alias = "Estimated")]
     Estimated,   // Calculated/derived data
+    #[serde(alias = "Listing")]
     Listing,     // Current market listings (Domain API)
 }
 
+/// Lets a list of levels bind as data_quality_enum[]
+impl sqlx::postgres::PgHasArrayType for DataQuality {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_data_quality_enum")
+    }
+}
+
 impl DataQuality {
+    /// Every variant, best scoring first
+    pub const ALL: [DataQuality; 4] = [
+        DataQuality::Individual,
+        DataQuality::Listing,
+        DataQuality::Aggregated,
+        DataQuality::Estimated,
+    ];
+
+    /// This level and every level that scores higher
+    pub fn at_least(self) -> Vec<DataQuality> {
+        DataQuality::ALL
+            .into_iter()
+            .filter(|q| q.score() >= self.score())
+            .collect()
+    }
+
     /// Quality score for conflict resolution (higher = better)
     pub fn score(&self) -> i32 {
         match self {
@@ -117,7 +223,7 @@ impl DataQuality {
 }
 
 /// Property record - pure data, no behavior
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertyRecord {
     // Core identification
     pub external_id: Option<String>,
@@ -125,6 +231,9 @@ pub struct PropertyRecord {
     pub suburb: String,
     pub state: State,
     pub postcode: Option<String>,
+    /// NSW Valuer General district code, e.g. "001", as by `normalize_district`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub district: Option<String>,
 
     // Property attributes
     pub property_type: PropertyType,
@@ -133,10 +242,21 @@ pub struct PropertyRecord {
     pub land_area_sqm: Option<Decimal>,
 
     // Financial data
-    pub sale_price: Option<i32>,
+    pub sale_price: Option<i64>,
     pub sale_date: Option<NaiveDate>,
     pub weekly_rent: Option<i32>,
+    /// How the source quoted the rent behind weekly_rent, which is always
+    /// per week (see `RentFrequency::to_weekly`); None when quoted weekly
+    #[serde(default)]
+    pub rent_frequency: Option<RentFrequency>,
     pub rental_yield: Option<Decimal>,
+    /// Sale price over land area; houses and vacant land only
+    #[serde(default)]
+    pub price_per_sqm: Option<Decimal>,
+    /// Earlier sales of the same property in the source, as (price, date),
+    /// oldest first; sale_price and sale_date are the latest
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prior_sales: Vec<(i64, NaiveDate)>,
 
     // Geolocation
     pub latitude: Option<Decimal>,
@@ -147,15 +267,61 @@ pub struct PropertyRecord {
 }
 
 /// Metadata about where this record came from
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceMetadata {
     pub source_id: String,
     pub data_quality: DataQuality,
     pub fetched_at: DateTime<Utc>,
     pub is_rental_estimated: bool,
+    pub is_bedrooms_estimated: bool,
+    /// Period of the rental median the rent (and yield) came from
+    pub rental_period: Option<NaiveDate>,
+    /// How far the rental matching had to look for that median
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rental_match_method: Option<RentalMatchMethod>,
+    /// Archive key of the raw file this record was parsed from
+    pub source_file: Option<String>,
+    /// Zero-based data row within that file (header excluded)
+    pub source_row: Option<i32>,
     pub confidence_score: f32, // 0.0-1.0
 }
 
+/// Where a matched rent came from, closest match first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[sqlx(type_name = "rental_match_method_enum", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RentalMatchMethod {
+    /// The property's own postcode and bedroom count
+    Postcode,
+    /// The median of bond lodgements for its postcode and bedroom count
+    Observations,
+    /// Another postcode of the same suburb, for its bedroom count
+    Suburb,
+    /// Its own postcode, between the bedroom counts either side with a median
+    Interpolated,
+    /// Its own postcode, scaled from the nearest bedroom count with a median
+    NearestBedrooms,
+}
+
+/// Key used to match a property to rental medians
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RentalLookup {
+    pub state: State,
+    pub postcode: String,
+    pub bedrooms: i32,
+}
+
+impl RentalLookup {
+    /// None when the record is missing a postcode or bedroom count
+    pub fn from_record(record: &PropertyRecord) -> Option<Self> {
+        Some(RentalLookup {
+            state: record.state,
+            postcode: record.postcode.clone()?,
+            bedrooms: record.bedrooms?,
+        })
+    }
+}
+
 /// Rental median data (for matching)
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct RentalMedian {
@@ -165,11 +331,95 @@ pub struct RentalMedian {
     pub bedrooms: i32,
     pub median_weekly_rent: i32,
     pub sample_size: Option<i32>,
+    /// None for the median across all dwelling types
+    pub dwelling_type: Option<PropertyType>,
+    pub period: NaiveDate,
+}
+
+/// One bond lodgement - a single rental event, without an address
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RentalObservation {
+    pub state: State,
+    pub postcode: String,
+    pub dwelling_type: PropertyType,
+    /// None where the lodgement didn't record it
+    pub bedrooms: Option<i32>,
+    pub weekly_rent: i32,
+    /// Month of the file the lodgement came from
     pub period: NaiveDate,
 }
 
+/// A postcode's SA3 region from the ABS postcode correspondence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PostcodeRegion {
+    pub postcode: String,
+    pub sa3_code: String,
+    pub sa3_name: String,
+    /// The SA4 containing the SA3: the first three digits of its code
+    pub sa4_code: String,
+    /// Only present when the correspondence file names SA4s
+    pub sa4_name: Option<String>,
+    /// None for Other Territories
+    pub state: Option<State>,
+    /// Share of the postcode that falls in this SA3; below 1 where it spans several
+    pub ratio: f64,
+}
+
+/// Whether an LGA correspondence row is keyed by suburb or by postcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LgaAreaType {
+    Suburb,
+    Postcode,
+}
+
+impl LgaAreaType {
+    /// Value of lga_correspondence.area_type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LgaAreaType::Suburb => "suburb",
+            LgaAreaType::Postcode => "postcode",
+        }
+    }
+}
+
+/// A suburb or postcode's local government area from the ABS correspondence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LgaCorrespondence {
+    pub area_type: LgaAreaType,
+    /// Upper-case suburb name, or the postcode
+    pub area: String,
+    pub state: State,
+    pub lga_code: String,
+    pub lga_name: String,
+    /// Share of the area that falls in this LGA; below 1 where it spans several
+    pub ratio: f64,
+}
+
+/// One council's average residential rates from the council rates dataset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CouncilRates {
+    pub lga_code: String,
+    pub lga_name: String,
+    /// None when the LGA code doesn't start with a state digit
+    pub state: Option<State>,
+    /// e.g. 2023-24
+    pub financial_year: Option<String>,
+    /// AUD a year
+    pub average_rates: i32,
+    /// Domestic waste and other annual charges, AUD a year
+    pub average_levies: Option<i32>,
+}
+
+impl CouncilRates {
+    /// Rates plus levies, AUD a year
+    pub fn annual_total(&self) -> i32 {
+        self.average_rates + self.average_levies.unwrap_or(0)
+    }
+}
+
 /// Database row from properties table
-#[derive(Debug, sqlx::FromRow)]
+#[derive(Debug, Clone, sqlx::FromRow)]
 pub struct PropertyRow {
     pub id: i32,
     pub address: String,
@@ -177,7 +427,7 @@ pub struct PropertyRow {
     pub state: State,
     pub postcode: Option<String>,
     pub bedrooms: Option<i32>,
-    pub price: Option<i32>,
+    pub price: Option<i64>,
     pub weekly_rent: Option<i32>,
     pub property_type: Option<PropertyType>,
     pub data_source: Option<String>,
@@ -204,35 +454,196 @@ impl PropertyRow {
 }
 
 /// Write operation statistics
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct WriteStats {
     pub inserted: usize,
     pub updated: usize,
     pub skipped: usize,
     pub errors: usize,
+    /// Non-residential records dropped before enrichment
+    #[serde(default)]
+    pub excluded: usize,
+    /// How updated properties' price, rent and yield moved
+    pub drift: crate::ingestion::drift::DriftReport,
+}
+
+impl std::ops::AddAssign for WriteStats {
+    fn add_assign(&mut self, other: WriteStats) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.skipped += other.skipped;
+        self.errors += other.errors;
+        self.excluded += other.excluded;
+        self.drift += &other.drift;
+    }
 }
 
 impl std::fmt::Display for WriteStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "inserted: {}, updated: {}, skipped: {}, errors: {}",
-            self.inserted, self.updated, self.skipped, self.errors
+            "inserted: {}, updated: {}, skipped: {}, errors: {}, excluded: {}",
+            self.inserted, self.updated, self.skipped, self.errors, self.excluded
         )
     }
 }
 
 /// Ingestion run record
-#[derive(Debug, sqlx::FromRow)]
+#[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct IngestionRun {
     pub id: i32,
     pub source_id: String,
     pub status: String,
-    pub started_at: DateTime<Utc>,
-    pub completed_at: Option<DateTime<Utc>>,
+    pub started_at: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
     pub records_fetched: i32,
     pub records_inserted: i32,
     pub records_updated: i32,
     pub records_skipped: i32,
     pub error_message: Option<String>,
+    /// Latest `runs::RunProgress`, None until the run reports any
+    pub progress: Option<serde_json::Value>,
+    /// `anomaly::RunMetrics`, recorded when the run completes
+    pub metrics: Option<serde_json::Value>,
+    /// `anomaly::Anomaly` list for runs completed with warnings
+    pub anomalies: Option<serde_json::Value>,
+    /// `archive::ArchivedFile` list of the files the run fetched
+    pub raw_files: Option<serde_json::Value>,
+    /// Rows that failed to parse; None for sources that don't report them
+    pub parse_errors: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(raw: RawData) -> String {
+        let mut content = String::new();
+        raw.into_reader()
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn test_into_reader() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("sales.csv");
+        std::fs::write(&path, "a,b\n1,2\n").unwrap();
+
+        assert_eq!(read_all(RawData::File(path.clone())), "a,b\n1,2\n");
+        assert_eq!(read_all(RawData::from("a,b\n1,2\n")), "a,b\n1,2\n");
+        assert_eq!(read_all(RawData::from(b"a,b\n".to_vec())), "a,b\n");
+
+        assert!(RawData::Files(vec![path.clone(), path]).into_reader().is_err());
+        let missing = RawData::File(temp.path().join("missing.csv")).into_reader();
+        assert!(missing.err().unwrap().to_string().contains("missing.csv"));
+    }
+
+    /// Labels declared for state_enum in the database schema
+    fn schema_state_labels() -> Vec<String> {
+        let schema = include_str!("../../../database/init/01_simple_init.sql");
+        let line = schema
+            .lines()
+            .find(|l| l.starts_with("CREATE TYPE state_enum AS ENUM"))
+            .expect("schema should declare state_enum");
+        let list = &line[line.find('(').unwrap() + 1..line.rfind(')').unwrap()];
+        list.split(',')
+            .map(|label| label.trim().trim_matches('\'').to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_state_names_match_postgres_labels() {
+        // A new variant stops this match compiling, as a reminder to add it
+        // to ALL; the compiler can't check ALL itself
+        for state in State::ALL {
+            match state {
+                State::NSW
+                | State::VIC
+                | State::QLD
+                | State::WA
+                | State::SA
+                | State::TAS
+                | State::ACT
+                | State::NT => {}
+            }
+        }
+
+        let serde_names: Vec<String> = State::ALL
+            .iter()
+            .map(|s| serde_json::to_value(s).unwrap().as_str().unwrap().to_string())
+            .collect();
+        let display_names: Vec<String> = State::ALL.iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(serde_names, schema_state_labels());
+        assert_eq!(display_names, schema_state_labels());
+
+        for label in schema_state_labels() {
+            assert_eq!(label.parse::<State>().unwrap().to_string(), label);
+        }
+        assert_eq!(" QLD ".parse::<State>(), Ok(State::QLD));
+        assert_eq!("nsw".parse::<State>(), Err("unknown state 'nsw'".to_string()));
+        assert!("".parse::<State>().is_err());
+    }
+
+    #[test]
+    fn test_data_quality_at_least() {
+        // A new variant stops this match compiling, as a reminder to add it
+        // to ALL; the compiler can't check ALL itself
+        for quality in DataQuality::ALL {
+            match quality {
+                DataQuality::Individual
+                | DataQuality::Aggregated
+                | DataQuality::Estimated
+                | DataQuality::Listing => {}
+            }
+        }
+
+        assert_eq!(
+            DataQuality::Aggregated.at_least(),
+            vec![
+                DataQuality::Individual,
+                DataQuality::Listing,
+                DataQuality::Aggregated
+            ]
+        );
+        assert_eq!(
+            DataQuality::Individual.at_least(),
+            vec![DataQuality::Individual]
+        );
+        assert_eq!(DataQuality::Estimated.at_least(), DataQuality::ALL);
+    }
+
+    #[test]
+    fn test_data_quality_serde() {
+        let read = |json: &str| serde_json::from_str::<DataQuality>(json).ok();
+
+        assert_eq!(read(r#""aggregated""#), Some(DataQuality::Aggregated));
+        // Records written before, as staged batches hold them
+        assert_eq!(read(r#""Aggregated""#), Some(DataQuality::Aggregated));
+        assert_eq!(read(r#""AGGREGATED""#), None);
+        assert_eq!(
+            serde_json::to_value(DataQuality::Listing).unwrap(),
+            "Listing"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_state_decodes_every_postgres_label() {
+        let db = sqlx::PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+
+        let states = sqlx::query_scalar::<_, State>(
+            "SELECT unnest(enum_range(NULL::state_enum)) ORDER BY 1",
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+
+        assert_eq!(states, State::ALL);
+    }
 }
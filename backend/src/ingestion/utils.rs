@@ -1,109 +1,1482 @@
 //! Utility functions for common operations
 
-use anyhow::Result;
-use reqwest::Client;
+use crate::ingestion::object_store::{ObjectStoreConfig, ObjectUrl};
+use crate::ingestion::types::State;
+use anyhow::{bail, Result};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use reqwest::header::{
+    HeaderMap, HeaderValue, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE, RETRY_AFTER,
+};
+use reqwest::{Client, Proxy, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::env;
+use std::fmt;
 use std::fs;
-use std::io;
-use std::path::{Path, PathBuf};
-use tracing::info;
+use std::io::{self, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tracing::{info, warn};
 
-/// Download a file via HTTP
-pub async fn http_get(url: &str) -> Result<Vec<u8>> {
-    info!("Downloading from {}", url);
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(300)) // 5 min timeout
-        .build()?;
+/// Settings of the one HTTP client every source fetch shares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpConfig {
+    /// Sent on every request; the NSW portal throttles anonymous defaults
+    pub user_agent: String,
+    /// Every request goes through this proxy when set
+    pub proxy_url: Option<String>,
+    /// Limit on establishing a connection
+    pub connect_timeout: Duration,
+    /// Limit on a whole request, reading the body included
+    pub timeout: Duration,
+    /// Accept invalid TLS certificates; only for internal mirrors
+    pub accept_invalid_certs: bool,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            user_agent: concat!("real-estate-backend/", env!("CARGO_PKG_VERSION")).to_string(),
+            proxy_url: None,
+            connect_timeout: Duration::from_secs(30),
+            timeout: Duration::from_secs(300), // 5 min, enough for the bulk files
+            accept_invalid_certs: false,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Defaults overridden by HTTP_USER_AGENT, HTTP_PROXY_URL,
+    /// HTTP_CONNECT_TIMEOUT_SECS, HTTP_TIMEOUT_SECS and HTTP_ACCEPT_INVALID_CERTS
+    pub fn from_env() -> Self {
+        let defaults = HttpConfig::default();
+        let var = |name: &str| env::var(name).ok().filter(|s| !s.is_empty());
+        let secs = |name: &str| {
+            var(name)
+                .and_then(|s| s.parse().ok())
+                .map(Duration::from_secs)
+        };
+
+        HttpConfig {
+            user_agent: var("HTTP_USER_AGENT").unwrap_or(defaults.user_agent),
+            proxy_url: var("HTTP_PROXY_URL"),
+            connect_timeout: secs("HTTP_CONNECT_TIMEOUT_SECS").unwrap_or(defaults.connect_timeout),
+            timeout: secs("HTTP_TIMEOUT_SECS").unwrap_or(defaults.timeout),
+            accept_invalid_certs: var("HTTP_ACCEPT_INVALID_CERTS")
+                .is_some_and(|s| s == "1" || s.eq_ignore_ascii_case("true")),
+        }
+    }
+
+    pub fn client(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .user_agent(&self.user_agent)
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout)
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(proxy_url) = &self.proxy_url {
+            builder = builder.proxy(Proxy::all(proxy_url)?);
+        }
+        Ok(builder.build()?)
+    }
+}
 
-    let response = client.get(url).send().await?;
-    let status = response.status();
+/// Retry and pacing rules shared by every source fetch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpPolicy {
+    /// Further attempts after a 429, a 5xx or a connection error
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each one after it
+    /// A Retry-After header takes precedence
+    pub initial_backoff: Duration,
+    /// Minimum time between consecutive requests to a paged API
+    pub min_delay: Duration,
+}
 
-    if !status.is_success() {
-        return Err(anyhow::anyhow!("HTTP request failed: {}", status));
+impl Default for HttpPolicy {
+    fn default() -> Self {
+        HttpPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            min_delay: Duration::ZERO,
+        }
     }
+}
+
+impl HttpPolicy {
+    /// Defaults overridden by HTTP_MAX_RETRIES, HTTP_RETRY_BACKOFF_MS and HTTP_MIN_DELAY_MS
+    pub fn from_env() -> Self {
+        let defaults = HttpPolicy::default();
+        let var = |name: &str| env::var(name).ok().and_then(|s| s.parse::<u64>().ok());
+
+        HttpPolicy {
+            max_retries: env::var("HTTP_MAX_RETRIES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.max_retries),
+            initial_backoff: var("HTTP_RETRY_BACKOFF_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.initial_backoff),
+            min_delay: var("HTTP_MIN_DELAY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.min_delay),
+        }
+    }
+
+    /// Wait before retry number `retry` (zero-based) when the server didn't say
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+    }
+
+    /// Send `request`, retrying transient failures; errors on any other non-2xx
+    /// status except 304, which only conditional requests get back
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let mut retry = 0;
+        loop {
+            let attempt = request
+                .try_clone()
+                .ok_or_else(|| anyhow::anyhow!("Request body can't be retried"))?;
+
+            let wait = match attempt.send().await {
+                Ok(response)
+                    if response.status().is_success()
+                        || response.status() == StatusCode::NOT_MODIFIED =>
+                {
+                    return Ok(response)
+                }
+                Ok(response) if retry < self.max_retries && is_transient(response.status()) => {
+                    let status = response.status();
+                    let wait = retry_after(&response).unwrap_or_else(|| self.backoff(retry));
+                    warn!("HTTP request got {}, retrying in {:?}", status, wait);
+                    wait
+                }
+                Ok(response) => {
+                    return Err(HttpStatusError {
+                        status: response.status(),
+                    }
+                    .into())
+                }
+                Err(e) if retry < self.max_retries && (e.is_connect() || e.is_timeout()) => {
+                    let wait = self.backoff(retry);
+                    warn!("HTTP request failed ({}), retrying in {:?}", e, wait);
+                    wait
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            tokio::time::sleep(wait).await;
+            retry += 1;
+        }
+    }
+}
+
+/// A request answered with a status `HttpPolicy::send` doesn't accept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("HTTP request failed: {status}")]
+pub struct HttpStatusError {
+    pub status: StatusCode,
+}
+
+/// Whether `error` is a request the server answered with `status`
+pub fn is_http_status(error: &anyhow::Error, status: StatusCode) -> bool {
+    error
+        .downcast_ref::<HttpStatusError>()
+        .is_some_and(|e| e.status == status)
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Retry-After given in seconds; the HTTP-date form is ignored
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Called as a download goes with the bytes received so far and the total
+/// from Content-Length, when the server sent one
+pub type ProgressFn = dyn FnMut(u64, Option<u64>) + Send;
+
+/// The shared client and the rules its source requests follow; cloning
+/// shares the client's connection pool and progress hook
+#[derive(Clone)]
+pub struct Fetcher {
+    pub client: Client,
+    pub policy: HttpPolicy,
+    /// Where `s3://` and `gs://` sources are fetched from, and their keys
+    pub object_store: ObjectStoreConfig,
+    /// Connections `download_to_file` splits a download across when the
+    /// server takes range requests; 1 downloads in a single stream
+    pub segments: usize,
+    on_progress: Option<Arc<Mutex<ProgressFn>>>,
+}
 
-    let bytes = response.bytes().await?;
-    info!("Downloaded {} bytes", bytes.len());
-    Ok(bytes.to_vec())
+impl fmt::Debug for Fetcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fetcher")
+            .field("client", &self.client)
+            .field("policy", &self.policy)
+            .field("object_store", &self.object_store)
+            .field("segments", &self.segments)
+            .field("on_progress", &self.on_progress.is_some())
+            .finish()
+    }
 }
 
-/// Extract the first CSV file from a ZIP archive
-pub fn extract_csv_from_zip(zip_path: &Path) -> Result<PathBuf> {
-    info!("Extracting CSV from {:?}", zip_path);
+impl Fetcher {
+    pub fn new(config: &HttpConfig, policy: HttpPolicy) -> Result<Self> {
+        Ok(Fetcher {
+            client: config.client()?,
+            policy,
+            object_store: ObjectStoreConfig::default(),
+            segments: 1,
+            on_progress: None,
+        })
+    }
+
+    /// Client from HttpConfig::from_env, rules from HttpPolicy::from_env,
+    /// object storage from ObjectStoreConfig::from_env and segments from
+    /// HTTP_DOWNLOAD_SEGMENTS: a count, or true for DEFAULT_DOWNLOAD_SEGMENTS;
+    /// unset downloads in a single stream
+    pub fn from_env() -> Result<Self> {
+        let segments = match env::var("HTTP_DOWNLOAD_SEGMENTS") {
+            Ok(s) if s.eq_ignore_ascii_case("true") => DEFAULT_DOWNLOAD_SEGMENTS,
+            Ok(s) => s.parse().unwrap_or(1),
+            Err(_) => 1,
+        };
+        let mut fetcher = Self::new(&HttpConfig::from_env(), HttpPolicy::from_env())?;
+        fetcher.object_store = ObjectStoreConfig::from_env();
+        Ok(fetcher.with_segments(segments))
+    }
+
+    /// Split downloads to files across `segments` connections, at least one
+    pub fn with_segments(mut self, segments: usize) -> Self {
+        self.segments = segments.max(1);
+        self
+    }
+
+    /// Call `on_progress` after every chunk this fetcher downloads, such as
+    /// to drive a progress bar
+    pub fn with_progress(
+        mut self,
+        on_progress: impl FnMut(u64, Option<u64>) + Send + 'static,
+    ) -> Self {
+        self.on_progress = Some(Arc::new(Mutex::new(on_progress)));
+        self
+    }
+
+    /// The URL to download `url` from: itself, or a presigned URL for an
+    /// object storage source
+    pub async fn resolve_url(&self, url: &str) -> Result<String> {
+        match SourceUrl::parse(url)? {
+            SourceUrl::Http(url) => Ok(url),
+            SourceUrl::Object(object) => self.object_store.presigned_url(&object).await,
+            SourceUrl::Local(path) => bail!("{:?} is a local file, not a download", path),
+        }
+    }
+
+    /// Send `request`, built on `self.client`, under the retry rules
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        self.policy.send(request).await
+    }
+
+    /// Download a file via HTTP, or from object storage
+    pub async fn get(&self, url: &str) -> Result<Vec<u8>> {
+        info!("Downloading from {}", url);
+        let response = self
+            .send(self.client.get(self.resolve_url(url).await?))
+            .await?;
+
+        let mut bytes = Vec::new();
+        self.download(response, &mut bytes).await?;
+        Ok(bytes)
+    }
+
+    /// Stream `response`'s body into `out` chunk by chunk, logging progress
+    /// now and then and the average throughput at the end; returns the bytes
+    /// written
+    pub async fn download(&self, mut response: Response, out: &mut impl Write) -> Result<u64> {
+        let started = Instant::now();
+        let mut progress = DownloadProgress::new(response.content_length(), started);
+
+        while let Some(chunk) = response.chunk().await? {
+            out.write_all(&chunk)?;
+            self.record_chunk(&mut progress, chunk.len() as u64);
+        }
+        out.flush()?;
+
+        log_finished(progress.downloaded, started);
+        Ok(progress.downloaded)
+    }
+
+    /// Send `request` for `url` and save the body to `path`
+    ///
+    /// With more than one segment the request asks for the first byte only,
+    /// and a 206 answer has the file fetched in that many ranges at once,
+    /// each written at its offset. A server that ignores the range sends the
+    /// whole file, which is then saved from that one stream.
+    pub async fn download_to_file(
+        &self,
+        request: RequestBuilder,
+        url: &str,
+        path: &Path,
+    ) -> Result<FileDownload> {
+        info!("Downloading from {}", without_signature(url));
+        let request = if self.segments > 1 {
+            request.header(RANGE, "bytes=0-0")
+        } else {
+            request
+        };
+        let mut response = self.send(request).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(FileDownload::NotModified);
+        }
+        let headers = response.headers().clone();
+
+        if response.status() == StatusCode::PARTIAL_CONTENT {
+            if let Some(total) = content_range_total(&headers) {
+                let downloaded = self.download_segments(url, &headers, path, total).await?;
+                return Ok(FileDownload::Saved {
+                    headers,
+                    expected: Some(total),
+                    downloaded,
+                });
+            }
+            warn!(
+                "{} sent no total size, downloading in one stream",
+                without_signature(url)
+            );
+            response = self.send(self.client.get(url)).await?;
+        } else if self.segments > 1 {
+            info!(
+                "{} doesn't take range requests, downloading in one stream",
+                without_signature(url)
+            );
+        }
+
+        let expected = response.content_length();
+        let downloaded = {
+            let mut file = io::BufWriter::new(fs::File::create(path)?);
+            self.download(response, &mut file).await?
+        };
+        Ok(FileDownload::Saved {
+            headers,
+            expected,
+            downloaded,
+        })
+    }
+
+    /// Fetch the `total` bytes of `url` as `self.segments` ranges at once into
+    /// a file of that size; a failed range resumes without restarting the others
+    async fn download_segments(
+        &self,
+        url: &str,
+        headers: &HeaderMap,
+        path: &Path,
+        total: u64,
+    ) -> Result<u64> {
+        // Sent as If-Range, so a file replaced mid-download fails its ranges
+        // rather than mixing versions; weak ETags aren't allowed there
+        let validator = headers
+            .get(ETAG)
+            .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+            .or_else(|| headers.get(LAST_MODIFIED));
+
+        fs::File::create(path)?.set_len(total)?;
+        let ranges = split_ranges(total, self.segments);
+        info!(
+            "Downloading {} in {} segments",
+            format_megabytes(total),
+            ranges.len()
+        );
+
+        let started = Instant::now();
+        let progress = Mutex::new(DownloadProgress::new(Some(total), started));
+        let mut segments: FuturesUnordered<_> = ranges
+            .into_iter()
+            .map(|(start, end)| self.download_segment(url, validator, path, start, end, &progress))
+            .collect();
+        while let Some(result) = segments.next().await {
+            result?;
+        }
+        drop(segments);
+
+        let downloaded = progress
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .downloaded;
+        let assembled = fs::metadata(path)?.len();
+        if downloaded != total || assembled != total {
+            bail!(
+                "Segmented download of {} got {} bytes into a {} byte file, expected {}",
+                without_signature(url),
+                downloaded,
+                assembled,
+                total
+            );
+        }
+        log_finished(downloaded, started);
+        Ok(downloaded)
+    }
+
+    /// Bytes `start..end` of `url` into `path` at the same offset, resuming
+    /// from the last byte written up to `policy.max_retries` times
+    async fn download_segment(
+        &self,
+        url: &str,
+        validator: Option<&HeaderValue>,
+        path: &Path,
+        start: u64,
+        end: u64,
+        progress: &Mutex<DownloadProgress>,
+    ) -> Result<()> {
+        let mut next = start;
+        let mut retry = 0;
+        loop {
+            let error = match self
+                .download_range(url, validator, path, &mut next, end, progress)
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            if retry >= self.policy.max_retries {
+                return Err(error.context(format!("Segment {}-{} failed", start, end - 1)));
+            }
+            let wait = self.policy.backoff(retry);
+            warn!(
+                "Segment {}-{} stopped at byte {} ({}), resuming in {:?}",
+                start,
+                end - 1,
+                next,
+                error,
+                wait
+            );
+            tokio::time::sleep(wait).await;
+            retry += 1;
+        }
+    }
+
+    /// One request for `*next..end`, advancing `*next` past every byte written
+    async fn download_range(
+        &self,
+        url: &str,
+        validator: Option<&HeaderValue>,
+        path: &Path,
+        next: &mut u64,
+        end: u64,
+        progress: &Mutex<DownloadProgress>,
+    ) -> Result<()> {
+        let mut request = self
+            .client
+            .get(url)
+            .header(RANGE, format!("bytes={}-{}", *next, end - 1));
+        if let Some(validator) = validator {
+            request = request.header(IF_RANGE, validator.clone());
+        }
+        let mut response = self.send(request).await?;
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            bail!(
+                "Range request got {}; the file may have changed",
+                response.status()
+            );
+        }
+
+        let mut file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+        file.seek(SeekFrom::Start(*next)).await?;
+        while let Some(chunk) = response.chunk().await? {
+            let len = chunk.len() as u64;
+            if *next + len > end {
+                bail!("Server sent more than bytes {}-{}", *next, end - 1);
+            }
+            file.write_all(&chunk).await?;
+            *next += len;
+            let mut progress = progress.lock().unwrap_or_else(|e| e.into_inner());
+            self.record_chunk(&mut progress, len);
+        }
+        file.flush().await?;
+
+        if *next < end {
+            bail!("Range ended early at byte {}", *next);
+        }
+        Ok(())
+    }
+
+    /// Count a downloaded chunk, logging when a line is due and telling the hook
+    fn record_chunk(&self, progress: &mut DownloadProgress, chunk: u64) {
+        if progress.advance(chunk, Instant::now()) {
+            info!("{}", progress.describe());
+        }
+        if let Some(on_progress) = &self.on_progress {
+            let mut on_progress = on_progress.lock().unwrap_or_else(|e| e.into_inner());
+            on_progress(progress.downloaded, progress.total);
+        }
+    }
+}
+
+/// Segments `HTTP_DOWNLOAD_SEGMENTS=true` splits a download into
+pub const DEFAULT_DOWNLOAD_SEGMENTS: usize = 4;
+
+/// What `Fetcher::download_to_file` got back
+#[derive(Debug)]
+pub enum FileDownload {
+    /// The server answered a conditional request with 304; nothing was written
+    NotModified,
+    Saved {
+        /// Headers of the first response, validators included
+        headers: HeaderMap,
+        /// Size the server announced in Content-Length or Content-Range
+        expected: Option<u64>,
+        /// Bytes written to the file
+        downloaded: u64,
+    },
+}
+
+/// The total of a Content-Range such as `bytes 0-0/1234`; None when it's `*`
+fn content_range_total(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .rsplit_once('/')?
+        .1
+        .parse()
+        .ok()
+}
+
+/// `url` for logs and errors, cut before a presigned URL's signature
+fn without_signature(url: &str) -> &str {
+    url.split_once("?X-Amz-").map_or(url, |(base, _)| base)
+}
+
+/// `total` bytes as at most `segments` half-open ranges of near equal size
+fn split_ranges(total: u64, segments: usize) -> Vec<(u64, u64)> {
+    let segments = (segments.max(1) as u64).min(total.max(1));
+    let size = total.div_ceil(segments);
+    (0..segments)
+        .map(|n| (n * size, ((n + 1) * size).min(total)))
+        .filter(|(start, end)| start < end)
+        .collect()
+}
+
+fn log_finished(downloaded: u64, started: Instant) {
+    let elapsed = started.elapsed();
+    info!(
+        "Downloaded {} in {:.1}s ({}/s)",
+        format_megabytes(downloaded),
+        elapsed.as_secs_f64(),
+        format_megabytes(throughput(downloaded, elapsed)),
+    );
+}
+
+/// Most time between two progress lines of one download
+pub const PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Most bytes received between two progress lines of one download
+pub const PROGRESS_LOG_BYTES: u64 = 25 * 1024 * 1024;
+
+/// How far a download has got, and when it was last logged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    logged_at: Instant,
+    logged_bytes: u64,
+}
+
+impl DownloadProgress {
+    pub fn new(total: Option<u64>, started: Instant) -> Self {
+        DownloadProgress {
+            downloaded: 0,
+            total,
+            logged_at: started,
+            logged_bytes: 0,
+        }
+    }
+
+    /// Count a chunk received at `now`; true when a progress line is due,
+    /// that is PROGRESS_LOG_INTERVAL or PROGRESS_LOG_BYTES since the last one
+    pub fn advance(&mut self, chunk: u64, now: Instant) -> bool {
+        self.downloaded += chunk;
+        let due = now.saturating_duration_since(self.logged_at) >= PROGRESS_LOG_INTERVAL
+            || self.downloaded - self.logged_bytes >= PROGRESS_LOG_BYTES;
+        if due {
+            self.logged_at = now;
+            self.logged_bytes = self.downloaded;
+        }
+        due
+    }
+
+    /// "Downloaded 12.5 of 250.0 MB (5%)", or just the bytes without a total
+    pub fn describe(&self) -> String {
+        match self.total {
+            Some(total) if total > 0 => format!(
+                "Downloaded {:.1} of {} ({}%)",
+                self.downloaded as f64 / MEGABYTE,
+                format_megabytes(total),
+                self.downloaded * 100 / total,
+            ),
+            _ => format!("Downloaded {}", format_megabytes(self.downloaded)),
+        }
+    }
+}
+
+const MEGABYTE: f64 = 1024.0 * 1024.0;
+
+fn format_megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / MEGABYTE)
+}
+
+/// Bytes per second over `elapsed`; all of them when it took no measurable time
+fn throughput(bytes: u64, elapsed: Duration) -> u64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        (bytes as f64 / secs) as u64
+    } else {
+        bytes
+    }
+}
+
+/// Where a source URL points, by scheme
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceUrl {
+    /// A `file://` URL or a plain path
+    Local(PathBuf),
+    /// `s3://` or `gs://`
+    Object(ObjectUrl),
+    /// Anything else is handed to the HTTP client
+    Http(String),
+}
+
+impl SourceUrl {
+    pub fn parse(url: &str) -> Result<Self> {
+        if let Some(path) = local_source_path(url) {
+            return Ok(SourceUrl::Local(path));
+        }
+        Ok(match ObjectUrl::parse(url)? {
+            Some(object) => SourceUrl::Object(object),
+            None => SourceUrl::Http(url.to_string()),
+        })
+    }
+}
+
+/// Path named by a `file://` URL or a plain path; None for anything to download
+pub fn local_source_path(url: &str) -> Option<PathBuf> {
+    match url.strip_prefix("file://") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => (!url.contains("://")).then(|| PathBuf::from(url)),
+    }
+}
+
+/// Fail naming `path` unless it is a readable file
+pub fn check_local_source(path: &Path) -> Result<()> {
+    if !path.is_file() {
+        anyhow::bail!("Source file {} does not exist", path.display());
+    }
+    Ok(())
+}
+
+/// A source's bytes, read from disk for local paths and downloaded otherwise
+pub async fn read_source(fetcher: &Fetcher, url: &str) -> Result<Vec<u8>> {
+    let SourceUrl::Local(path) = SourceUrl::parse(url)? else {
+        return fetcher.get(url).await;
+    };
+    check_local_source(&path)?;
+    info!("Reading {:?}", path);
+    Ok(fs::read(&path)?)
+}
+
+/// Save the source at `url` to `dest`, whatever its scheme: local files are
+/// copied and everything else is downloaded
+pub async fn fetch_url(fetcher: &Fetcher, url: &str, dest: &Path) -> Result<FileDownload> {
+    match SourceUrl::parse(url)? {
+        SourceUrl::Local(path) => {
+            check_local_source(&path)?;
+            let copied = fs::copy(&path, dest)?;
+            info!("Copied {:?} to {:?}", path, dest);
+            Ok(FileDownload::Saved {
+                headers: HeaderMap::new(),
+                expected: Some(copied),
+                downloaded: copied,
+            })
+        }
+        SourceUrl::Object(_) | SourceUrl::Http(_) => {
+            let url = fetcher.resolve_url(url).await?;
+            fetcher
+                .download_to_file(fetcher.client.get(&url), &url, dest)
+                .await
+        }
+    }
+}
+
+/// Extract every sales file, CSV or DAT, in a ZIP archive into `{stem}_csv`
+/// beside it, replacing whatever an earlier extraction left there; paths are
+/// sorted by name.
+/// Entries whose names could escape that directory are skipped.
+pub fn extract_sales_files_from_zip(zip_path: &Path) -> Result<Vec<PathBuf>> {
+    info!("Extracting sales files from {:?}", zip_path);
+
+    let stem = zip_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Cannot extract {:?}: no file name", zip_path))?;
+    let output_dir = zip_path.with_file_name(format!("{}_csv", stem));
+    if output_dir.exists() {
+        fs::remove_dir_all(&output_dir)?;
+    }
+    fs::create_dir_all(&output_dir)?;
 
     let file = fs::File::open(zip_path)?;
     let mut archive = zip::ZipArchive::new(file)?;
+    let mut paths = Vec::new();
 
-    // Find first CSV file
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
         let filename = file.name().to_string();
+        if file.is_dir() || !is_sales_file_name(&filename) {
+            continue;
+        }
+        let Some(relative) = safe_entry_path(&filename) else {
+            warn!("Skipping ZIP entry with an unsafe name: {:?}", filename);
+            continue;
+        };
 
-        if filename.ends_with(".csv") {
-            info!("Found CSV file: {}", filename);
+        let output_path = output_dir.join(relative);
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut output_file = fs::File::create(&output_path)?;
+        io::copy(&mut file, &mut output_file)?;
+        paths.push(output_path);
+    }
 
-            // Extract to same directory as ZIP
-            let output_dir = zip_path.parent().unwrap();
-            let output_path = output_dir.join(&filename);
+    if paths.is_empty() {
+        return Err(anyhow::anyhow!("No CSV or DAT file found in ZIP archive"));
+    }
+    paths.sort();
+    info!("Extracted {} sales files to {:?}", paths.len(), output_dir);
+    Ok(paths)
+}
 
-            let mut output_file = fs::File::create(&output_path)?;
-            io::copy(&mut file, &mut output_file)?;
+/// Whether a ZIP entry name is a CSV, whatever the extension's case
+pub fn is_csv_name(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".csv")
+}
 
-            info!("Extracted to {:?}", output_path);
-            return Ok(output_path);
-        }
-    }
+/// Whether a name is a Valuer General bulk sales .DAT file, whatever the case
+pub fn is_dat_name(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".dat")
+}
 
-    Err(anyhow::anyhow!("No CSV file found in ZIP archive"))
+/// Either format the NSW sales archives come in
+pub fn is_sales_file_name(name: &str) -> bool {
+    is_csv_name(name) || is_dat_name(name)
+}
+
+/// A ZIP entry name as a path relative to the extraction directory; None for
+/// absolute paths, `..` and anything else that could write outside it
+fn safe_entry_path(name: &str) -> Option<PathBuf> {
+    // Archives made on Windows may separate with backslashes
+    let name = name.replace('\\', "/");
+    let path = Path::new(&name);
+    let safe = path.components().next().is_some()
+        && path.components().all(|c| matches!(c, Component::Normal(_)));
+
+    safe.then(|| path.to_path_buf())
 }
 
 /// Parse property type from NSW "Nature of property" field
 pub fn parse_nsw_property_type(nature: &str) -> crate::ingestion::types::PropertyType {
     let lower = nature.to_lowercase();
 
-    if lower.contains("house") || lower.contains("dwelling") {
+    // Check townhouse before house, since "townhouse" contains "house"
+    if lower.contains("townhouse") || lower.contains("terrace") {
+        crate::ingestion::types::PropertyType::Townhouse
+    } else if lower.contains("house") || lower.contains("dwelling") {
         crate::ingestion::types::PropertyType::House
     } else if lower.contains("unit") || lower.contains("apartment") || lower.contains("flat") {
         crate::ingestion::types::PropertyType::Unit
-    } else if lower.contains("townhouse") || lower.contains("terrace") {
-        crate::ingestion::types::PropertyType::Townhouse
     } else if lower.contains("vacant") || lower.contains("land") {
         crate::ingestion::types::PropertyType::VacantLand
-    } else if lower.contains("commercial") || lower.contains("retail") || lower.contains("office")
-    {
+    } else if lower.contains("commercial") || lower.contains("retail") || lower.contains("office") {
         crate::ingestion::types::PropertyType::Commercial
     } else {
         crate::ingestion::types::PropertyType::Other
     }
 }
 
+/// Postcodes in use in each state, PO boxes and large users included; NSW
+/// takes in the 26xx and 29xx ranges it shares with the ACT along the border
+fn postcode_ranges(state: State) -> &'static [RangeInclusive<u16>] {
+    match state {
+        State::NSW => &[1000..=2999],
+        State::ACT => &[200..=299, 2600..=2618, 2900..=2920],
+        State::VIC => &[3000..=3999, 8000..=8999],
+        State::QLD => &[4000..=4999, 9000..=9999],
+        State::SA => &[5000..=5999],
+        State::WA => &[6000..=6999],
+        State::TAS => &[7000..=7999],
+        State::NT => &[800..=999],
+    }
+}
+
+/// A postcode as its four digits, or None when it isn't one of `state`'s
+/// "800" is "0800", its leading zero lost to a spreadsheet, and "2000.0" is
+/// "2000"; "UNKNOWN", "20000" and a VIC postcode on an NSW sale are None
+pub fn normalize_postcode(postcode: &str, state: State) -> Option<String> {
+    let postcode = postcode.trim();
+    let digits = match postcode.split_once('.') {
+        Some((whole, fraction)) if fraction.bytes().all(|b| b == b'0') => whole,
+        Some(_) => return None,
+        None => postcode,
+    };
+    if !(1..=4).contains(&digits.len()) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let number: u16 = digits.parse().ok()?;
+    postcode_ranges(state)
+        .iter()
+        .any(|range| range.contains(&number))
+        .then(|| format!("{:04}", number))
+}
+
 /// Format NSW address from components
+/// A unit goes before the house number with a slash: "2/10 Smith Street"
+/// The result is normalized as by `normalize_address`, so equal addresses
+/// compare equal
 pub fn format_nsw_address(
     unit: Option<&str>,
     house_number: Option<&str>,
     street_name: &str,
 ) -> String {
-    let mut parts = Vec::new();
+    let stray = |c: char| !c.is_alphanumeric();
+    let unit = unit
+        .map(|u| u.trim_matches(stray))
+        .filter(|u| !u.is_empty());
+    let house_number = house_number
+        .map(|h| h.trim_matches(stray))
+        .filter(|h| !h.is_empty());
+
+    let number = match (unit, house_number) {
+        (Some(u), Some(h)) => Some(format!("{}/{}", u, h)),
+        (Some(n), None) | (None, Some(n)) => Some(n.to_string()),
+        (None, None) => None,
+    };
+
+    let address = number
+        .iter()
+        .map(String::as_str)
+        .chain(street_name.split_whitespace())
+        .collect::<Vec<_>>()
+        .join(" ");
+    normalize_address(&address)
+}
+
+/// Words naming a unit ahead of its number: "Unit 2, 10 Smith Street"
+const UNIT_WORDS: &[&str] = &[
+    "unit",
+    "u",
+    "apartment",
+    "apt",
+    "flat",
+    "suite",
+    "villa",
+    "townhouse",
+    "shop",
+];
+
+/// Street type abbreviations and what they stand for
+const STREET_TYPES: &[(&str, &str)] = &[
+    ("st", "Street"),
+    ("rd", "Road"),
+    ("ave", "Avenue"),
+    ("av", "Avenue"),
+    ("dr", "Drive"),
+    ("pde", "Parade"),
+    ("pl", "Place"),
+    ("cres", "Crescent"),
+    ("cr", "Crescent"),
+    ("ct", "Court"),
+    ("cct", "Circuit"),
+    ("cl", "Close"),
+    ("tce", "Terrace"),
+    ("hwy", "Highway"),
+    ("blvd", "Boulevard"),
+    ("bvd", "Boulevard"),
+    ("ln", "Lane"),
+    ("gr", "Grove"),
+    ("sq", "Square"),
+    ("esp", "Esplanade"),
+];
+
+/// Directions that can follow a street type: "Smith Street North"
+const STREET_SUFFIXES: &[&str] = &["north", "south", "east", "west"];
+
+/// The one form of an address, however a source wrote it
+/// "Unit 2, 10 Smith St" and "2 / 10 Smith Street." both become
+/// "2/10 Smith Street": a unit word gives way to the slash, commas and stray
+/// punctuation go, and an abbreviated street type is spelled out, in capitals
+/// if it was written in them. "Lot 12 Boundary Rd" keeps its lot.
+pub fn normalize_address(address: &str) -> String {
+    let address = address.replace(',', " ");
+    let slashed = address
+        .split('/')
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut tokens: Vec<String> = slashed
+        .split_whitespace()
+        .map(|token| {
+            token
+                .trim_matches(|c: char| !c.is_alphanumeric())
+                .to_string()
+        })
+        .filter(|token| !token.is_empty())
+        .collect();
+
+    // "Unit 2 10 Smith Street" and "Unit 2/10 Smith Street" as "2/10 Smith Street"
+    if let [word, unit, rest @ ..] = tokens.as_slice() {
+        let is_unit_word = UNIT_WORDS.contains(&word.to_lowercase().as_str())
+            && unit.chars().any(|c| c.is_ascii_digit());
+        if is_unit_word && unit.contains('/') && !rest.is_empty() {
+            tokens.remove(0);
+        } else if is_unit_word && rest.len() > 1 && is_house_number(&rest[0]) {
+            let number = format!("{}/{}", unit, rest[0]);
+            tokens.splice(..3, [number]);
+        }
+    }
+
+    // The street type comes last, or ahead of a direction
+    let mut i = tokens.len().saturating_sub(1);
+    if tokens.len() > 2 && STREET_SUFFIXES.contains(&tokens[i].to_lowercase().as_str()) {
+        i -= 1;
+    }
+    if i > 0 {
+        let word = &tokens[i];
+        let lower = word.to_lowercase();
+        if let Some((_, full)) = STREET_TYPES.iter().find(|(short, _)| *short == lower) {
+            tokens[i] = match word.chars().all(|c| c.is_ascii_uppercase()) {
+                true => full.to_uppercase(),
+                false => full.to_string(),
+            };
+        }
+    }
+
+    tokens.join(" ")
+}
+
+/// The one form of a suburb name: "  Surry  hills " is "SURRY HILLS"
+pub fn normalize_suburb(suburb: &str) -> String {
+    suburb
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+/// Digits with at most one trailing letter, or a range of two of them:
+/// "10", "5B", "2-6"
+pub fn is_house_number(token: &str) -> bool {
+    let single = |part: &str| {
+        let digits = part.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        !digits.is_empty()
+            && digits.chars().all(|c| c.is_ascii_digit())
+            && part.len() - digits.len() <= 1
+    };
+    match token.split_once('-') {
+        Some((from, to)) => single(from) && single(to),
+        None => single(token),
+    }
+}
+
+/// Parse property type from WA Landgate "Land Use" and strata indicator
+/// A strata lot is a unit unless it is a townhouse or villa; vacant land and
+/// commercial uses win over the strata indicator
+pub fn parse_wa_property_type(
+    land_use: &str,
+    strata: bool,
+) -> crate::ingestion::types::PropertyType {
+    use crate::ingestion::types::PropertyType;
+
+    let lower = land_use.to_lowercase();
+    if lower.contains("vacant") {
+        PropertyType::VacantLand
+    } else if lower.contains("commercial")
+        || lower.contains("retail")
+        || lower.contains("industrial")
+    {
+        PropertyType::Commercial
+    } else if lower.contains("townhouse") || lower.contains("villa") {
+        PropertyType::Townhouse
+    } else if strata {
+        PropertyType::Unit
+    } else if lower.is_empty() || lower.contains("residential") || lower.contains("house") {
+        PropertyType::House
+    } else {
+        parse_nsw_property_type(&lower)
+    }
+}
 
-    if let Some(u) = unit {
-        if !u.trim().is_empty() {
-            parts.push(u.trim().to_string());
+/// Lot and plan numbers Landgate puts in WA address fields
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LotPlan {
+    pub lot: Option<String>,
+    pub plan: Option<String>,
+}
+
+impl LotPlan {
+    /// "wa_lot:502:30012" for lot 502 on plan 30012; None unless both are known,
+    /// since a lot number alone repeats from plan to plan
+    pub fn external_id(&self) -> Option<String> {
+        match (&self.lot, &self.plan) {
+            (Some(lot), Some(plan)) => Some(format!("wa_lot:{}:{}", lot, plan)),
+            _ => None,
         }
     }
+}
+
+/// Format WA address from components, taking out lot and plan numbers
+/// ("Lot 502", "on Deposited Plan 30012", "SP 4411") wherever they appear;
+/// otherwise as `format_nsw_address`
+pub fn format_wa_address(
+    unit: Option<&str>,
+    house_number: Option<&str>,
+    street_name: &str,
+) -> (String, LotPlan) {
+    let mut lot_plan = LotPlan::default();
+    let house_number = house_number.map(|h| strip_lot_plan(h, &mut lot_plan));
+    let street_name = strip_lot_plan(street_name, &mut lot_plan);
+    let address = format_nsw_address(unit, house_number.as_deref(), &street_name);
+    (address, lot_plan)
+}
 
-    if let Some(h) = house_number {
-        if !h.trim().is_empty() {
-            parts.push(h.trim().to_string());
+/// `field` without its lot and plan numbers, which go into `lot_plan`
+fn strip_lot_plan(field: &str, lot_plan: &mut LotPlan) -> String {
+    let words: Vec<&str> = field.split_whitespace().collect();
+    let number = |i: usize| {
+        words
+            .get(i)
+            .map(|w| w.trim_end_matches(','))
+            .filter(|w| w.chars().any(|c| c.is_ascii_digit()))
+            .map(str::to_string)
+    };
+
+    let mut kept = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        if words[i].eq_ignore_ascii_case("lot") {
+            if let Some(lot) = number(i + 1) {
+                lot_plan.lot = Some(lot);
+                i += 2;
+                continue;
+            }
+        }
+        // "on" only joins a lot to its plan
+        let start = if words[i].eq_ignore_ascii_case("on") {
+            i + 1
+        } else {
+            i
+        };
+        let keywords = plan_keywords(&words[start.min(words.len())..]);
+        if keywords > 0 {
+            if let Some(plan) = number(start + keywords) {
+                lot_plan.plan = Some(plan);
+                i = start + keywords + 1;
+                continue;
+            }
         }
+        kept.push(words[i]);
+        i += 1;
+    }
+    kept.join(" ")
+}
+
+/// How many of the leading `words` name a plan: "Plan", "DP", "Deposited Plan",
+/// "Survey Strata Plan" and the like; 0 when they don't
+fn plan_keywords(words: &[&str]) -> usize {
+    let upper: Vec<String> = words
+        .iter()
+        .take(3)
+        .map(|w| w.to_ascii_uppercase())
+        .collect();
+    let upper: Vec<&str> = upper.iter().map(String::as_str).collect();
+    match upper.as_slice() {
+        ["SURVEY", "STRATA", "PLAN", ..] => 3,
+        ["DEPOSITED" | "STRATA" | "SURVEY", "PLAN", ..] => 2,
+        ["PLAN" | "DP" | "SP" | "SSP", ..] => 1,
+        _ => 0,
     }
+}
+
+/// Parse property type from a Domain listing's "propertyType"
+/// Domain runs words together ("ApartmentUnitFlat", "SemiDetached"); types
+/// it adds later fall back to Other
+pub fn parse_domain_property_type(property_type: &str) -> crate::ingestion::types::PropertyType {
+    use crate::ingestion::types::PropertyType;
+
+    let lower = property_type.to_lowercase();
+    if lower.contains("townhouse") || lower.contains("villa") || lower.contains("terrace") {
+        PropertyType::Townhouse
+    } else if lower.contains("house") || lower.contains("duplex") || lower.contains("semi") {
+        PropertyType::House
+    } else if lower.contains("unit")
+        || lower.contains("apartment")
+        || lower.contains("studio")
+        || lower.contains("penthouse")
+    {
+        PropertyType::Unit
+    } else if lower.contains("land") {
+        PropertyType::VacantLand
+    } else {
+        PropertyType::Other
+    }
+}
+
+/// Dearest sale a price column may hold before it's flagged as a likely data
+/// error, such as two figures run together
+pub const MAX_PLAUSIBLE_PRICE: i64 = 500_000_000;
+
+/// Whether a price is over `MAX_PLAUSIBLE_PRICE`. Such a price is kept, as a
+/// handful of real sales run that high, but warned about for a person to check.
+pub fn is_implausible_price(price: i64) -> bool {
+    price > MAX_PLAUSIBLE_PRICE
+}
 
-    parts.push(street_name.trim().to_string());
+/// What sources put in a price column when there is no price to give
+const NO_PRICE_MARKERS: &[&str] = &[
+    "-",
+    "--",
+    "n/a",
+    "na",
+    "nil",
+    "null",
+    "none",
+    "poa",
+    "undisclosed",
+];
 
-    parts.join(" ")
+/// A price column that holds something other than a price
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PriceParseError {
+    #[error("not a price: {0:?}")]
+    Invalid(String),
+    #[error("negative price: {0:?}")]
+    Negative(String),
+}
+
+/// The whole dollars in a price column: "$750,000", " 750 000 ",
+/// "750000.00" (cents are dropped) or "$1.2m". Ok(None) when it's blank or a
+/// marker like "N/A" or "POA", and an error when it's anything else,
+/// including a figure too large to hold. A price over `MAX_PLAUSIBLE_PRICE`
+/// is still returned; see `is_implausible_price`.
+pub fn parse_price(text: &str) -> Result<Option<i64>, PriceParseError> {
+    use rust_decimal::prelude::ToPrimitive;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    let text = text.trim();
+    if text.is_empty()
+        || NO_PRICE_MARKERS
+            .iter()
+            .any(|m| text.eq_ignore_ascii_case(m))
+    {
+        return Ok(None);
+    }
+    let invalid = || PriceParseError::Invalid(text.to_string());
+
+    let cleaned: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '$')
+        .collect::<String>()
+        .to_lowercase();
+    let (negative, cleaned) = match cleaned.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, cleaned.as_str()),
+    };
+    let (number, multiplier) = if let Some(n) = cleaned.strip_suffix("million") {
+        (n, 1_000_000)
+    } else if let Some(n) = cleaned.strip_suffix('m') {
+        (n, 1_000_000)
+    } else if let Some(n) = cleaned.strip_suffix('k') {
+        (n, 1_000)
+    } else {
+        (cleaned, 1)
+    };
+    // "1,2m" writes 1.2 million with a decimal comma; elsewhere commas group
+    // thousands
+    let number = match number.split_once(',') {
+        Some((whole, fraction))
+            if multiplier > 1 && (1..=2).contains(&fraction.len()) && !fraction.contains(',') =>
+        {
+            format!("{}.{}", whole, fraction)
+        }
+        _ => number.replace(',', ""),
+    };
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        return Err(invalid());
+    }
+
+    let amount = Decimal::from_str(&number)
+        .ok()
+        .and_then(|n| n.checked_mul(Decimal::from(multiplier)))
+        .ok_or_else(invalid)?;
+    if negative && !amount.is_zero() {
+        return Err(PriceParseError::Negative(text.to_string()));
+    }
+    amount.trunc().to_i64().map(Some).ok_or_else(invalid)
+}
+
+/// A price read from a listing's display text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AskingPrice {
+    /// A single figure: "$850,000", "Price guide $1.2m"
+    Exact(i32),
+    /// The middle of a range: "$1.1m - $1.2m"
+    Midpoint(i32),
+    /// A floor the price may well be above: "Offers over $1.2m", "From $650k"
+    LowerBound(i32),
+}
+
+impl AskingPrice {
+    pub fn amount(self) -> i32 {
+        match self {
+            AskingPrice::Exact(amount)
+            | AskingPrice::Midpoint(amount)
+            | AskingPrice::LowerBound(amount) => amount,
+        }
+    }
+}
+
+/// The asking price in a listing's display text, e.g. "$850,000",
+/// "Offers over $1.2m" or "$600 - $650 per week"
+/// None when the text gives no dollar figure ("Contact agent", "Auction") or
+/// only a ceiling ("Under $900k")
+pub fn parse_asking_price(display: &str) -> Option<AskingPrice> {
+    let lower = display.to_lowercase();
+    let amounts = dollar_amounts(&lower);
+    let amount = |value: f64| i32::try_from(value.round() as i64).ok();
+
+    match amounts.as_slice() {
+        [] => None,
+        [low, high, ..] => amount((low + high) / 2.0).map(AskingPrice::Midpoint),
+        [value] => {
+            let has = |words: &[&str]| words.iter().any(|w| lower.contains(w));
+            if has(&["under", "below", "up to", "less than"]) {
+                None
+            } else if has(&["over", "above", "from", "excess", "more than", "+"]) {
+                amount(*value).map(AskingPrice::LowerBound)
+            } else {
+                amount(*value).map(AskingPrice::Exact)
+            }
+        }
+    }
+}
+
+/// How often the rent in a rental listing's display text is paid; weekly
+/// unless it says otherwise ("$2,800 per month", "$3,000 pcm")
+pub fn parse_rent_frequency(display: &str) -> crate::RentFrequency {
+    use crate::RentFrequency;
+
+    let lower = display.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '.')
+        .filter(|w| !w.is_empty())
+        .collect();
+    let has = |names: &[&str]| words.iter().any(|w| names.contains(w));
+    if has(&["fortnight", "fortnightly", "pf"]) {
+        RentFrequency::Fortnightly
+    } else if has(&["month", "monthly", "pcm", "pm"]) {
+        RentFrequency::Monthly
+    } else if has(&["annum", "annually", "year", "yearly", "pa", "p.a."]) {
+        RentFrequency::Annual
+    } else {
+        RentFrequency::Weekly
+    }
+}
+
+/// Every "$" figure in lowercased text, with "k" and "m" (or "million")
+/// multiplied out: "$1.1m - $1,250,000" gives 1_100_000 and 1_250_000
+fn dollar_amounts(text: &str) -> Vec<f64> {
+    let mut amounts = Vec::new();
+    for (i, _) in text.match_indices('$') {
+        let rest = text[i + 1..].trim_start();
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != ',' && c != '.')
+            .unwrap_or(rest.len());
+        let Ok(value) = rest[..end]
+            .trim_end_matches('.')
+            .replace(',', "")
+            .parse::<f64>()
+        else {
+            continue;
+        };
+        let suffix = rest[end..].trim_start();
+        let multiplier = if suffix.starts_with("mil") || suffix.starts_with('m') {
+            1_000_000.0
+        } else if suffix.starts_with('k') {
+            1_000.0
+        } else {
+            1.0
+        };
+        amounts.push(value * multiplier);
+    }
+    amounts
+}
+
+/// Deserialize the elements of a JSON array one at a time, without building a
+/// `serde_json::Value` tree; only the element being read is ever in memory
+///
+/// Fails up front if `bytes` isn't an array. An element that doesn't
+/// deserialize into `T` yields an error and the stream moves on to the next
+/// one; if the array itself is cut short or unbalanced, the error ends it.
+pub fn parse_json_array_stream<T: DeserializeOwned>(
+    bytes: &[u8],
+) -> Result<JsonArrayStream<'_, T>> {
+    let start = skip_json_whitespace(bytes, 0);
+    if bytes.get(start) != Some(&b'[') {
+        anyhow::bail!("Expected a JSON array");
+    }
+
+    let mut stream = JsonArrayStream {
+        bytes,
+        pos: start + 1,
+        index: 0,
+        done: false,
+        _record: PhantomData,
+    };
+    let first = skip_json_whitespace(bytes, stream.pos);
+    if bytes.get(first) == Some(&b']') {
+        stream.done = true;
+    }
+    Ok(stream)
+}
+
+/// Iterator returned by `parse_json_array_stream`
+pub struct JsonArrayStream<'a, T> {
+    bytes: &'a [u8],
+    /// Start of the next element, or of the whitespace before it
+    pos: usize,
+    /// Index of the next element, for error messages
+    index: usize,
+    done: bool,
+    _record: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> JsonArrayStream<'_, T> {
+    /// Step over the `,` or `]` after an element ending at `end`
+    fn advance_past(&mut self, end: usize) -> Result<()> {
+        let next = skip_json_whitespace(self.bytes, end);
+        self.index += 1;
+        match self.bytes.get(next) {
+            Some(b',') => {
+                self.pos = next + 1;
+                Ok(())
+            }
+            Some(b']') => {
+                self.done = true;
+                Ok(())
+            }
+            _ => {
+                self.done = true;
+                Err(anyhow::anyhow!(
+                    "JSON array element {} isn't followed by ',' or ']'",
+                    self.index - 1
+                ))
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for JsonArrayStream<'_, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let index = self.index;
+        let mut element =
+            serde_json::Deserializer::from_slice(&self.bytes[self.pos..]).into_iter::<T>();
+        let result = match element.next() {
+            Some(Ok(record)) => {
+                let end = self.pos + element.byte_offset();
+                self.advance_past(end).map(|_| record)
+            }
+            Some(Err(e)) => {
+                let error = anyhow::anyhow!("JSON array element {}: {}", index, e);
+                // Skip to the element's end by bracket matching, so one bad
+                // record doesn't lose the rest of the page
+                match skip_json_element(self.bytes, self.pos) {
+                    Some(end) => self.advance_past(end).and(Err(error)),
+                    None => {
+                        self.done = true;
+                        Err(error)
+                    }
+                }
+            }
+            None => {
+                self.done = true;
+                Err(anyhow::anyhow!("JSON array ends after {} elements", index))
+            }
+        };
+        Some(result)
+    }
+}
+
+fn skip_json_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+        pos += 1;
+    }
+    pos
+}
+
+/// End of the element starting at `pos`: the first `,` or `]` outside any
+/// string or nested brackets. None if the array never closes.
+fn skip_json_element(bytes: &[u8], pos: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, &byte) in bytes[pos..].iter().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => depth += 1,
+            b',' | b']' if depth == 0 => return Some(pos + offset),
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ingestion::object_store::ObjectScheme;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_parse_property_type() {
@@ -124,6 +1497,524 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_backoff_doubles() {
+        let policy = HttpPolicy {
+            initial_backoff: Duration::from_millis(500),
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+    }
+
+    /// Answers /echo with the request's Host and User-Agent, /slow after two
+    /// seconds, /chunked with four chunks and no Content-Length and /sized with
+    /// a Content-Length
+    async fn echo_server() -> String {
+        use axum::body::Body;
+        use axum::http::HeaderMap;
+        use axum::{routing::get, Router};
+
+        let app = Router::new()
+            .route(
+                "/echo",
+                get(|headers: HeaderMap| async move {
+                    let header = |name: &str| {
+                        headers
+                            .get(name)
+                            .and_then(|v| v.to_str().ok())
+                            .unwrap_or_default()
+                            .to_string()
+                    };
+                    format!("{}|{}", header("host"), header("user-agent"))
+                }),
+            )
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    "late"
+                }),
+            )
+            .route(
+                "/chunked",
+                get(|| async {
+                    let chunks = (0..4).map(|_| Ok::<_, io::Error>(vec![b'x'; 1000]));
+                    Body::from_stream(futures_util::stream::iter(chunks))
+                }),
+            )
+            .route("/sized", get(|| async { vec![b'x'; 64 * 1024] }));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", addr)
+    }
+
+    fn fetcher(config: HttpConfig) -> Fetcher {
+        let policy = HttpPolicy {
+            max_retries: 0,
+            ..Default::default()
+        };
+        Fetcher::new(&config, policy).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fetcher_sends_user_agent() {
+        let base = echo_server().await;
+        let named = fetcher(HttpConfig {
+            user_agent: "realtor-test/1.0 (ops@example.com)".to_string(),
+            ..Default::default()
+        });
+
+        let body = named.get(&format!("{}/echo", base)).await.unwrap();
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            format!(
+                "{}|realtor-test/1.0 (ops@example.com)",
+                base.trim_start_matches("http://")
+            )
+        );
+
+        // The default names the crate
+        let body = fetcher(HttpConfig::default())
+            .get(&format!("{}/echo", base))
+            .await
+            .unwrap();
+        let body = String::from_utf8(body).unwrap();
+        let default_agent = concat!("|real-estate-backend/", env!("CARGO_PKG_VERSION"));
+        assert!(body.ends_with(default_agent), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_fetcher_goes_through_proxy() {
+        let proxy = echo_server().await;
+        let fetcher = fetcher(HttpConfig {
+            proxy_url: Some(proxy),
+            ..Default::default()
+        });
+
+        // The host doesn't resolve, so only the proxy can have answered
+        let body = fetcher.get("http://mirror.invalid/echo").await.unwrap();
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.starts_with("mirror.invalid|"), "{}", body);
+
+        let bad = HttpConfig {
+            proxy_url: Some("not a proxy".to_string()),
+            ..Default::default()
+        };
+        assert!(bad.client().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_covers_the_whole_request() {
+        let base = echo_server().await;
+        let fetcher = fetcher(HttpConfig {
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        });
+
+        // Connecting is instant; waiting on the response is what times out
+        let error = fetcher.get(&format!("{}/slow", base)).await.unwrap_err();
+        let timed_out = error
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_timeout());
+        assert!(timed_out, "{}", error);
+    }
+
+    async fn download_calls(url: &str) -> (Vec<(u64, Option<u64>)>, Vec<u8>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let fetcher = fetcher(HttpConfig::default())
+            .with_progress(move |done, total| recorded.lock().unwrap().push((done, total)));
+
+        let body = fetcher.get(url).await.unwrap();
+        let calls = calls.lock().unwrap().clone();
+        (calls, body)
+    }
+
+    #[tokio::test]
+    async fn test_download_reports_progress() {
+        let base = echo_server().await;
+
+        let (calls, body) = download_calls(&format!("{}/chunked", base)).await;
+        assert_eq!(body.len(), 4000);
+        assert!(!calls.is_empty());
+        // No Content-Length, so bytes only, always going up to the whole body
+        assert!(
+            calls.iter().all(|(_, total)| total.is_none()),
+            "{:?}",
+            calls
+        );
+        assert!(calls.windows(2).all(|w| w[0].0 < w[1].0), "{:?}", calls);
+        assert_eq!(calls.last(), Some(&(4000, None)));
+
+        let (calls, body) = download_calls(&format!("{}/sized", base)).await;
+        assert_eq!(body.len(), 64 * 1024);
+        assert!(calls.iter().all(|(_, total)| *total == Some(64 * 1024)));
+        assert_eq!(calls.last().map(|c| c.0), Some(64 * 1024));
+    }
+
+    fn blob() -> Vec<u8> {
+        (0..100_000u32).map(|n| (n * 7 % 251) as u8).collect()
+    }
+
+    /// Serves blob() at /ranged honouring Range and If-Range, at /plain
+    /// ignoring Range, and at /flaky cutting off the first answer for each
+    /// range not starting at 0; returns every Range header received
+    async fn range_server() -> (String, Arc<Mutex<Vec<String>>>) {
+        use axum::body::Body;
+        use axum::extract::Path as UrlPath;
+        use axum::http::HeaderMap;
+        use axum::response::Response as AxumResponse;
+        use axum::{routing::get, Router};
+        use std::collections::HashSet;
+
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let cut = Arc::new(Mutex::new(HashSet::new()));
+        let seen = ranges.clone();
+
+        let app = Router::new().route(
+            "/:mode",
+            get(move |UrlPath(mode): UrlPath<String>, headers: HeaderMap| {
+                let seen = seen.clone();
+                let cut = cut.clone();
+                async move {
+                    let blob = blob();
+                    let header = |name: &str| headers.get(name).map(|v| v.to_str().unwrap());
+                    let range = header("range").filter(|_| mode != "plain");
+                    let fresh = header("if-range").is_none_or(|v| v == "\"blob\"");
+                    let Some(range) = range.filter(|_| fresh) else {
+                        return AxumResponse::builder()
+                            .header("etag", "\"blob\"")
+                            .body(Body::from(blob))
+                            .unwrap();
+                    };
+                    seen.lock().unwrap().push(range.to_string());
+
+                    let (start, end) = range
+                        .strip_prefix("bytes=")
+                        .and_then(|r| r.split_once('-'))
+                        .map(|(s, e)| (s.parse::<usize>().unwrap(), e.parse::<usize>().unwrap()))
+                        .unwrap();
+                    let part = blob[start..=end].to_vec();
+                    let builder = AxumResponse::builder()
+                        .status(206)
+                        .header("etag", "\"blob\"")
+                        .header(
+                            "content-range",
+                            format!("bytes {}-{}/{}", start, end, blob.len()),
+                        );
+
+                    if mode == "flaky" && start > 0 && cut.lock().unwrap().insert(start) {
+                        let half = part[..part.len() / 2].to_vec();
+                        let chunks = vec![Ok(half), Err(io::Error::other("cut off"))];
+                        return builder
+                            .body(Body::from_stream(futures_util::stream::iter(chunks)))
+                            .unwrap();
+                    }
+                    builder.body(Body::from(part)).unwrap()
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{}", addr), ranges)
+    }
+
+    fn segmented_fetcher(segments: usize) -> Fetcher {
+        let policy = HttpPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(10),
+            ..Default::default()
+        };
+        Fetcher::new(&HttpConfig::default(), policy)
+            .unwrap()
+            .with_segments(segments)
+    }
+
+    async fn download_file(fetcher: &Fetcher, url: &str, path: &Path) -> (Option<u64>, u64) {
+        let request = fetcher.client.get(url);
+        match fetcher.download_to_file(request, url, path).await.unwrap() {
+            FileDownload::Saved {
+                expected,
+                downloaded,
+                ..
+            } => (expected, downloaded),
+            FileDownload::NotModified => panic!("Unconditional request got 304"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_segmented_download_assembles_ranges() {
+        let (base, ranges) = range_server().await;
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("ranged.zip");
+
+        let url = format!("{}/ranged", base);
+        let sizes = download_file(&segmented_fetcher(4), &url, &path).await;
+        assert_eq!(sizes, (Some(100_000), 100_000));
+        assert_eq!(fs::read(&path).unwrap(), blob());
+        let mut seen = ranges.lock().unwrap().clone();
+        seen.sort();
+        assert_eq!(
+            seen,
+            [
+                "bytes=0-0",
+                "bytes=0-24999",
+                "bytes=25000-49999",
+                "bytes=50000-74999",
+                "bytes=75000-99999",
+            ]
+        );
+
+        // One stream when segmenting is off
+        ranges.lock().unwrap().clear();
+        let sizes = download_file(&segmented_fetcher(1), &url, &path).await;
+        assert_eq!(sizes, (Some(100_000), 100_000));
+        assert_eq!(fs::read(&path).unwrap(), blob());
+        assert!(ranges.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_segmented_download_falls_back_without_ranges() {
+        let (base, _) = range_server().await;
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("plain.zip");
+
+        let url = format!("{}/plain", base);
+        let sizes = download_file(&segmented_fetcher(4), &url, &path).await;
+        assert_eq!(sizes, (Some(100_000), 100_000));
+        assert_eq!(fs::read(&path).unwrap(), blob());
+    }
+
+    #[tokio::test]
+    async fn test_failed_segment_resumes_alone() {
+        let (base, ranges) = range_server().await;
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("flaky.zip");
+
+        let url = format!("{}/flaky", base);
+        let sizes = download_file(&segmented_fetcher(4), &url, &path).await;
+        assert_eq!(sizes, (Some(100_000), 100_000));
+        assert_eq!(fs::read(&path).unwrap(), blob());
+
+        // The probe, four segments and one retry for each cut-off segment;
+        // the first segment was never cut off, so never asked for again
+        let seen = ranges.lock().unwrap().clone();
+        assert_eq!(seen.len(), 8, "{:?}", seen);
+        let first = seen.iter().filter(|r| *r == "bytes=0-24999").count();
+        assert_eq!(first, 1, "{:?}", seen);
+        for end in [49_999, 74_999, 99_999] {
+            let suffix = format!("-{}", end);
+            let tries = seen.iter().filter(|r| r.ends_with(&suffix)).count();
+            assert_eq!(tries, 2, "{:?}", seen);
+        }
+
+        // Out of retries
+        let impatient = Fetcher::new(
+            &HttpConfig::default(),
+            HttpPolicy {
+                max_retries: 0,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .with_segments(2);
+        let (base, _) = range_server().await;
+        let url = format!("{}/flaky", base);
+        let error = impatient
+            .download_to_file(impatient.client.get(&url), &url, &path)
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Segment 50000-99999 failed");
+    }
+
+    #[test]
+    fn test_split_ranges_and_content_range() {
+        assert_eq!(split_ranges(10, 4), vec![(0, 3), (3, 6), (6, 9), (9, 10)]);
+        assert_eq!(split_ranges(2, 4), vec![(0, 1), (1, 2)]);
+        assert_eq!(split_ranges(0, 4), vec![]);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(content_range_total(&headers), None);
+        headers.insert(CONTENT_RANGE, HeaderValue::from_static("bytes 0-0/1234"));
+        assert_eq!(content_range_total(&headers), Some(1234));
+        headers.insert(CONTENT_RANGE, HeaderValue::from_static("bytes 0-0/*"));
+        assert_eq!(content_range_total(&headers), None);
+    }
+
+    #[test]
+    fn test_progress_logged_by_time_or_bytes() {
+        let start = Instant::now();
+        let mut progress = DownloadProgress::new(Some(100 * 1024 * 1024), start);
+
+        assert!(!progress.advance(1024 * 1024, start + Duration::from_secs(1)));
+        // Five seconds since the start
+        assert!(progress.advance(1024 * 1024, start + PROGRESS_LOG_INTERVAL));
+        assert_eq!(progress.describe(), "Downloaded 2.0 of 100.0 MB (2%)");
+        assert!(!progress.advance(1024 * 1024, start + Duration::from_secs(6)));
+        // A fast link logs every PROGRESS_LOG_BYTES instead
+        assert!(progress.advance(PROGRESS_LOG_BYTES, start + Duration::from_secs(7)));
+        assert!(!progress.advance(1, start + Duration::from_secs(8)));
+
+        let mut unsized_progress = DownloadProgress::new(None, start);
+        unsized_progress.advance(3 * 1024 * 1024 / 2, start);
+        assert_eq!(unsized_progress.describe(), "Downloaded 1.5 MB");
+        assert_eq!(throughput(1000, Duration::from_millis(500)), 2000);
+        assert_eq!(throughput(1000, Duration::ZERO), 1000);
+    }
+
+    #[test]
+    fn test_local_source_path() {
+        assert_eq!(
+            local_source_path("file:///data/archive.zip"),
+            Some(PathBuf::from("/data/archive.zip"))
+        );
+        assert_eq!(
+            local_source_path("fixtures/rentals.xlsx"),
+            Some(PathBuf::from("fixtures/rentals.xlsx"))
+        );
+        assert_eq!(
+            local_source_path("https://nswpropertysalesdata.com/data/archive.zip"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_source_url_schemes() {
+        assert_eq!(
+            SourceUrl::parse("file:///data/archive.zip").unwrap(),
+            SourceUrl::Local(PathBuf::from("/data/archive.zip"))
+        );
+        assert_eq!(
+            SourceUrl::parse("gs://nsw-sales/weekly/20240108.zip").unwrap(),
+            SourceUrl::Object(ObjectUrl {
+                scheme: ObjectScheme::Gcs,
+                bucket: "nsw-sales".to_string(),
+                key: "weekly/20240108.zip".to_string(),
+            })
+        );
+        assert_eq!(
+            SourceUrl::parse("https://example.com/archive.zip").unwrap(),
+            SourceUrl::Http("https://example.com/archive.zip".to_string())
+        );
+        assert!(SourceUrl::parse("s3://nsw-sales").is_err());
+    }
+
+    #[cfg(feature = "object-storage")]
+    #[tokio::test]
+    async fn test_fetch_url_presigns_object_sources() {
+        use crate::ingestion::object_store::Credentials;
+        use axum::extract::{Path as UrlPath, RawQuery};
+        use axum::{routing::get, Router};
+
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let seen = queries.clone();
+        let app = Router::new().route(
+            "/:bucket/*key",
+            get(
+                move |UrlPath((bucket, key)): UrlPath<(String, String)>,
+                      RawQuery(query): RawQuery| {
+                    let seen = seen.clone();
+                    async move {
+                        seen.lock().unwrap().push(query.unwrap_or_default());
+                        format!("{}/{}", bucket, key)
+                    }
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut fetcher = fetcher(HttpConfig::default());
+        fetcher.object_store = ObjectStoreConfig {
+            s3_endpoint: Some(format!("http://{}", addr)),
+            s3_credentials: Some(Credentials::new("AKIDEXAMPLE", "secret")),
+            ..Default::default()
+        };
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("archive.zip");
+
+        let url = "s3://nsw-sales/weekly/20240108.zip";
+        match fetch_url(&fetcher, url, &path).await.unwrap() {
+            FileDownload::Saved { downloaded, .. } => assert_eq!(downloaded, 29),
+            FileDownload::NotModified => panic!("Unconditional request got 304"),
+        }
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "nsw-sales/weekly/20240108.zip"
+        );
+        let queries = queries.lock().unwrap().clone();
+        assert_eq!(queries.len(), 1);
+        assert!(
+            queries[0].contains("X-Amz-Credential=AKIDEXAMPLE"),
+            "{}",
+            queries[0]
+        );
+        assert!(queries[0].contains("X-Amz-Signature="), "{}", queries[0]);
+
+        // Signing needs a key; GCS has no metadata fallback here
+        let error = fetch_url(&fetcher, "gs://nsw-sales/weekly.zip", &path)
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("GCS_HMAC_ACCESS_ID"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_extract_skips_entries_outside_the_directory() {
+        use std::io::Write;
+
+        let temp = tempfile::tempdir().unwrap();
+        let zip_path = temp.path().join("sales.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        for name in [
+            "../evil.csv",
+            "/tmp/absolute.csv",
+            "..\\windows.csv",
+            "notes.txt",
+            "2024/district_002.csv",
+            "district_001.CSV",
+            "001_SALES_DATA_NNME_08012024.DAT",
+        ] {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(b"header\nrow\n").unwrap();
+        }
+        zip.finish().unwrap();
+        // Left over from an earlier extraction
+        let output_dir = temp.path().join("sales_csv");
+        fs::create_dir_all(&output_dir).unwrap();
+        fs::write(output_dir.join("stale.csv"), "old").unwrap();
+
+        let paths = extract_sales_files_from_zip(&zip_path).unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                output_dir.join("001_SALES_DATA_NNME_08012024.DAT"),
+                output_dir.join("2024/district_002.csv"),
+                output_dir.join("district_001.CSV"),
+            ]
+        );
+        assert!(!temp.path().join("evil.csv").exists());
+        assert!(!temp.path().join("windows.csv").exists());
+        assert!(!output_dir.join("stale.csv").exists());
+
+        assert_eq!(safe_entry_path("a/b.csv"), Some(PathBuf::from("a/b.csv")));
+        assert_eq!(safe_entry_path("a/../../b.csv"), None);
+        assert_eq!(safe_entry_path(""), None);
+    }
+
     #[test]
     fn test_format_address() {
         assert_eq!(
@@ -133,12 +2024,444 @@ mod tests {
 
         assert_eq!(
             format_nsw_address(Some("2"), Some("10"), "Smith Street"),
-            "2 10 Smith Street"
+            "2/10 Smith Street"
+        );
+
+        assert_eq!(
+            format_nsw_address(Some(" G01 "), Some(""), "  Smith   Street "),
+            "G01 Smith Street"
         );
 
         assert_eq!(
             format_nsw_address(None, None, "Smith Street"),
             "Smith Street"
         );
+
+        assert_eq!(
+            format_nsw_address(Some("#2,"), Some("10,"), "Smith St."),
+            "2/10 Smith Street"
+        );
+
+        assert_eq!(
+            format_nsw_address(Some("G01"), None, "Smith St,"),
+            "G01 Smith Street"
+        );
+    }
+
+    #[test]
+    fn test_normalize_suburb() {
+        assert_eq!(normalize_suburb("Surry Hills"), "SURRY HILLS");
+        assert_eq!(normalize_suburb("  surry\thills "), "SURRY HILLS");
+        assert_eq!(normalize_suburb("ST IVES"), "ST IVES");
+        assert_eq!(normalize_suburb(""), "");
+    }
+
+    #[test]
+    fn test_normalize_address() {
+        let cases = [
+            // Units
+            ("2/10 Smith Street", "2/10 Smith Street"),
+            ("Unit 2, 10 Smith St", "2/10 Smith Street"),
+            ("unit 2 10 Smith St.", "2/10 Smith Street"),
+            ("U2/10 Smith Street", "U2/10 Smith Street"),
+            ("Apt 12A/5 Terrace Lane", "12A/5 Terrace Lane"),
+            ("2 / 10 Smith Street", "2/10 Smith Street"),
+            ("Shop 3, 88 Market St", "3/88 Market Street"),
+            ("4/22 GEORGE ST", "4/22 GEORGE STREET"),
+            // Townhouses and villas
+            ("Townhouse 7, 15-17 Railway Pde", "7/15-17 Railway Parade"),
+            ("Villa 3 21 Oak Ave", "3/21 Oak Avenue"),
+            ("5B Pacific Hwy", "5B Pacific Highway"),
+            // Lots and streets without a number
+            ("Lot 12 Boundary Rd", "Lot 12 Boundary Road"),
+            ("Lot 12, Boundary Road", "Lot 12 Boundary Road"),
+            ("Old Northern Rd", "Old Northern Road"),
+            // Street types only at the end, or ahead of a direction
+            ("10 St Johns Rd", "10 St Johns Road"),
+            ("1 Smith St North", "1 Smith Street North"),
+            ("Flat Rock Rd", "Flat Rock Road"),
+            ("9 The Avenue", "9 The Avenue"),
+            ("  10   Smith Street ", "10 Smith Street"),
+            ("", ""),
+        ];
+        for (raw, normal) in cases {
+            assert_eq!(normalize_address(raw), normal, "{}", raw);
+            assert_eq!(normalize_address(normal), normal, "{}", normal);
+        }
+    }
+
+    #[test]
+    fn test_format_wa_address_strips_lot_plan() {
+        let lot_plan = |lot: &str, plan: Option<&str>| LotPlan {
+            lot: Some(lot.to_string()),
+            plan: plan.map(str::to_string),
+        };
+
+        let (address, found) = format_wa_address(None, Some("Lot 502"), "Hay Street");
+        assert_eq!(address, "Hay Street");
+        assert_eq!(found, lot_plan("502", None));
+        assert_eq!(found.external_id(), None);
+
+        let (address, found) = format_wa_address(
+            Some("4"),
+            Some("12"),
+            "Lot 14 on Deposited Plan 30012 Hay Street",
+        );
+        assert_eq!(address, "4/12 Hay Street");
+        assert_eq!(found.external_id().as_deref(), Some("wa_lot:14:30012"));
+
+        let (address, found) =
+            format_wa_address(None, Some("LOT 7, SSP 4411"), "Survey Strata Plan Road");
+        assert_eq!(address, "Survey Strata Plan Road");
+        assert_eq!(found, lot_plan("7", Some("4411")));
+
+        // Nothing to take out
+        let (address, found) = format_wa_address(None, Some("10"), "Lotus Place");
+        assert_eq!(address, "10 Lotus Place");
+        assert_eq!(found, LotPlan::default());
+    }
+
+    #[test]
+    fn test_parse_wa_property_type() {
+        use crate::ingestion::types::PropertyType;
+
+        assert_eq!(
+            parse_wa_property_type("Residential", false),
+            PropertyType::House
+        );
+        assert_eq!(
+            parse_wa_property_type("Residential", true),
+            PropertyType::Unit
+        );
+        assert_eq!(
+            parse_wa_property_type("Villa", true),
+            PropertyType::Townhouse
+        );
+        assert_eq!(
+            parse_wa_property_type("Vacant Land", true),
+            PropertyType::VacantLand
+        );
+        assert_eq!(
+            parse_wa_property_type("Retail", false),
+            PropertyType::Commercial
+        );
+        assert_eq!(parse_wa_property_type("Rural", false), PropertyType::Other);
+    }
+
+    #[test]
+    fn test_parse_domain_property_type() {
+        use crate::ingestion::types::PropertyType;
+
+        for (domain, expected) in [
+            ("House", PropertyType::House),
+            ("SemiDetached", PropertyType::House),
+            ("ApartmentUnitFlat", PropertyType::Unit),
+            ("Studio", PropertyType::Unit),
+            ("Townhouse", PropertyType::Townhouse),
+            ("Villa", PropertyType::Townhouse),
+            ("VacantLand", PropertyType::VacantLand),
+            ("Acreage", PropertyType::Other),
+        ] {
+            assert_eq!(parse_domain_property_type(domain), expected, "{}", domain);
+        }
+    }
+
+    #[test]
+    fn test_normalize_postcode() {
+        let nsw = |postcode| normalize_postcode(postcode, State::NSW);
+        assert_eq!(nsw("2000"), Some("2000".to_string()));
+        assert_eq!(nsw(" 2000 "), Some("2000".to_string()));
+        assert_eq!(nsw("2000.0"), Some("2000".to_string()));
+        assert_eq!(nsw("2000.00"), Some("2000".to_string()));
+        assert_eq!(nsw("2000.5"), None);
+        assert_eq!(nsw("UNKNOWN"), None);
+        assert_eq!(nsw(""), None);
+        assert_eq!(nsw("20000"), None);
+        assert_eq!(nsw("2O00"), None);
+        assert_eq!(nsw("-2000"), None);
+        assert_eq!(nsw("3000"), None);
+        // Leading zeroes dropped by a spreadsheet go back on
+        assert_eq!(
+            normalize_postcode("800", State::NT),
+            Some("0800".to_string())
+        );
+        assert_eq!(
+            normalize_postcode("800.0", State::NT),
+            Some("0800".to_string())
+        );
+        assert_eq!(
+            normalize_postcode("0200", State::ACT),
+            Some("0200".to_string())
+        );
+        assert_eq!(normalize_postcode("8", State::NT), None);
+
+        // (state, first and last postcode of each range)
+        let ranges: &[(State, &[(&str, &str)])] = &[
+            (State::NSW, &[("1000", "2999")]),
+            (
+                State::ACT,
+                &[("0200", "0299"), ("2600", "2618"), ("2900", "2920")],
+            ),
+            (State::VIC, &[("3000", "3999"), ("8000", "8999")]),
+            (State::QLD, &[("4000", "4999"), ("9000", "9999")]),
+            (State::SA, &[("5000", "5999")]),
+            (State::WA, &[("6000", "6999")]),
+            (State::TAS, &[("7000", "7999")]),
+            (State::NT, &[("0800", "0999")]),
+        ];
+        for (state, ranges) in ranges {
+            for (first, last) in *ranges {
+                for postcode in [first, last] {
+                    assert_eq!(
+                        normalize_postcode(postcode, *state).as_deref(),
+                        Some(*postcode),
+                        "{} {}",
+                        state,
+                        postcode
+                    );
+                }
+                let before = format!("{:04}", first.parse::<u16>().unwrap() - 1);
+                let after = format!("{:04}", last.parse::<u16>().unwrap() + 1);
+                for postcode in [before, after] {
+                    let inside = ranges
+                        .iter()
+                        .any(|(first, last)| (*first..=*last).contains(&postcode.as_str()));
+                    assert_eq!(
+                        normalize_postcode(&postcode, *state).is_some(),
+                        inside,
+                        "{} {}",
+                        state,
+                        postcode
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_price() {
+        use PriceParseError::*;
+        let cases: &[(&str, Result<Option<i64>, PriceParseError>)] = &[
+            ("750000", Ok(Some(750_000))),
+            ("$750,000", Ok(Some(750_000))),
+            (" 750 000 ", Ok(Some(750_000))),
+            ("750000.00", Ok(Some(750_000))),
+            ("$750,000.99", Ok(Some(750_000))),
+            ("$ 1 250 000", Ok(Some(1_250_000))),
+            ("1,2m", Ok(Some(1_200_000))),
+            ("$1.2M", Ok(Some(1_200_000))),
+            ("$1.25 million", Ok(Some(1_250_000))),
+            ("850k", Ok(Some(850_000))),
+            ("0", Ok(Some(0))),
+            ("$499,999,999", Ok(Some(499_999_999))),
+            ("", Ok(None)),
+            ("   ", Ok(None)),
+            ("N/A", Ok(None)),
+            ("-", Ok(None)),
+            ("POA", Ok(None)),
+            ("null", Ok(None)),
+            ("Undisclosed", Ok(None)),
+            ("-500000", Err(Negative("-500000".to_string()))),
+            ("$-1,000", Err(Negative("$-1,000".to_string()))),
+            ("$2,500,000,000", Ok(Some(2_500_000_000))),
+            ("7500001250000", Ok(Some(7_500_001_250_000))),
+            (
+                "7922816251426433759354395033m",
+                Err(Invalid("7922816251426433759354395033m".to_string())),
+            ),
+            (
+                "99999999999999999999",
+                Err(Invalid("99999999999999999999".to_string())),
+            ),
+            ("abc", Err(Invalid("abc".to_string()))),
+            ("$75O,000", Err(Invalid("$75O,000".to_string()))),
+            ("1.234.567", Err(Invalid("1.234.567".to_string()))),
+            ("$", Err(Invalid("$".to_string()))),
+            ("12e5", Err(Invalid("12e5".to_string()))),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(&parse_price(text), expected, "{:?}", text);
+        }
+        assert!(!is_implausible_price(MAX_PLAUSIBLE_PRICE));
+        assert!(is_implausible_price(2_500_000_000));
+        assert_eq!(
+            parse_price("-3").unwrap_err().to_string(),
+            "negative price: \"-3\""
+        );
+    }
+
+    #[test]
+    fn test_parse_asking_price() {
+        use AskingPrice::*;
+
+        for (display, expected) in [
+            ("$850,000", Some(Exact(850_000))),
+            ("Price Guide $1.2m", Some(Exact(1_200_000))),
+            ("$1.25 million", Some(Exact(1_250_000))),
+            ("Offers over $1.2m", Some(LowerBound(1_200_000))),
+            ("From $650k", Some(LowerBound(650_000))),
+            ("$900,000+", Some(LowerBound(900_000))),
+            ("$1.1m - $1.2m", Some(Midpoint(1_150_000))),
+            ("$800,000 to $850,000", Some(Midpoint(825_000))),
+            ("$600 - $650 per week", Some(Midpoint(625))),
+            ("$550 pw", Some(Exact(550))),
+            ("Under $900k", None),
+            ("Contact Agent", None),
+            ("Auction 15 March", None),
+            ("$", None),
+            ("", None),
+        ] {
+            assert_eq!(parse_asking_price(display), expected, "{}", display);
+        }
+    }
+
+    #[test]
+    fn test_parse_rent_frequency() {
+        use crate::RentFrequency;
+
+        assert_eq!(parse_rent_frequency("$650 per week"), RentFrequency::Weekly);
+        assert_eq!(parse_rent_frequency("$650"), RentFrequency::Weekly);
+        assert_eq!(
+            parse_rent_frequency("$1,300 per fortnight"),
+            RentFrequency::Fortnightly
+        );
+        assert_eq!(parse_rent_frequency("$2,800 pcm"), RentFrequency::Monthly);
+        assert_eq!(parse_rent_frequency("$2,800/month"), RentFrequency::Monthly);
+        assert_eq!(parse_rent_frequency("$36,000 p.a."), RentFrequency::Annual);
+    }
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Sale {
+        id: u64,
+        street: String,
+    }
+
+    #[test]
+    fn test_json_array_stream_reads_elements_in_order() {
+        let json = br#" [ {"id": 1, "street": "Smith St"},
+            {"id": 2, "street": "George St, \"upper\" [end]", "extra": [1, {"a": 2}]} ] "#;
+        let sales: Vec<Sale> = parse_json_array_stream(json)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(
+            sales,
+            [
+                Sale {
+                    id: 1,
+                    street: "Smith St".to_string()
+                },
+                Sale {
+                    id: 2,
+                    street: "George St, \"upper\" [end]".to_string()
+                },
+            ]
+        );
+
+        assert_eq!(parse_json_array_stream::<Sale>(b"[]").unwrap().count(), 0);
+        assert!(parse_json_array_stream::<Sale>(br#"{"records": []}"#).is_err());
+        assert!(parse_json_array_stream::<Sale>(b"").is_err());
+    }
+
+    #[test]
+    fn test_json_array_stream_skips_bad_elements() {
+        let json = br#"[
+            {"id": 1, "street": "A St"},
+            {"id": "two", "street": "B St"},
+            {"id": 3, "street": "C St",, },
+            {"id": 4, "street": "D St"}
+        ]"#;
+        let results: Vec<Result<Sale>> = parse_json_array_stream(json).unwrap().collect();
+
+        let ids: Vec<Option<u64>> = results
+            .iter()
+            .map(|r| r.as_ref().ok().map(|s| s.id))
+            .collect();
+        assert_eq!(ids, [Some(1), None, None, Some(4)]);
+        let error = results[1].as_ref().unwrap_err().to_string();
+        assert!(error.starts_with("JSON array element 1:"), "{}", error);
+
+        // Cut short: what was read is kept, then one error ends the stream
+        let truncated = br#"[{"id": 1, "street": "A St"}, {"id": 2, "str"#;
+        let results: Vec<Result<Sale>> = parse_json_array_stream(truncated).unwrap().collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    /// Counts records alive at once
+    struct Live;
+
+    impl Default for Live {
+        fn default() -> Self {
+            let live = LIVE.fetch_add(1, Ordering::SeqCst) + 1;
+            PEAK.fetch_max(live, Ordering::SeqCst);
+            Live
+        }
+    }
+
+    impl Drop for Live {
+        fn drop(&mut self) {
+            LIVE.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    struct TrackedSale {
+        id: u64,
+        #[allow(dead_code)]
+        street: String,
+        #[serde(skip)]
+        _live: Live,
+    }
+
+    #[test]
+    fn test_json_array_stream_holds_one_record_at_a_time() {
+        const RECORDS: u64 = 200_000;
+        const MALFORMED: u64 = 123_456;
+
+        let mut json = String::from("[");
+        for id in 0..RECORDS {
+            if id > 0 {
+                json.push(',');
+            }
+            if id == MALFORMED {
+                json.push_str(r#"{"id": 123456, "street": }"#);
+            } else {
+                json.push_str(&format!(
+                    r#"{{"id": {}, "street": "{} Long Street"}}"#,
+                    id, id
+                ));
+            }
+        }
+        json.push(']');
+
+        let mut parsed = 0;
+        let mut failed = Vec::new();
+        let mut last_id = None;
+        for (index, result) in parse_json_array_stream::<TrackedSale>(json.as_bytes())
+            .unwrap()
+            .enumerate()
+        {
+            match result {
+                Ok(sale) => {
+                    parsed += 1;
+                    last_id = Some(sale.id);
+                }
+                Err(_) => failed.push(index as u64),
+            }
+        }
+
+        assert_eq!(parsed, RECORDS - 1);
+        assert_eq!(failed, [MALFORMED]);
+        assert_eq!(last_id, Some(RECORDS - 1));
+        // Peak live records against records read: a Value tree or a Vec of
+        // records would hold all of them; the stream holds one
+        assert_eq!(PEAK.load(Ordering::SeqCst), 1);
+        assert_eq!(LIVE.load(Ordering::SeqCst), 0);
     }
 }
@@ -0,0 +1,344 @@
+//! Integrity checks between fetch and parse - a truncated download or a
+//! suspiciously small parse fails the run instead of being written
+//!
+//! Every check is recorded in a `ValidationReport`, which is logged and
+//! stored in the run's metrics whether the run goes on or fails.
+
+use crate::ingestion::utils::{is_csv_name, is_dat_name};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Fewest rows a full NSW sales file parses to when NSW_SALES_MIN_RECORDS is not set
+pub const DEFAULT_NSW_SALES_MIN_RECORDS: usize = 100_000;
+
+/// Largest share of rows that may fail to parse when PARSE_MAX_ERROR_RATE is not set
+pub const DEFAULT_MAX_PARSE_ERROR_RATE: f64 = 0.1;
+
+/// Share of rows that may fail to parse before a run fails, from
+/// PARSE_MAX_ERROR_RATE; 1 never fails a run
+pub fn max_parse_error_rate_from_env() -> f64 {
+    env::var("PARSE_MAX_ERROR_RATE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|rate: &f64| (0.0..=1.0).contains(rate))
+        .unwrap_or(DEFAULT_MAX_PARSE_ERROR_RATE)
+}
+
+/// What a source's fetched file has to satisfy; the default checks nothing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationConfig {
+    /// Hex SHA-256 the download must match; not checked when None
+    pub expected_sha256: Option<String>,
+    /// Fewest records parsing may yield; 0 turns the check off
+    pub min_records: usize,
+}
+
+impl ValidationConfig {
+    /// NSW sales settings from NSW_SALES_SHA256 and NSW_SALES_MIN_RECORDS
+    pub fn nsw_sales_from_env() -> Self {
+        ValidationConfig {
+            expected_sha256: env::var("NSW_SALES_SHA256")
+                .ok()
+                .map(|s| s.trim().to_ascii_lowercase())
+                .filter(|s| !s.is_empty()),
+            min_records: env::var("NSW_SALES_MIN_RECORDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_NSW_SALES_MIN_RECORDS),
+        }
+    }
+}
+
+/// One check and what it found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Every check run on a fetched file, in the order they ran
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub checks: Vec<ValidationCheck>,
+}
+
+/// A run stopped by failed checks; the report lists every check, not just the failures
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Validation failed: {}", .report.failures().join("; "))]
+pub struct ValidationFailed {
+    pub report: ValidationReport,
+}
+
+impl ValidationReport {
+    pub fn record(&mut self, name: &str, outcome: Result<String, String>) {
+        let (passed, detail) = match outcome {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        self.checks.push(ValidationCheck {
+            name: name.to_string(),
+            passed,
+            detail,
+        });
+    }
+
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+
+    /// "name: detail" of each failed check
+    pub fn failures(&self) -> Vec<String> {
+        self.checks
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| format!("{}: {}", c.name, c.detail))
+            .collect()
+    }
+
+    /// Log every check, failures as warnings
+    pub fn log(&self) {
+        for check in &self.checks {
+            if check.passed {
+                info!("Validation {} passed: {}", check.name, check.detail);
+            } else {
+                warn!("Validation {} failed: {}", check.name, check.detail);
+            }
+        }
+    }
+
+    /// The report if every check passed
+    pub fn into_result(self) -> Result<Self, ValidationFailed> {
+        if self.passed() {
+            Ok(self)
+        } else {
+            Err(ValidationFailed { report: self })
+        }
+    }
+
+    /// Downloaded size against the Content-Length the server announced
+    pub fn check_content_length(&mut self, downloaded: u64, content_length: Option<u64>) {
+        let outcome = match content_length {
+            None => Ok(format!("{} bytes, no Content-Length sent", downloaded)),
+            Some(expected) if expected == downloaded => Ok(format!("{} bytes", downloaded)),
+            Some(expected) => Err(format!(
+                "downloaded {} bytes but Content-Length was {}",
+                downloaded, expected
+            )),
+        };
+        self.record("content_length", outcome);
+    }
+
+    /// SHA-256 of `path` against the expected hash, when one is configured
+    pub fn check_sha256(&mut self, path: &Path, expected: Option<&str>) {
+        let Some(expected) = expected else {
+            return;
+        };
+        let outcome = match sha256_hex(path) {
+            Ok(actual) if actual.eq_ignore_ascii_case(expected) => Ok(actual),
+            Ok(actual) => Err(format!("expected {} but got {}", expected, actual)),
+            Err(e) => Err(format!("could not read {:?}: {}", path, e)),
+        };
+        self.record("sha256", outcome);
+    }
+
+    /// The ZIP's central directory opens and lists at least one CSV or DAT file
+    pub fn check_zip(&mut self, path: &Path) {
+        let outcome = File::open(path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| zip::ZipArchive::new(file).map_err(|e| e.to_string()))
+            .map_err(|e| format!("not a readable ZIP: {}", e))
+            .and_then(|archive| {
+                let entries = archive.len();
+                let csvs = archive.file_names().filter(|n| is_csv_name(n)).count();
+                let dats = archive.file_names().filter(|n| is_dat_name(n)).count();
+                match (csvs, dats) {
+                    (0, 0) => Err(format!(
+                        "none of its {} entries is a CSV or DAT file",
+                        entries
+                    )),
+                    (csvs, 0) => Ok(format!("{} entries, {} CSV", entries, csvs)),
+                    (csvs, dats) => Ok(format!("{} entries, {} CSV, {} DAT", entries, csvs, dats)),
+                }
+            });
+        self.record("zip", outcome);
+    }
+
+    /// Parsing yielded at least `min` records; skipped when `min` is 0
+    pub fn check_record_count(&mut self, records: usize, min: usize) {
+        if min == 0 {
+            return;
+        }
+        let outcome = if records >= min {
+            Ok(format!("{} records", records))
+        } else {
+            Err(format!(
+                "only {} records parsed, expected at least {}",
+                records, min
+            ))
+        };
+        self.record("record_count", outcome);
+    }
+
+    /// No more than `max_rate` of the rows read failed to parse
+    pub fn check_parse_errors(&mut self, errors: usize, total_rows: usize, max_rate: f64) {
+        let rate = error_rate(errors, total_rows);
+        let detail = format!(
+            "{} of {} rows failed to parse ({:.1}%)",
+            errors,
+            total_rows,
+            rate * 100.0
+        );
+        let outcome = if rate > max_rate {
+            Err(format!(
+                "{}, over the {:.1}% allowed",
+                detail,
+                max_rate * 100.0
+            ))
+        } else {
+            Ok(detail)
+        };
+        self.record("parse_errors", outcome);
+    }
+}
+
+/// Share of `total_rows` that failed; 0 when there were none
+pub fn error_rate(errors: usize, total_rows: usize) -> f64 {
+    if total_rows == 0 {
+        0.0
+    } else {
+        errors as f64 / total_rows as f64
+    }
+}
+
+/// Hex SHA-256 of the file at `path`
+pub fn sha256_hex(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn write_zip(path: &Path, name: &str) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        zip.start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(b"header\nrow\n").unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_zip_checks() {
+        let temp = tempdir().unwrap();
+        let good = temp.path().join("good.zip");
+        write_zip(&good, "sales.csv");
+        let no_csv = temp.path().join("no_csv.zip");
+        write_zip(&no_csv, "readme.txt");
+        // Cut off before the central directory
+        let truncated = temp.path().join("truncated.zip");
+        let bytes = fs::read(&good).unwrap();
+        fs::write(&truncated, &bytes[..bytes.len() / 2]).unwrap();
+
+        let mut report = ValidationReport::default();
+        report.check_zip(&good);
+        assert!(report.passed(), "{:?}", report);
+
+        for path in [no_csv, truncated] {
+            let mut report = ValidationReport::default();
+            report.check_zip(&path);
+            assert!(!report.passed(), "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn test_content_length_and_sha256() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("file");
+        fs::write(&path, "abc").unwrap();
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        let mut report = ValidationReport::default();
+        report.check_content_length(3, Some(3));
+        report.check_content_length(3, None);
+        report.check_sha256(&path, Some(abc));
+        report.check_sha256(&path, Some(&abc.to_ascii_uppercase()));
+        // Not configured, so not recorded
+        report.check_sha256(&path, None);
+        assert!(report.passed());
+        assert_eq!(report.checks.len(), 4);
+
+        let mut report = ValidationReport::default();
+        report.check_content_length(3, Some(250_000_000));
+        report.check_sha256(&path, Some(&"0".repeat(64)));
+        assert_eq!(
+            report.failures(),
+            vec![
+                "content_length: downloaded 3 bytes but Content-Length was 250000000".to_string(),
+                format!("sha256: expected {} but got {}", "0".repeat(64), abc),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_count_fails_with_whole_report() {
+        let mut report = ValidationReport::default();
+        report.check_zip(Path::new("/nonexistent/sales.zip"));
+        report.check_record_count(40_000, DEFAULT_NSW_SALES_MIN_RECORDS);
+        // Turned off
+        report.check_record_count(0, 0);
+        assert_eq!(report.checks.len(), 2);
+
+        let failed = report.into_result().unwrap_err();
+        let message = failed.to_string();
+        assert!(
+            message.starts_with("Validation failed: zip: "),
+            "{}",
+            message
+        );
+        assert!(
+            message.ends_with("record_count: only 40000 records parsed, expected at least 100000"),
+            "{}",
+            message
+        );
+        assert_eq!(failed.report.checks.len(), 2);
+
+        let mut report = ValidationReport::default();
+        report.check_record_count(100_000, DEFAULT_NSW_SALES_MIN_RECORDS);
+        assert!(report.into_result().is_ok());
+    }
+
+    #[test]
+    fn test_parse_error_rate() {
+        let mut report = ValidationReport::default();
+        report.check_parse_errors(10, 100, DEFAULT_MAX_PARSE_ERROR_RATE);
+        report.check_parse_errors(0, 0, 0.0);
+        assert!(report.passed(), "{:?}", report);
+        assert_eq!(
+            report.checks[0].detail,
+            "10 of 100 rows failed to parse (10.0%)"
+        );
+
+        let mut report = ValidationReport::default();
+        report.check_parse_errors(40, 100, DEFAULT_MAX_PARSE_ERROR_RATE);
+        assert_eq!(
+            report.failures(),
+            vec!["parse_errors: 40 of 100 rows failed to parse (40.0%), over the 10.0% allowed"]
+        );
+    }
+}
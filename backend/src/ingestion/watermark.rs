@@ -0,0 +1,212 @@
+//! Watermarks for incremental sources - the latest source-side update time
+//! already loaded, so the next run only asks for what changed since
+//!
+//! Maintenance jobs that walk the properties table by id keep the last id
+//! they finished instead, so an interrupted job resumes where it stopped.
+
+use crate::ingestion::runs;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use sqlx::PgPool;
+
+/// None until the source has completed a run
+pub async fn get_watermark(db: &PgPool, source_id: &str) -> Result<Option<DateTime<Utc>>> {
+    let watermark = sqlx::query_scalar::<_, DateTime<Utc>>(
+        "SELECT watermark FROM source_watermarks WHERE source_id = $1",
+    )
+    .bind(source_id)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(watermark)
+}
+
+/// Record `to` as loaded; never moves a watermark backwards
+/// Call only once everything up to `to` has been written
+pub async fn advance_watermark(db: &PgPool, source_id: &str, to: DateTime<Utc>) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO source_watermarks (source_id, watermark)
+        VALUES ($1, $2)
+        ON CONFLICT (source_id) DO UPDATE
+        SET watermark = GREATEST(source_watermarks.watermark, EXCLUDED.watermark),
+            updated_at = NOW()
+        "#,
+    )
+    .bind(source_id)
+    .bind(to)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// The watermark of the weekly archives of `source_id`
+fn weekly_watermark(source_id: &str) -> String {
+    format!("{}_weekly", source_id)
+}
+
+/// Where weekly archives of `source_id` carry on from: the last week loaded,
+/// or before any week has been, the day of its last successful run
+/// None when it has never run, so there's nothing to build on
+pub async fn weekly_resume_date(db: &PgPool, source_id: &str) -> Result<Option<NaiveDate>> {
+    if let Some(week) = get_watermark(db, &weekly_watermark(source_id)).await? {
+        return Ok(Some(week.date_naive()));
+    }
+    let completed = runs::last_completed(db, source_id).await?;
+    Ok(completed.map(|at| at.date()))
+}
+
+/// Record every weekly archive of `source_id` up to `week` as loaded
+pub async fn advance_weekly_watermark(db: &PgPool, source_id: &str, week: NaiveDate) -> Result<()> {
+    let at = week.and_time(NaiveTime::MIN).and_utc();
+    advance_watermark(db, &weekly_watermark(source_id), at).await
+}
+
+/// Last property id `task` finished; None if it hasn't started or was reset
+pub async fn get_id_watermark(db: &PgPool, task: &str) -> Result<Option<i32>> {
+    let last_id =
+        sqlx::query_scalar::<_, i32>("SELECT last_id FROM maintenance_watermarks WHERE task = $1")
+            .bind(task)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(last_id)
+}
+
+/// Record every id up to `last_id` as done for `task`; never moves backwards
+pub async fn advance_id_watermark(db: &PgPool, task: &str, last_id: i32) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO maintenance_watermarks (task, last_id)
+        VALUES ($1, $2)
+        ON CONFLICT (task) DO UPDATE
+        SET last_id = GREATEST(maintenance_watermarks.last_id, EXCLUDED.last_id),
+            updated_at = NOW()
+        "#,
+    )
+    .bind(task)
+    .bind(last_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Forget `task`'s progress so its next run starts from the first id
+pub async fn reset_id_watermark(db: &PgPool, task: &str) -> Result<()> {
+    sqlx::query("DELETE FROM maintenance_watermarks WHERE task = $1")
+        .bind(task)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_watermark_only_moves_forward() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let source = "test_watermark_source";
+        sqlx::query("DELETE FROM source_watermarks WHERE source_id = $1")
+            .bind(source)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(get_watermark(&db, source).await.unwrap(), None);
+
+        let first: DateTime<Utc> = "2025-10-02T00:00:00Z".parse().unwrap();
+        advance_watermark(&db, source, first).await.unwrap();
+        advance_watermark(&db, source, "2025-10-01T00:00:00Z".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(get_watermark(&db, source).await.unwrap(), Some(first));
+
+        let later: DateTime<Utc> = "2025-10-03T12:00:00Z".parse().unwrap();
+        advance_watermark(&db, source, later).await.unwrap();
+        assert_eq!(get_watermark(&db, source).await.unwrap(), Some(later));
+
+        sqlx::query("DELETE FROM source_watermarks WHERE source_id = $1")
+            .bind(source)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_weekly_resume_date() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let source = "test_weekly_source";
+        let cleanup = || async {
+            sqlx::query("DELETE FROM ingestion_runs WHERE source_id = $1")
+                .bind(source)
+                .execute(&db)
+                .await
+                .unwrap();
+            sqlx::query("DELETE FROM source_watermarks WHERE source_id = $1")
+                .bind(weekly_watermark(source))
+                .execute(&db)
+                .await
+                .unwrap();
+        };
+        cleanup().await;
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        // Nothing to build on yet
+        assert_eq!(weekly_resume_date(&db, source).await.unwrap(), None);
+
+        // Failed runs don't count
+        let insert_run = |status: &'static str, completed: &'static str| {
+            sqlx::query(
+                "INSERT INTO ingestion_runs (source_id, status, started_at, completed_at) \
+                 VALUES ($1, $2, $3::TIMESTAMP - INTERVAL '1 hour', $3::TIMESTAMP)",
+            )
+            .bind(source)
+            .bind(status)
+            .bind(completed)
+            .execute(&db)
+        };
+        insert_run("completed", "2025-06-04 09:00:00")
+            .await
+            .unwrap();
+        insert_run("failed", "2025-06-20 09:00:00").await.unwrap();
+        assert_eq!(
+            weekly_resume_date(&db, source).await.unwrap(),
+            Some(date("2025-06-04"))
+        );
+
+        // Once a week is loaded, runs no longer matter
+        advance_weekly_watermark(&db, source, date("2025-06-09"))
+            .await
+            .unwrap();
+        insert_run("completed", "2025-06-25 09:00:00")
+            .await
+            .unwrap();
+        assert_eq!(
+            weekly_resume_date(&db, source).await.unwrap(),
+            Some(date("2025-06-09"))
+        );
+        advance_weekly_watermark(&db, source, date("2025-06-02"))
+            .await
+            .unwrap();
+        advance_weekly_watermark(&db, source, date("2025-06-16"))
+            .await
+            .unwrap();
+        assert_eq!(
+            weekly_resume_date(&db, source).await.unwrap(),
+            Some(date("2025-06-16"))
+        );
+
+        cleanup().await;
+    }
+}
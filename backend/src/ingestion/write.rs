@@ -1,25 +1,64 @@
 //! Write functions - persist data to PostgreSQL with conflict resolution
 
-use crate::ingestion::types::{PropertyRecord, PropertyRow, RentalMedian, WriteStats};
+use crate::ingestion::drift::{DriftConfig, PropertyDrift, PropertyValues};
+use crate::ingestion::utils::normalize_address;
+use crate::ingestion::types::{
+    CouncilRates, LgaCorrespondence, PostcodeRegion, PropertyRecord, PropertyRow, RentalMedian,
+    RentalObservation, WriteStats,
+};
 use anyhow::Result;
-use sqlx::PgPool;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{PgConnection, PgExecutor, PgPool};
 use tracing::{debug, info, warn};
 
 /// Write property records to database with intelligent conflict resolution
+/// Updates are checked for drift with the DRIFT_* settings from the environment
 pub async fn write_properties(db: &PgPool, records: Vec<PropertyRecord>) -> Result<WriteStats> {
+    write_properties_with(db, records, &DriftConfig::from_env()).await
+}
+
+/// `write_properties` with explicit drift settings
+pub async fn write_properties_with(
+    db: &PgPool,
+    records: Vec<PropertyRecord>,
+    drift_config: &DriftConfig,
+) -> Result<WriteStats> {
+    let mut conn = db.acquire().await?;
+    write_properties_on(&mut conn, records, drift_config).await
+}
+
+/// `write_properties_with` on one connection, which may be inside a transaction
+/// A failed record is counted in `errors`; in a transaction it also aborts the
+/// rest, so callers should roll back when any record failed
+pub async fn write_properties_on(
+    conn: &mut PgConnection,
+    records: Vec<PropertyRecord>,
+    drift_config: &DriftConfig,
+) -> Result<WriteStats> {
     info!("Writing {} property records to database", records.len());
 
     let mut stats = WriteStats::default();
+    let mut samples = 0;
 
     for record in records {
-        match write_single_property(db, &record).await {
-            Ok(inserted) => {
-                if inserted {
-                    stats.inserted += 1;
-                } else {
-                    stats.updated += 1;
+        match write_single_property(conn, &record).await {
+            Ok(WriteOutcome::Inserted) => stats.inserted += 1,
+            Ok(WriteOutcome::Updated(id, drift)) => {
+                stats.updated += 1;
+                stats.drift.record(&drift);
+
+                let suspicious = drift.suspicious(drift_config.threshold);
+                if !suspicious.is_empty() && samples < drift_config.max_samples {
+                    samples += 1;
+                    if let Err(e) = record_suspicious_update(conn, id, &record, &drift).await {
+                        warn!("Failed to record suspicious update of {}: {}", id, e);
+                    }
                 }
             }
+            Ok(WriteOutcome::Skipped) => stats.skipped += 1,
             Err(e) => {
                 warn!("Failed to write property {}: {}", record.address, e);
                 stats.errors += 1;
@@ -32,39 +71,92 @@ pub async fn write_properties(db: &PgPool, records: Vec<PropertyRecord>) -> Resu
     Ok(stats)
 }
 
+/// What writing one record did
+enum WriteOutcome {
+    Inserted,
+    /// Property id, and how its watched values moved
+    Updated(i32, PropertyDrift),
+    /// The stored data is better quality
+    Skipped,
+}
+
 /// Write a single property record with conflict resolution
-/// Returns true if inserted, false if updated
-async fn write_single_property(db: &PgPool, record: &PropertyRecord) -> Result<bool> {
+async fn write_single_property(
+    conn: &mut PgConnection,
+    record: &PropertyRecord,
+) -> Result<WriteOutcome> {
     // Check if property exists (by address + postcode or external_id)
-    let existing = find_existing_property(db, record).await?;
+    let existing = find_existing_property(conn, record).await?;
 
     match existing {
         None => {
             // Insert new property
-            insert_property(db, record).await?;
+            insert_property(conn, record).await?;
             debug!("Inserted new property: {}", record.address);
-            Ok(true)
+            Ok(WriteOutcome::Inserted)
         }
         Some(existing) => {
             // Decide if we should update based on data quality
             if should_replace(&existing, record) {
-                update_property(db, existing.id, record).await?;
+                let drift = update_property(conn, existing.id, record).await?;
                 debug!("Updated property: {} (id: {})", record.address, existing.id);
-                Ok(false)
+                Ok(WriteOutcome::Updated(existing.id, drift))
             } else {
                 debug!(
                     "Skipped property: {} (existing data is better quality)",
                     record.address
                 );
-                Ok(false)
+                Ok(WriteOutcome::Skipped)
             }
         }
     }
 }
 
+/// Sample an update whose values moved past the drift threshold for review
+async fn record_suspicious_update(
+    conn: &mut PgConnection,
+    property_id: i32,
+    record: &PropertyRecord,
+    drift: &PropertyDrift,
+) -> Result<()> {
+    let details = serde_json::json!({
+        "address": record.address,
+        "external_id": record.external_id,
+        "before": {
+            "price": drift.before.price,
+            "weekly_rent": drift.before.weekly_rent,
+            "rental_yield": drift.before.rental_yield,
+        },
+        "after": {
+            "price": drift.after.price,
+            "weekly_rent": drift.after.weekly_rent,
+            "rental_yield": drift.after.rental_yield,
+        },
+        "changes": drift
+            .suspicious(0.0)
+            .into_iter()
+            .map(|(field, change)| (field.to_string(), Value::from(change)))
+            .collect::<serde_json::Map<_, _>>(),
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO ingestion_errors (source_id, kind, property_id, details)
+        VALUES ($1, 'suspicious_update', $2, $3)
+        "#,
+    )
+    .bind(&record.source_metadata.source_id)
+    .bind(property_id)
+    .bind(details)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
 /// Find existing property by external_id or address+postcode
 async fn find_existing_property(
-    db: &PgPool,
+    conn: &mut PgConnection,
     record: &PropertyRecord,
 ) -> Result<Option<PropertyRow>> {
     // First try to find by external_id (most reliable)
@@ -73,8 +165,8 @@ async fn find_existing_property(
             "SELECT * FROM properties WHERE external_id = $1 AND state = $2",
         )
         .bind(external_id)
-        .bind(&record.state)
-        .fetch_optional(db)
+        .bind(record.state)
+        .fetch_optional(&mut *conn)
         .await?;
 
         if result.is_some() {
@@ -82,15 +174,15 @@ async fn find_existing_property(
         }
     }
 
-    // Fallback: find by address + postcode
+    // Fallback: find by address + postcode, the address in the form it's stored in
     if let Some(ref postcode) = record.postcode {
         let result = sqlx::query_as::<_, PropertyRow>(
             "SELECT * FROM properties WHERE address = $1 AND postcode = $2 AND state = $3",
         )
-        .bind(&record.address)
+        .bind(normalize_address(&record.address))
         .bind(postcode)
-        .bind(&record.state)
-        .fetch_optional(db)
+        .bind(record.state)
+        .fetch_optional(&mut *conn)
         .await?;
 
         return Ok(result);
@@ -109,25 +201,30 @@ fn should_replace(existing: &PropertyRow, new: &PropertyRecord) -> bool {
     new_score > existing_score * 1.1
 }
 
-/// Insert a new property record
-async fn insert_property(db: &PgPool, record: &PropertyRecord) -> Result<i32> {
+/// Insert a new property record, its address normalized
+pub(crate) async fn insert_property(
+    conn: &mut PgConnection,
+    record: &PropertyRecord,
+) -> Result<i32> {
     let id = sqlx::query_scalar::<_, i32>(
         r#"
         INSERT INTO properties (
             address, suburb, state, postcode, bedrooms, bathrooms, property_type,
             price, weekly_rent, rental_yield, latitude, longitude, sale_date,
             data_source, data_quality, is_rental_estimated, confidence_score,
-            external_id, land_area_sqm, last_updated
+            external_id, land_area_sqm, is_bedrooms_estimated, rental_period,
+            source_file, source_row, price_per_sqm, district, rental_match_method,
+            last_updated
         ) VALUES (
             $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13,
-            $14, $15, $16, $17, $18, $19, NOW()
+            $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, NOW()
         )
         RETURNING id
         "#,
     )
-    .bind(&record.address)
+    .bind(normalize_address(&record.address))
     .bind(&record.suburb)
-    .bind(&record.state)
+    .bind(record.state)
     .bind(&record.postcode)
     .bind(record.bedrooms)
     .bind(record.bathrooms)
@@ -139,40 +236,54 @@ async fn insert_property(db: &PgPool, record: &PropertyRecord) -> Result<i32> {
     .bind(record.longitude)
     .bind(record.sale_date)
     .bind(&record.source_metadata.source_id)
-    .bind(&record.source_metadata.data_quality)
+    .bind(record.source_metadata.data_quality)
     .bind(record.source_metadata.is_rental_estimated)
     .bind(record.source_metadata.confidence_score)
     .bind(&record.external_id)
     .bind(record.land_area_sqm)
-    .fetch_one(db)
+    .bind(record.source_metadata.is_bedrooms_estimated)
+    .bind(record.source_metadata.rental_period)
+    .bind(&record.source_metadata.source_file)
+    .bind(record.source_metadata.source_row)
+    .bind(record.price_per_sqm)
+    .bind(&record.district)
+    .bind(record.source_metadata.rental_match_method)
+    .fetch_one(&mut *conn)
     .await?;
 
-    // Also insert into sales history if we have sale data
-    if let (Some(price), Some(date)) = (record.sale_price, record.sale_date) {
-        insert_sale_history(db, id, price, date, &record.source_metadata.source_id).await?;
-    }
+    insert_record_sales(conn, id, record).await?;
 
     Ok(id)
 }
 
-/// Update an existing property record
-async fn update_property(db: &PgPool, id: i32, record: &PropertyRecord) -> Result<()> {
-    sqlx::query(
+/// Update an existing property record, returning how its price, rent and yield moved
+/// A district is kept when the update comes from a source without one
+async fn update_property(
+    conn: &mut PgConnection,
+    id: i32,
+    record: &PropertyRecord,
+) -> Result<PropertyDrift> {
+    let before = sqlx::query_as::<_, (Option<i64>, Option<i32>, Option<Decimal>)>(
         r#"
-        UPDATE properties SET
+        UPDATE properties p SET
             address = $1, suburb = $2, state = $3, postcode = $4,
             bedrooms = $5, bathrooms = $6, property_type = $7,
             price = $8, weekly_rent = $9, rental_yield = $10,
             latitude = $11, longitude = $12, sale_date = $13,
             data_source = $14, data_quality = $15, is_rental_estimated = $16,
             confidence_score = $17, external_id = $18, land_area_sqm = $19,
+            is_bedrooms_estimated = $20, rental_period = $21,
+            source_file = $22, source_row = $23, price_per_sqm = $24,
+            district = COALESCE($25, p.district), rental_match_method = $26,
             last_updated = NOW()
-        WHERE id = $20
+        FROM (SELECT id, price, weekly_rent, rental_yield FROM properties WHERE id = $27) old
+        WHERE p.id = old.id
+        RETURNING old.price, old.weekly_rent, old.rental_yield
         "#,
     )
-    .bind(&record.address)
+    .bind(normalize_address(&record.address))
     .bind(&record.suburb)
-    .bind(&record.state)
+    .bind(record.state)
     .bind(&record.postcode)
     .bind(record.bedrooms)
     .bind(record.bathrooms)
@@ -184,74 +295,265 @@ async fn update_property(db: &PgPool, id: i32, record: &PropertyRecord) -> Resul
     .bind(record.longitude)
     .bind(record.sale_date)
     .bind(&record.source_metadata.source_id)
-    .bind(&record.source_metadata.data_quality)
+    .bind(record.source_metadata.data_quality)
     .bind(record.source_metadata.is_rental_estimated)
     .bind(record.source_metadata.confidence_score)
     .bind(&record.external_id)
     .bind(record.land_area_sqm)
+    .bind(record.source_metadata.is_bedrooms_estimated)
+    .bind(record.source_metadata.rental_period)
+    .bind(&record.source_metadata.source_file)
+    .bind(record.source_metadata.source_row)
+    .bind(record.price_per_sqm)
+    .bind(&record.district)
+    .bind(record.source_metadata.rental_match_method)
     .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    insert_record_sales(conn, id, record).await?;
+
+    let (price, weekly_rent, rental_yield) = before;
+    Ok(PropertyDrift::between(
+        PropertyValues {
+            price,
+            weekly_rent,
+            rental_yield,
+        },
+        PropertyValues {
+            price: record.sale_price,
+            weekly_rent: record.weekly_rent,
+            rental_yield: record.rental_yield,
+        },
+    ))
+}
+
+/// One column that differs between two snapshots of a property
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Outcome of an audited property overwrite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditedWrite {
+    pub before: Value,
+    pub after: Value,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Columns left out of snapshots because they change on every write
+const SNAPSHOT_IGNORED: &[&str] = &["last_updated"];
+
+/// Overwrite a property with `record` regardless of data quality, recording
+/// the before/after snapshots in property_audit_log
+pub async fn write_property_audited(
+    db: &PgPool,
+    id: i32,
+    record: &PropertyRecord,
+    action: &str,
+    actor: &str,
+) -> Result<Option<AuditedWrite>> {
+    let mut conn = db.acquire().await?;
+    let Some(before) = property_snapshot(&mut *conn, id).await? else {
+        return Ok(None);
+    };
+
+    update_property(&mut conn, id, record).await?;
+    let after = property_snapshot(&mut *conn, id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Property {} disappeared during update", id))?;
+
+    insert_audit_entry(&mut *conn, id, action, actor, &before, &after).await?;
+
+    info!("Audited {} of property {} by {}", action, id, actor);
+
+    Ok(Some(AuditedWrite {
+        changes: diff_snapshots(&before, &after),
+        before,
+        after,
+    }))
+}
+
+/// Record a change to a property in property_audit_log
+pub(crate) async fn insert_audit_entry<'e, E: PgExecutor<'e>>(
+    db: E,
+    id: i32,
+    action: &str,
+    actor: &str,
+    before: &Value,
+    after: &Value,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO property_audit_log (property_id, action, actor, before, after)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(id)
+    .bind(action)
+    .bind(actor)
+    .bind(before)
+    .bind(after)
     .execute(db)
     .await?;
 
-    // Also insert into sales history if we have sale data
-    if let (Some(price), Some(date)) = (record.sale_price, record.sale_date) {
-        insert_sale_history(db, id, price, date, &record.source_metadata.source_id).await?;
+    Ok(())
+}
+
+/// The property's row as a JSON object
+pub(crate) async fn property_snapshot<'e, E: PgExecutor<'e>>(
+    db: E,
+    id: i32,
+) -> Result<Option<Value>> {
+    let mut snapshot =
+        sqlx::query_scalar::<_, Value>("SELECT to_jsonb(p) FROM properties p WHERE id = $1")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+
+    if let Some(Value::Object(fields)) = snapshot.as_mut() {
+        for column in SNAPSHOT_IGNORED {
+            fields.remove(*column);
+        }
     }
 
+    Ok(snapshot)
+}
+
+/// Fields whose values differ, in column name order
+pub fn diff_snapshots(before: &Value, after: &Value) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let old = before.get(field).cloned().unwrap_or(Value::Null);
+            let new = after.get(field).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| FieldChange {
+                field: field.clone(),
+                before: old,
+                after: new,
+            })
+        })
+        .collect()
+}
+
+/// Insert a record's sale and any prior sales collapsed into it into sales history
+async fn insert_record_sales(
+    conn: &mut PgConnection,
+    property_id: i32,
+    record: &PropertyRecord,
+) -> Result<()> {
+    let source = &record.source_metadata.source_id;
+    if let (Some(price), Some(date)) = (record.sale_price, record.sale_date) {
+        insert_sale_history(conn, property_id, price, date, source).await?;
+    }
+    if !record.prior_sales.is_empty() {
+        insert_prior_sales(conn, property_id, &record.prior_sales, source).await?;
+    }
     Ok(())
 }
 
-/// Insert a sale into sales history
-async fn insert_sale_history(
-    db: &PgPool,
+/// Insert earlier sales into sales history in one statement, skipping any
+/// already recorded for the property
+async fn insert_prior_sales(
+    conn: &mut PgConnection,
+    property_id: i32,
+    sales: &[(i64, NaiveDate)],
+    data_source: &str,
+) -> Result<u64> {
+    let (prices, dates): (Vec<i64>, Vec<NaiveDate>) = sales.iter().copied().unzip();
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO sales_history (property_id, sale_price, sale_date, data_source)
+        SELECT DISTINCT $1, s.price, s.date, $4
+        FROM UNNEST($2::bigint[], $3::date[]) AS s(price, date)
+        WHERE NOT EXISTS (
+            SELECT 1 FROM sales_history h
+            WHERE h.property_id = $1 AND h.sale_date = s.date AND h.sale_price = s.price
+        )
+        "#,
+    )
+    .bind(property_id)
+    .bind(prices)
+    .bind(dates)
+    .bind(data_source)
+    .execute(conn)
+    .await?
+    .rows_affected();
+
+    debug!("Inserted {} prior sales: property_id={}", inserted, property_id);
+
+    Ok(inserted)
+}
+
+/// Insert a sale into sales history and return its id
+/// A sale already recorded for the property (same date and price) is reused
+pub(crate) async fn insert_sale_history(
+    conn: &mut PgConnection,
     property_id: i32,
-    price: i32,
+    price: i64,
     sale_date: chrono::NaiveDate,
     data_source: &str,
-) -> Result<()> {
+) -> Result<i32> {
     // Only insert if this sale doesn't already exist
-    let exists = sqlx::query_scalar::<_, bool>(
+    let existing = sqlx::query_scalar::<_, i32>(
         r#"
-        SELECT EXISTS(
-            SELECT 1 FROM sales_history
-            WHERE property_id = $1 AND sale_date = $2 AND sale_price = $3
-        )
+        SELECT id FROM sales_history
+        WHERE property_id = $1 AND sale_date = $2 AND sale_price = $3
+        LIMIT 1
         "#,
     )
     .bind(property_id)
     .bind(sale_date)
     .bind(price)
-    .fetch_one(db)
+    .fetch_optional(&mut *conn)
     .await?;
 
-    if !exists {
-        sqlx::query(
-            r#"
-            INSERT INTO sales_history (property_id, sale_price, sale_date, data_source)
-            VALUES ($1, $2, $3, $4)
-            "#,
-        )
-        .bind(property_id)
-        .bind(price)
-        .bind(sale_date)
-        .bind(data_source)
-        .execute(db)
-        .await?;
-
-        debug!("Inserted sale history: property_id={}, price={}, date={}", property_id, price, sale_date);
+    if let Some(id) = existing {
+        return Ok(id);
     }
 
-    Ok(())
+    let id = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO sales_history (property_id, sale_price, sale_date, data_source)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(property_id)
+    .bind(price)
+    .bind(sale_date)
+    .bind(data_source)
+    .fetch_one(conn)
+    .await?;
+
+    debug!("Inserted sale history: property_id={}, price={}, date={}", property_id, price, sale_date);
+
+    Ok(id)
 }
 
-/// Write rental medians to database
-pub async fn write_rental_medians(db: &PgPool, rentals: Vec<RentalMedian>) -> Result<WriteStats> {
+/// Write rental medians to database, recorded against the source they came from
+pub async fn write_rental_medians(
+    db: &PgPool,
+    rentals: Vec<RentalMedian>,
+    data_source: &str,
+) -> Result<WriteStats> {
     info!("Writing {} rental medians to database", rentals.len());
 
     let mut stats = WriteStats::default();
 
     for rental in rentals {
-        match insert_rental_median(db, &rental).await {
+        match insert_rental_median(db, &rental, data_source).await {
             Ok(inserted) => {
                 if inserted {
                     stats.inserted += 1;
@@ -275,24 +577,29 @@ pub async fn write_rental_medians(db: &PgPool, rentals: Vec<RentalMedian>) -> Re
 }
 
 /// Insert a rental median (with conflict handling via UNIQUE constraint)
-async fn insert_rental_median(db: &PgPool, rental: &RentalMedian) -> Result<bool> {
+pub(crate) async fn insert_rental_median(
+    db: &PgPool,
+    rental: &RentalMedian,
+    data_source: &str,
+) -> Result<bool> {
     let result = sqlx::query(
         r#"
         INSERT INTO rental_medians (
             state, postcode, suburb, bedrooms, median_weekly_rent,
-            sample_size, data_source, period
+            sample_size, dwelling_type, data_source, period
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        ON CONFLICT (state, postcode, bedrooms, period, data_source) DO NOTHING
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (state, postcode, bedrooms, dwelling_type, period, data_source) DO NOTHING
         "#,
     )
-    .bind(&rental.state)
+    .bind(rental.state)
     .bind(&rental.postcode)
     .bind(&rental.suburb)
     .bind(rental.bedrooms)
     .bind(rental.median_weekly_rent)
     .bind(rental.sample_size)
-    .bind("nsw_rentals") // data_source
+    .bind(&rental.dwelling_type)
+    .bind(data_source)
     .bind(rental.period)
     .execute(db)
     .await?;
@@ -300,6 +607,241 @@ async fn insert_rental_median(db: &PgPool, rental: &RentalMedian) -> Result<bool
     Ok(result.rows_affected() > 0)
 }
 
+/// Source recorded against bond lodgement observations
+pub const BOND_LODGEMENTS_SOURCE: &str = "nsw_bond_lodgements";
+
+/// Remove the observations already stored for a period, so a re-run of the
+/// same monthly file replaces it rather than doubling it up
+/// Observations have no natural key to upsert on.
+pub async fn clear_rental_observations(db: &PgPool, period: NaiveDate) -> Result<u64> {
+    let result =
+        sqlx::query("DELETE FROM rental_observations WHERE data_source = $1 AND period = $2")
+            .bind(BOND_LODGEMENTS_SOURCE)
+            .bind(period)
+            .execute(db)
+            .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Write individual bond lodgements to database
+pub async fn write_rental_observations(
+    db: &PgPool,
+    observations: Vec<RentalObservation>,
+) -> Result<WriteStats> {
+    info!("Writing {} rental observations to database", observations.len());
+
+    let mut stats = WriteStats::default();
+
+    for observation in observations {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO rental_observations (
+                state, postcode, dwelling_type, bedrooms, weekly_rent, period, data_source
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(observation.state)
+        .bind(&observation.postcode)
+        .bind(&observation.dwelling_type)
+        .bind(observation.bedrooms)
+        .bind(observation.weekly_rent)
+        .bind(observation.period)
+        .bind(BOND_LODGEMENTS_SOURCE)
+        .execute(db)
+        .await;
+
+        match result {
+            Ok(_) => stats.inserted += 1,
+            Err(e) => {
+                warn!(
+                    "Failed to write rental observation for {}: {}",
+                    observation.postcode, e
+                );
+                stats.errors += 1;
+            }
+        }
+    }
+
+    info!("Rental observations write complete: {}", stats);
+
+    Ok(stats)
+}
+
+/// Upsert postcode regions, replacing any earlier mapping for the same postcode
+pub async fn write_postcode_regions(
+    db: &PgPool,
+    regions: Vec<PostcodeRegion>,
+) -> Result<WriteStats> {
+    info!("Writing {} postcode regions to database", regions.len());
+
+    let mut stats = WriteStats::default();
+
+    for region in regions {
+        let result = sqlx::query_scalar::<_, bool>(
+            r#"
+            INSERT INTO regions (postcode, sa3_code, sa3_name, sa4_code, sa4_name, state, ratio)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (postcode) DO UPDATE SET
+                sa3_code = EXCLUDED.sa3_code,
+                sa3_name = EXCLUDED.sa3_name,
+                sa4_code = EXCLUDED.sa4_code,
+                sa4_name = EXCLUDED.sa4_name,
+                state = EXCLUDED.state,
+                ratio = EXCLUDED.ratio,
+                updated_at = NOW()
+            RETURNING (xmax = 0)
+            "#,
+        )
+        .bind(&region.postcode)
+        .bind(&region.sa3_code)
+        .bind(&region.sa3_name)
+        .bind(&region.sa4_code)
+        .bind(&region.sa4_name)
+        .bind(region.state)
+        .bind(region.ratio)
+        .fetch_one(db)
+        .await;
+
+        match result {
+            Ok(true) => stats.inserted += 1,
+            Ok(false) => stats.updated += 1,
+            Err(e) => {
+                warn!("Failed to write region for {}: {}", region.postcode, e);
+                stats.errors += 1;
+            }
+        }
+    }
+
+    info!("Postcode regions write complete: {}", stats);
+
+    Ok(stats)
+}
+
+/// Upsert LGA correspondence rows, replacing any earlier mapping for the same area
+pub async fn write_lga_correspondence(
+    db: &PgPool,
+    mappings: Vec<LgaCorrespondence>,
+) -> Result<WriteStats> {
+    info!(
+        "Writing {} LGA correspondence rows to database",
+        mappings.len()
+    );
+
+    let mut stats = WriteStats::default();
+
+    for mapping in mappings {
+        let result = sqlx::query_scalar::<_, bool>(
+            r#"
+            INSERT INTO lga_correspondence (area_type, area, state, lga_code, lga_name, ratio)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (area_type, area, state) DO UPDATE SET
+                lga_code = EXCLUDED.lga_code,
+                lga_name = EXCLUDED.lga_name,
+                ratio = EXCLUDED.ratio,
+                updated_at = NOW()
+            RETURNING (xmax = 0)
+            "#,
+        )
+        .bind(mapping.area_type.as_str())
+        .bind(&mapping.area)
+        .bind(mapping.state)
+        .bind(&mapping.lga_code)
+        .bind(&mapping.lga_name)
+        .bind(mapping.ratio)
+        .fetch_one(db)
+        .await;
+
+        match result {
+            Ok(true) => stats.inserted += 1,
+            Ok(false) => stats.updated += 1,
+            Err(e) => {
+                warn!("Failed to write LGA for {}: {}", mapping.area, e);
+                stats.errors += 1;
+            }
+        }
+    }
+
+    info!("LGA correspondence write complete: {}", stats);
+
+    Ok(stats)
+}
+
+/// Upsert council rates, replacing any earlier figures for the same LGA
+pub async fn write_council_rates(db: &PgPool, councils: Vec<CouncilRates>) -> Result<WriteStats> {
+    info!("Writing rates for {} councils to database", councils.len());
+
+    let mut stats = WriteStats::default();
+
+    for council in councils {
+        let result = sqlx::query_scalar::<_, bool>(
+            r#"
+            INSERT INTO council_rates
+                (lga_code, lga_name, state, financial_year, average_rates, average_levies)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (lga_code) DO UPDATE SET
+                lga_name = EXCLUDED.lga_name,
+                state = EXCLUDED.state,
+                financial_year = EXCLUDED.financial_year,
+                average_rates = EXCLUDED.average_rates,
+                average_levies = EXCLUDED.average_levies,
+                updated_at = NOW()
+            RETURNING (xmax = 0)
+            "#,
+        )
+        .bind(&council.lga_code)
+        .bind(&council.lga_name)
+        .bind(council.state)
+        .bind(&council.financial_year)
+        .bind(council.average_rates)
+        .bind(council.average_levies)
+        .fetch_one(db)
+        .await;
+
+        match result {
+            Ok(true) => stats.inserted += 1,
+            Ok(false) => stats.updated += 1,
+            Err(e) => {
+                warn!(
+                    "Failed to write council rates for {}: {}",
+                    council.lga_code, e
+                );
+                stats.errors += 1;
+            }
+        }
+    }
+
+    info!("Council rates write complete: {}", stats);
+
+    Ok(stats)
+}
+
+/// Set each property's LGA from the correspondence: its suburb's LGA, else
+/// its postcode's. Returns how many properties changed LGA
+pub async fn assign_property_lgas(db: &PgPool) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE properties p
+        SET lga_code = m.lga_code, last_updated = NOW()
+        FROM (
+            SELECT p.id, COALESCE(s.lga_code, pc.lga_code) AS lga_code
+            FROM properties p
+            LEFT JOIN lga_correspondence s
+                ON s.area_type = 'suburb' AND s.area = UPPER(p.suburb) AND s.state = p.state
+            LEFT JOIN lga_correspondence pc
+                ON pc.area_type = 'postcode' AND pc.area = p.postcode AND pc.state = p.state
+        ) m
+        WHERE p.id = m.id
+          AND p.lga_code IS DISTINCT FROM m.lga_code
+        "#,
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -313,6 +855,7 @@ mod tests {
             suburb: "Testville".to_string(),
             state: State::NSW,
             postcode: Some("2000".to_string()),
+            district: None,
             property_type: PropertyType::House,
             bedrooms: Some(3),
             bathrooms: Some(2),
@@ -320,7 +863,10 @@ mod tests {
             sale_price: Some(800_000),
             sale_date: Some(chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()),
             weekly_rent: Some(600),
+            rent_frequency: None,
             rental_yield: Some(rust_decimal::Decimal::new(390, 2)), // 3.90%
+            price_per_sqm: None,
+            prior_sales: Vec::new(),
             latitude: None,
             longitude: None,
             source_metadata: SourceMetadata {
@@ -328,6 +874,11 @@ mod tests {
                 data_quality: DataQuality::Individual,
                 fetched_at: Utc::now(),
                 is_rental_estimated: true,
+                is_bedrooms_estimated: false,
+                rental_period: None,
+                rental_match_method: None,
+                source_file: None,
+                source_row: None,
                 confidence_score: 0.8,
             },
         }
@@ -384,4 +935,357 @@ mod tests {
         // 80 < 85 * 1.1 (93.5) -> should NOT replace
         assert!(!should_replace(&existing, &new));
     }
+
+    /// A VIC median as parse_vic_sales writes it
+    fn aggregate_record() -> PropertyRecord {
+        let mut record = mock_record();
+        record.external_id = Some("vic_median:TESTVILLE:house:all:2023".to_string());
+        record.address = "TESTVILLE MEDIAN (HOUSE)".to_string();
+        record.state = State::VIC;
+        record.postcode = None;
+        record.bedrooms = None;
+        record.weekly_rent = None;
+        record.rental_yield = None;
+        record.source_metadata.source_id = "vic_sales".to_string();
+        record.source_metadata.data_quality = DataQuality::Aggregated;
+        record.source_metadata.is_rental_estimated = false;
+        record
+    }
+
+    #[test]
+    fn test_individual_preferred_over_aggregate() {
+        let individual = PropertyRow {
+            id: 1,
+            address: "10 Test St".to_string(),
+            suburb: "Testville".to_string(),
+            state: State::NSW,
+            postcode: Some("2000".to_string()),
+            bedrooms: Some(3),
+            price: Some(800_000),
+            weekly_rent: Some(600),
+            property_type: Some(PropertyType::House),
+            data_source: Some("nsw_sales".to_string()),
+            data_quality: Some(DataQuality::Individual),
+            confidence_score: Some(rust_decimal::Decimal::new(5, 1)), // 0.5
+            external_id: Some("test-123".to_string()),
+        };
+
+        // Existing: 100 * 0.5 = 50
+        // New: 50 * 0.8 = 40 -> even a low-confidence sale isn't replaced
+        assert!(!should_replace(&individual, &aggregate_record()));
+
+        // Existing: 50 * 0.8 = 40
+        // New: 100 * 0.8 = 80 -> a sale replaces a median
+        let aggregate = PropertyRow {
+            data_quality: Some(DataQuality::Aggregated),
+            confidence_score: Some(rust_decimal::Decimal::new(8, 1)),
+            ..individual
+        };
+        assert!(should_replace(&aggregate, &mock_record()));
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let before = serde_json::json!({ "id": 1, "price": 700000, "weekly_rent": null });
+        let after = serde_json::json!({ "id": 1, "price": 750000, "weekly_rent": 620 });
+
+        let changes = diff_snapshots(&before, &after);
+
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    field: "price".to_string(),
+                    before: 700000.into(),
+                    after: 750000.into(),
+                },
+                FieldChange {
+                    field: "weekly_rent".to_string(),
+                    before: Value::Null,
+                    after: 620.into(),
+                },
+            ]
+        );
+        assert!(diff_snapshots(&before, &before).is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_large_update_is_flagged() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let suburb = "Drift Testville";
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+
+        let mut stored = mock_record();
+        stored.external_id = Some("drift-test-1".to_string());
+        stored.suburb = suburb.to_string();
+        stored.sale_price = Some(400_000);
+        stored.source_metadata.data_quality = DataQuality::Estimated;
+        stored.source_metadata.confidence_score = 0.5;
+        let config = DriftConfig::default();
+        write_properties_with(&db, vec![stored.clone()], &config)
+            .await
+            .unwrap();
+
+        // Better quality, so it replaces the row; price up 150%, rent unchanged
+        let update = PropertyRecord {
+            sale_price: Some(1_000_000),
+            source_metadata: mock_record().source_metadata,
+            ..stored
+        };
+        let stats = write_properties_with(&db, vec![update], &config)
+            .await
+            .unwrap();
+
+        assert_eq!(stats.updated, 1);
+        assert_eq!(stats.drift.price.changed[6], 1);
+        assert_eq!(stats.drift.weekly_rent.unchanged, 1);
+
+        let (kind, details) = sqlx::query_as::<_, (String, Value)>(
+            r#"
+            SELECT e.kind, e.details
+            FROM ingestion_errors e
+            JOIN properties p ON p.id = e.property_id
+            WHERE p.suburb = $1
+            "#,
+        )
+        .bind(suburb)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(kind, "suspicious_update");
+        assert_eq!(details["before"]["price"], 400_000);
+        assert_eq!(details["changes"]["price"], 1.5);
+
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_vic_aggregates_never_replace_individual_sales() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let suburb = "Aggregate Testville";
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+
+        let mut sale = mock_record();
+        sale.external_id = Some("aggregate-test-1".to_string());
+        sale.suburb = suburb.to_string();
+        let mut median = aggregate_record();
+        median.suburb = suburb.to_string();
+        median.sale_price = Some(1_500_000);
+        let config = DriftConfig::default();
+
+        // Their own external_id and no postcode, so the median is a row of its own
+        let stats = write_properties_with(&db, vec![sale.clone(), median.clone()], &config)
+            .await
+            .unwrap();
+        assert_eq!(stats.inserted, 2);
+
+        // Even matched to the sale, the median scores too low to replace it
+        let clash = PropertyRecord {
+            external_id: sale.external_id.clone(),
+            state: State::NSW,
+            ..median
+        };
+        let stats = write_properties_with(&db, vec![clash], &config)
+            .await
+            .unwrap();
+        assert_eq!(stats.skipped, 1);
+
+        let (price, quality) = sqlx::query_as::<_, (i64, DataQuality)>(
+            "SELECT price, data_quality FROM properties WHERE external_id = $1",
+        )
+        .bind(&sale.external_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(price, 800_000);
+        assert_eq!(quality, DataQuality::Individual);
+
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_address_forms_match_one_property() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let suburb = "Address Form Testville";
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+
+        let config = DriftConfig::default();
+        // No external_id, so only the address can match
+        let mut record = mock_record();
+        record.external_id = None;
+        record.suburb = suburb.to_string();
+        record.address = "Unit 2, 10 Smith St".to_string();
+        let stats = write_properties_with(&db, vec![record.clone()], &config)
+            .await
+            .unwrap();
+        assert_eq!(stats.inserted, 1);
+
+        // Another source's form of the same address, with a better score
+        record.address = "2/10 Smith Street".to_string();
+        record.source_metadata.confidence_score = 1.0;
+        record.sale_price = Some(820_000);
+        let stats = write_properties_with(&db, vec![record], &config)
+            .await
+            .unwrap();
+        assert_eq!((stats.inserted, stats.updated), (0, 1));
+
+        let addresses = sqlx::query_scalar::<_, String>(
+            "SELECT address FROM properties WHERE suburb = $1",
+        )
+        .bind(suburb)
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(addresses, ["2/10 Smith Street"]);
+
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_prior_sales_written_to_history() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let suburb = "Prior Sales Testville";
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut record = mock_record();
+        record.external_id = Some("prior-sales-test-1".to_string());
+        record.suburb = suburb.to_string();
+        record.prior_sales = vec![(520_000, date(2012, 3, 9)), (690_000, date(2018, 7, 2))];
+        let config = DriftConfig::default();
+
+        // Written twice, as a rerun of the same file would
+        for _ in 0..2 {
+            write_properties_with(&db, vec![record.clone()], &config)
+                .await
+                .unwrap();
+        }
+
+        let history = sqlx::query_as::<_, (i64, NaiveDate)>(
+            r#"
+            SELECT h.sale_price, h.sale_date FROM sales_history h
+            JOIN properties p ON p.id = h.property_id
+            WHERE p.external_id = $1
+            ORDER BY h.sale_date
+            "#,
+        )
+        .bind(&record.external_id)
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(
+            history,
+            vec![
+                (520_000, date(2012, 3, 9)),
+                (690_000, date(2018, 7, 2)),
+                (800_000, date(2024, 1, 15)),
+            ]
+        );
+
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_rental_medians_recorded_against_source() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let postcode = "4999";
+        let clear = || {
+            sqlx::query("DELETE FROM rental_medians WHERE postcode = $1")
+                .bind(postcode)
+                .execute(&db)
+        };
+        clear().await.unwrap();
+
+        let median = RentalMedian {
+            state: State::QLD,
+            postcode: postcode.to_string(),
+            suburb: None,
+            bedrooms: 2,
+            median_weekly_rent: 650,
+            sample_size: Some(40),
+            dwelling_type: None,
+            period: chrono::NaiveDate::from_ymd_opt(2024, 9, 30).unwrap(),
+        };
+        let stats = write_rental_medians(&db, vec![median.clone()], "qld_rentals")
+            .await
+            .unwrap();
+        assert_eq!(stats.inserted, 1);
+        // The same quarter again is already there
+        let stats = write_rental_medians(&db, vec![median], "qld_rentals")
+            .await
+            .unwrap();
+        assert_eq!(stats.skipped, 1);
+
+        let source: String =
+            sqlx::query_scalar("SELECT data_source FROM rental_medians WHERE postcode = $1")
+                .bind(postcode)
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(source, "qld_rentals");
+
+        clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_wa_and_nsw_records_never_match() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let suburb = "Cross State Testville";
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+
+        // Same external_id, address and postcode; only the state differs
+        let mut nsw = mock_record();
+        nsw.external_id = Some("cross-state-1".to_string());
+        nsw.suburb = suburb.to_string();
+        let wa = PropertyRecord {
+            state: State::WA,
+            source_metadata: SourceMetadata {
+                source_id: "wa_sales".to_string(),
+                confidence_score: 0.95,
+                ..nsw.source_metadata.clone()
+            },
+            ..nsw.clone()
+        };
+        let config = DriftConfig::default();
+        write_properties_with(&db, vec![nsw.clone()], &config)
+            .await
+            .unwrap();
+
+        let mut conn = db.acquire().await.unwrap();
+        assert!(find_existing_property(&mut conn, &wa)
+            .await
+            .unwrap()
+            .is_none());
+
+        let stats = write_properties_with(&db, vec![wa.clone()], &config)
+            .await
+            .unwrap();
+        assert_eq!(stats.inserted, 1);
+
+        let nsw_row = find_existing_property(&mut conn, &nsw).await.unwrap().unwrap();
+        let wa_row = find_existing_property(&mut conn, &wa).await.unwrap().unwrap();
+        assert_ne!(nsw_row.id, wa_row.id);
+        assert_eq!(nsw_row.state, State::NSW);
+        assert_eq!(wa_row.state, State::WA);
+
+        crate::test_support::delete_suburb(&db, suburb).await.unwrap();
+    }
 }
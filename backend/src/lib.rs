@@ -1,14 +1,239 @@
 // Library module for testable functions
 
+pub mod analytics;
+pub mod api;
+pub mod format;
 pub mod ingestion;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 
-/// Calculate rental yield percentage
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Why a yield calculation has no answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum CalculationError {
+    #[error("price must be above zero")]
+    NonPositivePrice,
+    #[error("rent can't be negative")]
+    NegativeRent,
+    #[error("target yield must be above zero")]
+    NonPositiveTarget,
+    #[error("the result is too large")]
+    Overflow,
+}
+
+/// How often a quoted rent is paid
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RentFrequency {
+    #[default]
+    Weekly,
+    Fortnightly,
+    Monthly,
+    Annual,
+}
+
+impl RentFrequency {
+    /// Payments in a year; a month is a twelfth of a year, never 4 weeks
+    pub fn periods_per_year(self) -> i32 {
+        match self {
+            RentFrequency::Weekly => 52,
+            RentFrequency::Fortnightly => 26,
+            RentFrequency::Monthly => 12,
+            RentFrequency::Annual => 1,
+        }
+    }
+
+    /// A year's rent at `amount` a period, widened so it can't overflow
+    pub fn annualize(self, amount: i32) -> Decimal {
+        Decimal::from(i64::from(amount) * i64::from(self.periods_per_year()))
+    }
+
+    /// The weekly rent equivalent to `amount` a period, rounded half-up to
+    /// whole dollars as weekly rents are stored
+    pub fn to_weekly(self, amount: i32) -> i32 {
+        (self.annualize(amount) / Decimal::from(52))
+            .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+            .to_i32()
+            .expect("a weekly equivalent is no larger than the amount")
+    }
+}
+
+/// Calculate rental yield percentage, rounded to its stored precision
 /// Formula: (weekly_rent × 52 / price) × 100
-pub fn calculate_rental_yield(price: i32, weekly_rent: i32) -> Option<f32> {
+/// The only place a yield is calculated, so stored and displayed values agree
+pub fn calculate_rental_yield(price: i64, weekly_rent: i32) -> Option<Decimal> {
+    try_calculate_rental_yield(price, weekly_rent).ok()
+}
+
+/// `calculate_rental_yield`, saying why there is no yield
+pub fn try_calculate_rental_yield(
+    price: i64,
+    weekly_rent: i32,
+) -> Result<Decimal, CalculationError> {
+    try_rental_yield(price, weekly_rent, RentFrequency::Weekly)
+}
+
+/// `calculate_rental_yield` for rent quoted at any frequency
+/// Formula: (rent × periods a year / price) × 100
+pub fn calculate_rental_yield_with_frequency(
+    price: i64,
+    rent: i32,
+    frequency: RentFrequency,
+) -> Option<Decimal> {
+    try_rental_yield(price, rent, frequency).ok()
+}
+
+fn try_rental_yield(
+    price: i64,
+    rent: i32,
+    frequency: RentFrequency,
+) -> Result<Decimal, CalculationError> {
+    if price <= 0 {
+        return Err(CalculationError::NonPositivePrice);
+    }
+    if rent < 0 {
+        return Err(CalculationError::NegativeRent);
+    }
+    let annual_rent = frequency.annualize(rent);
+    let yield_pct = annual_rent / Decimal::from(price) * Decimal::from(100);
+    Ok(format::round_yield_for_storage(yield_pct))
+}
+
+/// Smallest land area a price per square metre is calculated over; anything
+/// smaller is a data error rather than a real lot
+pub const MIN_LAND_AREA_SQM: Decimal = Decimal::TEN;
+
+/// Calculate price per square metre of land, to the cent
+/// None without a positive price or with an area under MIN_LAND_AREA_SQM
+pub fn price_per_sqm(price: i64, land_area_sqm: Decimal) -> Option<Decimal> {
+    if price <= 0 || land_area_sqm < MIN_LAND_AREA_SQM {
+        return None;
+    }
+    let per_sqm = Decimal::from(price) / land_area_sqm;
+    Some(per_sqm.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero))
+}
+
+/// Lowest weekly rent whose yield at `price` reaches `target_yield_pct`,
+/// rounded up to the next dollar
+/// Formula: price × target / 100 / 52
+pub fn required_weekly_rent(
+    price: i64,
+    target_yield_pct: Decimal,
+) -> Result<i32, CalculationError> {
+    if price <= 0 {
+        return Err(CalculationError::NonPositivePrice);
+    }
+    if target_yield_pct <= Decimal::ZERO {
+        return Err(CalculationError::NonPositiveTarget);
+    }
+    Decimal::from(price)
+        .checked_mul(target_yield_pct)
+        .map(|annual| annual / Decimal::from(5200))
+        .and_then(|rent| rent.ceil().to_i32())
+        .ok_or(CalculationError::Overflow)
+}
+
+/// Highest price at which `weekly_rent` still yields `target_yield_pct`,
+/// rounded down to the dollar
+/// Formula: weekly_rent × 52 × 100 / target
+pub fn max_price_for_yield(
+    weekly_rent: i32,
+    target_yield_pct: Decimal,
+) -> Result<i64, CalculationError> {
+    if weekly_rent < 0 {
+        return Err(CalculationError::NegativeRent);
+    }
+    if target_yield_pct <= Decimal::ZERO {
+        return Err(CalculationError::NonPositiveTarget);
+    }
+    (RentFrequency::Weekly.annualize(weekly_rent) * Decimal::ONE_HUNDRED)
+        .checked_div(target_yield_pct)
+        .and_then(|price| price.floor().to_i64())
+        .ok_or(CalculationError::Overflow)
+}
+
+/// Yearly costs of holding a rental, for `calculate_net_yield`
+/// Amounts are AUD a year; the management fee is charged on the rent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnnualExpenses {
+    pub council_rates: i32,
+    pub strata: i32,
+    pub insurance: i32,
+    /// Percent of annual rent, e.g. 7 for 7%
+    pub management_fee_pct: Decimal,
+    pub maintenance: i32,
+}
+
+impl Default for AnnualExpenses {
+    /// Typical NSW costs for a freestanding house: no strata, an agent on
+    /// 7% of rent and a maintenance allowance
+    fn default() -> Self {
+        AnnualExpenses {
+            council_rates: 1_800,
+            strata: 0,
+            insurance: 1_500,
+            management_fee_pct: Decimal::from(7),
+            maintenance: 2_000,
+        }
+    }
+}
+
+impl AnnualExpenses {
+    /// Every cost for a year at `annual_rent`, AUD
+    pub fn total(&self, annual_rent: Decimal) -> Decimal {
+        let management_fee = annual_rent * self.management_fee_pct / Decimal::from(100);
+        let fixed = self.council_rates + self.strata + self.insurance + self.maintenance;
+        Decimal::from(fixed) + management_fee
+    }
+}
+
+/// Calculate net rental yield percentage, rounded to its stored precision
+/// Formula: (weekly_rent × 52 − expenses) / price × 100
+/// Negative when expenses exceed the rent, so poor deals show as such
+pub fn calculate_net_yield(
+    price: i64,
+    weekly_rent: i32,
+    expenses: &AnnualExpenses,
+) -> Option<Decimal> {
     if price <= 0 {
         return None;
     }
-    Some((weekly_rent as f32 * 52.0 / price as f32) * 100.0)
+    let annual_rent = RentFrequency::Weekly.annualize(weekly_rent);
+    let net_rent = annual_rent - expenses.total(annual_rent);
+    let yield_pct = net_rent / Decimal::from(price) * Decimal::from(100);
+    Some(format::round_yield_for_storage(yield_pct))
+}
+
+/// Shortest gap between two sales that growth is annualized over; over
+/// shorter gaps small price differences compound into absurd annual rates
+pub const MIN_GROWTH_INTERVAL_DAYS: i64 = 90;
+
+/// Calculate compound annual growth percentage between two sales
+/// Formula: ((last_price / first_price) ^ (365.25 / days) − 1) × 100
+/// None for sales under MIN_GROWTH_INTERVAL_DAYS apart, same-day resales
+/// included, or without two positive prices. Negative when the later sale was
+/// cheaper.
+pub fn calculate_annualized_growth(
+    first_price: i64,
+    first_date: NaiveDate,
+    last_price: i64,
+    last_date: NaiveDate,
+) -> Option<f64> {
+    if first_price <= 0 || last_price <= 0 {
+        return None;
+    }
+    let days = (last_date - first_date).num_days();
+    if days < MIN_GROWTH_INTERVAL_DAYS {
+        return None;
+    }
+    let years = days as f64 / 365.25;
+    let ratio = last_price as f64 / first_price as f64;
+    Some((ratio.powf(1.0 / years) - 1.0) * 100.0)
 }
 
 #[cfg(test)]
@@ -21,7 +246,7 @@ mod tests {
         let yield_val = calculate_rental_yield(650000, 550);
         assert!(yield_val.is_some());
         let yield_val = yield_val.unwrap();
-        assert!((yield_val - 4.4).abs() < 0.01);
+        assert_eq!(yield_val, Decimal::new(44, 1));
     }
 
     #[test]
@@ -30,7 +255,7 @@ mod tests {
         let yield_val = calculate_rental_yield(480000, 420);
         assert!(yield_val.is_some());
         let yield_val = yield_val.unwrap();
-        assert!((yield_val - 4.55).abs() < 0.01);
+        assert_eq!(yield_val, Decimal::new(455, 2));
     }
 
     #[test]
@@ -53,7 +278,7 @@ mod tests {
         let yield_val = calculate_rental_yield(260000, 500);
         assert!(yield_val.is_some());
         let yield_val = yield_val.unwrap();
-        assert!((yield_val - 10.0).abs() < 0.01);
+        assert_eq!(yield_val, Decimal::from(10));
     }
 
     #[test]
@@ -62,6 +287,307 @@ mod tests {
         let yield_val = calculate_rental_yield(1300000, 500);
         assert!(yield_val.is_some());
         let yield_val = yield_val.unwrap();
-        assert!((yield_val - 2.0).abs() < 0.01);
+        assert_eq!(yield_val, Decimal::from(2));
+    }
+
+    #[test]
+    fn test_try_rental_yield_errors() {
+        assert_eq!(
+            try_calculate_rental_yield(0, 500),
+            Err(CalculationError::NonPositivePrice)
+        );
+        assert_eq!(
+            try_calculate_rental_yield(-100000, 500),
+            Err(CalculationError::NonPositivePrice)
+        );
+        // A negative rent used to come out as a negative yield
+        assert_eq!(
+            try_calculate_rental_yield(500000, -500),
+            Err(CalculationError::NegativeRent)
+        );
+        assert_eq!(calculate_rental_yield(500000, -500), None);
+        assert_eq!(try_calculate_rental_yield(500000, 0), Ok(Decimal::ZERO));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_rental_yield_at_i32_max() {
+        // 2,147,483,647 × 52 overflows an i32; 1 / 1 × 52 × 100 doesn't matter
+        assert_eq!(
+            try_calculate_rental_yield(i32::MAX.into(), i32::MAX),
+            Ok(Decimal::from(5200))
+        );
+        assert_eq!(
+            RentFrequency::Weekly.annualize(i32::MAX),
+            Decimal::from(111_669_149_644_i64)
+        );
+        assert_eq!(
+            try_calculate_rental_yield(1, i32::MAX),
+            Ok(Decimal::from(11_166_914_964_400_i64))
+        );
+    }
+
+    #[test]
+    fn test_rental_yield_rounded_for_storage() {
+        // 31,200 / 700,000 = 4.4571428...%
+        let yield_val = calculate_rental_yield(700000, 600).unwrap();
+        assert_eq!(yield_val, Decimal::new(44571, 4));
+    }
+
+    #[test]
+    fn test_net_yield_default_expenses() {
+        // 28,600 rent - (5,300 fixed + 2,002 management) = 21,298 / 650,000
+        let yield_val = calculate_net_yield(650000, 550, &AnnualExpenses::default()).unwrap();
+        assert_eq!(yield_val, Decimal::new(32766, 4));
+    }
+
+    #[test]
+    fn test_net_yield_management_fee_on_rent() {
+        // 10% of 26,000 rent, not of the price: 23,400 / 500,000
+        let expenses = AnnualExpenses {
+            council_rates: 0,
+            strata: 0,
+            insurance: 0,
+            management_fee_pct: Decimal::from(10),
+            maintenance: 0,
+        };
+        let yield_val = calculate_net_yield(500000, 500, &expenses).unwrap();
+        assert_eq!(yield_val, Decimal::new(468, 2));
+    }
+
+    #[test]
+    fn test_net_yield_strata_unit() {
+        // 31,200 rent - (2,400 rates + 4,000 strata + 500 insurance
+        // + 1,000 maintenance + 1,716 management) = 21,584 / 600,000
+        let expenses = AnnualExpenses {
+            council_rates: 2400,
+            strata: 4000,
+            insurance: 500,
+            management_fee_pct: Decimal::new(55, 1),
+            maintenance: 1000,
+        };
+        let yield_val = calculate_net_yield(600000, 600, &expenses).unwrap();
+        assert_eq!(yield_val, Decimal::new(35973, 4));
+    }
+
+    #[test]
+    fn test_net_yield_negative_not_clamped() {
+        // 15,600 rent - (17,300 fixed + 1,092 management) = -2,792 / 1,000,000
+        let expenses = AnnualExpenses {
+            strata: 12000,
+            ..AnnualExpenses::default()
+        };
+        let yield_val = calculate_net_yield(1000000, 300, &expenses).unwrap();
+        assert_eq!(yield_val, Decimal::new(-2792, 4));
+    }
+
+    #[test]
+    fn test_net_yield_without_expenses_is_gross() {
+        let expenses = AnnualExpenses {
+            council_rates: 0,
+            strata: 0,
+            insurance: 0,
+            management_fee_pct: Decimal::ZERO,
+            maintenance: 0,
+        };
+        assert_eq!(
+            calculate_net_yield(700000, 600, &expenses),
+            calculate_rental_yield(700000, 600)
+        );
+        assert!(calculate_net_yield(0, 500, &expenses).is_none());
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn assert_growth(growth: Option<f64>, expected: f64) {
+        let growth = growth.unwrap();
+        assert!((growth - expected).abs() < 0.0001, "{}", growth);
+    }
+
+    #[test]
+    fn test_annualized_growth_doubling() {
+        // Doubling over 3,652 days: 2 ^ (365.25 / 3652) - 1
+        let growth =
+            calculate_annualized_growth(500000, date(2014, 1, 1), 1000000, date(2024, 1, 1));
+        assert_growth(growth, 7.1784);
+    }
+
+    #[test]
+    fn test_annualized_growth_part_years() {
+        let growth =
+            calculate_annualized_growth(500000, date(2020, 6, 15), 650000, date(2024, 3, 1));
+        assert_growth(growth, 7.3283);
+
+        // Exactly the minimum interval is annualized
+        let growth =
+            calculate_annualized_growth(500000, date(2024, 1, 1), 510000, date(2024, 3, 31));
+        assert_growth(growth, 8.3683);
+    }
+
+    #[test]
+    fn test_annualized_growth_price_fall_is_negative() {
+        let growth =
+            calculate_annualized_growth(800000, date(2022, 3, 1), 720000, date(2024, 3, 1));
+        assert_growth(growth, -5.1283);
+
+        let growth =
+            calculate_annualized_growth(600000, date(2020, 1, 1), 600000, date(2023, 1, 1));
+        assert_growth(growth, 0.0);
+    }
+
+    #[test]
+    fn test_annualized_growth_short_interval() {
+        // Same-day resale, 89 days, and dates out of order
+        for (first, last) in [
+            (date(2024, 1, 1), date(2024, 1, 1)),
+            (date(2024, 1, 1), date(2024, 3, 30)),
+            (date(2024, 1, 1), date(2020, 1, 1)),
+        ] {
+            assert!(calculate_annualized_growth(500000, first, 550000, last).is_none());
+        }
+    }
+
+    #[test]
+    fn test_annualized_growth_non_positive_price() {
+        let (first, last) = (date(2020, 1, 1), date(2024, 1, 1));
+        assert!(calculate_annualized_growth(0, first, 500000, last).is_none());
+        assert!(calculate_annualized_growth(500000, first, -1, last).is_none());
+    }
+
+    #[test]
+    fn test_rent_frequency_conversions() {
+        assert_eq!(RentFrequency::Weekly.annualize(600), Decimal::from(31200));
+        assert_eq!(
+            RentFrequency::Fortnightly.annualize(1200),
+            Decimal::from(31200)
+        );
+        assert_eq!(RentFrequency::Monthly.annualize(2600), Decimal::from(31200));
+        assert_eq!(RentFrequency::Annual.annualize(31200), Decimal::from(31200));
+
+        assert_eq!(RentFrequency::Weekly.to_weekly(600), 600);
+        assert_eq!(RentFrequency::Fortnightly.to_weekly(1200), 600);
+        assert_eq!(RentFrequency::Monthly.to_weekly(2600), 600);
+        // 24,000 / 52 = 461.54
+        assert_eq!(RentFrequency::Monthly.to_weekly(2000), 462);
+        // 26 / 52 = 0.5 rounds up
+        assert_eq!(RentFrequency::Annual.to_weekly(26), 1);
+        assert_eq!(RentFrequency::default(), RentFrequency::Weekly);
+    }
+
+    #[test]
+    fn test_rental_yield_same_across_frequencies() {
+        // 10 a week is 520 a year however it is quoted
+        assert_eq!(RentFrequency::Weekly.annualize(10), Decimal::from(520));
+
+        let weekly = calculate_rental_yield(700000, 600);
+        for (rent, frequency) in [
+            (1200, RentFrequency::Fortnightly),
+            (2600, RentFrequency::Monthly),
+            (31200, RentFrequency::Annual),
+        ] {
+            let yield_val = calculate_rental_yield_with_frequency(700000, rent, frequency);
+            assert_eq!(yield_val, weekly, "{:?}", frequency);
+        }
+
+        // Treating a month as 4 weeks understates the yield
+        assert!(
+            calculate_rental_yield_with_frequency(700000, 600 * 4, RentFrequency::Monthly) < weekly
+        );
+        assert!(calculate_rental_yield_with_frequency(0, 2600, RentFrequency::Monthly).is_none());
+    }
+
+    #[test]
+    fn test_required_weekly_rent() {
+        // 650,000 × 5% = 32,500 a year = 625 a week exactly
+        assert_eq!(required_weekly_rent(650000, Decimal::from(5)), Ok(625));
+        // 31,500 a year is 605.77 a week, so 606
+        assert_eq!(required_weekly_rent(700000, Decimal::new(45, 1)), Ok(606));
+        assert_eq!(
+            required_weekly_rent(0, Decimal::from(5)),
+            Err(CalculationError::NonPositivePrice)
+        );
+        assert_eq!(
+            required_weekly_rent(650000, Decimal::ZERO),
+            Err(CalculationError::NonPositiveTarget)
+        );
+        assert_eq!(
+            required_weekly_rent(i64::MAX, Decimal::MAX),
+            Err(CalculationError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_max_price_for_yield() {
+        // 500 a week is 26,000 a year, 5% of 520,000
+        assert_eq!(max_price_for_yield(500, Decimal::from(5)), Ok(520000));
+        // 31,200 / 0.07 = 445,714.29
+        assert_eq!(max_price_for_yield(600, Decimal::from(7)), Ok(445714));
+        // No rent supports no price
+        assert_eq!(max_price_for_yield(0, Decimal::from(5)), Ok(0));
+        assert_eq!(
+            max_price_for_yield(-1, Decimal::from(5)),
+            Err(CalculationError::NegativeRent)
+        );
+        assert_eq!(
+            max_price_for_yield(500, Decimal::new(-1, 0)),
+            Err(CalculationError::NonPositiveTarget)
+        );
+        // Past an i32, as prices are; and more than a Decimal can hold
+        assert_eq!(
+            max_price_for_yield(i32::MAX, Decimal::new(1, 2)),
+            Ok(1_116_691_496_440_000)
+        );
+        assert_eq!(
+            max_price_for_yield(i32::MAX, Decimal::new(1, 28)),
+            Err(CalculationError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_break_even_round_trips_with_yield() {
+        for price in [250000, 650000, 1234567, 9999999] {
+            for target in ["0.5", "3", "4.25", "5", "12.5", "50"] {
+                let target: Decimal = target.parse().unwrap();
+                let rent = required_weekly_rent(price, target).unwrap();
+                assert!(calculate_rental_yield(price, rent).unwrap() >= target);
+                // A dollar less falls short, or is level once rounded to
+                // stored precision (8,173 / 9,999,999 gives 4.24996%)
+                assert!(calculate_rental_yield(price, rent - 1).unwrap() <= target);
+
+                let max_price = max_price_for_yield(rent, target).unwrap();
+                assert!(max_price >= price);
+                assert!(calculate_rental_yield(max_price, rent).unwrap() >= target);
+                // A dollar more is at best level with the target once rounded
+                assert!(calculate_rental_yield(max_price + 1, rent).unwrap() <= target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_price_per_sqm() {
+        // 1,200,000 over 600 sqm
+        assert_eq!(
+            price_per_sqm(1200000, Decimal::from(600)),
+            Some(Decimal::from(2000))
+        );
+        // 850,000 / 556.3 = 1,527.95...
+        assert_eq!(
+            price_per_sqm(850000, Decimal::new(5563, 1)),
+            Some(Decimal::new(152795, 2))
+        );
+        // The smallest area accepted
+        assert_eq!(
+            price_per_sqm(500000, Decimal::TEN),
+            Some(Decimal::from(50000))
+        );
+    }
+
+    #[test]
+    fn test_price_per_sqm_missing_data() {
+        assert_eq!(price_per_sqm(500000, Decimal::ZERO), None);
+        assert_eq!(price_per_sqm(500000, Decimal::new(95, 1)), None);
+        assert_eq!(price_per_sqm(500000, Decimal::from(-600)), None);
+        assert_eq!(price_per_sqm(0, Decimal::from(600)), None);
+    }
+}
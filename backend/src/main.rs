@@ -1,160 +1,141 @@
-use axum::{
-    routing::get,
-    http::StatusCode,
-    Json, Router,
-    extract::State,
+use axum::{routing::get, Router};
+use real_estate_backend::analytics::suppression::SuppressionConfig;
+use real_estate_backend::api::admin::AdminConfig;
+use real_estate_backend::api::cache::ResponseCaches;
+use real_estate_backend::api::compression::CompressionConfig;
+use real_estate_backend::api::cors::CorsConfig;
+use real_estate_backend::api::export::ExportBudget;
+use real_estate_backend::api::map::MapConfig;
+use real_estate_backend::api::properties::{ListingCacheConfig, TopYieldsConfig};
+use real_estate_backend::api::server::ServerConfig;
+use real_estate_backend::api::tier::TierConfig;
+use real_estate_backend::api::{
+    self, health, ingestion_watch, logging, share, stats_refresh, AppState,
 };
-use real_estate_backend::calculate_rental_yield;
-use serde::{Deserialize, Serialize};
-use sqlx::postgres::PgPoolOptions;
-use sqlx::PgPool;
-use std::net::SocketAddr;
-use tower_http::cors::CorsLayer;
-
-#[derive(Clone)]
-struct AppState {
-    db: PgPool,
-}
-
-#[derive(Serialize, Deserialize)]
-struct ApiResponse {
-    message: String,
-    status: String,
-}
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info};
 
 #[tokio::main]
 async fn main() {
-    println!("🏠 Starting Realtor API server...");
-
     // Load environment variables
     dotenvy::dotenv().ok();
+    logging::init_tracing();
+    info!("Starting Realtor API server");
+
+    let server = ServerConfig::from_env().unwrap_or_else(|e| {
+        error!("Invalid server configuration: {}", e);
+        std::process::exit(1);
+    });
 
     // Get database URL from environment
     let database_url = std::env::var("DATABASE_URL")
         .expect("DATABASE_URL must be set in .env file");
 
     // Create database connection pool
-    println!("📦 Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
+    info!("Connecting to database");
+    let pool = server
+        .pool_options()
         .connect(&database_url)
         .await
         .expect("Failed to connect to database");
 
-    println!("✅ Database connected successfully");
+    info!("Database connected");
 
-    let state = AppState { db: pool };
+    // GET handlers read from a replica when DATABASE_READ_URL is set
+    let read_pool = api::read_pool_from_env(&pool, server.pool_options())
+        .await
+        .expect("Failed to connect to read database");
+    if std::env::var("DATABASE_READ_URL").is_ok_and(|url| !url.trim().is_empty()) {
+        info!("Read replica connected");
+    }
+
+    let caches = Arc::new(ResponseCaches::from_env());
+
+    // Optional background refresh of suburb statistics
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let refresh_task = std::env::var("STATS_REFRESH_INTERVAL")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(|secs| {
+            info!(interval_secs = secs, "Refreshing suburb statistics periodically");
+            stats_refresh::spawn_stats_refresh(
+                pool.clone(),
+                caches.clone(),
+                Duration::from_secs(secs),
+                shutdown_rx.clone(),
+            )
+        });
+
+    // Cached aggregates are dropped whenever an ingestion run completes
+    let watch_task = ingestion_watch::spawn_ingestion_watch(
+        pool.clone(),
+        caches.clone(),
+        ingestion_watch::interval_from_env(),
+        shutdown_rx,
+    );
+
+    let state = AppState {
+        db: pool,
+        read_db: read_pool,
+        suppression: SuppressionConfig::from_env(),
+        share_limiter: Arc::new(share::share_rate_limiter_from_env()),
+        caches,
+        admin: Arc::new(AdminConfig::from_env()),
+        export_budget: ExportBudget::from_env(),
+        tiers: Arc::new(TierConfig::from_env()),
+        map: MapConfig::from_env(),
+        top_yields: TopYieldsConfig::from_env(),
+        listing_cache: ListingCacheConfig::from_env(),
+    };
 
     let app = Router::new()
-        .route("/", get(health_check))
-        .route("/api/health", get(health_check))
-        .route("/api/properties", get(get_properties))
-        .layer(CorsLayer::permissive())
-        .with_state(state);
+        .route("/", get(health::liveness))
+        .merge(api::router_with_cors(&CorsConfig::from_env()));
+    let app = CompressionConfig::from_env().apply(app);
+    let app = logging::apply(app).with_state(state);
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3001));
-    println!("🚀 Server running on http://{}", addr);
+    let addr = server.addr();
+    info!(%addr, "Server running");
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
-}
-
-async fn health_check() -> Json<ApiResponse> {
-    Json(ApiResponse {
-        message: "Realtor API is running!".to_string(),
-        status: "ok".to_string(),
-    })
-}
-
-async fn get_properties(State(state): State<AppState>) -> Result<Json<Vec<Property>>, StatusCode> {
-    let properties = sqlx::query_as!(
-        PropertyRow,
-        r#"
-        SELECT
-            id,
-            address,
-            suburb,
-            state as "state: StateEnum",
-            bedrooms,
-            price,
-            weekly_rent,
-            latitude,
-            longitude
-        FROM properties
-        ORDER BY id
-        "#
-    )
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| {
-        eprintln!("Database error: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    // Convert to response format with calculated rental yield
-    let response: Vec<Property> = properties
-        .into_iter()
-        .map(|p| {
-            let rental_yield = if let (Some(price), Some(rent)) = (p.price, p.weekly_rent) {
-                calculate_rental_yield(price, rent)
-            } else {
-                None
-            };
-
-            Property {
-                id: p.id,
-                address: p.address,
-                suburb: p.suburb,
-                state: format!("{:?}", p.state),
-                bedrooms: p.bedrooms,
-                price: p.price,
-                weekly_rent: p.weekly_rent,
-                latitude: p.latitude,
-                longitude: p.longitude,
-                rental_yield,
-            }
-        })
-        .collect();
-
-    Ok(Json(response))
-}
-
-#[derive(sqlx::Type, Debug)]
-#[sqlx(type_name = "state_enum", rename_all = "UPPERCASE")]
-enum StateEnum {
-    NSW,
-    VIC,
-    QLD,
-    WA,
-    SA,
-    TAS,
-    ACT,
-    NT,
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+
+    // Stop background work before exiting
+    let _ = shutdown_tx.send(true);
+    if let Some(task) = refresh_task {
+        let _ = task.await;
+    }
+    let _ = watch_task.await;
+    info!("Server stopped");
 }
 
-#[derive(sqlx::FromRow)]
-struct PropertyRow {
-    id: i32,
-    address: String,
-    suburb: String,
-    state: StateEnum,
-    bedrooms: Option<i32>,
-    price: Option<i32>,
-    weekly_rent: Option<i32>,
-    latitude: Option<rust_decimal::Decimal>,
-    longitude: Option<rust_decimal::Decimal>,
+/// Resolve when the process receives Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
-
-#[derive(Serialize, Deserialize)]
-struct Property {
-    id: i32,
-    address: String,
-    suburb: String,
-    state: String,
-    bedrooms: Option<i32>,
-    price: Option<i32>,
-    weekly_rent: Option<i32>,
-    latitude: Option<rust_decimal::Decimal>,
-    longitude: Option<rust_decimal::Decimal>,
-    rental_yield: Option<f32>,
-}
\ No newline at end of file
@@ -0,0 +1,559 @@
+//! Test fixtures - typed builders for seeding the database in handler tests
+//!
+//! Properties, sales and rental medians are written through the same insert
+//! functions the ingestion pipeline uses, so fixtures pick up schema changes
+//! with the production code. Every required column has a default here; a new
+//! NOT NULL column only needs adding to the matching `new()`.
+//!
+//! Available to unit tests, and to other crates with the `test-support` feature.
+
+use crate::api::share::share_rate_limiter_from_env;
+use crate::api::AppState;
+use crate::ingestion::types::{
+    DataQuality, PropertyRecord, PropertyType, RentalMatchMethod, RentalMedian, SourceMetadata,
+    State,
+};
+use crate::ingestion::write;
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// App state over `db` (as both pools) with every setting at its default
+/// Override fields with struct update: `AppState { tiers, ..test_state(db) }`
+pub fn test_state(db: PgPool) -> AppState {
+    AppState {
+        db: db.clone(),
+        read_db: db,
+        suppression: Default::default(),
+        share_limiter: Arc::new(share_rate_limiter_from_env()),
+        caches: Default::default(),
+        admin: Default::default(),
+        export_budget: Default::default(),
+        tiers: Default::default(),
+        map: Default::default(),
+        top_yields: Default::default(),
+        listing_cache: Default::default(),
+    }
+}
+
+/// Every table tests write to, children before the tables they reference
+pub const TRUNCATE_ORDER: &[&str] = &[
+    "ingestion_errors",
+    "property_audit_log",
+    "sales_history",
+    "price_history",
+    "properties",
+    "properties_staging",
+    "staging_batches",
+    "rental_medians",
+    "rental_observations",
+    "suburb_statistics",
+    "region_statistics",
+    "regions",
+    "lga_correspondence",
+    "council_rates",
+    "shared_comparisons",
+    "saved_searches",
+    "ingestion_runs",
+    "ingestion_logs",
+    "source_watermarks",
+    "maintenance_watermarks",
+    "geocode_cache",
+    "gnaf_addresses",
+    "change_log",
+];
+
+/// Tables truncate_all resets rather than empties: the change log's one-row
+/// sequence counter has to stay for the properties trigger to take seqs from
+pub const RESET_TABLES: &[&str] = &["change_log_sequence"];
+
+/// Empty every table in TRUNCATE_ORDER, reset their id sequences and restart
+/// the change log's seqs
+/// Fails rather than cascading if a table outside the list references one of them.
+/// Only for disposable test databases.
+pub async fn truncate_all(db: &PgPool) -> Result<()> {
+    let mut tx = db.begin().await?;
+    sqlx::query(&format!(
+        "TRUNCATE {} RESTART IDENTITY",
+        TRUNCATE_ORDER.join(", ")
+    ))
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE change_log_sequence SET last_seq = 0")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Remove every property in a suburb, along with its sales and audit rows
+pub async fn delete_suburb(db: &PgPool, suburb: &str) -> Result<()> {
+    sqlx::query("DELETE FROM properties WHERE suburb = $1")
+        .bind(suburb)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// A property, inserted the way ingestion inserts one
+#[derive(Debug, Clone)]
+pub struct PropertyFixture {
+    record: PropertyRecord,
+    last_updated: Option<NaiveDateTime>,
+}
+
+impl Default for PropertyFixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PropertyFixture {
+    /// A 3 bedroom NSW house with no price, rent or coordinates
+    pub fn new() -> Self {
+        PropertyFixture {
+            record: PropertyRecord {
+                external_id: None,
+                address: "1 Test St".to_string(),
+                suburb: "Testville".to_string(),
+                state: State::NSW,
+                postcode: Some("2999".to_string()),
+                district: None,
+                property_type: PropertyType::House,
+                bedrooms: Some(3),
+                bathrooms: None,
+                land_area_sqm: None,
+                sale_price: None,
+                sale_date: None,
+                weekly_rent: None,
+                rent_frequency: None,
+                rental_yield: None,
+                price_per_sqm: None,
+                prior_sales: Vec::new(),
+                latitude: None,
+                longitude: None,
+                source_metadata: SourceMetadata {
+                    source_id: "test".to_string(),
+                    data_quality: DataQuality::Individual,
+                    fetched_at: Utc::now(),
+                    is_rental_estimated: false,
+                    is_bedrooms_estimated: false,
+                    rental_period: None,
+                    rental_match_method: None,
+                    source_file: None,
+                    source_row: None,
+                    confidence_score: 1.0,
+                },
+            },
+            last_updated: None,
+        }
+    }
+
+    pub fn address(mut self, address: &str) -> Self {
+        self.record.address = address.to_string();
+        self
+    }
+
+    pub fn suburb(mut self, suburb: &str) -> Self {
+        self.record.suburb = suburb.to_string();
+        self
+    }
+
+    pub fn state(mut self, state: State) -> Self {
+        self.record.state = state;
+        self
+    }
+
+    pub fn postcode(mut self, postcode: &str) -> Self {
+        self.record.postcode = Some(postcode.to_string());
+        self
+    }
+
+    pub fn district(mut self, district: &str) -> Self {
+        self.record.district = Some(district.to_string());
+        self
+    }
+
+    pub fn property_type(mut self, property_type: PropertyType) -> Self {
+        self.record.property_type = property_type;
+        self
+    }
+
+    pub fn bedrooms(mut self, bedrooms: i32) -> Self {
+        self.record.bedrooms = Some(bedrooms);
+        self
+    }
+
+    pub fn bedrooms_estimated(mut self, estimated: bool) -> Self {
+        self.record.source_metadata.is_bedrooms_estimated = estimated;
+        self
+    }
+
+    pub fn land_area_sqm(mut self, area: Decimal) -> Self {
+        self.record.land_area_sqm = Some(area);
+        self
+    }
+
+    /// Current price, without a sale in sales_history
+    pub fn price(mut self, price: i64) -> Self {
+        self.record.sale_price = Some(price);
+        self
+    }
+
+    /// Price plus a sales_history row, as ingestion records a sale
+    pub fn sold(mut self, price: i64, date: NaiveDate) -> Self {
+        self.record.sale_price = Some(price);
+        self.record.sale_date = Some(date);
+        self
+    }
+
+    pub fn weekly_rent(mut self, rent: i32) -> Self {
+        self.record.weekly_rent = Some(rent);
+        self
+    }
+
+    pub fn rental_estimated(mut self, estimated: bool) -> Self {
+        self.record.source_metadata.is_rental_estimated = estimated;
+        self
+    }
+
+    /// A rent matched from a median this way, which also marks it estimated
+    pub fn rental_match_method(mut self, method: RentalMatchMethod) -> Self {
+        self.record.source_metadata.is_rental_estimated = true;
+        self.record.source_metadata.rental_match_method = Some(method);
+        self
+    }
+
+    /// Yield in percent, e.g. "5.20"
+    pub fn rental_yield(mut self, rental_yield: &str) -> Self {
+        self.record.rental_yield = Some(rental_yield.parse().expect("decimal yield"));
+        self
+    }
+
+    pub fn coordinates(mut self, latitude: Decimal, longitude: Decimal) -> Self {
+        self.record.latitude = Some(latitude);
+        self.record.longitude = Some(longitude);
+        self
+    }
+
+    pub fn data_source(mut self, source_id: &str) -> Self {
+        self.record.source_metadata.source_id = source_id.to_string();
+        self
+    }
+
+    pub fn data_quality(mut self, data_quality: DataQuality, confidence_score: f32) -> Self {
+        self.record.source_metadata.data_quality = data_quality;
+        self.record.source_metadata.confidence_score = confidence_score;
+        self
+    }
+
+    /// Archive key and row the property was parsed from
+    pub fn source_row(mut self, source_file: &str, row: i32) -> Self {
+        self.record.source_metadata.source_file = Some(source_file.to_string());
+        self.record.source_metadata.source_row = Some(row);
+        self
+    }
+
+    /// Override last_updated, which otherwise defaults to the insert time
+    pub fn last_updated(mut self, last_updated: NaiveDateTime) -> Self {
+        self.last_updated = Some(last_updated);
+        self
+    }
+
+    pub fn record(&self) -> &PropertyRecord {
+        &self.record
+    }
+
+    /// Insert and return the property id
+    pub async fn insert(&self, db: &PgPool) -> Result<i32> {
+        let id = write::insert_property(&mut *db.acquire().await?, &self.record).await?;
+
+        if let Some(last_updated) = self.last_updated {
+            sqlx::query("UPDATE properties SET last_updated = $2 WHERE id = $1")
+                .bind(id)
+                .bind(last_updated)
+                .execute(db)
+                .await?;
+        }
+
+        Ok(id)
+    }
+}
+
+/// A sale of an existing property
+#[derive(Debug, Clone)]
+pub struct SaleFixture {
+    property_id: i32,
+    price: i64,
+    date: NaiveDate,
+    data_source: String,
+}
+
+impl SaleFixture {
+    pub fn new(property_id: i32, price: i64, date: NaiveDate) -> Self {
+        SaleFixture {
+            property_id,
+            price,
+            date,
+            data_source: "test".to_string(),
+        }
+    }
+
+    /// Insert and return the sale id
+    pub async fn insert(&self, db: &PgPool) -> Result<i32> {
+        write::insert_sale_history(
+            &mut *db.acquire().await?,
+            self.property_id,
+            self.price,
+            self.date,
+            &self.data_source,
+        )
+        .await
+    }
+}
+
+/// A rental median, inserted the way the rentals pipeline inserts one
+#[derive(Debug, Clone)]
+pub struct RentalMedianFixture {
+    median: RentalMedian,
+    data_source: String,
+}
+
+impl RentalMedianFixture {
+    /// 2 bedroom median of $500/week from 12 bonds, for `period`
+    pub fn new(postcode: &str, period: NaiveDate) -> Self {
+        RentalMedianFixture {
+            median: RentalMedian {
+                state: State::NSW,
+                postcode: postcode.to_string(),
+                suburb: None,
+                bedrooms: 2,
+                median_weekly_rent: 500,
+                sample_size: Some(12),
+                dwelling_type: None,
+                period,
+            },
+            data_source: "nsw_rentals".to_string(),
+        }
+    }
+
+    pub fn state(mut self, state: State) -> Self {
+        self.median.state = state;
+        self
+    }
+
+    pub fn suburb(mut self, suburb: &str) -> Self {
+        self.median.suburb = Some(suburb.to_string());
+        self
+    }
+
+    pub fn bedrooms(mut self, bedrooms: i32) -> Self {
+        self.median.bedrooms = bedrooms;
+        self
+    }
+
+    pub fn rent(mut self, median_weekly_rent: i32) -> Self {
+        self.median.median_weekly_rent = median_weekly_rent;
+        self
+    }
+
+    pub fn sample_size(mut self, sample_size: i32) -> Self {
+        self.median.sample_size = Some(sample_size);
+        self
+    }
+
+    /// A median for one dwelling type rather than all dwellings
+    pub fn dwelling_type(mut self, dwelling_type: PropertyType) -> Self {
+        self.median.dwelling_type = Some(dwelling_type);
+        self
+    }
+
+    pub fn data_source(mut self, source_id: &str) -> Self {
+        self.data_source = source_id.to_string();
+        self
+    }
+
+    /// Insert; false when a median for the same key and period already exists
+    pub async fn insert(&self, db: &PgPool) -> Result<bool> {
+        write::insert_rental_median(db, &self.median, &self.data_source).await
+    }
+}
+
+/// A suburb_statistics row for today
+/// Production rows come from `refresh_suburb_statistics`, which aggregates
+/// properties; this writes a row directly so stats can be set exactly.
+#[derive(Debug, Clone)]
+pub struct SuburbStatsFixture {
+    suburb: String,
+    postcode: Option<String>,
+    state: State,
+    bedrooms: Option<i32>,
+    median_price: Option<i32>,
+    median_weekly_rent: Option<i32>,
+    median_rental_yield: Option<Decimal>,
+    min_yield: Option<Decimal>,
+    max_yield: Option<Decimal>,
+    property_count: i32,
+    calculated_date: NaiveDate,
+}
+
+impl SuburbStatsFixture {
+    pub fn new(suburb: &str) -> Self {
+        SuburbStatsFixture {
+            suburb: suburb.to_string(),
+            postcode: Some("2999".to_string()),
+            state: State::NSW,
+            bedrooms: Some(3),
+            median_price: None,
+            median_weekly_rent: None,
+            median_rental_yield: None,
+            min_yield: None,
+            max_yield: None,
+            property_count: 10,
+            calculated_date: Utc::now().date_naive(),
+        }
+    }
+
+    pub fn postcode(mut self, postcode: &str) -> Self {
+        self.postcode = Some(postcode.to_string());
+        self
+    }
+
+    pub fn state(mut self, state: State) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn bedrooms(mut self, bedrooms: i32) -> Self {
+        self.bedrooms = Some(bedrooms);
+        self
+    }
+
+    pub fn median_price(mut self, price: i32) -> Self {
+        self.median_price = Some(price);
+        self
+    }
+
+    pub fn median_weekly_rent(mut self, rent: i32) -> Self {
+        self.median_weekly_rent = Some(rent);
+        self
+    }
+
+    /// Yield in percent, e.g. "5.20"
+    pub fn median_rental_yield(mut self, rental_yield: &str) -> Self {
+        self.median_rental_yield = Some(rental_yield.parse().expect("decimal yield"));
+        self
+    }
+
+    /// Lowest and highest yields in percent
+    pub fn yield_range(mut self, min: &str, max: &str) -> Self {
+        self.min_yield = Some(min.parse().expect("decimal yield"));
+        self.max_yield = Some(max.parse().expect("decimal yield"));
+        self
+    }
+
+    pub fn property_count(mut self, count: i32) -> Self {
+        self.property_count = count;
+        self
+    }
+
+    pub fn calculated_date(mut self, date: NaiveDate) -> Self {
+        self.calculated_date = date;
+        self
+    }
+
+    /// Insert and return the row id
+    pub async fn insert(&self, db: &PgPool) -> Result<i32> {
+        let id = sqlx::query_scalar::<_, i32>(
+            r#"
+            INSERT INTO suburb_statistics (
+                suburb, postcode, state, bedrooms, median_price, median_weekly_rent,
+                median_rental_yield, min_yield, max_yield, property_count,
+                calculated_date, data_source
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, 'test')
+            RETURNING id
+            "#,
+        )
+        .bind(&self.suburb)
+        .bind(&self.postcode)
+        .bind(self.state)
+        .bind(self.bedrooms)
+        .bind(self.median_price)
+        .bind(self.median_weekly_rent)
+        .bind(self.median_rental_yield)
+        .bind(self.min_yield)
+        .bind(self.max_yield)
+        .bind(self.property_count)
+        .bind(self.calculated_date)
+        .fetch_one(db)
+        .await?;
+
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_property_fixture_defaults_and_overrides() {
+        let fixture = PropertyFixture::new()
+            .suburb("Bondi")
+            .postcode("2026")
+            .sold(900_000, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap())
+            .rental_yield("4.10");
+        let record = fixture.record();
+
+        assert_eq!(record.suburb, "Bondi");
+        assert_eq!(record.postcode.as_deref(), Some("2026"));
+        assert_eq!(record.sale_price, Some(900_000));
+        assert_eq!(record.rental_yield, Some(Decimal::new(410, 2)));
+        assert_eq!(record.bedrooms, Some(3));
+        assert_eq!(record.state, State::NSW);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires DATABASE_URL pointing at a database with the schema applied
+    async fn test_fixtures_insert() {
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let suburb = "Fixture Testville";
+        delete_suburb(&db, suburb).await.unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let id = PropertyFixture::new()
+            .suburb(suburb)
+            .sold(900_000, date)
+            .insert(&db)
+            .await
+            .unwrap();
+        let later = SaleFixture::new(id, 950_000, date.succ_opt().unwrap())
+            .insert(&db)
+            .await
+            .unwrap();
+        // The same sale again is found rather than duplicated
+        let again = SaleFixture::new(id, 950_000, date.succ_opt().unwrap())
+            .insert(&db)
+            .await
+            .unwrap();
+        assert_eq!(later, again);
+
+        let sales = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM sales_history WHERE property_id = $1",
+        )
+        .bind(id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(sales, 2);
+
+        delete_suburb(&db, suburb).await.unwrap();
+    }
+}
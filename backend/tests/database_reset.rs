@@ -0,0 +1,76 @@
+//! truncate_all against a real database. Kept out of the library's tests,
+//! which share the database concurrently, since it empties every table.
+//! Run with `cargo test --features test-support --test database_reset -- --ignored`
+
+use chrono::NaiveDate;
+use real_estate_backend::test_support::{
+    truncate_all, PropertyFixture, SaleFixture, RESET_TABLES, TRUNCATE_ORDER,
+};
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+
+#[tokio::test]
+#[ignore] // Requires DATABASE_URL pointing at a disposable database with the schema applied
+async fn truncate_all_empties_every_table() {
+    let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+        .await
+        .unwrap();
+
+    // A new table has to be added to TRUNCATE_ORDER (or RESET_TABLES)
+    let tables: BTreeSet<String> = sqlx::query_scalar(
+        "SELECT table_name::TEXT FROM information_schema.tables \
+         WHERE table_schema = 'public' AND table_type = 'BASE TABLE'",
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap()
+    .into_iter()
+    .collect();
+    let listed: BTreeSet<String> = TRUNCATE_ORDER
+        .iter()
+        .chain(RESET_TABLES)
+        .map(|t| t.to_string())
+        .collect();
+    assert_eq!(tables, listed);
+
+    // A property with a sale fills a parent, a child and the change log
+    let id = PropertyFixture::new()
+        .suburb("Truncate Testville")
+        .insert(&db)
+        .await
+        .unwrap();
+    SaleFixture::new(id, 650_000, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap())
+        .insert(&db)
+        .await
+        .unwrap();
+
+    truncate_all(&db).await.unwrap();
+
+    for table in TRUNCATE_ORDER {
+        let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(count, 0, "{}", table);
+    }
+    let last_seq: i64 = sqlx::query_scalar("SELECT last_seq FROM change_log_sequence")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(last_seq, 0);
+
+    // Ids restart and the change log carries on from seq 1
+    let id = PropertyFixture::new()
+        .suburb("Truncate Testville")
+        .insert(&db)
+        .await
+        .unwrap();
+    assert_eq!(id, 1);
+    let seqs: Vec<i64> = sqlx::query_scalar("SELECT seq FROM change_log")
+        .fetch_all(&db)
+        .await
+        .unwrap();
+    assert_eq!(seqs, vec![1]);
+
+    truncate_all(&db).await.unwrap();
+}
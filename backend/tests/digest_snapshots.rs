@@ -0,0 +1,119 @@
+//! Snapshot tests for the rendered weekly digest
+//! Regenerate expectations with `BLESS=1 cargo test --test digest_snapshots`
+
+mod golden;
+
+use chrono::NaiveDate;
+use golden::assert_snapshot;
+use real_estate_backend::analytics::digest::{
+    render_html, render_json, yield_movers, Digest, DigestProperty, IngestionHealth,
+    SuburbYield, TOP_MOVERS,
+};
+use real_estate_backend::ingestion::{IngestionRun, State};
+
+fn property(id: i32, address: &str, suburb: &str, price: i64, rent: i32) -> DigestProperty {
+    DigestProperty {
+        id,
+        address: address.to_string(),
+        suburb: suburb.to_string(),
+        postcode: Some("2150".to_string()),
+        state: State::NSW,
+        bedrooms: Some(2),
+        price: Some(price),
+        weekly_rent: Some(rent),
+        rental_yield: (f64::from(rent) * 52.0 / price as f64 * 10_000.0).round() / 100.0,
+    }
+}
+
+fn suburb_yield(suburb: &str, bedrooms: i32, median_rental_yield: f64) -> SuburbYield {
+    SuburbYield {
+        suburb: suburb.to_string(),
+        postcode: Some("2150".to_string()),
+        state: State::NSW,
+        bedrooms: Some(bedrooms),
+        median_rental_yield,
+    }
+}
+
+fn run(source_id: &str, status: &str, inserted: i32) -> IngestionRun {
+    IngestionRun {
+        id: 0,
+        source_id: source_id.to_string(),
+        status: status.to_string(),
+        started_at: NaiveDate::from_ymd_opt(2025, 5, 27)
+            .unwrap()
+            .and_hms_opt(2, 0, 0)
+            .unwrap(),
+        completed_at: None,
+        records_fetched: inserted,
+        records_inserted: inserted,
+        records_updated: 0,
+        records_skipped: 0,
+        error_message: None,
+        progress: None,
+        metrics: None,
+        anomalies: None,
+        raw_files: None,
+        parse_errors: None,
+    }
+}
+
+fn week_ending() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()
+}
+
+fn seeded_digest() -> Digest {
+    let previous = [
+        suburb_yield("Parramatta", 2, 4.10),
+        suburb_yield("Parramatta", 3, 3.80),
+        suburb_yield("Harris Park", 2, 4.60),
+        suburb_yield("Westmead", 2, 4.00),
+    ];
+    let current = [
+        suburb_yield("Parramatta", 2, 4.35),
+        suburb_yield("Parramatta", 3, 3.80),
+        suburb_yield("Harris Park", 2, 4.15),
+        suburb_yield("Westmead", 2, 4.05),
+    ];
+
+    Digest {
+        week_starting: NaiveDate::from_ymd_opt(2025, 5, 26).unwrap(),
+        week_ending: week_ending(),
+        top_new_properties: vec![
+            property(11, "4/12 Church St", "Parramatta", 520_000, 560),
+            property(12, "7 Station St", "Harris Park", 610_000, 600),
+            property(13, "3 O'Connell St & Lane", "Parramatta", 880_000, 720),
+        ],
+        yield_movers: yield_movers(&current, &previous, TOP_MOVERS),
+        ingestion: IngestionHealth::from_runs(&[
+            run("nsw_sales", "completed", 1_240),
+            run("nsw_rentals", "completed_with_warnings", 310),
+            run("nsw_sales", "failed", 0),
+        ]),
+    }
+}
+
+fn empty_digest() -> Digest {
+    Digest {
+        week_starting: NaiveDate::from_ymd_opt(2025, 5, 26).unwrap(),
+        week_ending: week_ending(),
+        top_new_properties: Vec::new(),
+        yield_movers: Vec::new(),
+        ingestion: IngestionHealth::from_runs(&[]),
+    }
+}
+
+#[test]
+fn snapshot_weekly_digest_json() {
+    assert_snapshot("weekly_digest.json", &render_json(&seeded_digest()));
+}
+
+#[test]
+fn snapshot_weekly_digest_html() {
+    assert_snapshot("weekly_digest.html", &render_html(&seeded_digest()));
+}
+
+#[test]
+fn snapshot_weekly_digest_without_ingestion() {
+    assert_snapshot("weekly_digest_empty.html", &render_html(&empty_digest()));
+}
@@ -0,0 +1,177 @@
+//! Golden-file testing for parsers
+//!
+//! Each case serializes its parsed records to JSON, one per line, and compares
+//! them field-by-field with `tests/golden/expected/<name>.jsonl`. Run with
+//! `BLESS=1` to write the current output as the new expectation after an
+//! intentional parser change, then review the diff before committing.
+//!
+//! Rendered text (reports, digests) is compared whole with `assert_snapshot`
+//! against `tests/golden/expected/<name>`, blessed the same way.
+
+// Shared by several test binaries, each using only some of the helpers
+#![allow(dead_code)]
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+/// Path to a checked-in fixture input under `tests/fixtures`
+pub fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn expected_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden/expected")
+        .join(format!("{}.jsonl", name))
+}
+
+fn blessing() -> bool {
+    std::env::var("BLESS").is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// Compare `records` with the expected JSONL for `name`, or rewrite it under BLESS
+pub fn assert_golden<T: Serialize>(name: &str, records: &[T]) {
+    let actual: Vec<Value> = records
+        .iter()
+        .map(|r| serde_json::to_value(r).expect("record should serialize"))
+        .collect();
+    let path = expected_path(name);
+
+    if blessing() {
+        let mut out = String::new();
+        for value in &actual {
+            out.push_str(&serde_json::to_string(value).unwrap());
+            out.push('\n');
+        }
+        fs::write(&path, out).unwrap();
+        eprintln!("Blessed {} ({} records)", path.display(), actual.len());
+        return;
+    }
+
+    let contents = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "Missing golden file {} - run with BLESS=1 to create it",
+            path.display()
+        )
+    });
+    let expected: Vec<Value> = contents
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).expect("golden file should be valid JSONL"))
+        .collect();
+
+    let report = diff_records(&expected, &actual);
+    if !report.is_empty() {
+        panic!(
+            "Golden mismatch for {} ({}):\n{}\nRe-run with BLESS=1 if the change is intended.",
+            name,
+            path.display(),
+            report
+        );
+    }
+}
+
+/// Compare rendered `actual` text with `tests/golden/expected/<name>`, or rewrite it under BLESS
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden/expected")
+        .join(name);
+
+    if blessing() {
+        fs::write(&path, actual).unwrap();
+        eprintln!("Blessed {}", path.display());
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "Missing snapshot {} - run with BLESS=1 to create it",
+            path.display()
+        )
+    });
+    if expected != actual {
+        let mut report = String::new();
+        for (i, (e, a)) in expected.lines().zip(actual.lines()).enumerate() {
+            if e != a {
+                let _ = writeln!(
+                    report,
+                    "  line {}:
+    expected: {}
+    actual:   {}",
+                    i + 1,
+                    e,
+                    a
+                );
+            }
+        }
+        let (e, a) = (expected.lines().count(), actual.lines().count());
+        if e != a {
+            let _ = writeln!(report, "  line count: expected {}, got {}", e, a);
+        }
+        panic!(
+            "Snapshot mismatch for {}:\n{}\nRe-run with BLESS=1 if the change is intended.",
+            path.display(),
+            report
+        );
+    }
+}
+
+/// Human-readable list of differences, empty when the records match
+pub fn diff_records(expected: &[Value], actual: &[Value]) -> String {
+    let mut report = String::new();
+
+    if expected.len() != actual.len() {
+        let _ = writeln!(
+            report,
+            "  record count: expected {}, got {}",
+            expected.len(),
+            actual.len()
+        );
+    }
+
+    for (i, (e, a)) in expected.iter().zip(actual).enumerate() {
+        diff_value(&format!("record {}", i), e, a, &mut report);
+    }
+
+    for (i, extra) in actual.iter().enumerate().skip(expected.len()) {
+        let _ = writeln!(report, "  record {}: unexpected {}", i, extra);
+    }
+    for (i, missing) in expected.iter().enumerate().skip(actual.len()) {
+        let _ = writeln!(report, "  record {}: missing {}", i, missing);
+    }
+
+    report
+}
+
+fn diff_value(path: &str, expected: &Value, actual: &Value, report: &mut String) {
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => {
+            let mut keys: Vec<&String> = e.keys().chain(a.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let child = format!("{}.{}", path, key);
+                match (e.get(key), a.get(key)) {
+                    (Some(ev), Some(av)) => diff_value(&child, ev, av, report),
+                    (Some(ev), None) => {
+                        let _ = writeln!(report, "  {}: expected {}, field missing", child, ev);
+                    }
+                    (None, Some(av)) => {
+                        let _ = writeln!(report, "  {}: unexpected field {}", child, av);
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if expected != actual => {
+            let _ = writeln!(report, "  {}: expected {}, got {}", path, expected, actual);
+        }
+        _ => {}
+    }
+}
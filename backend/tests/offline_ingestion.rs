@@ -0,0 +1,219 @@
+//! Fetch then parse from checked-in fixtures given as local sources, with no
+//! network - the same way NSW_SALES_URL and NSW_RENTALS_URL can point at files
+
+mod golden;
+
+use chrono::NaiveDate;
+use golden::{assert_golden, fixture};
+use real_estate_backend::ingestion::archive::{self, RawArchive};
+use real_estate_backend::ingestion::fetch;
+use real_estate_backend::ingestion::parse;
+use real_estate_backend::ingestion::utils::{Fetcher, HttpConfig, HttpPolicy};
+use real_estate_backend::ingestion::validate::ValidationConfig;
+use real_estate_backend::ingestion::PropertyRecord;
+use tempfile::tempdir;
+
+/// Local sources never touch the network, so the defaults will do
+fn fetcher() -> Fetcher {
+    Fetcher::new(&HttpConfig::default(), HttpPolicy::default()).unwrap()
+}
+
+#[tokio::test]
+async fn offline_nsw_sales_dat_archive() {
+    // The Valuer General's own format, a .DAT file per district and week
+    let temp = tempdir().unwrap();
+    let url = format!("file://{}", fixture("nsw_sales_dat.zip").display());
+    let validation = ValidationConfig::default();
+
+    let fetched = fetch::fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &validation)
+        .await
+        .unwrap();
+    assert_eq!(
+        fetched.validation.checks[0].detail,
+        "1 entries, 0 CSV, 1 DAT"
+    );
+    let files = fetched.raw_data.as_file_paths().unwrap().to_vec();
+    assert!(
+        files[0].ends_with("001_SALES_DATA_NNME_08012024.DAT"),
+        "{:?}",
+        files
+    );
+
+    let report = parse::parse_nsw_sales(fetched.raw_data, "nsw_sales".to_string())
+        .await
+        .unwrap();
+    assert_eq!(report.records.len(), 4);
+    assert_eq!(report.records[0].address, "10 NEW SOUTH HEAD ROAD");
+    assert_eq!(report.errors.len(), 2);
+}
+
+#[tokio::test]
+async fn offline_nsw_sales_from_file_url() {
+    let temp = tempdir().unwrap();
+    let url = format!("file://{}", fixture("nsw_sales_archive.zip").display());
+    let validation = ValidationConfig {
+        expected_sha256: None,
+        min_records: 10,
+    };
+
+    let fetched = fetch::fetch_nsw_sales(&url, temp.path(), &fetcher(), false, &validation)
+        .await
+        .unwrap();
+    assert!(!fetched.unchanged);
+    // Extracted into temp_dir, leaving the fixtures alone
+    let csvs = fetched.raw_data.as_file_paths().unwrap().to_vec();
+    assert_eq!(csvs.len(), 1);
+    assert!(csvs[0].starts_with(temp.path()), "{:?}", csvs);
+
+    let records = parse::parse_nsw_sales(fetched.raw_data, "nsw_sales".to_string())
+        .await
+        .unwrap()
+        .records;
+    assert_eq!(records.len(), 10);
+    assert_eq!(records[0].suburb, "Sydney");
+    assert_eq!(records[9].sale_price, Some(845_000));
+
+    let mut report = fetched.validation;
+    report.check_record_count(records.len(), validation.min_records);
+    let names: Vec<_> = report.checks.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["zip", "record_count"]);
+    assert!(report.passed());
+}
+
+#[tokio::test]
+async fn offline_nsw_sales_archive_of_districts() {
+    let temp = tempdir().unwrap();
+    let url = format!("file://{}", fixture("nsw_sales_districts.zip").display());
+
+    let fetched = fetch::fetch_nsw_sales(
+        &url,
+        temp.path(),
+        &fetcher(),
+        false,
+        &ValidationConfig::default(),
+    )
+    .await
+    .unwrap();
+    // Both district CSVs, in name order; the README is left in the archive
+    let names: Vec<_> = fetched
+        .raw_data
+        .as_file_paths()
+        .unwrap()
+        .iter()
+        .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["district_001.csv", "district_002.csv"]);
+
+    let records = parse::parse_nsw_sales(fetched.raw_data, "nsw_sales".to_string())
+        .await
+        .unwrap()
+        .records;
+    let suburbs: Vec<_> = records.iter().map(|r| r.suburb.as_str()).collect();
+    assert_eq!(
+        suburbs,
+        [
+            "Newcastle",
+            "Cooks Hill",
+            "Mayfield",
+            "Wollongong",
+            "Corrimal"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn offline_nsw_sales_replayed_from_archive() {
+    let temp = tempdir().unwrap();
+    let url = format!("file://{}", fixture("nsw_sales_archive.zip").display());
+    let fetched = fetch::fetch_nsw_sales(
+        &url,
+        &temp.path().join("fetch"),
+        &fetcher(),
+        false,
+        &ValidationConfig::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(fetched.archives.len(), 1);
+
+    let raw_archive = RawArchive::new(temp.path().join("archive"));
+    let date = NaiveDate::from_ymd_opt(2025, 6, 2).unwrap();
+    let archived = raw_archive
+        .archive_file("nsw_sales", date, &fetched.archives[0])
+        .unwrap();
+    assert_eq!(archived.key, "nsw_sales/2025-06-02/nsw_sales.zip");
+    assert_eq!(archived.sha256.len(), 64);
+
+    // The archived ZIP parses to the same records, and its path says where it's from
+    let path = raw_archive.resolve(&archived.key).unwrap();
+    assert_eq!(
+        archive::archived_source(&path),
+        Some(("nsw_sales".to_string(), date))
+    );
+    let (raw_data, report) = fetch::replay_nsw_sales(&path, &temp.path().join("replay")).unwrap();
+    assert!(report.passed());
+    let replayed = parse::parse_nsw_sales(raw_data, "nsw_sales".to_string())
+        .await
+        .unwrap()
+        .records;
+    let original = parse::parse_nsw_sales(fetched.raw_data, "nsw_sales".to_string())
+        .await
+        .unwrap()
+        .records;
+    assert_eq!(replayed.len(), 10);
+    let sales = |records: &[PropertyRecord]| {
+        records
+            .iter()
+            .map(|r| (r.address.clone(), r.sale_price))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sales(&replayed), sales(&original));
+    // Extracted beside the replay's copy, not inside the archive
+    assert_eq!(
+        std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn offline_nsw_rentals_from_plain_path() {
+    let path = fixture("nsw_rentals.xlsx");
+
+    let raw = fetch::fetch_nsw_rentals(path.to_str().unwrap(), &fetcher())
+        .await
+        .unwrap();
+    let period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+    let rentals = parse::parse_nsw_rentals(raw, period).await.unwrap().records;
+
+    // Same rows as parsing the fixture directly
+    assert_golden("nsw_rentals", &rentals);
+}
+
+#[tokio::test]
+async fn offline_missing_files_name_the_path() {
+    let temp = tempdir().unwrap();
+    let missing = temp.path().join("nowhere/archive.zip");
+
+    let error = fetch::fetch_nsw_sales(
+        &format!("file://{}", missing.display()),
+        temp.path(),
+        &fetcher(),
+        false,
+        &ValidationConfig::default(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!("Source file {} does not exist", missing.display())
+    );
+
+    let error = fetch::fetch_nsw_rentals(missing.to_str().unwrap(), &fetcher())
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("nowhere/archive.zip"),
+        "{}",
+        error
+    );
+}
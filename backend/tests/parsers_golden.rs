@@ -0,0 +1,826 @@
+//! Golden-file tests for the ingestion parsers
+//! Regenerate expectations with `BLESS=1 cargo test --test parsers_golden`
+
+mod golden;
+
+use chrono::{DateTime, NaiveDate};
+use golden::{assert_golden, diff_records, fixture};
+use real_estate_backend::ingestion::{parse, PropertyRecord, RawData};
+use serde_json::json;
+
+/// Replace the only non-deterministic field so output is stable between runs
+fn pin_fetched_at(records: Vec<PropertyRecord>) -> Vec<PropertyRecord> {
+    let fixed = DateTime::from_timestamp(0, 0).unwrap();
+    records
+        .into_iter()
+        .map(|mut r| {
+            r.source_metadata.fetched_at = fixed;
+            r
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn golden_nsw_sales_csv() {
+    let raw = RawData::File(fixture("nsw_sales.csv"));
+    let records = parse::parse_nsw_sales(raw, "nsw_sales".to_string())
+        .await
+        .unwrap()
+        .records;
+
+    assert_golden("nsw_sales", &pin_fetched_at(records));
+}
+
+#[tokio::test]
+async fn golden_nsw_sales_corrupted_rows_reported() {
+    // A truncated row, a row with bytes that aren't UTF-8 and a row with a field too many
+    let raw = RawData::File(fixture("nsw_sales_corrupted.csv"));
+    let report = parse::parse_nsw_sales(raw, "nsw_sales".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.total_rows, 6);
+    let errors: Vec<_> = report
+        .errors
+        .iter()
+        .map(|e| (e.source.as_str(), e.row_number, e.column.as_deref()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            ("nsw_sales_corrupted.csv", 3, None),
+            ("nsw_sales_corrupted.csv", 5, Some("Property locality")),
+            ("nsw_sales_corrupted.csv", 6, None),
+        ]
+    );
+    assert!(
+        report.errors[0]
+            .message
+            .contains("found record with 5 fields"),
+        "{:?}",
+        report.errors[0]
+    );
+    assert!(
+        report.errors[1].message.contains("invalid UTF-8"),
+        "{:?}",
+        report.errors[1]
+    );
+    assert_eq!(report.error_rate(), 0.5);
+    assert_golden("nsw_sales_corrupted", &pin_fetched_at(report.records));
+}
+
+#[tokio::test]
+async fn nsw_sales_messy_prices() {
+    let raw = RawData::File(fixture("nsw_sales_messy_prices.csv"));
+    let report = parse::parse_nsw_sales(raw, "nsw_sales".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.total_rows, 8);
+    let prices: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| (r.external_id.as_deref().unwrap(), r.sale_price))
+        .collect();
+    assert_eq!(
+        prices,
+        vec![
+            ("3001", Some(750_000)),
+            ("3002", Some(750_000)),
+            ("3003", None),
+            ("3004", Some(1_200_000)),
+            ("3005", Some(2_612_000_000)),
+            ("3008", None),
+        ]
+    );
+    // Blank and implausible prices are kept; prices that can't be read are
+    // row errors
+    assert_eq!(report.blank_prices, 2);
+    assert_eq!(report.implausible_prices, 1);
+    let errors: Vec<_> = report
+        .errors
+        .iter()
+        .map(|e| (e.row_number, e.column.as_deref(), e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (7, Some("Purchase price"), "negative price: \"-500000\""),
+            (8, Some("Purchase price"), "not a price: \"see contract\""),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn golden_nsw_sales_dat() {
+    // Addresses run over into C rows; a C row follows the wrong property and
+    // a B row has a price that isn't a number
+    let raw = RawData::File(fixture("001_SALES_DATA_NNME_08012024.DAT"));
+    let report = parse::parse_nsw_sales_dat(raw, "nsw_sales".to_string())
+        .await
+        .unwrap();
+
+    let sales: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| (r.address.as_str(), r.suburb.as_str(), r.sale_price))
+        .collect();
+    assert_eq!(
+        sales,
+        vec![
+            ("10 NEW SOUTH HEAD ROAD", "DOUBLE BAY", Some(2_750_000)),
+            ("4/22 GEORGE STREET", "PARRAMATTA", Some(520_000)),
+            ("OLD NORTHERN ROAD", "DURAL", Some(1_250_000)),
+            ("88 MARKET STREET", "SYDNEY", Some(4_100_000)),
+        ]
+    );
+    let errors: Vec<_> = report
+        .errors
+        .iter()
+        .map(|e| (e.row_number, e.column.as_deref(), e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (
+                10,
+                None,
+                "continues property 9999 but follows no B row for it"
+            ),
+            (11, Some("Purchase price"), "not a price: \"ABC\""),
+        ]
+    );
+    assert_eq!(report.total_rows, 10);
+    assert_golden("nsw_sales_dat", &pin_fetched_at(report.records));
+
+    // The sales path reads .DAT files the same way
+    let raw = RawData::File(fixture("001_SALES_DATA_NNME_08012024.DAT"));
+    let via_sales = parse::parse_nsw_sales(raw, "nsw_sales".to_string())
+        .await
+        .unwrap();
+    assert_golden("nsw_sales_dat", &pin_fetched_at(via_sales.records));
+    assert_eq!(via_sales.errors, report.errors);
+
+    let sale = parse::parse_nsw_sales_row(
+        RawData::File(fixture("001_SALES_DATA_NNME_08012024.DAT")),
+        "nsw_sales".to_string(),
+        2,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(sale.address, "OLD NORTHERN ROAD");
+}
+
+#[tokio::test]
+async fn nsw_sales_dat_messy_prices() {
+    let raw = RawData::File(fixture("nsw_sales_messy_prices.DAT"));
+    let report = parse::parse_nsw_sales_dat(raw, "nsw_sales".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.total_rows, 7);
+    let prices: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| (r.external_id.as_deref().unwrap(), r.sale_price))
+        .collect();
+    assert_eq!(
+        prices,
+        vec![
+            ("3001", Some(750_000)),
+            ("3002", Some(750_000)),
+            ("3003", None),
+            ("3004", Some(2_612_000_000)),
+            ("3007", None),
+        ]
+    );
+    assert_eq!(report.blank_prices, 2);
+    assert_eq!(report.implausible_prices, 1);
+    let errors: Vec<_> = report
+        .errors
+        .iter()
+        .map(|e| (e.row_number, e.column.as_deref(), e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (6, Some("Purchase price"), "negative price: \"-500000\""),
+            (7, Some("Purchase price"), "not a price: \"see contract\""),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn nsw_sales_single_row_matches_full_parse() {
+    let all = parse::parse_nsw_sales(
+        RawData::File(fixture("nsw_sales.csv")),
+        "nsw_sales".to_string(),
+    )
+    .await
+    .unwrap()
+    .records;
+
+    let row = parse::parse_nsw_sales_row(
+        RawData::File(fixture("nsw_sales.csv")),
+        "nsw_sales".to_string(),
+        1,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(row.source_metadata.source_row, Some(1));
+    assert_eq!(
+        serde_json::to_value(pin_fetched_at(vec![row])).unwrap(),
+        serde_json::to_value(pin_fetched_at(vec![all[1].clone()])).unwrap()
+    );
+
+    let past_end = parse::parse_nsw_sales_row(
+        RawData::File(fixture("nsw_sales.csv")),
+        "nsw_sales".to_string(),
+        10_000,
+    )
+    .await
+    .unwrap();
+    assert!(past_end.is_none());
+}
+
+#[tokio::test]
+async fn golden_nsw_rentals_xlsx() {
+    let raw = RawData::Bytes(std::fs::read(fixture("nsw_rentals.xlsx")).unwrap());
+    let period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+    let rentals = parse::parse_nsw_rentals(raw, period).await.unwrap().records;
+
+    assert_golden("nsw_rentals", &rentals);
+}
+
+#[tokio::test]
+async fn nsw_rentals_row_errors_reported() {
+    // The fixture's 2158 row has "three" bedrooms
+    let raw = RawData::Bytes(std::fs::read(fixture("nsw_rentals.xlsx")).unwrap());
+    let period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+    let report = parse::parse_nsw_rentals(raw, period).await.unwrap();
+
+    assert_eq!(report.total_rows, 6);
+    assert_eq!(report.records.len(), 5);
+    assert_eq!(
+        report.errors,
+        vec![parse::RowError {
+            source: "Rents".to_string(),
+            row_number: 5,
+            column: Some("Bedrooms".to_string()),
+            message: "not a bedroom count: \"three\"".to_string(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn golden_nsw_rentals_in_any_column_order() {
+    // The same medians, one file with title rows and a Dwelling Type column,
+    // the other with its columns shuffled under other header names
+    let period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+    for name in [
+        "nsw_rentals_dwelling_type.xlsx",
+        "nsw_rentals_reordered.xlsx",
+    ] {
+        let raw = RawData::Bytes(std::fs::read(fixture(name)).unwrap());
+        let rentals = parse::parse_nsw_rentals(raw, period).await.unwrap().records;
+
+        assert_golden("nsw_rentals_sampled", &rentals);
+    }
+}
+
+#[tokio::test]
+async fn golden_nsw_rentals_merged_across_sheets() {
+    // Notes, then a houses sheet, then all dwellings; each sheet's medians keep
+    // their own dwelling type, and the repeated 2150 row is dropped
+    let raw = RawData::Bytes(std::fs::read(fixture("nsw_rentals_sheets.xlsx")).unwrap());
+    let period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+    let rentals = parse::parse_nsw_rentals(raw, period).await.unwrap().records;
+
+    assert_golden("nsw_rentals_sheets", &rentals);
+}
+
+#[tokio::test]
+async fn nsw_rentals_without_a_median_sheet_fail() {
+    // The lodgement file lists single rents, not medians
+    let raw = RawData::Bytes(std::fs::read(fixture("nsw_bond_lodgements.xlsx")).unwrap());
+    let period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+    let error = parse::parse_nsw_rentals(raw, period).await.unwrap_err();
+
+    let message = error.to_string();
+    assert!(
+        message.starts_with("No rental median sheet; "),
+        "{}",
+        message
+    );
+    assert!(message.contains("no Median Rent column"), "{}", message);
+}
+
+#[tokio::test]
+async fn golden_qld_rentals_csv() {
+    // Quarters written "Sep-24" and "Dec 2023"; only the all dwellings rows
+    // for a single bedroom count are kept
+    let raw = RawData::Bytes(std::fs::read(fixture("qld_rentals.csv")).unwrap());
+    let report = parse::parse_qld_rentals(raw).await.unwrap();
+
+    let medians: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| (r.postcode.as_str(), r.bedrooms, r.period.to_string()))
+        .collect();
+    assert_eq!(
+        medians,
+        vec![
+            ("4000", 1, "2024-09-30".to_string()),
+            ("4000", 2, "2024-09-30".to_string()),
+            ("4101", 2, "2024-09-30".to_string()),
+            ("4101", 4, "2024-09-30".to_string()),
+            ("4000", 2, "2024-06-30".to_string()),
+            ("4217", 3, "2023-12-31".to_string()),
+        ]
+    );
+    assert_golden("qld_rentals", &report.records);
+}
+
+#[tokio::test]
+async fn qld_rentals_row_errors_reported() {
+    // August ends no quarter; the blank line doesn't throw the line numbers out
+    let raw = RawData::Bytes(std::fs::read(fixture("qld_rentals.csv")).unwrap());
+    let report = parse::parse_qld_rentals(raw).await.unwrap();
+
+    assert_eq!(report.total_rows, 14);
+    let errors: Vec<_> = report
+        .errors
+        .iter()
+        .map(|e| (e.row_number, e.column.as_deref(), e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (12, Some("Quarter"), "not a quarter: \"Aug-24\""),
+            (13, Some("Postcode"), "not a postcode: \"400\""),
+            (16, Some("Median Rent"), "not a rent: \"n/a\""),
+        ]
+    );
+
+    let raw = RawData::from("Postcode,Bedrooms,Median Rent\n4000,2,$500\n");
+    let error = parse::parse_qld_rentals(raw).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "No Quarter, Dwelling Type column in QLD median rents header"
+    );
+}
+
+#[tokio::test]
+async fn golden_wa_sales_csv() {
+    // Lot and plan numbers in the house number and street name fields, strata
+    // lots and dates written both ways Landgate has
+    let raw = RawData::File(fixture("wa_sales.csv"));
+    let report = parse::parse_wa_sales(raw, "wa_sales".to_string())
+        .await
+        .unwrap();
+
+    let sales: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| {
+            (
+                r.external_id.as_deref().unwrap(),
+                r.address.as_str(),
+                r.property_type.to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        sales,
+        vec![
+            ("1001234", "12 Hay Street", "house".to_string()),
+            (
+                "wa_lot:14:30012",
+                "4/17 Mounts Bay Road",
+                "unit".to_string()
+            ),
+            (
+                "wa_lot:502:40123",
+                "Marmion Avenue",
+                "vacant_land".to_string()
+            ),
+            ("1001237", "9 Beach Road", "townhouse".to_string()),
+            ("1001240", "40 Stirling Highway", "commercial".to_string()),
+            ("1001241", "88 St Georges Terrace", "commercial".to_string()),
+        ]
+    );
+    assert_golden("wa_sales", &pin_fetched_at(report.records));
+}
+
+#[tokio::test]
+async fn wa_sales_row_errors_reported() {
+    let raw = RawData::File(fixture("wa_sales.csv"));
+    let report = parse::parse_wa_sales(raw, "wa_sales".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.total_rows, 8);
+    let errors: Vec<_> = report
+        .errors
+        .iter()
+        .map(|e| (e.row_number, e.column.as_deref(), e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (6, Some("Postcode"), "not a WA postcode: \"2153\""),
+            (7, Some("Contract Date"), "not a date: \"31-Jun-2024\""),
+        ]
+    );
+    // "POA" is a sale without a published price, not a bad row
+    assert_eq!(report.blank_prices, 1);
+}
+
+#[tokio::test]
+async fn wa_sales_messy_prices() {
+    let raw = RawData::File(fixture("wa_sales_messy_prices.csv"));
+    let report = parse::parse_wa_sales(raw, "wa_sales".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.total_rows, 7);
+    let prices: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| (r.external_id.as_deref().unwrap(), r.sale_price))
+        .collect();
+    assert_eq!(
+        prices,
+        vec![
+            ("3001", Some(750_000)),
+            ("3002", Some(750_000)),
+            ("3003", None),
+            ("3004", Some(2_612_000_000)),
+            ("3007", None),
+        ]
+    );
+    assert_eq!(report.blank_prices, 2);
+    assert_eq!(report.implausible_prices, 1);
+    let errors: Vec<_> = report
+        .errors
+        .iter()
+        .map(|e| (e.row_number, e.column.as_deref(), e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (6, Some("Sale Price"), "negative price: \"-500000\""),
+            (7, Some("Sale Price"), "not a price: \"see contract\""),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn golden_vic_sales_xlsx() {
+    // Title rows above the header, a notes sheet and a later sheet repeating a median
+    let raw = RawData::Bytes(std::fs::read(fixture("vic_sales.xlsx")).unwrap());
+    let report = parse::parse_vic_sales(raw, "vic_sales".to_string())
+        .await
+        .unwrap();
+
+    let medians: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| {
+            (
+                r.address.as_str(),
+                r.external_id.as_deref().unwrap(),
+                r.sale_price,
+            )
+        })
+        .collect();
+    assert_eq!(
+        medians,
+        vec![
+            (
+                "RICHMOND MEDIAN (HOUSE)",
+                "vic_median:RICHMOND:house:all:2023",
+                Some(1_450_000)
+            ),
+            (
+                "RICHMOND MEDIAN (UNIT)",
+                "vic_median:RICHMOND:unit:all:2023",
+                Some(585_000)
+            ),
+            (
+                "RICHMOND MEDIAN (3BR HOUSE)",
+                "vic_median:RICHMOND:house:3:2023",
+                Some(1_250_000)
+            ),
+            (
+                "FITZROY MEDIAN (HOUSE)",
+                "vic_median:FITZROY:house:all:2022",
+                Some(1_600_000)
+            ),
+            (
+                "COLLINGWOOD MEDIAN (UNIT)",
+                "vic_median:COLLINGWOOD:unit:all:2023",
+                Some(620_000)
+            ),
+        ]
+    );
+    assert_golden("vic_sales", &pin_fetched_at(report.records));
+}
+
+#[tokio::test]
+async fn vic_sales_row_errors_reported() {
+    // KEW's median is suppressed, BRUNSWICK's is "POA" and CARLTON's type is "Shed"
+    let raw = RawData::Bytes(std::fs::read(fixture("vic_sales.xlsx")).unwrap());
+    let report = parse::parse_vic_sales(raw, "vic_sales".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.total_rows, 9);
+    assert_eq!(
+        report.errors,
+        vec![
+            parse::RowError {
+                source: "Median Sales".to_string(),
+                row_number: 9,
+                column: Some("Median Price".to_string()),
+                message: "not a price: \"POA\"".to_string(),
+            },
+            parse::RowError {
+                source: "Median Sales".to_string(),
+                row_number: 11,
+                column: Some("Property Type".to_string()),
+                message: "not a property type: \"Shed\"".to_string(),
+            },
+        ]
+    );
+
+    // The rental workbook has no median sales sheet
+    let raw = RawData::Bytes(std::fs::read(fixture("nsw_rentals.xlsx")).unwrap());
+    let error = parse::parse_vic_sales(raw, "vic_sales".to_string())
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().starts_with("No VIC median sales sheet; "),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn golden_sa_medians_xlsx() {
+    // Quarters labelled several ways, a units sheet repeating a house median
+    let raw = RawData::Bytes(std::fs::read(fixture("sa_medians.xlsx")).unwrap());
+    let report = parse::parse_sa_medians(raw, "sa_medians".to_string())
+        .await
+        .unwrap();
+
+    let medians: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| {
+            (
+                r.external_id.as_deref().unwrap(),
+                r.postcode.as_deref(),
+                r.sale_date,
+                r.source_metadata.confidence_score,
+            )
+        })
+        .collect();
+    let quarter_end = |month, day| NaiveDate::from_ymd_opt(2024, month, day);
+    assert_eq!(
+        medians,
+        vec![
+            (
+                "sa_median:ADELAIDE:house:all:2024Q3",
+                Some("5000"),
+                quarter_end(9, 30),
+                0.8
+            ),
+            (
+                "sa_median:NORWOOD:house:all:2024Q2",
+                Some("5067"),
+                quarter_end(6, 30),
+                0.8
+            ),
+            (
+                "sa_median:NORWOOD:house:all:2024Q3",
+                Some("5067"),
+                quarter_end(9, 30),
+                0.4
+            ),
+            (
+                "sa_median:MAWSON LAKES:house:all:2024Q3",
+                None,
+                quarter_end(9, 30),
+                0.8
+            ),
+            (
+                "sa_median:ADELAIDE:unit:all:2024Q3",
+                None,
+                quarter_end(9, 30),
+                0.8
+            ),
+        ]
+    );
+    assert!(report.records.iter().all(|r| r.bedrooms.is_none()));
+    assert_golden("sa_medians", &pin_fetched_at(report.records));
+}
+
+#[tokio::test]
+async fn sa_medians_row_errors_reported() {
+    // ELIZABETH's median is suppressed; GLENELG's is "POA", PORT ADELAIDE has
+    // a WA postcode and UNLEY's quarter ends in August
+    let raw = RawData::Bytes(std::fs::read(fixture("sa_medians.xlsx")).unwrap());
+    let report = parse::parse_sa_medians(raw, "sa_medians".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.total_rows, 10);
+    let row_error = |row_number, column: &str, message: &str| parse::RowError {
+        source: "Median Sales".to_string(),
+        row_number,
+        column: Some(column.to_string()),
+        message: message.to_string(),
+    };
+    assert_eq!(
+        report.errors,
+        vec![
+            row_error(9, "Median Price", "not a price: \"POA\""),
+            row_error(10, "Postcode", "not a SA postcode: \"6015\""),
+            row_error(11, "Quarter", "not a quarter: \"Aug-24\""),
+        ]
+    );
+
+    let raw = RawData::Bytes(std::fs::read(fixture("vic_sales.xlsx")).unwrap());
+    let error = parse::parse_sa_medians(raw, "sa_medians".to_string())
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().starts_with("No SA median sales sheet; "),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn golden_domain_listings_json() {
+    // Sales priced as a figure, a range and a floor, rentals quoted weekly and
+    // monthly, a "Contact Agent" listing and one listed twice
+    let raw = RawData::Json(std::fs::read(fixture("domain_listings.json")).unwrap());
+    let report = parse::parse_domain_listings(raw, "domain_listings".to_string())
+        .await
+        .unwrap();
+
+    let listings: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| {
+            (
+                r.external_id.as_deref().unwrap(),
+                r.sale_price,
+                r.weekly_rent,
+                r.source_metadata.confidence_score,
+            )
+        })
+        .collect();
+    assert_eq!(
+        listings,
+        vec![
+            ("domain:2019400101", Some(1_850_000), None, 0.9),
+            ("domain:2019400102", Some(1_150_000), None, 0.7),
+            ("domain:2019400103", Some(950_000), None, 0.6),
+            ("domain:2019400201", None, Some(950), 0.9),
+            ("domain:2019400202", None, Some(785), 0.9),
+            ("domain:2019400107", Some(780_000), None, 0.9),
+        ]
+    );
+    assert_golden("domain_listings", &pin_fetched_at(report.records));
+
+    assert_eq!(report.total_rows, 10);
+    let row_error = |row_number, column: &str, message: &str| parse::RowError {
+        source: "Domain listings".to_string(),
+        row_number,
+        column: Some(column.to_string()),
+        message: message.to_string(),
+    };
+    assert_eq!(
+        report.errors,
+        vec![
+            row_error(7, "propertyDetails.state", "not a state: \"XYZ\""),
+            row_error(8, "propertyDetails.street", "no street address"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn golden_nsw_bond_lodgements_xlsx() {
+    let raw = RawData::Bytes(std::fs::read(fixture("nsw_bond_lodgements.xlsx")).unwrap());
+    let period = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+    let observations = parse::parse_nsw_bond_lodgements(raw, period).await.unwrap();
+
+    // Title rows, the missing postcode and the unusable rents are dropped
+    assert_eq!(observations.len(), 321);
+    assert_golden("nsw_bond_lodgements", &observations);
+}
+
+#[tokio::test]
+async fn golden_abs_postcode_regions_csv() {
+    let raw = RawData::Bytes(std::fs::read(fixture("abs_postcode_sa3.csv")).unwrap());
+    let regions = parse::parse_abs_postcode_regions(raw).await.unwrap();
+
+    // 24 rows: split postcodes collapse to one mapping, the blank ratio and postcode are dropped
+    assert_eq!(regions.len(), 15);
+    assert_golden("abs_postcode_regions", &regions);
+}
+
+#[tokio::test]
+async fn golden_abs_postcode_lga_csv() {
+    let raw = RawData::Bytes(std::fs::read(fixture("abs_postcode_lga.csv")).unwrap());
+    let mappings = parse::parse_lga_correspondence(raw).await.unwrap();
+
+    // 19 rows: split postcodes collapse to one LGA, Other Territories and the blanks are dropped
+    assert_eq!(mappings.len(), 12);
+    assert_golden("abs_postcode_lga", &mappings);
+}
+
+#[tokio::test]
+async fn golden_council_rates_csv() {
+    let raw = RawData::Bytes(std::fs::read(fixture("council_rates.csv")).unwrap());
+    let councils = parse::parse_council_rates(raw).await.unwrap();
+
+    // Sydney's older year, the short code and the missing rates are dropped
+    assert_eq!(councils.len(), 8);
+    assert_golden("council_rates", &councils);
+}
+
+#[tokio::test]
+async fn golden_nsw_sales_api_json() {
+    let raw = RawData::Json(std::fs::read(fixture("nsw_sales_api.json")).unwrap());
+    let report = parse::parse_nsw_sales_api(raw, "nsw_sales_api".to_string())
+        .await
+        .unwrap();
+
+    // The record with a null address is dropped
+    assert_eq!(report.records.len(), 5);
+    assert_eq!(report.total_rows, 6);
+    assert_eq!(report.blank_prices, 1);
+    assert_golden("nsw_sales_api", &pin_fetched_at(report.records));
+}
+
+#[tokio::test]
+async fn nsw_sales_api_messy_prices() {
+    let raw = RawData::Json(std::fs::read(fixture("nsw_sales_api_messy_prices.json")).unwrap());
+    let report = parse::parse_nsw_sales_api(raw, "nsw_sales_api".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(report.total_rows, 7);
+    let prices: Vec<_> = report
+        .records
+        .iter()
+        .map(|r| (r.external_id.as_deref().unwrap(), r.sale_price))
+        .collect();
+    assert_eq!(
+        prices,
+        vec![
+            ("3001", Some(750_000)),
+            ("3002", Some(750_000)),
+            ("3003", None),
+            ("3004", Some(2_612_000_000)),
+            ("3007", None),
+        ]
+    );
+    assert_eq!(report.blank_prices, 2);
+    assert_eq!(report.implausible_prices, 1);
+    let errors: Vec<_> = report
+        .errors
+        .iter()
+        .map(|e| (e.row_number, e.column.as_deref(), e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (5, Some("purchasePrice"), "negative price: \"-500000\""),
+            (6, Some("purchasePrice"), "not a price: \"see contract\""),
+        ]
+    );
+}
+
+#[test]
+fn diff_report_names_fields() {
+    let expected = vec![json!({ "address": "10 Smith St", "sale_price": 750000 })];
+    let actual = vec![
+        json!({ "address": "10 Smith Street", "sale_price": 750000 }),
+        json!({ "address": "extra" }),
+    ];
+
+    let report = diff_records(&expected, &actual);
+
+    assert!(report.contains("record count: expected 1, got 2"));
+    assert!(report.contains("record 0.address: expected \"10 Smith St\", got \"10 Smith Street\""));
+    assert!(!report.contains("sale_price"));
+    assert!(report.contains("record 1: unexpected"));
+    assert!(diff_records(&expected, &expected).is_empty());
+}
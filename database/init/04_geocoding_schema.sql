@@ -0,0 +1,30 @@
+-- Geocoding support
+-- G-NAF address points for the primary lookup, plus a cache of external geocoder results
+
+-- How precisely a coordinate locates the address
+CREATE TYPE geocode_precision_enum AS ENUM ('address', 'street', 'locality');
+
+-- G-NAF address points (loaded separately from the G-NAF release)
+CREATE TABLE IF NOT EXISTS gnaf_addresses (
+    address_detail_pid VARCHAR(15) PRIMARY KEY,
+    street_address VARCHAR(255) NOT NULL, -- Uppercase, e.g. '10 SMITH ST'
+    locality_name VARCHAR(100) NOT NULL,  -- Uppercase suburb name
+    state state_enum NOT NULL,
+    postcode VARCHAR(10),
+    latitude DECIMAL(10, 8) NOT NULL,
+    longitude DECIMAL(11, 8) NOT NULL,
+    precision geocode_precision_enum NOT NULL DEFAULT 'address'
+);
+
+-- External geocoder results, so an address is never sent externally twice
+-- latitude/longitude are NULL when the provider found nothing
+CREATE TABLE IF NOT EXISTS geocode_cache (
+    query_key VARCHAR(400) PRIMARY KEY, -- Normalised single-line address
+    latitude DECIMAL(10, 8),
+    longitude DECIMAL(11, 8),
+    precision geocode_precision_enum,
+    provider VARCHAR(50) NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_gnaf_addresses_lookup ON gnaf_addresses(state, locality_name, street_address);
@@ -0,0 +1,13 @@
+-- Shared property comparisons
+-- Snapshots are frozen at share time; readers also see current values
+
+CREATE TABLE IF NOT EXISTS shared_comparisons (
+    token VARCHAR(64) PRIMARY KEY, -- Random, URL-safe
+    property_ids INTEGER[] NOT NULL,
+    note TEXT,
+    snapshot JSONB NOT NULL, -- Property values as they were when shared
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_shared_comparisons_expires ON shared_comparisons(expires_at);
@@ -0,0 +1,5 @@
+-- Rental matching provenance on properties
+-- Records whether bedrooms were estimated and which rental median period was matched
+
+ALTER TABLE properties ADD COLUMN IF NOT EXISTS is_bedrooms_estimated BOOLEAN DEFAULT FALSE;
+ALTER TABLE properties ADD COLUMN IF NOT EXISTS rental_period DATE;
@@ -0,0 +1,5 @@
+-- Indexes for enum-native state filters
+-- Filters bind state_enum directly and match suburbs case-insensitively
+
+CREATE INDEX IF NOT EXISTS idx_properties_state_suburb_lower ON properties(state, LOWER(suburb));
+CREATE INDEX IF NOT EXISTS idx_rental_medians_state ON rental_medians(state);
@@ -0,0 +1,18 @@
+-- Row-level provenance and audit trail for admin property refreshes
+
+-- Where each property was parsed from: archive key of the raw file and data row
+ALTER TABLE properties ADD COLUMN IF NOT EXISTS source_file VARCHAR(500);
+ALTER TABLE properties ADD COLUMN IF NOT EXISTS source_row INTEGER;
+
+-- One row per manual change to a property, with full before/after snapshots
+CREATE TABLE IF NOT EXISTS property_audit_log (
+    id SERIAL PRIMARY KEY,
+    property_id INTEGER NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+    action VARCHAR(50) NOT NULL,
+    actor VARCHAR(100) NOT NULL,
+    before JSONB NOT NULL,
+    after JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_property_audit_log_property ON property_audit_log(property_id, created_at DESC);
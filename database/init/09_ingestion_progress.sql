@@ -0,0 +1,4 @@
+-- Progress reporting for ingestion runs
+
+-- Latest stage and percent complete, written by the pipeline while the run is going
+ALTER TABLE ingestion_runs ADD COLUMN IF NOT EXISTS progress JSONB;
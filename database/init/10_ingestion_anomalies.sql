@@ -0,0 +1,9 @@
+-- Anomaly alerting for ingestion runs
+
+-- Room for the 'completed_with_warnings' status
+ALTER TABLE ingestion_runs ALTER COLUMN status TYPE VARCHAR(30);
+
+-- Key metrics of each run, compared against recent runs of the same source
+ALTER TABLE ingestion_runs ADD COLUMN IF NOT EXISTS metrics JSONB;
+-- Metrics that deviated from the baseline, for runs completed with warnings
+ALTER TABLE ingestion_runs ADD COLUMN IF NOT EXISTS anomalies JSONB;
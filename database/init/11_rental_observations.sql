@@ -0,0 +1,21 @@
+-- Individual rental events from the detailed bond lodgement files
+-- No address is published, so each row is just key + rent for a month
+
+CREATE TABLE IF NOT EXISTS rental_observations (
+    id SERIAL PRIMARY KEY,
+    state state_enum NOT NULL,
+    postcode VARCHAR(10) NOT NULL,
+    dwelling_type property_type_enum NOT NULL,
+    bedrooms INTEGER, -- NULL when the lodgement didn't record it
+    weekly_rent INTEGER NOT NULL,
+    period DATE NOT NULL, -- Month of the lodgement file
+    data_source VARCHAR(50) NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+-- Filtering for the observations API and observation-derived medians
+CREATE INDEX IF NOT EXISTS idx_rental_observations_key
+    ON rental_observations(state, postcode, bedrooms, period DESC);
+-- Replacing a month's file
+CREATE INDEX IF NOT EXISTS idx_rental_observations_source_period
+    ON rental_observations(data_source, period);
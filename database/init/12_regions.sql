@@ -0,0 +1,38 @@
+-- Postcode to ABS SA3/SA4 regions, from the ABS postcode correspondence file
+-- Postcodes spanning several SA3s map to the one holding the largest share
+
+CREATE TABLE IF NOT EXISTS regions (
+    postcode VARCHAR(10) PRIMARY KEY,
+    sa3_code VARCHAR(5) NOT NULL,
+    sa3_name VARCHAR(100) NOT NULL,
+    sa4_code VARCHAR(3) NOT NULL, -- Leading digits of the SA3 code
+    sa4_name VARCHAR(100), -- Only when the correspondence file names SA4s
+    state state_enum, -- NULL for Other Territories
+    ratio DECIMAL(7, 6) NOT NULL, -- Share of the postcode in this SA3
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_regions_sa3 ON regions(sa3_code);
+CREATE INDEX IF NOT EXISTS idx_regions_sa4 ON regions(sa4_code);
+
+-- SA3-level rollup, calculated alongside suburb_statistics
+CREATE TABLE IF NOT EXISTS region_statistics (
+    id SERIAL PRIMARY KEY,
+    sa3_code VARCHAR(5) NOT NULL,
+    sa3_name VARCHAR(100) NOT NULL,
+    sa4_code VARCHAR(3) NOT NULL,
+    state state_enum NOT NULL,
+    bedrooms INTEGER,
+
+    median_price INTEGER,
+    median_weekly_rent INTEGER,
+    median_rental_yield DECIMAL(5,2),
+    avg_rental_yield DECIMAL(5,2),
+    property_count INTEGER,
+
+    calculated_date DATE NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_region_stats_location
+    ON region_statistics(state, sa3_code, bedrooms, calculated_date DESC);
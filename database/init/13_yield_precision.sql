@@ -0,0 +1,4 @@
+-- Store property yields at the precision they are calculated with (4 dp)
+-- Display rounding to 2 dp happens in the application, so stored and shown values agree
+
+ALTER TABLE properties ALTER COLUMN rental_yield TYPE DECIMAL(7, 4);
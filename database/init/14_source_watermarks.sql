@@ -0,0 +1,8 @@
+-- How far each incremental source has been ingested
+-- Only moved forward after a run's records are written, so a failed run is retried from the same point
+
+CREATE TABLE IF NOT EXISTS source_watermarks (
+    source_id VARCHAR(50) PRIMARY KEY,
+    watermark TIMESTAMPTZ NOT NULL, -- Latest source-side update time loaded
+    updated_at TIMESTAMP DEFAULT NOW()
+);
@@ -0,0 +1,5 @@
+-- Bounding-box lookups for the map clustering endpoint
+
+CREATE INDEX IF NOT EXISTS idx_properties_coordinates
+    ON properties(latitude, longitude)
+    WHERE latitude IS NOT NULL AND longitude IS NOT NULL;
@@ -0,0 +1,13 @@
+-- Records the write path set aside for review rather than failing the run
+-- suspicious_update: an update moved price, rent or yield past DRIFT_FLAG_THRESHOLD
+
+CREATE TABLE IF NOT EXISTS ingestion_errors (
+    id SERIAL PRIMARY KEY,
+    source_id VARCHAR(50),
+    kind VARCHAR(50) NOT NULL,
+    property_id INTEGER REFERENCES properties(id) ON DELETE CASCADE,
+    details JSONB, -- Before and after values, and the relative changes
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingestion_errors_kind ON ingestion_errors(kind, created_at);
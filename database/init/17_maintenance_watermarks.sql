@@ -0,0 +1,8 @@
+-- Progress of resumable maintenance jobs over the properties table
+-- renormalize_addresses: last property id processed, optionally per suburb scope
+
+CREATE TABLE IF NOT EXISTS maintenance_watermarks (
+    task VARCHAR(150) PRIMARY KEY,
+    last_id INTEGER NOT NULL, -- Rows with ids up to this are done
+    updated_at TIMESTAMP DEFAULT NOW()
+);
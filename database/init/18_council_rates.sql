@@ -0,0 +1,30 @@
+-- Local government areas and their council rates, for per-LGA holding costs
+
+-- Suburb or postcode to LGA, from the ABS correspondence files
+-- Areas spanning several LGAs map to the one holding the largest share
+CREATE TABLE IF NOT EXISTS lga_correspondence (
+    area_type VARCHAR(10) NOT NULL CHECK (area_type IN ('suburb', 'postcode')),
+    area VARCHAR(100) NOT NULL, -- Upper-case suburb name, or the postcode
+    state state_enum NOT NULL,
+    lga_code VARCHAR(5) NOT NULL,
+    lga_name VARCHAR(100) NOT NULL,
+    ratio DECIMAL(7, 6) NOT NULL, -- Share of the area in this LGA
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (area_type, area, state)
+);
+
+-- Average residential rates per council, from the council rates dataset
+CREATE TABLE IF NOT EXISTS council_rates (
+    lga_code VARCHAR(5) PRIMARY KEY,
+    lga_name VARCHAR(100) NOT NULL,
+    state state_enum,
+    financial_year VARCHAR(7), -- e.g. 2023-24
+    average_rates INTEGER NOT NULL, -- AUD a year
+    average_levies INTEGER, -- Domestic waste and other annual charges, AUD a year
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- Suburb match first, postcode otherwise; NULL when neither is in the correspondence
+ALTER TABLE properties ADD COLUMN IF NOT EXISTS lga_code VARCHAR(5);
+
+CREATE INDEX IF NOT EXISTS idx_properties_lga ON properties(lga_code);
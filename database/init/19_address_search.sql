@@ -0,0 +1,9 @@
+-- Free-text address search: trigram indexes serve the ILIKE '%term%' matches
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_properties_address_trgm
+    ON properties USING GIN (address gin_trgm_ops);
+
+CREATE INDEX IF NOT EXISTS idx_properties_suburb_trgm
+    ON properties USING GIN (suburb gin_trgm_ops);
@@ -0,0 +1,26 @@
+-- Staged writes: records from untrusted sources wait here for an admin to
+-- approve them into properties or reject them
+
+CREATE TABLE IF NOT EXISTS staging_batches (
+    id SERIAL PRIMARY KEY,
+    source_id VARCHAR(50) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'approved', 'rejected')),
+    record_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    reviewed_at TIMESTAMP,
+    reviewed_by VARCHAR(100),
+    merge_stats JSONB -- Inserted, updated and skipped counts of an approved batch
+);
+
+CREATE INDEX IF NOT EXISTS idx_staging_batches_status ON staging_batches(status, created_at);
+
+-- Rows are deleted once their batch is merged or rejected
+CREATE TABLE IF NOT EXISTS properties_staging (
+    id SERIAL PRIMARY KEY,
+    batch_id INTEGER NOT NULL REFERENCES staging_batches(id) ON DELETE CASCADE,
+    row_number INTEGER NOT NULL, -- Order within the batch
+    record JSONB NOT NULL -- The full parsed and enriched record, provenance included
+);
+
+CREATE INDEX IF NOT EXISTS idx_properties_staging_batch ON properties_staging(batch_id, row_number);
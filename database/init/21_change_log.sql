@@ -0,0 +1,62 @@
+-- Row-level change feed of properties, for downstream mirrors (GET /api/changes)
+
+-- One row per insert, content change or removal of a property, in commit order
+CREATE TABLE IF NOT EXISTS change_log (
+    seq BIGINT PRIMARY KEY, -- Gap-free, from change_log_sequence
+    property_id INTEGER NOT NULL, -- No foreign key: archived properties are gone
+    change_type VARCHAR(10) NOT NULL CHECK (change_type IN ('insert', 'update', 'archive')),
+    content_hash CHAR(32), -- md5 of the row without last_updated; NULL for archive
+    changed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- Last seq handed out. Taking the next one locks this row until the writing
+-- transaction ends, so seqs become visible in order and a reader never sees
+-- seq N+1 before N. Writers to properties serialize on it.
+CREATE TABLE IF NOT EXISTS change_log_sequence (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    last_seq BIGINT NOT NULL
+);
+
+INSERT INTO change_log_sequence (id, last_seq) VALUES (TRUE, 0) ON CONFLICT DO NOTHING;
+
+-- Properties that existed before the feed start it as inserts
+INSERT INTO change_log (seq, property_id, change_type, content_hash)
+SELECT ROW_NUMBER() OVER (ORDER BY id), id, 'insert', md5((to_jsonb(p) - 'last_updated')::TEXT)
+FROM properties p
+WHERE NOT EXISTS (SELECT 1 FROM change_log);
+
+UPDATE change_log_sequence
+SET last_seq = (SELECT COALESCE(MAX(seq), 0) FROM change_log)
+WHERE last_seq = 0;
+
+CREATE OR REPLACE FUNCTION log_property_change() RETURNS TRIGGER AS $$
+DECLARE
+    new_hash CHAR(32);
+    next_seq BIGINT;
+BEGIN
+    IF TG_OP <> 'DELETE' THEN
+        new_hash := md5((to_jsonb(NEW) - 'last_updated')::TEXT);
+        -- Rewrites that only touch last_updated aren't changes
+        IF TG_OP = 'UPDATE' AND new_hash = md5((to_jsonb(OLD) - 'last_updated')::TEXT) THEN
+            RETURN NULL;
+        END IF;
+    END IF;
+
+    UPDATE change_log_sequence SET last_seq = last_seq + 1 RETURNING last_seq INTO next_seq;
+
+    INSERT INTO change_log (seq, property_id, change_type, content_hash)
+    VALUES (
+        next_seq,
+        CASE WHEN TG_OP = 'DELETE' THEN OLD.id ELSE NEW.id END,
+        CASE TG_OP WHEN 'INSERT' THEN 'insert' WHEN 'UPDATE' THEN 'update' ELSE 'archive' END,
+        new_hash
+    );
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS properties_change_log ON properties;
+CREATE TRIGGER properties_change_log
+    AFTER INSERT OR UPDATE OR DELETE ON properties
+    FOR EACH ROW EXECUTE FUNCTION log_property_change();
@@ -0,0 +1,11 @@
+-- Saved property searches (GET/POST /api/searches)
+
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id SERIAL PRIMARY KEY,
+    owner CHAR(32) NOT NULL, -- md5 of the caller's API key or client id
+    name VARCHAR(100) NOT NULL,
+    filter JSONB NOT NULL, -- The validated /api/properties query
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_saved_searches_owner ON saved_searches(owner, id);
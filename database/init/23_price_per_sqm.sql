@@ -0,0 +1,4 @@
+-- Sale price over land area, calculated during enrichment for houses and
+-- vacant land; NULL for strata properties and implausible areas
+
+ALTER TABLE properties ADD COLUMN IF NOT EXISTS price_per_sqm DECIMAL(12, 2);
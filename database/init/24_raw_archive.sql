@@ -0,0 +1,4 @@
+-- Raw files kept for each ingestion run
+
+-- Archive key, SHA-256 and size of every file the run fetched, so it can be replayed
+ALTER TABLE ingestion_runs ADD COLUMN IF NOT EXISTS raw_files JSONB;
@@ -0,0 +1,4 @@
+-- Rows each ingestion run could not parse
+
+-- How many rows failed to parse; the first of them are in the run's error sidecar
+ALTER TABLE ingestion_runs ADD COLUMN IF NOT EXISTS parse_errors INTEGER;
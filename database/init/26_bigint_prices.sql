@@ -0,0 +1,5 @@
+-- Sale prices as BIGINT: commercial sales run past an INTEGER's $2.1B limit
+
+ALTER TABLE properties ALTER COLUMN price TYPE BIGINT;
+ALTER TABLE sales_history ALTER COLUMN sale_price TYPE BIGINT;
+ALTER TABLE price_history ALTER COLUMN price TYPE BIGINT;
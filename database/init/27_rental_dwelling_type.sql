@@ -0,0 +1,12 @@
+-- Rental medians per dwelling type: bond data gives houses, units and
+-- townhouses their own medians as well as one for all dwellings, which is
+-- stored with a NULL dwelling_type
+
+ALTER TABLE rental_medians ADD COLUMN IF NOT EXISTS dwelling_type property_type_enum;
+
+-- NULLS NOT DISTINCT so re-running a file doesn't double the all dwellings medians
+ALTER TABLE rental_medians
+    DROP CONSTRAINT IF EXISTS rental_medians_state_postcode_bedrooms_period_data_source_key;
+ALTER TABLE rental_medians DROP CONSTRAINT IF EXISTS unique_rental_median;
+ALTER TABLE rental_medians ADD CONSTRAINT unique_rental_median
+    UNIQUE NULLS NOT DISTINCT (state, postcode, bedrooms, dwelling_type, period, data_source);
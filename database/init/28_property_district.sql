@@ -0,0 +1,6 @@
+-- NSW Valuer General district of each property, from the bulk sales data,
+-- for filtering and aggregating at council level
+
+ALTER TABLE properties ADD COLUMN IF NOT EXISTS district VARCHAR(3);
+
+CREATE INDEX IF NOT EXISTS idx_properties_district ON properties(district);
@@ -0,0 +1,9 @@
+-- How far rental matching had to look for a property's rent: its own postcode
+-- and bedroom count, bond lodgements, the rest of its suburb, or the nearest
+-- bedroom count. NULL when the rent wasn't matched from a median
+
+CREATE TYPE rental_match_method_enum AS ENUM (
+    'postcode', 'observations', 'suburb', 'nearest_bedrooms'
+);
+
+ALTER TABLE properties ADD COLUMN IF NOT EXISTS rental_match_method rental_match_method_enum;
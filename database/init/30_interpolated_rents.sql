@@ -0,0 +1,4 @@
+-- Rents interpolated between the medians for the bedroom counts either side
+-- of a property's own
+
+ALTER TYPE rental_match_method_enum ADD VALUE IF NOT EXISTS 'interpolated' BEFORE 'nearest_bedrooms';
@@ -1,9 +1,10 @@
-import type { Property } from './types';
+import type { PropertiesPage } from './types';
 
 const API_BASE_URL = 'http://localhost:3001/api';
 
-export async function fetchProperties(): Promise<Property[]> {
-	const response = await fetch(`${API_BASE_URL}/properties`);
+export async function fetchProperties(page = 1, pageSize = 50): Promise<PropertiesPage> {
+	const params = new URLSearchParams({ page: String(page), page_size: String(pageSize) });
+	const response = await fetch(`${API_BASE_URL}/properties?${params}`);
 	if (!response.ok) {
 		throw new Error('Failed to fetch properties');
 	}
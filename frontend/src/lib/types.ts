@@ -11,6 +11,14 @@ export interface Property {
 	rental_yield: number | null;
 }
 
+export interface PropertiesPage {
+	properties: Property[];
+	page: number;
+	page_size: number;
+	total: number;
+	total_pages: number;
+}
+
 export interface ApiResponse<T> {
 	data?: T;
 	error?: string;